@@ -0,0 +1,116 @@
+use mysql::prelude::Queryable;
+use mysql::Conn;
+use std::error::Error;
+use std::time::Instant;
+
+/// The number of rows inserted per statement when loading the table
+const LOAD_BATCH_SIZE: usize = 1000;
+
+/// Group bys over a wide table, both with a handful of groups and with lots of groups.
+pub fn run(connection: &mut Conn, s: u32) -> Result<(), Box<dyn Error>> {
+    let row_count = 200_000 * s as usize;
+
+    // These fail the first time round, that's fine
+    let _ = connection.query_drop("create database bench_aggregation");
+    connection.query_drop("use bench_aggregation")?;
+    let _ = connection.query_drop("drop table wide");
+    connection.query_drop(
+        "\
+CREATE TABLE wide
+(
+    id  BIGINT,
+    g1  INTEGER,
+    g2  INTEGER,
+    v1  DECIMAL(12,2),
+    v2  DECIMAL(12,2),
+    v3  DECIMAL(12,2),
+    v4  DECIMAL(12,2),
+    v5  DECIMAL(12,2),
+    v6  DECIMAL(12,2),
+    v7  DECIMAL(12,2),
+    v8  DECIMAL(12,2)
+)
+    ",
+    )?;
+
+    eprintln!("Loading wide");
+    let start = Instant::now();
+    for batch_start in (0..row_count).step_by(LOAD_BATCH_SIZE) {
+        let batch_end = (batch_start + LOAD_BATCH_SIZE).min(row_count);
+        let values: Vec<_> = (batch_start..batch_end)
+            .map(|id| {
+                let measures: Vec<_> = (1..=8)
+                    .map(|i| format!("{}.{:0>2}", (id * i) % 10_000, (id + i) % 100))
+                    .collect();
+                format!(
+                    "({}, {}, {}, {})",
+                    id,
+                    id % 10,
+                    id % 10_000,
+                    measures.join(", ")
+                )
+            })
+            .collect();
+        connection.query_drop(format!("INSERT INTO wide VALUES {}", values.join(",")))?;
+    }
+    println!("  load total_time: {:?}", start.elapsed());
+
+    run_query(
+        connection,
+        "few groups",
+        r#"
+select
+  g1,
+  count(*),
+  sum(v1), sum(v2), sum(v3), sum(v4),
+  avg(v5), avg(v6), avg(v7), avg(v8)
+from
+  wide
+group by
+  g1
+    "#,
+    )?;
+
+    run_query(
+        connection,
+        "many groups",
+        r#"
+select
+  g2,
+  count(*),
+  sum(v1), sum(v2), sum(v3), sum(v4),
+  avg(v5), avg(v6), avg(v7), avg(v8)
+from
+  wide
+group by
+  g2
+    "#,
+    )?;
+
+    run_query(
+        connection,
+        "multi column groups",
+        r#"
+select
+  g1,
+  g2,
+  count(*),
+  sum(v1 * v2),
+  sum(v3 - v4)
+from
+  wide
+group by
+  g1,
+  g2
+    "#,
+    )?;
+    Ok(())
+}
+
+fn run_query(connection: &mut Conn, query_name: &str, query: &str) -> Result<(), Box<dyn Error>> {
+    eprintln!("Running query {}", query_name);
+    let start = Instant::now();
+    connection.query_drop(query)?;
+    println!("  total_time: {:?}", start.elapsed());
+    Ok(())
+}
@@ -1,10 +1,13 @@
+mod aggregation;
 mod incresql_runner;
 mod mysql_runner;
+mod oltp;
 use std::error::Error;
 use std::process::Command;
 
 use clap::{App, Arg};
 use incresql_runner::IncresqlRunner;
+use mysql::Conn;
 use mysql_runner::MysqlRunner;
 use std::path::Path;
 
@@ -23,10 +26,33 @@ pub fn main() -> Result<(), Box<dyn Error>> {
                 .takes_value(false),
         )
         .arg(Arg::with_name("mysql").long("mysql").takes_value(false))
+        .arg(
+            Arg::with_name("suite")
+                .long("suite")
+                .default_value("tpch")
+                .possible_values(&["tpch", "oltp", "aggregation"]),
+        )
         .get_matches();
 
     let s = matches.value_of("scale").unwrap().parse().unwrap();
     let skip_load = matches.is_present("skip_load");
+    let suite = matches.value_of("suite").unwrap();
+
+    // The other suites generate their own data
+    if suite != "tpch" {
+        let mut runner: Box<dyn BenchmarkRunner> = if matches.is_present("mysql") {
+            Box::from(MysqlRunner::new(s as u8)?)
+        } else {
+            Box::from(IncresqlRunner::new(s as u8, true)?)
+        };
+        if suite == "oltp" {
+            oltp::run(runner.connection(), s)?;
+        } else {
+            aggregation::run(runner.connection(), s)?;
+        }
+        eprintln!("Done");
+        return Ok(());
+    }
 
     let current_dir = std::env::current_dir().unwrap();
     let dbgendata_dir = current_dir.join("target").join(format!("dbgen_s{}", s));
@@ -89,4 +115,5 @@ pub trait BenchmarkRunner {
     fn create_tables(&mut self) -> Result<(), Box<dyn Error>>;
     fn load_tables(&mut self, data_dir: &str) -> Result<(), Box<dyn Error>>;
     fn run_queries(&mut self) -> Result<(), Box<dyn Error>>;
+    fn connection(&mut self) -> &mut Conn;
 }
@@ -263,6 +263,10 @@ LOAD DATA INFILE '{}/supplier.tbl' INTO TABLE supplier
         Ok(())
     }
 
+    fn connection(&mut self) -> &mut Conn {
+        &mut self.mysql_connection
+    }
+
     fn run_queries(&mut self) -> Result<(), Box<dyn Error>> {
         self.mysql_connection
             .query_drop(format!("use tpch_{}", self.s))?;
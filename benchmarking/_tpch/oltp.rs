@@ -0,0 +1,103 @@
+use mysql::prelude::Queryable;
+use mysql::Conn;
+use std::error::Error;
+use std::time::Instant;
+
+/// The number of rows inserted per statement when loading the table
+const LOAD_BATCH_SIZE: usize = 1000;
+
+/// A sysbench style oltp workload, bulk loads a table and then runs point lookups, single row
+/// inserts and point deletes against it, reporting the throughput of each.
+pub fn run(connection: &mut Conn, s: u32) -> Result<(), Box<dyn Error>> {
+    let row_count = 100_000 * s as usize;
+    let op_count = 10_000;
+
+    // These fail the first time round, that's fine
+    let _ = connection.query_drop("create database bench_oltp");
+    connection.query_drop("use bench_oltp")?;
+    let _ = connection.query_drop("drop table sbtest");
+    connection.query_drop(
+        "\
+CREATE TABLE sbtest
+(
+    id   BIGINT,
+    k    INTEGER,
+    c    TEXT,
+    pad  TEXT
+)
+    ",
+    )?;
+
+    eprintln!("Loading sbtest");
+    let start = Instant::now();
+    for batch_start in (0..row_count).step_by(LOAD_BATCH_SIZE) {
+        let batch_end = (batch_start + LOAD_BATCH_SIZE).min(row_count);
+        let values: Vec<_> = (batch_start..batch_end).map(row_values).collect();
+        connection.query_drop(format!("INSERT INTO sbtest VALUES {}", values.join(",")))?;
+    }
+    report("load", row_count, start);
+
+    let mut ids = Lcg::new(row_count as u64);
+
+    eprintln!("Running point lookups");
+    let start = Instant::now();
+    for _ in 0..op_count {
+        connection.query_drop(format!("SELECT c FROM sbtest WHERE id = {}", ids.next()))?;
+    }
+    report("point lookups", op_count, start);
+
+    eprintln!("Running single row inserts");
+    let start = Instant::now();
+    for id in row_count..(row_count + op_count) {
+        connection.query_drop(format!("INSERT INTO sbtest VALUES {}", row_values(id)))?;
+    }
+    report("inserts", op_count, start);
+
+    eprintln!("Running point deletes");
+    let start = Instant::now();
+    for id in row_count..(row_count + op_count) {
+        connection.query_drop(format!("DELETE FROM sbtest WHERE id = {}", id))?;
+    }
+    report("deletes", op_count, start);
+    Ok(())
+}
+
+fn row_values(id: usize) -> String {
+    format!(
+        "({}, {}, 'c-{:0>20}', 'pad-{:0>20}')",
+        id,
+        id % 1000,
+        id * 7,
+        id * 13
+    )
+}
+
+fn report(name: &str, ops: usize, start: Instant) {
+    let elapsed = start.elapsed();
+    println!("  {} total_time: {:?}", name, elapsed);
+    println!(
+        "  {} ops/sec:    {:.0}",
+        name,
+        ops as f64 / elapsed.as_secs_f64()
+    );
+}
+
+/// Tiny deterministic random number generator so runs are repeatable between runners.
+struct Lcg {
+    state: u64,
+    max: u64,
+}
+
+impl Lcg {
+    fn new(max: u64) -> Self {
+        Lcg { state: 42, max }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (self.state >> 33) % self.max
+    }
+}
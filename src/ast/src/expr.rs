@@ -1,5 +1,6 @@
+use crate::rel::logical::LogicalOperator;
 use data::rust_decimal::Decimal;
-use data::{DataType, Datum, SortOrder};
+use data::{DataType, Datum, NullOrder, SortOrder};
 use functions::{AggregateFunction, Function, FunctionSignature};
 use regex::Regex;
 use std::cmp::max;
@@ -19,6 +20,7 @@ pub enum Expression {
     CompiledAggregate(CompiledAggregate),
     ColumnReference(ColumnReference),
     CompiledColumnReference(CompiledColumnReference),
+    Subquery(Subquery),
 }
 
 impl Default for Expression {
@@ -40,6 +42,8 @@ pub struct FunctionCall {
 pub struct Cast {
     pub expr: Box<Expression>,
     pub datatype: DataType,
+    // TRY_CAST, values that can't be converted are always null even in strict mode
+    pub try_cast: bool,
 }
 
 /// Represents a scalar function call once its been resolved and type
@@ -69,6 +73,8 @@ impl Eq for CompiledFunctionCall {}
 pub struct CompiledAggregate {
     pub function: &'static dyn AggregateFunction,
     pub args: Box<[Expression]>,
+    // ie count(DISTINCT a), the planner rewrites these into a dedupe below the group by
+    pub distinct: bool,
     // Used to store the evaluation results of the sub expressions during execution
     pub expr_buffer: Box<[Datum<'static>]>,
     pub signature: Box<FunctionSignature<'static>>,
@@ -76,7 +82,9 @@ pub struct CompiledAggregate {
 
 impl PartialEq for CompiledAggregate {
     fn eq(&self, other: &Self) -> bool {
-        self.args == other.args && self.signature == other.signature
+        self.args == other.args
+            && self.distinct == other.distinct
+            && self.signature == other.signature
     }
 }
 
@@ -102,6 +110,15 @@ pub struct CompiledColumnReference {
     pub datatype: DataType,
 }
 
+/// A subquery used as a predicate, ie `a IN (SELECT ...)` or `EXISTS (SELECT ...)`.
+/// These only live until the planner rewrites them into semi/anti joins.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Subquery {
+    // The left hand side of an IN, None for EXISTS
+    pub operand: Option<Box<Expression>>,
+    pub query: Box<LogicalOperator>,
+}
+
 /// Named expression, ie select foo as bar
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NamedExpression {
@@ -109,10 +126,12 @@ pub struct NamedExpression {
     pub expression: Expression,
 }
 
-/// Sort expression, ie order by abs(foo) desc
+/// Sort expression, ie order by abs(foo) desc nulls first
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SortExpression {
     pub ordering: SortOrder,
+    // None for the default of nulls sorting as the smallest value
+    pub nulls: Option<NullOrder>,
     pub expression: Expression,
 }
 
@@ -124,6 +143,7 @@ impl Expression {
             Expression::CompiledFunctionCall(function_call) => Box::from(function_call.args.iter()),
             Expression::CompiledAggregate(function_call) => Box::from(function_call.args.iter()),
             Expression::Cast(cast) => Box::from(once(&*cast.expr)),
+            Expression::Subquery(subquery) => Box::from(subquery.operand.iter().map(Box::as_ref)),
             Expression::CompiledColumnReference(_)
             | Expression::Constant(_, _)
             | Expression::ColumnReference(_) => Box::from(empty()),
@@ -141,11 +161,34 @@ impl Expression {
                 Box::from(function_call.args.iter_mut())
             }
             Expression::Cast(cast) => Box::from(once(&mut *cast.expr)),
+            Expression::Subquery(subquery) => {
+                Box::from(subquery.operand.iter_mut().map(Box::as_mut))
+            }
             Expression::CompiledColumnReference(_)
             | Expression::Constant(_, _)
             | Expression::ColumnReference(_) => Box::from(empty()),
         }
     }
+
+    // Collects the queries of any subqueries within this expression.
+    pub(crate) fn collect_subqueries_mut<'a>(
+        &'a mut self,
+        subqueries: &mut Vec<&'a mut LogicalOperator>,
+    ) {
+        match self {
+            Expression::Subquery(Subquery { operand, query }) => {
+                if let Some(operand) = operand {
+                    operand.collect_subqueries_mut(subqueries);
+                }
+                subqueries.push(query.as_mut());
+            }
+            expression => {
+                for child in expression.children_mut() {
+                    child.collect_subqueries_mut(subqueries);
+                }
+            }
+        }
+    }
 }
 
 // Convenience helpers to construct expression literals
@@ -183,6 +226,12 @@ impl From<Decimal> for Expression {
     }
 }
 
+impl From<f64> for Expression {
+    fn from(d: f64) -> Self {
+        Expression::Constant(Datum::from(d), DataType::Double)
+    }
+}
+
 impl From<&'static str> for Expression {
     fn from(s: &'static str) -> Self {
         Expression::Constant(Datum::from(s), DataType::Text)
@@ -205,7 +254,13 @@ impl Display for Expression {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Expression::Constant(d, dt) => f.write_fmt(format_args!("{:#}", d.typed_with(*dt))),
-            Expression::Cast(c) => f.write_fmt(format_args!("CAST({} AS {})", c.expr, c.datatype)),
+            Expression::Cast(c) => {
+                let function_name = if c.try_cast { "TRY_CAST" } else { "CAST" };
+                f.write_fmt(format_args!(
+                    "{}({} AS {})",
+                    function_name, c.expr, c.datatype
+                ))
+            }
             // For any function name containing anything other that letters and underscores we'll quote.
             Expression::FunctionCall(function_call) => {
                 let args = function_call
@@ -240,10 +295,21 @@ impl Display for Expression {
                     .map(ToString::to_string)
                     .collect::<Vec<_>>()
                     .join(", ");
+                let distinct = if function_call.distinct {
+                    "DISTINCT "
+                } else {
+                    ""
+                };
                 if IDENTIFIER_OK.is_match(&function_call.signature.name) {
-                    f.write_fmt(format_args!("{}({})", function_call.signature.name, args))
+                    f.write_fmt(format_args!(
+                        "{}({}{})",
+                        function_call.signature.name, distinct, args
+                    ))
                 } else {
-                    f.write_fmt(format_args!("`{}`({})", function_call.signature.name, args))
+                    f.write_fmt(format_args!(
+                        "`{}`({}{})",
+                        function_call.signature.name, distinct, args
+                    ))
                 }
             }
             Expression::ColumnReference(column_reference) => Display::fmt(column_reference, f),
@@ -252,6 +318,14 @@ impl Display for Expression {
                 // our sources
                 f.write_fmt(format_args!("<OFFSET {}>", &column_reference.offset))
             }
+            // Like above we'd need to be able to format the logical operator back into sql
+            Expression::Subquery(subquery) => {
+                if let Some(operand) = &subquery.operand {
+                    f.write_fmt(format_args!("{} IN (<SUBQUERY>)", operand))
+                } else {
+                    f.write_str("EXISTS(<SUBQUERY>)")
+                }
+            }
         }
     }
 }
@@ -295,7 +369,8 @@ mod tests {
     #[test]
     fn test_expr_size() {
         // This is already way larger than I would have liked...
-        assert_eq!(std::mem::size_of::<Expression>(), 64);
+        // The distinct flag on CompiledAggregate tipped it over another word.
+        assert_eq!(std::mem::size_of::<Expression>(), 72);
     }
 
     #[test]
@@ -377,6 +452,7 @@ mod tests {
                 Expression::Cast(Cast {
                     expr: Box::new(Expression::from("5")),
                     datatype: DataType::Integer,
+                    try_cast: false,
                 }),
                 Expression::FunctionCall(FunctionCall {
                     function_name: "pow".to_string(),
@@ -1,5 +1,7 @@
 use crate::expr::{Expression, NamedExpression, SortExpression};
-use data::DataType;
+use data::jsonpath_utils::JsonPathExpression;
+use data::statistics::ColumnStatistics;
+use data::{DataType, Datum, LogicalTimestamp, SortOrder};
 use std::iter::{empty, once};
 use storage::Table;
 
@@ -18,11 +20,13 @@ pub enum LogicalOperator {
     Values(Values),
     TableAlias(TableAlias),
     UnionAll(UnionAll),
+    SetOperation(SetOperation),
     TableReference(TableReference),
-    ResolvedTable(ResolvedTable),
+    ResolvedTable(Box<ResolvedTable>),
     TableInsert(TableInsert),
     NegateFreq(Box<LogicalOperator>),
     FileScan(FileScan),
+    JsonTable(JsonTable),
 }
 
 impl Default for LogicalOperator {
@@ -42,6 +46,7 @@ pub struct Project {
 pub struct GroupBy {
     pub expressions: Vec<NamedExpression>,
     pub key_expressions: Vec<Expression>,
+    pub distinct_on: bool, // Comes from parser, planner will rewrite to take the first row per key
     pub source: Box<LogicalOperator>,
 }
 
@@ -56,6 +61,21 @@ pub struct Join {
 pub enum JoinType {
     Inner,
     LeftOuter,
+    // Only outputs the left rows that have a match on the right, the right side's columns are
+    // only visible to the join condition. Used for IN/EXISTS subqueries.
+    Semi,
+    // Only outputs the left rows that have no match on the right, for NOT IN/NOT EXISTS.
+    Anti,
+}
+
+impl JoinType {
+    /// Returns true if the right side's columns are part of the join's output.
+    pub fn outputs_right(self) -> bool {
+        match self {
+            JoinType::Inner | JoinType::LeftOuter => true,
+            JoinType::Semi | JoinType::Anti => false,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -97,18 +117,134 @@ pub struct UnionAll {
     pub sources: Vec<LogicalOperator>,
 }
 
+/// UNION [DISTINCT], INTERSECT and EXCEPT, the planner rewrites these into a group by over a
+/// union all.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SetOperation {
+    pub operator: SetOperator,
+    pub left: Box<LogicalOperator>,
+    pub right: Box<LogicalOperator>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
 /// A "table" reference, ie "FROM foo",
 /// This table could be a table, a view or even a CTE
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct TableReference {
     pub database: Option<String>,
     pub table: String,
+    // Set for time travel queries, ie FROM t AS OF TIMESTAMP '...'
+    pub as_of: Option<LogicalTimestamp>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ResolvedTable {
+    pub database: String,
+    pub name: String,
     pub columns: Vec<(String, DataType)>,
+    // One entry per column
+    pub column_defaults: Vec<Option<ColumnDefault>>,
+    // One entry per column, true for columns declared NOT NULL
+    pub column_not_null: Vec<bool>,
     pub table: Table,
+    // One entry per column, None if the column hasn't been analyzed
+    pub statistics: Vec<Option<ColumnStatistics>>,
+    // For partitioned tables the table above holds no data, it's all stored in the partitions.
+    pub partitioning: Option<Partitioning>,
+    pub fulltext_indexes: Vec<FulltextIndex>,
+    pub secondary_indexes: Vec<SecondaryIndex>,
+    // The primary key is always the leading columns of the table
+    pub primary_key_len: usize,
+    // The timestamp to read the table as of, None to read the latest data
+    pub as_of: Option<LogicalTimestamp>,
+}
+
+/// An inverted index over a text column, keyed on the term followed by the row's columns.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FulltextIndex {
+    pub name: String,
+    // The offset of the indexed column
+    pub column: usize,
+    pub table: Table,
+}
+
+/// An index over some of the columns of a table, keyed on the indexed columns followed by the
+/// row's columns.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SecondaryIndex {
+    pub name: String,
+    // The offsets of the indexed columns
+    pub columns: Vec<usize>,
+    pub table: Table,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PartitionMethod {
+    Range,
+    Hash,
+}
+
+/// How the rows of a partitioned table are split up between its partitions.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Partitioning {
+    pub method: PartitionMethod,
+    // The offset of the column the table is partitioned by
+    pub column: usize,
+    pub column_type: DataType,
+    // For range partitioning these are in order of their upper bounds.
+    pub partitions: Vec<TablePartition>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TablePartition {
+    pub name: String,
+    // The exclusive upper bound of a range partition, None for MAXVALUE or hash partitions.
+    pub upper_bound: Option<Datum<'static>>,
+    pub table: Table,
+    // Set by the planner when the partition can't contain any rows matching the query.
+    pub pruned: bool,
+}
+
+impl Partitioning {
+    /// Returns the index of the partition a row with the given value for the partition column
+    /// belongs in, None if there's no range partition that covers the value.
+    /// Nulls sort first so always go into the first partition.
+    pub fn partition_for(&self, datum: &Datum) -> Option<usize> {
+        match self.method {
+            PartitionMethod::Range => self.partitions.iter().position(|partition| {
+                partition
+                    .upper_bound
+                    .as_ref()
+                    .map_or(true, |bound| datum < bound)
+            }),
+            PartitionMethod::Hash if datum.is_null() => Some(0),
+            PartitionMethod::Hash => {
+                // The hash is persisted (in the form of where rows are stored) so we can't use
+                // the std hashers as they're not guaranteed to be stable.
+                let mut buf = vec![];
+                datum.as_sortable_bytes(SortOrder::Asc, &mut buf);
+                let hash = buf.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                    (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+                });
+                Some((hash % self.partitions.len() as u64) as usize)
+            }
+        }
+    }
+}
+
+/// How a column gets populated when inserting into a table.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ColumnDefault {
+    // DEFAULT <expr>, used when the column isn't one of the columns inserted into.
+    Default(Expression),
+    // AS (<expr>), a stored generated column, always computed from the other columns on insert.
+    Generated(Expression),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -118,26 +254,108 @@ pub struct TableInsert {
     // the existing code paths. Only TableReference and resolved table
     // are valid here.
     pub table: Box<LogicalOperator>,
+    // The columns being inserted into, empty means all columns (bar generated columns) in order.
+    pub columns: Vec<String>,
     pub source: Box<LogicalOperator>,
+    // Rather than adding the source tuples to the table, remove every copy of each source tuple
+    // from the table. Used by multi-table deletes where the same row may be matched many times.
+    pub delete_matching: bool,
+    // Set for LOAD DATA, writes in larger batches and skips reading back existing rows when
+    // the table starts out empty.
+    pub bulk_load: Option<BulkLoad>,
+    // Set for REPLACE INTO and INSERT ... ON DUPLICATE KEY UPDATE
+    pub upsert: Option<Upsert>,
+}
+
+impl TableInsert {
+    /// Deletes are inserts of retractions of rows read from the table itself.
+    pub fn is_delete(&self) -> bool {
+        self.delete_matching || matches!(*self.source, LogicalOperator::NegateFreq(_))
+    }
+}
+
+/// Rather than being added alongside any existing row with the same primary key, inserted rows
+/// replace them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Upsert {
+    // The ON DUPLICATE KEY UPDATE column assignments, empty for REPLACE INTO where the inserted
+    // row replaces the existing row as is. The expressions see the columns of the existing row,
+    // while VALUES(col) refers to the row that was going to be inserted.
+    pub assignments: Vec<(String, Expression)>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BulkLoad {
+    // When false the writes aren't fsync'd until the end of the load.
+    pub sync: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct FileScan {
     pub directory: String,
     pub serde_options: SerdeOptions,
+    // The columns of an external table, when empty each row comes through as a json array of
+    // strings in a single data column.
+    pub columns: Vec<(String, DataType)>,
+    // The trailing columns whose values come from key=value subdirectories rather than the files
+    pub partition_columns: usize,
+    // The subdirectories found for a partitioned scan, filled in by the planner.
+    pub partitions: Vec<FilePartition>,
+}
+
+/// One leaf key=value subdirectory of a partitioned file scan.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FilePartition {
+    // The path relative to the scan's directory, ie dt=2020-01-01/region=us
+    pub name: String,
+    // The value for each of the partition columns
+    pub values: Vec<Datum<'static>>,
+    // Set by the planner when the partition can't contain any rows matching the query.
+    pub pruned: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct SerdeOptions {
     pub delimiter: u8,
+    // When set the first line of each file is skipped
+    pub header: bool,
+    // When set the files in subdirectories are read too
+    pub recursive: bool,
 }
 
 impl Default for SerdeOptions {
     fn default() -> Self {
-        SerdeOptions { delimiter: b',' }
+        SerdeOptions {
+            delimiter: b',',
+            header: false,
+            recursive: true,
+        }
     }
 }
 
+/// JSON_TABLE(doc, path COLUMNS (...)), a lateral operator that for each row of the source
+/// evaluates the path against the row's document and outputs the source row followed by the
+/// json table's columns once per match.
+/// When the json table isn't joined onto anything the source is just Single.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JsonTable {
+    pub document: Expression,
+    pub path: JsonPathExpression,
+    pub columns: Vec<JsonTableColumn>,
+    // Qualifies the json table's columns, the source's columns keep their own qualifiers
+    pub alias: Option<String>,
+    pub source: Box<LogicalOperator>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JsonTableColumn {
+    pub name: String,
+    pub datatype: DataType,
+    // The path within each match to read the column's value from, None for the
+    // FOR ORDINALITY column which counts the matches from 1.
+    pub path: Option<JsonPathExpression>,
+}
+
 impl LogicalOperator {
     /// Iterates over the named(output) expressions *owned* by this operator.
     /// To iterate over the output fields instead use one of the fields methods in the planner
@@ -153,11 +371,13 @@ impl LogicalOperator {
             | LogicalOperator::Values(_)
             | LogicalOperator::TableAlias(_)
             | LogicalOperator::UnionAll(_)
+            | LogicalOperator::SetOperation(_)
             | LogicalOperator::TableReference(_)
             | LogicalOperator::ResolvedTable(_)
             | LogicalOperator::TableInsert(_)
             | LogicalOperator::NegateFreq(_)
-            | LogicalOperator::FileScan(_) => Box::from(empty()),
+            | LogicalOperator::FileScan(_)
+            | LogicalOperator::JsonTable(_) => Box::from(empty()),
         }
     }
 
@@ -175,11 +395,13 @@ impl LogicalOperator {
             | LogicalOperator::Values(_)
             | LogicalOperator::TableAlias(_)
             | LogicalOperator::UnionAll(_)
+            | LogicalOperator::SetOperation(_)
             | LogicalOperator::TableReference(_)
             | LogicalOperator::ResolvedTable(_)
             | LogicalOperator::TableInsert(_)
             | LogicalOperator::NegateFreq(_)
-            | LogicalOperator::FileScan(_) => Box::from(empty()),
+            | LogicalOperator::FileScan(_)
+            | LogicalOperator::JsonTable(_) => Box::from(empty()),
         }
     }
 
@@ -206,10 +428,12 @@ impl LogicalOperator {
                     .map(|se| &mut se.expression),
             ),
             LogicalOperator::Join(join) => Box::from(once(&mut join.on)),
+            LogicalOperator::JsonTable(json_table) => Box::from(once(&mut json_table.document)),
             LogicalOperator::Single
             | LogicalOperator::Limit(_)
             | LogicalOperator::TableAlias(_)
             | LogicalOperator::UnionAll(_)
+            | LogicalOperator::SetOperation(_)
             | LogicalOperator::TableReference(_)
             | LogicalOperator::ResolvedTable(_)
             | LogicalOperator::TableInsert(_)
@@ -218,6 +442,16 @@ impl LogicalOperator {
         }
     }
 
+    /// Iterates over the queries of any subqueries used within this operator's expressions, ie
+    /// `WHERE a IN (SELECT ...)`. These aren't children as they don't feed rows into the operator.
+    pub fn subqueries_mut(&mut self) -> Box<dyn Iterator<Item = &mut LogicalOperator> + '_> {
+        let mut subqueries = vec![];
+        for expression in self.expressions_mut() {
+            expression.collect_subqueries_mut(&mut subqueries);
+        }
+        Box::from(subqueries.into_iter())
+    }
+
     /// Iterates over the immediate child operators of this operator
     pub fn children_mut(&mut self) -> Box<dyn Iterator<Item = &mut LogicalOperator> + '_> {
         match self {
@@ -234,6 +468,10 @@ impl LogicalOperator {
             ),
             LogicalOperator::UnionAll(union_all) => Box::from(union_all.sources.iter_mut()),
             LogicalOperator::NegateFreq(source) => Box::from(once(source.as_mut())),
+            LogicalOperator::JsonTable(json_table) => Box::from(once(json_table.source.as_mut())),
+            LogicalOperator::SetOperation(set_operation) => Box::from(
+                once(set_operation.left.as_mut()).chain(once(set_operation.right.as_mut())),
+            ),
             LogicalOperator::Join(join) => {
                 Box::from(once(join.left.as_mut()).chain(once(join.right.as_mut())))
             }
@@ -331,4 +569,40 @@ mod tests {
 
         assert_eq!(children, vec![&mut Expression::from(1)]);
     }
+
+    #[test]
+    fn test_partition_for() {
+        let storage = storage::Storage::new_in_mem().unwrap();
+        let partition = |name: &str, upper_bound: Option<i32>| TablePartition {
+            name: name.to_string(),
+            upper_bound: upper_bound.map(Datum::from),
+            table: storage.table(10, 1, vec![SortOrder::Asc]),
+            pruned: false,
+        };
+
+        let mut partitioning = Partitioning {
+            method: PartitionMethod::Range,
+            column: 0,
+            column_type: DataType::Integer,
+            partitions: vec![partition("p0", Some(10)), partition("p1", Some(20))],
+        };
+        assert_eq!(partitioning.partition_for(&Datum::Null), Some(0));
+        assert_eq!(partitioning.partition_for(&Datum::from(9)), Some(0));
+        assert_eq!(partitioning.partition_for(&Datum::from(10)), Some(1));
+        assert_eq!(partitioning.partition_for(&Datum::from(20)), None);
+
+        partitioning.partitions.push(partition("p2", None));
+        assert_eq!(partitioning.partition_for(&Datum::from(20)), Some(2));
+
+        partitioning.method = PartitionMethod::Hash;
+        let mut counts = [0; 3];
+        for i in 0..300 {
+            counts[partitioning.partition_for(&Datum::from(i)).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|count| *count > 50), "{:?}", counts);
+        assert_eq!(
+            partitioning.partition_for(&Datum::from(42)),
+            partitioning.partition_for(&Datum::from(42))
+        );
+    }
 }
@@ -1,5 +1,8 @@
 use crate::expr::{Expression, SortExpression};
-use crate::rel::logical::{JoinType, SerdeOptions};
+use crate::rel::logical::{
+    BulkLoad, FulltextIndex, JoinType, Partitioning, SecondaryIndex, SerdeOptions,
+};
+use data::jsonpath_utils::JsonPathExpression;
 use data::{Datum, LogicalTimestamp};
 use storage::Table;
 
@@ -13,12 +16,15 @@ pub enum PointInTimeOperator {
     Sort(Sort),
     UnionAll(UnionAll),
     TableScan(TableScan),
+    FulltextScan(FulltextScan),
     TableInsert(TableInsert),
     NegateFreq(Box<PointInTimeOperator>),
     SortedGroup(Group),
     HashGroup(Group),
     HashJoin(Join),
     FileScan(FileScan),
+    Gather(Gather),
+    JsonTable(JsonTable),
 }
 
 impl Default for PointInTimeOperator {
@@ -62,17 +68,62 @@ pub struct UnionAll {
 pub struct TableScan {
     pub table: Table,
     pub timestamp: LogicalTimestamp,
+    // The key ranges to scan, None for a full scan
+    pub ranges: Option<Vec<KeyRange>>,
+    // Only the columns flagged true are decoded, the rest come back as nulls.
+    // None decodes every column
+    pub column_mask: Option<Vec<bool>>,
+}
+
+/// A range of keys to scan, the bounds are inclusive key prefixes ordered as per the table's pk.
+/// ie for a descending first column the correct range is from: 5 to: 1.
+/// None for either bound leaves that side of the range open.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct KeyRange {
+    pub from: Option<Vec<Datum<'static>>>,
+    pub to: Option<Vec<Datum<'static>>>,
+}
+
+/// Scans the rows of a table containing any of the terms via a full-text index.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FulltextScan {
+    pub index: FulltextIndex,
+    pub terms: Vec<String>,
+    pub timestamp: LogicalTimestamp,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct TableInsert {
     pub table: Table,
+    // When set the rows are written to the partitions rather than the table itself.
+    pub partitioning: Option<Partitioning>,
+    // Maintained alongside the table, written to with the same freqs as the table.
+    pub fulltext_indexes: Vec<FulltextIndex>,
+    pub secondary_indexes: Vec<SecondaryIndex>,
     pub source: Box<PointInTimeOperator>,
+    pub delete_matching: bool,
+    pub bulk_load: Option<BulkLoad>,
+    pub upsert: Option<Upsert>,
+    // (offset, column name) of the NOT NULL columns, checked for each row written.
+    pub not_null_columns: Vec<(usize, String)>,
+}
+
+/// REPLACE INTO and INSERT ... ON DUPLICATE KEY UPDATE, any existing rows sharing the primary key
+/// of an inserted row are retracted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Upsert {
+    // The number of leading columns making up the primary key.
+    pub key_len: usize,
+    // (column offset, expression), evaluated against the existing row followed by the inserted
+    // row to produce the updated row. Empty to just replace the existing row.
+    pub assignments: Vec<(usize, Expression)>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Sort {
     pub sort_expressions: Vec<SortExpression>,
+    // Set when only the first n rows are needed (ie sort followed by a limit)
+    pub limit: Option<i64>,
     pub source: Box<PointInTimeOperator>,
 }
 
@@ -81,6 +132,21 @@ pub struct Group {
     pub source: Box<PointInTimeOperator>,
     pub expressions: Vec<Expression>,
     pub key_len: usize,
+    pub mode: AggregateMode,
+}
+
+/// Group bys can be split in two, a partial group by that collapses duplicate keys early and
+/// a final group by that merges the partial states together.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AggregateMode {
+    /// Aggregates the source tuples and outputs the finalized expressions
+    Complete,
+    /// Aggregates the source tuples but outputs the raw aggregate states rather than
+    /// finalizing them
+    Partial,
+    /// Merges the states output by partial group bys, the source tuples being the grouping keys
+    /// followed by the states.
+    Final,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -92,8 +158,30 @@ pub struct Join {
     pub join_type: JoinType,
 }
 
+/// Runs the source on several threads at once. The scan at the bottom of the source is read on
+/// the gathering thread and its rows handed out in batches to whichever worker is free.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Gather {
+    pub source: Box<PointInTimeOperator>,
+    pub workers: usize,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct FileScan {
     pub directory: String,
     pub serde_options: SerdeOptions,
+    // Parses each (text) field into the type of its column, when empty each row comes through
+    // as a json array of strings.
+    pub columns: Vec<Expression>,
+}
+
+/// Outputs each source row followed by the columns once per match of the path against the
+/// row's document.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct JsonTable {
+    pub document: Expression,
+    pub path: JsonPathExpression,
+    // Evaluated against a tuple of the match (json) followed by its ordinality (bigint).
+    pub columns: Vec<Expression>,
+    pub source: Box<PointInTimeOperator>,
 }
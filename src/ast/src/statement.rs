@@ -1,5 +1,6 @@
-use crate::rel::logical::LogicalOperator;
-use data::DataType;
+use crate::expr::Expression;
+use crate::rel::logical::{ColumnDefault, LogicalOperator, PartitionMethod, SerdeOptions};
+use data::{DataType, SortOrder};
 
 /// The top level structure parsed, could be a query or DDL statement.
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -7,20 +8,73 @@ pub enum Statement {
     Query(LogicalOperator),
     ShowFunctions,
     ShowDatabases,
-    ShowTables,
+    // The optional string is the database, defaulting to the current one
+    ShowTables(Option<String>),
+    ShowColumns(ShowColumns),
+    ShowIndex(ShowIndex),
+    // The optional string is the LIKE pattern
+    ShowVariables(Option<String>),
+    ShowCollation(Option<String>),
+    ShowCharacterSet(Option<String>),
+    ShowProcesslist,
+    ShowWarnings,
     CreateDatabase(CreateDatabase),
     DropDatabase(String),
+    CreateUser(CreateUser),
+    DropUser(String),
+    AlterUser(AlterUser),
     UseDatabase(String),
     CreateTable(CreateTable),
     CreateView(CreateView),
+    CreateFulltextIndex(CreateFulltextIndex),
+    CreateIndex(CreateIndex),
+    CreateSink(CreateSink),
+    CreateExternalTable(CreateExternalTable),
+    DropSink(DropSink),
+    CreateFunction(CreateFunction),
+    DropFunction(String),
+    LoadData(LoadData),
     CompactTable(CompactTable),
+    AnalyzeTable(AnalyzeTable),
+    AlterTable(AlterTable),
     DropTable(DropTable),
     Explain(Explain),
+    SetVariables(Vec<SetVariable>),
+    Transaction(Transaction),
+    Savepoint(Savepoint),
+    Kill(Kill),
+    Tail(Tail),
+    // SUBSCRIBE TO query
+    Subscribe(LogicalOperator),
+    SelectIntoOutfile(SelectIntoOutfile),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Explain {
     pub operator: LogicalOperator,
+    pub format: ExplainFormat,
+}
+
+/// EXPLAIN FORMAT=TEXT|DOT, text being the default
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum ExplainFormat {
+    Text,
+    Dot,
+}
+
+/// KILL [CONNECTION | QUERY] id
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Kill {
+    pub connection_id: u32,
+    // KILL QUERY only stops the running statement, the connection is left open
+    pub query_only: bool,
+}
+
+/// TAIL t, streams the changes made to a table (or view) as they happen.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Tail {
+    pub database: Option<String>,
+    pub name: String,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -28,24 +82,177 @@ pub struct CreateDatabase {
     pub name: String,
 }
 
+/// CREATE USER name [IDENTIFIED BY 'password'], the password is in plain text here and
+/// gets hashed before being stored.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateUser {
+    pub name: String,
+    pub password: Option<String>,
+}
+
+/// ALTER USER name IDENTIFIED BY 'password' or SET PASSWORD [FOR name] = 'password', a missing
+/// name means the current user.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AlterUser {
+    pub name: Option<String>,
+    pub password: String,
+}
+
+/// LOAD DATA INFILE 'path' INTO TABLE t or COPY t FROM 'path', bulk loads a csv file (or
+/// directory of them) into a table.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct LoadData {
+    pub path: String,
+    pub database: Option<String>,
+    pub table: String,
+    // The columns being loaded into, empty means all columns in order.
+    pub columns: Vec<String>,
+    pub serde_options: SerdeOptions,
+    // When false the writes aren't fsync'd until the end of the load.
+    pub sync: bool,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct CreateTable {
     pub database: Option<String>,
     pub name: String,
     pub columns: Vec<(String, DataType)>,
+    // One entry per column
+    pub column_defaults: Vec<Option<ColumnDefault>>,
+    // One entry per column, true for columns declared NOT NULL
+    pub column_not_null: Vec<bool>,
+    // PRIMARY KEY (col [ASC|DESC], ...), empty if not specified
+    pub primary_key: Vec<(String, SortOrder)>,
+    pub partition_by: Option<PartitionBy>,
+    // FULLTEXT [INDEX] [name] (col), (index name, column)
+    pub fulltext_indexes: Vec<(String, String)>,
+}
+
+/// PARTITION BY RANGE/HASH (column) ...
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PartitionBy {
+    pub method: PartitionMethod,
+    pub column: String,
+    pub partitions: Vec<PartitionDefinition>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PartitionDefinition {
+    pub name: String,
+    // VALUES LESS THAN (expr) for range partitions, None for MAXVALUE and hash partitions.
+    pub upper_bound: Option<Expression>,
 }
 
 /// Create view we grab the raw text as well as the logical operator.
 /// once we've validated the operator is good we actually throw it
 /// away and just store the sql.  This may change in the future tho.
+/// Materialized views keep their results stored in a table that's kept up to date as the
+/// tables they read from change.
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct CreateView {
     pub database: Option<String>,
     pub name: String,
+    pub materialized: bool,
     pub sql: String,
     pub query: LogicalOperator,
 }
 
+/// CREATE FULLTEXT INDEX name ON t (col)
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateFulltextIndex {
+    pub database: Option<String>,
+    pub table: String,
+    pub name: String,
+    pub column: String,
+}
+
+/// CREATE INDEX name ON t (col, ...)
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateIndex {
+    pub database: Option<String>,
+    pub table: String,
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+/// CREATE SINK name FROM t INTO FILE 'path' [FORMAT JSON|CSV], continuously exports the
+/// changes made to a table or view.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateSink {
+    pub database: Option<String>,
+    pub name: String,
+    pub source_database: Option<String>,
+    pub source: String,
+    pub path: String,
+    pub format: SinkFormat,
+}
+
+/// CREATE EXTERNAL TABLE t (cols...) [PARTITIONED BY (cols...)] LOCATION 'dir' [FORMAT CSV]
+/// [(HEADER, DELIMITER 'x')], a table read straight from the csv files in a directory.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateExternalTable {
+    pub database: Option<String>,
+    pub name: String,
+    pub columns: Vec<(String, DataType)>,
+    // Read from the key=value subdirectories the files are in
+    pub partition_columns: Vec<(String, DataType)>,
+    pub location: String,
+    pub delimiter: u8,
+    pub header: bool,
+}
+
+/// CREATE FUNCTION name(arg type, ...) RETURNS type [LANGUAGE SQL] AS 'expression' or
+/// CREATE FUNCTION name(arg type, ...) RETURNS type LANGUAGE WASM AS module
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CreateFunction {
+    pub name: String,
+    pub args: Vec<(String, DataType)>,
+    pub returns: DataType,
+    pub body: FunctionBody,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum FunctionBody {
+    // Evaluated to get the bytes of the module, the module's export of the same name implements
+    // the function
+    Wasm(Expression),
+    // An expression over the args that calls to the function are replaced with
+    Sql(String),
+}
+
+/// The format of the records written by a sink or outfile, JSON being the default
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SinkFormat {
+    Json,
+    Csv,
+}
+
+/// SELECT ... INTO OUTFILE 'path' [FORMAT JSON|CSV] [FIELDS TERMINATED BY ','
+/// [OPTIONALLY] ENCLOSED BY '"'] [COMPRESSION GZIP], writes the results of the query to a file
+/// instead of sending them back to the client.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SelectIntoOutfile {
+    pub query: LogicalOperator,
+    pub path: String,
+    pub format: SinkFormat,
+    // The delimiter and quote are only used for csv
+    pub delimiter: u8,
+    pub quote: u8,
+    pub compression: Compression,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Compression {
+    None,
+    Gzip,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct DropSink {
+    pub database: Option<String>,
+    pub name: String,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct DropTable {
     pub database: Option<String>,
@@ -56,4 +263,62 @@ pub struct DropTable {
 pub struct CompactTable {
     pub database: Option<String>,
     pub name: String,
+    pub partition: Option<String>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AlterTable {
+    pub database: Option<String>,
+    pub name: String,
+    pub action: AlterTableAction,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum AlterTableAction {
+    AddPartition(PartitionDefinition),
+    DropPartition(String),
+}
+
+/// SHOW COLUMNS FROM t [FROM db] or DESCRIBE t
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ShowColumns {
+    pub database: Option<String>,
+    pub name: String,
+}
+
+/// SHOW INDEX FROM t [FROM db]
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ShowIndex {
+    pub database: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct AnalyzeTable {
+    pub database: Option<String>,
+    pub name: String,
+}
+
+/// BEGIN, COMMIT and ROLLBACK
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Transaction {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+/// SAVEPOINT name, ROLLBACK TO SAVEPOINT name and RELEASE SAVEPOINT name
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum Savepoint {
+    Create(String),
+    RollbackTo(String),
+    Release(String),
+}
+
+/// A single assignment from a set statement, ie SET NAMES utf8mb4 or SET autocommit = 1.
+/// The name has any scope (ie SESSION, @@global.) stripped, user variables keep their @ (ie @x).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SetVariable {
+    pub name: String,
+    pub value: Expression,
 }
@@ -1,4 +1,9 @@
-use crate::{Catalog, CatalogError, DATABASES_TABLE_ID, PREFIX_METADATA_TABLE_ID, TABLES_TABLE_ID};
+use crate::{
+    Catalog, CatalogError, COLUMNS_TABLE_ID, COLUMN_BOUNDS_TABLE_ID, COLUMN_STATISTICS_TABLE_ID,
+    DATABASES_TABLE_ID, FUNCTIONS_TABLE_ID, INDEXES_TABLE_ID, PARTITIONS_TABLE_ID,
+    PREFIX_METADATA_TABLE_ID, SEQUENCES_TABLE_ID, SINKS_TABLE_ID, SLOW_LOG_TABLE_ID,
+    TABLES_TABLE_ID, USERS_TABLE_ID,
+};
 use data::{DataType, Datum, SortOrder};
 
 impl Catalog {
@@ -16,7 +21,7 @@ impl Catalog {
             )?
             .is_some()
         {
-            return Ok(());
+            return self.bootstrap_upgrades();
         }
 
         self.create_database_impl("incresql")?;
@@ -32,7 +37,10 @@ impl Catalog {
                 ("column_len".to_string(), DataType::Integer),
                 ("pk_sort".to_string(), DataType::Json),
             ],
+            &[],
+            &[],
             &[SortOrder::Asc],
+            1,
             true,
         )?;
 
@@ -41,7 +49,10 @@ impl Catalog {
             "databases",
             DATABASES_TABLE_ID,
             &[("name".to_string(), DataType::Text)],
+            &[],
+            &[],
             &[SortOrder::Asc],
+            1,
             true,
         )?;
 
@@ -59,10 +70,276 @@ impl Catalog {
                 ("columns".to_string(), DataType::Json),
                 ("system".to_string(), DataType::Boolean),
             ],
+            &[],
+            &[],
             &[SortOrder::Asc, SortOrder::Asc],
+            2,
             true,
         )?;
 
+        self.bootstrap_upgrades()
+    }
+
+    /// System tables added after the initial release, these need creating when upgrading
+    /// existing databases too.
+    fn bootstrap_upgrades(&mut self) -> Result<(), CatalogError> {
+        self.create_system_table_if_missing(
+            "column_statistics",
+            COLUMN_STATISTICS_TABLE_ID,
+            &[
+                ("table_id".to_string(), DataType::BigInt),
+                ("column_name".to_string(), DataType::Text),
+                ("row_count".to_string(), DataType::BigInt),
+                ("null_count".to_string(), DataType::BigInt),
+                ("ndv".to_string(), DataType::BigInt),
+                ("histogram".to_string(), DataType::ByteA),
+                ("sketch".to_string(), DataType::ByteA),
+            ],
+            2,
+        )?;
+
+        self.create_system_table_if_missing(
+            "partitions",
+            PARTITIONS_TABLE_ID,
+            &[
+                ("table_id".to_string(), DataType::BigInt),
+                ("ordinal".to_string(), DataType::Integer),
+                ("name".to_string(), DataType::Text),
+                ("partition_id".to_string(), DataType::BigInt),
+                ("method".to_string(), DataType::Text),
+                ("column_idx".to_string(), DataType::Integer),
+                ("upper_bound".to_string(), DataType::ByteA),
+            ],
+            2,
+        )?;
+
+        let indexes_created = self.create_system_table_if_missing(
+            "indexes",
+            INDEXES_TABLE_ID,
+            &[
+                ("database_name".to_string(), DataType::Text),
+                ("table_name".to_string(), DataType::Text),
+                ("index_name".to_string(), DataType::Text),
+                ("seq_in_index".to_string(), DataType::Integer),
+                ("column_name".to_string(), DataType::Text),
+                ("collation".to_string(), DataType::Text),
+                ("non_unique".to_string(), DataType::Boolean),
+                ("index_table_id".to_string(), DataType::BigInt),
+            ],
+            4,
+        )?;
+        if indexes_created {
+            self.backfill_indexes()?;
+        }
+
+        if !self.table_exists("information_schema", "statistics")? {
+            self.create_view_impl(
+                "information_schema",
+                "statistics",
+                &[
+                    ("table_schema".to_string(), DataType::Text),
+                    ("table_name".to_string(), DataType::Text),
+                    ("non_unique".to_string(), DataType::Boolean),
+                    ("index_name".to_string(), DataType::Text),
+                    ("seq_in_index".to_string(), DataType::Integer),
+                    ("column_name".to_string(), DataType::Text),
+                    ("collation".to_string(), DataType::Text),
+                    ("index_table_id".to_string(), DataType::BigInt),
+                ],
+                "SELECT database_name AS table_schema, table_name, non_unique, index_name, \
+                 seq_in_index, column_name, collation, index_table_id FROM incresql.indexes",
+                "incresql",
+                true,
+            )?;
+        }
+
+        let columns_created = self.create_system_table_if_missing(
+            "columns",
+            COLUMNS_TABLE_ID,
+            &[
+                ("database_name".to_string(), DataType::Text),
+                ("table_name".to_string(), DataType::Text),
+                ("ordinal_position".to_string(), DataType::Integer),
+                ("column_name".to_string(), DataType::Text),
+                ("data_type".to_string(), DataType::Text),
+                ("column_default".to_string(), DataType::Text),
+                ("generation_expression".to_string(), DataType::Text),
+                ("is_nullable".to_string(), DataType::Text),
+                ("column_key".to_string(), DataType::Text),
+            ],
+            3,
+        )?;
+        if columns_created {
+            self.backfill_columns()?;
+        }
+
+        if !self.table_exists("information_schema", "columns")? {
+            self.create_view_impl(
+                "information_schema",
+                "columns",
+                &[
+                    ("table_schema".to_string(), DataType::Text),
+                    ("table_name".to_string(), DataType::Text),
+                    ("column_name".to_string(), DataType::Text),
+                    ("ordinal_position".to_string(), DataType::Integer),
+                    ("column_default".to_string(), DataType::Text),
+                    ("is_nullable".to_string(), DataType::Text),
+                    ("data_type".to_string(), DataType::Text),
+                    ("generation_expression".to_string(), DataType::Text),
+                    ("column_key".to_string(), DataType::Text),
+                ],
+                "SELECT database_name AS table_schema, table_name, column_name, ordinal_position, \
+                 column_default, is_nullable, data_type, generation_expression, column_key \
+                 FROM incresql.columns",
+                "incresql",
+                true,
+            )?;
+        }
+
+        let users_created = self.create_system_table_if_missing(
+            "users",
+            USERS_TABLE_ID,
+            &[
+                ("name".to_string(), DataType::Text),
+                ("authentication_string".to_string(), DataType::Text),
+            ],
+            1,
+        )?;
+        if users_created {
+            // Start out with a passwordless root user so fresh installs can still connect
+            self.create_user("root", "")?;
+        }
+
+        self.create_system_table_if_missing(
+            "sinks",
+            SINKS_TABLE_ID,
+            &[
+                ("database_name".to_string(), DataType::Text),
+                ("name".to_string(), DataType::Text),
+                ("source_database".to_string(), DataType::Text),
+                ("source_name".to_string(), DataType::Text),
+                ("path".to_string(), DataType::Text),
+                ("format".to_string(), DataType::Text),
+            ],
+            2,
+        )?;
+
+        self.create_system_table_if_missing(
+            "column_bounds",
+            COLUMN_BOUNDS_TABLE_ID,
+            &[
+                ("table_id".to_string(), DataType::BigInt),
+                ("column_name".to_string(), DataType::Text),
+                ("min_value".to_string(), DataType::Text),
+                ("max_value".to_string(), DataType::Text),
+            ],
+            2,
+        )?;
+
+        // Existing tables are skipped over as ids get allocated so there's no need to seed this
+        self.create_system_table_if_missing(
+            "sequences",
+            SEQUENCES_TABLE_ID,
+            &[
+                ("name".to_string(), DataType::Text),
+                ("next_value".to_string(), DataType::BigInt),
+            ],
+            1,
+        )?;
+
+        self.create_system_table_if_missing(
+            "functions",
+            FUNCTIONS_TABLE_ID,
+            &[
+                ("name".to_string(), DataType::Text),
+                ("args".to_string(), DataType::Json),
+                ("return_type".to_string(), DataType::Text),
+                ("language".to_string(), DataType::Text),
+                ("body".to_string(), DataType::ByteA),
+            ],
+            1,
+        )?;
+
+        self.create_system_table_if_missing(
+            "slow_log",
+            SLOW_LOG_TABLE_ID,
+            &[
+                ("start_time".to_string(), DataType::Timestamp),
+                ("connection_id".to_string(), DataType::BigInt),
+                // Start times are only down to the millisecond, so the sql is needed to tell
+                // apart quick statements run by the same connection
+                ("sql_text".to_string(), DataType::Text),
+                ("user".to_string(), DataType::Text),
+                ("db".to_string(), DataType::Text),
+                ("duration_ms".to_string(), DataType::BigInt),
+                ("rows_examined".to_string(), DataType::BigInt),
+                ("rows_sent".to_string(), DataType::BigInt),
+            ],
+            3,
+        )?;
+
+        if !self.table_exists("information_schema", "column_statistics")? {
+            self.create_view_impl(
+                "information_schema",
+                "column_statistics",
+                &[
+                    ("schema_name".to_string(), DataType::Text),
+                    ("table_name".to_string(), DataType::Text),
+                    ("column_name".to_string(), DataType::Text),
+                    ("row_count".to_string(), DataType::BigInt),
+                    ("null_count".to_string(), DataType::BigInt),
+                    ("ndv".to_string(), DataType::BigInt),
+                    ("min_value".to_string(), DataType::Text),
+                    ("max_value".to_string(), DataType::Text),
+                ],
+                // Stats collected before the bounds were recorded won't have any
+                "SELECT t.database_name AS schema_name, t.name AS table_name, s.column_name, \
+                 s.row_count, s.null_count, s.ndv, b.min_value, b.max_value \
+                 FROM incresql.column_statistics s \
+                 JOIN incresql.tables t ON s.table_id = t.table_id \
+                 LEFT JOIN incresql.column_bounds b \
+                 ON s.table_id = b.table_id AND s.column_name = b.column_name",
+                "incresql",
+                true,
+            )?;
+        }
         Ok(())
     }
+
+    /// Creates a system table with the leading `pk_len` columns as its pk if it doesn't
+    /// already exist, returns true if the table was created.
+    fn create_system_table_if_missing(
+        &mut self,
+        table_name: &str,
+        table_id: u32,
+        columns: &[(String, DataType)],
+        pk_len: usize,
+    ) -> Result<bool, CatalogError> {
+        let mut key_buf = vec![];
+        let mut value_buf = vec![];
+        if self
+            .prefix_metadata_table
+            .system_point_lookup(
+                &[Datum::from(table_id as i64)],
+                &mut key_buf,
+                &mut value_buf,
+            )?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        self.create_table_impl(
+            "incresql",
+            table_name,
+            table_id,
+            columns,
+            &[],
+            &[],
+            &vec![SortOrder::Asc; pk_len],
+            pk_len,
+            true,
+        )?;
+        Ok(true)
+    }
 }
@@ -0,0 +1,161 @@
+use crate::{Catalog, CatalogError, ColumnDefault};
+use data::{DataType, Datum, LogicalTimestamp, TupleIter};
+use storage::{StorageError, Writer};
+
+impl Catalog {
+    /// Records the columns of a newly created table or view as part of a larger write.
+    /// The first key_len columns make up the primary key, 0 for views.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn write_columns(
+        &self,
+        batch: &mut Writer,
+        database_name: &str,
+        table_name: &str,
+        columns: &[(String, DataType)],
+        column_defaults: &[Option<ColumnDefault>],
+        column_not_null: &[bool],
+        key_len: usize,
+    ) {
+        for (idx, (column_name, datatype)) in columns.iter().enumerate() {
+            let (default, generated) = match column_defaults.get(idx) {
+                Some(Some(ColumnDefault::Default(sql))) => (Datum::from(sql.as_str()), Datum::Null),
+                Some(Some(ColumnDefault::Generated(sql))) => {
+                    (Datum::Null, Datum::from(sql.as_str()))
+                }
+                _ => (Datum::Null, Datum::Null),
+            };
+            let is_nullable = if column_not_null.get(idx) == Some(&true) {
+                "NO"
+            } else {
+                "YES"
+            };
+            let column_key = if idx < key_len { "PRI" } else { "" };
+            let tuple = [
+                Datum::from(database_name),
+                Datum::from(table_name),
+                Datum::from(idx as i32 + 1),
+                Datum::from(column_name.as_str()),
+                Datum::from(format!("{:#}", datatype)),
+                default,
+                generated,
+                Datum::from(is_nullable),
+                Datum::from(column_key),
+            ];
+            batch.system_write_tuple(&self.columns_table, &tuple, 1);
+        }
+    }
+
+    /// Removes all the column entries for a table as part of a larger write.
+    pub(crate) fn delete_columns(
+        &self,
+        batch: &mut Writer,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<(), StorageError> {
+        let table_key = [Datum::from(database_name), Datum::from(table_name)];
+        let mut iter = self.columns_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        while let Some((tuple, _freq)) = iter.next()? {
+            batch.system_delete_tuple(&self.columns_table, &tuple[..3]);
+        }
+        Ok(())
+    }
+
+    /// Populates the columns table for tables and views created before it existed.
+    pub(crate) fn backfill_columns(&mut self) -> Result<(), CatalogError> {
+        let mut tables = vec![];
+        let mut iter = self.tables_table.full_scan(LogicalTimestamp::MAX);
+        while let Some((tuple, freq)) = iter.next()? {
+            if freq > 0 {
+                tables.push((
+                    tuple[0].as_text().to_string(),
+                    tuple[1].as_text().to_string(),
+                ));
+            }
+        }
+
+        for (database_name, table_name) in tables {
+            let item = self.item(&database_name, &table_name)?;
+            self.columns_table
+                .atomic_write::<_, StorageError>(|batch| {
+                    self.write_columns(
+                        batch,
+                        &database_name,
+                        &table_name,
+                        &item.columns,
+                        &item.column_defaults,
+                        &item.column_not_null,
+                        item.primary_key_len,
+                    );
+                    Ok(())
+                })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::SortOrder;
+
+    fn column_rows(catalog: &Catalog) -> Result<Vec<Vec<Datum<'static>>>, CatalogError> {
+        let key = [Datum::from("default"), Datum::from("t")];
+        let mut iter =
+            catalog
+                .columns_table
+                .range_scan(Some(&key), Some(&key), LogicalTimestamp::MAX);
+        let mut rows = vec![];
+        while let Some((tuple, _freq)) = iter.next()? {
+            rows.push(tuple[2..].iter().map(|d| d.as_static()).collect());
+        }
+        Ok(rows)
+    }
+
+    #[test]
+    fn test_columns_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        catalog.create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Text),
+            ],
+            &[None, Some(ColumnDefault::Default("\"x\"".to_string()))],
+            &[false, true],
+            &[("a".to_string(), SortOrder::Asc)],
+        )?;
+
+        assert_eq!(
+            column_rows(&catalog)?,
+            vec![
+                vec![
+                    Datum::from(1),
+                    Datum::from("a"),
+                    Datum::from("INTEGER"),
+                    Datum::Null,
+                    Datum::Null,
+                    Datum::from("YES"),
+                    Datum::from("PRI")
+                ],
+                vec![
+                    Datum::from(2),
+                    Datum::from("b"),
+                    Datum::from("TEXT"),
+                    Datum::from("\"x\""),
+                    Datum::Null,
+                    Datum::from("NO"),
+                    Datum::from("")
+                ],
+            ]
+        );
+
+        catalog.drop_table("default", "t")?;
+        assert_eq!(column_rows(&catalog)?, Vec::<Vec<Datum>>::new());
+        Ok(())
+    }
+}
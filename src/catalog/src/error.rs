@@ -9,6 +9,19 @@ pub enum CatalogError {
     DatabaseAlreadyExists(String),
     DatabaseNotFound(String),
     DatabaseNotEmpty(String),
+    PartitionAlreadyExists(String, String, String),
+    PartitionNotFound(String, String, String),
+    InvalidPartitioning(String),
+    InvalidPrimaryKey(String),
+    ObjectLocked(String),
+    IndexAlreadyExists(String, String, String),
+    InvalidIndex(String),
+    UserAlreadyExists(String),
+    UserNotFound(String),
+    SinkAlreadyExists(String, String),
+    SinkNotFound(String, String),
+    FunctionAlreadyExists(String),
+    FunctionNotFound(String),
 }
 
 impl Display for CatalogError {
@@ -31,6 +44,42 @@ impl Display for CatalogError {
                 "Database {} is not empty, please remote all contained tables first",
                 db
             )),
+            CatalogError::PartitionAlreadyExists(db, table, partition) => f.write_fmt(
+                format_args!("Partition {} of {}.{} already exists", partition, db, table),
+            ),
+            CatalogError::PartitionNotFound(db, table, partition) => f.write_fmt(format_args!(
+                "Partition {} of {}.{} not found",
+                partition, db, table
+            )),
+            CatalogError::InvalidPartitioning(message) => f.write_str(message),
+            CatalogError::InvalidPrimaryKey(message) => f.write_str(message),
+            CatalogError::ObjectLocked(object) => f.write_fmt(format_args!(
+                "{} is locked by another DDL statement, please retry",
+                object
+            )),
+            CatalogError::IndexAlreadyExists(db, table, index) => f.write_fmt(format_args!(
+                "Index {} on {}.{} already exists",
+                index, db, table
+            )),
+            CatalogError::InvalidIndex(message) => f.write_str(message),
+            CatalogError::UserAlreadyExists(user) => {
+                f.write_fmt(format_args!("User {} already exists", user))
+            }
+            CatalogError::UserNotFound(user) => {
+                f.write_fmt(format_args!("User {} not found", user))
+            }
+            CatalogError::SinkAlreadyExists(db, sink) => {
+                f.write_fmt(format_args!("Sink {}.{} already exists", db, sink))
+            }
+            CatalogError::SinkNotFound(db, sink) => {
+                f.write_fmt(format_args!("Sink {}.{} not found", db, sink))
+            }
+            CatalogError::FunctionAlreadyExists(name) => {
+                f.write_fmt(format_args!("Function {} already exists", name))
+            }
+            CatalogError::FunctionNotFound(name) => {
+                f.write_fmt(format_args!("Function {} not found", name))
+            }
         }
     }
 }
@@ -0,0 +1,117 @@
+use crate::{columns_datum, Catalog, CatalogError};
+use data::json::{JsonBuilder, OwnedJson};
+use data::{DataType, Datum, LogicalTimestamp};
+
+/// A table whose rows are read straight out of the csv files in a directory rather than being
+/// stored, the files are parsed each time the table is scanned.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ExternalTable {
+    pub location: String,
+    pub delimiter: u8,
+    // When set the first line of each file is skipped
+    pub header: bool,
+    // The number of trailing columns read from key=value subdirectory names (ie hive style
+    // partitioning) rather than from the files themselves.
+    pub partition_columns: usize,
+}
+
+impl Catalog {
+    /// Creates a new external table, the location isn't checked until it's read from.
+    pub fn create_external_table(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        columns: &[(String, DataType)],
+        external_table: &ExternalTable,
+    ) -> Result<(), CatalogError> {
+        self.check_db_exists(database_name)?;
+        self.check_table_not_exists(database_name, table_name)?;
+
+        // The options are kept as json in the sql context column
+        let options = Datum::from(JsonBuilder::default().object(|object| {
+            object.push_string("format", "csv");
+            object.push_string("delimiter", &(external_table.delimiter as char).to_string());
+            object.push_bool("header", external_table.header);
+            object.push_int("partition_columns", external_table.partition_columns as i64);
+        }))
+        .typed_with(DataType::Json)
+        .to_string();
+        let tuple = [
+            Datum::from(database_name),
+            Datum::from(table_name),
+            Datum::from("external table"),
+            Datum::from(external_table.location.as_str()),
+            Datum::from(options.as_str()),
+            Datum::Null,
+            columns_datum(columns),
+            Datum::from(false),
+        ];
+        self.tables_table.atomic_write::<_, CatalogError>(|batch| {
+            self.check_can_create_table(batch, database_name, table_name)?;
+            self.write_columns(batch, database_name, table_name, columns, &[], &[], 0);
+            batch.write_tuple(&self.tables_table, &tuple, LogicalTimestamp::now(), 1)?;
+            Ok(())
+        })
+    }
+}
+
+/// Rebuilds the external table from its location and the options stored alongside it.
+pub(crate) fn external_table(location: &str, options: &str) -> ExternalTable {
+    let options = OwnedJson::parse(options).expect("Bogus external table options");
+    let mut external_table = ExternalTable {
+        location: location.to_string(),
+        delimiter: b',',
+        header: false,
+        partition_columns: 0,
+    };
+    for (key, value) in options.as_json().iter_object().unwrap() {
+        match key {
+            "delimiter" => {
+                let delimiter = value.get_string().unwrap_or(",");
+                external_table.delimiter = delimiter.as_bytes().first().copied().unwrap_or(b',');
+            }
+            "header" => external_table.header = value.get_boolean().unwrap_or(false),
+            "partition_columns" => {
+                let count = value.get_number().map(|n| n.to_string());
+                external_table.partition_columns =
+                    count.and_then(|count| count.parse().ok()).unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+    external_table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableOrView;
+
+    #[test]
+    fn test_external_table_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = [
+            ("id".to_string(), DataType::Integer),
+            ("name".to_string(), DataType::Text),
+            ("country".to_string(), DataType::Text),
+        ];
+        let external_table = ExternalTable {
+            location: "/data/people".to_string(),
+            delimiter: b'|',
+            header: true,
+            partition_columns: 1,
+        };
+        catalog.create_external_table("default", "people", &columns, &external_table)?;
+
+        let item = catalog.item("default", "people")?;
+        assert_eq!(item.columns, columns.to_vec());
+        assert_eq!(item.item, TableOrView::External(external_table.clone()));
+        assert!(catalog
+            .create_external_table("default", "people", &columns, &external_table)
+            .is_err());
+
+        catalog.drop_table("default", "people")?;
+        assert!(catalog.item("default", "people").is_err());
+        Ok(())
+    }
+}
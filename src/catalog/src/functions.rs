@@ -0,0 +1,169 @@
+use crate::{Catalog, CatalogError};
+use data::json::JsonBuilder;
+use data::{DataType, Datum, LogicalTimestamp, TupleIter};
+use std::convert::TryFrom;
+use storage::StorageError;
+
+/// A user defined function, it's up to the function registry to make sense of the body.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct UserFunction {
+    pub name: String,
+    pub args: Vec<(String, DataType)>,
+    pub ret: DataType,
+    pub body: FunctionBody,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum FunctionBody {
+    // The bytes of a wasm module
+    Wasm(Vec<u8>),
+    // The sql for an expression
+    Sql(String),
+}
+
+impl Catalog {
+    /// Records a user defined function, it's up to the caller to register it with the function
+    /// registry.
+    pub fn create_function(&mut self, function: &UserFunction) -> Result<(), CatalogError> {
+        if self.function(&function.name)?.is_some() {
+            return Err(CatalogError::FunctionAlreadyExists(function.name.clone()));
+        }
+        let args = Datum::from(JsonBuilder::default().array(|array| {
+            for (name, datatype) in &function.args {
+                array.push_array(|arg| {
+                    arg.push_string(name);
+                    arg.push_string(&format!("{:#}", datatype));
+                });
+            }
+        }));
+        let (language, body) = match &function.body {
+            FunctionBody::Wasm(module) => ("wasm", Datum::from(module.clone())),
+            FunctionBody::Sql(sql) => ("sql", Datum::from(sql.as_str())),
+        };
+
+        self.functions_table
+            .atomic_write::<_, StorageError>(|batch| {
+                batch.system_write_tuple(
+                    &self.functions_table,
+                    &[
+                        Datum::from(function.name.as_str()),
+                        args,
+                        Datum::from(format!("{:#}", function.ret)),
+                        Datum::from(language),
+                        body,
+                    ],
+                    1,
+                );
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Drops a user defined function
+    pub fn drop_function(&mut self, name: &str) -> Result<(), CatalogError> {
+        if self.function(name)?.is_none() {
+            return Err(CatalogError::FunctionNotFound(name.to_string()));
+        }
+        self.functions_table
+            .atomic_write::<_, StorageError>(|batch| {
+                batch.system_delete_tuple(&self.functions_table, &[Datum::from(name)]);
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Returns the user defined function with the given name or None if it doesn't exist.
+    pub fn function(&self, name: &str) -> Result<Option<UserFunction>, CatalogError> {
+        let mut key_buf = vec![];
+        let mut rest = vec![];
+        let freq = self.functions_table.system_point_lookup(
+            &[Datum::from(name)],
+            &mut key_buf,
+            &mut rest,
+        )?;
+        Ok(freq.map(|_| user_function(name, &rest)))
+    }
+
+    /// A number that changes whenever the user defined functions do.
+    pub fn functions_version(&self) -> u64 {
+        self.functions_table.version()
+    }
+
+    /// Lists all the user defined functions.
+    pub fn functions(&self) -> Result<Vec<UserFunction>, CatalogError> {
+        let mut functions = vec![];
+        let mut iter = self.functions_table.full_scan(LogicalTimestamp::MAX);
+        while let Some((tuple, freq)) = iter.next()? {
+            if freq > 0 {
+                functions.push(user_function(tuple[0].as_text(), &tuple[1..]));
+            }
+        }
+        Ok(functions)
+    }
+}
+
+/// Builds the function from the columns after its name
+fn user_function(name: &str, rest: &[Datum]) -> UserFunction {
+    UserFunction {
+        name: name.to_string(),
+        args: rest[0]
+            .as_json()
+            .iter_array()
+            .unwrap()
+            .map(|arg| {
+                let mut iter = arg.iter_array().unwrap();
+                let name = iter.next().unwrap().get_string().unwrap();
+                let datatype = DataType::try_from(iter.next().unwrap().get_string().unwrap());
+                (name.to_string(), datatype.unwrap())
+            })
+            .collect(),
+        ret: DataType::try_from(rest[1].as_text()).unwrap(),
+        body: if rest[2].as_text() == "sql" {
+            FunctionBody::Sql(rest[3].as_text().to_string())
+        } else {
+            FunctionBody::Wasm(rest[3].as_bytea().to_vec())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_functions_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let function = UserFunction {
+            name: "add_one".to_string(),
+            args: vec![("x".to_string(), DataType::BigInt)],
+            ret: DataType::BigInt,
+            body: FunctionBody::Wasm(vec![0, 97, 115, 109]),
+        };
+        let sql_function = UserFunction {
+            name: "double".to_string(),
+            args: vec![("x".to_string(), DataType::Integer)],
+            ret: DataType::Integer,
+            body: FunctionBody::Sql("x * 2".to_string()),
+        };
+
+        catalog.create_function(&function)?;
+        catalog.create_function(&sql_function)?;
+        assert_eq!(catalog.function("add_one")?, Some(function.clone()));
+        assert_eq!(
+            catalog.functions()?,
+            vec![function.clone(), sql_function.clone()]
+        );
+        assert_eq!(
+            catalog.create_function(&function),
+            Err(CatalogError::FunctionAlreadyExists("add_one".to_string()))
+        );
+
+        catalog.drop_function("add_one")?;
+        assert_eq!(catalog.functions()?, vec![sql_function]);
+        assert_eq!(
+            catalog.drop_function("add_one"),
+            Err(CatalogError::FunctionNotFound("add_one".to_string()))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,667 @@
+use crate::{prefix_metadata_tuple, Catalog, CatalogError, TableOrView};
+use data::fulltext::postings;
+use data::index::index_tuple;
+use data::{DataType, Datum, LogicalTimestamp, SortOrder, TupleIter};
+use storage::{StorageError, Table, Writer};
+
+/// The name given to the index backing a table's primary key
+const PRIMARY_INDEX_NAME: &str = "PRIMARY";
+
+/// A full-text index over a text column.
+/// The index is an inverted index stored in its own table keyed on the term followed by all the
+/// columns of the indexed row, so a range scan over a term returns the matching rows themselves.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FulltextIndex {
+    pub name: String,
+    // The offset of the indexed column
+    pub column: usize,
+    pub table: Table,
+}
+
+/// A secondary index over one or more columns of a table.
+/// The index is stored in its own table keyed on the indexed columns followed by all the columns
+/// of the row, so like full-text indexes a range scan of the index returns the rows themselves.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SecondaryIndex {
+    pub name: String,
+    // The offsets of the indexed columns
+    pub columns: Vec<usize>,
+    pub table: Table,
+}
+
+impl Catalog {
+    /// Creates a secondary index on the columns of a table, indexing any existing rows.
+    pub fn create_index(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        index_name: &str,
+        column_names: &[String],
+    ) -> Result<(), CatalogError> {
+        let item = self.item(database_name, table_name)?;
+        let table = match &item.item {
+            TableOrView::Table(table) if item.materialized_view.is_none() => table,
+            _ => {
+                return Err(CatalogError::InvalidIndex(format!(
+                    "{}.{} is a view, only tables can be indexed",
+                    database_name, table_name
+                )))
+            }
+        };
+        if item.partitioning.is_some() {
+            return Err(CatalogError::InvalidIndex(format!(
+                "Indexes are not supported on partitioned tables ({}.{})",
+                database_name, table_name
+            )));
+        }
+        let mut columns = vec![];
+        for column_name in column_names {
+            let column = item
+                .columns
+                .iter()
+                .position(|(alias, _)| alias == column_name)
+                .ok_or_else(|| {
+                    CatalogError::InvalidIndex(format!("Unknown index column {}", column_name))
+                })?;
+            if columns.contains(&column) {
+                return Err(CatalogError::InvalidIndex(format!(
+                    "Duplicate index column {}",
+                    column_name
+                )));
+            }
+            columns.push(column);
+        }
+        self.check_index_not_exists(database_name, table_name, index_name)?;
+
+        let index_table_id = self.generate_table_id()?;
+        let mut pks = vec![SortOrder::Asc; columns.len()];
+        pks.extend_from_slice(table.pk());
+        let index_table = self.storage.table(index_table_id, pks.len(), pks.clone());
+
+        let timestamp = LogicalTimestamp::now();
+        self.indexes_table
+            .atomic_write::<_, StorageError>(|batch| {
+                let prefix_tuple = prefix_metadata_tuple(index_table_id, pks.len(), &pks);
+                batch.write_tuple(&self.prefix_metadata_table, &prefix_tuple, timestamp, 1)?;
+                for (seq, column_name) in column_names.iter().enumerate() {
+                    let tuple = [
+                        Datum::from(database_name),
+                        Datum::from(table_name),
+                        Datum::from(index_name),
+                        Datum::from(seq as i32 + 1),
+                        Datum::from(column_name.as_str()),
+                        Datum::from("A"),
+                        Datum::from(true),
+                        Datum::from(index_table_id as i64),
+                    ];
+                    batch.system_write_tuple(&self.indexes_table, &tuple, 1);
+                }
+
+                let mut rows = table.full_scan(LogicalTimestamp::MAX);
+                while let Some((row, freq)) = rows.next()? {
+                    batch.write_tuple(
+                        &index_table,
+                        &index_tuple(row, &columns),
+                        timestamp,
+                        freq,
+                    )?;
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    fn check_index_not_exists(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        index_name: &str,
+    ) -> Result<(), CatalogError> {
+        let index_key = [
+            Datum::from(database_name),
+            Datum::from(table_name),
+            Datum::from(index_name),
+        ];
+        let mut iter = self.indexes_table.range_scan(
+            Some(&index_key),
+            Some(&index_key),
+            LogicalTimestamp::MAX,
+        );
+        if iter.next()?.is_some() {
+            Err(CatalogError::IndexAlreadyExists(
+                database_name.to_string(),
+                table_name.to_string(),
+                index_name.to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Creates a full-text index on a text column of a table, indexing any existing rows.
+    pub fn create_fulltext_index(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        index_name: &str,
+        column_name: &str,
+    ) -> Result<(), CatalogError> {
+        let item = self.item(database_name, table_name)?;
+        let table = match &item.item {
+            TableOrView::Table(table) => table,
+            TableOrView::View(_) | TableOrView::External(_) => {
+                return Err(CatalogError::InvalidIndex(format!(
+                    "{}.{} is not a table, only tables can be indexed",
+                    database_name, table_name
+                )))
+            }
+        };
+        if item.partitioning.is_some() {
+            return Err(CatalogError::InvalidIndex(format!(
+                "Full-text indexes are not supported on partitioned tables ({}.{})",
+                database_name, table_name
+            )));
+        }
+        let column = match item
+            .columns
+            .iter()
+            .position(|(alias, _)| alias == column_name)
+        {
+            Some(column) if item.columns[column].1 == DataType::Text => column,
+            Some(_) => {
+                return Err(CatalogError::InvalidIndex(format!(
+                    "Full-text index column {} must be of type TEXT",
+                    column_name
+                )))
+            }
+            None => {
+                return Err(CatalogError::InvalidIndex(format!(
+                    "Unknown full-text index column {}",
+                    column_name
+                )))
+            }
+        };
+        self.check_index_not_exists(database_name, table_name, index_name)?;
+
+        let index_table_id = self.generate_table_id()?;
+        let mut pks = vec![SortOrder::Asc];
+        pks.extend_from_slice(table.pk());
+        let index_table = self
+            .storage
+            .table(index_table_id, item.columns.len() + 1, pks.clone());
+
+        let timestamp = LogicalTimestamp::now();
+        self.indexes_table
+            .atomic_write::<_, StorageError>(|batch| {
+                let prefix_tuple = prefix_metadata_tuple(index_table_id, pks.len(), &pks);
+                batch.write_tuple(&self.prefix_metadata_table, &prefix_tuple, timestamp, 1)?;
+                let tuple = [
+                    Datum::from(database_name),
+                    Datum::from(table_name),
+                    Datum::from(index_name),
+                    Datum::from(1),
+                    Datum::from(column_name),
+                    Datum::Null,
+                    Datum::from(true),
+                    Datum::from(index_table_id as i64),
+                ];
+                batch.system_write_tuple(&self.indexes_table, &tuple, 1);
+
+                let mut rows = table.full_scan(LogicalTimestamp::MAX);
+                while let Some((row, freq)) = rows.next()? {
+                    for posting in postings(row, column) {
+                        batch.write_tuple(&index_table, &posting, timestamp, freq)?;
+                    }
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Returns the full-text indexes of a table.
+    pub(crate) fn fulltext_indexes(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        columns: &[(String, DataType)],
+        pks: &[SortOrder],
+    ) -> Result<Vec<FulltextIndex>, CatalogError> {
+        let table_key = [Datum::from(database_name), Datum::from(table_name)];
+        let mut iter = self.indexes_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        let mut indexes = vec![];
+        while let Some((tuple, _freq)) = iter.next()? {
+            // Only full-text indexes are without a collation
+            if tuple[5].is_null() {
+                let column_name = tuple[4].as_text();
+                let mut index_pks = vec![SortOrder::Asc];
+                index_pks.extend_from_slice(pks);
+                indexes.push(FulltextIndex {
+                    name: tuple[2].as_text().to_string(),
+                    column: columns
+                        .iter()
+                        .position(|(alias, _)| alias == column_name)
+                        .unwrap(),
+                    table: self.storage.table(
+                        tuple[7].as_bigint() as u32,
+                        columns.len() + 1,
+                        index_pks,
+                    ),
+                });
+            }
+        }
+        Ok(indexes)
+    }
+
+    /// Returns the secondary indexes of a table.
+    pub(crate) fn secondary_indexes(
+        &self,
+        database_name: &str,
+        table_name: &str,
+        columns: &[(String, DataType)],
+        pks: &[SortOrder],
+    ) -> Result<Vec<SecondaryIndex>, CatalogError> {
+        let table_key = [Datum::from(database_name), Datum::from(table_name)];
+        let mut iter = self.indexes_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        // (name, columns, index table id), the rows for each index come through in
+        // seq_in_index order
+        let mut indexes: Vec<(String, Vec<usize>, u32)> = vec![];
+        while let Some((tuple, _freq)) = iter.next()? {
+            let index_name = tuple[2].as_text();
+            if tuple[5].is_null() || index_name == PRIMARY_INDEX_NAME {
+                continue;
+            }
+            let column_name = tuple[4].as_text();
+            let column = columns
+                .iter()
+                .position(|(alias, _)| alias == column_name)
+                .unwrap();
+            match indexes.last_mut() {
+                Some((name, index_columns, _)) if name == index_name => index_columns.push(column),
+                _ => indexes.push((
+                    index_name.to_string(),
+                    vec![column],
+                    tuple[7].as_bigint() as u32,
+                )),
+            }
+        }
+
+        Ok(indexes
+            .into_iter()
+            .map(|(name, columns, index_table_id)| {
+                let mut index_pks = vec![SortOrder::Asc; columns.len()];
+                index_pks.extend_from_slice(pks);
+                SecondaryIndex {
+                    name,
+                    columns,
+                    table: self
+                        .storage
+                        .table(index_table_id, index_pks.len(), index_pks),
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the number of columns in a table's primary key, ie the length of its primary index.
+    pub(crate) fn primary_key_len(
+        &self,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<usize, CatalogError> {
+        let index_key = [
+            Datum::from(database_name),
+            Datum::from(table_name),
+            Datum::from(PRIMARY_INDEX_NAME),
+        ];
+        let mut iter = self.indexes_table.range_scan(
+            Some(&index_key),
+            Some(&index_key),
+            LogicalTimestamp::MAX,
+        );
+        let mut len = 0;
+        while iter.next()?.is_some() {
+            len += 1;
+        }
+        Ok(len)
+    }
+
+    /// Removes the data and metadata of a table's full-text and secondary indexes as part of a
+    /// larger write.
+    pub(crate) fn delete_index_tables(
+        &self,
+        batch: &mut Writer,
+        database_name: &str,
+        table_name: &str,
+        timestamp: LogicalTimestamp,
+    ) -> Result<(), StorageError> {
+        let table_key = [Datum::from(database_name), Datum::from(table_name)];
+        let mut iter = self.indexes_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        while let Some((tuple, _freq)) = iter.next()? {
+            // The primary index is the table itself, and each index only needs deleting once
+            if tuple[2].as_text() == PRIMARY_INDEX_NAME || tuple[3].as_integer() != 1 {
+                continue;
+            }
+            let index_table_id = tuple[7].as_bigint() as u32;
+            self.indexes_table
+                .atomic_write_without_index::<_, StorageError>(|write_batch| {
+                    write_batch.delete_range(
+                        index_table_id.to_be_bytes(),
+                        (index_table_id + 2).to_be_bytes(),
+                    );
+                    Ok(())
+                })?;
+            let prefix_key = [tuple[7].clone()];
+            let mut prefix_iter = self.prefix_metadata_table.range_scan(
+                Some(&prefix_key),
+                Some(&prefix_key),
+                LogicalTimestamp::MAX,
+            );
+            if let Some((prefix_tuple, prefix_freq)) = prefix_iter.next()? {
+                batch.write_tuple(
+                    &self.prefix_metadata_table,
+                    prefix_tuple,
+                    timestamp,
+                    -prefix_freq,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records the primary index of a newly created table as part of a larger write.
+    pub(crate) fn write_primary_index(
+        &self,
+        batch: &mut Writer,
+        database_name: &str,
+        table_name: &str,
+        table_id: u32,
+        columns: &[(String, DataType)],
+        pks: &[SortOrder],
+    ) {
+        for (seq, ((column_name, _), sort_order)) in columns.iter().zip(pks).enumerate() {
+            let collation = match sort_order {
+                SortOrder::Asc => "A",
+                SortOrder::Desc => "D",
+            };
+            let tuple = [
+                Datum::from(database_name),
+                Datum::from(table_name),
+                Datum::from(PRIMARY_INDEX_NAME),
+                Datum::from(seq as i32 + 1),
+                Datum::from(column_name.as_str()),
+                Datum::from(collation),
+                Datum::from(false),
+                Datum::from(table_id as i64),
+            ];
+            batch.system_write_tuple(&self.indexes_table, &tuple, 1);
+        }
+    }
+
+    /// Removes all the index entries for a table as part of a larger write.
+    pub(crate) fn delete_indexes(
+        &self,
+        batch: &mut Writer,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<(), StorageError> {
+        let table_key = [Datum::from(database_name), Datum::from(table_name)];
+        let mut iter = self.indexes_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        while let Some((tuple, _freq)) = iter.next()? {
+            batch.system_delete_tuple(&self.indexes_table, &tuple[..4]);
+        }
+        Ok(())
+    }
+
+    /// Populates the indexes table for tables created before it existed.
+    pub(crate) fn backfill_indexes(&mut self) -> Result<(), CatalogError> {
+        let mut tables = vec![];
+        let mut iter = self.tables_table.full_scan(LogicalTimestamp::MAX);
+        while let Some((tuple, freq)) = iter.next()? {
+            if freq > 0 && tuple[2].as_text() == "table" {
+                tables.push((
+                    tuple[0].as_text().to_string(),
+                    tuple[1].as_text().to_string(),
+                ));
+            }
+        }
+
+        for (database_name, table_name) in tables {
+            let item = self.item(&database_name, &table_name)?;
+            if let TableOrView::Table(table) = &item.item {
+                self.indexes_table
+                    .atomic_write::<_, StorageError>(|batch| {
+                        self.write_primary_index(
+                            batch,
+                            &database_name,
+                            &table_name,
+                            table.id(),
+                            &item.columns,
+                            table.pk(),
+                        );
+                        Ok(())
+                    })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_rows(catalog: &Catalog) -> Result<Vec<Vec<Datum<'static>>>, CatalogError> {
+        let key = [Datum::from("default"), Datum::from("t")];
+        let mut iter =
+            catalog
+                .indexes_table
+                .range_scan(Some(&key), Some(&key), LogicalTimestamp::MAX);
+        let mut rows = vec![];
+        while let Some((tuple, _freq)) = iter.next()? {
+            rows.push(tuple[2..].iter().map(|d| d.as_static()).collect());
+        }
+        Ok(rows)
+    }
+
+    fn create_documents_table(catalog: &mut Catalog) -> Result<Table, CatalogError> {
+        catalog.create_table(
+            "default",
+            "t",
+            &[
+                ("id".to_string(), DataType::Integer),
+                ("body".to_string(), DataType::Text),
+            ],
+            &[],
+            &[],
+            &[],
+        )?;
+        if let TableOrView::Table(table) = catalog.item("default", "t")?.item {
+            Ok(table)
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn test_primary_index_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        catalog.create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Text),
+                ("c".to_string(), DataType::Boolean),
+            ],
+            &[],
+            &[],
+            &[
+                ("a".to_string(), SortOrder::Asc),
+                ("b".to_string(), SortOrder::Desc),
+            ],
+        )?;
+
+        let rows = index_rows(&catalog)?;
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][0], Datum::from(PRIMARY_INDEX_NAME));
+        assert_eq!(rows[0][1], Datum::from(1));
+        assert_eq!(rows[0][2], Datum::from("a"));
+        assert_eq!(rows[0][3], Datum::from("A"));
+        assert_eq!(rows[1][2], Datum::from("b"));
+        assert_eq!(rows[1][3], Datum::from("D"));
+
+        catalog.drop_table("default", "t")?;
+        assert!(index_rows(&catalog)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fulltext_index_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let table = create_documents_table(&mut catalog)?;
+        table.atomic_write::<_, StorageError>(|batch| {
+            batch.write_tuple(
+                &table,
+                &[Datum::from(1), Datum::from("Hello World")],
+                LogicalTimestamp::now(),
+                1,
+            )
+        })?;
+
+        catalog.create_fulltext_index("default", "t", "body_idx", "body")?;
+        let item = catalog.item("default", "t")?;
+        assert_eq!(item.fulltext_indexes.len(), 1);
+        let index = &item.fulltext_indexes[0];
+        assert_eq!(index.name, "body_idx");
+        assert_eq!(index.column, 1);
+
+        // The existing row should have been indexed
+        let mut iter = index.table.full_scan(LogicalTimestamp::MAX);
+        assert_eq!(
+            iter.next()?,
+            Some((
+                [
+                    Datum::from("hello"),
+                    Datum::from(1),
+                    Datum::from("Hello World")
+                ]
+                .as_ref(),
+                1
+            ))
+        );
+        assert_eq!(iter.next()?.unwrap().0[0], Datum::from("world"));
+        assert_eq!(iter.next()?, None);
+
+        let rows = index_rows(&catalog)?;
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0], Datum::from(PRIMARY_INDEX_NAME));
+        assert_eq!(rows[2][0], Datum::from("body_idx"));
+        assert_eq!(rows[2][3], Datum::Null);
+        assert_eq!(rows[2][4], Datum::from(true));
+
+        assert_eq!(
+            catalog.create_fulltext_index("default", "t", "body_idx", "body"),
+            Err(CatalogError::IndexAlreadyExists(
+                "default".to_string(),
+                "t".to_string(),
+                "body_idx".to_string()
+            ))
+        );
+
+        let index_table = index.table.clone();
+        catalog.drop_table("default", "t")?;
+        assert!(index_rows(&catalog)?.is_empty());
+        assert_eq!(index_table.full_scan(LogicalTimestamp::MAX).next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fulltext_index_invalid_column() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        create_documents_table(&mut catalog)?;
+        assert!(matches!(
+            catalog.create_fulltext_index("default", "t", "idx", "id"),
+            Err(CatalogError::InvalidIndex(_))
+        ));
+        assert!(matches!(
+            catalog.create_fulltext_index("default", "t", "idx", "missing"),
+            Err(CatalogError::InvalidIndex(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_secondary_index_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let table = create_documents_table(&mut catalog)?;
+        table.atomic_write::<_, StorageError>(|batch| {
+            batch.write_tuple(
+                &table,
+                &[Datum::from(1), Datum::from("abc")],
+                LogicalTimestamp::now(),
+                2,
+            )
+        })?;
+
+        catalog.create_index(
+            "default",
+            "t",
+            "body_id",
+            &["body".to_string(), "id".to_string()],
+        )?;
+        let item = catalog.item("default", "t")?;
+        assert_eq!(item.secondary_indexes.len(), 1);
+        let index = &item.secondary_indexes[0];
+        assert_eq!(index.name, "body_id");
+        assert_eq!(index.columns, vec![1, 0]);
+
+        // The existing row should have been indexed
+        let mut iter = index.table.full_scan(LogicalTimestamp::MAX);
+        assert_eq!(
+            iter.next()?,
+            Some((
+                [
+                    Datum::from("abc"),
+                    Datum::from(1),
+                    Datum::from(1),
+                    Datum::from("abc")
+                ]
+                .as_ref(),
+                2
+            ))
+        );
+        assert_eq!(iter.next()?, None);
+
+        let rows = index_rows(&catalog)?;
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[2][0], Datum::from("body_id"));
+        assert_eq!(rows[2][2], Datum::from("body"));
+        assert_eq!(rows[3][2], Datum::from("id"));
+
+        assert!(matches!(
+            catalog.create_index("default", "t", "idx", &["missing".to_string()]),
+            Err(CatalogError::InvalidIndex(_))
+        ));
+
+        let index_table = index.table.clone();
+        catalog.drop_table("default", "t")?;
+        assert!(index_rows(&catalog)?.is_empty());
+        assert_eq!(index_table.full_scan(LogicalTimestamp::MAX).next()?, None);
+        Ok(())
+    }
+}
@@ -1,13 +1,32 @@
 mod bootstrap;
+mod columns;
+mod external;
+mod functions;
+mod indexes;
+mod locks;
+mod partitions;
+mod replication;
+mod sequences;
+mod sinks;
+mod slow_log;
+mod statistics;
+mod users;
 use data::json::JsonBuilder;
 use data::{DataType, Datum, LogicalTimestamp, SortOrder, TupleIter};
 use std::convert::TryFrom;
-use storage::{Storage, StorageError, Table};
+use storage::{Storage, StorageError, Table, Writer};
 
 mod error;
 pub use error::*;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+pub use external::ExternalTable;
+pub use functions::{FunctionBody, UserFunction};
+pub use indexes::{FulltextIndex, SecondaryIndex};
+use locks::LockManager;
+pub use locks::ObjectLock;
+pub use partitions::{Partition, PartitionBy, PartitionMethod, Partitioning};
+pub use replication::Replication;
+pub use sinks::{Sink, SinkFormat};
+pub use slow_log::SlowQuery;
 
 /// The catalog is responsible for the lifecycles and naming of all the
 /// database objects.
@@ -24,19 +43,76 @@ pub struct Catalog {
     // Table listing tables
     // database_name:text(pk), table_name:text(pk), type:text, sql:text, sql_context:text, table_id:bigint, columns:json, system:bool
     tables_table: Table,
+    // Table holding the statistics collected by ANALYZE
+    // table_id:bigint(pk), column_name:text(pk), row_count:bigint, null_count:bigint, ndv:bigint,
+    // histogram:bytea, sketch:bytea
+    column_statistics_table: Table,
+    // The min and max values of each analyzed column rendered as text, for users to read
+    // table_id:bigint(pk), column_name:text(pk), min_value:text, max_value:text
+    column_bounds_table: Table,
+    // Table listing the partitions of partitioned tables
+    // table_id:bigint(pk), ordinal:int(pk), name:text, partition_id:bigint, method:text,
+    // column_idx:int, upper_bound:bytea
+    partitions_table: Table,
+    // Table listing the columns of the indexes on each table
+    // database_name:text(pk), table_name:text(pk), index_name:text(pk), seq_in_index:int(pk),
+    // column_name:text, collation:text, non_unique:bool, index_table_id:bigint
+    indexes_table: Table,
+    // Table listing the columns of each table and view
+    // database_name:text(pk), table_name:text(pk), ordinal_position:int(pk), column_name:text,
+    // data_type:text, column_default:text, generation_expression:text
+    columns_table: Table,
+    // Table listing the users allowed to connect
+    // name:text(pk), authentication_string:text
+    users_table: Table,
+    // Table listing the sinks exporting changes to files
+    // database_name:text(pk), name:text(pk), source_database:text, source_name:text, path:text,
+    // format:text
+    sinks_table: Table,
+    // Table holding the next value of each of the sequences the catalog allocates ids from
+    // name:text(pk), next_value:bigint
+    sequences_table: Table,
+    // Table listing the user defined functions
+    // name:text(pk), args:json, return_type:text, language:text, body:bytea
+    functions_table: Table,
+    // Table recording the statements that took longer than long_query_time
+    // start_time:timestamp(pk), connection_id:bigint(pk), sql_text:text(pk), user:text, db:text,
+    // duration_ms:bigint, rows_examined:bigint, rows_sent:bigint
+    slow_log_table: Table,
+    locks: LockManager,
 }
 
 /// Represents an item returned by the catalog
 #[derive(Debug, Eq, PartialEq)]
 pub struct CatalogItem {
     pub columns: Vec<(String, DataType)>,
+    // One entry per column
+    pub column_defaults: Vec<Option<ColumnDefault>>,
+    // One entry per column, true for columns declared NOT NULL
+    pub column_not_null: Vec<bool>,
     pub item: TableOrView,
+    // Only set for partitioned tables, the table in item won't hold any data itself.
+    pub partitioning: Option<Partitioning>,
+    pub fulltext_indexes: Vec<FulltextIndex>,
+    pub secondary_indexes: Vec<SecondaryIndex>,
+    // Only set for materialized views, item will be the table holding the view's contents.
+    pub materialized_view: Option<View>,
+    // The primary key is always the leading columns of the table, zero for views.
+    pub primary_key_len: usize,
+}
+
+/// The sql for a column's default value or generated expression
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ColumnDefault {
+    Default(String),
+    Generated(String),
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub enum TableOrView {
     Table(Table),
     View(View),
+    External(ExternalTable),
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -45,9 +121,28 @@ pub struct View {
     pub db_context: String,
 }
 
+/// A materialized view along with the table its contents are kept in.
+#[derive(Debug, Eq, PartialEq)]
+pub struct MaterializedView {
+    pub database: String,
+    pub name: String,
+    pub view: View,
+    pub table: Table,
+}
+
 const PREFIX_METADATA_TABLE_ID: u32 = 0;
 const DATABASES_TABLE_ID: u32 = 2;
 const TABLES_TABLE_ID: u32 = 4;
+const COLUMN_STATISTICS_TABLE_ID: u32 = 6;
+const PARTITIONS_TABLE_ID: u32 = 8;
+const INDEXES_TABLE_ID: u32 = 10;
+const COLUMNS_TABLE_ID: u32 = 12;
+const USERS_TABLE_ID: u32 = 14;
+const SINKS_TABLE_ID: u32 = 16;
+const COLUMN_BOUNDS_TABLE_ID: u32 = 18;
+const SEQUENCES_TABLE_ID: u32 = 20;
+const FUNCTIONS_TABLE_ID: u32 = 22;
+const SLOW_LOG_TABLE_ID: u32 = 24;
 
 impl Catalog {
     /// Creates a catalog, wrapping the passed in storage
@@ -56,11 +151,37 @@ impl Catalog {
             storage.table(PREFIX_METADATA_TABLE_ID, 3, vec![SortOrder::Asc]);
         let databases_table = storage.table(DATABASES_TABLE_ID, 1, vec![SortOrder::Asc]);
         let tables_table = storage.table(TABLES_TABLE_ID, 8, vec![SortOrder::Asc, SortOrder::Asc]);
+        let column_statistics_table = storage.table(
+            COLUMN_STATISTICS_TABLE_ID,
+            7,
+            vec![SortOrder::Asc, SortOrder::Asc],
+        );
+        let partitions_table =
+            storage.table(PARTITIONS_TABLE_ID, 7, vec![SortOrder::Asc, SortOrder::Asc]);
+        let indexes_table = storage.table(INDEXES_TABLE_ID, 8, vec![SortOrder::Asc; 4]);
+        let columns_table = storage.table(COLUMNS_TABLE_ID, 9, vec![SortOrder::Asc; 3]);
+        let users_table = storage.table(USERS_TABLE_ID, 2, vec![SortOrder::Asc]);
+        let sinks_table = storage.table(SINKS_TABLE_ID, 6, vec![SortOrder::Asc; 2]);
+        let column_bounds_table = storage.table(COLUMN_BOUNDS_TABLE_ID, 4, vec![SortOrder::Asc; 2]);
+        let sequences_table = storage.table(SEQUENCES_TABLE_ID, 2, vec![SortOrder::Asc]);
+        let functions_table = storage.table(FUNCTIONS_TABLE_ID, 5, vec![SortOrder::Asc]);
+        let slow_log_table = storage.table(SLOW_LOG_TABLE_ID, 8, vec![SortOrder::Asc; 3]);
         let mut catalog = Catalog {
             storage,
             prefix_metadata_table,
             databases_table,
             tables_table,
+            column_statistics_table,
+            column_bounds_table,
+            partitions_table,
+            indexes_table,
+            columns_table,
+            users_table,
+            sinks_table,
+            sequences_table,
+            functions_table,
+            slow_log_table,
+            locks: LockManager::default(),
         };
         catalog.bootstrap()?;
         Ok(catalog)
@@ -71,6 +192,32 @@ impl Catalog {
         Catalog::new(Storage::new_in_mem()?)
     }
 
+    /// Flushes everything written so far out to disk, used when shutting down.
+    pub fn flush(&self) -> Result<(), CatalogError> {
+        Ok(self.storage.flush()?)
+    }
+
+    /// Returns a number that changes whenever anything in the catalog does, ie for invalidating
+    /// anything derived from the catalog such as cached plans.
+    pub fn version(&self) -> u64 {
+        [
+            &self.prefix_metadata_table,
+            &self.databases_table,
+            &self.tables_table,
+            &self.column_statistics_table,
+            &self.column_bounds_table,
+            &self.partitions_table,
+            &self.indexes_table,
+            &self.columns_table,
+            &self.users_table,
+            &self.sinks_table,
+            &self.functions_table,
+        ]
+        .iter()
+        .map(|table| table.version())
+        .sum()
+    }
+
     /// Returns the catalog item with the given name
     pub fn item(&self, database: &str, table: &str) -> Result<CatalogItem, CatalogError> {
         let tables_pk = [Datum::from(database), Datum::from(table)];
@@ -89,21 +236,46 @@ impl Catalog {
         }
         let table_type = value[0].as_text();
 
-        let columns: Vec<_> = value[4]
-            .as_json()
-            .iter_array()
-            .unwrap()
-            .map(|col| {
-                let mut iter = col.iter_array().unwrap();
-                let col_name = iter.next().unwrap().get_string().unwrap();
-                let col_type =
-                    DataType::try_from(iter.next().unwrap().get_string().unwrap()).unwrap();
-                (col_name.to_string(), col_type)
+        let mut columns = vec![];
+        let mut column_defaults = vec![];
+        let mut column_not_null = vec![];
+        for col in value[4].as_json().iter_array().unwrap() {
+            let mut iter = col.iter_array().unwrap();
+            let col_name = iter.next().unwrap().get_string().unwrap();
+            let col_type = DataType::try_from(iter.next().unwrap().get_string().unwrap()).unwrap();
+            // Columns with defaults have two extra entries, the type of default and the sql,
+            // NOT NULL columns have a trailing "not null" entry.
+            let mut col_default = None;
+            let mut not_null = false;
+            while let Some(entry) = iter.next() {
+                match entry.get_string().unwrap() {
+                    "not null" => not_null = true,
+                    default_type => {
+                        let sql = iter.next().unwrap().get_string().unwrap().to_string();
+                        col_default = Some(match default_type {
+                            "default" => ColumnDefault::Default(sql),
+                            "generated" => ColumnDefault::Generated(sql),
+                            dt => panic!("Unknown default type {}", dt),
+                        });
+                    }
+                }
+            }
+            columns.push((col_name.to_string(), col_type));
+            column_defaults.push(col_default);
+            column_not_null.push(not_null);
+        }
+
+        let materialized_view = if table_type == "materialized view" {
+            Some(View {
+                sql: value[1].as_text().to_string(),
+                db_context: value[2].as_text().to_string(),
             })
-            .collect();
+        } else {
+            None
+        };
 
-        let item = match table_type {
-            "table" => {
+        let (item, partitioning, fulltext_indexes, secondary_indexes) = match table_type {
+            "table" | "materialized view" => {
                 let id = value[3].as_bigint() as u32;
 
                 let prefix_pk = [value[3].clone()];
@@ -111,7 +283,7 @@ impl Catalog {
                     .system_point_lookup(&prefix_pk, &mut key_buf, &mut value)?
                     .unwrap();
 
-                let pk = value[1]
+                let pk: Vec<_> = value[1]
                     .as_json()
                     .iter_array()
                     .unwrap()
@@ -124,16 +296,88 @@ impl Catalog {
                     })
                     .collect();
 
-                TableOrView::Table(self.storage.table(id, columns.len(), pk))
+                let partitioning = self.partitioning(id, columns.len(), &pk)?;
+                let fulltext_indexes = self.fulltext_indexes(database, table, &columns, &pk)?;
+                let secondary_indexes = self.secondary_indexes(database, table, &columns, &pk)?;
+                (
+                    TableOrView::Table(self.storage.table(id, columns.len(), pk)),
+                    partitioning,
+                    fulltext_indexes,
+                    secondary_indexes,
+                )
             }
-            "view" => TableOrView::View(View {
-                sql: value[1].as_text().to_string(),
-                db_context: value[2].as_text().to_string(),
-            }),
+            "view" => (
+                TableOrView::View(View {
+                    sql: value[1].as_text().to_string(),
+                    db_context: value[2].as_text().to_string(),
+                }),
+                None,
+                vec![],
+                vec![],
+            ),
+            "external table" => (
+                TableOrView::External(external::external_table(
+                    value[1].as_text(),
+                    value[2].as_text(),
+                )),
+                None,
+                vec![],
+                vec![],
+            ),
             tt => panic!("Unknown table type {}", tt),
         };
 
-        Ok(CatalogItem { columns, item })
+        let primary_key_len = self.primary_key_len(database, table)?;
+        Ok(CatalogItem {
+            columns,
+            column_defaults,
+            column_not_null,
+            item,
+            partitioning,
+            fulltext_indexes,
+            secondary_indexes,
+            materialized_view,
+            primary_key_len,
+        })
+    }
+
+    /// Returns the materialized view with the given name, None if it's a table or plain view.
+    pub fn materialized_view(
+        &self,
+        database: &str,
+        name: &str,
+    ) -> Result<Option<MaterializedView>, CatalogError> {
+        let item = self.item(database, name)?;
+        if let (TableOrView::Table(table), Some(view)) = (item.item, item.materialized_view) {
+            Ok(Some(MaterializedView {
+                database: database.to_string(),
+                name: name.to_string(),
+                view,
+                table,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Lists all the materialized views across all the databases.
+    pub fn materialized_views(&self) -> Result<Vec<MaterializedView>, CatalogError> {
+        let mut names = vec![];
+        let mut iter = self.tables_table.full_scan(LogicalTimestamp::MAX);
+        while let Some((tuple, freq)) = iter.next()? {
+            if freq > 0 && tuple[2].as_text() == "materialized view" {
+                names.push((
+                    tuple[0].as_text().to_string(),
+                    tuple[1].as_text().to_string(),
+                ));
+            }
+        }
+
+        let mut views = vec![];
+        for (database, name) in names {
+            views.extend(self.materialized_view(&database, &name)?);
+        }
+        Ok(views)
     }
 
     /// Called to create a database
@@ -158,19 +402,34 @@ impl Catalog {
         Ok(())
     }
 
-    /// Creates a new table
+    /// Creates a new table, column_defaults and column_not_null may be left empty if none of the
+    /// columns have defaults or are NOT NULL, and primary_key may be left empty to sort all
+    /// columns ascending.
     pub fn create_table(
         &mut self,
         database_name: &str,
         table_name: &str,
         columns: &[(String, DataType)],
+        column_defaults: &[Option<ColumnDefault>],
+        column_not_null: &[bool],
+        primary_key: &[(String, SortOrder)],
     ) -> Result<(), CatalogError> {
         self.check_db_exists(database_name)?;
         self.check_table_not_exists(database_name, table_name)?;
-        let id = self.generate_table_id(table_name)?;
-        let pk: Vec<_> = columns.iter().map(|_| SortOrder::Asc).collect();
+        let pk = primary_key_sort_orders(columns, primary_key)?;
+        let id = self.generate_table_id()?;
 
-        self.create_table_impl(database_name, table_name, id, columns, &pk, false)
+        self.create_table_impl(
+            database_name,
+            table_name,
+            id,
+            columns,
+            column_defaults,
+            column_not_null,
+            &pk,
+            primary_key_len(columns, primary_key),
+            false,
+        )
     }
 
     /// Creates a new view
@@ -194,6 +453,54 @@ impl Catalog {
         )
     }
 
+    /// Creates a new materialized view, the view starts off empty, it's up to the caller to
+    /// populate it.
+    pub fn create_materialized_view(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        columns: &[(String, DataType)],
+        view_sql: &str,
+        view_context: &str,
+    ) -> Result<(), CatalogError> {
+        self.check_db_exists(database_name)?;
+        self.check_table_not_exists(database_name, table_name)?;
+        let id = self.generate_table_id()?;
+        let pks = vec![SortOrder::Asc; columns.len()];
+        let timestamp = LogicalTimestamp::now();
+        let (mut table_tuple, prefix_tuple) = table_metadata_tuples(
+            database_name,
+            table_name,
+            id,
+            columns,
+            &[],
+            &[],
+            &pks,
+            false,
+        );
+        table_tuple[2] = Datum::from("materialized view");
+        table_tuple[3] = Datum::from(view_sql);
+        table_tuple[4] = Datum::from(view_context);
+
+        self.tables_table.atomic_write::<_, CatalogError>(|batch| {
+            self.check_can_create_table(batch, database_name, table_name)?;
+            batch.write_tuple(&self.tables_table, &table_tuple, timestamp, 1)?;
+            batch.write_tuple(&self.prefix_metadata_table, &prefix_tuple, timestamp, 1)?;
+            self.write_primary_index(batch, database_name, table_name, id, columns, &pks);
+            self.write_columns(
+                batch,
+                database_name,
+                table_name,
+                columns,
+                &[],
+                &[],
+                pks.len(),
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
     /// Drops a table or a view
     pub fn drop_table(
         &mut self,
@@ -204,17 +511,20 @@ impl Catalog {
         self.drop_table_impl(database_name, table_name)
     }
 
-    /// Creates a database, doesn't do any checks to see if the database already exists etc.
+    /// Creates a database, the check that the database doesn't already exist is done as part of
+    /// the same write so two racing creates can't both succeed.
     fn create_database_impl(&mut self, database_name: &str) -> Result<(), CatalogError> {
-        self.databases_table.atomic_write(|batch| {
-            batch.write_tuple(
-                &self.databases_table,
-                &[Datum::from(database_name)],
-                LogicalTimestamp::now(),
-                1,
-            )
-        })?;
-        Ok(())
+        self.databases_table
+            .atomic_write::<_, CatalogError>(|batch| {
+                let db_datum = [Datum::from(database_name)];
+                if batch.freq(&self.databases_table, &db_datum)? != 0 {
+                    return Err(CatalogError::DatabaseAlreadyExists(
+                        database_name.to_string(),
+                    ));
+                }
+                batch.write_tuple(&self.databases_table, &db_datum, LogicalTimestamp::now(), 1)?;
+                Ok(())
+            })
     }
 
     /// Check database empty.
@@ -304,80 +614,84 @@ impl Catalog {
         Ok(iter.next()?.is_some())
     }
 
-    fn generate_table_id(&mut self, table_name: &str) -> Result<u32, CatalogError> {
-        let mut hasher = DefaultHasher::new();
-        table_name.hash(&mut hasher);
-        let mut id = hasher.finish() as u32;
-        // Make sure table_id is even
-        if id & 1 == 1 {
-            id -= 1;
+    /// Checks the database exists and the table doesn't from within the batch that's creating
+    /// the table. The reads see the latest committed state (plus anything earlier in the batch)
+    /// and commit along with the create, so a racing create of the same name or drop of the
+    /// database can't slip in between the check and the write.
+    fn check_can_create_table(
+        &self,
+        batch: &mut Writer,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<(), CatalogError> {
+        if batch.freq(&self.databases_table, &[Datum::from(database_name)])? == 0 {
+            return Err(CatalogError::DatabaseNotFound(database_name.to_string()));
         }
-        loop {
-            let proposed = [Datum::from(id as i64)];
-            let mut iter = self.prefix_metadata_table.range_scan(
-                Some(&proposed),
-                Some(&proposed),
-                LogicalTimestamp::MAX,
-            );
-            if iter.next()?.is_none() {
-                return Ok(id);
-            } else {
-                id += 2;
-            }
+        let table_key = [Datum::from(database_name), Datum::from(table_name)];
+        if batch.freq(&self.tables_table, &table_key)? != 0 {
+            return Err(CatalogError::TableAlreadyExists(
+                database_name.to_string(),
+                table_name.to_string(),
+            ));
         }
+        Ok(())
     }
 
-    /// Creates a table but doesn't do any checks around the database, table, or id.
+    /// Creates a table but doesn't do any checks around the id, the database and table names are
+    /// checked as part of the write.
+    #[allow(clippy::too_many_arguments)]
     fn create_table_impl(
         &mut self,
         database_name: &str,
         table_name: &str,
         table_id: u32,
         columns: &[(String, DataType)],
+        column_defaults: &[Option<ColumnDefault>],
+        column_not_null: &[bool],
         pks: &[SortOrder],
+        key_len: usize,
         system: bool,
     ) -> Result<(), CatalogError> {
         let timestamp = LogicalTimestamp::now();
+        let (table_tuple, prefix_tuple) = table_metadata_tuples(
+            database_name,
+            table_name,
+            table_id,
+            columns,
+            column_defaults,
+            column_not_null,
+            pks,
+            system,
+        );
 
-        let columns_datum = Datum::from(JsonBuilder::default().array(|array| {
-            for (alias, datatype) in columns {
-                array.push_array(|col_array| {
-                    col_array.push_string(alias);
-                    col_array.push_string(&format!("{:#}", datatype));
-                })
-            }
-        }));
-
-        let pks = Datum::from(JsonBuilder::default().array(|array| {
-            for pk in pks {
-                array.push_bool(pk.is_desc());
-            }
-        }));
-
-        self.tables_table.atomic_write(|batch| {
-            let tuple = [
-                Datum::from(database_name),
-                Datum::from(table_name),
-                Datum::from("table"),
-                Datum::Null,
-                Datum::Null,
-                Datum::from(table_id as i64),
-                columns_datum,
-                Datum::from(system),
-            ];
-            batch.write_tuple(&self.tables_table, &tuple, timestamp, 1)?;
-
-            let tuple = [
-                Datum::from(table_id as i64),
-                Datum::from(columns.len() as i32),
-                pks,
-            ];
-            batch.write_tuple(&self.prefix_metadata_table, &tuple, timestamp, 1)
+        self.tables_table.atomic_write::<_, CatalogError>(|batch| {
+            self.check_can_create_table(batch, database_name, table_name)?;
+            batch.write_tuple(&self.tables_table, &table_tuple, timestamp, 1)?;
+            batch.write_tuple(&self.prefix_metadata_table, &prefix_tuple, timestamp, 1)?;
+            let primary_index = &pks[..key_len];
+            self.write_primary_index(
+                batch,
+                database_name,
+                table_name,
+                table_id,
+                columns,
+                primary_index,
+            );
+            self.write_columns(
+                batch,
+                database_name,
+                table_name,
+                columns,
+                column_defaults,
+                column_not_null,
+                key_len,
+            );
+            Ok(())
         })?;
         Ok(())
     }
 
-    /// Creates a view but doesn't do any checks around name clashes etc
+    /// Creates a view, name clashes are checked as part of the write
     fn create_view_impl(
         &mut self,
         database_name: &str,
@@ -389,16 +703,8 @@ impl Catalog {
     ) -> Result<(), CatalogError> {
         let timestamp = LogicalTimestamp::now();
 
-        let columns_datum = Datum::from(JsonBuilder::default().array(|array| {
-            for (alias, datatype) in columns {
-                array.push_array(|col_array| {
-                    col_array.push_string(alias);
-                    col_array.push_string(&format!("{:#}", datatype));
-                })
-            }
-        }));
-
-        self.tables_table.atomic_write(|batch| {
+        self.tables_table.atomic_write::<_, CatalogError>(|batch| {
+            self.check_can_create_table(batch, database_name, table_name)?;
             let tuple = [
                 Datum::from(database_name),
                 Datum::from(table_name),
@@ -406,12 +712,13 @@ impl Catalog {
                 Datum::from(sql),
                 Datum::from(context),
                 Datum::Null,
-                columns_datum,
+                columns_datum(columns),
                 Datum::from(system),
             ];
-            batch.write_tuple(&self.tables_table, &tuple, timestamp, 1)
-        })?;
-        Ok(())
+            self.write_columns(batch, database_name, table_name, columns, &[], &[], 0);
+            batch.write_tuple(&self.tables_table, &tuple, timestamp, 1)?;
+            Ok(())
+        })
     }
 
     /// Drops a table or view but doesn't do any of the pre checks
@@ -429,7 +736,7 @@ impl Catalog {
         let (table_tuple, table_freq) = tables_iter.next()?.unwrap();
         self.tables_table.atomic_write::<_, StorageError>(|batch| {
             match table_tuple[2].as_text() {
-                "table" => {
+                "table" | "materialized view" => {
                     // first drop the data, then the meta data
                     // TODO we should be able to genericise write batch and write batch WI so we can choose
                     // to opt into/outof read after write vs higher perf(and delete range support!)
@@ -444,6 +751,10 @@ impl Catalog {
 
                     let (prefix_tuple, prefix_freq) = prefix_iter.next()?.unwrap();
 
+                    self.delete_column_statistics(batch, table_id)?;
+                    self.delete_partitions(batch, table_id, now)?;
+                    self.delete_index_tables(batch, database_name, table_name, now)?;
+
                     self.tables_table
                         .atomic_write_without_index::<_, StorageError>(|write_batch| {
                             write_batch
@@ -457,10 +768,12 @@ impl Catalog {
                         -prefix_freq,
                     )?;
                 }
-                "view" => {}
+                "view" | "external table" => {}
                 tt => panic!("Unknown table type {}", tt),
             }
 
+            self.delete_indexes(batch, database_name, table_name)?;
+            self.delete_columns(batch, database_name, table_name)?;
             batch.write_tuple(&self.tables_table, table_tuple, now, -table_freq)?;
 
             Ok(())
@@ -469,6 +782,135 @@ impl Catalog {
     }
 }
 
+/// The columns of a view (or external table) as stored in the tables table, a json array of
+/// [name, type] pairs.
+fn columns_datum(columns: &[(String, DataType)]) -> Datum<'static> {
+    Datum::from(JsonBuilder::default().array(|array| {
+        for (alias, datatype) in columns {
+            array.push_array(|col_array| {
+                col_array.push_string(alias);
+                col_array.push_string(&format!("{:#}", datatype));
+            })
+        }
+    }))
+}
+
+/// Works out the sort order for each column of a user table.
+/// User tables are keyed on all their columns so the primary key here only controls the sort
+/// order of the key, the primary key columns must be the leading columns of the table (in
+/// order), any columns after them are sorted ascending.
+fn primary_key_sort_orders(
+    columns: &[(String, DataType)],
+    primary_key: &[(String, SortOrder)],
+) -> Result<Vec<SortOrder>, CatalogError> {
+    for (idx, (pk_column, _)) in primary_key.iter().enumerate() {
+        if !columns.iter().any(|(alias, _)| alias == pk_column) {
+            return Err(CatalogError::InvalidPrimaryKey(format!(
+                "Unknown primary key column {}",
+                pk_column
+            )));
+        }
+        if columns[idx].0 != *pk_column {
+            return Err(CatalogError::InvalidPrimaryKey(format!(
+                "Primary key column {} must be column {} of the table",
+                pk_column,
+                idx + 1
+            )));
+        }
+    }
+
+    Ok(columns
+        .iter()
+        .enumerate()
+        .map(|(idx, _)| {
+            primary_key
+                .get(idx)
+                .map_or(SortOrder::Asc, |(_, sort_order)| *sort_order)
+        })
+        .collect())
+}
+
+/// Builds the rows describing a table for the tables and prefix metadata tables.
+/// The number of columns making up the primary key, when one isn't declared all the columns
+/// make up the key.
+fn primary_key_len(columns: &[(String, DataType)], primary_key: &[(String, SortOrder)]) -> usize {
+    if primary_key.is_empty() {
+        columns.len()
+    } else {
+        primary_key.len()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn table_metadata_tuples<'a>(
+    database_name: &'a str,
+    table_name: &'a str,
+    table_id: u32,
+    columns: &[(String, DataType)],
+    column_defaults: &[Option<ColumnDefault>],
+    column_not_null: &[bool],
+    pks: &[SortOrder],
+    system: bool,
+) -> ([Datum<'a>; 8], [Datum<'static>; 3]) {
+    let columns_datum = Datum::from(JsonBuilder::default().array(|array| {
+        for (idx, (alias, datatype)) in columns.iter().enumerate() {
+            array.push_array(|col_array| {
+                col_array.push_string(alias);
+                col_array.push_string(&format!("{:#}", datatype));
+                match column_defaults.get(idx) {
+                    Some(Some(ColumnDefault::Default(sql))) => {
+                        col_array.push_string("default");
+                        col_array.push_string(sql);
+                    }
+                    Some(Some(ColumnDefault::Generated(sql))) => {
+                        col_array.push_string("generated");
+                        col_array.push_string(sql);
+                    }
+                    _ => {}
+                }
+                if column_not_null.get(idx) == Some(&true) {
+                    col_array.push_string("not null");
+                }
+            })
+        }
+    }));
+
+    let table_tuple = [
+        Datum::from(database_name),
+        Datum::from(table_name),
+        Datum::from("table"),
+        Datum::Null,
+        Datum::Null,
+        Datum::from(table_id as i64),
+        columns_datum,
+        Datum::from(system),
+    ];
+
+    (
+        table_tuple,
+        prefix_metadata_tuple(table_id, columns.len(), pks),
+    )
+}
+
+/// Builds the row for the prefix metadata table.
+fn prefix_metadata_tuple(
+    table_id: u32,
+    column_len: usize,
+    pks: &[SortOrder],
+) -> [Datum<'static>; 3] {
+    let pks = Datum::from(JsonBuilder::default().array(|array| {
+        for pk in pks {
+            array.push_bool(pk.is_desc());
+        }
+    }));
+
+    [
+        Datum::from(table_id as i64),
+        Datum::from(column_len as i32),
+        pks,
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -486,6 +928,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_version() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let version = catalog.version();
+        assert_eq!(catalog.version(), version);
+
+        catalog.create_database("abc")?;
+        let created_version = catalog.version();
+        assert!(created_version > version);
+
+        catalog.drop_database("abc")?;
+        assert!(catalog.version() > created_version);
+        Ok(())
+    }
+
     #[test]
     fn test_create_database() -> Result<(), CatalogError> {
         let mut catalog = Catalog::new_for_test()?;
@@ -521,16 +978,154 @@ mod tests {
         let mut catalog = Catalog::new_for_test()?;
         let columns = vec![("a".to_string(), DataType::Integer)];
 
-        catalog.create_table("default", "test", &columns)?;
+        catalog.create_table("default", "test", &columns, &[], &[], &[])?;
 
         let item = catalog.item("default", "test")?;
         assert_eq!(item.columns, columns.as_slice());
+        assert_eq!(item.column_defaults, vec![None]);
 
         catalog.drop_table("default", "test")?;
         assert!(catalog.item("default", "test").is_err());
         Ok(())
     }
 
+    #[test]
+    fn test_create_checks_are_part_of_the_write() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![("a".to_string(), DataType::Integer)];
+        catalog.create_table("default", "test", &columns, &[], &[], &[])?;
+
+        // Going straight to the impls is what a create that raced past the up front checks
+        // looks like, the writes themselves must still refuse to clobber the existing table.
+        let id = catalog.generate_table_id()?;
+        assert_eq!(
+            catalog.create_table_impl(
+                "default",
+                "test",
+                id,
+                &columns,
+                &[],
+                &[],
+                &[SortOrder::Asc],
+                1,
+                false
+            ),
+            Err(CatalogError::TableAlreadyExists(
+                "default".to_string(),
+                "test".to_string()
+            ))
+        );
+        assert_eq!(
+            catalog.create_view_impl("default", "test", &columns, "select 1", "default", false),
+            Err(CatalogError::TableAlreadyExists(
+                "default".to_string(),
+                "test".to_string()
+            ))
+        );
+        assert_eq!(
+            catalog.create_view_impl("missing", "test", &columns, "select 1", "default", false),
+            Err(CatalogError::DatabaseNotFound("missing".to_string()))
+        );
+        assert_eq!(
+            catalog.create_database_impl("default"),
+            Err(CatalogError::DatabaseAlreadyExists("default".to_string()))
+        );
+
+        // Dropped names can be reused
+        catalog.drop_table("default", "test")?;
+        catalog.create_view_impl("default", "test", &columns, "select 1", "default", false)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_table_with_primary_key() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![
+            ("a".to_string(), DataType::Integer),
+            ("b".to_string(), DataType::Integer),
+            ("c".to_string(), DataType::Integer),
+        ];
+
+        catalog.create_table(
+            "default",
+            "test",
+            &columns,
+            &[],
+            &[],
+            &[
+                ("a".to_string(), SortOrder::Asc),
+                ("b".to_string(), SortOrder::Desc),
+            ],
+        )?;
+        let item = catalog.item("default", "test")?;
+        assert_eq!(item.primary_key_len, 2);
+        if let TableOrView::Table(table) = item.item {
+            assert_eq!(
+                table.pk(),
+                &[SortOrder::Asc, SortOrder::Desc, SortOrder::Asc]
+            );
+        } else {
+            panic!()
+        }
+
+        assert_eq!(
+            catalog.create_table(
+                "default",
+                "test2",
+                &columns,
+                &[],
+                &[],
+                &[("b".to_string(), SortOrder::Desc)],
+            ),
+            Err(CatalogError::InvalidPrimaryKey(
+                "Primary key column b must be column 1 of the table".to_string()
+            ))
+        );
+        assert!(catalog
+            .create_table(
+                "default",
+                "test2",
+                &columns,
+                &[],
+                &[],
+                &[("d".to_string(), SortOrder::Desc)],
+            )
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_table_with_defaults() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![
+            ("a".to_string(), DataType::Integer),
+            ("b".to_string(), DataType::Integer),
+            ("c".to_string(), DataType::Integer),
+        ];
+        let column_defaults = vec![
+            None,
+            Some(ColumnDefault::Default("1".to_string())),
+            Some(ColumnDefault::Generated("a + b".to_string())),
+        ];
+
+        let column_not_null = vec![true, true, false];
+
+        catalog.create_table(
+            "default",
+            "test",
+            &columns,
+            &column_defaults,
+            &column_not_null,
+            &[],
+        )?;
+
+        let item = catalog.item("default", "test")?;
+        assert_eq!(item.columns, columns.as_slice());
+        assert_eq!(item.column_defaults, column_defaults);
+        assert_eq!(item.column_not_null, column_not_null);
+        Ok(())
+    }
+
     #[test]
     fn test_create_view() -> Result<(), CatalogError> {
         let mut catalog = Catalog::new_for_test()?;
@@ -552,4 +1147,32 @@ mod tests {
         assert!(catalog.item("default", "test").is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_create_materialized_view() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![("a".to_string(), DataType::Integer)];
+
+        catalog.create_materialized_view("default", "test", &columns, "select 1", "foo")?;
+
+        let item = catalog.item("default", "test")?;
+        assert_eq!(item.columns, columns.as_slice());
+        assert!(matches!(item.item, TableOrView::Table(_)));
+        assert_eq!(
+            item.materialized_view,
+            Some(View {
+                sql: "select 1".to_string(),
+                db_context: "foo".to_string()
+            })
+        );
+
+        let views = catalog.materialized_views()?;
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].name, "test");
+
+        catalog.drop_table("default", "test")?;
+        assert!(catalog.item("default", "test").is_err());
+        assert!(catalog.materialized_views()?.is_empty());
+        Ok(())
+    }
 }
@@ -0,0 +1,111 @@
+use crate::{Catalog, CatalogError};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Tracks the catalog objects currently being altered by DDL statements.
+/// DDL statements often need to read from the catalog, do some work and then write back to it,
+/// these locks are held for the whole statement so that conflicting DDL (ie a drop racing an
+/// alter) fails cleanly instead of interleaving their writes.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct LockManager {
+    locked: Arc<Mutex<HashSet<LockKey>>>,
+}
+
+/// A database lock is represented by a key without a table
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+struct LockKey {
+    database: String,
+    table: Option<String>,
+}
+
+/// A lock on a database or table, released when dropped.
+#[derive(Debug)]
+pub struct ObjectLock {
+    locked: Arc<Mutex<HashSet<LockKey>>>,
+    key: LockKey,
+}
+
+impl Drop for ObjectLock {
+    fn drop(&mut self) {
+        self.locked.lock().unwrap().remove(&self.key);
+    }
+}
+
+impl LockManager {
+    fn lock(&self, key: LockKey) -> Result<ObjectLock, CatalogError> {
+        let mut locked = self.locked.lock().unwrap();
+        let conflict = locked.iter().any(|other| {
+            other.database == key.database
+                && (other.table.is_none() || key.table.is_none() || other.table == key.table)
+        });
+        if conflict {
+            return Err(CatalogError::ObjectLocked(match &key.table {
+                Some(table) => format!("{}.{}", key.database, table),
+                None => key.database,
+            }));
+        }
+        locked.insert(key.clone());
+        Ok(ObjectLock {
+            locked: Arc::clone(&self.locked),
+            key,
+        })
+    }
+}
+
+impl Catalog {
+    /// Locks a table (or view) for the duration of a DDL statement, fails if the table or its
+    /// database is already locked.
+    pub fn lock_table(&self, database: &str, table: &str) -> Result<ObjectLock, CatalogError> {
+        self.locks.lock(LockKey {
+            database: database.to_string(),
+            table: Some(table.to_string()),
+        })
+    }
+
+    /// Locks a database for the duration of a DDL statement, fails if the database or any of
+    /// its tables are already locked.
+    pub fn lock_database(&self, database: &str) -> Result<ObjectLock, CatalogError> {
+        self.locks.lock(LockKey {
+            database: database.to_string(),
+            table: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_locks() -> Result<(), CatalogError> {
+        let catalog = Catalog::new_for_test()?;
+        let lock = catalog.lock_table("default", "t1")?;
+        assert_eq!(
+            catalog.lock_table("default", "t1").unwrap_err(),
+            CatalogError::ObjectLocked("default.t1".to_string())
+        );
+        // Other tables aren't affected
+        catalog.lock_table("default", "t2")?;
+        catalog.lock_table("other", "t1")?;
+
+        drop(lock);
+        catalog.lock_table("default", "t1")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_database_locks() -> Result<(), CatalogError> {
+        let catalog = Catalog::new_for_test()?;
+        let table_lock = catalog.lock_table("default", "t1")?;
+        assert_eq!(
+            catalog.lock_database("default").unwrap_err(),
+            CatalogError::ObjectLocked("default".to_string())
+        );
+        drop(table_lock);
+
+        let _database_lock = catalog.lock_database("default")?;
+        assert!(catalog.lock_table("default", "t1").is_err());
+        catalog.lock_table("other", "t1")?;
+        Ok(())
+    }
+}
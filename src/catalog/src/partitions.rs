@@ -0,0 +1,523 @@
+use crate::{
+    prefix_metadata_tuple, primary_key_len, primary_key_sort_orders, table_metadata_tuples,
+    Catalog, CatalogError, ColumnDefault, TableOrView,
+};
+use data::{DataType, Datum, LogicalTimestamp, SortOrder, TupleIter};
+use storage::{StorageError, Table, Writer};
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PartitionMethod {
+    Range,
+    Hash,
+}
+
+/// How the rows of a partitioned table are split up between its partitions, each partition
+/// is stored under its own table id.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Partitioning {
+    pub method: PartitionMethod,
+    // The offset of the column the table is partitioned by
+    pub column: usize,
+    // Range partitions are in order of their upper bounds
+    pub partitions: Vec<Partition>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Partition {
+    pub name: String,
+    // The exclusive upper bound of a range partition, None for MAXVALUE or hash partitions.
+    pub upper_bound: Option<Datum<'static>>,
+    pub table: Table,
+}
+
+/// The partitioning to create a table with.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PartitionBy {
+    pub method: PartitionMethod,
+    pub column: String,
+    // The name and upper bound of each partition
+    pub partitions: Vec<(String, Option<Datum<'static>>)>,
+}
+
+impl Catalog {
+    /// Creates a new table with its rows split up into partitions.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_partitioned_table(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        columns: &[(String, DataType)],
+        column_defaults: &[Option<ColumnDefault>],
+        column_not_null: &[bool],
+        primary_key: &[(String, SortOrder)],
+        partition_by: &PartitionBy,
+    ) -> Result<(), CatalogError> {
+        self.check_db_exists(database_name)?;
+        self.check_table_not_exists(database_name, table_name)?;
+        let column = columns
+            .iter()
+            .position(|(alias, _)| alias == &partition_by.column)
+            .ok_or_else(|| {
+                CatalogError::InvalidPartitioning(format!(
+                    "Unknown partition column {}",
+                    partition_by.column
+                ))
+            })?;
+        validate_partitions(partition_by.method, &partition_by.partitions)?;
+        let pk = primary_key_sort_orders(columns, primary_key)?;
+
+        let id = self.generate_table_id()?;
+        let mut ids = vec![id];
+        for _ in &partition_by.partitions {
+            ids.push(self.generate_table_id()?);
+        }
+
+        let timestamp = LogicalTimestamp::now();
+        let (table_tuple, prefix_tuple) = table_metadata_tuples(
+            database_name,
+            table_name,
+            id,
+            columns,
+            column_defaults,
+            column_not_null,
+            &pk,
+            false,
+        );
+
+        self.tables_table.atomic_write::<_, CatalogError>(|batch| {
+            self.check_can_create_table(batch, database_name, table_name)?;
+            batch.write_tuple(&self.tables_table, &table_tuple, timestamp, 1)?;
+            batch.write_tuple(&self.prefix_metadata_table, &prefix_tuple, timestamp, 1)?;
+            let primary_index = &pk[..primary_key_len(columns, primary_key)];
+            self.write_primary_index(batch, database_name, table_name, id, columns, primary_index);
+            self.write_columns(
+                batch,
+                database_name,
+                table_name,
+                columns,
+                column_defaults,
+                column_not_null,
+                primary_index.len(),
+            );
+
+            for (ordinal, ((name, upper_bound), partition_id)) in
+                partition_by.partitions.iter().zip(&ids[1..]).enumerate()
+            {
+                let tuple = prefix_metadata_tuple(*partition_id, columns.len(), &pk);
+                batch.write_tuple(&self.prefix_metadata_table, &tuple, timestamp, 1)?;
+
+                let tuple = partition_tuple(
+                    id,
+                    ordinal,
+                    name,
+                    *partition_id,
+                    (partition_by.method, column),
+                    upper_bound.as_ref(),
+                );
+                batch.write_tuple(&self.partitions_table, &tuple, timestamp, 1)?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Adds a new range partition to the end of a range partitioned table.
+    pub fn add_partition(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        partition_name: &str,
+        upper_bound: Option<Datum<'static>>,
+    ) -> Result<(), CatalogError> {
+        let (table, partitioning) = self.range_partitioned_table(database_name, table_name)?;
+        if partitioning
+            .partitions
+            .iter()
+            .any(|partition| partition.name == partition_name)
+        {
+            return Err(CatalogError::PartitionAlreadyExists(
+                database_name.to_string(),
+                table_name.to_string(),
+                partition_name.to_string(),
+            ));
+        }
+
+        let mut partitions: Vec<_> = partitioning
+            .partitions
+            .iter()
+            .map(|partition| (partition.name.clone(), partition.upper_bound.clone()))
+            .collect();
+        partitions.push((partition_name.to_string(), upper_bound.clone()));
+        validate_partitions(PartitionMethod::Range, &partitions)?;
+
+        let ordinal = self
+            .partition_rows(table.id())?
+            .last()
+            .map_or(0, |(tuple, _freq)| tuple[1].as_integer() as usize + 1);
+        let partition_id = self.generate_table_id()?;
+
+        let timestamp = LogicalTimestamp::now();
+        self.tables_table.atomic_write::<_, StorageError>(|batch| {
+            // User tables are keyed on all their columns
+            let tuple = prefix_metadata_tuple(partition_id, table.pk().len(), table.pk());
+            batch.write_tuple(&self.prefix_metadata_table, &tuple, timestamp, 1)?;
+
+            let tuple = partition_tuple(
+                table.id(),
+                ordinal,
+                partition_name,
+                partition_id,
+                (PartitionMethod::Range, partitioning.column),
+                upper_bound.as_ref(),
+            );
+            batch.write_tuple(&self.partitions_table, &tuple, timestamp, 1)
+        })?;
+        Ok(())
+    }
+
+    /// Drops a range partition along with all the data stored in it.
+    pub fn drop_partition(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        partition_name: &str,
+    ) -> Result<(), CatalogError> {
+        let (table, partitioning) = self.range_partitioned_table(database_name, table_name)?;
+        let partition = partitioning
+            .partitions
+            .iter()
+            .find(|partition| partition.name == partition_name)
+            .ok_or_else(|| {
+                CatalogError::PartitionNotFound(
+                    database_name.to_string(),
+                    table_name.to_string(),
+                    partition_name.to_string(),
+                )
+            })?;
+        if partitioning.partitions.len() == 1 {
+            return Err(CatalogError::InvalidPartitioning(
+                "Can not drop the last partition of a table, drop the table instead".to_string(),
+            ));
+        }
+
+        let timestamp = LogicalTimestamp::now();
+        let rows = self.partition_rows(table.id())?;
+        self.tables_table.atomic_write::<_, StorageError>(|batch| {
+            for (tuple, freq) in &rows {
+                if tuple[2].as_text() == partition_name {
+                    self.delete_partition(batch, tuple, *freq, timestamp)?;
+                }
+            }
+            Ok(())
+        })?;
+        partition
+            .table
+            .atomic_write_without_index::<_, StorageError>(|write_batch| {
+                let partition_id = partition.table.id();
+                write_batch
+                    .delete_range(partition_id.to_be_bytes(), (partition_id + 2).to_be_bytes());
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Loads the partitioning for a table, None if the table isn't partitioned.
+    pub(crate) fn partitioning(
+        &self,
+        table_id: u32,
+        column_len: usize,
+        pk: &[SortOrder],
+    ) -> Result<Option<Partitioning>, CatalogError> {
+        let rows = self.partition_rows(table_id)?;
+        let (method, column) = if let Some((tuple, _freq)) = rows.first() {
+            let method = match tuple[4].as_text() {
+                "range" => PartitionMethod::Range,
+                "hash" => PartitionMethod::Hash,
+                method => panic!("Unknown partition method {}", method),
+            };
+            (method, tuple[5].as_integer() as usize)
+        } else {
+            return Ok(None);
+        };
+
+        let partitions = rows
+            .iter()
+            .map(|(tuple, _freq)| Partition {
+                name: tuple[2].as_text().to_string(),
+                upper_bound: if tuple[6].is_null() {
+                    None
+                } else {
+                    let mut datum = Datum::Null;
+                    datum.from_sortable_bytes(tuple[6].as_bytea());
+                    Some(datum.as_static())
+                },
+                table: self
+                    .storage
+                    .table(tuple[3].as_bigint() as u32, column_len, pk.to_vec()),
+            })
+            .collect();
+
+        Ok(Some(Partitioning {
+            method,
+            column,
+            partitions,
+        }))
+    }
+
+    /// Removes all the partitions of a table, including their data, as part of a larger write.
+    pub(crate) fn delete_partitions(
+        &self,
+        batch: &mut Writer,
+        table_id: u32,
+        timestamp: LogicalTimestamp,
+    ) -> Result<(), StorageError> {
+        for (tuple, freq) in self.partition_rows(table_id)? {
+            self.delete_partition(batch, &tuple, freq, timestamp)?;
+            let partition_id = tuple[3].as_bigint() as u32;
+            self.tables_table
+                .atomic_write_without_index::<_, StorageError>(|write_batch| {
+                    write_batch
+                        .delete_range(partition_id.to_be_bytes(), (partition_id + 2).to_be_bytes());
+                    Ok(())
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Removes the metadata for a partition, the row passed in being from the partitions table.
+    fn delete_partition(
+        &self,
+        batch: &mut Writer,
+        partition_tuple: &[Datum],
+        freq: i64,
+        timestamp: LogicalTimestamp,
+    ) -> Result<(), StorageError> {
+        let prefix_key = &partition_tuple[3..4];
+        let mut prefix_iter = self.prefix_metadata_table.range_scan(
+            Some(prefix_key),
+            Some(prefix_key),
+            LogicalTimestamp::MAX,
+        );
+        if let Some((prefix_tuple, prefix_freq)) = prefix_iter.next()? {
+            batch.write_tuple(
+                &self.prefix_metadata_table,
+                prefix_tuple,
+                timestamp,
+                -prefix_freq,
+            )?;
+        }
+        batch.write_tuple(&self.partitions_table, partition_tuple, timestamp, -freq)
+    }
+
+    /// Returns the rows from the partitions table for a table, in ordinal order.
+    fn partition_rows(
+        &self,
+        table_id: u32,
+    ) -> Result<Vec<(Vec<Datum<'static>>, i64)>, StorageError> {
+        let table_key = [Datum::from(table_id as i64)];
+        let mut iter = self.partitions_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        let mut rows = vec![];
+        while let Some((tuple, freq)) = iter.next()? {
+            rows.push((tuple.iter().map(Datum::as_static).collect(), freq));
+        }
+        Ok(rows)
+    }
+
+    /// Looks up a table, checking that it's range partitioned.
+    fn range_partitioned_table(
+        &self,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<(Table, Partitioning), CatalogError> {
+        let item = self.item(database_name, table_name)?;
+        match (item.item, item.partitioning) {
+            (TableOrView::Table(table), Some(partitioning))
+                if partitioning.method == PartitionMethod::Range =>
+            {
+                Ok((table, partitioning))
+            }
+            _ => Err(CatalogError::InvalidPartitioning(format!(
+                "Table {}.{} is not range partitioned",
+                database_name, table_name
+            ))),
+        }
+    }
+}
+
+/// Checks the partitions have unique names, and for range partitions that their upper bounds
+/// are increasing with only the last allowed to be MAXVALUE.
+fn validate_partitions(
+    method: PartitionMethod,
+    partitions: &[(String, Option<Datum<'static>>)],
+) -> Result<(), CatalogError> {
+    if partitions.is_empty() {
+        return Err(CatalogError::InvalidPartitioning(
+            "Partitioned tables need at least one partition".to_string(),
+        ));
+    }
+
+    for (idx, (name, upper_bound)) in partitions.iter().enumerate() {
+        if partitions[..idx].iter().any(|(other, _)| other == name) {
+            return Err(CatalogError::InvalidPartitioning(format!(
+                "Duplicate partition name {}",
+                name
+            )));
+        }
+
+        if method == PartitionMethod::Range {
+            let valid = match (
+                idx.checked_sub(1).map(|prev| &partitions[prev].1),
+                upper_bound,
+            ) {
+                (_, Some(Datum::Null)) => false,
+                (None, _) => true,
+                (Some(Some(prev)), Some(upper_bound)) => prev < upper_bound,
+                (Some(Some(_)), None) => true,
+                (Some(None), _) => false,
+            };
+            if !valid {
+                return Err(CatalogError::InvalidPartitioning(
+                    "VALUES LESS THAN values must be strictly increasing for each partition"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds the row for the partitions table.
+fn partition_tuple<'a>(
+    table_id: u32,
+    ordinal: usize,
+    name: &'a str,
+    partition_id: u32,
+    (method, column): (PartitionMethod, usize),
+    upper_bound: Option<&Datum>,
+) -> [Datum<'a>; 7] {
+    let method = match method {
+        PartitionMethod::Range => "range",
+        PartitionMethod::Hash => "hash",
+    };
+    let upper_bound = upper_bound.map_or(Datum::Null, |upper_bound| {
+        let mut buf = vec![];
+        upper_bound.as_sortable_bytes(SortOrder::Asc, &mut buf);
+        Datum::from(buf)
+    });
+
+    [
+        Datum::from(table_id as i64),
+        Datum::from(ordinal as i32),
+        Datum::from(name),
+        Datum::from(partition_id as i64),
+        Datum::from(method),
+        Datum::from(column as i32),
+        upper_bound,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partition_names(catalog: &Catalog) -> Vec<String> {
+        catalog
+            .item("default", "test")
+            .unwrap()
+            .partitioning
+            .unwrap()
+            .partitions
+            .into_iter()
+            .map(|partition| partition.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_partitioned_table() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![("a".to_string(), DataType::Integer)];
+        let partition_by = PartitionBy {
+            method: PartitionMethod::Range,
+            column: "a".to_string(),
+            partitions: vec![
+                ("p0".to_string(), Some(Datum::from(10))),
+                ("p1".to_string(), Some(Datum::from(20))),
+            ],
+        };
+
+        catalog.create_partitioned_table(
+            "default",
+            "test",
+            &columns,
+            &[],
+            &[],
+            &[],
+            &partition_by,
+        )?;
+        let item = catalog.item("default", "test")?;
+        let partitioning = item.partitioning.unwrap();
+        assert_eq!(partitioning.method, PartitionMethod::Range);
+        assert_eq!(partitioning.column, 0);
+        assert_eq!(
+            partitioning.partitions[1].upper_bound,
+            Some(Datum::from(20))
+        );
+        assert_ne!(
+            partitioning.partitions[0].table.id(),
+            partitioning.partitions[1].table.id()
+        );
+
+        // Bounds need to keep increasing
+        assert!(catalog
+            .add_partition("default", "test", "p2", Some(Datum::from(15)))
+            .is_err());
+        catalog.add_partition("default", "test", "p2", None)?;
+        assert_eq!(partition_names(&catalog), vec!["p0", "p1", "p2"]);
+
+        catalog.drop_partition("default", "test", "p0")?;
+        assert_eq!(partition_names(&catalog), vec!["p1", "p2"]);
+        assert_eq!(
+            catalog.drop_partition("default", "test", "p0"),
+            Err(CatalogError::PartitionNotFound(
+                "default".to_string(),
+                "test".to_string(),
+                "p0".to_string()
+            ))
+        );
+
+        catalog.drop_table("default", "test")?;
+        assert!(catalog.item("default", "test").is_err());
+        assert!(catalog
+            .partition_rows(partitioning.partitions[0].table.id())?
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_partitioning() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![("a".to_string(), DataType::Integer)];
+        let mut partition_by = PartitionBy {
+            method: PartitionMethod::Range,
+            column: "b".to_string(),
+            partitions: vec![("p0".to_string(), None)],
+        };
+        assert!(catalog
+            .create_partitioned_table("default", "test", &columns, &[], &[], &[], &partition_by)
+            .is_err());
+
+        partition_by.column = "a".to_string();
+        partition_by.partitions = vec![
+            ("p0".to_string(), None),
+            ("p1".to_string(), Some(Datum::from(1))),
+        ];
+        assert!(catalog
+            .create_partitioned_table("default", "test", &columns, &[], &[], &[], &partition_by)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crate::users::authentication_string;
+use crate::{Catalog, CatalogError};
+use data::{LogicalTimestamp, TupleIter};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use storage::{ReplicatedBatch, Storage, StorageError, Table};
+
+/// Handle used to ship writes from a leader to its followers. It's detached from the catalog so
+/// it can be moved onto the threads doing the shipping.
+#[derive(Debug, Clone)]
+pub struct Replication {
+    storage: Storage,
+    prefix_metadata_table: Table,
+    users_table: Table,
+}
+
+impl Catalog {
+    /// Returns the replication handle for the underlying storage
+    pub fn replication(&self) -> Replication {
+        Replication {
+            storage: self.storage.clone(),
+            prefix_metadata_table: self.prefix_metadata_table.clone(),
+            users_table: self.users_table.clone(),
+        }
+    }
+}
+
+impl Replication {
+    /// Turns on replication, needs to be called on the leader before any followers subscribe.
+    pub fn enable(&self) {
+        self.storage.enable_replication()
+    }
+
+    /// Subscribes a new follower, a snapshot of every table is first passed to send_snapshot then
+    /// the returned receiver yields the batches committed after it.
+    pub fn subscribe<S, E>(&self, send_snapshot: S) -> Result<Receiver<Arc<ReplicatedBatch>>, E>
+    where
+        S: FnMut(ReplicatedBatch) -> Result<(), E>,
+        E: From<StorageError>,
+    {
+        self.storage.subscribe(
+            || {
+                // Every table, system or otherwise has an entry in the prefix table
+                let mut table_ids = vec![];
+                let mut iter = self.prefix_metadata_table.full_scan(LogicalTimestamp::MAX);
+                while let Some((tuple, _freq)) = iter.next()? {
+                    table_ids.push(tuple[0].as_bigint() as u32);
+                }
+                Ok::<_, StorageError>(table_ids)
+            },
+            send_snapshot,
+        )
+    }
+
+    /// Applies a batch received from the leader
+    pub fn apply(&self, batch: &ReplicatedBatch) -> Result<(), CatalogError> {
+        Ok(self.storage.apply_replicated(batch)?)
+    }
+
+    /// Sends an empty batch to the followers so they know we're still here, call it regularly.
+    pub fn heartbeat(&self) {
+        self.storage.replication_heartbeat()
+    }
+
+    /// The sequence of the last batch applied from the leader
+    pub fn sequence(&self) -> u64 {
+        self.storage.replicated_sequence()
+    }
+
+    /// The leader's timestamp as of the last batch applied from it, followers read as of this.
+    pub fn timestamp(&self) -> LogicalTimestamp {
+        self.storage.replicated_timestamp()
+    }
+
+    /// Looks up the authentication string for the user, followers have to log in before
+    /// they're sent anything.
+    pub fn user_authentication_string(
+        &self,
+        user_name: &str,
+    ) -> Result<Option<String>, CatalogError> {
+        authentication_string(&self.users_table, user_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::{DataType, Datum};
+
+    #[test]
+    fn test_replication() -> Result<(), CatalogError> {
+        let mut leader = Catalog::new_for_test()?;
+        let follower = Catalog::new_for_test()?;
+        leader.replication().enable();
+        leader.create_database("test")?;
+
+        let replication = follower.replication();
+        let receiver = leader
+            .replication()
+            .subscribe(|batch| replication.apply(&batch))?;
+        leader.create_table(
+            "test",
+            "t",
+            &[("a".to_string(), DataType::Integer)],
+            &[],
+            &[],
+            &[],
+        )?;
+
+        while let Ok(batch) = receiver.try_recv() {
+            replication.apply(&batch)?;
+        }
+
+        assert!(follower.item("test", "t").is_ok());
+        let mut iter = follower.databases_table.full_scan(LogicalTimestamp::MAX);
+        let mut databases = vec![];
+        while let Some((tuple, _freq)) = iter.next()? {
+            databases.push(tuple[0].as_static());
+        }
+        assert!(databases.contains(&Datum::from("test")));
+        Ok(())
+    }
+}
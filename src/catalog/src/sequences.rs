@@ -0,0 +1,81 @@
+use crate::{Catalog, CatalogError};
+use data::{Datum, LogicalTimestamp, TupleIter};
+use storage::StorageError;
+
+/// Table ids below this are reserved for system tables.
+pub(crate) const FIRST_USER_TABLE_ID: u32 = 1024;
+
+/// The name of the sequence table ids are allocated from.
+const TABLE_ID_SEQUENCE: &str = "table_id";
+
+impl Catalog {
+    /// Allocates a new table id. Ids are handed out in increasing order and are persisted
+    /// before being returned so they're never reused, even if we crash before the table gets
+    /// created or the table is later dropped.
+    /// Table ids are always even, the odd id after each table's id holds its log entries.
+    pub(crate) fn generate_table_id(&self) -> Result<u32, CatalogError> {
+        let key = [Datum::from(TABLE_ID_SEQUENCE)];
+        let mut key_buf = vec![];
+        let mut value = vec![];
+        let mut id =
+            match self
+                .sequences_table
+                .system_point_lookup(&key, &mut key_buf, &mut value)?
+            {
+                Some(_) => value[0].as_bigint() as u32,
+                None => FIRST_USER_TABLE_ID,
+            };
+
+        // Databases created before ids were allocated sequentially have their tables scattered
+        // through the id space, so skip over any that have been taken.
+        while self.table_id_in_use(id)? {
+            id += 2;
+        }
+
+        self.sequences_table
+            .atomic_write::<_, StorageError>(|batch| {
+                batch.system_write_tuple(
+                    &self.sequences_table,
+                    &[Datum::from(TABLE_ID_SEQUENCE), Datum::from(id as i64 + 2)],
+                    1,
+                );
+                Ok(())
+            })?;
+        Ok(id)
+    }
+
+    fn table_id_in_use(&self, id: u32) -> Result<bool, CatalogError> {
+        let key = [Datum::from(id as i64)];
+        let mut iter =
+            self.prefix_metadata_table
+                .range_scan(Some(&key), Some(&key), LogicalTimestamp::MAX);
+        Ok(iter.next()?.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::{DataType, SortOrder};
+
+    #[test]
+    fn test_generate_table_id() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let first = catalog.generate_table_id()?;
+        assert_eq!(first, FIRST_USER_TABLE_ID);
+        assert_eq!(catalog.generate_table_id()?, first + 2);
+
+        // Ids aren't reused after a table is dropped
+        catalog.create_table(
+            "default",
+            "t",
+            &[("a".to_string(), DataType::Integer)],
+            &[],
+            &[],
+            &[("a".to_string(), SortOrder::Asc)],
+        )?;
+        catalog.drop_table("default", "t")?;
+        assert_eq!(catalog.generate_table_id()?, first + 6);
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{Catalog, CatalogError};
+use data::{Datum, LogicalTimestamp, TupleIter};
+use storage::StorageError;
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum SinkFormat {
+    Json,
+    Csv,
+}
+
+/// A sink continuously exports the changes made to a table or view to a file.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Sink {
+    pub database: String,
+    pub name: String,
+    // The table or view whose changes are exported
+    pub source_database: String,
+    pub source_name: String,
+    pub path: String,
+    pub format: SinkFormat,
+}
+
+impl Catalog {
+    /// Creates a new sink, it's up to the caller to start exporting to it.
+    pub fn create_sink(&mut self, sink: &Sink) -> Result<(), CatalogError> {
+        self.check_db_exists(&sink.database)?;
+        if self.sink(&sink.database, &sink.name)?.is_some() {
+            return Err(CatalogError::SinkAlreadyExists(
+                sink.database.clone(),
+                sink.name.clone(),
+            ));
+        }
+        // Make sure the source exists
+        self.item(&sink.source_database, &sink.source_name)?;
+
+        self.sinks_table.atomic_write::<_, StorageError>(|batch| {
+            batch.system_write_tuple(
+                &self.sinks_table,
+                &[
+                    Datum::from(sink.database.as_str()),
+                    Datum::from(sink.name.as_str()),
+                    Datum::from(sink.source_database.as_str()),
+                    Datum::from(sink.source_name.as_str()),
+                    Datum::from(sink.path.as_str()),
+                    Datum::from(format_name(sink.format)),
+                ],
+                1,
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Drops a sink
+    pub fn drop_sink(&mut self, database: &str, name: &str) -> Result<(), CatalogError> {
+        if self.sink(database, name)?.is_none() {
+            return Err(CatalogError::SinkNotFound(
+                database.to_string(),
+                name.to_string(),
+            ));
+        }
+        self.sinks_table.atomic_write::<_, StorageError>(|batch| {
+            batch.system_delete_tuple(
+                &self.sinks_table,
+                &[Datum::from(database), Datum::from(name)],
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Returns the sink with the given name or None if it doesn't exist.
+    pub fn sink(&self, database: &str, name: &str) -> Result<Option<Sink>, CatalogError> {
+        let mut key_buf = vec![];
+        let mut rest = vec![];
+        let freq = self.sinks_table.system_point_lookup(
+            &[Datum::from(database), Datum::from(name)],
+            &mut key_buf,
+            &mut rest,
+        )?;
+        Ok(freq.map(|_| Sink {
+            database: database.to_string(),
+            name: name.to_string(),
+            source_database: rest[0].as_text().to_string(),
+            source_name: rest[1].as_text().to_string(),
+            path: rest[2].as_text().to_string(),
+            format: parse_format(rest[3].as_text()),
+        }))
+    }
+
+    /// Lists all the sinks across all the databases.
+    pub fn sinks(&self) -> Result<Vec<Sink>, CatalogError> {
+        let mut sinks = vec![];
+        let mut iter = self.sinks_table.full_scan(LogicalTimestamp::MAX);
+        while let Some((tuple, freq)) = iter.next()? {
+            if freq > 0 {
+                sinks.push(Sink {
+                    database: tuple[0].as_text().to_string(),
+                    name: tuple[1].as_text().to_string(),
+                    source_database: tuple[2].as_text().to_string(),
+                    source_name: tuple[3].as_text().to_string(),
+                    path: tuple[4].as_text().to_string(),
+                    format: parse_format(tuple[5].as_text()),
+                });
+            }
+        }
+        Ok(sinks)
+    }
+}
+
+fn format_name(format: SinkFormat) -> &'static str {
+    match format {
+        SinkFormat::Json => "json",
+        SinkFormat::Csv => "csv",
+    }
+}
+
+fn parse_format(name: &str) -> SinkFormat {
+    if name == "csv" {
+        SinkFormat::Csv
+    } else {
+        SinkFormat::Json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::DataType;
+
+    #[test]
+    fn test_sinks_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        catalog.create_table(
+            "default",
+            "orders",
+            &[("id".to_string(), DataType::Integer)],
+            &[],
+            &[],
+            &[],
+        )?;
+        let sink = Sink {
+            database: "default".to_string(),
+            name: "orders_sink".to_string(),
+            source_database: "default".to_string(),
+            source_name: "orders".to_string(),
+            path: "/tmp/orders.csv".to_string(),
+            format: SinkFormat::Csv,
+        };
+
+        catalog.create_sink(&sink)?;
+        assert_eq!(catalog.sink("default", "orders_sink")?, Some(sink.clone()));
+        assert_eq!(catalog.sinks()?, vec![sink.clone()]);
+        assert_eq!(
+            catalog.create_sink(&sink),
+            Err(CatalogError::SinkAlreadyExists(
+                "default".to_string(),
+                "orders_sink".to_string()
+            ))
+        );
+
+        catalog.drop_sink("default", "orders_sink")?;
+        assert_eq!(catalog.sinks()?, vec![]);
+        assert_eq!(
+            catalog.drop_sink("default", "orders_sink"),
+            Err(CatalogError::SinkNotFound(
+                "default".to_string(),
+                "orders_sink".to_string()
+            ))
+        );
+        Ok(())
+    }
+}
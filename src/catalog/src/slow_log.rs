@@ -0,0 +1,94 @@
+use crate::{Catalog, CatalogError};
+use data::chrono::NaiveDateTime;
+use data::{Datum, LogicalTimestamp, TupleIter};
+use storage::StorageError;
+
+/// A statement that ran for longer than the session's long_query_time.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SlowQuery {
+    pub start_time: NaiveDateTime,
+    pub connection_id: u32,
+    pub user: String,
+    pub database: String,
+    pub duration_ms: i64,
+    pub rows_examined: i64,
+    pub rows_sent: i64,
+    pub sql: String,
+}
+
+impl Catalog {
+    /// Records a slow query in incresql.slow_log. Unlike the rest of the catalog this is
+    /// written without waiting for an fsync, losing the last few entries in a crash is better
+    /// than slowing down every slow query further.
+    pub fn log_slow_query(&self, slow_query: &SlowQuery) -> Result<(), CatalogError> {
+        self.slow_log_table
+            .atomic_write_unsynced::<_, StorageError>(|batch| {
+                batch.system_write_tuple(
+                    &self.slow_log_table,
+                    &[
+                        Datum::from(slow_query.start_time),
+                        Datum::from(slow_query.connection_id as i64),
+                        Datum::from(slow_query.sql.as_str()),
+                        Datum::from(slow_query.user.as_str()),
+                        Datum::from(slow_query.database.as_str()),
+                        Datum::from(slow_query.duration_ms),
+                        Datum::from(slow_query.rows_examined),
+                        Datum::from(slow_query.rows_sent),
+                    ],
+                    1,
+                );
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Returns the slow queries logged, oldest first.
+    pub fn slow_queries(&self) -> Result<Vec<SlowQuery>, CatalogError> {
+        let mut iter = self.slow_log_table.full_scan(LogicalTimestamp::MAX);
+        let mut slow_queries = vec![];
+        while let Some((tuple, _freq)) = iter.next()? {
+            slow_queries.push(SlowQuery {
+                start_time: tuple[0].as_timestamp(),
+                connection_id: tuple[1].as_bigint() as u32,
+                sql: tuple[2].as_text().to_string(),
+                user: tuple[3].as_text().to_string(),
+                database: tuple[4].as_text().to_string(),
+                duration_ms: tuple[5].as_bigint(),
+                rows_examined: tuple[6].as_bigint(),
+                rows_sent: tuple[7].as_bigint(),
+            });
+        }
+        Ok(slow_queries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::chrono::NaiveDate;
+
+    #[test]
+    fn test_slow_log() -> Result<(), CatalogError> {
+        let catalog = Catalog::new_for_test()?;
+        assert_eq!(catalog.slow_queries()?, vec![]);
+
+        let slow_query = |connection_id, second| SlowQuery {
+            start_time: NaiveDate::from_ymd(2020, 11, 1).and_hms(12, 0, second),
+            connection_id,
+            user: "root".to_string(),
+            database: "default".to_string(),
+            duration_ms: 12_000,
+            rows_examined: 1000,
+            rows_sent: 10,
+            sql: "SELECT * FROM orders".to_string(),
+        };
+        catalog.log_slow_query(&slow_query(2, 30))?;
+        catalog.log_slow_query(&slow_query(1, 30))?;
+        catalog.log_slow_query(&slow_query(3, 0))?;
+        assert_eq!(
+            catalog.slow_queries()?,
+            vec![slow_query(3, 0), slow_query(1, 30), slow_query(2, 30)]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{Catalog, CatalogError};
+use data::statistics::{ColumnStatistics, HyperLogLog};
+use data::{DataType, Datum, LogicalTimestamp, TupleIter};
+use storage::{StorageError, Writer};
+
+impl Catalog {
+    /// Stores the statistics for the columns of a table, replacing any existing statistics
+    /// for the table. The column types are needed to render the min and max values for users.
+    pub fn set_column_statistics(
+        &mut self,
+        table_id: u32,
+        statistics: &[(String, DataType, ColumnStatistics)],
+    ) -> Result<(), CatalogError> {
+        self.column_statistics_table
+            .atomic_write::<_, StorageError>(|batch| {
+                self.delete_column_statistics(batch, table_id)?;
+
+                for (column_name, datatype, stats) in statistics {
+                    let tuple = [
+                        Datum::from(table_id as i64),
+                        Datum::from(column_name.as_str()),
+                        Datum::from(stats.row_count),
+                        Datum::from(stats.null_count),
+                        Datum::from(stats.ndv),
+                        Datum::from(stats.histogram_bytes()),
+                        Datum::from(stats.sketch.as_bytes().to_vec()),
+                    ];
+                    batch.system_write_tuple(&self.column_statistics_table, &tuple, 1);
+
+                    let render = |bound: Option<&Datum>| {
+                        bound.map_or(Datum::Null, |datum| {
+                            Datum::from(datum.typed_with(*datatype).to_string())
+                        })
+                    };
+                    let bounds = [
+                        Datum::from(table_id as i64),
+                        Datum::from(column_name.as_str()),
+                        render(stats.min()),
+                        render(stats.max()),
+                    ];
+                    batch.system_write_tuple(&self.column_bounds_table, &bounds, 1);
+                }
+                Ok(())
+            })?;
+        Ok(())
+    }
+
+    /// Returns the statistics for the given columns of a table, columns that haven't been
+    /// analyzed will have None returned.
+    pub fn column_statistics<S: AsRef<str>>(
+        &self,
+        table_id: u32,
+        columns: &[S],
+    ) -> Result<Vec<Option<ColumnStatistics>>, CatalogError> {
+        let table_key = [Datum::from(table_id as i64)];
+        let mut iter = self.column_statistics_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+
+        let mut statistics: Vec<Option<ColumnStatistics>> = columns.iter().map(|_| None).collect();
+        while let Some((tuple, _freq)) = iter.next()? {
+            let column_name = tuple[1].as_text();
+            if let Some(idx) = columns.iter().position(|c| c.as_ref() == column_name) {
+                statistics[idx] = Some(ColumnStatistics {
+                    row_count: tuple[2].as_bigint(),
+                    null_count: tuple[3].as_bigint(),
+                    ndv: tuple[4].as_bigint(),
+                    histogram: ColumnStatistics::histogram_from_bytes(tuple[5].as_bytea()),
+                    sketch: HyperLogLog::from_bytes(tuple[6].as_bytea()),
+                });
+            }
+        }
+        Ok(statistics)
+    }
+
+    /// Removes all the statistics for a table as part of a larger write.
+    pub(crate) fn delete_column_statistics(
+        &self,
+        batch: &mut Writer,
+        table_id: u32,
+    ) -> Result<(), StorageError> {
+        let table_key = [Datum::from(table_id as i64)];
+        let mut iter = self.column_statistics_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        while let Some((tuple, _freq)) = iter.next()? {
+            batch.system_delete_tuple(&self.column_statistics_table, &tuple[..2]);
+        }
+
+        let mut iter = self.column_bounds_table.range_scan(
+            Some(&table_key),
+            Some(&table_key),
+            LogicalTimestamp::MAX,
+        );
+        while let Some((tuple, _freq)) = iter.next()? {
+            batch.system_delete_tuple(&self.column_bounds_table, &tuple[..2]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TableOrView;
+    use data::statistics::ColumnStatisticsBuilder;
+
+    fn stats(values: &[i32]) -> ColumnStatistics {
+        let mut builder = ColumnStatisticsBuilder::default();
+        for value in values {
+            builder.add(&Datum::from(*value), 1);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_column_statistics() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        let columns = vec![
+            ("a".to_string(), DataType::Integer),
+            ("b".to_string(), DataType::Integer),
+        ];
+        catalog.create_table("default", "test", &columns, &[], &[], &[])?;
+        let table_id = if let TableOrView::Table(table) = catalog.item("default", "test")?.item {
+            table.id()
+        } else {
+            panic!()
+        };
+
+        assert_eq!(
+            catalog.column_statistics(table_id, &["a", "b"])?,
+            vec![None, None]
+        );
+
+        let a_stats = stats(&[1, 2, 3]);
+        let b_stats = stats(&[4, 4]);
+        catalog.set_column_statistics(
+            table_id,
+            &[
+                ("a".to_string(), DataType::Integer, a_stats.clone()),
+                ("b".to_string(), DataType::Integer, stats(&[1])),
+            ],
+        )?;
+        catalog.set_column_statistics(
+            table_id,
+            &[("b".to_string(), DataType::Integer, b_stats.clone())],
+        )?;
+        assert_eq!(
+            catalog.column_statistics(table_id, &["b", "a"])?,
+            vec![Some(b_stats), None]
+        );
+
+        catalog
+            .set_column_statistics(table_id, &[("a".to_string(), DataType::Integer, a_stats)])?;
+        catalog.drop_table("default", "test")?;
+        assert_eq!(catalog.column_statistics(table_id, &["a"])?, vec![None]);
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{Catalog, CatalogError};
+use data::Datum;
+use storage::{StorageError, Table};
+
+impl Catalog {
+    /// Creates a new user, the authentication string is the already hashed password as
+    /// produced by the auth plugin, an empty string means the user has no password.
+    pub fn create_user(
+        &mut self,
+        user_name: &str,
+        authentication_string: &str,
+    ) -> Result<(), CatalogError> {
+        if self.user_authentication_string(user_name)?.is_some() {
+            return Err(CatalogError::UserAlreadyExists(user_name.to_string()));
+        }
+        self.users_table.atomic_write::<_, StorageError>(|batch| {
+            batch.system_write_tuple(
+                &self.users_table,
+                &[Datum::from(user_name), Datum::from(authentication_string)],
+                1,
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Changes a user's authentication string
+    pub fn alter_user(
+        &mut self,
+        user_name: &str,
+        authentication_string: &str,
+    ) -> Result<(), CatalogError> {
+        if self.user_authentication_string(user_name)?.is_none() {
+            return Err(CatalogError::UserNotFound(user_name.to_string()));
+        }
+        self.users_table.atomic_write::<_, StorageError>(|batch| {
+            batch.system_write_tuple(
+                &self.users_table,
+                &[Datum::from(user_name), Datum::from(authentication_string)],
+                1,
+            );
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Drops a user
+    pub fn drop_user(&mut self, user_name: &str) -> Result<(), CatalogError> {
+        if self.user_authentication_string(user_name)?.is_none() {
+            return Err(CatalogError::UserNotFound(user_name.to_string()));
+        }
+        self.users_table.atomic_write::<_, StorageError>(|batch| {
+            batch.system_delete_tuple(&self.users_table, &[Datum::from(user_name)]);
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Returns the stored authentication string for a user or None if the user doesn't exist.
+    pub fn user_authentication_string(
+        &self,
+        user_name: &str,
+    ) -> Result<Option<String>, CatalogError> {
+        authentication_string(&self.users_table, user_name)
+    }
+}
+
+/// Looks up a user's authentication string, shared with the replication handle.
+pub(crate) fn authentication_string(
+    users_table: &Table,
+    user_name: &str,
+) -> Result<Option<String>, CatalogError> {
+    let mut key_buf = vec![];
+    let mut rest = vec![];
+    let freq =
+        users_table.system_point_lookup(&[Datum::from(user_name)], &mut key_buf, &mut rest)?;
+    Ok(freq.map(|_| rest[0].as_text().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_users_lifecycle() -> Result<(), CatalogError> {
+        let mut catalog = Catalog::new_for_test()?;
+        // Root gets created on bootstrap without a password
+        assert_eq!(
+            catalog.user_authentication_string("root")?,
+            Some(String::new())
+        );
+        assert_eq!(catalog.user_authentication_string("bob")?, None);
+
+        catalog.create_user("bob", "*ABC")?;
+        assert_eq!(
+            catalog.user_authentication_string("bob")?,
+            Some("*ABC".to_string())
+        );
+        assert_eq!(
+            catalog.create_user("bob", ""),
+            Err(CatalogError::UserAlreadyExists("bob".to_string()))
+        );
+
+        catalog.alter_user("bob", "*DEF")?;
+        assert_eq!(
+            catalog.user_authentication_string("bob")?,
+            Some("*DEF".to_string())
+        );
+        assert_eq!(
+            catalog.alter_user("alice", ""),
+            Err(CatalogError::UserNotFound("alice".to_string()))
+        );
+
+        catalog.drop_user("bob")?;
+        assert_eq!(catalog.user_authentication_string("bob")?, None);
+        assert_eq!(
+            catalog.drop_user("bob"),
+            Err(CatalogError::UserNotFound("bob".to_string()))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use std::borrow::Cow;
+
+/// How text is compared, sorted and grouped. Text is always stored as is, the collation only
+/// changes how it's compared.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Collation {
+    // Compares the raw utf8 bytes, ie utf8mb4_bin
+    Binary,
+    // Compares ignoring case like utf8mb4_general_ci, accents still matter though
+    CaseInsensitive,
+}
+
+impl Collation {
+    /// Works out the collation from its mysql name, anything with a _ci suffix is treated as case
+    /// insensitive, everything else (_bin, _cs, binary) as binary.
+    pub fn from_name(name: &str) -> Self {
+        if name.to_ascii_lowercase().ends_with("_ci") {
+            Collation::CaseInsensitive
+        } else {
+            Collation::Binary
+        }
+    }
+
+    /// Transforms the text into a key that compares, sorts and hashes according to the collation
+    /// when compared byte for byte. Case insensitive text is upper cased as that's what mysql
+    /// does, so ie "_" sorts after the letters.
+    pub fn key<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Collation::CaseInsensitive
+                if !text.is_ascii() || text.bytes().any(|b| b.is_ascii_lowercase()) =>
+            {
+                Cow::Owned(text.to_uppercase())
+            }
+            _ => Cow::Borrowed(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            Collation::from_name("utf8mb4_general_ci"),
+            Collation::CaseInsensitive
+        );
+        assert_eq!(
+            Collation::from_name("UTF8MB4_0900_AI_CI"),
+            Collation::CaseInsensitive
+        );
+        assert_eq!(Collation::from_name("utf8mb4_bin"), Collation::Binary);
+        assert_eq!(Collation::from_name("binary"), Collation::Binary);
+    }
+
+    #[test]
+    fn test_key() {
+        let ci = Collation::CaseInsensitive;
+        assert_eq!(ci.key("Hello World"), "HELLO WORLD");
+        assert_eq!(ci.key("straße"), "STRASSE");
+        assert!(matches!(ci.key("ABC_1"), Cow::Borrowed("ABC_1")));
+        assert!(ci.key("a") < ci.key("_"));
+        assert_eq!(Collation::Binary.key("Hello"), "Hello");
+    }
+}
@@ -1,4 +1,6 @@
+use crate::Datum;
 use regex::Regex;
+use rust_decimal::Decimal;
 use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
 
@@ -7,16 +9,28 @@ pub enum DataType {
     // Could be considered a wildcard, ie nulls can be cast to anything
     Null,
     Boolean,
+    // The mysql sized and unsigned ints, these are range checked on the way in but otherwise
+    // stored as the smallest of int, bigint or decimal that can hold them
+    TinyInt,
+    SmallInt,
     Integer,
     BigInt,
+    UnsignedTinyInt,
+    UnsignedSmallInt,
+    UnsignedInteger,
+    UnsignedBigInt,
     // Precision and scale
     Decimal(u8, u8),
+    // 64 bit floating point
+    Double,
     Text,
     ByteA,
     Json,
     Date,
     Timestamp,
     JsonPath,
+    // Number of dimensions
+    Vector(u16),
 }
 
 pub const DECIMAL_MAX_PRECISION: u8 = 28;
@@ -27,17 +41,58 @@ impl DataType {
         match self {
             DataType::Null => panic!("Attempted cast to null"),
             DataType::Boolean => "to_bool",
+            DataType::TinyInt => "to_tinyint",
+            DataType::SmallInt => "to_smallint",
             DataType::Integer => "to_int",
             DataType::BigInt => "to_bigint",
+            DataType::UnsignedTinyInt => "to_utinyint",
+            DataType::UnsignedSmallInt => "to_usmallint",
+            DataType::UnsignedInteger => "to_uint",
+            DataType::UnsignedBigInt => "to_ubigint",
             DataType::Decimal(..) => "to_decimal",
+            DataType::Double => "to_double",
             DataType::Text => "to_text",
             DataType::ByteA => "to_bytes",
             DataType::Json => "to_json",
             DataType::Date => "to_date",
             DataType::Timestamp => "to_timestamp",
             DataType::JsonPath => "to_jsonpath",
+            DataType::Vector(_) => "to_vector",
         }
     }
+
+    /// The min and max values for the integer types, None for everything else.
+    pub fn integer_range(&self) -> Option<(i128, i128)> {
+        match self {
+            DataType::TinyInt => Some((i8::MIN as i128, i8::MAX as i128)),
+            DataType::SmallInt => Some((i16::MIN as i128, i16::MAX as i128)),
+            DataType::Integer => Some((i32::MIN as i128, i32::MAX as i128)),
+            DataType::BigInt => Some((i64::MIN as i128, i64::MAX as i128)),
+            DataType::UnsignedTinyInt => Some((0, u8::MAX as i128)),
+            DataType::UnsignedSmallInt => Some((0, u16::MAX as i128)),
+            DataType::UnsignedInteger => Some((0, u32::MAX as i128)),
+            DataType::UnsignedBigInt => Some((0, u64::MAX as i128)),
+            _ => None,
+        }
+    }
+
+    /// Builds the datum for an integer type, returning None if the value is out of range for
+    /// the type (or the type isn't an integer type).
+    pub fn integer_datum(&self, value: i128) -> Option<Datum<'static>> {
+        let (min, max) = self.integer_range()?;
+        if value < min || value > max {
+            return None;
+        }
+        Some(match self {
+            DataType::TinyInt
+            | DataType::SmallInt
+            | DataType::Integer
+            | DataType::UnsignedTinyInt
+            | DataType::UnsignedSmallInt => Datum::from(value as i32),
+            DataType::BigInt | DataType::UnsignedInteger => Datum::from(value as i64),
+            _ => Datum::from(Decimal::from(value as u64)),
+        })
+    }
 }
 
 impl Display for DataType {
@@ -45,21 +100,30 @@ impl Display for DataType {
         match self {
             DataType::Null => f.write_str("NULL"),
             DataType::Boolean => f.write_str("BOOLEAN"),
+            DataType::TinyInt => f.write_str("TINYINT"),
+            DataType::SmallInt => f.write_str("SMALLINT"),
             DataType::Integer => f.write_str("INTEGER"),
             DataType::BigInt => f.write_str("BIGINT"),
+            DataType::UnsignedTinyInt => f.write_str("TINYINT UNSIGNED"),
+            DataType::UnsignedSmallInt => f.write_str("SMALLINT UNSIGNED"),
+            DataType::UnsignedInteger => f.write_str("INTEGER UNSIGNED"),
+            DataType::UnsignedBigInt => f.write_str("BIGINT UNSIGNED"),
             DataType::Decimal(p, s) => f.write_fmt(format_args!("DECIMAL({},{})", p, s)),
+            DataType::Double => f.write_str("DOUBLE"),
             DataType::Text => f.write_str("TEXT"),
             DataType::ByteA => f.write_str("BYTEA"),
             DataType::Json => f.write_str("JSON"),
             DataType::Date => f.write_str("DATE"),
             DataType::Timestamp => f.write_str("TIMESTAMP"),
             DataType::JsonPath => f.write_str("JSONPATH"),
+            DataType::Vector(dimensions) => f.write_fmt(format_args!("VECTOR({})", dimensions)),
         }
     }
 }
 
 lazy_static! {
     static ref DECIMAL_RE: Regex = Regex::new(r"^DECIMAL\(([0-9]+),([0-9]+)\)$").unwrap();
+    static ref VECTOR_RE: Regex = Regex::new(r"^VECTOR\(([0-9]+)\)$").unwrap();
 }
 
 /// Takes strings serialized from Display and turns them back
@@ -71,8 +135,15 @@ impl TryFrom<&str> for DataType {
         match value {
             "NULL" => Ok(DataType::Null),
             "BOOLEAN" => Ok(DataType::Boolean),
+            "TINYINT" => Ok(DataType::TinyInt),
+            "SMALLINT" => Ok(DataType::SmallInt),
             "INTEGER" => Ok(DataType::Integer),
             "BIGINT" => Ok(DataType::BigInt),
+            "TINYINT UNSIGNED" => Ok(DataType::UnsignedTinyInt),
+            "SMALLINT UNSIGNED" => Ok(DataType::UnsignedSmallInt),
+            "INTEGER UNSIGNED" => Ok(DataType::UnsignedInteger),
+            "BIGINT UNSIGNED" => Ok(DataType::UnsignedBigInt),
+            "DOUBLE" => Ok(DataType::Double),
             "TEXT" => Ok(DataType::Text),
             "BYTEA" => Ok(DataType::ByteA),
             "JSON" => Ok(DataType::Json),
@@ -86,6 +157,11 @@ impl TryFrom<&str> for DataType {
                     let s = d_match.get(2).unwrap().as_str().parse::<u8>().unwrap();
                     DataType::Decimal(p, s)
                 })
+                .or_else(|| {
+                    VECTOR_RE.captures(value).map(|v_match| {
+                        DataType::Vector(v_match.get(1).unwrap().as_str().parse::<u16>().unwrap())
+                    })
+                })
                 .ok_or(()),
         }
     }
@@ -98,15 +174,43 @@ mod tests {
     #[test]
     fn test_datatype_display() {
         assert_eq!(DataType::Null.to_string(), "NULL");
+        assert_eq!(DataType::Double.to_string(), "DOUBLE");
         assert_eq!(DataType::Decimal(1, 2).to_string(), "DECIMAL(1,2)");
+        assert_eq!(DataType::Vector(3).to_string(), "VECTOR(3)");
+        assert_eq!(DataType::UnsignedTinyInt.to_string(), "TINYINT UNSIGNED");
     }
 
     #[test]
     fn test_datatype_from_str() {
         assert_eq!(DataType::try_from("NULL"), Ok(DataType::Null));
+        assert_eq!(DataType::try_from("DOUBLE"), Ok(DataType::Double));
         assert_eq!(
             DataType::try_from("DECIMAL(1,2)"),
             Ok(DataType::Decimal(1, 2))
         );
+        assert_eq!(DataType::try_from("VECTOR(3)"), Ok(DataType::Vector(3)));
+        assert_eq!(
+            DataType::try_from("BIGINT UNSIGNED"),
+            Ok(DataType::UnsignedBigInt)
+        );
+    }
+
+    #[test]
+    fn test_integer_datum() {
+        assert_eq!(
+            DataType::TinyInt.integer_datum(-128),
+            Some(Datum::from(-128))
+        );
+        assert_eq!(DataType::TinyInt.integer_datum(128), None);
+        assert_eq!(DataType::UnsignedSmallInt.integer_datum(-1), None);
+        assert_eq!(
+            DataType::UnsignedInteger.integer_datum(4294967295),
+            Some(Datum::from(4294967295_i64))
+        );
+        assert_eq!(
+            DataType::UnsignedBigInt.integer_datum(u64::MAX as i128),
+            Some(Datum::from(Decimal::from(u64::MAX)))
+        );
+        assert_eq!(DataType::Text.integer_datum(1), None);
     }
 }
@@ -1,10 +1,11 @@
+use crate::encoding_core::sortable_double_bits;
 use crate::json::{Json, OwnedJson};
 use crate::jsonpath_utils::JsonPathExpression;
 use crate::DataType;
 use chrono::{Datelike, NaiveDate, NaiveDateTime};
 use rust_decimal::Decimal;
 use std::cmp::Ordering;
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::{Debug, Display, Formatter, LowerExp, Write};
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 
@@ -26,6 +27,7 @@ pub enum Datum<'a> {
     Integer(i32),
     BigInt(i64),
     Decimal(Decimal),
+    Double(f64),
 
     // Compiled Datum types
     Jsonpath(Box<JsonPathExpression>),
@@ -52,6 +54,7 @@ impl<'a> Datum<'a> {
             Datum::Integer(i) => Datum::Integer(*i),
             Datum::BigInt(i) => Datum::BigInt(*i),
             Datum::Decimal(d) => Datum::Decimal(*d),
+            Datum::Double(d) => Datum::Double(*d),
             Datum::ByteAOwned(s) => Datum::ByteAOwned(s.clone()),
             Datum::ByteAInline(l, bytes) => Datum::ByteAInline(*l, *bytes),
             Datum::ByteARef(s) => {
@@ -80,6 +83,7 @@ impl<'a> Datum<'a> {
             Datum::Integer(i) => Datum::Integer(i),
             Datum::BigInt(i) => Datum::BigInt(i),
             Datum::Decimal(d) => Datum::Decimal(d),
+            Datum::Double(d) => Datum::Double(d),
             Datum::ByteAOwned(s) => Datum::ByteAOwned(s),
             Datum::ByteAInline(l, bytes) => Datum::ByteAInline(l, bytes),
             Datum::ByteARef(s) => {
@@ -112,6 +116,9 @@ impl<'a> Datum<'a> {
             Datum::Integer(i) => other.as_maybe_integer() == Some(*i),
             Datum::BigInt(i) => other.as_maybe_bigint() == Some(*i),
             Datum::Decimal(d) => other.as_maybe_decimal() == Some(*d),
+            Datum::Double(d) => {
+                other.as_maybe_double().map(sortable_double_bits) == Some(sortable_double_bits(*d))
+            }
             Datum::ByteAOwned(_) | Datum::ByteAInline(..) | Datum::ByteARef(_) => {
                 self.as_maybe_text() == other.as_maybe_text()
             }
@@ -171,6 +178,13 @@ impl Ord for Datum<'_> {
                     Ordering::Greater
                 }
             }
+            Datum::Double(d) => {
+                if let Some(o) = other.as_maybe_double() {
+                    sortable_double_bits(*d).cmp(&sortable_double_bits(o))
+                } else {
+                    Ordering::Greater
+                }
+            }
             Datum::ByteAOwned(_) | Datum::ByteAInline(..) | Datum::ByteARef(_) => {
                 if let Some(t) = other.as_maybe_text() {
                     self.as_text().cmp(t)
@@ -226,6 +240,12 @@ impl From<Decimal> for Datum<'static> {
     }
 }
 
+impl From<f64> for Datum<'static> {
+    fn from(d: f64) -> Self {
+        Datum::Double(d)
+    }
+}
+
 impl From<NaiveDate> for Datum<'static> {
     fn from(d: NaiveDate) -> Self {
         Datum::Integer((d.year() << 9) + (d.ordinal() as i32))
@@ -295,8 +315,16 @@ impl Display for TypedDatum<'_> {
                     DataType::Text => {
                         let str = self.datum.as_text();
                         if f.alternate() {
-                            // The debug trait should quote and escape our strings for us
-                            Debug::fmt(str, f)
+                            // Only quotes and backslashes need escaping, anything else is read
+                            // back by the parser as is.
+                            f.write_char('"')?;
+                            for c in str.chars() {
+                                if c == '"' || c == '\\' {
+                                    f.write_char('\\')?;
+                                }
+                                f.write_char(c)?;
+                            }
+                            f.write_char('"')
                         } else {
                             f.write_str(str)
                         }
@@ -305,20 +333,21 @@ impl Display for TypedDatum<'_> {
                         let json = Json::from_bytes(self.datum.as_bytea());
                         f.write_str(&serde_json::to_string(&json).unwrap())
                     }
+                    // The text form of a vector is also valid sql so it doesn't need quoting
+                    DataType::Vector(_) => Display::fmt(&self.datum.as_vector(), f),
+                    // Bytes are shown as hex, as a sql hex literal (x'0aff') when alternate
                     _ => {
                         let bytes = self.datum.as_bytea();
                         if f.alternate() {
-                            f.write_str("\"")?;
-                            for b in bytes {
-                                f.write_fmt(format_args!("{:x}", b))?;
-                            }
-                            f.write_str("\"")
-                        } else {
-                            for b in bytes {
-                                f.write_fmt(format_args!("{:x}", b))?;
-                            }
-                            Ok(())
+                            f.write_str("x'")?;
+                        }
+                        for b in bytes {
+                            f.write_fmt(format_args!("{:02x}", b))?;
                         }
+                        if f.alternate() {
+                            f.write_str("'")?;
+                        }
+                        Ok(())
                     }
                 }
             }
@@ -331,9 +360,17 @@ impl Display for TypedDatum<'_> {
                 DataType::Timestamp => Display::fmt(&self.datum.as_timestamp(), f),
                 _ => Display::fmt(i, f),
             },
-            Datum::Decimal(d) => {
-                if let DataType::Decimal(_p, s) = self.datatype {
-                    f.write_fmt(format_args!("{:.*}", s as usize, d))
+            Datum::Decimal(d) => match self.datatype {
+                DataType::Decimal(_p, s) => f.write_fmt(format_args!("{:.*}", s as usize, d)),
+                // Unsigned bigints are stored as decimals but the scale can come back from
+                // storage bigger than zero
+                DataType::UnsignedBigInt => f.write_fmt(format_args!("{:.0}", d)),
+                _ => Display::fmt(d, f),
+            },
+            Datum::Double(d) => {
+                if f.alternate() {
+                    // Doubles need an exponent to be parsed back as a double rather than decimal
+                    LowerExp::fmt(d, f)
                 } else {
                     Display::fmt(d, f)
                 }
@@ -445,6 +482,28 @@ impl<'a> Datum<'a> {
         }
     }
 
+    pub fn as_maybe_double(&self) -> Option<f64> {
+        if let Datum::Double(d) = self {
+            Some(*d)
+        } else {
+            None
+        }
+    }
+
+    #[track_caller]
+    pub fn as_double(&self) -> f64 {
+        self.as_maybe_double().unwrap()
+    }
+
+    #[track_caller]
+    pub fn as_double_mut(&mut self) -> &mut f64 {
+        if let Datum::Double(d) = self {
+            d
+        } else {
+            panic!()
+        }
+    }
+
     pub fn as_maybe_date(&self) -> Option<NaiveDate> {
         if let Datum::Integer(i) = self {
             Some(NaiveDate::from_yo(i >> 9, (i & 511) as u32))
@@ -511,6 +570,7 @@ impl Hash for Datum<'_> {
             Datum::Integer(i) => i.hash(state),
             Datum::BigInt(i) => i.hash(state),
             Datum::Decimal(d) => d.hash(state),
+            Datum::Double(d) => sortable_double_bits(*d).hash(state),
             Datum::ByteAOwned(_) | Datum::ByteAInline(_, _) | Datum::ByteARef(_) => {
                 self.as_bytea().hash(state)
             }
@@ -617,6 +677,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_datum_from_double() {
+        assert_eq!(Datum::from(1.5), Datum::Double(1.5));
+        // Unlike floats, doubles need to be equal to themselves to be usable as keys
+        assert_eq!(Datum::from(std::f64::NAN), Datum::from(std::f64::NAN));
+        assert_eq!(Datum::from(-0.0), Datum::from(0.0));
+        assert!(Datum::from(-1.5) < Datum::from(0.5));
+    }
+
     #[test]
     fn test_datum_from_string() {
         assert_eq!(
@@ -749,6 +818,14 @@ mod tests {
             "68656c6c6f"
         );
 
+        assert_eq!(
+            format!(
+                "{:#}",
+                Datum::from(vec![0_u8, 10, 255]).typed_with(DataType::ByteA)
+            ),
+            "x'000aff'"
+        );
+
         assert_eq!(
             format!(
                 "{}",
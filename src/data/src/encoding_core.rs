@@ -68,6 +68,49 @@ impl SortableEncoding for [u8] {
     }
 }
 
+impl SortableEncoding for f64 {
+    fn write_sortable_bytes(&self, sort_order: SortOrder, buffer: &mut Vec<u8>) {
+        let bits = sortable_double_bits(*self);
+        if sort_order.is_asc() {
+            buffer.extend_from_slice(&bits.to_be_bytes());
+        } else {
+            buffer.extend_from_slice(&(!bits).to_be_bytes());
+        }
+    }
+
+    fn read_sortable_bytes<'a>(&mut self, sort_order: SortOrder, buffer: &'a [u8]) -> &'a [u8] {
+        let bits = u64::from_be_bytes(buffer[..8].try_into().unwrap());
+        *self = if sort_order.is_asc() {
+            double_from_sortable_bits(bits)
+        } else {
+            double_from_sortable_bits(!bits)
+        };
+        &buffer[8..]
+    }
+}
+
+/// Maps a double onto a u64 with the same ordering, for positive numbers we just need to flip
+/// the sign bit, for negative numbers all the bits get flipped so that bigger magnitudes sort
+/// first. -0.0 is folded into 0.0 so they come out as equal.
+pub fn sortable_double_bits(f: f64) -> u64 {
+    let f = if f == 0.0 { 0.0 } else { f };
+    let bits = f.to_bits();
+    if (bits >> 63) == 0 {
+        bits | (1 << 63)
+    } else {
+        !bits
+    }
+}
+
+/// The inverse of sortable_double_bits
+pub fn double_from_sortable_bits(bits: u64) -> f64 {
+    if (bits >> 63) == 1 {
+        f64::from_bits(bits & !(1 << 63))
+    } else {
+        f64::from_bits(!bits)
+    }
+}
+
 lazy_static! {
     // If we're bigger than this one we actually need to divide by 10
     static ref DECIMAL_LARGEST: Decimal = Decimal::from_i128_with_scale(10_000_000_000_000_000_000_000_000_000,0);
@@ -538,6 +581,21 @@ fn read_sortable_bytes<'a>(
     rem
 }
 
+/// Skips over bytes written by write_sortable_bytes without copying them out, returning the
+/// rest of the buffer.
+pub(crate) fn skip_sortable_bytes(sort_order: SortOrder, buffer: &[u8]) -> &[u8] {
+    let more = if sort_order.is_asc() { 9 } else { !9 };
+    let mut rem = buffer;
+    loop {
+        let t = rem[8];
+        rem = &rem[9..];
+        if t != more {
+            break;
+        }
+    }
+    rem
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -695,6 +753,9 @@ mod tests {
             let rem = actual.read_sortable_bytes(SortOrder::Desc, &desc_buf);
             assert_eq!(actual, *expected);
             assert!(rem.is_empty());
+
+            assert!(skip_sortable_bytes(SortOrder::Asc, &asc_buf).is_empty());
+            assert!(skip_sortable_bytes(SortOrder::Desc, &desc_buf).is_empty());
         }
     }
 
@@ -755,4 +816,54 @@ mod tests {
             assert!(rem.is_empty());
         }
     }
+
+    #[test]
+    fn test_doubles() {
+        let mut numbers = [
+            std::f64::NEG_INFINITY,
+            std::f64::MIN,
+            -1234.5,
+            -1.0,
+            -std::f64::MIN_POSITIVE,
+            0.0,
+            std::f64::MIN_POSITIVE,
+            0.1,
+            1.0,
+            1234.5,
+            std::f64::MAX,
+            std::f64::INFINITY,
+        ];
+        let mut asc_byte_arrays = vec![];
+        let mut desc_byte_arrays = vec![];
+
+        for d in &numbers {
+            let mut buf = vec![];
+            d.write_sortable_bytes(SortOrder::Asc, &mut buf);
+            asc_byte_arrays.push(buf);
+
+            let mut buf = vec![];
+            d.write_sortable_bytes(SortOrder::Desc, &mut buf);
+            desc_byte_arrays.push(buf);
+        }
+
+        asc_byte_arrays.sort();
+        desc_byte_arrays.sort();
+        desc_byte_arrays.reverse();
+        numbers.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        for ((expected, asc_buf), desc_buf) in
+            numbers.iter().zip(asc_byte_arrays).zip(desc_byte_arrays)
+        {
+            let mut actual = 0.0_f64;
+            let rem = actual.read_sortable_bytes(SortOrder::Asc, &asc_buf);
+            assert_eq!(actual.to_bits(), expected.to_bits());
+            assert!(rem.is_empty());
+
+            let rem = actual.read_sortable_bytes(SortOrder::Desc, &desc_buf);
+            assert_eq!(actual.to_bits(), expected.to_bits());
+            assert!(rem.is_empty());
+        }
+
+        assert_eq!(sortable_double_bits(-0.0), sortable_double_bits(0.0));
+    }
 }
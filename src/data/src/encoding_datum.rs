@@ -1,6 +1,6 @@
-use crate::encoding_core::SortableEncoding;
+use crate::encoding_core::{skip_sortable_bytes, SortableEncoding};
 use crate::jsonpath_utils::JsonPathExpression;
-use crate::{Datum, SortOrder};
+use crate::{Datum, NullOrder, SortOrder};
 use rust_decimal::prelude::Zero;
 use rust_decimal::Decimal;
 
@@ -64,6 +64,14 @@ impl Datum<'_> {
                 }
                 self.as_bytea().write_sortable_bytes(sort_order, buffer)
             }
+            Datum::Double(d) => {
+                if sort_order.is_asc() {
+                    buffer.push(9)
+                } else {
+                    buffer.push(!9)
+                }
+                d.write_sortable_bytes(sort_order, buffer);
+            }
             Datum::Jsonpath(_) | Datum::JsonpathRef(_) => {
                 if sort_order.is_asc() {
                     buffer.push(8)
@@ -78,6 +86,29 @@ impl Datum<'_> {
         }
     }
 
+    /// Like as_sortable_bytes but with control over where nulls sort, None being the default of
+    /// nulls sorting as the smallest value.
+    pub fn as_sortable_bytes_with_nulls(
+        &self,
+        sort_order: SortOrder,
+        null_order: Option<NullOrder>,
+        buffer: &mut Vec<u8>,
+    ) {
+        match null_order {
+            Some(null_order)
+                if self.is_null() && null_order != NullOrder::default_for(sort_order) =>
+            {
+                // 126 sorts after all the other tags, and its complement before them
+                if sort_order.is_asc() {
+                    buffer.push(126)
+                } else {
+                    buffer.push(!126)
+                }
+            }
+            _ => self.as_sortable_bytes(sort_order, buffer),
+        }
+    }
+
     pub fn from_sortable_bytes<'a>(&mut self, buffer: &'a [u8]) -> &'a [u8] {
         let rem = &buffer[1..];
         // Infer sort order based from data instead
@@ -88,7 +119,7 @@ impl Datum<'_> {
         };
 
         match buffer[0] {
-            1 | 254 => {
+            1 | 254 | 126 | 129 => {
                 *self = Datum::Null;
                 rem
             }
@@ -140,9 +171,26 @@ impl Datum<'_> {
                 ));
                 rem
             }
+            9 | 246 => {
+                let mut d = 0.0_f64;
+                let rem = d.read_sortable_bytes(sort_order, rem);
+                *self = Datum::Double(d);
+                rem
+            }
             _ => panic!("Got unexpected datum encoding {}", buffer[0]),
         }
     }
+
+    /// Skips over a datum written by as_sortable_bytes, returning the rest of the buffer. Used to
+    /// avoid allocating for the text/bytea datums nobody is going to look at.
+    pub fn skip_sortable_bytes(buffer: &[u8]) -> &[u8] {
+        match buffer[0] {
+            7 | 8 => skip_sortable_bytes(SortOrder::Asc, &buffer[1..]),
+            248 | 247 => skip_sortable_bytes(SortOrder::Desc, &buffer[1..]),
+            // Everything else is cheap to decode
+            _ => Datum::Null.from_sortable_bytes(buffer),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +215,9 @@ mod tests {
             Datum::from(Decimal::new(67832, 2)),
             Datum::from("abcd"),
             Datum::from("efg"),
+            Datum::from(-1.5),
+            Datum::from(0.0),
+            Datum::from(2.5),
         ];
         let mut asc_byte_arrays = vec![];
         let mut desc_byte_arrays = vec![];
@@ -201,6 +252,42 @@ mod tests {
             let rem = actual.from_sortable_bytes(&desc_buf);
             assert!(actual.sql_eq(expected, true));
             assert!(rem.is_empty());
+
+            assert!(Datum::skip_sortable_bytes(&asc_buf).is_empty());
+            assert!(Datum::skip_sortable_bytes(&desc_buf).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_null_order() {
+        let encode = |datum: &Datum, sort_order, null_order| {
+            let mut buf = vec![];
+            datum.as_sortable_bytes_with_nulls(sort_order, null_order, &mut buf);
+            buf
+        };
+        let null = Datum::Null;
+        let values = [Datum::from(false), Datum::from("abc"), Datum::from(2.5)];
+
+        for value in &values {
+            for sort_order in &[SortOrder::Asc, SortOrder::Desc] {
+                let sort_order = *sort_order;
+                let null_first = encode(&null, sort_order, Some(NullOrder::First));
+                let null_last = encode(&null, sort_order, Some(NullOrder::Last));
+                let encoded = encode(value, sort_order, None);
+                assert!(null_first < encoded);
+                assert!(null_last > encoded);
+                assert_eq!(
+                    encode(&null, sort_order, None),
+                    encode(&null, sort_order, Some(NullOrder::default_for(sort_order)))
+                );
+
+                for buf in &[null_first, null_last] {
+                    let mut actual = Datum::from(1);
+                    assert!(actual.from_sortable_bytes(buf).is_empty());
+                    assert!(actual.is_null());
+                    assert!(Datum::skip_sortable_bytes(buf).is_empty());
+                }
+            }
         }
     }
 }
@@ -0,0 +1,57 @@
+//! Text tokenization shared by full-text indexes and the match function, so that the terms
+//! written into an index are always the same terms looked up when querying it.
+use crate::Datum;
+
+/// Splits text into its distinct, lower-cased, alphanumeric terms (in sorted order).
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut terms: Vec<_> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Returns the rows to write to a full-text index for a row written to the indexed table, ie
+/// the row prefixed by each of the terms in the indexed column.
+pub fn postings<'a>(tuple: &[Datum<'a>], column: usize) -> Vec<Vec<Datum<'a>>> {
+    let terms = tuple[column].as_maybe_text().map_or(vec![], tokenize);
+    terms
+        .into_iter()
+        .map(|term| {
+            let mut posting = Vec::with_capacity(tuple.len() + 1);
+            posting.push(Datum::from(term));
+            posting.extend(tuple.iter().cloned());
+            posting
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("The quick, brown fox -- the LAZY dog's bone"),
+            vec!["bone", "brown", "dog", "fox", "lazy", "quick", "s", "the"]
+        );
+        assert!(tokenize("  ,. ").is_empty());
+    }
+
+    #[test]
+    fn test_postings() {
+        let tuple = [Datum::from(1), Datum::from("b a")];
+        assert_eq!(
+            postings(&tuple, 1),
+            vec![
+                vec![Datum::from("a"), Datum::from(1), Datum::from("b a")],
+                vec![Datum::from("b"), Datum::from(1), Datum::from("b a")],
+            ]
+        );
+        assert!(postings(&[Datum::Null], 0).is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+//! The layout of secondary index rows, shared by the catalog (when building an index over
+//! existing rows) and inserts so both write exactly the same keys.
+use crate::Datum;
+
+/// Returns the row to write to a secondary index for a row written to the indexed table, ie
+/// the indexed columns followed by the row itself.
+pub fn index_tuple<'a>(tuple: &[Datum<'a>], columns: &[usize]) -> Vec<Datum<'a>> {
+    columns
+        .iter()
+        .map(|column| tuple[*column].clone())
+        .chain(tuple.iter().cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_tuple() {
+        let tuple = [Datum::from(1), Datum::from("abc"), Datum::from(true)];
+        assert_eq!(
+            index_tuple(&tuple, &[2, 0]),
+            vec![
+                Datum::from(true),
+                Datum::from(1),
+                Datum::from(1),
+                Datum::from("abc"),
+                Datum::from(true)
+            ]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{DataType, Datum};
 use rust_decimal::prelude::*;
 use std::convert::TryInto;
 use std::fmt::Debug;
@@ -386,6 +387,41 @@ impl JsonBuilder {
         self.inner.push_object(f);
         self.inner.build()
     }
+
+    /// Creates a copy of an existing json tape
+    pub fn json(mut self, j: Json) -> OwnedJson {
+        self.inner.push_json(j);
+        self.inner.build()
+    }
+
+    /// Creates a json tape from a sql value, numbers and bools become their json equivalents,
+    /// json is passed through as is and everything else becomes a string.
+    pub fn datum(mut self, datum: &Datum, datatype: DataType) -> OwnedJson {
+        match datatype {
+            _ if datum.is_null() => self.inner.push_null(),
+            DataType::Json => self.inner.push_json(datum.as_json()),
+            DataType::Boolean => self.inner.push_bool(datum.as_boolean()),
+            DataType::TinyInt
+            | DataType::SmallInt
+            | DataType::Integer
+            | DataType::UnsignedTinyInt
+            | DataType::UnsignedSmallInt => self.inner.push_int(datum.as_integer() as i64),
+            DataType::BigInt | DataType::UnsignedInteger => self.inner.push_int(datum.as_bigint()),
+            DataType::UnsignedBigInt | DataType::Decimal(..) => {
+                self.inner.push_decimal(datum.as_decimal())
+            }
+            // Json numbers are stored as decimals, infinities etc can't be represented.
+            DataType::Double => match Decimal::from_str(&datum.as_double().to_string()) {
+                Ok(d) => self.inner.push_decimal(d),
+                Err(_) => self.inner.push_null(),
+            },
+            DataType::Text => self.inner.push_string(datum.as_text()),
+            _ => self
+                .inner
+                .push_string(&datum.typed_with(datatype).to_string()),
+        }
+        self.inner.build()
+    }
 }
 
 /// Builder for arrays
@@ -482,6 +518,12 @@ impl ObjectJsonBuilder<'_> {
         self.inner.push_string(key);
         self.inner.push_object(f);
     }
+
+    /// Append an existing json object/reference
+    pub fn push_json(&mut self, key: &str, j: Json) {
+        self.inner.push_string(key);
+        self.inner.push_json(j);
+    }
 }
 
 /// Impl part of JsonBuilder that knows how to work with all the types.
@@ -1,13 +1,14 @@
-use crate::json::{Json, JsonType};
+use crate::json::{Json, JsonBuilder, JsonBuilderInner, JsonType, OwnedJson};
 use nom::branch::alt;
 use nom::bytes::complete::escaped_transform;
-use nom::bytes::complete::{is_not, tag, tag_no_case, take, take_while};
-use nom::combinator::{all_consuming, cut, map, map_res, opt, recognize, value};
+use nom::bytes::complete::{is_not, tag, tag_no_case, take, take_while, take_while1};
+use nom::character::complete::{digit1, multispace0};
+use nom::combinator::{all_consuming, cut, map, map_opt, map_res, opt, recognize, value, verify};
 use nom::error::context;
 use nom::lib::std::cmp::Ordering;
 use nom::lib::std::fmt::Formatter;
-use nom::multi::many0;
-use nom::sequence::{delimited, pair, preceded};
+use nom::multi::{fold_many0, many0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::{AsChar, IResult};
 use std::fmt::Display;
 
@@ -39,18 +40,17 @@ impl JsonPathExpression {
     /// is to return values wrapped in a json array if this is true, otherwise to return
     /// the singular value (or null)
     pub fn could_return_many(&self) -> bool {
-        self.selectors
-            .iter()
-            .any(|selector| selector == &JsonPathSelector::Wildcard)
+        self.selectors.iter().any(|selector| {
+            !matches!(
+                selector,
+                JsonPathSelector::StringIdentifier(_) | JsonPathSelector::NumericIdentifier(_)
+            )
+        })
     }
 
     /// Evaluates the given jsonpath and calls a call back for each match.
     pub fn evaluate<'a, 'b: 'a, F: FnMut(Json<'b>)>(&'a self, json: Json<'b>, f: &mut F) {
-        if self.selectors.is_empty() {
-            f(json)
-        } else {
-            self.selectors[0].evaluate(json, &self.selectors[1..], f);
-        }
+        evaluate_selectors(&self.selectors, json, json, f);
     }
 
     /// Returns the first match if one exists
@@ -63,6 +63,39 @@ impl JsonPathExpression {
     pub fn original(&self) -> &str {
         &self.original
     }
+
+    /// Returns a copy of the json with the modification applied at the path.
+    /// Like mysql, paths with wildcards and removing the root aren't allowed, None is returned
+    /// for these.
+    pub fn modify(&self, json: Json, modification: JsonModification) -> Option<OwnedJson> {
+        if self.could_return_many() {
+            return None;
+        }
+        if self.selectors.is_empty() {
+            return match modification {
+                JsonModification::Set(value) | JsonModification::Replace(value) => {
+                    Some(JsonBuilder::default().json(value))
+                }
+                JsonModification::Insert(_) => Some(JsonBuilder::default().json(json)),
+                JsonModification::Remove => None,
+            };
+        }
+        let mut builder = JsonBuilderInner::default();
+        self.selectors[0].modify(&mut builder, json, &self.selectors[1..], modification);
+        Some(builder.build())
+    }
+}
+
+/// The modifications for json_set etc.
+#[derive(Copy, Clone, Debug)]
+pub enum JsonModification<'a> {
+    /// Replaces existing values and adds missing ones
+    Set(Json<'a>),
+    /// Only adds missing values
+    Insert(Json<'a>),
+    /// Only replaces existing values
+    Replace(Json<'a>),
+    Remove,
 }
 
 impl Display for JsonPathExpression {
@@ -88,37 +121,78 @@ enum JsonPathSelector {
     Wildcard,
     StringIdentifier(String),
     NumericIdentifier(i64),
+    /// `..`, the node itself and all its descendants, always followed by the selector that picks
+    /// out the matches from them.
+    Descendants,
+    /// `[start:end:step]`
+    Slice(Option<i64>, Option<i64>, i64),
+    /// `[0, 2]`, `["a", "b"]` etc
+    Union(Vec<JsonPathSelector>),
+    /// `[?(@.price > 10)]`
+    Filter(Box<FilterExpression>),
+}
+
+/// Evaluates the selectors in turn, calling the call back for each match.
+fn evaluate_selectors<'b, F: FnMut(Json<'b>)>(
+    selectors: &[JsonPathSelector],
+    input: Json<'b>,
+    root: Json<'b>,
+    f: &mut F,
+) {
+    if let Some((selector, rest)) = selectors.split_first() {
+        selector.evaluate(input, rest, root, f);
+    } else {
+        f(input)
+    }
 }
 
 impl JsonPathSelector {
     /// Evaluate the given selector, calling the call back function on any matches.
+    /// The root is the whole document, needed for filters that refer back to it.
     pub fn evaluate<'a, 'b: 'a, F: FnMut(Json<'b>)>(
         &'a self,
         input: Json<'b>,
         rest: &[JsonPathSelector],
+        root: Json<'b>,
         f: &mut F,
     ) {
+        if let JsonPathSelector::Descendants = self {
+            evaluate_selectors(rest, input, root, f);
+            match input.json_type() {
+                JsonType::Object => {
+                    for (_, v) in input.iter_object().unwrap() {
+                        self.evaluate(v, rest, root, f);
+                    }
+                }
+                JsonType::Array => {
+                    for v in input.iter_array().unwrap() {
+                        self.evaluate(v, rest, root, f);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        if let JsonPathSelector::Union(selectors) = self {
+            for selector in selectors {
+                selector.evaluate(input, rest, root, f);
+            }
+            return;
+        }
+
         match input.json_type() {
             JsonType::Object => {
                 let kv_iter = input.iter_object().unwrap();
                 match self {
                     JsonPathSelector::Wildcard => {
                         for (_, v) in kv_iter {
-                            if rest.is_empty() {
-                                f(v);
-                            } else {
-                                rest[0].evaluate(v, &rest[1..], f);
-                            }
+                            evaluate_selectors(rest, v, root, f);
                         }
                     }
                     JsonPathSelector::StringIdentifier(str) => {
                         for (k, v) in kv_iter {
                             if k.eq_ignore_ascii_case(str) {
-                                if rest.is_empty() {
-                                    f(v);
-                                } else {
-                                    rest[0].evaluate(v, &rest[1..], f);
-                                }
+                                evaluate_selectors(rest, v, root, f);
                                 break;
                             }
                         }
@@ -129,60 +203,62 @@ impl JsonPathSelector {
                         // as keys
                         for (k, v) in kv_iter {
                             if k.eq(&idx.to_string()) {
-                                if rest.is_empty() {
-                                    f(v);
-                                } else {
-                                    rest[0].evaluate(v, &rest[1..], f);
-                                }
+                                evaluate_selectors(rest, v, root, f);
                                 break;
                             }
                         }
                     }
+                    JsonPathSelector::Filter(filter) => {
+                        for (_, v) in kv_iter {
+                            if filter.test(v, root) {
+                                evaluate_selectors(rest, v, root, f);
+                            }
+                        }
+                    }
+                    JsonPathSelector::Slice(..)
+                    | JsonPathSelector::Descendants
+                    | JsonPathSelector::Union(_) => {}
                 }
             }
             JsonType::Array => {
-                let v_iter = input.iter_array().unwrap();
+                let mut v_iter = input.iter_array().unwrap();
 
                 match self {
                     JsonPathSelector::Wildcard => {
                         for v in v_iter {
-                            if rest.is_empty() {
-                                f(v);
-                            } else {
-                                rest[0].evaluate(v, &rest[1..], f);
-                            }
+                            evaluate_selectors(rest, v, root, f);
                         }
                     }
                     JsonPathSelector::StringIdentifier(s) => {
                         if let Ok(i) = s.parse::<i64>() {
                             if i >= 0 {
-                                for (idx, v) in v_iter.enumerate() {
-                                    if idx == i as usize {
-                                        if rest.is_empty() {
-                                            f(v);
-                                        } else {
-                                            rest[0].evaluate(v, &rest[1..], f);
-                                        }
-                                        break;
-                                    }
+                                if let Some(v) = v_iter.nth(i as usize) {
+                                    evaluate_selectors(rest, v, root, f);
                                 }
                             }
                         }
                     }
                     JsonPathSelector::NumericIdentifier(i) => {
                         if *i >= 0 {
-                            for (idx, v) in v_iter.enumerate() {
-                                if idx == *i as usize {
-                                    if rest.is_empty() {
-                                        f(v);
-                                    } else {
-                                        rest[0].evaluate(v, &rest[1..], f);
-                                    }
-                                    break;
-                                }
+                            if let Some(v) = v_iter.nth(*i as usize) {
+                                evaluate_selectors(rest, v, root, f);
                             }
                         }
                     }
+                    JsonPathSelector::Slice(start, end, step) => {
+                        let values: Vec<_> = v_iter.collect();
+                        for idx in slice_indexes(*start, *end, *step, values.len()) {
+                            evaluate_selectors(rest, values[idx], root, f);
+                        }
+                    }
+                    JsonPathSelector::Filter(filter) => {
+                        for v in v_iter {
+                            if filter.test(v, root) {
+                                evaluate_selectors(rest, v, root, f);
+                            }
+                        }
+                    }
+                    JsonPathSelector::Descendants | JsonPathSelector::Union(_) => {}
                 }
             }
             _ => {}
@@ -190,29 +266,436 @@ impl JsonPathSelector {
     }
 }
 
+/// The indexes of an array of the given length picked out by a slice, negative starts and ends
+/// count back from the end of the array. Like python a negative step walks the array backwards.
+fn slice_indexes(start: Option<i64>, end: Option<i64>, step: i64, len: usize) -> Vec<usize> {
+    let len = len as i64;
+    let normalize = |idx: i64| if idx < 0 { idx + len } else { idx };
+    match step.cmp(&0) {
+        Ordering::Greater => {
+            let start = start.map_or(0, normalize).max(0).min(len);
+            let end = end.map_or(len, normalize).max(0).min(len);
+            (start..end)
+                .step_by(step as usize)
+                .map(|idx| idx as usize)
+                .collect()
+        }
+        Ordering::Less => {
+            let start = start.map_or(len - 1, |start| normalize(start).max(-1).min(len - 1));
+            let end = end.map_or(-1, |end| normalize(end).max(-1).min(len - 1));
+            let mut indexes = vec![];
+            let mut idx = start;
+            while idx > end {
+                indexes.push(idx as usize);
+                idx += step;
+            }
+            indexes
+        }
+        Ordering::Equal => vec![],
+    }
+}
+
+/// The predicate of a filter selector, tested against each of the array's elements (or
+/// object's values).
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum FilterExpression {
+    Or(Box<FilterExpression>, Box<FilterExpression>),
+    And(Box<FilterExpression>, Box<FilterExpression>),
+    Not(Box<FilterExpression>),
+    /// `[?(@.price)]`, true if the path matches anything
+    Exists(FilterOperand),
+    Compare(FilterOperand, CompareOp, FilterOperand),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum FilterOperand {
+    /// A path starting at the value being tested (`@`)
+    Current(Vec<JsonPathSelector>),
+    /// A path starting at the root of the document (`$`)
+    Root(Vec<JsonPathSelector>),
+    Literal(OwnedJson),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl FilterExpression {
+    fn test(&self, current: Json, root: Json) -> bool {
+        match self {
+            FilterExpression::Or(left, right) => {
+                left.test(current, root) || right.test(current, root)
+            }
+            FilterExpression::And(left, right) => {
+                left.test(current, root) && right.test(current, root)
+            }
+            FilterExpression::Not(expr) => !expr.test(current, root),
+            FilterExpression::Exists(operand) => operand.value(current, root).is_some(),
+            FilterExpression::Compare(left, op, right) => {
+                let left = left.value(current, root);
+                let right = right.value(current, root);
+                // Paths that don't match anything are only equal to each other and can't be
+                // ordered.
+                let equal = match (left, right) {
+                    (Some(left), Some(right)) => json_equal(left, right),
+                    (left, right) => left.is_none() && right.is_none(),
+                };
+                let ordering = match (left, right) {
+                    (Some(left), Some(right)) => json_ordering(left, right),
+                    _ => None,
+                };
+                match op {
+                    CompareOp::Eq => equal,
+                    CompareOp::NotEq => !equal,
+                    CompareOp::Lt => ordering == Some(Ordering::Less),
+                    CompareOp::LtEq => equal || ordering == Some(Ordering::Less),
+                    CompareOp::Gt => ordering == Some(Ordering::Greater),
+                    CompareOp::GtEq => equal || ordering == Some(Ordering::Greater),
+                }
+            }
+        }
+    }
+}
+
+impl FilterOperand {
+    /// The (first) value of the operand, None if its path doesn't match anything.
+    fn value<'b>(&'b self, current: Json<'b>, root: Json<'b>) -> Option<Json<'b>> {
+        let first_match = |selectors: &'b [JsonPathSelector], input: Json<'b>| {
+            let mut result = None;
+            evaluate_selectors(selectors, input, root, &mut |json| {
+                if result.is_none() {
+                    result = Some(json)
+                }
+            });
+            result
+        };
+        match self {
+            FilterOperand::Current(selectors) => first_match(selectors.as_slice(), current),
+            FilterOperand::Root(selectors) => first_match(selectors.as_slice(), root),
+            FilterOperand::Literal(json) => Some(json.as_json()),
+        }
+    }
+}
+
+/// Numbers are compared by value so 1 and 1.0 are equal, arrays and objects are compared
+/// element by element.
+fn json_equal(left: Json, right: Json) -> bool {
+    match (left.json_type(), right.json_type()) {
+        (JsonType::Null, JsonType::Null) => true,
+        (JsonType::Boolean, JsonType::Boolean) => left.get_boolean() == right.get_boolean(),
+        (JsonType::Number, JsonType::Number) => left.get_number() == right.get_number(),
+        (JsonType::String, JsonType::String) => left.get_string() == right.get_string(),
+        (JsonType::Array, JsonType::Array) => {
+            let left: Vec<_> = left.iter_array().unwrap().collect();
+            let right: Vec<_> = right.iter_array().unwrap().collect();
+            left.len() == right.len()
+                && left
+                    .into_iter()
+                    .zip(right)
+                    .all(|(left, right)| json_equal(left, right))
+        }
+        (JsonType::Object, JsonType::Object) => {
+            let left: Vec<_> = left.iter_object().unwrap().collect();
+            let right: Vec<_> = right.iter_object().unwrap().collect();
+            left.len() == right.len()
+                && left.iter().all(|(key, left)| {
+                    right
+                        .iter()
+                        .any(|(k, right)| k == key && json_equal(*left, *right))
+                })
+        }
+        _ => false,
+    }
+}
+
+/// Only numbers and strings can be ordered, and only against their own type.
+fn json_ordering(left: Json, right: Json) -> Option<Ordering> {
+    match (left.json_type(), right.json_type()) {
+        (JsonType::Number, JsonType::Number) => Some(left.get_number()?.cmp(&right.get_number()?)),
+        (JsonType::String, JsonType::String) => Some(left.get_string()?.cmp(right.get_string()?)),
+        _ => None,
+    }
+}
+
+impl JsonPathSelector {
+    /// Writes out a copy of the input with the modification applied, the selectors are
+    /// followed down the tree with everything off the path being copied as is.
+    fn modify(
+        &self,
+        builder: &mut JsonBuilderInner,
+        input: Json,
+        rest: &[JsonPathSelector],
+        modification: JsonModification,
+    ) {
+        let modify_child = |builder: &mut JsonBuilderInner, child: Json| {
+            if let Some(next) = rest.first() {
+                next.modify(builder, child, &rest[1..], modification);
+            } else {
+                match modification {
+                    JsonModification::Set(value) | JsonModification::Replace(value) => {
+                        builder.push_json(value)
+                    }
+                    JsonModification::Insert(_) | JsonModification::Remove => {
+                        builder.push_json(child)
+                    }
+                }
+            }
+        };
+        // The value to add when the last selector doesn't match anything
+        let missing_value = match modification {
+            JsonModification::Set(value) | JsonModification::Insert(value) if rest.is_empty() => {
+                Some(value)
+            }
+            _ => None,
+        };
+        let removing = rest.is_empty() && matches!(modification, JsonModification::Remove);
+
+        match (input.json_type(), self) {
+            (JsonType::Object, JsonPathSelector::StringIdentifier(_))
+            | (JsonType::Object, JsonPathSelector::NumericIdentifier(_)) => {
+                let key = match self {
+                    JsonPathSelector::StringIdentifier(key) => key.clone(),
+                    JsonPathSelector::NumericIdentifier(idx) => idx.to_string(),
+                    _ => unreachable!(),
+                };
+                builder.push_object(|object| {
+                    let mut found = false;
+                    for (k, v) in input.iter_object().unwrap() {
+                        if !found && k.eq_ignore_ascii_case(&key) {
+                            found = true;
+                            if !removing {
+                                object.inner.push_string(k);
+                                modify_child(&mut *object.inner, v);
+                            }
+                        } else {
+                            object.push_json(k, v);
+                        }
+                    }
+                    if let (false, Some(value)) = (found, missing_value) {
+                        object.push_json(&key, value);
+                    }
+                });
+            }
+            (JsonType::Array, JsonPathSelector::NumericIdentifier(_))
+            | (JsonType::Array, JsonPathSelector::StringIdentifier(_)) => {
+                let idx = match self {
+                    JsonPathSelector::NumericIdentifier(idx) => Some(*idx),
+                    JsonPathSelector::StringIdentifier(s) => s.parse::<i64>().ok(),
+                    _ => unreachable!(),
+                };
+                builder.push_array(|array| {
+                    let mut found = false;
+                    for (i, v) in input.iter_array().unwrap().enumerate() {
+                        if Some(i as i64) == idx {
+                            found = true;
+                            if !removing {
+                                modify_child(&mut *array.inner, v);
+                            }
+                        } else {
+                            array.push_json(v);
+                        }
+                    }
+                    // Like mysql, setting past the end of an array appends to it
+                    if let (false, Some(value)) = (found, missing_value) {
+                        if idx.map_or(false, |idx| idx >= 0) {
+                            array.push_json(value);
+                        }
+                    }
+                });
+            }
+            _ => builder.push_json(input),
+        }
+    }
+}
+
 type ParserResult<'a, T> = IResult<&'a str, T>;
 
 fn parse_expression(input: &str) -> ParserResult<Vec<JsonPathSelector>> {
-    all_consuming(preceded(tag("$"), many0(parse_selector)))(input)
+    all_consuming(preceded(tag("$"), parse_selectors(false)))(input)
 }
 
-fn parse_selector(input: &str) -> ParserResult<JsonPathSelector> {
+/// Parses the selectors following a `$` or `@`. Inside filters member names can only be made up
+/// of alphanumerics so the rest of the filter isn't taken as part of the name.
+fn parse_selectors<'a>(
+    in_filter: bool,
+) -> impl FnMut(&'a str) -> ParserResult<'a, Vec<JsonPathSelector>> {
+    let member_name = move |input: &'a str| -> ParserResult<'a, JsonPathSelector> {
+        let name = if in_filter {
+            take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)
+        } else {
+            is_not(".[")(input)
+        };
+        name.map(|(rest, name)| (rest, JsonPathSelector::StringIdentifier(name.to_string())))
+    };
     // Its always either dot or bracket notation, numbers only seem to be able to work via
     // bracket notation
-    alt((
-        value(JsonPathSelector::Wildcard, alt((tag(".*"), tag("[*]")))),
-        map(delimited(tag("["), integer, tag("]")), |i| {
-            JsonPathSelector::NumericIdentifier(i)
-        }),
+    let selector = alt((
+        value(JsonPathSelector::Wildcard, tag(".*")),
+        parse_brackets,
         map(preceded(tag("."), integer), |i: i64| {
             JsonPathSelector::NumericIdentifier(i)
         }),
-        map(delimited(tag("["), quoted_string, tag("]")), |s| {
-            JsonPathSelector::StringIdentifier(s)
-        }),
-        map(preceded(tag("."), is_not(".[")), |s: &str| {
-            JsonPathSelector::StringIdentifier(s.to_string())
+        preceded(tag("."), member_name),
+    ));
+    let descendants = preceded(
+        tag(".."),
+        alt((
+            value(JsonPathSelector::Wildcard, tag("*")),
+            parse_brackets,
+            member_name,
+        )),
+    );
+    map(
+        many0(alt((
+            map(descendants, |selector| {
+                vec![JsonPathSelector::Descendants, selector]
+            }),
+            map(selector, |selector| vec![selector]),
+        ))),
+        |selectors| selectors.into_iter().flatten().collect(),
+    )
+}
+
+/// Everything in square brackets, a filter or one or more (comma separated) names, indexes or
+/// slices.
+fn parse_brackets(input: &str) -> ParserResult<JsonPathSelector> {
+    let comma = delimited(multispace0, tag(","), multispace0);
+    delimited(
+        pair(tag("["), multispace0),
+        alt((
+            map(
+                preceded(pair(tag("?"), multispace0), parse_filter_or),
+                |filter| JsonPathSelector::Filter(Box::new(filter)),
+            ),
+            map(
+                separated_list1(comma, parse_bracket_member),
+                |mut members| {
+                    if members.len() == 1 {
+                        members.remove(0)
+                    } else {
+                        JsonPathSelector::Union(members)
+                    }
+                },
+            ),
+        )),
+        pair(multispace0, tag("]")),
+    )(input)
+}
+
+fn parse_bracket_member(input: &str) -> ParserResult<JsonPathSelector> {
+    alt((
+        value(JsonPathSelector::Wildcard, tag("*")),
+        parse_slice,
+        map(integer, JsonPathSelector::NumericIdentifier),
+        map(quoted_string, JsonPathSelector::StringIdentifier),
+    ))(input)
+}
+
+fn parse_slice(input: &str) -> ParserResult<JsonPathSelector> {
+    map(
+        tuple((
+            opt(terminated(integer, multispace0)),
+            preceded(
+                pair(tag(":"), multispace0),
+                opt(terminated(integer, multispace0)),
+            ),
+            opt(preceded(pair(tag(":"), multispace0), opt(integer))),
+        )),
+        |(start, end, step)| JsonPathSelector::Slice(start, end, step.flatten().unwrap_or(1)),
+    )(input)
+}
+
+fn parse_filter_or(input: &str) -> ParserResult<FilterExpression> {
+    let (input, first) = parse_filter_and(input)?;
+    fold_many0(
+        preceded(
+            delimited(multispace0, tag("||"), multispace0),
+            parse_filter_and,
+        ),
+        first,
+        |left, right| FilterExpression::Or(Box::new(left), Box::new(right)),
+    )(input)
+}
+
+fn parse_filter_and(input: &str) -> ParserResult<FilterExpression> {
+    let (input, first) = parse_filter_unary(input)?;
+    fold_many0(
+        preceded(
+            delimited(multispace0, tag("&&"), multispace0),
+            parse_filter_unary,
+        ),
+        first,
+        |left, right| FilterExpression::And(Box::new(left), Box::new(right)),
+    )(input)
+}
+
+fn parse_filter_unary(input: &str) -> ParserResult<FilterExpression> {
+    alt((
+        map(
+            preceded(pair(tag("!"), multispace0), parse_filter_unary),
+            |expr| FilterExpression::Not(Box::new(expr)),
+        ),
+        delimited(
+            pair(tag("("), multispace0),
+            parse_filter_or,
+            pair(multispace0, tag(")")),
+        ),
+        map(
+            tuple((
+                parse_filter_operand,
+                delimited(multispace0, parse_compare_op, multispace0),
+                parse_filter_operand,
+            )),
+            |(left, op, right)| FilterExpression::Compare(left, op, right),
+        ),
+        map(
+            verify(parse_filter_operand, |operand: &FilterOperand| {
+                !matches!(operand, FilterOperand::Literal(_))
+            }),
+            FilterExpression::Exists,
+        ),
+    ))(input)
+}
+
+fn parse_compare_op(input: &str) -> ParserResult<CompareOp> {
+    alt((
+        value(CompareOp::Eq, tag("==")),
+        value(CompareOp::NotEq, tag("!=")),
+        value(CompareOp::LtEq, tag("<=")),
+        value(CompareOp::Lt, tag("<")),
+        value(CompareOp::GtEq, tag(">=")),
+        value(CompareOp::Gt, tag(">")),
+    ))(input)
+}
+
+fn parse_filter_operand(input: &str) -> ParserResult<FilterOperand> {
+    alt((
+        map(
+            preceded(tag("@"), parse_selectors(true)),
+            FilterOperand::Current,
+        ),
+        map(
+            preceded(tag("$"), parse_selectors(true)),
+            FilterOperand::Root,
+        ),
+        map(quoted_string, |s| {
+            FilterOperand::Literal(JsonBuilder::default().string(&s))
         }),
+        map_opt(
+            alt((
+                tag("true"),
+                tag("false"),
+                tag("null"),
+                recognize(tuple((opt(tag("-")), digit1, opt(pair(tag("."), digit1))))),
+            )),
+            |literal| OwnedJson::parse(literal).map(FilterOperand::Literal),
+        ),
     ))(input)
 }
 
@@ -238,6 +721,12 @@ pub fn quoted_string(input: &str) -> ParserResult<String> {
             escaped_transform(is_not("\"\\"), '\\', trans),
             cut(context("Missing closing double quote", tag("\""))),
         ),
+        value(String::new(), tag("''")),
+        delimited(
+            tag("'"),
+            escaped_transform(is_not("'\\"), '\\', trans),
+            cut(context("Missing closing single quote", tag("'"))),
+        ),
     ))(input)
 }
 
@@ -420,4 +909,152 @@ mod tests {
             Some(expected.as_json())
         );
     }
+
+    /// All the matches of the path as a json array.
+    fn matches(path: &str, input: &str) -> OwnedJson {
+        let expr = JsonPathExpression::parse(path).unwrap();
+        assert_eq!(expr.could_return_many(), true);
+        let input = OwnedJson::parse(input).unwrap();
+        JsonBuilder::default()
+            .array(|array| expr.evaluate(input.as_json(), &mut |json| array.push_json(json)))
+    }
+
+    fn json(s: &str) -> OwnedJson {
+        OwnedJson::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_recursive_descent() {
+        assert_eq!(
+            JsonPathExpression::parse("$..a[0]").unwrap().selectors,
+            vec![
+                JsonPathSelector::Descendants,
+                JsonPathSelector::StringIdentifier("a".to_string()),
+                JsonPathSelector::NumericIdentifier(0)
+            ]
+        );
+        let input = r#"{"a": 1, "b": {"a": 2, "c": [{"a": 3}]}}"#;
+        assert_eq!(matches("$..a", input), json("[1, 2, 3]"));
+        assert_eq!(matches("$.b..a", input), json("[2, 3]"));
+        assert_eq!(matches("$..[0]", input), json(r#"[{"a": 3}]"#));
+    }
+
+    #[test]
+    fn test_slice_selector() {
+        assert_eq!(
+            JsonPathExpression::parse("$[1:]").unwrap().selectors,
+            vec![JsonPathSelector::Slice(Some(1), None, 1)]
+        );
+        let input = "[0, 1, 2, 3, 4]";
+        assert_eq!(matches("$[1:3]", input), json("[1, 2]"));
+        assert_eq!(matches("$[-2:]", input), json("[3, 4]"));
+        assert_eq!(matches("$[::2]", input), json("[0, 2, 4]"));
+        assert_eq!(matches("$[::-2]", input), json("[4, 2, 0]"));
+        assert_eq!(matches("$[10:]", input), json("[]"));
+    }
+
+    #[test]
+    fn test_union_selector() {
+        assert_eq!(
+            JsonPathExpression::parse("$[0, 2]").unwrap().selectors,
+            vec![JsonPathSelector::Union(vec![
+                JsonPathSelector::NumericIdentifier(0),
+                JsonPathSelector::NumericIdentifier(2)
+            ])]
+        );
+        assert_eq!(
+            matches("$[0, 2]", r#"["a", "b", "c"]"#),
+            json(r#"["a", "c"]"#)
+        );
+        assert_eq!(matches("$[3:, 0]", "[0, 1, 2, 3, 4]"), json("[3, 4, 0]"));
+        assert_eq!(
+            matches(r#"$["x", 'z']"#, r#"{"x": 1, "y": 2, "z": 3}"#),
+            json("[1, 3]")
+        );
+    }
+
+    #[test]
+    fn test_filter_selector() {
+        let input = r#"{"min": 10, "items": [
+            {"name": "a", "price": 5, "tags": ["x"]},
+            {"name": "b", "price": 15.0},
+            {"name": "c", "price": 10, "tags": ["y"]},
+            {"name": "d"}
+        ]}"#;
+        assert_eq!(
+            matches("$.items[?(@.price > 10)].name", input),
+            json(r#"["b"]"#)
+        );
+        assert_eq!(
+            matches("$.items[?(@.price >= $.min)].name", input),
+            json(r#"["b", "c"]"#)
+        );
+        assert_eq!(
+            matches("$.items[?(@.price == 15 || !@.price)].name", input),
+            json(r#"["b", "d"]"#)
+        );
+        assert_eq!(
+            matches("$.items[?@.tags && @.name != 'c'].name", input),
+            json(r#"["a"]"#)
+        );
+        assert_eq!(
+            matches(r#"$.items[?(@.tags[0] == "y")].price"#, input),
+            json("[10]")
+        );
+        assert_eq!(
+            matches("$..[?(@.price < 10)].name", input),
+            json(r#"["a"]"#)
+        );
+
+        assert_eq!(JsonPathExpression::parse("$[?(@.price ==)]"), None);
+        assert_eq!(JsonPathExpression::parse("$[?(10)]"), None);
+    }
+
+    #[test]
+    fn test_modify() {
+        let input = OwnedJson::parse(r#"{"a": 1, "b": [1, 2]}"#).unwrap();
+        let value = OwnedJson::parse(r#"{"x": true}"#).unwrap();
+        let modify = |path: &str, modification| {
+            JsonPathExpression::parse(path)
+                .unwrap()
+                .modify(input.as_json(), modification)
+        };
+        let json = |s: &str| Some(OwnedJson::parse(s).unwrap());
+
+        let set = JsonModification::Set(value.as_json());
+        assert_eq!(
+            modify("$.a", set),
+            json(r#"{"a": {"x": true}, "b": [1, 2]}"#)
+        );
+        assert_eq!(
+            modify("$.c", set),
+            json(r#"{"a": 1, "b": [1, 2], "c": {"x": true}}"#)
+        );
+        assert_eq!(
+            modify("$.b[5]", set),
+            json(r#"{"a": 1, "b": [1, 2, {"x": true}]}"#)
+        );
+        assert_eq!(modify("$.c.d", set), json(r#"{"a": 1, "b": [1, 2]}"#));
+        assert_eq!(modify("$", set), json(r#"{"x": true}"#));
+
+        let insert = JsonModification::Insert(value.as_json());
+        assert_eq!(modify("$.a", insert), json(r#"{"a": 1, "b": [1, 2]}"#));
+        assert_eq!(
+            modify("$.b[2]", insert),
+            json(r#"{"a": 1, "b": [1, 2, {"x": true}]}"#)
+        );
+
+        let replace = JsonModification::Replace(value.as_json());
+        assert_eq!(
+            modify("$.b[0]", replace),
+            json(r#"{"a": 1, "b": [{"x": true}, 2]}"#)
+        );
+        assert_eq!(modify("$.c", replace), json(r#"{"a": 1, "b": [1, 2]}"#));
+
+        let remove = JsonModification::Remove;
+        assert_eq!(modify("$.a", remove), json(r#"{"b": [1, 2]}"#));
+        assert_eq!(modify("$.b[0]", remove), json(r#"{"a": 1, "b": [2]}"#));
+        assert_eq!(modify("$", remove), None);
+        assert_eq!(modify("$.b[*]", remove), None);
+    }
 }
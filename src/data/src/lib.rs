@@ -2,19 +2,26 @@
 // NaiveDate
 pub use chrono;
 pub use rust_decimal;
+mod collation;
 mod datatype;
 mod datum;
 pub mod encoding_core;
 mod encoding_datum;
+pub mod fulltext;
+pub mod index;
 pub mod json;
 mod json_serde;
 mod session;
+pub mod statistics;
 mod tuple_iter;
+pub mod vector;
+pub use collation::Collation;
 pub use datatype::*;
 pub use datum::Datum;
 use serde::export::Formatter;
-pub use session::Session;
+pub use session::{parse_time_zone, Session, SERVER_VERSION};
 use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, Ordering};
 pub use tuple_iter::*;
 pub mod jsonpath_utils;
 
@@ -47,6 +54,34 @@ impl Display for SortOrder {
     }
 }
 
+/// Where nulls sort relative to everything else.
+/// By default nulls are treated as the smallest value (like MySQL), so they come first when
+/// sorting ascending and last when sorting descending.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum NullOrder {
+    First,
+    Last,
+}
+
+impl NullOrder {
+    /// The null order used when a sort doesn't ask for one.
+    pub fn default_for(sort_order: SortOrder) -> Self {
+        match sort_order {
+            SortOrder::Asc => NullOrder::First,
+            SortOrder::Desc => NullOrder::Last,
+        }
+    }
+}
+
+impl Display for NullOrder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NullOrder::First => f.write_str("NULLS FIRST"),
+            NullOrder::Last => f.write_str("NULLS LAST"),
+        }
+    }
+}
+
 /// Timestamps for tracking tuples through the system, used for MVCC style point in time queries,
 #[derive(Default, Debug, Eq, PartialEq, Copy, Clone, Ord, PartialOrd)]
 pub struct LogicalTimestamp {
@@ -69,4 +104,35 @@ impl LogicalTimestamp {
                 .as_millis() as u64,
         }
     }
+
+    /// The current time, or if it's later the latest timestamp handed out for a write.
+    pub fn latest() -> Self {
+        let ms = LogicalTimestamp::now()
+            .ms
+            .max(LAST_WRITE.load(Ordering::SeqCst));
+        LogicalTimestamp { ms }
+    }
+
+    /// The timestamp for a write to read as of, it sees everything written before it (including
+    /// anything written this ms) but nothing the write itself goes on to write.
+    pub fn snapshot() -> Self {
+        LogicalTimestamp {
+            ms: LogicalTimestamp::latest().ms + 1,
+        }
+    }
+
+    /// The timestamp to write at for a write reading as of the snapshot, it's never before the
+    /// snapshot so the write doesn't see its own changes, and later snapshots are always past it.
+    pub fn for_write(snapshot: LogicalTimestamp) -> Self {
+        let mut timestamp = LogicalTimestamp::now();
+        if snapshot != LogicalTimestamp::MAX {
+            timestamp = timestamp.max(snapshot);
+            LAST_WRITE.fetch_max(timestamp.ms, Ordering::SeqCst);
+        }
+        timestamp
+    }
 }
+
+/// The latest timestamp handed out by LogicalTimestamp::for_write, which may be just ahead of the
+/// clock.
+static LAST_WRITE: AtomicU64 = AtomicU64::new(0);
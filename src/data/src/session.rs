@@ -1,5 +1,15 @@
-use std::sync::atomic::AtomicBool;
+use crate::{Collation, DataType, Datum, LogicalTimestamp, DECIMAL_MAX_PRECISION};
+use chrono::{FixedOffset, NaiveDateTime};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::RwLock;
+use std::time::Instant;
+
+/// The server version reported to clients, ie via the handshake and version()
+pub const SERVER_VERSION: &str = "8.0.0-incresql";
+
+/// Like mysql's max_error_count, only the first this many warnings for a statement are kept.
+const MAX_WARNINGS: usize = 64;
 
 /// Stores any and all session variables.
 #[derive(Debug)]
@@ -8,6 +18,40 @@ pub struct Session {
     pub current_database: RwLock<String>,
     pub connection_id: u32,
     pub kill_flag: AtomicBool,
+    // Set by KILL CONNECTION, the connection gets closed once its current command is done
+    pub connection_killed: AtomicBool,
+    // The statement currently being run and when it started, for SHOW PROCESSLIST
+    pub running_statement: RwLock<Option<(String, Instant)>>,
+    // The number of rows read by scans and sent back to the client for the running statement,
+    // for the slow query log
+    pub rows_examined: AtomicU64,
+    pub rows_sent: AtomicU64,
+    // The number of rows inserted/deleted by the last statement
+    pub affected_rows: AtomicU64,
+    // The id generated for an auto increment column by the last statement, 0 if none were
+    pub last_insert_id: AtomicU64,
+    // The info message for the last statement sent back in the ok packet, ie
+    // "Records: 3  Duplicates: 0  Warnings: 0" for inserts
+    pub info: RwLock<String>,
+    // System variables, ie character_set_client, mostly set by clients via SET statements and
+    // recorded rather than acted upon. User variables live here too, named with their @ (ie @x).
+    pub variables: RwLock<HashMap<String, Datum<'static>>>,
+    // Set by functions whose result doesn't fit their type (ie integer overflows), functions
+    // can't fail so they return null and the executors turn this into an error.
+    pub out_of_range: RwLock<Option<String>>,
+    // Set along with out_of_range so executors checking after every row don't have to take the
+    // lock.
+    pub out_of_range_raised: AtomicBool,
+    // The (code, message) of warnings raised by the statement, ie text that isn't a number being
+    // compared to a number. Cleared as each statement starts, for SHOW WARNINGS.
+    pub warnings: RwLock<Vec<(u16, String)>>,
+    // Scans planned to read the latest data (LogicalTimestamp::MAX) read as of this instead.
+    // Followers set it to just past the last batch applied from the leader as each statement
+    // starts, so they don't see the leader's writes as they're half way through being applied.
+    pub read_timestamp: AtomicU64,
+    // Bumped as each statement starts, for state that only lives as long as a statement (ie the
+    // memory of wasm functions).
+    pub statement_id: AtomicU64,
 }
 
 impl Session {
@@ -17,6 +61,358 @@ impl Session {
             current_database: RwLock::from(String::from("default")),
             connection_id,
             kill_flag: AtomicBool::from(false),
+            connection_killed: AtomicBool::from(false),
+            running_statement: RwLock::from(None),
+            rows_examined: AtomicU64::from(0),
+            rows_sent: AtomicU64::from(0),
+            affected_rows: AtomicU64::from(0),
+            last_insert_id: AtomicU64::from(0),
+            info: RwLock::from(String::new()),
+            variables: RwLock::from(default_variables()),
+            out_of_range: RwLock::from(None),
+            out_of_range_raised: AtomicBool::from(false),
+            warnings: RwLock::from(vec![]),
+            read_timestamp: AtomicU64::from(LogicalTimestamp::MAX.ms),
+            statement_id: AtomicU64::from(0),
+        }
+    }
+
+    /// Whether the session can manage users and see their password hashes. Connections from
+    /// the network always log in, so an empty user means an embedded connection (ie tests)
+    /// which gets the same privileges as root.
+    pub fn is_root(&self) -> bool {
+        let user = self.user.read().unwrap();
+        user.is_empty() || *user == "root"
+    }
+
+    /// Clears out the affected rows etc reported for the last statement, called as each statement
+    /// starts.
+    pub fn reset_statement_info(&self) {
+        self.affected_rows.store(0, Ordering::Relaxed);
+        self.last_insert_id.store(0, Ordering::Relaxed);
+        self.info.write().unwrap().clear();
+        self.kill_flag.store(false, Ordering::Relaxed);
+        *self.out_of_range.write().unwrap() = None;
+        self.out_of_range_raised.store(false, Ordering::Release);
+        self.warnings.write().unwrap().clear();
+        self.statement_id.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a function produced a value out of range for its type, only the first one
+    /// for a statement is kept.
+    pub fn raise_out_of_range(&self, message: String) {
+        let mut out_of_range = self.out_of_range.write().unwrap();
+        if out_of_range.is_none() {
+            *out_of_range = Some(message);
+            self.out_of_range_raised.store(true, Ordering::Release);
+        }
+    }
+
+    /// Takes the out of range error raised while evaluating the last rows if there was one.
+    pub fn take_out_of_range(&self) -> Option<String> {
+        if !self.out_of_range_raised.load(Ordering::Acquire) {
+            return None;
+        }
+        let mut out_of_range = self.out_of_range.write().unwrap();
+        self.out_of_range_raised.store(false, Ordering::Release);
+        out_of_range.take()
+    }
+
+    /// Records a warning for SHOW WARNINGS, only the first MAX_WARNINGS are kept.
+    pub fn raise_warning(&self, code: u16, message: String) {
+        let mut warnings = self.warnings.write().unwrap();
+        if warnings.len() < MAX_WARNINGS {
+            warnings.push((code, message));
+        }
+    }
+
+    /// The timestamp a scan planned to read at the given timestamp should actually read at.
+    pub fn scan_timestamp(&self, timestamp: LogicalTimestamp) -> LogicalTimestamp {
+        if timestamp == LogicalTimestamp::MAX {
+            LogicalTimestamp::new(self.read_timestamp.load(Ordering::Relaxed))
+        } else {
+            timestamp
+        }
+    }
+
+    /// Records the statement being run so it can be seen in SHOW PROCESSLIST.
+    pub fn statement_started(&self, statement: &str) {
+        self.rows_examined.store(0, Ordering::Relaxed);
+        self.rows_sent.store(0, Ordering::Relaxed);
+        *self.running_statement.write().unwrap() = Some((statement.to_string(), Instant::now()));
+    }
+
+    /// Called once the results of the running statement have been sent back to the client,
+    /// returns the statement and when it started.
+    pub fn statement_finished(&self) -> Option<(String, Instant)> {
+        self.running_statement.write().unwrap().take()
+    }
+
+    /// Looks up a system variable returning its value along with its type. Variables are only
+    /// ever set from constants so the type can be inferred from the datum itself.
+    pub fn variable(&self, name: &str) -> Option<(Datum<'static>, DataType)> {
+        self.variables.read().unwrap().get(name).map(|datum| {
+            let datatype = match datum {
+                Datum::Null => DataType::Null,
+                Datum::Boolean(_) => DataType::Boolean,
+                Datum::Integer(_) => DataType::Integer,
+                Datum::BigInt(_) => DataType::BigInt,
+                Datum::Decimal(d) => DataType::Decimal(DECIMAL_MAX_PRECISION, d.scale() as u8),
+                Datum::Double(_) => DataType::Double,
+                _ => DataType::Text,
+            };
+            (datum.clone(), datatype)
+        })
+    }
+
+    /// Whether the session's sql_mode includes one of mysql's strict modes, in which case values
+    /// that can't be converted by a CAST are errors rather than nulls.
+    pub fn strict_mode(&self) -> bool {
+        self.variables
+            .read()
+            .unwrap()
+            .get("sql_mode")
+            .and_then(|datum| datum.as_maybe_text())
+            .map(|sql_mode| {
+                sql_mode.split(',').any(|mode| {
+                    let mode = mode.trim();
+                    mode.eq_ignore_ascii_case("STRICT_TRANS_TABLES")
+                        || mode.eq_ignore_ascii_case("STRICT_ALL_TABLES")
+                        || mode.eq_ignore_ascii_case("TRADITIONAL")
+                })
+            })
+            .unwrap_or(false)
+    }
+
+    /// The collation text is compared with, from collation_connection. This defaults to
+    /// utf8mb4_general_ci so like mysql comparisons are case insensitive unless the client asks
+    /// for a binary collation.
+    pub fn collation(&self) -> Collation {
+        self.variables
+            .read()
+            .unwrap()
+            .get("collation_connection")
+            .and_then(|datum| datum.as_maybe_text())
+            .map_or(Collation::CaseInsensitive, Collation::from_name)
+    }
+
+    /// The offset of the session's time_zone from UTC. Timestamps are always stored in UTC, this
+    /// is used to convert timestamps coming from and going out to the client.
+    pub fn time_zone(&self) -> FixedOffset {
+        self.variables
+            .read()
+            .unwrap()
+            .get("time_zone")
+            .and_then(|datum| datum.as_maybe_text().and_then(parse_time_zone))
+            .unwrap_or_else(|| FixedOffset::east(0))
+    }
+
+    /// Converts a timestamp in the session's time zone to UTC
+    pub fn to_utc(&self, timestamp: NaiveDateTime) -> NaiveDateTime {
+        timestamp - self.time_zone()
+    }
+
+    /// Converts a UTC timestamp to the session's time zone
+    pub fn to_session_time(&self, timestamp: NaiveDateTime) -> NaiveDateTime {
+        timestamp + self.time_zone()
+    }
+}
+
+/// Parses a time zone as accepted by mysql's time_zone variable and CONVERT_TZ. We don't have
+/// the time zone tables so only offsets (ie +10:00) and UTC are supported, the server's system
+/// time zone is always UTC.
+pub fn parse_time_zone(time_zone: &str) -> Option<FixedOffset> {
+    if time_zone.eq_ignore_ascii_case("SYSTEM") || time_zone.eq_ignore_ascii_case("UTC") {
+        return Some(FixedOffset::east(0));
+    }
+    let sign = match time_zone.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let mut parts = time_zone[1..].splitn(2, ':');
+    let hours = parts.next()?;
+    let minutes = parts.next()?;
+    if hours.is_empty() || hours.len() > 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    let seconds = sign * (hours * 3600 + minutes * 60);
+    // The same range mysql allows
+    if minutes > 59 || seconds < -(13 * 3600 + 59 * 60) || seconds > 14 * 3600 {
+        return None;
+    }
+    Some(FixedOffset::east(seconds))
+}
+
+/// The initial values for the session's system variables, these are mostly canned values
+/// that drivers ask for when connecting.
+fn default_variables() -> HashMap<String, Datum<'static>> {
+    let text_variables = [
+        ("character_set_client", "utf8mb4"),
+        ("character_set_connection", "utf8mb4"),
+        ("character_set_database", "utf8mb4"),
+        ("character_set_results", "utf8mb4"),
+        ("character_set_server", "utf8mb4"),
+        ("collation_connection", "utf8mb4_general_ci"),
+        ("collation_database", "utf8mb4_general_ci"),
+        ("collation_server", "utf8mb4_general_ci"),
+        ("init_connect", ""),
+        ("license", ""),
+        ("query_cache_type", "OFF"),
+        ("socket", ""),
+        ("sql_mode", ""),
+        ("system_time_zone", "UTC"),
+        ("time_zone", "SYSTEM"),
+        ("transaction_isolation", "REPEATABLE-READ"),
+        ("tx_isolation", "REPEATABLE-READ"),
+        ("version", SERVER_VERSION),
+        ("version_comment", "incresql"),
+    ];
+    let integer_variables = [
+        ("auto_increment_increment", 1),
+        ("autocommit", 1),
+        ("interactive_timeout", 28800),
+        ("lower_case_table_names", 0),
+        ("max_allowed_packet", 0xffffff),
+        ("max_execution_time", 0),
+        ("net_buffer_length", 16384),
+        ("net_write_timeout", 600),
+        ("parallel_workers", 1),
+        ("performance_schema", 0),
+        ("query_cache_size", 0),
+        ("sort_buffer_size", 128 * 1024 * 1024),
+        ("transaction_read_only", 0),
+        ("tx_read_only", 0),
+        ("wait_timeout", 28800),
+    ];
+
+    text_variables
+        .iter()
+        .map(|(name, value)| (name.to_string(), Datum::from(*value)))
+        .chain(
+            integer_variables
+                .iter()
+                .map(|(name, value)| (name.to_string(), Datum::from(*value))),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable() {
+        let session = Session::new(1);
+        assert_eq!(
+            session.variable("version_comment"),
+            Some((Datum::from("incresql"), DataType::Text))
+        );
+        assert_eq!(
+            session.variable("autocommit"),
+            Some((Datum::from(1), DataType::Integer))
+        );
+        assert_eq!(session.variable("not_a_variable"), None);
+    }
+
+    #[test]
+    fn test_parse_time_zone() {
+        assert_eq!(parse_time_zone("SYSTEM"), Some(FixedOffset::east(0)));
+        assert_eq!(parse_time_zone("utc"), Some(FixedOffset::east(0)));
+        assert_eq!(parse_time_zone("+10:00"), Some(FixedOffset::east(36000)));
+        assert_eq!(parse_time_zone("-5:30"), Some(FixedOffset::west(19800)));
+        assert_eq!(parse_time_zone("+14:00"), Some(FixedOffset::east(50400)));
+        assert_eq!(parse_time_zone("+14:01"), None);
+        assert_eq!(parse_time_zone("+10:60"), None);
+        assert_eq!(parse_time_zone("+10"), None);
+        assert_eq!(parse_time_zone("Europe/London"), None);
+        assert_eq!(parse_time_zone(""), None);
+    }
+
+    #[test]
+    fn test_time_zone() {
+        let session = Session::new(1);
+        let timestamp = NaiveDateTime::from_timestamp(1_000_000_000, 0);
+        assert_eq!(session.time_zone(), FixedOffset::east(0));
+        assert_eq!(session.to_utc(timestamp), timestamp);
+
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("time_zone".to_string(), Datum::from("+10:00"));
+        assert_eq!(session.time_zone(), FixedOffset::east(36000));
+        assert_eq!(
+            session.to_utc(timestamp),
+            NaiveDateTime::from_timestamp(1_000_000_000 - 36000, 0)
+        );
+        assert_eq!(
+            session.to_session_time(session.to_utc(timestamp)),
+            timestamp
+        );
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let session = Session::new(1);
+        assert!(!session.strict_mode());
+
+        let set_sql_mode = |sql_mode: &'static str| {
+            session
+                .variables
+                .write()
+                .unwrap()
+                .insert("sql_mode".to_string(), Datum::from(sql_mode))
+        };
+        set_sql_mode("ONLY_FULL_GROUP_BY,strict_trans_tables");
+        assert!(session.strict_mode());
+        set_sql_mode("TRADITIONAL");
+        assert!(session.strict_mode());
+        set_sql_mode("ANSI");
+        assert!(!session.strict_mode());
+    }
+
+    #[test]
+    fn test_collation() {
+        let session = Session::new(1);
+        assert_eq!(session.collation(), Collation::CaseInsensitive);
+
+        session.variables.write().unwrap().insert(
+            "collation_connection".to_string(),
+            Datum::from("utf8mb4_bin"),
+        );
+        assert_eq!(session.collation(), Collation::Binary);
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let session = Session::new(1);
+        assert_eq!(session.take_out_of_range(), None);
+        session.raise_out_of_range("first".to_string());
+        session.raise_out_of_range("second".to_string());
+        assert_eq!(session.take_out_of_range(), Some("first".to_string()));
+        assert_eq!(session.take_out_of_range(), None);
+
+        session.raise_out_of_range("third".to_string());
+        session.reset_statement_info();
+        assert_eq!(session.take_out_of_range(), None);
+        session.raise_out_of_range("fourth".to_string());
+        assert_eq!(session.take_out_of_range(), Some("fourth".to_string()));
+    }
+
+    #[test]
+    fn test_warnings() {
+        let session = Session::new(1);
+        for idx in 0..MAX_WARNINGS + 1 {
+            session.raise_warning(1292, idx.to_string());
         }
+        let warnings = session.warnings.read().unwrap().clone();
+        assert_eq!(warnings.len(), MAX_WARNINGS);
+        assert_eq!(warnings[0], (1292, "0".to_string()));
+
+        session.reset_statement_info();
+        assert!(session.warnings.read().unwrap().is_empty());
     }
 }
@@ -0,0 +1,412 @@
+use crate::{Datum, SortOrder};
+use rust_decimal::prelude::ToPrimitive;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The number of buckets in the equi-depth histograms.
+pub const HISTOGRAM_BUCKETS: usize = 32;
+/// The max number of values sampled from a column to build its histogram.
+const HISTOGRAM_SAMPLE_SIZE: usize = 10_000;
+/// The number of bits of the hash used to pick the hyperloglog register.
+const HLL_PRECISION: u32 = 12;
+const HLL_REGISTERS: usize = 1 << HLL_PRECISION;
+
+/// Statistics about the values of a column, as collected by ANALYZE. Used for estimating the
+/// selectivity of predicates.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ColumnStatistics {
+    pub row_count: i64,
+    pub null_count: i64,
+    /// Estimated number of distinct (non null) values
+    pub ndv: i64,
+    /// The bounds of an equi-depth histogram over the non null values, the first entry is the
+    /// min value and each following entry is the upper bound of a bucket. Each bucket holds
+    /// (roughly) the same number of rows. The buckets are built from a sample but the first and
+    /// last bounds are always the column's actual min and max.
+    pub histogram: Vec<Datum<'static>>,
+    /// The distinct count sketch, kept around so that stats can be merged in the future.
+    pub sketch: HyperLogLog,
+}
+
+impl ColumnStatistics {
+    /// The smallest non null value in the column, None if they're all null.
+    pub fn min(&self) -> Option<&Datum<'static>> {
+        self.histogram.first()
+    }
+
+    /// The largest non null value in the column, None if they're all null.
+    pub fn max(&self) -> Option<&Datum<'static>> {
+        self.histogram.last()
+    }
+
+    /// The fraction of rows that are null.
+    pub fn null_fraction(&self) -> f64 {
+        if self.row_count <= 0 {
+            0.0
+        } else {
+            self.null_count as f64 / self.row_count as f64
+        }
+    }
+
+    /// The fraction of rows that are equal to any given (non null) value.
+    pub fn eq_selectivity(&self) -> f64 {
+        if self.ndv <= 0 {
+            0.0
+        } else {
+            (1.0 - self.null_fraction()) / self.ndv as f64
+        }
+    }
+
+    /// The fraction of rows that are less than (or equal to if inclusive) the value.
+    pub fn less_than_selectivity(&self, value: &Datum, inclusive: bool) -> f64 {
+        let less_or_equal = self.less_or_equal_selectivity(value);
+        if inclusive {
+            less_or_equal
+        } else {
+            (less_or_equal - self.eq_selectivity()).max(0.0)
+        }
+    }
+
+    fn less_or_equal_selectivity(&self, value: &Datum) -> f64 {
+        let non_null = 1.0 - self.null_fraction();
+        let (min, bounds) = if let Some((min, bounds)) = self.histogram.split_first() {
+            (min, bounds)
+        } else {
+            return 0.0;
+        };
+
+        if compare(value, min) == Ordering::Less {
+            return 0.0;
+        }
+
+        // Find the first bucket whose upper bound is past the value
+        let buckets = bounds.len();
+        let bucket = bounds
+            .iter()
+            .position(|bound| compare(value, bound) == Ordering::Less);
+        let bucket = if let Some(bucket) = bucket {
+            bucket
+        } else {
+            return non_null;
+        };
+
+        // Interpolate within the bucket if we can, otherwise assume half way.
+        let lower = if bucket == 0 {
+            min
+        } else {
+            &bounds[bucket - 1]
+        };
+        let upper = &bounds[bucket];
+        let within = match (
+            numeric_value(lower),
+            numeric_value(upper),
+            numeric_value(value),
+        ) {
+            (Some(lower), Some(upper), Some(value)) if upper > lower => {
+                ((value - lower) / (upper - lower)).max(0.0).min(1.0)
+            }
+            _ => 0.5,
+        };
+
+        non_null * (bucket as f64 + within) / buckets as f64
+    }
+
+    /// Encodes the histogram bounds so they can be stored.
+    pub fn histogram_bytes(&self) -> Vec<u8> {
+        let mut buffer = vec![];
+        for bound in &self.histogram {
+            bound.as_sortable_bytes(SortOrder::Asc, &mut buffer);
+        }
+        buffer
+    }
+
+    /// Decodes histogram bounds previously encoded with histogram_bytes.
+    pub fn histogram_from_bytes(mut buffer: &[u8]) -> Vec<Datum<'static>> {
+        let mut histogram = vec![];
+        while !buffer.is_empty() {
+            let mut datum = Datum::Null;
+            buffer = datum.from_sortable_bytes(buffer);
+            histogram.push(datum.as_static());
+        }
+        histogram
+    }
+}
+
+/// Compares two datums, numbers of different types are compared by value so that
+/// predicates can be estimated without the constants needing to be cast to the column's type.
+fn compare(a: &Datum, b: &Datum) -> Ordering {
+    match (numeric_value(a), numeric_value(b)) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+fn numeric_value(datum: &Datum) -> Option<f64> {
+    match datum {
+        Datum::Integer(i) => Some(*i as f64),
+        Datum::BigInt(i) => Some(*i as f64),
+        Datum::Decimal(d) => d.to_f64(),
+        Datum::Double(d) => Some(*d),
+        _ => None,
+    }
+}
+
+/// Builds up the statistics for a column by feeding it all the values from the column.
+pub struct ColumnStatisticsBuilder {
+    row_count: i64,
+    null_count: i64,
+    sketch: HyperLogLog,
+    // Reservoir sample of the non null values
+    sample: Vec<Datum<'static>>,
+    non_null_count: u64,
+    rng_state: u64,
+    // The exact bounds, the sample may well have missed them
+    min: Option<Datum<'static>>,
+    max: Option<Datum<'static>>,
+}
+
+impl Default for ColumnStatisticsBuilder {
+    fn default() -> Self {
+        ColumnStatisticsBuilder {
+            row_count: 0,
+            null_count: 0,
+            sketch: HyperLogLog::default(),
+            sample: vec![],
+            non_null_count: 0,
+            rng_state: 0x2545_F491_4F6C_DD1D,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl ColumnStatisticsBuilder {
+    /// Adds a value from the column, freq being the number of rows with that value.
+    pub fn add(&mut self, datum: &Datum, freq: i64) {
+        if freq <= 0 {
+            return;
+        }
+        self.row_count += freq;
+        if datum.is_null() {
+            self.null_count += freq;
+            return;
+        }
+
+        self.sketch.add(datum);
+        if self.min.as_ref().map_or(true, |min| datum < min) {
+            self.min = Some(datum.as_static());
+        }
+        if self.max.as_ref().map_or(true, |max| datum > max) {
+            self.max = Some(datum.as_static());
+        }
+        for _ in 0..freq {
+            self.non_null_count += 1;
+            if self.sample.len() < HISTOGRAM_SAMPLE_SIZE {
+                self.sample.push(datum.as_static());
+            } else {
+                let idx = (self.next_random() % self.non_null_count) as usize;
+                if idx < HISTOGRAM_SAMPLE_SIZE {
+                    self.sample[idx] = datum.as_static();
+                }
+            }
+        }
+    }
+
+    pub fn build(mut self) -> ColumnStatistics {
+        self.sample.sort();
+        let buckets = HISTOGRAM_BUCKETS.min(self.sample.len());
+        let mut histogram = Vec::with_capacity(buckets + 1);
+        if let Some(min) = self.sample.first() {
+            histogram.push(min.clone());
+            for bucket in 1..=buckets {
+                let idx = bucket * self.sample.len() / buckets - 1;
+                histogram.push(self.sample[idx].clone());
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            histogram[0] = min;
+            histogram[buckets] = max;
+        }
+
+        ColumnStatistics {
+            row_count: self.row_count,
+            null_count: self.null_count,
+            ndv: self.sketch.estimate(),
+            histogram,
+            sketch: self.sketch,
+        }
+    }
+
+    /// xorshift, we don't need anything fancy for sampling.
+    fn next_random(&mut self) -> u64 {
+        self.rng_state ^= self.rng_state << 13;
+        self.rng_state ^= self.rng_state >> 7;
+        self.rng_state ^= self.rng_state << 17;
+        self.rng_state
+    }
+}
+
+/// A HyperLogLog sketch for estimating the number of distinct values.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        HyperLogLog {
+            registers: vec![0; HLL_REGISTERS],
+        }
+    }
+}
+
+impl HyperLogLog {
+    pub fn add(&mut self, datum: &Datum) {
+        let mut hasher = DefaultHasher::new();
+        datum.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let register = (hash >> (64 - HLL_PRECISION)) as usize;
+        // The or'd in bit caps the rank in case the rest of the hash is all zeros
+        let rest = (hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        if rank > self.registers[register] {
+            self.registers[register] = rank;
+        }
+    }
+
+    /// Merges in another sketch, the result estimates the distinct count of the union.
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    pub fn estimate(&self) -> i64 {
+        let m = HLL_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|register| 2_f64.powi(-(*register as i32)))
+            .sum();
+        let estimate = alpha * m * m / sum;
+
+        let zeros = self.registers.len() - self.registers.iter().filter(|r| **r != 0).count();
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Small range correction, aka linear counting
+            (m * (m / zeros as f64).ln()).round() as i64
+        } else {
+            estimate.round() as i64
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.registers
+    }
+
+    /// Recreates a sketch from the bytes returned by as_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), HLL_REGISTERS, "Bad hyperloglog sketch");
+        HyperLogLog {
+            registers: bytes.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(values: impl Iterator<Item = Datum<'static>>) -> ColumnStatistics {
+        let mut builder = ColumnStatisticsBuilder::default();
+        for value in values {
+            builder.add(&value, 1);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_hyperloglog() {
+        let mut hll = HyperLogLog::default();
+        assert_eq!(hll.estimate(), 0);
+        for i in 0..10_000 {
+            hll.add(&Datum::from(i % 1000));
+        }
+        let estimate = hll.estimate();
+        assert!((950..=1050).contains(&estimate), "{}", estimate);
+
+        let mut other = HyperLogLog::default();
+        for i in 500..1500 {
+            other.add(&Datum::from(i));
+        }
+        hll.merge(&other);
+        let estimate = hll.estimate();
+        assert!((1425..=1575).contains(&estimate), "{}", estimate);
+
+        assert_eq!(HyperLogLog::from_bytes(hll.as_bytes()), hll);
+    }
+
+    #[test]
+    fn test_column_statistics() {
+        let stats = build(
+            (0..1000)
+                .map(Datum::from)
+                .chain((0..250).map(|_| Datum::Null)),
+        );
+        assert_eq!(stats.row_count, 1250);
+        assert_eq!(stats.null_count, 250);
+        assert!((950..=1050).contains(&stats.ndv));
+        assert_eq!(stats.histogram.len(), HISTOGRAM_BUCKETS + 1);
+        assert_eq!(stats.histogram[0], Datum::from(0));
+        assert_eq!(stats.histogram[HISTOGRAM_BUCKETS], Datum::from(999));
+        assert_eq!(stats.min(), Some(&Datum::from(0)));
+        assert_eq!(stats.max(), Some(&Datum::from(999)));
+
+        assert!((stats.null_fraction() - 0.2).abs() < 0.001);
+        assert!((stats.eq_selectivity() - 0.0008).abs() < 0.0001);
+
+        let lt =
+            |value: i32, inclusive| stats.less_than_selectivity(&Datum::from(value), inclusive);
+        assert!(lt(-1, true).abs() < 1e-9);
+        assert!(lt(0, false).abs() < 1e-9);
+        assert!((lt(500, false) - 0.4).abs() < 0.01);
+        assert!((lt(250, true) - 0.2).abs() < 0.01);
+        assert!((lt(2000, false) - 0.8).abs() < 0.001);
+        // Numbers of other types are compared by value
+        assert!((stats.less_than_selectivity(&Datum::from(500_i64), false) - 0.4).abs() < 0.01);
+
+        assert_eq!(
+            ColumnStatistics::histogram_from_bytes(&stats.histogram_bytes()),
+            stats.histogram
+        );
+    }
+
+    #[test]
+    fn test_column_statistics_exact_bounds() {
+        // Far more values than get sampled, the bounds should still be exact
+        let stats = build((1..100_000).map(|i| Datum::from((i * 7919) % 100_000)));
+        assert_eq!(stats.min(), Some(&Datum::from(1)));
+        assert_eq!(stats.max(), Some(&Datum::from(99_999)));
+    }
+
+    #[test]
+    fn test_column_statistics_text_and_empty() {
+        let stats = build(
+            ["a", "b", "b", "c"]
+                .iter()
+                .map(|s| Datum::from(s.to_string())),
+        );
+        assert_eq!(stats.histogram.len(), 5);
+        // No interpolation for text, so we assume half way through the bucket
+        assert!((stats.less_than_selectivity(&Datum::from("a"), true) - 0.375).abs() < 0.001);
+        assert!((stats.less_than_selectivity(&Datum::from("d"), true) - 1.0).abs() < 1e-9);
+
+        let stats = build(std::iter::empty());
+        assert_eq!(stats.row_count, 0);
+        assert_eq!(stats.min(), None);
+        assert!(stats.eq_selectivity().abs() < 1e-9);
+        assert!(stats.less_than_selectivity(&Datum::from(1), true).abs() < 1e-9);
+    }
+}
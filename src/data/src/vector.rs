@@ -0,0 +1,110 @@
+use crate::Datum;
+use std::convert::TryInto;
+use std::fmt::{Display, Formatter};
+
+/// The largest number of dimensions a vector column can have
+pub const VECTOR_MAX_DIMENSIONS: u16 = 16384;
+
+/// A fixed dimension vector of floats, ie for storing embeddings.
+/// Vectors are stored in the same bytea datums as text/json with each element encoded as a
+/// little endian f32, so a vector takes up 4 bytes per dimension with no extra overhead.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Vector<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Vector<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        Vector { bytes }
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.bytes.len() / 4
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + 'a {
+        self.bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+    }
+
+    /// Parses the text representation of a vector, ie "[1, 2.5, -3]"
+    pub fn parse(text: &str) -> Option<Vec<f32>> {
+        let text = text.trim();
+        if !text.starts_with('[') || !text.ends_with(']') {
+            return None;
+        }
+        let inner = text[1..text.len() - 1].trim();
+        if inner.is_empty() {
+            return Some(vec![]);
+        }
+        inner
+            .split(',')
+            .map(|element| element.trim().parse::<f32>().ok().filter(|f| f.is_finite()))
+            .collect()
+    }
+}
+
+impl Display for Vector<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[")?;
+        for (idx, element) in self.iter().enumerate() {
+            if idx != 0 {
+                f.write_str(",")?;
+            }
+            Display::fmt(&element, f)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl From<&[f32]> for Datum<'static> {
+    fn from(elements: &[f32]) -> Self {
+        let mut bytes = Vec::with_capacity(elements.len() * 4);
+        for element in elements {
+            bytes.extend_from_slice(&element.to_le_bytes());
+        }
+        Datum::from(bytes)
+    }
+}
+
+impl<'a> Datum<'a> {
+    pub fn as_maybe_vector(&'a self) -> Option<Vector<'a>> {
+        self.as_maybe_bytea().map(Vector::from_bytes)
+    }
+
+    #[track_caller]
+    pub fn as_vector(&'a self) -> Vector<'a> {
+        self.as_maybe_vector().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataType;
+
+    #[test]
+    fn test_vector_round_trip() {
+        let elements = Vector::parse(" [1, 2.5,-3 ] ").unwrap();
+        assert_eq!(elements, vec![1.0, 2.5, -3.0]);
+
+        let datum = Datum::from(elements.as_slice());
+        let vector = datum.as_vector();
+        assert_eq!(vector.dimensions(), 3);
+        assert_eq!(vector.iter().collect::<Vec<_>>(), elements);
+        assert_eq!(
+            datum.typed_with(DataType::Vector(3)).to_string(),
+            "[1,2.5,-3]"
+        );
+    }
+
+    #[test]
+    fn test_vector_parse_invalid() {
+        assert_eq!(Vector::parse("[]"), Some(vec![]));
+        assert_eq!(Vector::parse("1, 2"), None);
+        assert_eq!(Vector::parse("[1, abc]"), None);
+        assert_eq!(Vector::parse("[1,,2]"), None);
+        assert_eq!(Vector::parse("[NaN]"), None);
+    }
+}
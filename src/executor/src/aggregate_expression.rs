@@ -1,6 +1,7 @@
 use crate::scalar_expression::EvalScalarRow;
 use crate::utils::{right_size_new, right_size_new_to};
 use ast::expr::{CompiledAggregate, CompiledColumnReference, Expression};
+use ast::rel::point_in_time::AggregateMode;
 use data::{DataType, Datum, Session};
 use functions::{Function, FunctionSignature};
 
@@ -114,6 +115,30 @@ impl AggregateExpression {
         }
     }
 
+    /// Merges a state produced by a partial aggregation into our state
+    pub fn merge(&self, input_state: &[Datum<'static>], state: &mut [Datum<'static>]) {
+        match self {
+            AggregateExpression::ScalarFunctionCall(funct) => {
+                let mut offset = 0_usize;
+                for arg in funct.args.iter() {
+                    arg.merge(&input_state[offset..], &mut state[offset..]);
+                    offset += arg.state_len();
+                }
+            }
+            AggregateExpression::CompiledAggregate(function_call) => {
+                function_call
+                    .function
+                    .merge(&function_call.signature, input_state, state)
+            }
+            AggregateExpression::ColumnReference(_) => {
+                if state[0].is_null() {
+                    state[0] = input_state[0].clone()
+                }
+            }
+            AggregateExpression::Constant(_, _) => {}
+        }
+    }
+
     /// Returns the output of the expression at the current time
     pub fn finalize<'a>(&'a mut self, session: &Session, state: &'a [Datum<'a>]) -> Datum<'a> {
         match self {
@@ -195,7 +220,10 @@ impl From<&Expression> for AggregateExpression {
                 AggregateExpression::ColumnReference(column_ref.clone())
             }
 
-            Expression::FunctionCall(_) | Expression::ColumnReference(_) | Expression::Cast(_) => {
+            Expression::FunctionCall(_)
+            | Expression::ColumnReference(_)
+            | Expression::Cast(_)
+            | Expression::Subquery(_) => {
                 panic!("Hit uncompiled expressions when converting to aggregation")
             }
         }
@@ -214,6 +242,7 @@ pub trait EvalAggregateRow {
     fn state_len(&self) -> usize;
     fn reset(&self, state: &mut [Datum<'static>]);
     fn apply(&mut self, session: &Session, row: &[Datum], freq: i64, state: &mut [Datum<'static>]);
+    fn merge(&self, input_state: &[Datum<'static>], state: &mut [Datum<'static>]);
     fn finalize<'a>(
         &'a mut self,
         session: &Session,
@@ -245,6 +274,14 @@ impl EvalAggregateRow for [AggregateExpression] {
         }
     }
 
+    fn merge(&self, input_state: &[Datum<'static>], state: &mut [Datum<'static>]) {
+        let mut offset = 0_usize;
+        for expr in self.iter() {
+            expr.merge(&input_state[offset..], &mut state[offset..]);
+            offset += expr.state_len();
+        }
+    }
+
     fn finalize<'a>(
         &'a mut self,
         session: &Session,
@@ -260,6 +297,28 @@ impl EvalAggregateRow for [AggregateExpression] {
     }
 }
 
+/// Folds a source tuple into the aggregation state, for final aggregations the tuple is made up
+/// of the grouping keys followed by a state output by a partial aggregation.
+pub(crate) fn fold_tuple(
+    expressions: &mut [AggregateExpression],
+    session: &Session,
+    mode: AggregateMode,
+    key_len: usize,
+    tuple: &[Datum],
+    freq: i64,
+    state: &mut [Datum<'static>],
+) {
+    if mode == AggregateMode::Final {
+        let input_state: Vec<_> = tuple[key_len..]
+            .iter()
+            .map(|datum| datum.as_static())
+            .collect();
+        expressions.merge(&input_state, state);
+    } else {
+        expressions.apply(session, tuple, freq, state);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,6 +374,7 @@ mod tests {
                 },
             )]
             .into_boxed_slice(),
+            distinct: false,
             expr_buffer: vec![].into_boxed_slice(),
             signature: Box::new(sig),
         });
@@ -332,6 +392,46 @@ mod tests {
         assert_eq!(result, Datum::from(7));
     }
 
+    #[test]
+    fn test_merge_aggregate() {
+        use data::rust_decimal::Decimal;
+        let signature = FunctionSignature {
+            name: "avg",
+            args: vec![DataType::Integer],
+            ret: DataType::Null,
+        };
+        let (sig, function) = Registry::default().resolve_function(&signature).unwrap();
+        let expression = Expression::CompiledAggregate(CompiledAggregate {
+            function: function.as_aggregate(),
+            args: vec![Expression::CompiledColumnReference(
+                CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                },
+            )]
+            .into_boxed_slice(),
+            distinct: false,
+            expr_buffer: vec![].into_boxed_slice(),
+            signature: Box::new(sig),
+        });
+        let session = Session::new(1);
+
+        let mut agg_expression = AggregateExpression::from(&expression);
+
+        let mut partial_state = right_size_new_to(agg_expression.state_len());
+        agg_expression.reset(&mut partial_state);
+        agg_expression.apply(&session, &[Datum::from(1)], 1, &mut partial_state);
+
+        let mut state = right_size_new_to(agg_expression.state_len());
+        agg_expression.reset(&mut state);
+        agg_expression.apply(&session, &[Datum::from(4)], 2, &mut state);
+        agg_expression.merge(&partial_state, &mut state);
+
+        let result = agg_expression.finalize(&session, &state);
+
+        assert_eq!(result, Datum::from(Decimal::new(3, 0)));
+    }
+
     #[test]
     fn test_eval_scalar_function() {
         let signature = FunctionSignature {
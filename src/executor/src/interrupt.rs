@@ -0,0 +1,116 @@
+use crate::ExecutionError;
+use data::{Datum, Session};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The session variable holding the number of milliseconds a statement can run for before it's
+/// interrupted, 0 for no limit.
+pub const MAX_EXECUTION_TIME_VARIABLE: &str = "max_execution_time";
+
+/// The number of rows processed between checks, checking the clock for every row would be a
+/// waste.
+const CHECK_INTERVAL: usize = 1024;
+
+/// Used by the long running executors to bail out once the statement has been killed or has
+/// run past the session's max_execution_time.
+pub(crate) struct InterruptCheck {
+    session: Arc<Session>,
+    deadline: Option<Instant>,
+    rows: usize,
+}
+
+impl InterruptCheck {
+    pub fn new(session: Arc<Session>) -> Self {
+        let max_execution_time = match session.variable(MAX_EXECUTION_TIME_VARIABLE) {
+            Some((Datum::Integer(millis), _)) => millis as i64,
+            Some((Datum::BigInt(millis), _)) => millis,
+            _ => 0,
+        };
+        // Statements run outside of a client command (ie view maintenance) have no start time
+        // recorded and so never time out.
+        let started = session
+            .running_statement
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|(_, started)| *started);
+        let deadline = match started {
+            Some(started) if max_execution_time > 0 => {
+                Some(started + Duration::from_millis(max_execution_time as u64))
+            }
+            _ => None,
+        };
+        InterruptCheck {
+            session,
+            deadline,
+            rows: 0,
+        }
+    }
+
+    /// Called for each row, every so often checks whether the statement should be interrupted.
+    pub fn tick(&mut self) -> Result<(), ExecutionError> {
+        self.rows += 1;
+        if self.rows % CHECK_INTERVAL == 0 {
+            self.check()
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn check(&self) -> Result<(), ExecutionError> {
+        if self.session.kill_flag.load(Ordering::Relaxed) {
+            Err(ExecutionError::Interrupted)
+        } else if matches!(self.deadline, Some(deadline) if Instant::now() >= deadline) {
+            Err(ExecutionError::TimedOut)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupt_check_kill_flag() {
+        let session = Arc::new(Session::new(1));
+        let mut check = InterruptCheck::new(Arc::clone(&session));
+        for _ in 0..CHECK_INTERVAL * 2 {
+            assert_eq!(check.tick(), Ok(()));
+        }
+
+        session.kill_flag.store(true, Ordering::Relaxed);
+        // Only every CHECK_INTERVAL rows actually look at the flag
+        for _ in 0..CHECK_INTERVAL - 1 {
+            assert_eq!(check.tick(), Ok(()));
+        }
+        assert_eq!(check.tick(), Err(ExecutionError::Interrupted));
+    }
+
+    #[test]
+    fn test_interrupt_check_timeout() {
+        let session = Arc::new(Session::new(1));
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert(MAX_EXECUTION_TIME_VARIABLE.to_string(), Datum::from(10));
+
+        // No statement running, no timeout
+        let check = InterruptCheck::new(Arc::clone(&session));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(check.check(), Ok(()));
+
+        session.statement_started("SELECT 1");
+        let check = InterruptCheck::new(Arc::clone(&session));
+        assert_eq!(check.check(), Ok(()));
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(check.check(), Err(ExecutionError::TimedOut));
+    }
+}
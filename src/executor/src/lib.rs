@@ -3,6 +3,7 @@ use std::fmt::{Display, Formatter};
 use storage::StorageError;
 
 mod aggregate_expression;
+mod interrupt;
 pub mod point_in_time;
 mod scalar_expression;
 mod utils;
@@ -12,16 +13,58 @@ pub enum ExecutionError {
     StorageError(StorageError),
     IOError(String),
     DecodingError(String),
+    NoPartitionForValue(String),
+    // Writing a null to a NOT NULL column, the column name
+    NullNotAllowed(String),
+    // The statement was killed
+    Interrupted,
+    // The statement ran past the session's max_execution_time
+    TimedOut,
+    // A function's result didn't fit its type, ie an integer overflow
+    OutOfRange(String),
 }
 
 impl Error for ExecutionError {}
 
+impl ExecutionError {
+    /// Write conflicts are transient, the statement can be retried.
+    pub fn is_write_conflict(&self) -> bool {
+        matches!(
+            self,
+            ExecutionError::StorageError(StorageError::WriteConflict(_))
+        )
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self, ExecutionError::Interrupted)
+    }
+
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, ExecutionError::TimedOut)
+    }
+
+    pub fn is_out_of_range(&self) -> bool {
+        matches!(self, ExecutionError::OutOfRange(_))
+    }
+}
+
 impl Display for ExecutionError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ExecutionError::StorageError(err) => Display::fmt(err, f),
             ExecutionError::IOError(err) => f.write_str(err),
             ExecutionError::DecodingError(err) => f.write_str(err),
+            ExecutionError::NoPartitionForValue(value) => {
+                f.write_fmt(format_args!("Table has no partition for value {}", value))
+            }
+            ExecutionError::NullNotAllowed(column) => {
+                f.write_fmt(format_args!("Column '{}' cannot be null", column))
+            }
+            ExecutionError::Interrupted => f.write_str("Query execution was interrupted"),
+            ExecutionError::TimedOut => f.write_str(
+                "Query execution was interrupted, maximum statement execution time exceeded",
+            ),
+            ExecutionError::OutOfRange(message) => f.write_str(message),
         }
     }
 }
@@ -0,0 +1,263 @@
+use crate::point_in_time::BoxedExecutor;
+use crate::ExecutionError;
+use data::{Datum, TupleIter};
+use std::ops::Range;
+
+/// The number of rows operators work on at a time in batch mode
+pub const BATCH_SIZE: usize = 1024;
+
+pub type BoxedBatchExecutor = Box<dyn BatchIter>;
+
+/// The batch equivalent of a TupleIter, rather than paying for a virtual call per row per
+/// operator, operators hand each other up to BATCH_SIZE rows at a time.
+pub trait BatchIter {
+    /// Fills the batch with the next lot of rows, returns false once the source is exhausted.
+    /// The batch is handed in by the consumer so the buffers can be reused from batch to batch.
+    fn next_batch(&mut self, batch: &mut Batch) -> Result<bool, ExecutionError>;
+
+    /// Returns the count of columns in each row. Used to help size buffers etc
+    fn column_count(&self) -> usize;
+}
+
+/// A chunk of rows, the rows are laid out one after another in a single buffer so each row can
+/// still be passed as a slice to the scalar expressions. All the datums are owned so the rows
+/// remain valid no matter what the source gets up to afterwards.
+#[derive(Debug, Default)]
+pub struct Batch {
+    column_count: usize,
+    data: Vec<Datum<'static>>,
+    freqs: Vec<i64>,
+}
+
+impl Batch {
+    pub fn new(column_count: usize) -> Self {
+        Batch {
+            column_count,
+            data: Vec::with_capacity(column_count * BATCH_SIZE),
+            freqs: Vec::with_capacity(BATCH_SIZE),
+        }
+    }
+
+    /// The number of rows in the batch
+    pub fn len(&self) -> usize {
+        self.freqs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.freqs.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.freqs.len() >= BATCH_SIZE
+    }
+
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.freqs.clear();
+    }
+
+    pub fn row(&self, idx: usize) -> (&[Datum], i64) {
+        (&self.data[self.row_range(idx)], self.freqs[idx])
+    }
+
+    /// Appends a row, taking ownership of any data the datums point to.
+    pub fn push_row<'a>(&mut self, tuple: impl IntoIterator<Item = Datum<'a>>, freq: i64) {
+        self.data
+            .extend(tuple.into_iter().map(|datum| datum.into_static()));
+        self.freqs.push(freq);
+    }
+
+    /// Keeps only the rows matching the predicate, the rows are shuffled down in place.
+    pub fn retain(&mut self, mut predicate: impl FnMut(&[Datum]) -> bool) {
+        let mut kept = 0;
+        for idx in 0..self.len() {
+            if predicate(&self.data[self.row_range(idx)]) {
+                if kept != idx {
+                    for column in 0..self.column_count {
+                        self.data.swap(
+                            kept * self.column_count + column,
+                            idx * self.column_count + column,
+                        );
+                    }
+                    self.freqs.swap(kept, idx);
+                }
+                kept += 1;
+            }
+        }
+        self.data.truncate(kept * self.column_count);
+        self.freqs.truncate(kept);
+    }
+
+    fn row_range(&self, idx: usize) -> Range<usize> {
+        (idx * self.column_count)..((idx + 1) * self.column_count)
+    }
+}
+
+/// Fills batches from a (tuple at a time) scan, used as the leaf of batched plans.
+pub struct ScanBatchExecutor {
+    source: BoxedExecutor,
+}
+
+impl ScanBatchExecutor {
+    pub fn new(source: BoxedExecutor) -> Self {
+        ScanBatchExecutor { source }
+    }
+}
+
+impl BatchIter for ScanBatchExecutor {
+    fn next_batch(&mut self, batch: &mut Batch) -> Result<bool, ExecutionError> {
+        batch.clear();
+        while !batch.is_full() {
+            if let Some((tuple, freq)) = self.source.next()? {
+                batch.push_row(tuple.iter().map(Datum::as_static), freq);
+            } else {
+                break;
+            }
+        }
+        Ok(!batch.is_empty())
+    }
+
+    fn column_count(&self) -> usize {
+        self.source.column_count()
+    }
+}
+
+/// Serves up the rows of a batched plan a tuple at a time for operators that don't know about
+/// batches.
+pub struct UnbatchExecutor {
+    source: BoxedBatchExecutor,
+    batch: Batch,
+    // The current row within the batch, None before the first advance
+    idx: Option<usize>,
+    done: bool,
+}
+
+impl UnbatchExecutor {
+    pub fn new(source: BoxedBatchExecutor) -> Self {
+        let batch = Batch::new(source.column_count());
+        UnbatchExecutor {
+            source,
+            batch,
+            idx: None,
+            done: false,
+        }
+    }
+}
+
+impl TupleIter for UnbatchExecutor {
+    type E = ExecutionError;
+
+    fn advance(&mut self) -> Result<(), ExecutionError> {
+        let mut next = self.idx.map_or(0, |idx| idx + 1);
+        while next >= self.batch.len() && !self.done {
+            if self.source.next_batch(&mut self.batch)? {
+                next = 0;
+            } else {
+                // Free up our memory here.
+                self.batch = Batch::default();
+                self.done = true;
+            }
+        }
+        self.idx = Some(next);
+        Ok(())
+    }
+
+    fn get(&self) -> Option<(&[Datum], i64)> {
+        match (self.done, self.idx) {
+            (false, Some(idx)) => Some(self.batch.row(idx)),
+            _ => None,
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        self.source.column_count()
+    }
+}
+
+/// The source for an operator that can consume either a tuple or batch at a time.
+pub enum Input {
+    Tuples(BoxedExecutor),
+    Batches(BoxedBatchExecutor, Batch),
+}
+
+impl Input {
+    pub fn batches(source: BoxedBatchExecutor) -> Self {
+        let batch = Batch::new(source.column_count());
+        Input::Batches(source, batch)
+    }
+
+    /// Calls the function for every row of the source, stopping at the first error.
+    pub fn for_each(
+        &mut self,
+        mut f: impl FnMut(&[Datum], i64) -> Result<(), ExecutionError>,
+    ) -> Result<(), ExecutionError> {
+        match self {
+            Input::Tuples(source) => {
+                while let Some((tuple, freq)) = source.next()? {
+                    f(tuple, freq)?;
+                }
+            }
+            Input::Batches(source, batch) => {
+                while source.next_batch(batch)? {
+                    for idx in 0..batch.len() {
+                        let (tuple, freq) = batch.row(idx);
+                        f(tuple, freq)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_in_time::values::ValuesExecutor;
+
+    #[test]
+    fn test_batches() -> Result<(), ExecutionError> {
+        let values: Vec<_> = (0..2500)
+            .map(|i| vec![Datum::from(i), Datum::from(format!("row {}", i))])
+            .collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+        let mut scan = ScanBatchExecutor::new(source);
+
+        let mut batch = Batch::new(2);
+        let mut sizes = vec![];
+        while scan.next_batch(&mut batch)? {
+            let unfiltered = batch.len();
+            batch.retain(|tuple| tuple[0].as_integer() % 2 == 0);
+            sizes.push((unfiltered, batch.len()));
+            // The rows should have been shuffled down to fill in the gaps
+            let first = batch.row(0).0[0].as_integer();
+            assert_eq!(
+                batch.row(1),
+                (
+                    [
+                        Datum::from(first + 2),
+                        Datum::from(format!("row {}", first + 2))
+                    ]
+                    .as_ref(),
+                    1
+                )
+            );
+        }
+        assert_eq!(sizes, vec![(1024, 512), (1024, 512), (452, 226)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unbatch_executor() -> Result<(), ExecutionError> {
+        let values: Vec<_> = (0..2000).map(|i| vec![Datum::from(i)]).collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+        let mut executor = UnbatchExecutor::new(Box::from(ScanBatchExecutor::new(source)));
+
+        for i in 0..2000 {
+            assert_eq!(executor.next()?, Some(([Datum::from(i)].as_ref(), 1)));
+        }
+        assert_eq!(executor.next()?, None);
+        assert_eq!(executor.next()?, None);
+        Ok(())
+    }
+}
@@ -1,36 +1,95 @@
+use crate::scalar_expression::EvalScalarRow;
+use crate::utils::{check_out_of_range, right_size_new, transmute_buf, transmute_muf_buf};
 use crate::ExecutionError;
+use ast::expr::Expression;
 use ast::rel::logical::SerdeOptions;
+use csv::StringRecord;
 use data::json::{JsonBuilder, OwnedJson};
-use data::{Datum, TupleIter};
+use data::{Datum, Session, TupleIter};
+use flate2::read::MultiGzDecoder;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
 use std::iter::{empty, once};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
-/// Walks all the files in the directory reads them in as json.
+/// Walks all the files in the directory (or matching the glob pattern) reading them in as csv,
+/// .gz and .zst files are decompressed as they're read.
+/// Without any columns each line comes through as a json array of its fields, otherwise each
+/// field is parsed into the type of its column, empty fields being null.
 pub struct FileScanExecutor {
-    lines: Box<dyn Iterator<Item = Result<OwnedJson, ExecutionError>>>,
-    tuple: [Datum<'static>; 1],
+    records: Box<dyn Iterator<Item = Result<StringRecord, ExecutionError>>>,
+    session: Arc<Session>,
+    columns: Vec<Expression>,
+    fields: Vec<Datum<'static>>,
+    tuple: Vec<Datum<'static>>,
+    // Rows read so far, added to the session's rows_examined once we're dropped
+    rows_examined: u64,
     done: bool,
 }
 
 impl FileScanExecutor {
-    pub fn new(directory: String, serde_options: SerdeOptions) -> Self {
-        let file_entries = entries(PathBuf::from(directory));
+    pub fn new(
+        session: Arc<Session>,
+        directory: String,
+        serde_options: SerdeOptions,
+        columns: Vec<Expression>,
+    ) -> Self {
+        let file_entries = files(&directory, serde_options.recursive);
+        let tuple = if columns.is_empty() {
+            vec![Datum::Null]
+        } else {
+            right_size_new(&columns)
+        };
 
         FileScanExecutor {
-            lines: Box::from(file_entries.flat_map(move |e| csv_lines(e, &serde_options))),
-            tuple: [Datum::Null; 1],
+            records: Box::from(file_entries.flat_map(move |e| csv_records(e, &serde_options))),
+            session,
+            fields: right_size_new(&columns),
+            columns,
+            tuple,
+            rows_examined: 0,
             done: false,
         }
     }
 }
 
+impl Drop for FileScanExecutor {
+    fn drop(&mut self) {
+        self.session
+            .rows_examined
+            .fetch_add(self.rows_examined, Ordering::Relaxed);
+    }
+}
+
 impl TupleIter for FileScanExecutor {
     type E = ExecutionError;
 
     fn advance(&mut self) -> Result<(), Self::E> {
-        if let Some(next) = self.lines.next() {
-            let line = next?;
-            self.tuple[0] = Datum::from(line);
+        if let Some(next) = self.records.next() {
+            let record = next?;
+            self.rows_examined += 1;
+            if self.columns.is_empty() {
+                self.tuple[0] = Datum::from(json_array(&record));
+            } else {
+                // Missing fields are treated as empty ones
+                for (idx, field) in self.fields.iter_mut().enumerate() {
+                    *field = match record.get(idx) {
+                        Some(text) if !text.is_empty() => Datum::from(text.to_string()),
+                        _ => Datum::Null,
+                    };
+                }
+                // The parsed values may point back into the fields, they're both only replaced
+                // on the next call to advance.
+                self.columns.eval_scalar(
+                    &self.session,
+                    transmute_buf(&self.fields),
+                    transmute_muf_buf(&mut self.tuple),
+                );
+                check_out_of_range(&self.session)?;
+            }
         } else {
             self.done = true;
         }
@@ -41,24 +100,67 @@ impl TupleIter for FileScanExecutor {
         if self.done {
             None
         } else {
-            Some((&self.tuple, 1))
+            Some((transmute_buf(&self.tuple), 1))
         }
     }
 
     fn column_count(&self) -> usize {
-        1
+        self.tuple.len()
+    }
+}
+
+/// Returns the files to read, the directory may be a glob pattern (ie data/**/*.csv) in which
+/// case any directories matched have their files read too.
+fn files(
+    directory: &str,
+    recursive: bool,
+) -> Box<dyn Iterator<Item = Result<PathBuf, std::io::Error>>> {
+    if !directory.contains(|c| c == '*' || c == '?' || c == '[') {
+        return entries(PathBuf::from(directory), recursive);
+    }
+    // A trailing ** only matches the directories below, not the files sitting alongside them
+    let pattern = if directory.ends_with("**") {
+        format!("{}/*", directory)
+    } else {
+        directory.to_string()
+    };
+    match glob::glob(&pattern) {
+        Ok(paths) => {
+            // A pattern like data/** matches both the directories and the files within them
+            let mut seen = HashSet::new();
+            Box::from(
+                paths
+                    .flat_map(move |path| match path {
+                        Ok(path) => entries(path, recursive),
+                        Err(err) => Box::from(once(Err(err.into_error()))),
+                    })
+                    .filter(move |path| match path {
+                        Ok(path) => seen.insert(path.clone()),
+                        Err(_) => true,
+                    }),
+            )
+        }
+        Err(err) => Box::from(once(Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            err.msg,
+        )))),
     }
 }
 
 /// Returns a flattened iterator of all the files within a director
 /// horrible unwrapping and rewrapping of result types
-fn entries(entry: PathBuf) -> Box<dyn Iterator<Item = Result<PathBuf, std::io::Error>>> {
+fn entries(
+    entry: PathBuf,
+    recursive: bool,
+) -> Box<dyn Iterator<Item = Result<PathBuf, std::io::Error>>> {
     if entry.is_file() {
         Box::from(once(Ok(entry)))
     } else if entry.is_dir() {
         match entry.read_dir() {
-            Ok(iter) => Box::from(iter.flat_map(|entry_result| match entry_result {
-                Ok(entry) => entries(entry.path()),
+            Ok(iter) => Box::from(iter.flat_map(move |entry_result| match entry_result {
+                // Without recursion only the files directly within the directory are read
+                Ok(entry) if !recursive && entry.path().is_dir() => Box::from(empty()),
+                Ok(entry) => entries(entry.path(), recursive),
                 Err(err) => Box::from(once(Err(err))),
             })),
             Err(e) => Box::from(once(Err(e))),
@@ -84,28 +186,24 @@ fn entries(entry: PathBuf) -> Box<dyn Iterator<Item = Result<PathBuf, std::io::E
 //     }
 // }
 
-fn csv_lines(
+fn csv_records(
     entry: Result<PathBuf, std::io::Error>,
     serde_options: &SerdeOptions,
-) -> Box<dyn Iterator<Item = Result<OwnedJson, ExecutionError>>> {
+) -> Box<dyn Iterator<Item = Result<StringRecord, ExecutionError>>> {
     match entry {
         Ok(entry) => {
             let mut builder = csv::ReaderBuilder::new();
-            builder.has_headers(false);
+            builder.has_headers(serde_options.header);
             builder.delimiter(serde_options.delimiter);
-            let reader_result = builder.from_path(entry);
-            match reader_result {
-                Ok(reader) => Box::from(reader.into_records().map(|record_result| {
-                    record_result
-                        .map(|record| {
-                            JsonBuilder::default().array(|array| {
-                                for col in record.iter() {
-                                    array.push_string(col);
-                                }
-                            })
-                        })
-                        .map_err(ExecutionError::from)
-                })),
+            // Rows missing trailing fields are padded out with nulls
+            builder.flexible(true);
+            match open_file(&entry) {
+                Ok(file) => Box::from(
+                    builder
+                        .from_reader(file)
+                        .into_records()
+                        .map(|record_result| record_result.map_err(ExecutionError::from)),
+                ),
                 Err(e) => Box::from(once(Err(e.into()))),
             }
         }
@@ -113,31 +211,62 @@ fn csv_lines(
     }
 }
 
+/// Opens a file, wrapping it in a decoder based on its extension if it's compressed.
+fn open_file(path: &Path) -> Result<Box<dyn Read>, std::io::Error> {
+    let file = File::open(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        // Concatenated gzip members are common for exports written in chunks
+        Some("gz") => Ok(Box::from(MultiGzDecoder::new(file))),
+        Some("zst") => Ok(Box::from(zstd::stream::read::Decoder::new(file)?)),
+        _ => Ok(Box::from(file)),
+    }
+}
+
+fn json_array(record: &StringRecord) -> OwnedJson {
+    JsonBuilder::default().array(|array| {
+        for col in record.iter() {
+            array.push_string(col);
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ast::expr::CompiledColumnReference;
+    use data::DataType;
+
+    fn json_scan(directory: &str) -> FileScanExecutor {
+        FileScanExecutor::new(
+            Arc::new(Session::new(1)),
+            directory.to_string(),
+            SerdeOptions::default(),
+            vec![],
+        )
+    }
 
     #[test]
-    fn test_csv_lines() -> Result<(), ExecutionError> {
+    fn test_csv_records() -> Result<(), ExecutionError> {
         let path = PathBuf::from("../../test_data/csv/simple.csv");
 
-        let mut line_iter = csv_lines(Ok(path), &SerdeOptions::default());
+        let mut record_iter = csv_records(Ok(path), &SerdeOptions::default());
 
-        let expected_line1 = OwnedJson::parse(r#"["123","abc","12.1"]"#).unwrap();
-        let expected_line2 = OwnedJson::parse(r#"["456","d,ef","13.2"]"#).unwrap();
-
-        assert_eq!(line_iter.next().unwrap().unwrap(), expected_line1);
-        assert_eq!(line_iter.next().unwrap().unwrap(), expected_line2);
-        assert_eq!(line_iter.next(), None);
+        assert_eq!(
+            record_iter.next().unwrap()?,
+            StringRecord::from(vec!["123", "abc", "12.1"])
+        );
+        assert_eq!(
+            record_iter.next().unwrap()?,
+            StringRecord::from(vec!["456", "d,ef", "13.2"])
+        );
+        assert!(record_iter.next().is_none());
 
         Ok(())
     }
 
     #[test]
     fn test_single_csv() -> Result<(), ExecutionError> {
-        let directory = "../../test_data/csv/simple.csv".to_string();
-
-        let mut executor = FileScanExecutor::new(directory, SerdeOptions::default());
+        let mut executor = json_scan("../../test_data/csv/simple.csv");
 
         let expected_line1 = OwnedJson::parse(r#"["123","abc","12.1"]"#).unwrap();
         let expected_line2 = OwnedJson::parse(r#"["456","d,ef","13.2"]"#).unwrap();
@@ -157,9 +286,7 @@ mod tests {
 
     #[test]
     fn test_csv_director() -> Result<(), ExecutionError> {
-        let directory = "../../test_data/csv".to_string();
-
-        let mut executor = FileScanExecutor::new(directory, SerdeOptions::default());
+        let mut executor = json_scan("../../test_data/csv");
 
         let expected_line1 = OwnedJson::parse(r#"["123","abc","12.1"]"#).unwrap();
 
@@ -171,4 +298,93 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_files() {
+        let file_names = |directory: &str, recursive: bool| -> Vec<String> {
+            let root = PathBuf::from("../../test_data/partitioned");
+            let mut names: Vec<_> = files(&format!("../../test_data/{}", directory), recursive)
+                .map(|path| {
+                    let path = path.unwrap();
+                    path.strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(file_names("partitioned", false), vec!["_SUCCESS"]);
+        assert_eq!(file_names("partitioned", true).len(), 4);
+        assert_eq!(
+            file_names("partitioned/**/*.csv", false),
+            vec![
+                "dt=2020-11-01/region=eu/part-0.csv",
+                "dt=2020-11-01/region=us/part-0.csv",
+                "dt=2020-11-02/region=us/part-0.csv"
+            ]
+        );
+        // Matched directories are read, only once even if matched more than once
+        assert_eq!(file_names("partitioned/*", true).len(), 4);
+        assert_eq!(file_names("partitioned/**", true).len(), 4);
+        assert_eq!(file_names("partitioned/dt=*", false), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_compressed_csv() -> Result<(), ExecutionError> {
+        for file in &["simple.csv.gz", "simple.csv.zst"] {
+            let path = PathBuf::from("../../test_data/compressed").join(file);
+            let mut record_iter = csv_records(Ok(path), &SerdeOptions::default());
+
+            assert_eq!(
+                record_iter.next().unwrap()?,
+                StringRecord::from(vec!["123", "abc", "12.1"])
+            );
+            assert_eq!(
+                record_iter.next().unwrap()?,
+                StringRecord::from(vec!["456", "d,ef", "13.2"])
+            );
+            assert!(record_iter.next().is_none());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_columns() -> Result<(), ExecutionError> {
+        let column = |offset| {
+            Expression::CompiledColumnReference(CompiledColumnReference {
+                offset,
+                datatype: DataType::Text,
+            })
+        };
+        // The header option skips the first line
+        let mut executor = FileScanExecutor::new(
+            Arc::new(Session::new(1)),
+            "../../test_data/csv/simple.csv".to_string(),
+            SerdeOptions {
+                header: true,
+                ..SerdeOptions::default()
+            },
+            vec![column(1), column(0), column(2), column(3)],
+        );
+
+        assert_eq!(
+            executor.next()?,
+            Some((
+                [
+                    Datum::from("d,ef"),
+                    Datum::from("456"),
+                    Datum::from("13.2"),
+                    Datum::Null
+                ]
+                .as_ref(),
+                1
+            ))
+        );
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
 }
@@ -1,5 +1,7 @@
+use crate::point_in_time::batch::{Batch, BatchIter, BoxedBatchExecutor};
 use crate::point_in_time::BoxedExecutor;
 use crate::scalar_expression::EvalScalar;
+use crate::utils::check_out_of_range;
 use crate::ExecutionError;
 use ast::expr::Expression;
 use data::{Datum, Session, TupleIter};
@@ -25,7 +27,9 @@ impl TupleIter for FilterExecutor {
     type E = ExecutionError;
     fn advance(&mut self) -> Result<(), ExecutionError> {
         while let Some((tuple, _freq)) = self.source.next()? {
-            if self.predicate.eval_scalar(&self.session, tuple) == Datum::from(true) {
+            let matched = self.predicate.eval_scalar(&self.session, tuple) == Datum::from(true);
+            check_out_of_range(&self.session)?;
+            if matched {
                 break;
             }
         }
@@ -41,9 +45,47 @@ impl TupleIter for FilterExecutor {
     }
 }
 
+/// The batch at a time version of the filter, the rows not matching the predicate are dropped
+/// from each batch in place.
+pub struct BatchFilterExecutor {
+    source: BoxedBatchExecutor,
+    session: Arc<Session>,
+    predicate: Expression,
+}
+
+impl BatchFilterExecutor {
+    pub fn new(session: Arc<Session>, source: BoxedBatchExecutor, predicate: Expression) -> Self {
+        BatchFilterExecutor {
+            source,
+            session,
+            predicate,
+        }
+    }
+}
+
+impl BatchIter for BatchFilterExecutor {
+    fn next_batch(&mut self, batch: &mut Batch) -> Result<bool, ExecutionError> {
+        let predicate = &mut self.predicate;
+        let session = &self.session;
+        while self.source.next_batch(batch)? {
+            batch.retain(|tuple| predicate.eval_scalar(session, tuple) == Datum::from(true));
+            check_out_of_range(session)?;
+            if !batch.is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn column_count(&self) -> usize {
+        self.source.column_count()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::point_in_time::batch::{ScanBatchExecutor, UnbatchExecutor};
     use crate::point_in_time::values::ValuesExecutor;
     use crate::ExecutionError;
     use ast::expr::CompiledColumnReference;
@@ -74,4 +116,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_batch_filter_executor() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let values: Vec<_> = (0..3000)
+            .map(|i| vec![Datum::from(i), Datum::from(i % 1000 == 7)])
+            .collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+
+        let predicate = Expression::CompiledColumnReference(CompiledColumnReference {
+            offset: 1,
+            datatype: DataType::Boolean,
+        });
+
+        let mut executor = UnbatchExecutor::new(Box::from(BatchFilterExecutor::new(
+            session,
+            Box::from(ScanBatchExecutor::new(source)),
+            predicate,
+        )));
+        for i in &[7, 1007, 2007] {
+            assert_eq!(
+                executor.next()?,
+                Some(([Datum::from(*i), Datum::from(true)].as_ref(), 1))
+            );
+        }
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,184 @@
+use crate::ExecutionError;
+use ast::rel::logical::FulltextIndex;
+use data::fulltext::tokenize;
+use data::{Datum, LogicalTimestamp, TupleIter};
+use storage::{StorageError, Table};
+
+/// Scans the rows containing any of the terms via a full-text index.
+/// Each term is looked up in turn, a row containing many of the terms is only returned for the
+/// first of its terms so the output doesn't contain duplicates.
+pub struct FulltextScanExecutor {
+    // We must drop scan_iter first
+    scan_iter: Box<dyn TupleIter<E = StorageError>>,
+    index: FulltextIndex,
+    terms: Vec<String>,
+    // The term currently being scanned
+    term_idx: usize,
+    timestamp: LogicalTimestamp,
+}
+
+impl FulltextScanExecutor {
+    pub fn new(index: FulltextIndex, terms: Vec<String>, timestamp: LogicalTimestamp) -> Self {
+        let first_term = terms.first().map_or("", String::as_str);
+        let scan_iter = Self::scan(&index.table, first_term, timestamp);
+        FulltextScanExecutor {
+            scan_iter,
+            index,
+            terms,
+            term_idx: 0,
+            timestamp,
+        }
+    }
+
+    fn scan(
+        table: &Table,
+        term: &str,
+        timestamp: LogicalTimestamp,
+    ) -> Box<dyn TupleIter<E = StorageError>> {
+        let term = [Datum::from(term)];
+        let scan_iter: Box<dyn TupleIter<E = StorageError> + '_> =
+            Box::from(table.range_scan(Some(&term), Some(&term), timestamp));
+        // As per the table scan the lifetime of the rocksdb iter is tied to the table.
+        unsafe {
+            std::mem::transmute::<
+                Box<dyn TupleIter<E = StorageError> + '_>,
+                Box<dyn TupleIter<E = StorageError>>,
+            >(scan_iter)
+        }
+    }
+
+    /// Returns true if the row contains any of the terms we've already scanned.
+    fn seen(&self, tuple: &[Datum]) -> bool {
+        if self.term_idx == 0 {
+            return false;
+        }
+        let row_terms = tuple[self.index.column + 1]
+            .as_maybe_text()
+            .map_or(vec![], tokenize);
+        self.terms[..self.term_idx]
+            .iter()
+            .any(|term| row_terms.binary_search(term).is_ok())
+    }
+}
+
+impl TupleIter for FulltextScanExecutor {
+    type E = ExecutionError;
+
+    fn advance(&mut self) -> Result<(), ExecutionError> {
+        while self.term_idx < self.terms.len() {
+            self.scan_iter.advance()?;
+            match self.scan_iter.get() {
+                Some((tuple, _freq)) => {
+                    if !self.seen(tuple) {
+                        return Ok(());
+                    }
+                }
+                None => {
+                    self.term_idx += 1;
+                    if let Some(term) = self.terms.get(self.term_idx) {
+                        self.scan_iter = Self::scan(&self.index.table, term, self.timestamp);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> Option<(&[Datum], i64)> {
+        if self.term_idx < self.terms.len() {
+            self.scan_iter
+                .get()
+                .map(|(tuple, freq)| (&tuple[1..], freq))
+        } else {
+            None
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        // The index is keyed on the term and then the indexed row
+        self.scan_iter.column_count() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_in_time::table_insert::TableInsertExecutor;
+    use crate::point_in_time::values::ValuesExecutor;
+    use catalog::{Catalog, TableOrView};
+    use data::{DataType, Session};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fulltext_scan_executor() -> Result<(), ExecutionError> {
+        let mut catalog = Catalog::new_for_test().unwrap();
+        catalog
+            .create_table(
+                "default",
+                "test",
+                &[
+                    ("id".to_string(), DataType::Integer),
+                    ("body".to_string(), DataType::Text),
+                ],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        catalog
+            .create_fulltext_index("default", "test", "body", "body")
+            .unwrap();
+        let item = catalog.item("default", "test").unwrap();
+        let table = if let TableOrView::Table(table) = item.item {
+            table
+        } else {
+            panic!()
+        };
+        let index = &item.fulltext_indexes[0];
+        let index = FulltextIndex {
+            name: index.name.clone(),
+            column: index.column,
+            table: index.table.clone(),
+        };
+
+        let values = vec![
+            vec![Datum::from(1), Datum::from("red fish")],
+            vec![Datum::from(2), Datum::from("blue fish")],
+            vec![Datum::from(3), Datum::from("red blue")],
+            vec![Datum::from(4), Datum::from("green")],
+        ];
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+        let mut insert = TableInsertExecutor::new(
+            Arc::new(Session::new(1)),
+            source,
+            table,
+            None,
+            vec![index.clone()],
+            vec![],
+            false,
+        );
+        assert_eq!(insert.next()?, None);
+
+        let mut executor = FulltextScanExecutor::new(
+            index,
+            vec!["fish".to_string(), "red".to_string(), "yellow".to_string()],
+            LogicalTimestamp::MAX,
+        );
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from(1), Datum::from("red fish")].as_ref(), 1))
+        );
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from(2), Datum::from("blue fish")].as_ref(), 1))
+        );
+        // Row 1 has already been returned for "fish"
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from(3), Datum::from("red blue")].as_ref(), 1))
+        );
+        assert_eq!(executor.next()?, None);
+        assert_eq!(executor.column_count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+use crate::point_in_time::batch::{
+    Batch, BatchIter, BoxedBatchExecutor, Input, ScanBatchExecutor, UnbatchExecutor,
+};
+use crate::point_in_time::filter::BatchFilterExecutor;
+use crate::point_in_time::hash_group::HashGroupExecutor;
+use crate::point_in_time::project::BatchProjectExecutor;
+use crate::point_in_time::sorted_group::SortedGroupExecutor;
+use crate::point_in_time::{build_executor, BoxedExecutor};
+use crate::ExecutionError;
+use ast::rel::point_in_time::PointInTimeOperator;
+use data::Session;
+use std::sync::mpsc::{channel, sync_channel, Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Runs the plan across several worker threads. The scan at the bottom of the plan is read on
+/// this thread (the storage iterators can't be shared across threads) and its rows handed out
+/// in batches (morsels) to whichever worker is free, each worker running its own copy of the
+/// operators above the scan.
+pub struct GatherExecutor {
+    scan: ScanBatchExecutor,
+    // Dropped once the scan is exhausted to let the workers know there's no more to come
+    morsels: Option<SyncSender<Batch>>,
+    results: Receiver<Result<Batch, ExecutionError>>,
+    workers: Vec<JoinHandle<()>>,
+    column_count: usize,
+}
+
+impl GatherExecutor {
+    pub fn new(session: &Arc<Session>, plan: &PointInTimeOperator, workers: usize) -> Self {
+        let scan = ScanBatchExecutor::new(build_executor(session, scan_of(plan)));
+        // Only a couple of morsels per worker are read ahead of the workers
+        let (morsel_sender, morsel_receiver) = sync_channel(workers * 2);
+        let morsel_receiver = Arc::new(Mutex::new(morsel_receiver));
+        let (result_sender, results) = channel();
+
+        let morsel_executor = |morsels: &Arc<Mutex<Receiver<Batch>>>| {
+            Box::from(MorselExecutor {
+                morsels: Arc::clone(morsels),
+                column_count: scan.column_count(),
+            })
+        };
+        // The executors can't be sent across threads so each worker builds its own, we build one
+        // here too just to find out how many columns they'll output.
+        let column_count =
+            build_worker_executor(session, plan, morsel_executor(&morsel_receiver)).column_count();
+
+        let workers = (0..workers)
+            .map(|_| {
+                let session = Arc::clone(session);
+                let plan = plan.clone();
+                let morsels = morsel_executor(&morsel_receiver);
+                let results = result_sender.clone();
+                thread::spawn(move || {
+                    let mut executor = build_worker_executor(&session, &plan, morsels);
+                    loop {
+                        let mut batch = Batch::new(executor.column_count());
+                        match executor.next_batch(&mut batch) {
+                            Ok(true) => {}
+                            Ok(false) => break,
+                            Err(err) => {
+                                results.send(Err(err)).ok();
+                                break;
+                            }
+                        }
+                        if results.send(Ok(batch)).is_err() {
+                            // We've been dropped, ie a limit has been hit
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        GatherExecutor {
+            scan,
+            morsels: Some(morsel_sender),
+            results,
+            workers,
+            column_count,
+        }
+    }
+}
+
+impl BatchIter for GatherExecutor {
+    fn next_batch(&mut self, batch: &mut Batch) -> Result<bool, ExecutionError> {
+        loop {
+            let result = match self.results.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => {
+                    // Keep the workers busy while we wait
+                    if let Some(morsels) = &self.morsels {
+                        let mut morsel = Batch::new(self.scan.column_count());
+                        if self.scan.next_batch(&mut morsel)? {
+                            morsels.send(morsel).ok();
+                        } else {
+                            self.morsels = None;
+                        }
+                        continue;
+                    }
+                    match self.results.recv() {
+                        Ok(result) => result,
+                        Err(_) => return self.finish(),
+                    }
+                }
+                Err(TryRecvError::Disconnected) => return self.finish(),
+            };
+            *batch = result?;
+            return Ok(true);
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        self.column_count
+    }
+}
+
+impl GatherExecutor {
+    /// Called once all the workers are done, a worker panicking would otherwise look like it
+    /// had run out of rows.
+    fn finish(&mut self) -> Result<bool, ExecutionError> {
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                panic!("Parallel query worker panicked");
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Hands out the morsels from the scan, shared between all the workers.
+struct MorselExecutor {
+    morsels: Arc<Mutex<Receiver<Batch>>>,
+    column_count: usize,
+}
+
+impl BatchIter for MorselExecutor {
+    fn next_batch(&mut self, batch: &mut Batch) -> Result<bool, ExecutionError> {
+        let morsel = self.morsels.lock().unwrap().recv();
+        if let Ok(morsel) = morsel {
+            *batch = morsel;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        self.column_count
+    }
+}
+
+/// The scan at the bottom of the plan
+fn scan_of(plan: &PointInTimeOperator) -> &PointInTimeOperator {
+    match plan {
+        PointInTimeOperator::Project(project) => scan_of(&project.source),
+        PointInTimeOperator::Filter(filter) => scan_of(&filter.source),
+        PointInTimeOperator::HashGroup(group) | PointInTimeOperator::SortedGroup(group) => {
+            scan_of(&group.source)
+        }
+        scan => scan,
+    }
+}
+
+/// Builds a worker's copy of the plan with the scan replaced by the morsels.
+fn build_worker_executor(
+    session: &Arc<Session>,
+    plan: &PointInTimeOperator,
+    morsels: BoxedBatchExecutor,
+) -> BoxedBatchExecutor {
+    match plan {
+        PointInTimeOperator::Project(project) => Box::from(BatchProjectExecutor::new(
+            Arc::clone(session),
+            build_worker_executor(session, &project.source, morsels),
+            project.expressions.clone(),
+        )),
+        PointInTimeOperator::Filter(filter) => Box::from(BatchFilterExecutor::new(
+            Arc::clone(session),
+            build_worker_executor(session, &filter.source, morsels),
+            filter.predicate.clone(),
+        )),
+        PointInTimeOperator::HashGroup(group) => {
+            let executor: BoxedExecutor = Box::from(HashGroupExecutor::new(
+                Input::batches(build_worker_executor(session, &group.source, morsels)),
+                Arc::clone(session),
+                group.key_len,
+                group.expressions.clone(),
+                group.mode,
+            ));
+            Box::from(ScanBatchExecutor::new(executor))
+        }
+        PointInTimeOperator::SortedGroup(group) => {
+            let source = build_worker_executor(session, &group.source, morsels);
+            let executor: BoxedExecutor = Box::from(SortedGroupExecutor::new(
+                Box::from(UnbatchExecutor::new(source)),
+                Arc::clone(session),
+                group.key_len,
+                group.expressions.clone(),
+                group.mode,
+            ));
+            Box::from(ScanBatchExecutor::new(executor))
+        }
+        _ => morsels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::expr::{CompiledColumnReference, Expression};
+    use ast::rel::point_in_time::{Filter, Project, Values};
+    use data::{DataType, Datum, TupleIter};
+
+    #[test]
+    fn test_gather_executor() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let column = |offset| {
+            Expression::CompiledColumnReference(CompiledColumnReference {
+                offset,
+                datatype: DataType::Integer,
+            })
+        };
+        let plan = PointInTimeOperator::Project(Project {
+            expressions: vec![column(0)],
+            source: Box::new(PointInTimeOperator::Filter(Filter {
+                predicate: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 1,
+                    datatype: DataType::Boolean,
+                }),
+                source: Box::new(PointInTimeOperator::Values(Values {
+                    data: (0..10000)
+                        .map(|i| vec![Datum::from(i), Datum::from(i % 3 == 0)])
+                        .collect(),
+                    column_count: 2,
+                })),
+            })),
+        });
+
+        let mut executor = UnbatchExecutor::new(Box::from(GatherExecutor::new(&session, &plan, 4)));
+        assert_eq!(executor.column_count(), 1);
+        let mut rows = vec![];
+        while let Some((tuple, freq)) = executor.next()? {
+            assert_eq!(freq, 1);
+            rows.push(tuple[0].as_integer());
+        }
+        rows.sort_unstable();
+        assert_eq!(rows, (0..10000).filter(|i| i % 3 == 0).collect::<Vec<_>>());
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
-use crate::aggregate_expression::{AggregateExpression, EvalAggregateRow};
-use crate::point_in_time::BoxedExecutor;
-use crate::utils::{right_size_new, transmute_muf_buf};
+use crate::aggregate_expression::{fold_tuple, AggregateExpression, EvalAggregateRow};
+use crate::interrupt::InterruptCheck;
+use crate::point_in_time::batch::Input;
+use crate::utils::{check_out_of_range, right_size_new, transmute_muf_buf};
 use crate::ExecutionError;
 use ast::expr::Expression;
+use ast::rel::point_in_time::AggregateMode;
 use data::{Datum, Session, SortOrder, TupleIter};
 use std::collections::hash_map::IntoIter;
 use std::collections::HashMap;
@@ -12,37 +14,46 @@ use std::sync::Arc;
 /// partial aggregates in a hashmap.
 /// This executor doesn't properly handle the case where there's no grouping keys,
 /// sorted_group should be used for that instead.
+/// When used for a partial aggregation the raw aggregate states are output instead of the
+/// finalized expressions.
+/// The source can be either a tuple or batch at a time.
 pub struct HashGroupExecutor {
-    source: BoxedExecutor,
+    source: Input,
     session: Arc<Session>,
     key_len: usize,
     expressions: Vec<AggregateExpression>,
+    mode: AggregateMode,
     state: HashMap<Vec<u8>, Vec<Datum<'static>>>,
     state_iter: Option<IntoIter<Vec<u8>, Vec<Datum<'static>>>>,
     output_state: Vec<Datum<'static>>,
     output_tuple: Vec<Datum<'static>>,
     done: bool,
+    interrupts: InterruptCheck,
 }
 
 impl HashGroupExecutor {
     pub fn new(
-        source: BoxedExecutor,
+        source: Input,
         session: Arc<Session>,
         key_len: usize,
         expressions: Vec<Expression>,
+        mode: AggregateMode,
     ) -> Self {
         let expressions: Vec<_> = expressions.iter().map(AggregateExpression::from).collect();
         let output_tuple = right_size_new(&expressions);
+        let interrupts = InterruptCheck::new(Arc::clone(&session));
         HashGroupExecutor {
             source,
             session,
             key_len,
             expressions,
+            mode,
             state: HashMap::new(),
             state_iter: None,
             output_tuple,
             output_state: vec![],
             done: false,
+            interrupts,
         }
     }
 }
@@ -52,24 +63,31 @@ impl TupleIter for HashGroupExecutor {
 
     fn advance(&mut self) -> Result<(), ExecutionError> {
         if self.state_iter.is_none() {
+            let expressions = &mut self.expressions;
+            let groups = &mut self.state;
+            let session = &self.session;
+            let interrupts = &mut self.interrupts;
+            let (key_len, mode) = (self.key_len, self.mode);
             let mut key_buf = vec![];
-            while let Some((tuple, freq)) = self.source.next()? {
+            self.source.for_each(|tuple, freq| {
+                interrupts.tick()?;
                 key_buf.clear();
-                for datum in &tuple[..(self.key_len)] {
+                for datum in &tuple[..key_len] {
                     datum.as_sortable_bytes(SortOrder::Asc, &mut key_buf);
                 }
 
-                if let Some(state) = self.state.get_mut(&key_buf) {
-                    self.expressions.apply(&self.session, tuple, freq, state);
+                if let Some(state) = groups.get_mut(&key_buf) {
+                    fold_tuple(expressions, session, mode, key_len, tuple, freq, state);
                 } else {
                     let mut key = vec![];
                     std::mem::swap(&mut key, &mut key_buf);
-                    let mut state = self.expressions.initialize();
-                    self.expressions
-                        .apply(&self.session, tuple, freq, &mut state);
-                    self.state.insert(key, state);
+                    let mut state = expressions.initialize();
+                    fold_tuple(expressions, session, mode, key_len, tuple, freq, &mut state);
+                    groups.insert(key, state);
                 }
-            }
+                Ok(())
+            })?;
+            check_out_of_range(session)?;
 
             let mut state = HashMap::new();
             std::mem::swap(&mut state, &mut self.state);
@@ -81,11 +99,13 @@ impl TupleIter for HashGroupExecutor {
             // state and the output_tuple on the SortedGroupExecutor struct.
             self.output_state = state;
 
-            self.expressions.finalize(
-                &self.session,
-                &self.output_state,
-                transmute_muf_buf(&mut self.output_tuple),
-            );
+            if self.mode != AggregateMode::Partial {
+                self.expressions.finalize(
+                    &self.session,
+                    &self.output_state,
+                    transmute_muf_buf(&mut self.output_tuple),
+                );
+            }
         } else {
             self.done = true;
         }
@@ -96,13 +116,19 @@ impl TupleIter for HashGroupExecutor {
     fn get(&self) -> Option<(&[Datum], i64)> {
         if self.done {
             None
+        } else if self.mode == AggregateMode::Partial {
+            Some((&self.output_state, 1))
         } else {
             Some((&self.output_tuple, 1))
         }
     }
 
     fn column_count(&self) -> usize {
-        self.expressions.len()
+        if self.mode == AggregateMode::Partial {
+            self.expressions.state_len()
+        } else {
+            self.expressions.len()
+        }
     }
 }
 
@@ -153,17 +179,25 @@ mod tests {
                     },
                 )]
                 .into_boxed_slice(),
+                distinct: false,
                 expr_buffer: vec![].into_boxed_slice(),
                 signature: Box::new(sig),
             }),
         ];
 
-        let executor = HashGroupExecutor::new(source, Arc::clone(&session), 1, expressions);
+        let executor = HashGroupExecutor::new(
+            Input::Tuples(source),
+            Arc::clone(&session),
+            1,
+            expressions,
+            AggregateMode::Complete,
+        );
         let mut sorted = SortExecutor::new(
             session,
             Box::from(executor),
             vec![SortExpression {
                 ordering: SortOrder::Asc,
+                nulls: None,
                 expression: Expression::CompiledColumnReference(CompiledColumnReference {
                     offset: 0,
                     datatype: DataType::Text,
@@ -187,4 +221,82 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_hash_group_executor_partial_and_final() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let values = vec![
+            vec![Datum::from("a"), Datum::from(1)],
+            vec![Datum::from("b"), Datum::from(3)],
+            vec![Datum::from("a"), Datum::from(2)],
+        ];
+
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+
+        let (sig, sum_function) = Registry::default()
+            .resolve_function(&FunctionSignature {
+                name: "sum",
+                args: vec![DataType::Integer],
+                ret: DataType::Null,
+            })
+            .unwrap();
+
+        let key = Expression::CompiledColumnReference(CompiledColumnReference {
+            offset: 0,
+            datatype: DataType::Text,
+        });
+        let sum = Expression::CompiledAggregate(CompiledAggregate {
+            function: sum_function.as_aggregate(),
+            args: vec![Expression::CompiledColumnReference(
+                CompiledColumnReference {
+                    offset: 1,
+                    datatype: DataType::Integer,
+                },
+            )]
+            .into_boxed_slice(),
+            distinct: false,
+            expr_buffer: vec![].into_boxed_slice(),
+            signature: Box::new(sig),
+        });
+
+        // The partial aggregation needs to carry the keys through in its state for the final
+        // aggregation to group on.
+        let partial = HashGroupExecutor::new(
+            Input::Tuples(source),
+            Arc::clone(&session),
+            1,
+            vec![key.clone(), key.clone(), sum.clone()],
+            AggregateMode::Partial,
+        );
+        assert_eq!(partial.column_count(), 3);
+
+        let executor = HashGroupExecutor::new(
+            Input::Tuples(Box::from(partial)),
+            Arc::clone(&session),
+            1,
+            vec![key.clone(), sum],
+            AggregateMode::Final,
+        );
+        let mut sorted = SortExecutor::new(
+            session,
+            Box::from(executor),
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: None,
+                expression: key,
+            }],
+        );
+
+        assert_eq!(
+            sorted.next()?,
+            Some(([Datum::from("a"), Datum::from(3)].as_ref(), 1))
+        );
+        assert_eq!(
+            sorted.next()?,
+            Some(([Datum::from("b"), Datum::from(3)].as_ref(), 1))
+        );
+        assert_eq!(sorted.next()?, None);
+
+        Ok(())
+    }
 }
@@ -1,6 +1,8 @@
+use crate::interrupt::InterruptCheck;
+use crate::point_in_time::runtime_filter::RuntimeFilter;
 use crate::point_in_time::BoxedExecutor;
 use crate::scalar_expression::EvalScalar;
-use crate::utils::{right_size_new_to, transmute_muf_buf};
+use crate::utils::{check_out_of_range, right_size_new_to, transmute_muf_buf};
 use crate::ExecutionError;
 use ast::expr::Expression;
 use ast::rel::logical::JoinType;
@@ -13,7 +15,8 @@ use std::sync::Arc;
 /// columns being the equi join condition.  Any non-equi-join conditions can be filtered
 /// by a downstream filter executor.
 /// The right input will be fully consumed first to populate the hashtable.
-/// The output rows will be a combination of left and right.
+/// The output rows will be a combination of left and right, except for semi and anti joins which
+/// only output the left rows (once) that do or don't have a match.
 pub struct HashJoinExecutor {
     left: BoxedExecutor,
     right: BoxedExecutor,
@@ -23,11 +26,13 @@ pub struct HashJoinExecutor {
     left_len: usize,
     join_type: JoinType,
     hash_table: Option<HashMap<Vec<Datum<'static>>, Bucket>>,
+    runtime_filter: Option<Arc<RuntimeFilter>>,
     tuple_buf: Vec<Datum<'static>>,
     left_freq: i64,
     freq: i64,
     bucket_iter: Iter<'static, (Vec<Datum<'static>>, i64)>,
     done: bool,
+    interrupts: InterruptCheck,
 }
 
 type Bucket = Vec<(Vec<Datum<'static>>, i64)>;
@@ -46,6 +51,7 @@ impl HashJoinExecutor {
     ) -> Self {
         let tuple_buf = right_size_new_to(left.column_count() + right.column_count());
         let left_len = left.column_count();
+        let interrupts = InterruptCheck::new(Arc::clone(&session));
         HashJoinExecutor {
             left,
             right,
@@ -55,13 +61,22 @@ impl HashJoinExecutor {
             left_len,
             join_type,
             hash_table: None,
+            runtime_filter: None,
             tuple_buf,
             left_freq: 0,
             freq: 0,
             bucket_iter: [].iter(),
             done: false,
+            interrupts,
         }
     }
+
+    /// Sets a filter to be populated with the join keys once the hash table is built, this is
+    /// then used to skip rows on the probe(left) side of the join.
+    pub fn with_runtime_filter(mut self, runtime_filter: Arc<RuntimeFilter>) -> Self {
+        self.runtime_filter = Some(runtime_filter);
+        self
+    }
 }
 
 impl TupleIter for HashJoinExecutor {
@@ -72,6 +87,10 @@ impl TupleIter for HashJoinExecutor {
         // a hit we must populate the left side of the tuple and then walk an iterator
         // of the right side values.
 
+        // The join condition was evaluated against the rows returned by the last call, any
+        // overflows from that need to fail the statement before we go any further.
+        check_out_of_range(&self.session)?;
+
         // The offset where we must write the non-key columns out to.
         let right_offset = self.left.column_count() + self.key_len;
 
@@ -91,6 +110,7 @@ impl TupleIter for HashJoinExecutor {
         if self.hash_table.is_none() {
             let mut hash_table: HashMap<Vec<Datum<'static>>, Bucket> = HashMap::new();
             while let Some((tuple, freq)) = self.right.next()? {
+                self.interrupts.tick()?;
                 let key: Vec<_> = tuple[0..(self.key_len)]
                     .iter()
                     .map(Datum::as_static)
@@ -108,6 +128,9 @@ impl TupleIter for HashJoinExecutor {
                 let bucket = hash_table.entry(key).or_default();
                 bucket.push((rest, freq));
             }
+            if let Some(runtime_filter) = &self.runtime_filter {
+                runtime_filter.populate(hash_table.keys());
+            }
             self.hash_table = Some(hash_table);
         }
 
@@ -116,6 +139,7 @@ impl TupleIter for HashJoinExecutor {
         // Walk down the left tuples until we find a hit.
         'outer: loop {
             if let Some((tuple, left_freq)) = self.left.next()? {
+                self.interrupts.tick()?;
                 if let Some(bucket) = hash_table.get(&tuple[0..(self.key_len)]) {
                     // We've got a hit, populate the left side of the tuple
                     let buf = transmute_muf_buf(&mut self.tuple_buf);
@@ -127,6 +151,27 @@ impl TupleIter for HashJoinExecutor {
                         }
                     }
                     self.left_freq = left_freq;
+
+                    if !self.join_type.outputs_right() {
+                        let mut matched = false;
+                        for (right_tuple, _right_freq) in bucket {
+                            for (idx, datum) in right_tuple.iter().enumerate() {
+                                buf[right_offset + idx] = datum.ref_clone();
+                            }
+                            if self.non_equi_condition.eval_scalar(&self.session, buf)
+                                == Datum::from(true)
+                            {
+                                matched = true;
+                                break;
+                            }
+                        }
+                        if matched == (self.join_type == JoinType::Semi) {
+                            self.freq = left_freq;
+                            break 'outer;
+                        }
+                        continue;
+                    }
+
                     self.bucket_iter = unsafe { std::mem::transmute(bucket.iter()) };
 
                     // Process the first item in the bucket
@@ -144,6 +189,16 @@ impl TupleIter for HashJoinExecutor {
                     }
                 }
 
+                if self.join_type == JoinType::Anti {
+                    let buf = transmute_muf_buf(&mut self.tuple_buf);
+                    for (idx, datum) in tuple.iter().enumerate() {
+                        buf[idx] = datum.ref_clone();
+                    }
+                    self.freq = left_freq;
+
+                    break;
+                }
+
                 if self.join_type == JoinType::LeftOuter {
                     // Populate the left side of the output tuple
                     let buf = transmute_muf_buf(&mut self.tuple_buf);
@@ -172,12 +227,16 @@ impl TupleIter for HashJoinExecutor {
         if self.done {
             None
         } else {
-            Some((&self.tuple_buf, self.freq))
+            Some((&self.tuple_buf[..self.column_count()], self.freq))
         }
     }
 
     fn column_count(&self) -> usize {
-        self.left.column_count() + self.right.column_count()
+        if self.join_type.outputs_right() {
+            self.left.column_count() + self.right.column_count()
+        } else {
+            self.left_len
+        }
     }
 }
 
@@ -226,6 +285,7 @@ mod tests {
             vec![
                 SortExpression {
                     ordering: SortOrder::Asc,
+                    nulls: None,
                     expression: Expression::CompiledColumnReference(CompiledColumnReference {
                         offset: 1,
                         datatype: DataType::Text,
@@ -233,6 +293,7 @@ mod tests {
                 },
                 SortExpression {
                     ordering: SortOrder::Asc,
+                    nulls: None,
                     expression: Expression::CompiledColumnReference(CompiledColumnReference {
                         offset: 3,
                         datatype: DataType::Text,
@@ -344,6 +405,7 @@ mod tests {
             vec![
                 SortExpression {
                     ordering: SortOrder::Asc,
+                    nulls: None,
                     expression: Expression::CompiledColumnReference(CompiledColumnReference {
                         offset: 1,
                         datatype: DataType::Text,
@@ -351,6 +413,7 @@ mod tests {
                 },
                 SortExpression {
                     ordering: SortOrder::Asc,
+                    nulls: None,
                     expression: Expression::CompiledColumnReference(CompiledColumnReference {
                         offset: 3,
                         datatype: DataType::Text,
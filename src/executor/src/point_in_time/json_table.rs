@@ -0,0 +1,158 @@
+use crate::point_in_time::BoxedExecutor;
+use crate::scalar_expression::EvalScalar;
+use crate::utils::{check_out_of_range, right_size_new_to};
+use crate::ExecutionError;
+use ast::expr::Expression;
+use data::json::{JsonBuilder, OwnedJson};
+use data::jsonpath_utils::JsonPathExpression;
+use data::{Datum, Session, TupleIter};
+use std::sync::Arc;
+
+/// Outputs a row for each match of the path in each source row's document, the source row's
+/// columns followed by the json table columns. The columns are evaluated against
+/// [match, ordinality].
+/// Source rows without any matches (or without a document) are dropped.
+pub struct JsonTableExecutor {
+    source: BoxedExecutor,
+    session: Arc<Session>,
+    document: Expression,
+    path: JsonPathExpression,
+    columns: Vec<Expression>,
+
+    matches: Vec<OwnedJson>,
+    next_match: usize,
+    freq: i64,
+    has_row: bool,
+    tuple_buffer: Vec<Datum<'static>>,
+}
+
+impl JsonTableExecutor {
+    pub fn new(
+        session: Arc<Session>,
+        source: BoxedExecutor,
+        document: Expression,
+        path: JsonPathExpression,
+        columns: Vec<Expression>,
+    ) -> Self {
+        let tuple_buffer = right_size_new_to(source.column_count() + columns.len());
+        JsonTableExecutor {
+            source,
+            session,
+            document,
+            path,
+            columns,
+            matches: vec![],
+            next_match: 0,
+            freq: 0,
+            has_row: false,
+            tuple_buffer,
+        }
+    }
+}
+
+impl TupleIter for JsonTableExecutor {
+    type E = ExecutionError;
+
+    fn advance(&mut self) -> Result<(), ExecutionError> {
+        loop {
+            if let Some(json) = self.matches.get(self.next_match) {
+                self.next_match += 1;
+                let row = [
+                    Datum::from(json.as_json()),
+                    Datum::from(self.next_match as i64),
+                ];
+                let source_len = self.tuple_buffer.len() - self.columns.len();
+                for (target, column) in self.tuple_buffer[source_len..]
+                    .iter_mut()
+                    .zip(self.columns.iter_mut())
+                {
+                    *target = column.eval_scalar(&self.session, &row).as_static();
+                }
+                check_out_of_range(&self.session)?;
+                self.has_row = true;
+                return Ok(());
+            }
+
+            self.matches.clear();
+            self.next_match = 0;
+            if let Some((tuple, freq)) = self.source.next()? {
+                self.freq = freq;
+                let document = self.document.eval_scalar(&self.session, tuple);
+                if let Some(json) = document.as_maybe_json() {
+                    let matches = &mut self.matches;
+                    self.path.evaluate(json, &mut |json| {
+                        matches.push(JsonBuilder::default().json(json))
+                    });
+                }
+                for (target, datum) in self.tuple_buffer.iter_mut().zip(tuple) {
+                    *target = datum.as_static();
+                }
+            } else {
+                self.has_row = false;
+                return Ok(());
+            }
+        }
+    }
+
+    fn get(&self) -> Option<(&[Datum], i64)> {
+        if self.has_row {
+            Some((&self.tuple_buffer, self.freq))
+        } else {
+            None
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        self.tuple_buffer.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_in_time::values::ValuesExecutor;
+    use ast::expr::CompiledColumnReference;
+    use data::DataType;
+
+    #[test]
+    fn test_json_table_executor() -> Result<(), ExecutionError> {
+        let values = vec![
+            vec![
+                Datum::from(1),
+                Datum::from(OwnedJson::parse(r#"[{"a": 1}, {"a": 2}]"#).unwrap()),
+            ],
+            vec![Datum::from(2), Datum::Null],
+            vec![
+                Datum::from(3),
+                Datum::from(OwnedJson::parse(r#"[{"b": 3}]"#).unwrap()),
+            ],
+        ];
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+        let column = |offset, datatype| {
+            Expression::CompiledColumnReference(CompiledColumnReference { offset, datatype })
+        };
+
+        let mut executor = JsonTableExecutor::new(
+            Arc::new(Session::new(1)),
+            source,
+            column(1, DataType::Json),
+            JsonPathExpression::parse("$[*]").unwrap(),
+            vec![column(1, DataType::BigInt)],
+        );
+        assert_eq!(executor.column_count(), 3);
+
+        let mut rows = vec![];
+        while let Some((tuple, freq)) = executor.next()? {
+            rows.push((tuple[0].as_static(), tuple[2].as_static(), freq));
+        }
+        assert_eq!(
+            rows,
+            vec![
+                (Datum::from(1), Datum::from(1_i64), 1),
+                (Datum::from(1), Datum::from(2_i64), 1),
+                (Datum::from(3), Datum::from(1_i64), 1),
+            ]
+        );
+        Ok(())
+    }
+}
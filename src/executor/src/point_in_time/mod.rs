@@ -1,29 +1,42 @@
+use crate::point_in_time::batch::{BoxedBatchExecutor, Input, ScanBatchExecutor, UnbatchExecutor};
 use crate::point_in_time::file_scan::FileScanExecutor;
-use crate::point_in_time::filter::FilterExecutor;
+use crate::point_in_time::filter::{BatchFilterExecutor, FilterExecutor};
+use crate::point_in_time::fulltext_scan::FulltextScanExecutor;
+use crate::point_in_time::gather::GatherExecutor;
 use crate::point_in_time::hash_group::HashGroupExecutor;
 use crate::point_in_time::hash_join::HashJoinExecutor;
+use crate::point_in_time::json_table::JsonTableExecutor;
 use crate::point_in_time::limit::LimitExecutor;
 use crate::point_in_time::negate_freq::NegateFreqExecutor;
-use crate::point_in_time::project::ProjectExecutor;
+use crate::point_in_time::project::{BatchProjectExecutor, ProjectExecutor};
+use crate::point_in_time::runtime_filter::RuntimeFilter;
 use crate::point_in_time::single::SingleExecutor;
 use crate::point_in_time::sort::SortExecutor;
 use crate::point_in_time::sorted_group::SortedGroupExecutor;
 use crate::point_in_time::table_insert::TableInsertExecutor;
-use crate::point_in_time::table_scan::TableScanExecutor;
+use crate::point_in_time::table_scan::{ScanFilter, TableScanExecutor};
 use crate::point_in_time::union_all::UnionAllExecutor;
 use crate::point_in_time::values::ValuesExecutor;
 use crate::ExecutionError;
-use ast::rel::point_in_time::PointInTimeOperator;
+use ast::expr::Expression;
+use ast::rel::logical::JoinType;
+use ast::rel::point_in_time::{PointInTimeOperator, TableInsert, TableScan};
 use data::{Session, TupleIter};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use storage::Transaction;
 
+mod batch;
 mod file_scan;
 mod filter;
+mod fulltext_scan;
+mod gather;
 mod hash_group;
 mod hash_join;
+mod json_table;
 mod limit;
 mod negate_freq;
 mod project;
+mod runtime_filter;
 mod single;
 mod sort;
 mod sorted_group;
@@ -34,7 +47,40 @@ mod values;
 
 pub type BoxedExecutor = Box<dyn TupleIter<E = ExecutionError>>;
 
+/// Builds the executor for an insert run as part of a transaction, rather than being committed
+/// its writes are added to the transaction.
+pub fn build_transactional_insert(
+    session: &Arc<Session>,
+    table_insert: &TableInsert,
+    transaction: &Arc<Mutex<Transaction>>,
+) -> BoxedExecutor {
+    Box::from(
+        table_insert_executor(session, table_insert).with_transaction(Arc::clone(transaction)),
+    )
+}
+
+fn table_insert_executor(
+    session: &Arc<Session>,
+    table_insert: &TableInsert,
+) -> TableInsertExecutor {
+    TableInsertExecutor::new(
+        Arc::clone(session),
+        build_executor(session, &table_insert.source),
+        table_insert.table.clone(),
+        table_insert.partitioning.clone(),
+        table_insert.fulltext_indexes.clone(),
+        table_insert.secondary_indexes.clone(),
+        table_insert.delete_matching,
+    )
+    .with_bulk_load(table_insert.bulk_load)
+    .with_upsert(table_insert.upsert.clone())
+    .with_not_null_columns(table_insert.not_null_columns.clone())
+}
+
 pub fn build_executor(session: &Arc<Session>, plan: &PointInTimeOperator) -> BoxedExecutor {
+    if supports_batches(plan) {
+        return Box::from(UnbatchExecutor::new(build_batch_executor(session, plan)));
+    }
     match plan {
         PointInTimeOperator::Single => Box::from(SingleExecutor::new()),
         PointInTimeOperator::Project(project) => Box::from(ProjectExecutor::new(
@@ -52,11 +98,18 @@ pub fn build_executor(session: &Arc<Session>, plan: &PointInTimeOperator) -> Box
             limit.offset,
             limit.limit,
         )),
-        PointInTimeOperator::Sort(sort) => Box::from(SortExecutor::new(
-            Arc::clone(session),
-            build_executor(session, &sort.source),
-            sort.sort_expressions.clone(),
-        )),
+        PointInTimeOperator::Sort(sort) => {
+            let executor = SortExecutor::new(
+                Arc::clone(session),
+                build_executor(session, &sort.source),
+                sort.sort_expressions.clone(),
+            );
+            if let Some(limit) = sort.limit {
+                Box::from(executor.with_limit(limit))
+            } else {
+                Box::from(executor)
+            }
+        }
         PointInTimeOperator::Values(values) => Box::from(ValuesExecutor::new(
             Box::from(values.data.clone().into_iter()),
             values.column_count,
@@ -68,14 +121,17 @@ pub fn build_executor(session: &Arc<Session>, plan: &PointInTimeOperator) -> Box
                 .map(|source| build_executor(session, source))
                 .collect(),
         )),
-        PointInTimeOperator::TableScan(table_scan) => Box::from(TableScanExecutor::new(
-            table_scan.table.clone(),
-            table_scan.timestamp,
-        )),
-        PointInTimeOperator::TableInsert(table_insert) => Box::from(TableInsertExecutor::new(
-            build_executor(session, &table_insert.source),
-            table_insert.table.clone(),
+        PointInTimeOperator::TableScan(table_scan) => {
+            Box::from(table_scan_executor(session, table_scan))
+        }
+        PointInTimeOperator::FulltextScan(fulltext_scan) => Box::from(FulltextScanExecutor::new(
+            fulltext_scan.index.clone(),
+            fulltext_scan.terms.clone(),
+            session.scan_timestamp(fulltext_scan.timestamp),
         )),
+        PointInTimeOperator::TableInsert(table_insert) => {
+            Box::from(table_insert_executor(session, table_insert))
+        }
         PointInTimeOperator::NegateFreq(source) => {
             Box::from(NegateFreqExecutor::new(build_executor(session, &source)))
         }
@@ -84,28 +140,178 @@ pub fn build_executor(session: &Arc<Session>, plan: &PointInTimeOperator) -> Box
             Arc::clone(&session),
             group.key_len,
             group.expressions.clone(),
+            group.mode,
         )),
         PointInTimeOperator::HashGroup(group) => Box::from(HashGroupExecutor::new(
-            build_executor(session, &group.source),
+            if supports_batches(&group.source) {
+                Input::batches(build_batch_executor(session, &group.source))
+            } else {
+                Input::Tuples(build_executor(session, &group.source))
+            },
             Arc::clone(&session),
             group.key_len,
             group.expressions.clone(),
+            group.mode,
         )),
         PointInTimeOperator::FileScan(file_scan) => Box::from(FileScanExecutor::new(
+            Arc::clone(&session),
             file_scan.directory.clone(),
             file_scan.serde_options.clone(),
+            file_scan.columns.clone(),
         )),
-        PointInTimeOperator::HashJoin(join) => Box::from(HashJoinExecutor::new(
-            build_executor(session, &join.left),
-            build_executor(session, &join.right),
-            join.key_len,
-            join.non_equi_condition.clone(),
-            join.join_type,
+        PointInTimeOperator::JsonTable(json_table) => Box::from(JsonTableExecutor::new(
             Arc::clone(&session),
+            build_executor(session, &json_table.source),
+            json_table.document.clone(),
+            json_table.path.clone(),
+            json_table.columns.clone(),
         )),
+        PointInTimeOperator::Gather(gather) => Box::from(UnbatchExecutor::new(Box::from(
+            GatherExecutor::new(session, &gather.source, gather.workers),
+        ))),
+        PointInTimeOperator::HashJoin(join) => {
+            // For inner and semi joins we can filter the left(probe) side based on the keys seen
+            // on the right(build) side.
+            let runtime_filter = Arc::new(RuntimeFilter::default());
+            let filtered_left =
+                if join.join_type == JoinType::Inner || join.join_type == JoinType::Semi {
+                    let key_columns = (0..join.key_len).map(Some).collect();
+                    build_probe_executor(session, &join.left, &runtime_filter, key_columns)
+                } else {
+                    None
+                };
+            let filtered = filtered_left.is_some();
+            let left = filtered_left.unwrap_or_else(|| build_executor(session, &join.left));
+
+            let executor = HashJoinExecutor::new(
+                left,
+                build_executor(session, &join.right),
+                join.key_len,
+                join.non_equi_condition.clone(),
+                join.join_type,
+                Arc::clone(&session),
+            );
+            if filtered {
+                Box::from(executor.with_runtime_filter(runtime_filter))
+            } else {
+                Box::from(executor)
+            }
+        }
     }
 }
 
+/// Whether the plan can be run a batch at a time, ie filters and projects over a scan. There's
+/// nothing to gain from batching the scan on its own. Parallel plans are always run a batch at
+/// a time.
+fn supports_batches(plan: &PointInTimeOperator) -> bool {
+    let batchable_source = |source: &PointInTimeOperator| match source {
+        PointInTimeOperator::TableScan(_) | PointInTimeOperator::FileScan(_) => true,
+        source => supports_batches(source),
+    };
+    match plan {
+        PointInTimeOperator::Project(project) => batchable_source(&project.source),
+        PointInTimeOperator::Filter(filter) => batchable_source(&filter.source),
+        PointInTimeOperator::Gather(_) => true,
+        _ => false,
+    }
+}
+
+/// Builds the batch at a time version of the plan, anything that can't be run a batch at a time
+/// gets built as usual with its tuples gathered up into batches.
+fn build_batch_executor(session: &Arc<Session>, plan: &PointInTimeOperator) -> BoxedBatchExecutor {
+    match plan {
+        PointInTimeOperator::Project(project) => Box::from(BatchProjectExecutor::new(
+            Arc::clone(session),
+            build_batch_executor(session, &project.source),
+            project.expressions.clone(),
+        )),
+        PointInTimeOperator::Filter(filter) => Box::from(BatchFilterExecutor::new(
+            Arc::clone(session),
+            build_batch_executor(session, &filter.source),
+            filter.predicate.clone(),
+        )),
+        PointInTimeOperator::Gather(gather) => {
+            Box::from(GatherExecutor::new(session, &gather.source, gather.workers))
+        }
+        plan => Box::from(ScanBatchExecutor::new(build_executor(session, plan))),
+    }
+}
+
+/// Attempts to build the probe side of a hash join with the join's runtime filter pushed down
+/// into the table scan. The columns map each join key to the column of the plan's output it
+/// comes from. Returns None if the filter can't be pushed all the way down to a table scan.
+fn build_probe_executor(
+    session: &Arc<Session>,
+    plan: &PointInTimeOperator,
+    runtime_filter: &Arc<RuntimeFilter>,
+    columns: Vec<Option<usize>>,
+) -> Option<BoxedExecutor> {
+    if columns.iter().all(Option::is_none) {
+        return None;
+    }
+
+    match plan {
+        PointInTimeOperator::Project(project) => {
+            // We can only follow keys that are straight column references.
+            let source_columns = columns
+                .iter()
+                .map(|column| {
+                    column.and_then(|column| {
+                        if let Expression::CompiledColumnReference(column_ref) =
+                            &project.expressions[column]
+                        {
+                            Some(column_ref.offset)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .collect();
+            let source =
+                build_probe_executor(session, &project.source, runtime_filter, source_columns)?;
+            Some(Box::from(ProjectExecutor::new(
+                Arc::clone(session),
+                source,
+                project.expressions.clone(),
+            )))
+        }
+        PointInTimeOperator::Filter(filter) => {
+            let source = build_probe_executor(session, &filter.source, runtime_filter, columns)?;
+            Some(Box::from(FilterExecutor::new(
+                Arc::clone(session),
+                source,
+                filter.predicate.clone(),
+            )))
+        }
+        PointInTimeOperator::TableScan(table_scan) => Some(Box::from(
+            table_scan_executor(session, table_scan)
+                .with_runtime_filter(ScanFilter::new(Arc::clone(runtime_filter), columns)),
+        )),
+        _ => None,
+    }
+}
+
+fn table_scan_executor(session: &Arc<Session>, table_scan: &TableScan) -> TableScanExecutor {
+    let executor = if let Some(ranges) = &table_scan.ranges {
+        TableScanExecutor::new_with_ranges(
+            table_scan.table.clone(),
+            session.scan_timestamp(table_scan.timestamp),
+            ranges.clone(),
+        )
+    } else {
+        TableScanExecutor::new(
+            table_scan.table.clone(),
+            session.scan_timestamp(table_scan.timestamp),
+        )
+    };
+    let executor = if let Some(column_mask) = &table_scan.column_mask {
+        executor.with_column_mask(column_mask.clone())
+    } else {
+        executor
+    };
+    executor.with_interrupts(Arc::clone(session))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,4 +1,6 @@
+use crate::point_in_time::batch::{Batch, BatchIter, BoxedBatchExecutor};
 use crate::point_in_time::BoxedExecutor;
+use crate::scalar_expression::EvalScalar;
 use crate::scalar_expression::EvalScalarRow;
 use crate::utils::*;
 use crate::ExecutionError;
@@ -42,6 +44,7 @@ impl TupleIter for ProjectExecutor {
                 tuple,
                 transmute_muf_buf(&mut self.tuple_buffer),
             );
+            check_out_of_range(&self.session)?;
         }
         Ok(())
     }
@@ -57,11 +60,66 @@ impl TupleIter for ProjectExecutor {
     }
 }
 
+/// The batch at a time version of the project. As the expressions hold onto their intermediate
+/// results between rows the outputs are copied into the batch rather than borrowed.
+pub struct BatchProjectExecutor {
+    source: BoxedBatchExecutor,
+    session: Arc<Session>,
+    expressions: Vec<Expression>,
+
+    source_batch: Batch,
+}
+
+impl BatchProjectExecutor {
+    pub fn new(
+        session: Arc<Session>,
+        source: BoxedBatchExecutor,
+        expressions: Vec<Expression>,
+    ) -> Self {
+        let source_batch = Batch::new(source.column_count());
+        BatchProjectExecutor {
+            source,
+            session,
+            expressions,
+            source_batch,
+        }
+    }
+}
+
+impl BatchIter for BatchProjectExecutor {
+    fn next_batch(&mut self, batch: &mut Batch) -> Result<bool, ExecutionError> {
+        batch.clear();
+        if !self.source.next_batch(&mut self.source_batch)? {
+            return Ok(false);
+        }
+        let session = &self.session;
+        for idx in 0..self.source_batch.len() {
+            let (tuple, freq) = self.source_batch.row(idx);
+            batch.push_row(
+                self.expressions
+                    .iter_mut()
+                    .map(|expression| expression.eval_scalar(session, tuple)),
+                freq,
+            );
+        }
+        check_out_of_range(session)?;
+        Ok(true)
+    }
+
+    fn column_count(&self) -> usize {
+        self.expressions.len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::point_in_time::batch::{ScanBatchExecutor, UnbatchExecutor};
     use crate::point_in_time::single::SingleExecutor;
+    use crate::point_in_time::values::ValuesExecutor;
     use crate::ExecutionError;
+    use ast::expr::CompiledColumnReference;
+    use data::DataType;
 
     #[test]
     fn test_project_executor() -> Result<(), ExecutionError> {
@@ -81,4 +139,47 @@ mod tests {
         assert_eq!(executor.next()?, None);
         Ok(())
     }
+
+    #[test]
+    fn test_batch_project_executor() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let values: Vec<_> = (0..2000)
+            .map(|i| {
+                vec![
+                    Datum::from(i),
+                    Datum::from(format!("a long enough string {}", i)),
+                ]
+            })
+            .collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+
+        let mut executor = UnbatchExecutor::new(Box::from(BatchProjectExecutor::new(
+            session,
+            Box::from(ScanBatchExecutor::new(source)),
+            vec![
+                Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 1,
+                    datatype: DataType::Text,
+                }),
+                Expression::from(1),
+            ],
+        )));
+        assert_eq!(executor.column_count(), 2);
+
+        for i in 0..2000 {
+            assert_eq!(
+                executor.next()?,
+                Some((
+                    [
+                        Datum::from(format!("a long enough string {}", i)),
+                        Datum::from(1)
+                    ]
+                    .as_ref(),
+                    1
+                ))
+            );
+        }
+        assert_eq!(executor.next()?, None);
+        Ok(())
+    }
 }
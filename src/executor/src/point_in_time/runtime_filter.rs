@@ -0,0 +1,175 @@
+use data::Datum;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+/// A filter derived at runtime from the build side of a hash join, this gets pushed down into
+/// the probe side table scan to skip rows that can't possibly join.
+/// It's made up of a min/max range for each of the join keys and a bloom filter over the keys
+/// as a whole.
+/// The filter is shared between the hash join and the scan, and will be empty until the hash
+/// join has built its hash table (which happens before the probe side is first read).
+#[derive(Debug, Default)]
+pub struct RuntimeFilter {
+    state: RwLock<Option<Arc<FilterState>>>,
+}
+
+#[derive(Debug)]
+pub struct FilterState {
+    // None if the build side had no (non null) keys
+    ranges: Option<Vec<(Datum<'static>, Datum<'static>)>>,
+    bloom: Vec<u64>,
+}
+
+/// Number of bits to use in the bloom filter per key
+const BLOOM_BITS_PER_KEY: usize = 10;
+/// Number of hash functions to use in the bloom filter
+const BLOOM_PROBES: u64 = 3;
+
+impl RuntimeFilter {
+    /// Populates the filter from the keys on the build side of the join.
+    pub fn populate<'a, I: ExactSizeIterator<Item = &'a Vec<Datum<'static>>>>(&self, keys: I) {
+        let bits = (keys.len() * BLOOM_BITS_PER_KEY)
+            .next_power_of_two()
+            .max(64);
+        let mut bloom = vec![0_u64; bits / 64];
+        let mut ranges: Option<Vec<(Datum<'static>, Datum<'static>)>> = None;
+
+        for key in keys {
+            for bit in bloom_bits(key.iter(), bits) {
+                bloom[bit / 64] |= 1 << (bit % 64);
+            }
+
+            if let Some(ranges) = &mut ranges {
+                for ((min, max), datum) in ranges.iter_mut().zip(key) {
+                    if *datum < *min {
+                        *min = datum.clone();
+                    }
+                    if *datum > *max {
+                        *max = datum.clone();
+                    }
+                }
+            } else {
+                ranges = Some(key.iter().map(|d| (d.clone(), d.clone())).collect());
+            }
+        }
+
+        *self.state.write().unwrap() = Some(Arc::new(FilterState { ranges, bloom }));
+    }
+
+    /// Returns the populated filter state, or None if the filter hasn't been populated yet.
+    pub fn state(&self) -> Option<Arc<FilterState>> {
+        self.state.read().unwrap().clone()
+    }
+}
+
+impl FilterState {
+    /// Returns the min/max range for the key at the given index, or None if the filter rejects
+    /// everything.
+    pub fn range(&self, key_idx: usize) -> Option<&(Datum<'static>, Datum<'static>)> {
+        self.ranges.as_ref().map(|ranges| &ranges[key_idx])
+    }
+
+    /// Returns true if the filter doesn't match anything.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_none()
+    }
+
+    /// Checks the key columns of a tuple against the filter, the columns array is the column
+    /// offset in the tuple for each key, keys without a column are assumed to match.
+    /// As with the join itself null keys never match.
+    pub fn matches(&self, tuple: &[Datum], columns: &[Option<usize>]) -> bool {
+        let ranges = if let Some(ranges) = &self.ranges {
+            ranges
+        } else {
+            return false;
+        };
+
+        for ((min, max), column) in ranges.iter().zip(columns) {
+            if let Some(column) = column {
+                let datum = &tuple[*column];
+                if datum.is_null() || datum < min || datum > max {
+                    return false;
+                }
+            }
+        }
+
+        // The bloom filter is over the whole key so we can only use it if we've got all the
+        // columns.
+        if columns.iter().all(Option::is_some) {
+            let bits = self.bloom.len() * 64;
+            let key = columns.iter().map(|column| &tuple[column.unwrap()]);
+            for bit in bloom_bits(key, bits) {
+                if self.bloom[bit / 64] & (1 << (bit % 64)) == 0 {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Returns the bit positions for the key in a bloom filter of the given size (a power of two)
+/// using double hashing.
+fn bloom_bits<'a, 'b: 'a, I: Iterator<Item = &'a Datum<'b>>>(
+    key: I,
+    bits: usize,
+) -> impl Iterator<Item = usize> {
+    let mut hasher = DefaultHasher::new();
+    for datum in key {
+        datum.hash(&mut hasher);
+    }
+    let hash = hasher.finish();
+    let h1 = hash & 0xFFFF_FFFF;
+    let h2 = (hash >> 32) | 1;
+    let mask = bits as u64 - 1;
+    (0..BLOOM_PROBES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) & mask) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_filter() {
+        let filter = RuntimeFilter::default();
+        assert!(filter.state().is_none());
+
+        let keys: Vec<Vec<Datum<'static>>> = (0..100)
+            .map(|i| vec![Datum::from(i * 2), Datum::from("a")])
+            .collect();
+        filter.populate(keys.iter());
+        let state = filter.state().unwrap();
+
+        assert!(!state.is_empty());
+        assert_eq!(state.range(0), Some(&(Datum::from(0), Datum::from(198))));
+
+        let all_columns = [Some(1), Some(0)];
+        for key in &keys {
+            // The tuple is in a different order to the keys
+            assert!(state.matches(&[key[1].clone(), key[0].clone()], &all_columns));
+        }
+
+        // Out of range and null
+        assert!(!state.matches(&[Datum::from("a"), Datum::from(200)], &all_columns));
+        assert!(!state.matches(&[Datum::from("a"), Datum::Null], &all_columns));
+
+        // The bloom filter should catch most of the misses within the range
+        let false_positives = (0..100)
+            .filter(|i| state.matches(&[Datum::from("a"), Datum::from(i * 2 + 1)], &all_columns))
+            .count();
+        assert!(false_positives < 20);
+
+        // Only using the range check
+        assert!(state.matches(&[Datum::from(101)], &[Some(0), None]));
+    }
+
+    #[test]
+    fn test_runtime_filter_empty() {
+        let filter = RuntimeFilter::default();
+        filter.populate(vec![].iter());
+        let state = filter.state().unwrap();
+        assert!(state.is_empty());
+        assert!(!state.matches(&[Datum::from(1)], &[Some(0)]));
+    }
+}
@@ -1,13 +1,29 @@
+use crate::interrupt::InterruptCheck;
 use crate::point_in_time::BoxedExecutor;
 use crate::scalar_expression::EvalScalar;
-use crate::utils::right_size_new_to;
+use crate::utils::{check_out_of_range, right_size_new_to};
 use crate::ExecutionError;
 use ast::expr::SortExpression;
 use data::encoding_core::SortableEncoding;
 use data::{Datum, Session, SortOrder, TupleIter};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::sync::Arc;
 use std::vec::IntoIter;
 
+/// The session variable holding the number of bytes a sort can buffer in memory before it
+/// starts spilling sorted runs out to disk.
+pub const SORT_BUFFER_SIZE_VARIABLE: &str = "sort_buffer_size";
+
+/// Used if the session variable has been set to something that isn't a number
+const DEFAULT_SORT_BUFFER_SIZE: i64 = 128 * 1024 * 1024;
+
+/// The number of rows limited sorts make room for up front, the limit comes from the user so
+/// anything past this is only allocated as the rows actually turn up.
+const LIMITED_SORT_INITIAL_ROWS: usize = 4096;
+
 /// An executor that sorts expressions based on some sort expression(s).
 pub struct SortExecutor {
     source: BoxedExecutor,
@@ -19,6 +35,49 @@ pub struct SortExecutor {
     state: State,
     tuple_buffer: Vec<Datum<'static>>,
     freq: i64,
+    // When set only the first n rows are needed
+    limit: Option<i64>,
+    // The sorted runs spilled out to disk once the sort buffer fills up
+    runs: Vec<SortedRun>,
+    // The head entry of each run, smallest first, along with the run it came from
+    merge_heap: BinaryHeap<Reverse<(Vec<u8>, usize)>>,
+    interrupts: InterruptCheck,
+}
+
+/// A temp file holding length prefixed entries in sorted order.
+struct SortedRun {
+    reader: BufReader<File>,
+}
+
+impl SortedRun {
+    /// Writes the entries out to an anonymous temp file, it's cleaned up when the run is dropped.
+    fn write<'a>(entries: impl Iterator<Item = &'a [u8]>) -> Result<Self, ExecutionError> {
+        let mut file = tempfile::tempfile()?;
+        let mut writer = BufWriter::new(&file);
+        for entry in entries {
+            writer.write_all(&(entry.len() as u32).to_le_bytes())?;
+            writer.write_all(entry)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        file.seek(SeekFrom::Start(0))?;
+        Ok(SortedRun {
+            reader: BufReader::new(file),
+        })
+    }
+
+    /// Reads the next entry, returns None once the run is exhausted.
+    fn next_entry(&mut self) -> Result<Option<Vec<u8>>, ExecutionError> {
+        let mut len = [0_u8; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let mut entry = vec![0_u8; u32::from_le_bytes(len) as usize];
+        self.reader.read_exact(&mut entry)?;
+        Ok(Some(entry))
+    }
 }
 
 #[derive(Eq, PartialEq)]
@@ -41,8 +100,10 @@ enum State {
 // into one big buffer and keep a set of pointers into the buffer and then we'll sort
 // the pointers.
 // Due to our buffer potentially resizing and reallocating our pointers can't be slices,
-// but rather integers, we'll choose u32 for now which will limit our sorts to 4gb of
-// data we'd want to spill to disk and implement external sort way before then
+// but rather integers, we'll choose u32 which limits the buffer to 4gb.
+//
+// Once the buffer grows past sort_buffer_size we sort it and write it out to a temp file as a
+// sorted run and start afresh, once all the rows are in we k-way merge the runs back together.
 
 impl SortExecutor {
     pub fn new(
@@ -51,6 +112,7 @@ impl SortExecutor {
         sort_expressions: Vec<SortExpression>,
     ) -> Self {
         let tuple_buffer = right_size_new_to(source.column_count());
+        let interrupts = InterruptCheck::new(Arc::clone(&session));
         SortExecutor {
             source,
             session,
@@ -60,8 +122,20 @@ impl SortExecutor {
             state: State::Ready,
             tuple_buffer,
             freq: 0,
+            limit: None,
+            runs: vec![],
+            merge_heap: BinaryHeap::new(),
+            interrupts,
         }
     }
+
+    /// Creates a sort that only outputs the first n rows, ie for ORDER BY .. LIMIT n.
+    /// Rather than holding onto every row the sort periodically throws away the rows that can't
+    /// make the cut, falling back to spilling if those rows outgrow the sort buffer.
+    pub fn with_limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
 }
 
 impl TupleIter for SortExecutor {
@@ -73,17 +147,21 @@ impl TupleIter for SortExecutor {
             self.state = State::Serving
         }
 
-        if let Some((start, end)) = self.sort_indexes.next() {
-            let mut slice = &self.sort_buffer[(start as usize)..(end as usize)];
-            let mut sort_datum = Datum::Null;
-            // First Ingest/throw away the sort keys.
-            for _ in 0..self.sort_expressions.len() {
-                slice = sort_datum.from_sortable_bytes(slice);
-            }
-            for datum in &mut self.tuple_buffer {
-                slice = datum.from_sortable_bytes(slice);
+        if !self.runs.is_empty() {
+            if let Some(Reverse((entry, run_idx))) = self.merge_heap.pop() {
+                self.read_entry(&entry);
+                if let Some(next) = self.runs[run_idx].next_entry()? {
+                    self.merge_heap.push(Reverse((next, run_idx)));
+                }
+            } else {
+                // Dropping the runs removes the temp files.
+                self.runs = vec![];
+                self.state = State::Done
             }
-            self.freq.read_sortable_bytes(SortOrder::Asc, slice);
+        } else if let Some((start, end)) = self.sort_indexes.next() {
+            let sort_buffer = std::mem::take(&mut self.sort_buffer);
+            self.read_entry(&sort_buffer[(start as usize)..(end as usize)]);
+            self.sort_buffer = sort_buffer;
         } else {
             // Free up all our memory here.
             self.sort_buffer = vec![];
@@ -112,17 +190,29 @@ impl SortExecutor {
     fn ingest(&mut self) -> Result<(), ExecutionError> {
         // Try and size our buffers big enough initially that malloc will
         // mmap and be able to grow via realloc without memcopy'ing.
-        self.sort_buffer = Vec::with_capacity(128 * 1024 * 1024);
-        let mut sort_indexes =
-            Vec::with_capacity(128 * 1024 * 1024 / std::mem::size_of::<(u32, u32)>());
+        // Limited sorts only hold onto a small number of rows at a time.
+        let buffer_size = self.buffer_size();
+        let mut sort_indexes = if self.limit.is_some() {
+            Vec::with_capacity(LIMITED_SORT_INITIAL_ROWS)
+        } else {
+            let initial_size = buffer_size.min(DEFAULT_SORT_BUFFER_SIZE as usize);
+            self.sort_buffer = Vec::with_capacity(initial_size);
+            Vec::with_capacity(initial_size / std::mem::size_of::<(u32, u32)>())
+        };
 
         while let Some((tuple, freq)) = self.source.next()? {
+            self.interrupts.tick()?;
             let start = self.sort_buffer.len() as u32;
 
             for sort_expr in &mut self.sort_expressions {
                 let datum = sort_expr.expression.eval_scalar(&self.session, tuple);
-                datum.as_sortable_bytes(sort_expr.ordering, &mut self.sort_buffer);
+                datum.as_sortable_bytes_with_nulls(
+                    sort_expr.ordering,
+                    sort_expr.nulls,
+                    &mut self.sort_buffer,
+                );
             }
+            check_out_of_range(&self.session)?;
 
             for datum in tuple {
                 datum.as_sortable_bytes(SortOrder::Asc, &mut self.sort_buffer);
@@ -130,22 +220,143 @@ impl SortExecutor {
             freq.write_sortable_bytes(SortOrder::Asc, &mut self.sort_buffer);
 
             if self.sort_buffer.len() > u32::MAX as usize {
-                panic!("Oversized sort, external sort not yet implemented");
+                panic!("Oversized sort row, rows must be under 4gb");
             }
             let end = self.sort_buffer.len() as u32;
             sort_indexes.push((start, end));
+
+            if let Some(limit) = self.limit {
+                let over_budget = self.sort_buffer.len() >= buffer_size;
+                if over_budget || sort_indexes.len() >= Self::limited_capacity(limit) {
+                    self.truncate_to_limit(&mut sort_indexes, limit);
+                }
+                // If the rows making the cut take up most of the budget by themselves we give
+                // up on the limit and spill like any other sort, the limit above us still cuts
+                // the output off.
+                if self.sort_buffer.len() >= buffer_size / 2 {
+                    self.limit = None;
+                    self.spill(&mut sort_indexes)?;
+                }
+            } else if self.sort_buffer.len() >= buffer_size {
+                self.spill(&mut sort_indexes)?;
+            }
+        }
+
+        if let Some(limit) = self.limit {
+            self.truncate_to_limit(&mut sort_indexes, limit);
+        } else if !self.runs.is_empty() {
+            // Once we've started spilling the remainder goes out as a run too so everything
+            // can be merged together.
+            if !sort_indexes.is_empty() {
+                self.spill(&mut sort_indexes)?;
+            }
+            self.sort_buffer = vec![];
+            for (run_idx, run) in self.runs.iter_mut().enumerate() {
+                if let Some(entry) = run.next_entry()? {
+                    self.merge_heap.push(Reverse((entry, run_idx)));
+                }
+            }
+        } else {
+            self.sort(&mut sort_indexes);
         }
 
-        let sort_buffer = &mut self.sort_buffer;
+        self.sort_indexes = sort_indexes.into_iter();
+
+        Ok(())
+    }
+
+    /// The number of bytes we'll buffer before spilling, from the sort_buffer_size session
+    /// variable.
+    fn buffer_size(&self) -> usize {
+        let size = match self.session.variable(SORT_BUFFER_SIZE_VARIABLE) {
+            Some((Datum::Integer(size), _)) => size as i64,
+            Some((Datum::BigInt(size), _)) => size,
+            _ => DEFAULT_SORT_BUFFER_SIZE,
+        };
+        // Leave some headroom under the 4gb our u32 pointers can address for the row that
+        // pushes us over.
+        size.max(1).min(u32::MAX as i64 / 2) as usize
+    }
+
+    /// Sorts the buffered rows and writes them out to disk as a sorted run, clearing the buffer
+    /// ready for the next lot of rows.
+    fn spill(&mut self, sort_indexes: &mut Vec<(u32, u32)>) -> Result<(), ExecutionError> {
+        self.sort(sort_indexes);
+        let sort_buffer = &self.sort_buffer;
+        let entries = sort_indexes
+            .iter()
+            .map(|(start, end)| &sort_buffer[(*start as usize)..(*end as usize)]);
+        self.runs.push(SortedRun::write(entries)?);
+        sort_indexes.clear();
+        self.sort_buffer.clear();
+        Ok(())
+    }
+
+    /// Reads a tuple and its freq out of an entry, skipping over the sort keys.
+    fn read_entry(&mut self, mut slice: &[u8]) {
+        let mut sort_datum = Datum::Null;
+        // First Ingest/throw away the sort keys.
+        for _ in 0..self.sort_expressions.len() {
+            slice = sort_datum.from_sortable_bytes(slice);
+        }
+        for datum in &mut self.tuple_buffer {
+            slice = datum.from_sortable_bytes(slice);
+        }
+        self.freq.read_sortable_bytes(SortOrder::Asc, slice);
+    }
+
+    fn sort(&self, sort_indexes: &mut Vec<(u32, u32)>) {
+        let sort_buffer = &self.sort_buffer;
         sort_indexes.sort_unstable_by(|(start1, end1), (start2, end2)| {
             let a = &sort_buffer[(*start1 as usize)..(*end1 as usize)];
             let b = &sort_buffer[(*start2 as usize)..(*end2 as usize)];
             a.cmp(b)
         });
+    }
 
-        self.sort_indexes = sort_indexes.into_iter();
+    /// The number of rows a limited sort buffers up before throwing away the excess rows.
+    fn limited_capacity(limit: i64) -> usize {
+        std::cmp::max(
+            (limit.max(0) as usize).saturating_mul(2),
+            LIMITED_SORT_INITIAL_ROWS,
+        )
+    }
 
-        Ok(())
+    /// Sorts the rows and throws away all but the first n rows, compacting the sort buffer.
+    fn truncate_to_limit(&mut self, sort_indexes: &mut Vec<(u32, u32)>, limit: i64) {
+        self.sort(sort_indexes);
+
+        let mut row_count = 0;
+        let keep = sort_indexes
+            .iter()
+            .take_while(|(start, end)| {
+                let fits = row_count < limit;
+                row_count += self.entry_freq(*start, *end);
+                fits
+            })
+            .count();
+        sort_indexes.truncate(keep);
+
+        let mut compacted = Vec::with_capacity(self.sort_buffer.len());
+        for (start, end) in sort_indexes.iter_mut() {
+            let new_start = compacted.len() as u32;
+            compacted.extend_from_slice(&self.sort_buffer[(*start as usize)..(*end as usize)]);
+            *start = new_start;
+            *end = compacted.len() as u32;
+        }
+        self.sort_buffer = compacted;
+    }
+
+    /// Reads the freq back out of an entry in the sort buffer
+    fn entry_freq(&self, start: u32, end: u32) -> i64 {
+        let mut slice = &self.sort_buffer[(start as usize)..(end as usize)];
+        let mut datum = Datum::Null;
+        for _ in 0..(self.sort_expressions.len() + self.tuple_buffer.len()) {
+            slice = datum.from_sortable_bytes(slice);
+        }
+        let mut freq = 0_i64;
+        freq.read_sortable_bytes(SortOrder::Asc, slice);
+        freq
     }
 }
 
@@ -154,7 +365,8 @@ mod tests {
     use super::*;
     use crate::point_in_time::values::ValuesExecutor;
     use ast::expr::{CompiledColumnReference, Expression};
-    use data::DataType;
+    use data::{DataType, NullOrder};
+    use std::sync::atomic::Ordering;
 
     #[test]
     fn test_sort_executor() -> Result<(), ExecutionError> {
@@ -173,6 +385,7 @@ mod tests {
             vec![
                 SortExpression {
                     ordering: SortOrder::Desc,
+                    nulls: None,
                     expression: Expression::CompiledColumnReference(CompiledColumnReference {
                         offset: 0,
                         datatype: DataType::Integer,
@@ -180,6 +393,7 @@ mod tests {
                 },
                 SortExpression {
                     ordering: SortOrder::Asc,
+                    nulls: None,
                     expression: Expression::CompiledColumnReference(CompiledColumnReference {
                         offset: 1,
                         datatype: DataType::Text,
@@ -204,4 +418,190 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_sort_executor_with_limit() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let values: Vec<_> = (0..10000).rev().map(|i| vec![Datum::from(i)]).collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+
+        let mut executor = SortExecutor::new(
+            session,
+            source,
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: None,
+                expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                }),
+            }],
+        )
+        .with_limit(3);
+
+        assert_eq!(executor.next()?, Some(([Datum::from(0)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::from(1)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::from(2)].as_ref(), 1)));
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_executor_with_huge_limit() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let values: Vec<_> = (0..3).rev().map(|i| vec![Datum::from(i)]).collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+
+        let mut executor = SortExecutor::new(
+            session,
+            source,
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: None,
+                expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                }),
+            }],
+        )
+        .with_limit(i64::MAX);
+
+        assert_eq!(executor.next()?, Some(([Datum::from(0)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::from(1)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::from(2)].as_ref(), 1)));
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_executor_nulls_last() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let values = vec![
+            vec![Datum::from(2)],
+            vec![Datum::Null],
+            vec![Datum::from(1)],
+        ];
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+
+        let mut executor = SortExecutor::new(
+            session,
+            source,
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: Some(NullOrder::Last),
+                expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                }),
+            }],
+        );
+
+        assert_eq!(executor.next()?, Some(([Datum::from(1)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::from(2)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::Null].as_ref(), 1)));
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_executor_spills() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert(SORT_BUFFER_SIZE_VARIABLE.to_string(), Datum::from(1024));
+        let values: Vec<_> = (0..1000)
+            .map(|i| vec![Datum::from((i * 7) % 1000), Datum::from("x")])
+            .collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+
+        let mut executor = SortExecutor::new(
+            session,
+            source,
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: None,
+                expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                }),
+            }],
+        );
+
+        for i in 0..1000 {
+            assert_eq!(
+                executor.next()?,
+                Some(([Datum::from(i), Datum::from("x")].as_ref(), 1))
+            );
+        }
+        assert!(executor.runs.len() > 1);
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_executor_with_limit_spills() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert(SORT_BUFFER_SIZE_VARIABLE.to_string(), Datum::from(1024));
+        let values: Vec<_> = (0..1000)
+            .map(|i| vec![Datum::from((i * 7) % 1000), Datum::from("x")])
+            .collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
+
+        let mut executor = SortExecutor::new(
+            session,
+            source,
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: None,
+                expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                }),
+            }],
+        )
+        .with_limit(500);
+
+        for i in 0..500 {
+            assert_eq!(
+                executor.next()?,
+                Some(([Datum::from(i), Datum::from("x")].as_ref(), 1))
+            );
+        }
+        assert!(executor.runs.len() > 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_executor_killed() {
+        let session = Arc::new(Session::new(1));
+        session.kill_flag.store(true, Ordering::Relaxed);
+        let values: Vec<_> = (0..10000).map(|i| vec![Datum::from(i)]).collect();
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+
+        let mut executor = SortExecutor::new(
+            session,
+            source,
+            vec![SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: None,
+                expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: 0,
+                    datatype: DataType::Integer,
+                }),
+            }],
+        );
+
+        assert_eq!(executor.next(), Err(ExecutionError::Interrupted));
+    }
 }
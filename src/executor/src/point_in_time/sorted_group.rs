@@ -1,8 +1,10 @@
-use crate::aggregate_expression::{AggregateExpression, EvalAggregateRow};
+use crate::aggregate_expression::{fold_tuple, AggregateExpression, EvalAggregateRow};
+use crate::interrupt::InterruptCheck;
 use crate::point_in_time::BoxedExecutor;
-use crate::utils::{right_size_new, transmute_muf_buf};
+use crate::utils::{check_out_of_range, right_size_new, transmute_muf_buf};
 use crate::ExecutionError;
 use ast::expr::Expression;
+use ast::rel::point_in_time::AggregateMode;
 use data::{Datum, PeekableIter, Session, TupleIter};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -14,14 +16,18 @@ use std::sync::Arc;
 /// grouping keys must have already have been evaluated...
 /// so we'll expect that the incoming tuples are prefixed with the
 /// grouping keys.
+/// When used for a partial aggregation the raw aggregate states are output instead of the
+/// finalized expressions.
 pub struct SortedGroupExecutor {
     source: PeekableIter<dyn TupleIter<E = ExecutionError>>,
     session: Arc<Session>,
     key_len: usize,
     expressions: Vec<AggregateExpression>,
+    mode: AggregateMode,
     current_state: Vec<Datum<'static>>,
     output_tuple: Vec<Datum<'static>>,
     state: State,
+    interrupts: InterruptCheck,
 }
 
 #[derive(Eq, PartialEq)]
@@ -37,18 +43,36 @@ impl SortedGroupExecutor {
         session: Arc<Session>,
         key_len: usize,
         expressions: Vec<Expression>,
+        mode: AggregateMode,
     ) -> Self {
         let expressions: Vec<_> = expressions.iter().map(AggregateExpression::from).collect();
         let current_state = expressions.initialize();
         let output_tuple = right_size_new(&expressions);
+        let interrupts = InterruptCheck::new(Arc::clone(&session));
         SortedGroupExecutor {
             source: PeekableIter::from(source),
             session,
             key_len,
             expressions,
+            mode,
             current_state,
             output_tuple,
             state: State::Initial,
+            interrupts,
+        }
+    }
+}
+
+impl SortedGroupExecutor {
+    /// Renders the output tuple from the current state, partial aggregations output the state
+    /// as is so there's nothing to do.
+    fn finalize_group(&mut self) {
+        if self.mode != AggregateMode::Partial {
+            self.expressions.finalize(
+                &self.session,
+                &self.current_state,
+                transmute_muf_buf(&mut self.output_tuple),
+            );
         }
     }
 }
@@ -70,14 +94,18 @@ impl TupleIter for SortedGroupExecutor {
         if self.key_len == 0 && self.state == State::Initial {
             self.expressions.reset(&mut self.current_state);
             while let Some((tuple, freq)) = self.source.next()? {
-                self.expressions
-                    .apply(&self.session, tuple, freq, &mut self.current_state);
+                self.interrupts.tick()?;
+                fold_tuple(
+                    &mut self.expressions,
+                    &self.session,
+                    self.mode,
+                    self.key_len,
+                    tuple,
+                    freq,
+                    &mut self.current_state,
+                );
             }
-            self.expressions.finalize(
-                &self.session,
-                &self.current_state,
-                transmute_muf_buf(&mut self.output_tuple),
-            );
+            self.finalize_group();
             self.state = State::Processing;
         } else if self.key_len == 0 && self.state == State::Processing {
             self.state = State::Done;
@@ -86,8 +114,15 @@ impl TupleIter for SortedGroupExecutor {
 
             let group_hash = if let Some((tuple, freq)) = self.source.next()? {
                 self.expressions.reset(&mut self.current_state);
-                self.expressions
-                    .apply(&self.session, tuple, freq, &mut self.current_state);
+                fold_tuple(
+                    &mut self.expressions,
+                    &self.session,
+                    self.mode,
+                    self.key_len,
+                    tuple,
+                    freq,
+                    &mut self.current_state,
+                );
                 hash_tuple(tuple, self.key_len)
             } else {
                 self.state = State::Done;
@@ -99,42 +134,48 @@ impl TupleIter for SortedGroupExecutor {
                     let hash = hash_tuple(tuple, self.key_len);
                     if hash != group_hash {
                         // We've stepped into the next tuple, finalize the row and break
-                        self.expressions.finalize(
-                            &self.session,
-                            &self.current_state,
-                            transmute_muf_buf(&mut self.output_tuple),
-                        );
+                        self.finalize_group();
                         break;
                     }
-                    self.expressions
-                        .apply(&self.session, tuple, freq, &mut self.current_state);
+                    fold_tuple(
+                        &mut self.expressions,
+                        &self.session,
+                        self.mode,
+                        self.key_len,
+                        tuple,
+                        freq,
+                        &mut self.current_state,
+                    );
                     // "advance" the inter
                     self.source.lock_in();
+                    self.interrupts.tick()?;
                 } else {
                     // No next record to peek at, we need to act like we've stepped into a
                     // new key and write out our current state
-                    self.expressions.finalize(
-                        &self.session,
-                        &self.current_state,
-                        transmute_muf_buf(&mut self.output_tuple),
-                    );
+                    self.finalize_group();
                     break;
                 }
             }
         }
-        Ok(())
+        check_out_of_range(&self.session)
     }
 
     fn get(&self) -> Option<(&[Datum], i64)> {
         if self.state == State::Done {
             None
+        } else if self.mode == AggregateMode::Partial {
+            Some((&self.current_state, 1))
         } else {
             Some((&self.output_tuple, 1))
         }
     }
 
     fn column_count(&self) -> usize {
-        self.expressions.len()
+        if self.mode == AggregateMode::Partial {
+            self.expressions.state_len()
+        } else {
+            self.expressions.len()
+        }
     }
 }
 
@@ -184,12 +225,14 @@ mod tests {
                     },
                 )]
                 .into_boxed_slice(),
+                distinct: false,
                 expr_buffer: vec![].into_boxed_slice(),
                 signature: Box::new(sig),
             }),
         ];
 
-        let mut executor = SortedGroupExecutor::new(source, session, 1, expressions);
+        let mut executor =
+            SortedGroupExecutor::new(source, session, 1, expressions, AggregateMode::Complete);
 
         assert_eq!(
             executor.next()?,
@@ -229,11 +272,13 @@ mod tests {
         let expressions = vec![Expression::CompiledAggregate(CompiledAggregate {
             function: count_function.as_aggregate(),
             args: vec![].into_boxed_slice(),
+            distinct: false,
             expr_buffer: vec![].into_boxed_slice(),
             signature: Box::new(sig),
         })];
 
-        let mut executor = SortedGroupExecutor::new(source, session, 0, expressions);
+        let mut executor =
+            SortedGroupExecutor::new(source, session, 0, expressions, AggregateMode::Complete);
 
         assert_eq!(
             executor.next()?,
@@ -243,4 +288,53 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_sorted_group_executor_partial_and_final() -> Result<(), ExecutionError> {
+        let session = Arc::new(Session::new(1));
+        let (sig, count_function) = Registry::default()
+            .resolve_function(&FunctionSignature {
+                name: "count",
+                args: vec![],
+                ret: DataType::Null,
+            })
+            .unwrap();
+        let expressions = vec![Expression::CompiledAggregate(CompiledAggregate {
+            function: count_function.as_aggregate(),
+            args: vec![].into_boxed_slice(),
+            distinct: false,
+            expr_buffer: vec![].into_boxed_slice(),
+            signature: Box::new(sig),
+        })];
+
+        // A partial aggregation over no rows still has to output the initial state
+        let values = vec![];
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+        let mut partial = SortedGroupExecutor::new(
+            source,
+            Arc::clone(&session),
+            0,
+            expressions.clone(),
+            AggregateMode::Partial,
+        );
+        assert_eq!(partial.next()?, Some(([Datum::from(0 as i64)].as_ref(), 1)));
+        assert_eq!(partial.next()?, None);
+
+        // The final aggregation merges the count states
+        let states = vec![
+            vec![Datum::from(2 as i64)],
+            vec![Datum::from(0 as i64)],
+            vec![Datum::from(3 as i64)],
+        ];
+        let source = Box::from(ValuesExecutor::new(Box::from(states.into_iter()), 1));
+        let mut executor =
+            SortedGroupExecutor::new(source, session, 0, expressions, AggregateMode::Final);
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from(5 as i64)].as_ref(), 1))
+        );
+        assert_eq!(executor.next()?, None);
+
+        Ok(())
+    }
 }
@@ -1,20 +1,129 @@
 use crate::point_in_time::BoxedExecutor;
+use crate::scalar_expression::EvalScalar;
+use crate::utils::check_out_of_range;
 use crate::ExecutionError;
-use data::{Datum, LogicalTimestamp, PeekableIter, TupleIter};
-use storage::Table;
+use ast::rel::logical::{BulkLoad, FulltextIndex, Partitioning, SecondaryIndex};
+use ast::rel::point_in_time::Upsert;
+use data::fulltext::postings;
+use data::index::index_tuple;
+use data::{Datum, LogicalTimestamp, PeekableIter, Session, TupleIter};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use storage::{Table, Transaction, Writer};
+
+/// The size (in bytes) at which we'll cut off a write batch and write it out to storage.
+const MAX_BATCH_SIZE: usize = 16 * 1024 * 1024;
+
+/// Bulk loads don't need to share nicely with other writers so can use bigger batches.
+const BULK_LOAD_BATCH_SIZE: usize = 64 * 1024 * 1024;
 
 /// When advance is called this simply inserts all tuples
-/// into the table
+/// into the table, or when delete_matching is set removes all copies of each tuple from the
+/// table. The number of rows written is recorded against the session's affected rows.
+/// For partitioned tables each row is routed to the partition covering its partition column.
+/// Any full-text and secondary indexes are written to in the same batch as the rows they index.
+/// The source is read as of the session's read timestamp, taken as the statement was planned,
+/// and each batch is written after it. If another writer has touched the primary key of any row
+/// written since the read timestamp we'll bail out with a write conflict rather than writing
+/// based on stale reads.
+/// Bulk loads into empty tables skip reading back the existing rows as each row is written, so
+/// any rows repeated within the load end up in the table just once, they're not checked for
+/// conflicts either.
+/// Upserts first retract any existing rows sharing the primary key of the row being inserted,
+/// for ON DUPLICATE KEY UPDATE the row written is then the existing row with the assignments
+/// applied. Rows written earlier in the statement are tracked in memory as they may not have
+/// been committed yet.
+/// Rows being inserted with a null in one of the NOT NULL columns fail the statement.
+/// Inserts that are part of a transaction add their writes to it in one go rather than
+/// committing them.
 pub struct TableInsertExecutor {
     source: PeekableIter<dyn TupleIter<E = ExecutionError>>,
+    session: Arc<Session>,
     table: Table,
+    partitioning: Option<Partitioning>,
+    fulltext_indexes: Vec<FulltextIndex>,
+    secondary_indexes: Vec<SecondaryIndex>,
+    delete_matching: bool,
+    bulk_load: Option<BulkLoad>,
+    upsert: Option<Upsert>,
+    not_null_columns: Vec<(usize, String)>,
+    transaction: Option<Arc<Mutex<Transaction>>>,
 }
 
+/// The rows written so far by an upsert keyed by their primary key, None if the rows for a key
+/// have been retracted.
+type WrittenRows = HashMap<Vec<Datum<'static>>, Option<(Vec<Datum<'static>>, usize)>>;
+
 impl TableInsertExecutor {
-    pub fn new(source: BoxedExecutor, table: Table) -> Self {
+    pub fn new(
+        session: Arc<Session>,
+        source: BoxedExecutor,
+        table: Table,
+        partitioning: Option<Partitioning>,
+        fulltext_indexes: Vec<FulltextIndex>,
+        secondary_indexes: Vec<SecondaryIndex>,
+        delete_matching: bool,
+    ) -> Self {
         TableInsertExecutor {
             source: PeekableIter::from(source),
+            session,
             table,
+            partitioning,
+            fulltext_indexes,
+            secondary_indexes,
+            delete_matching,
+            bulk_load: None,
+            upsert: None,
+            not_null_columns: vec![],
+            transaction: None,
+        }
+    }
+
+    /// Turns on the bulk load behaviour, used by LOAD DATA.
+    pub fn with_bulk_load(mut self, bulk_load: Option<BulkLoad>) -> Self {
+        self.bulk_load = bulk_load;
+        self
+    }
+
+    /// Turns the insert into an upsert, used by REPLACE and ON DUPLICATE KEY UPDATE.
+    pub fn with_upsert(mut self, upsert: Option<Upsert>) -> Self {
+        self.upsert = upsert;
+        self
+    }
+
+    /// Sets the (offset, name) of the columns that may not be null.
+    pub fn with_not_null_columns(mut self, not_null_columns: Vec<(usize, String)>) -> Self {
+        self.not_null_columns = not_null_columns;
+        self
+    }
+
+    /// Adds the writes to the transaction rather than committing them.
+    pub fn with_transaction(mut self, transaction: Arc<Mutex<Transaction>>) -> Self {
+        self.transaction = Some(transaction);
+        self
+    }
+
+    /// Returns true if the table (or all of its partitions) is empty.
+    fn target_is_empty(&self) -> Result<bool, ExecutionError> {
+        if let Some(partitioning) = &self.partitioning {
+            for partition in &partitioning.partitions {
+                if partition
+                    .table
+                    .full_scan(LogicalTimestamp::MAX)
+                    .next()?
+                    .is_some()
+                {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        } else {
+            Ok(self
+                .table
+                .full_scan(LogicalTimestamp::MAX)
+                .next()?
+                .is_none())
         }
     }
 }
@@ -23,24 +132,135 @@ impl TupleIter for TableInsertExecutor {
     type E = ExecutionError;
 
     fn advance(&mut self) -> Result<(), ExecutionError> {
+        // Nothing to read back from an empty table so bulk loads can write blindly, unless
+        // earlier statements in the transaction have written to it.
+        let blind_writes = self.bulk_load.is_some()
+            && !self.delete_matching
+            && self.transaction.is_none()
+            && self.target_is_empty()?;
+        let sync = self.bulk_load.map_or(true, |bulk_load| bulk_load.sync);
+        let max_batch_size = if self.transaction.is_some() {
+            usize::MAX
+        } else if self.bulk_load.is_some() {
+            BULK_LOAD_BATCH_SIZE
+        } else {
+            MAX_BATCH_SIZE
+        };
         let iter = &mut self.source;
+        let session = &self.session;
         let table = &self.table;
+        let partitioning = self.partitioning.as_ref();
+        let fulltext_indexes = &self.fulltext_indexes;
+        let secondary_indexes = &self.secondary_indexes;
+        let delete_matching = self.delete_matching;
+        let upsert = &mut self.upsert;
+        let not_null_columns = &self.not_null_columns;
+        let mut written = WrittenRows::new();
+        let mut inserted = 0_u64;
+        let mut deleted = 0_u64;
+        let mut records = 0_u64;
+        let mut duplicates = 0_u64;
+        let read_timestamp = session.scan_timestamp(LogicalTimestamp::MAX);
+        // The timestamps of the batches we've already written
+        let mut own_writes = vec![];
 
         while iter.peek()?.is_some() {
-            table.atomic_write::<_, ExecutionError>(|batch| {
+            let timestamp = LogicalTimestamp::for_write(read_timestamp);
+            let own_writes_so_far = &own_writes;
+            let write_batch = |batch: &mut Writer| -> Result<(), ExecutionError> {
                 // Chunk our write batches as we don't want to blow out our memory.
                 // We'll lose atomicity but tables are only really meant for lookup
                 // data etc not for etl type workloads
-                let mut c = 10000;
                 while let Some((tuple, freq)) = iter.next()? {
-                    batch.write_tuple(table, tuple, LogicalTimestamp::now(), freq)?;
-                    c -= 1;
-                    if c == 0 {
+                    match upsert {
+                        Some(upsert) if freq > 0 && !delete_matching => {
+                            let retracted = upsert_tuple(
+                                batch,
+                                session,
+                                upsert,
+                                &mut written,
+                                table,
+                                partitioning,
+                                fulltext_indexes,
+                                secondary_indexes,
+                                not_null_columns,
+                                tuple,
+                                timestamp,
+                                read_timestamp,
+                                own_writes_so_far,
+                            )?;
+                            inserted += 1;
+                            deleted += retracted;
+                            records += 1;
+                            if retracted > 0 {
+                                duplicates += 1;
+                            }
+                        }
+                        _ => {
+                            if freq > 0 && !delete_matching {
+                                check_not_null(not_null_columns, tuple)?;
+                            }
+                            let (table, _) = route_tuple(table, partitioning, tuple)?;
+                            if !blind_writes {
+                                batch.check_conflict(
+                                    table,
+                                    tuple,
+                                    read_timestamp,
+                                    own_writes_so_far,
+                                )?;
+                            }
+                            let freq = if delete_matching {
+                                -batch.delete_tuple(table, tuple, timestamp)?
+                            } else if blind_writes && freq > 0 {
+                                batch.blind_write_tuple(table, tuple, timestamp, freq);
+                                freq
+                            } else {
+                                batch.write_tuple(table, tuple, timestamp, freq)?;
+                                freq
+                            };
+                            if freq > 0 {
+                                inserted += freq as u64;
+                                records += freq as u64;
+                            } else {
+                                deleted += freq.abs() as u64;
+                            }
+                            write_indexes(
+                                batch,
+                                fulltext_indexes,
+                                secondary_indexes,
+                                tuple,
+                                timestamp,
+                                freq,
+                            )?;
+                        }
+                    }
+                    if batch.batch_size() >= max_batch_size {
                         break;
                     }
                 }
                 Ok(())
-            })?;
+            };
+            if let Some(transaction) = &self.transaction {
+                transaction.lock().unwrap().write(table, write_batch)?;
+            } else if sync {
+                table.atomic_write(write_batch)?;
+            } else {
+                table.atomic_write_unsynced(write_batch)?;
+            }
+            own_writes.push(timestamp);
+        }
+        if !sync {
+            table.sync_wal()?;
+        }
+        self.session
+            .affected_rows
+            .store(inserted + deleted, Ordering::Relaxed);
+        // Like mysql we only report the info for inserts of anything but a single row
+        if (deleted == 0 || self.upsert.is_some()) && records != 1 {
+            *self.session.info.write().unwrap() = format!(
+                "Records: {}  Duplicates: {}  Warnings: 0",
+                records, duplicates
+            );
         }
         Ok(())
     }
@@ -54,6 +274,162 @@ impl TupleIter for TableInsertExecutor {
     }
 }
 
+/// Returns the table (or partition) a tuple should be written to along with the index of the
+/// partition.
+fn route_tuple<'a>(
+    table: &'a Table,
+    partitioning: Option<&'a Partitioning>,
+    tuple: &[Datum],
+) -> Result<(&'a Table, usize), ExecutionError> {
+    if let Some(partitioning) = partitioning {
+        let value = &tuple[partitioning.column];
+        let idx = partitioning.partition_for(value).ok_or_else(|| {
+            ExecutionError::NoPartitionForValue(
+                value.typed_with(partitioning.column_type).to_string(),
+            )
+        })?;
+        Ok((&partitioning.partitions[idx].table, idx))
+    } else {
+        Ok((table, 0))
+    }
+}
+
+/// Errors if any of the NOT NULL columns of the tuple are null.
+fn check_not_null(
+    not_null_columns: &[(usize, String)],
+    tuple: &[Datum],
+) -> Result<(), ExecutionError> {
+    match not_null_columns
+        .iter()
+        .find(|(offset, _)| tuple[*offset].is_null())
+    {
+        Some((_, column)) => Err(ExecutionError::NullNotAllowed(column.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Writes the full-text and secondary index entries for a tuple.
+fn write_indexes(
+    batch: &mut Writer,
+    fulltext_indexes: &[FulltextIndex],
+    secondary_indexes: &[SecondaryIndex],
+    tuple: &[Datum],
+    timestamp: LogicalTimestamp,
+    freq: i64,
+) -> Result<(), ExecutionError> {
+    if freq != 0 {
+        for index in fulltext_indexes {
+            for posting in postings(tuple, index.column) {
+                batch.write_tuple(&index.table, &posting, timestamp, freq)?;
+            }
+        }
+        for index in secondary_indexes {
+            let row = index_tuple(tuple, &index.columns);
+            batch.write_tuple(&index.table, &row, timestamp, freq)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a single row of an upsert, retracting any existing rows with the same primary key.
+/// Returns the number of rows retracted.
+#[allow(clippy::too_many_arguments)]
+fn upsert_tuple(
+    batch: &mut Writer,
+    session: &Session,
+    upsert: &mut Upsert,
+    written: &mut WrittenRows,
+    table: &Table,
+    partitioning: Option<&Partitioning>,
+    fulltext_indexes: &[FulltextIndex],
+    secondary_indexes: &[SecondaryIndex],
+    not_null_columns: &[(usize, String)],
+    tuple: &[Datum],
+    timestamp: LogicalTimestamp,
+    read_timestamp: LogicalTimestamp,
+    own_writes: &[LogicalTimestamp],
+) -> Result<u64, ExecutionError> {
+    let tables: Vec<&Table> = if let Some(partitioning) = partitioning {
+        partitioning
+            .partitions
+            .iter()
+            .map(|partition| &partition.table)
+            .collect()
+    } else {
+        vec![table]
+    };
+    let key: Vec<_> = tuple[..upsert.key_len]
+        .iter()
+        .map(Datum::as_static)
+        .collect();
+
+    // Rows we've written ourselves take precedence over what's in storage
+    let mut existing = vec![];
+    if let Some(written_row) = written.get(&key) {
+        existing.extend(written_row.iter().map(|(row, idx)| (row.clone(), 1, *idx)));
+    } else {
+        for (idx, table) in tables.iter().enumerate() {
+            let mut rows = table.range_scan(Some(&key), Some(&key), read_timestamp);
+            while let Some((row, freq)) = rows.next()? {
+                batch.check_conflict(table, row, read_timestamp, own_writes)?;
+                existing.push((
+                    row.iter().map(Datum::as_static).collect::<Vec<_>>(),
+                    freq,
+                    idx,
+                ));
+            }
+        }
+    }
+
+    let mut retracted = 0;
+    for (row, freq, idx) in &existing {
+        batch.write_tuple(tables[*idx], row, timestamp, -freq)?;
+        write_indexes(
+            batch,
+            fulltext_indexes,
+            secondary_indexes,
+            row,
+            timestamp,
+            -freq,
+        )?;
+        retracted += *freq as u64;
+    }
+    written.insert(key, None);
+
+    let row: Vec<_> = match existing.first() {
+        Some((existing_row, _, _)) if !upsert.assignments.is_empty() => {
+            let source: Vec<_> = existing_row
+                .iter()
+                .chain(tuple.iter())
+                .map(Datum::as_static)
+                .collect();
+            let mut row = existing_row.clone();
+            for (offset, expression) in &mut upsert.assignments {
+                row[*offset] = expression.eval_scalar(session, &source).as_static();
+            }
+            check_out_of_range(session)?;
+            row
+        }
+        _ => tuple.iter().map(Datum::as_static).collect(),
+    };
+    check_not_null(not_null_columns, &row)?;
+
+    let (target, idx) = route_tuple(table, partitioning, &row)?;
+    batch.check_conflict(target, &row, read_timestamp, own_writes)?;
+    batch.write_tuple(target, &row, timestamp, 1)?;
+    write_indexes(
+        batch,
+        fulltext_indexes,
+        secondary_indexes,
+        &row,
+        timestamp,
+        1,
+    )?;
+    let key = row[..upsert.key_len].to_vec();
+    written.insert(key, Some((row, idx)));
+    Ok(retracted)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -67,7 +443,14 @@ mod tests {
         let mut catalog = Catalog::new_for_test().unwrap();
 
         catalog
-            .create_table("default", "test", &[("a".to_string(), DataType::Integer)])
+            .create_table(
+                "default",
+                "test",
+                &[("a".to_string(), DataType::Integer)],
+                &[],
+                &[],
+                &[],
+            )
             .unwrap();
         let table = if let TableOrView::Table(table) = catalog.item("default", "test").unwrap().item
         {
@@ -83,8 +466,18 @@ mod tests {
         ];
         let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 2));
 
-        let mut executor = TableInsertExecutor::new(source, table.clone());
+        let session = Arc::new(Session::new(1));
+        let mut executor = TableInsertExecutor::new(
+            Arc::clone(&session),
+            source,
+            table.clone(),
+            None,
+            vec![],
+            vec![],
+            false,
+        );
         assert_eq!(executor.next()?, None);
+        assert_eq!(session.affected_rows.load(Ordering::Relaxed), 3);
 
         let mut table_iter = table.full_scan(LogicalTimestamp::MAX);
 
@@ -93,4 +486,153 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_bulk_load() -> Result<(), ExecutionError> {
+        let mut catalog = Catalog::new_for_test().unwrap();
+
+        catalog
+            .create_table(
+                "default",
+                "test",
+                &[("a".to_string(), DataType::Integer)],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        let table = if let TableOrView::Table(table) = catalog.item("default", "test").unwrap().item
+        {
+            table
+        } else {
+            panic!()
+        };
+
+        let session = Arc::new(Session::new(1));
+        for _ in 0..2 {
+            let values = vec![vec![Datum::from(1)], vec![Datum::from(2)]];
+            let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+            let mut executor = TableInsertExecutor::new(
+                Arc::clone(&session),
+                source,
+                table.clone(),
+                None,
+                vec![],
+                vec![],
+                false,
+            )
+            .with_bulk_load(Some(BulkLoad { sync: false }));
+            assert_eq!(executor.next()?, None);
+            assert_eq!(session.affected_rows.load(Ordering::Relaxed), 2);
+        }
+
+        // The second load isn't into an empty table so has to add to the existing freqs
+        let mut table_iter = table.full_scan(LogicalTimestamp::MAX);
+        assert_eq!(table_iter.next()?, Some(([Datum::from(1)].as_ref(), 2)));
+        assert_eq!(table_iter.next()?, Some(([Datum::from(2)].as_ref(), 2)));
+        assert_eq!(table_iter.next()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_not_null() -> Result<(), ExecutionError> {
+        let mut catalog = Catalog::new_for_test().unwrap();
+
+        catalog
+            .create_table(
+                "default",
+                "test",
+                &[("a".to_string(), DataType::Integer)],
+                &[],
+                &[true],
+                &[],
+            )
+            .unwrap();
+        let table = if let TableOrView::Table(table) = catalog.item("default", "test").unwrap().item
+        {
+            table
+        } else {
+            panic!()
+        };
+
+        let values = vec![vec![Datum::from(1)], vec![Datum::Null]];
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+        let mut executor = TableInsertExecutor::new(
+            Arc::new(Session::new(1)),
+            source,
+            table.clone(),
+            None,
+            vec![],
+            vec![],
+            false,
+        )
+        .with_not_null_columns(vec![(0, "a".to_string())]);
+        assert_eq!(
+            executor.next(),
+            Err(ExecutionError::NullNotAllowed("a".to_string()))
+        );
+
+        // The whole batch is rolled back
+        assert_eq!(table.full_scan(LogicalTimestamp::MAX).next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_conflict() -> Result<(), ExecutionError> {
+        let mut catalog = Catalog::new_for_test().unwrap();
+
+        catalog
+            .create_table(
+                "default",
+                "test",
+                &[("a".to_string(), DataType::Integer)],
+                &[],
+                &[],
+                &[],
+            )
+            .unwrap();
+        let table = if let TableOrView::Table(table) = catalog.item("default", "test").unwrap().item
+        {
+            table
+        } else {
+            panic!()
+        };
+
+        // Someone else has written to the row since we "read" it at 100
+        table.atomic_write::<_, ExecutionError>(|batch| {
+            batch.write_tuple(&table, &[Datum::from(1)], LogicalTimestamp::new(200), 1)?;
+            Ok(())
+        })?;
+
+        let session = Arc::new(Session::new(1));
+        session.read_timestamp.store(100, Ordering::Relaxed);
+        // Inserts conflict just the same as deletes
+        for delete_matching in &[true, false] {
+            let values = vec![vec![Datum::from(1)]];
+            let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+            let mut executor = TableInsertExecutor::new(
+                Arc::clone(&session),
+                source,
+                table.clone(),
+                None,
+                vec![],
+                vec![],
+                *delete_matching,
+            );
+            let err = executor.next().unwrap_err();
+            assert!(err.is_write_conflict());
+        }
+
+        // Reading after the write there's no conflict
+        session.read_timestamp.store(201, Ordering::Relaxed);
+        let values = vec![vec![Datum::from(1)]];
+        let source = Box::from(ValuesExecutor::new(Box::from(values.into_iter()), 1));
+        let mut executor =
+            TableInsertExecutor::new(session, source, table.clone(), None, vec![], vec![], true);
+        executor.next()?;
+        assert_eq!(table.full_scan(LogicalTimestamp::MAX).next()?, None);
+
+        Ok(())
+    }
 }
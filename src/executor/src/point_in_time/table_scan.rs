@@ -1,28 +1,193 @@
+use crate::interrupt::InterruptCheck;
+use crate::point_in_time::runtime_filter::{FilterState, RuntimeFilter};
 use crate::ExecutionError;
-use data::{Datum, LogicalTimestamp, TupleIter};
+use ast::rel::point_in_time::KeyRange;
+use data::{Datum, LogicalTimestamp, Session, SortOrder, TupleIter};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 use storage::{StorageError, Table};
 
 pub struct TableScanExecutor {
-    // We must drop scan_iter first
-    scan_iter: Box<dyn TupleIter<E = StorageError>>,
-    #[allow(dead_code)]
+    // We must drop scan_iter first, None until the scan is first advanced
+    scan_iter: Option<Box<dyn TupleIter<E = StorageError>>>,
     table: Table,
+    timestamp: LogicalTimestamp,
+    // The key ranges left to scan (in reverse order), None for a full scan
+    ranges: Option<Vec<KeyRange>>,
+    // The columns to decode, None for all of them
+    column_mask: Option<Vec<bool>>,
+    runtime_filter: Option<ScanFilter>,
+    // Unset for scans that can't be killed, ie those in tests
+    interrupts: Option<InterruptCheck>,
+    // Rows read so far, added to the session's rows_examined once we're dropped
+    rows_examined: u64,
+    done: bool,
+}
+
+/// A runtime filter pushed down into the scan, along with the table columns for each of the
+/// filters keys.
+pub struct ScanFilter {
+    filter: Arc<RuntimeFilter>,
+    columns: Vec<Option<usize>>,
+    state: Option<Arc<FilterState>>,
+}
+
+impl ScanFilter {
+    pub fn new(filter: Arc<RuntimeFilter>, columns: Vec<Option<usize>>) -> Self {
+        ScanFilter {
+            filter,
+            columns,
+            state: None,
+        }
+    }
 }
 
 impl TableScanExecutor {
     pub fn new(table: Table, timestamp: LogicalTimestamp) -> Self {
+        TableScanExecutor {
+            scan_iter: None,
+            table,
+            timestamp,
+            ranges: None,
+            column_mask: None,
+            runtime_filter: None,
+            interrupts: None,
+            rows_examined: 0,
+            done: false,
+        }
+    }
+
+    /// Creates a table scan that only reads the rows within the key ranges.
+    pub fn new_with_ranges(
+        table: Table,
+        timestamp: LogicalTimestamp,
+        mut ranges: Vec<KeyRange>,
+    ) -> Self {
+        ranges.reverse();
+        let mut executor = Self::new(table, timestamp);
+        executor.done = ranges.is_empty();
+        executor.ranges = Some(ranges);
+        executor
+    }
+
+    /// Skips any rows not matching the runtime filter.
+    pub fn with_runtime_filter(mut self, runtime_filter: ScanFilter) -> Self {
+        self.runtime_filter = Some(runtime_filter);
+        self
+    }
+
+    /// Only decodes the columns flagged in the mask, the other columns come back as nulls.
+    pub fn with_column_mask(mut self, column_mask: Vec<bool>) -> Self {
+        self.column_mask = Some(column_mask);
+        self
+    }
+
+    /// Stops the scan with an error once the session's statement is killed or times out.
+    pub fn with_interrupts(mut self, session: Arc<Session>) -> Self {
+        self.interrupts = Some(InterruptCheck::new(session));
+        self
+    }
+
+    fn scan(
+        table: &Table,
+        from: Option<&[Datum]>,
+        to: Option<&[Datum]>,
+        timestamp: LogicalTimestamp,
+        column_mask: Option<&[bool]>,
+    ) -> Box<dyn TupleIter<E = StorageError>> {
+        let scan_iter: Box<dyn TupleIter<E = StorageError> + '_> =
+            Box::from(table.projected_range_scan(from, to, timestamp, column_mask));
         // The lifetime of an rocksdb iter is tied to the underlying rocksdb.
         // In our case table holds an Arc<db> so if we keep that alive we're ok.
         // so below we fudge the lifetimes to make it work
-        let scan_iter = Box::from(table.full_scan(timestamp));
-        let scan_iter = unsafe {
+        unsafe {
             std::mem::transmute::<
-                Box<dyn TupleIter<E = StorageError>>,
+                Box<dyn TupleIter<E = StorageError> + '_>,
                 Box<dyn TupleIter<E = StorageError>>,
             >(scan_iter)
-        };
+        }
+    }
+
+    /// Called on the first advance, at this point the hash join will have populated the filter.
+    /// If the filter's on the first column of the pk we can narrow down the scan to just the
+    /// range of keys from the build side, unless the scan is already limited to key ranges.
+    fn apply_runtime_filter(&mut self) {
+        if let Some(runtime_filter) = &mut self.runtime_filter {
+            if let Some(state) = runtime_filter.filter.state() {
+                if state.is_empty() {
+                    self.done = true;
+                } else if let (None, Some(key_idx)) = (
+                    &self.ranges,
+                    runtime_filter
+                        .columns
+                        .iter()
+                        .position(|column| *column == Some(0)),
+                ) {
+                    // The range needs to be in the order the first pk column is stored in
+                    let desc = self.table.pk().first() == Some(&SortOrder::Desc);
+                    let range = state.range(key_idx).map(|(min, max)| {
+                        if desc {
+                            (max.clone(), min.clone())
+                        } else {
+                            (min.clone(), max.clone())
+                        }
+                    });
+                    if let Some((from, to)) = range {
+                        self.scan_iter = Some(Self::scan(
+                            &self.table,
+                            Some(std::slice::from_ref(&from)),
+                            Some(std::slice::from_ref(&to)),
+                            self.timestamp,
+                            self.column_mask.as_deref(),
+                        ));
+                    }
+                }
+                runtime_filter.state = Some(state);
+            }
+        }
+    }
+
+    /// Advances the underlying scan, opening it on the first call and moving onto the next key
+    /// range once each one runs dry.
+    fn advance_scan(&mut self) -> Result<(), ExecutionError> {
+        if let Some(scan_iter) = &mut self.scan_iter {
+            scan_iter.advance()?;
+        }
+        while self.get().is_none() {
+            let range = match &mut self.ranges {
+                Some(ranges) => ranges.pop(),
+                None if self.scan_iter.is_none() => Some(KeyRange {
+                    from: None,
+                    to: None,
+                }),
+                None => None,
+            };
+            if let Some(range) = range {
+                let mut scan_iter = Self::scan(
+                    &self.table,
+                    range.from.as_deref(),
+                    range.to.as_deref(),
+                    self.timestamp,
+                    self.column_mask.as_deref(),
+                );
+                scan_iter.advance()?;
+                self.scan_iter = Some(scan_iter);
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
 
-        TableScanExecutor { scan_iter, table }
+impl Drop for TableScanExecutor {
+    fn drop(&mut self) {
+        if let Some(interrupts) = &self.interrupts {
+            interrupts
+                .session()
+                .rows_examined
+                .fetch_add(self.rows_examined, Ordering::Relaxed);
+        }
     }
 }
 
@@ -30,16 +195,56 @@ impl TupleIter for TableScanExecutor {
     type E = ExecutionError;
 
     fn advance(&mut self) -> Result<(), ExecutionError> {
-        self.scan_iter.advance()?;
-        Ok(())
+        if self.done {
+            return Ok(());
+        }
+        if let Some(runtime_filter) = &self.runtime_filter {
+            if runtime_filter.state.is_none() {
+                self.apply_runtime_filter();
+                if self.done {
+                    return Ok(());
+                }
+            }
+        }
+
+        loop {
+            self.advance_scan()?;
+            if self.get().is_some() {
+                self.rows_examined += 1;
+            }
+            // Rows skipped by the runtime filter count too, they still take time to read
+            if let Some(interrupts) = &mut self.interrupts {
+                interrupts.tick()?;
+            }
+            let matches = match (&self.runtime_filter, self.get()) {
+                (
+                    Some(ScanFilter {
+                        columns,
+                        state: Some(state),
+                        ..
+                    }),
+                    Some((tuple, _freq)),
+                ) => state.matches(tuple, columns),
+                _ => true,
+            };
+            if matches {
+                return Ok(());
+            }
+        }
     }
 
     fn get(&self) -> Option<(&[Datum], i64)> {
-        self.scan_iter.get()
+        if self.done {
+            None
+        } else {
+            self.scan_iter
+                .as_ref()
+                .and_then(|scan_iter| scan_iter.get())
+        }
     }
 
     fn column_count(&self) -> usize {
-        self.scan_iter.column_count()
+        self.table.column_count()
     }
 }
 
@@ -77,4 +282,116 @@ mod tests {
         assert_eq!(executor.next()?, None);
         Ok(())
     }
+
+    #[test]
+    fn test_table_scan_executor_with_ranges() -> Result<(), ExecutionError> {
+        let storage = Storage::new_in_mem()?;
+        let catalog = Catalog::new(storage).unwrap();
+        let table = if let TableOrView::Table(table) =
+            catalog.item("incresql", "databases").unwrap().item
+        {
+            table
+        } else {
+            panic!()
+        };
+
+        let point = |name: &'static str| KeyRange {
+            from: Some(vec![Datum::from(name)]),
+            to: Some(vec![Datum::from(name)]),
+        };
+        let mut executor = TableScanExecutor::new_with_ranges(
+            table.clone(),
+            LogicalTimestamp::MAX,
+            vec![
+                point("information_schema"),
+                point("missing"),
+                point("default"),
+            ],
+        );
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from("information_schema")].as_ref(), 1))
+        );
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from("default")].as_ref(), 1))
+        );
+        assert_eq!(executor.next()?, None);
+
+        let mut executor = TableScanExecutor::new_with_ranges(
+            table,
+            LogicalTimestamp::MAX,
+            vec![KeyRange {
+                from: Some(vec![Datum::from("e")]),
+                to: None,
+            }],
+        );
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from("incresql")].as_ref(), 1))
+        );
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from("information_schema")].as_ref(), 1))
+        );
+        assert_eq!(executor.next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_scan_executor_with_column_mask() -> Result<(), ExecutionError> {
+        let storage = Storage::new_in_mem()?;
+        let catalog = Catalog::new(storage).unwrap();
+        let table =
+            if let TableOrView::Table(table) = catalog.item("incresql", "tables").unwrap().item {
+                table
+            } else {
+                panic!()
+            };
+        let mut column_mask = vec![false; table.column_count()];
+        column_mask[1] = true;
+
+        let mut executor =
+            TableScanExecutor::new(table, LogicalTimestamp::MAX).with_column_mask(column_mask);
+        let (tuple, _freq) = executor.next()?.unwrap();
+        assert!(tuple[1] != Datum::Null);
+        assert!(tuple
+            .iter()
+            .enumerate()
+            .all(|(idx, datum)| idx == 1 || *datum == Datum::Null));
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_scan_executor_with_runtime_filter() -> Result<(), ExecutionError> {
+        let storage = Storage::new_in_mem()?;
+        let catalog = Catalog::new(storage).unwrap();
+        let table = if let TableOrView::Table(table) =
+            catalog.item("incresql", "databases").unwrap().item
+        {
+            table
+        } else {
+            panic!()
+        };
+
+        let runtime_filter = Arc::new(RuntimeFilter::default());
+        let mut executor = TableScanExecutor::new(table.clone(), LogicalTimestamp::MAX)
+            .with_runtime_filter(ScanFilter::new(Arc::clone(&runtime_filter), vec![Some(0)]));
+        // The hash join populates the filter before the scan is read
+        runtime_filter.populate(vec![vec![Datum::from("incresql")]].iter());
+
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from("incresql")].as_ref(), 1))
+        );
+        assert_eq!(executor.next()?, None);
+
+        // Empty build side means we don't even need to scan
+        let runtime_filter = Arc::new(RuntimeFilter::default());
+        runtime_filter.populate(vec![].iter());
+        let mut executor = TableScanExecutor::new(table, LogicalTimestamp::MAX)
+            .with_runtime_filter(ScanFilter::new(runtime_filter, vec![Some(0)]));
+        assert_eq!(executor.next()?, None);
+        Ok(())
+    }
 }
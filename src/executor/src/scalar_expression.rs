@@ -42,7 +42,10 @@ impl EvalScalar for Expression {
                 row[column_reference.offset].ref_clone()
             }
             // These should be compiled away by this point
-            Expression::FunctionCall(_) | Expression::Cast(_) | Expression::ColumnReference(_) => {
+            Expression::FunctionCall(_)
+            | Expression::Cast(_)
+            | Expression::ColumnReference(_)
+            | Expression::Subquery(_) => {
                 panic!("Hit uncompiled expression during evaluation {:?}", self)
             }
             Expression::CompiledAggregate(_) => {
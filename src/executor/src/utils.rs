@@ -1,4 +1,5 @@
-use data::Datum;
+use crate::ExecutionError;
+use data::{Datum, Session};
 
 /// Initializes a buffer(vector) to the same size as the passed in vector and returns it.
 /// Fills the buffer with the default values
@@ -12,6 +13,15 @@ pub fn right_size_new_to<T: Default>(size: usize) -> Vec<T> {
     (0..size).map(|_| T::default()).collect()
 }
 
+/// Functions raise overflows etc on the session rather than failing, this turns them into an
+/// error for executors that have just evaluated expressions.
+pub(crate) fn check_out_of_range(session: &Session) -> Result<(), ExecutionError> {
+    match session.take_out_of_range() {
+        Some(message) => Err(ExecutionError::OutOfRange(message)),
+        None => Ok(()),
+    }
+}
+
 /// Used to transmute a datum buffer from static to 'a so we can insert data into it
 pub(crate) fn transmute_muf_buf<'a>(buf: &'a mut [Datum<'static>]) -> &'a mut [Datum<'a>] {
     unsafe {
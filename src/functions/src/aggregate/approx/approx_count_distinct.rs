@@ -0,0 +1,175 @@
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, SortOrder};
+
+/// The number of bits of the hash used to pick a register, 2^12 registers gives a standard error
+/// of around 1.6%.
+const PRECISION: u32 = 12;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// A HyperLogLog count distinct. The state is the sketch's registers as a bytea, sketches for
+/// the same registers count are merged by taking the max of each register so partial
+/// aggregates (and stored states) can be combined without rereading the inputs.
+/// Values can't be removed from a sketch so there's no retracting.
+#[derive(Debug)]
+struct ApproxCountDistinct {}
+
+impl AggregateFunction for ApproxCountDistinct {
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        // Empty until the first value comes in, empty is the same as all zeros
+        state[0] = Datum::from(vec![]);
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if args[0].is_null() || freq <= 0 {
+            return;
+        }
+        let mut bytes = vec![];
+        args[0].as_sortable_bytes(SortOrder::Asc, &mut bytes);
+        let hash = hash_bytes(&bytes);
+        let register = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION).leading_zeros().min(64 - PRECISION) + 1) as u8;
+
+        let registers = registers_mut(&mut state[0]);
+        if registers[register] < rank {
+            registers[register] = rank;
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        let input = input_state[0].as_bytea();
+        if input.is_empty() {
+            return;
+        }
+        let registers = registers_mut(&mut state[0]);
+        for (register, rank) in registers.iter_mut().zip(input) {
+            if *register < *rank {
+                *register = *rank;
+            }
+        }
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        Datum::from(estimate(state[0].as_bytea()))
+    }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
+}
+
+/// Gets the registers to update in place, allocating them on first use.
+fn registers_mut<'a>(state: &'a mut Datum<'static>) -> &'a mut [u8] {
+    if !matches!(state, Datum::ByteAOwned(registers) if registers.len() == REGISTERS) {
+        let mut registers = state.as_bytea().to_vec();
+        registers.resize(REGISTERS, 0);
+        *state = Datum::from(registers);
+    }
+    if let Datum::ByteAOwned(registers) = state {
+        registers
+    } else {
+        unreachable!()
+    }
+}
+
+/// The standard HyperLogLog estimate with the linear counting correction for small cardinalities.
+fn estimate(registers: &[u8]) -> i64 {
+    if registers.is_empty() {
+        return 0;
+    }
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = registers
+        .iter()
+        .map(|rank| 2_f64.powi(-(*rank as i32)))
+        .sum();
+    let raw = alpha * m * m / sum;
+    let zeros = registers.len() - registers.iter().filter(|rank| **rank != 0).count();
+    let estimate = if raw <= 2.5 * m && zeros > 0 {
+        m * (m / zeros as f64).ln()
+    } else {
+        raw
+    };
+    estimate.round() as i64
+}
+
+/// The sketches are persisted so we need a hash that's stable across builds, this is FNV-1a with
+/// a murmur3 finalizer to spread the bits about.
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "approx_count_distinct",
+        vec![DataType::Null],
+        DataType::BigInt,
+        FunctionType::Aggregate(&ApproxCountDistinct {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "approx_count_distinct",
+        args: vec![],
+        ret: DataType::BigInt,
+    };
+
+    #[test]
+    fn test_small_counts() {
+        let function = ApproxCountDistinct {};
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+        assert_eq!(function.finalize(&DUMMY_SIG, &state), Datum::from(0_i64));
+
+        for i in &[1, 2, 3, 2, 1] {
+            function.apply(&DUMMY_SIG, &[Datum::from(*i)], 1, &mut state);
+        }
+        function.apply(&DUMMY_SIG, &[Datum::Null], 1, &mut state);
+        assert_eq!(function.finalize(&DUMMY_SIG, &state), Datum::from(3_i64));
+    }
+
+    #[test]
+    fn test_large_counts_and_merge() {
+        let function = ApproxCountDistinct {};
+        let mut state1 = vec![Datum::Null];
+        let mut state2 = vec![Datum::Null];
+        function.initialize(&mut state1);
+        function.initialize(&mut state2);
+
+        for i in 0..60000 {
+            function.apply(&DUMMY_SIG, &[Datum::from(i)], 1, &mut state1);
+        }
+        // Overlapping with the first half
+        for i in 40000..100000 {
+            function.apply(&DUMMY_SIG, &[Datum::from(i)], 1, &mut state2);
+        }
+        function.merge(&DUMMY_SIG, &state2, &mut state1);
+
+        let count = function.finalize(&DUMMY_SIG, &state1).as_bigint();
+        assert!((95000..105000).contains(&count), "{}", count);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum};
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+
+/// The t-digest compression factor, roughly the max number of centroids kept after compressing.
+const COMPRESSION: f64 = 100.0;
+/// How many centroids we let build up before compressing them again.
+const MAX_CENTROIDS: usize = 500;
+const CENTROID_SIZE: usize = 16;
+
+/// approx_percentile(value, percentile), a t-digest based percentile where the percentile is
+/// between 0 and 1.
+/// The state is the digest's centroids (mean, weight) encoded into a bytea along with the
+/// percentile we're after, digests are merged by combining their centroids and recompressing
+/// so the state stays small however many rows go in.
+#[derive(Debug)]
+struct ApproxPercentile {}
+
+impl AggregateFunction for ApproxPercentile {
+    fn state_size(&self) -> usize {
+        2
+    }
+
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::from(vec![]);
+        state[1] = Datum::Null;
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if let (Some(value), Some(percentile)) =
+            (args[0].as_maybe_double(), args[1].as_maybe_double())
+        {
+            if freq <= 0 || value.is_nan() {
+                return;
+            }
+            if state[1].is_null() {
+                state[1] = Datum::from(percentile);
+            }
+            let mut centroids = state[0].as_bytea().to_vec();
+            write_centroid(&mut centroids, value, freq as f64);
+            state[0] = Datum::from(maybe_compress(centroids));
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        if state[1].is_null() {
+            state[1] = input_state[1].as_static();
+        }
+        let input = input_state[0].as_bytea();
+        if !input.is_empty() {
+            let mut centroids = state[0].as_bytea().to_vec();
+            centroids.extend_from_slice(input);
+            state[0] = Datum::from(maybe_compress(centroids));
+        }
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        state[1]
+            .as_maybe_double()
+            .filter(|percentile| (0.0..=1.0).contains(percentile))
+            .and_then(|percentile| quantile(&read_centroids(state[0].as_bytea()), percentile))
+            .map(Datum::from)
+            .unwrap_or(Datum::Null)
+    }
+}
+
+fn write_centroid(buffer: &mut Vec<u8>, mean: f64, weight: f64) {
+    buffer.extend_from_slice(&mean.to_le_bytes());
+    buffer.extend_from_slice(&weight.to_le_bytes());
+}
+
+/// Reads the centroids back out sorted by their means.
+fn read_centroids(buffer: &[u8]) -> Vec<(f64, f64)> {
+    let mut centroids: Vec<_> = buffer
+        .chunks_exact(CENTROID_SIZE)
+        .map(|chunk| {
+            let mut mean = [0_u8; 8];
+            let mut weight = [0_u8; 8];
+            mean.copy_from_slice(&chunk[..8]);
+            weight.copy_from_slice(&chunk[8..]);
+            (f64::from_le_bytes(mean), f64::from_le_bytes(weight))
+        })
+        .collect();
+    centroids.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    centroids
+}
+
+fn maybe_compress(buffer: Vec<u8>) -> Vec<u8> {
+    if buffer.len() / CENTROID_SIZE <= MAX_CENTROIDS {
+        return buffer;
+    }
+    let centroids = read_centroids(&buffer);
+    let mut compressed = Vec::with_capacity(COMPRESSION as usize * 2 * CENTROID_SIZE);
+    for (mean, weight) in compress(&centroids) {
+        write_centroid(&mut compressed, mean, weight);
+    }
+    compressed
+}
+
+/// Merges neighbouring centroids together using the k1 scale function, this keeps the
+/// centroids small near the tails (where the accuracy matters) and lets them grow in the middle.
+fn compress(centroids: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let total: f64 = centroids.iter().map(|(_, weight)| weight).sum();
+    let k = |q: f64| COMPRESSION / (2.0 * PI) * (2.0 * q - 1.0).asin();
+    let q = |k: f64| ((k * 2.0 * PI / COMPRESSION).sin() + 1.0) / 2.0;
+
+    let mut compressed = vec![];
+    let mut iter = centroids.iter();
+    let (mut mean, mut weight) = *iter.next().unwrap();
+    let mut weight_so_far = 0.0;
+    let mut q_limit = q(k(0.0) + 1.0);
+
+    for (next_mean, next_weight) in iter {
+        if (weight_so_far + weight + next_weight) / total <= q_limit {
+            weight += next_weight;
+            mean += (next_mean - mean) * next_weight / weight;
+        } else {
+            compressed.push((mean, weight));
+            weight_so_far += weight;
+            q_limit = q(k(weight_so_far / total) + 1.0);
+            mean = *next_mean;
+            weight = *next_weight;
+        }
+    }
+    compressed.push((mean, weight));
+    compressed
+}
+
+/// Treats each centroid's weight as sitting at its mean and interpolates between them.
+fn quantile(centroids: &[(f64, f64)], percentile: f64) -> Option<f64> {
+    let (first_mean, first_weight) = *centroids.first()?;
+    let total: f64 = centroids.iter().map(|(_, weight)| weight).sum();
+    let target = percentile * total;
+
+    let mut prev_mean = first_mean;
+    let mut prev_mid = first_weight / 2.0;
+    if target <= prev_mid {
+        return Some(first_mean);
+    }
+    let mut weight_so_far = first_weight;
+    for (mean, weight) in &centroids[1..] {
+        let mid = weight_so_far + weight / 2.0;
+        if target <= mid {
+            return Some(prev_mean + (mean - prev_mean) * (target - prev_mid) / (mid - prev_mid));
+        }
+        prev_mean = *mean;
+        prev_mid = mid;
+        weight_so_far += weight;
+    }
+    Some(prev_mean)
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "approx_percentile",
+        vec![DataType::Double, DataType::Double],
+        DataType::Double,
+        FunctionType::Aggregate(&ApproxPercentile {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "approx_percentile",
+        args: vec![],
+        ret: DataType::Double,
+    };
+
+    fn percentile_of(values: impl Iterator<Item = f64>, percentile: f64) -> Datum<'static> {
+        let function = ApproxPercentile {};
+        let mut state = vec![Datum::Null, Datum::Null];
+        function.initialize(&mut state);
+        for value in values {
+            function.apply(
+                &DUMMY_SIG,
+                &[Datum::from(value), Datum::from(percentile)],
+                1,
+                &mut state,
+            );
+        }
+        function.finalize(&DUMMY_SIG, &state).as_static()
+    }
+
+    #[test]
+    fn test_small_inputs() {
+        assert_eq!(percentile_of(vec![].into_iter(), 0.5), Datum::Null);
+        assert_eq!(
+            percentile_of(vec![3.0, 1.0, 2.0, 5.0, 4.0].into_iter(), 0.5),
+            Datum::from(3.0)
+        );
+        assert_eq!(
+            percentile_of(vec![1.0, 2.0, 3.0, 4.0].into_iter(), 0.5),
+            Datum::from(2.5)
+        );
+        assert_eq!(
+            percentile_of(vec![1.0, 2.0, 3.0, 4.0].into_iter(), 1.0),
+            Datum::from(4.0)
+        );
+        assert_eq!(
+            percentile_of(vec![1.0, 2.0, 3.0, 4.0].into_iter(), 1.5),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_large_inputs_and_merge() {
+        let function = ApproxPercentile {};
+        let mut state1 = vec![Datum::Null, Datum::Null];
+        let mut state2 = vec![Datum::Null, Datum::Null];
+        function.initialize(&mut state1);
+        function.initialize(&mut state2);
+
+        for i in 0..100000 {
+            // Interleave the values between the two digests
+            let state = if i % 3 == 0 { &mut state1 } else { &mut state2 };
+            let value = ((i * 7919) % 100000) as f64;
+            function.apply(
+                &DUMMY_SIG,
+                &[Datum::from(value), Datum::from(0.9)],
+                1,
+                state,
+            );
+        }
+        assert!(state1[0].as_bytea().len() / CENTROID_SIZE <= MAX_CENTROIDS);
+        function.merge(&DUMMY_SIG, &state2, &mut state1);
+
+        let p90 = function.finalize(&DUMMY_SIG, &state1).as_double();
+        assert!((89000.0..91000.0).contains(&p90), "{}", p90);
+    }
+}
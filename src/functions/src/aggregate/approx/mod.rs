@@ -0,0 +1,9 @@
+mod approx_count_distinct;
+mod approx_percentile;
+
+use crate::registry::Registry;
+
+pub fn register_builtins(registry: &mut Registry) {
+    approx_count_distinct::register_builtins(registry);
+    approx_percentile::register_builtins(registry);
+}
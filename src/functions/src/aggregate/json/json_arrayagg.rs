@@ -0,0 +1,116 @@
+use super::{append_json, merge_json, split_json};
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonBuilder;
+use data::{DataType, Datum};
+
+/// json_arrayagg(value) (also array_agg(value)), collects the values from the group into a json
+/// array in the order they come in, nulls included.
+#[derive(Debug)]
+struct JsonArrayAgg {}
+
+impl AggregateFunction for JsonArrayAgg {
+    fn apply<'a>(
+        &self,
+        signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if state[0].is_null() {
+            state[0] = Datum::from(vec![]);
+        }
+        for _ in 0..freq {
+            append_json(&args[0], signature.args[0], &mut state[0]);
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        if state[0].is_null() {
+            state[0] = input_state[0].as_static();
+        } else if !input_state[0].is_null() {
+            merge_json(&input_state[0], &mut state[0]);
+        }
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        if state[0].is_null() {
+            return Datum::Null;
+        }
+        Datum::from(JsonBuilder::default().array(|array| {
+            for value in split_json(state[0].as_bytea()) {
+                array.push_json(value);
+            }
+        }))
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for function_name in &["json_arrayagg", "array_agg"] {
+        registry.register_function(FunctionDefinition::new(
+            *function_name,
+            vec![DataType::Null],
+            DataType::Json,
+            FunctionType::Aggregate(&JsonArrayAgg {}),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+
+    fn sig(arg: DataType) -> FunctionSignature<'static> {
+        FunctionSignature {
+            name: "json_arrayagg",
+            args: vec![arg],
+            ret: DataType::Json,
+        }
+    }
+
+    #[test]
+    fn test_json_arrayagg() {
+        let function = JsonArrayAgg {};
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+        assert_eq!(function.finalize(&sig(DataType::Text), &state), Datum::Null);
+
+        function.apply(&sig(DataType::Text), &[Datum::from("a")], 1, &mut state);
+        function.apply(&sig(DataType::Text), &[Datum::Null], 1, &mut state);
+        function.apply(&sig(DataType::Text), &[Datum::from("b")], 2, &mut state);
+
+        let mut other = vec![Datum::Null];
+        function.initialize(&mut other);
+        function.apply(&sig(DataType::Text), &[Datum::from("c")], 1, &mut other);
+        function.merge(&sig(DataType::Text), &other, &mut state);
+
+        assert_eq!(
+            function.finalize(&sig(DataType::Text), &state),
+            Datum::from(OwnedJson::parse(r#"["a",null,"b","b","c"]"#).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_json_arrayagg_types() {
+        let function = JsonArrayAgg {};
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+
+        function.apply(&sig(DataType::Integer), &[Datum::from(1)], 1, &mut state);
+        function.apply(&sig(DataType::Double), &[Datum::from(1.5)], 1, &mut state);
+        function.apply(&sig(DataType::Boolean), &[Datum::from(true)], 1, &mut state);
+        let json = Datum::from(OwnedJson::parse(r#"{"a":[1]}"#).unwrap());
+        function.apply(&sig(DataType::Json), &[json], 1, &mut state);
+
+        assert_eq!(
+            function.finalize(&sig(DataType::Json), &state),
+            Datum::from(OwnedJson::parse(r#"[1,1.5,true,{"a":[1]}]"#).unwrap())
+        );
+    }
+}
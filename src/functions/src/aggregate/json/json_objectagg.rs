@@ -0,0 +1,108 @@
+use super::{append_json, merge_json, split_json};
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonBuilder;
+use data::{DataType, Datum};
+use std::collections::BTreeMap;
+
+/// json_objectagg(key, value), collects the key value pairs from the group into a json object.
+/// Like mysql when a key appears more than once the last value wins, rows with null keys are
+/// skipped.
+#[derive(Debug)]
+struct JsonObjectAgg {}
+
+impl AggregateFunction for JsonObjectAgg {
+    fn apply<'a>(
+        &self,
+        signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if args[0].is_null() || freq <= 0 {
+            return;
+        }
+        if state[0].is_null() {
+            state[0] = Datum::from(vec![]);
+        }
+        append_json(&args[0], DataType::Text, &mut state[0]);
+        append_json(&args[1], signature.args[1], &mut state[0]);
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        if state[0].is_null() {
+            state[0] = input_state[0].as_static();
+        } else if !input_state[0].is_null() {
+            merge_json(&input_state[0], &mut state[0]);
+        }
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        if state[0].is_null() {
+            return Datum::Null;
+        }
+        let values = split_json(state[0].as_bytea());
+        let object: BTreeMap<_, _> = values
+            .chunks_exact(2)
+            .map(|pair| (pair[0].get_string().unwrap(), pair[1]))
+            .collect();
+        Datum::from(JsonBuilder::default().object(|builder| {
+            for (key, value) in object {
+                builder.push_json(key, value);
+            }
+        }))
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "json_objectagg",
+        vec![DataType::Text, DataType::Null],
+        DataType::Json,
+        FunctionType::Aggregate(&JsonObjectAgg {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "json_objectagg",
+        args: vec![],
+        ret: DataType::Json,
+    };
+
+    #[test]
+    fn test_json_objectagg() {
+        let sig = FunctionSignature {
+            name: "json_objectagg",
+            args: vec![DataType::Text, DataType::Integer],
+            ret: DataType::Json,
+        };
+        let function = JsonObjectAgg {};
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+        assert_eq!(function.finalize(&DUMMY_SIG, &state), Datum::Null);
+
+        function.apply(&sig, &[Datum::from("b"), Datum::from(1)], 1, &mut state);
+        function.apply(&sig, &[Datum::from("a"), Datum::Null], 1, &mut state);
+        function.apply(&sig, &[Datum::Null, Datum::from(3)], 1, &mut state);
+
+        let mut other = vec![Datum::Null];
+        function.initialize(&mut other);
+        function.apply(&sig, &[Datum::from("b"), Datum::from(2)], 1, &mut other);
+        function.merge(&sig, &other, &mut state);
+
+        assert_eq!(
+            function.finalize(&DUMMY_SIG, &state),
+            Datum::from(OwnedJson::parse(r#"{"a":null,"b":2}"#).unwrap())
+        );
+    }
+}
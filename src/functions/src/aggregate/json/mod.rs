@@ -0,0 +1,38 @@
+use crate::registry::Registry;
+use data::json::{Json, JsonBuilder};
+use data::{DataType, Datum};
+
+mod json_arrayagg;
+mod json_objectagg;
+
+pub fn register_builtins(registry: &mut Registry) {
+    json_arrayagg::register_builtins(registry);
+    json_objectagg::register_builtins(registry);
+}
+
+/// Appends the json tape for a value onto the aggregate state, the states are just json
+/// values laid end to end which get wrapped up into an array/object when finalizing.
+fn append_json(datum: &Datum, datatype: DataType, state: &mut Datum<'static>) {
+    let json = Datum::from(JsonBuilder::default().datum(datum, datatype));
+    let mut bytes = state.as_bytea().to_vec();
+    bytes.extend_from_slice(json.as_bytea());
+    *state = Datum::from(bytes);
+}
+
+/// Splits the state back out into the json values that were appended.
+fn split_json(mut bytes: &[u8]) -> Vec<Json> {
+    let mut values = vec![];
+    while !bytes.is_empty() {
+        let size = Json::from_bytes(bytes).size();
+        values.push(Json::from_bytes(&bytes[..size]));
+        bytes = &bytes[size..];
+    }
+    values
+}
+
+/// Merges two states by appending the json values from one onto the other.
+fn merge_json(input_state: &Datum, state: &mut Datum<'static>) {
+    let mut bytes = state.as_bytea().to_vec();
+    bytes.extend_from_slice(input_state.as_bytea());
+    *state = Datum::from(bytes);
+}
@@ -161,6 +161,57 @@ impl AggregateFunction for DecimalAvg {
     }
 }
 
+#[derive(Debug)]
+struct DoubleAvg {}
+
+/// Average will keep sum and a count,
+/// for avg(double) both these will double, bigint
+impl AggregateFunction for DoubleAvg {
+    fn state_size(&self) -> usize {
+        2
+    }
+
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::from(0.0);
+        state[1] = Datum::from(0 as i64);
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if let Some(d) = args[0].as_maybe_double() {
+            *state[0].as_double_mut() += freq as f64 * d;
+            *state[1].as_bigint_mut() += freq;
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        *state[0].as_double_mut() += input_state[0].as_double();
+        *state[1].as_bigint_mut() += input_state[1].as_bigint();
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        if state[1].as_bigint() == 0 {
+            Datum::Null
+        } else {
+            Datum::from(state[0].as_double() / state[1].as_bigint() as f64)
+        }
+    }
+
+    fn supports_retract(&self) -> bool {
+        true
+    }
+}
+
 pub fn register_builtins(registry: &mut Registry) {
     registry.register_function(FunctionDefinition::new(
         "avg",
@@ -182,6 +233,13 @@ pub fn register_builtins(registry: &mut Registry) {
         DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
         FunctionType::Aggregate(&DecimalAvg {}),
     ));
+
+    registry.register_function(FunctionDefinition::new(
+        "avg",
+        vec![DataType::Double],
+        DataType::Double,
+        FunctionType::Aggregate(&DoubleAvg {}),
+    ));
 }
 
 #[cfg(test)]
@@ -241,4 +299,19 @@ mod tests {
 
         assert_eq!(answer, Datum::from(Decimal::new(5, 0)))
     }
+
+    #[test]
+    fn test_apply_double() {
+        let funct = &DoubleAvg {};
+
+        let mut state = vec![Datum::Null, Datum::Null];
+        funct.initialize(&mut state);
+
+        funct.apply(&DUMMY_SIG, &[Datum::from(1.5)], 2, &mut state);
+        funct.apply(&DUMMY_SIG, &[Datum::from(3.0)], 1, &mut state);
+
+        let answer = funct.finalize(&DUMMY_SIG, &mut state);
+        // 1.5 + 1.5 + 3 = 6, 6/3 = 2
+        assert_eq!(answer, Datum::from(2.0))
+    }
 }
@@ -0,0 +1,162 @@
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum};
+
+#[derive(Debug)]
+enum BitOp {
+    And,
+    Or,
+    Xor,
+}
+
+/// bit_and(value), bit_or(value) and bit_xor(value), like mysql these work on the 64 bit
+/// (two's complement) representation of the ints and return an unsigned bigint, an empty group
+/// returns all ones for bit_and and zero for the others.
+/// Xor can be undone by xor'ing the value back in so bit_xor supports retraction.
+#[derive(Debug)]
+struct BitAggregate {
+    op: BitOp,
+}
+
+impl AggregateFunction for BitAggregate {
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = match self.op {
+            BitOp::And => Datum::from(-1_i64),
+            BitOp::Or | BitOp::Xor => Datum::from(0_i64),
+        };
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if let Some(i) = args[0].as_maybe_bigint() {
+            let bits = state[0].as_bigint_mut();
+            match self.op {
+                BitOp::And if freq > 0 => *bits &= i,
+                BitOp::Or if freq > 0 => *bits |= i,
+                // Xor'ing the same value an even number of times cancels out
+                BitOp::Xor if freq % 2 != 0 => *bits ^= i,
+                _ => {}
+            }
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        let i = input_state[0].as_bigint();
+        let bits = state[0].as_bigint_mut();
+        match self.op {
+            BitOp::And => *bits &= i,
+            BitOp::Or => *bits |= i,
+            BitOp::Xor => *bits ^= i,
+        }
+    }
+
+    fn finalize<'a>(&self, signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        signature
+            .ret
+            .integer_datum(i128::from(state[0].as_bigint() as u64))
+            .unwrap()
+    }
+
+    fn supports_retract(&self) -> bool {
+        matches!(self.op, BitOp::Xor)
+    }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "bit_and",
+        vec![DataType::BigInt],
+        DataType::UnsignedBigInt,
+        FunctionType::Aggregate(&BitAggregate { op: BitOp::And }),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "bit_or",
+        vec![DataType::BigInt],
+        DataType::UnsignedBigInt,
+        FunctionType::Aggregate(&BitAggregate { op: BitOp::Or }),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "bit_xor",
+        vec![DataType::BigInt],
+        DataType::UnsignedBigInt,
+        FunctionType::Aggregate(&BitAggregate { op: BitOp::Xor }),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::rust_decimal::Decimal;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "bit_and",
+        args: vec![],
+        ret: DataType::UnsignedBigInt,
+    };
+
+    fn aggregate(op: BitOp, values: &[i64]) -> Datum<'static> {
+        let function = BitAggregate { op };
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+        for i in values {
+            function.apply(&DUMMY_SIG, &[Datum::from(*i)], 1, &mut state);
+        }
+        function.apply(&DUMMY_SIG, &[Datum::Null], 1, &mut state);
+        function.finalize(&DUMMY_SIG, &state).as_static()
+    }
+
+    #[test]
+    fn test_bit_ops() {
+        assert_eq!(
+            aggregate(BitOp::And, &[]),
+            Datum::from(Decimal::from(u64::MAX))
+        );
+        assert_eq!(aggregate(BitOp::Or, &[]), Datum::from(Decimal::from(0)));
+        assert_eq!(
+            aggregate(BitOp::And, &[0b110, 0b011]),
+            Datum::from(Decimal::from(0b010))
+        );
+        assert_eq!(
+            aggregate(BitOp::Or, &[0b110, 0b011]),
+            Datum::from(Decimal::from(0b111))
+        );
+        assert_eq!(
+            aggregate(BitOp::Xor, &[0b110, 0b011]),
+            Datum::from(Decimal::from(0b101))
+        );
+        assert_eq!(
+            aggregate(BitOp::Or, &[-1]),
+            Datum::from(Decimal::from(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_bit_xor_retract() {
+        let function = BitAggregate { op: BitOp::Xor };
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+        function.apply(&DUMMY_SIG, &[Datum::from(0b110_i64)], 1, &mut state);
+        function.apply(&DUMMY_SIG, &[Datum::from(0b011_i64)], 3, &mut state);
+        function.apply(&DUMMY_SIG, &[Datum::from(0b110_i64)], -1, &mut state);
+        assert_eq!(
+            function.finalize(&DUMMY_SIG, &state),
+            Datum::from(Decimal::from(0b011))
+        );
+        assert!(function.supports_retract());
+        assert!(!BitAggregate { op: BitOp::And }.supports_retract());
+    }
+}
@@ -1,9 +1,13 @@
 mod avg;
+mod bit_ops;
+mod percentile;
 mod sum;
 
 use crate::registry::Registry;
 
 pub fn register_builtins(registry: &mut Registry) {
     avg::register_builtins(registry);
+    bit_ops::register_builtins(registry);
+    percentile::register_builtins(registry);
     sum::register_builtins(registry);
 }
@@ -0,0 +1,331 @@
+use crate::aggregate::value_counts::{apply_value, merge_counts, present_values};
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use data::rust_decimal::Decimal;
+use data::{DataType, Datum, DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE};
+
+/// percentile_cont(value, percentile) and median(value), the exact percentile interpolated
+/// between the two nearest values.
+/// Every value has to be kept so the state is a count of each distinct value along with the
+/// percentile, keeping counts means values can be retracted too.
+#[derive(Debug)]
+struct PercentileCont {
+    median: bool,
+}
+
+impl AggregateFunction for PercentileCont {
+    fn state_size(&self) -> usize {
+        2
+    }
+
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::from(vec![]);
+        state[1] = if self.median {
+            Datum::from(0.5)
+        } else {
+            Datum::Null
+        };
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        apply_percentile(args, freq, state);
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        merge_percentile(input_state, state);
+    }
+
+    fn finalize<'a>(&self, signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        let percentile = if let Some(percentile) = valid_percentile(&state[1]) {
+            percentile
+        } else {
+            return Datum::Null;
+        };
+        let values = present_values(&state[0]);
+        let count: i64 = values.iter().map(|(_, count)| count).sum();
+        if count == 0 {
+            return Datum::Null;
+        }
+
+        if signature.ret == DataType::Double {
+            let position = percentile * (count - 1) as f64;
+            let lower = position.floor();
+            let low = value_at(&values, lower as i64).as_double();
+            let high = value_at(&values, position.ceil() as i64).as_double();
+            Datum::from(low + (high - low) * (position - lower))
+        } else {
+            // Rounded so we get the 0.1 that was typed in rather than 0.1000000000000000055511
+            let percentile = Decimal::from_f64(percentile)
+                .unwrap()
+                .round_dp(DECIMAL_MAX_SCALE as u32);
+            let position = percentile * Decimal::from(count - 1);
+            let lower = position.floor();
+            let lower_idx = lower.to_i64().unwrap();
+            let low = value_at(&values, lower_idx).as_decimal();
+            if position == lower {
+                Datum::from(low)
+            } else {
+                let high = value_at(&values, lower_idx + 1).as_decimal();
+                Datum::from(low + (high - low) * (position - lower))
+            }
+        }
+    }
+
+    fn supports_retract(&self) -> bool {
+        true
+    }
+}
+
+/// percentile_disc(value, percentile), the first value whose cumulative distribution reaches the
+/// percentile, unlike percentile_cont this works for any type and always returns one of the
+/// input values.
+#[derive(Debug)]
+struct PercentileDisc {}
+
+impl AggregateFunction for PercentileDisc {
+    fn state_size(&self) -> usize {
+        2
+    }
+
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::from(vec![]);
+        state[1] = Datum::Null;
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        apply_percentile(args, freq, state);
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        merge_percentile(input_state, state);
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        let percentile = if let Some(percentile) = valid_percentile(&state[1]) {
+            percentile
+        } else {
+            return Datum::Null;
+        };
+        let values = present_values(&state[0]);
+        let count: i64 = values.iter().map(|(_, count)| count).sum();
+        let target = percentile * count as f64;
+
+        let mut so_far = 0;
+        for (value, value_count) in values {
+            so_far += value_count;
+            if so_far as f64 >= target {
+                return value;
+            }
+        }
+        Datum::Null
+    }
+
+    fn supports_retract(&self) -> bool {
+        true
+    }
+}
+
+/// The percentile is taken from the first row with one, median has no percentile arg as
+/// its state is initialized with 0.5.
+fn apply_percentile(args: &[Datum], freq: i64, state: &mut [Datum<'static>]) {
+    if args[0].is_null() {
+        return;
+    }
+    if state[1].is_null() {
+        state[1] = args[1].as_static();
+    }
+    apply_value(&args[0], freq, &mut state[0]);
+}
+
+fn merge_percentile(input_state: &[Datum<'static>], state: &mut [Datum<'static>]) {
+    if state[1].is_null() {
+        state[1] = input_state[1].as_static();
+    }
+    merge_counts(&input_state[0], &mut state[0]);
+}
+
+fn valid_percentile(percentile: &Datum) -> Option<f64> {
+    percentile
+        .as_maybe_double()
+        .filter(|percentile| (0.0..=1.0).contains(percentile))
+}
+
+/// The value at the given (zero based) position if all the values were laid out in order.
+fn value_at<'a>(values: &'a [(Datum<'static>, i64)], idx: i64) -> &'a Datum<'static> {
+    let mut so_far = 0;
+    for (value, count) in values {
+        so_far += count;
+        if so_far > idx {
+            return value;
+        }
+    }
+    &values[values.len() - 1].0
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "percentile_cont",
+        vec![DataType::Decimal(0, 0), DataType::Double],
+        DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
+        FunctionType::Aggregate(&PercentileCont { median: false }),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "percentile_cont",
+        vec![DataType::Double, DataType::Double],
+        DataType::Double,
+        FunctionType::Aggregate(&PercentileCont { median: false }),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "median",
+        vec![DataType::Decimal(0, 0)],
+        DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
+        FunctionType::Aggregate(&PercentileCont { median: true }),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "median",
+        vec![DataType::Double],
+        DataType::Double,
+        FunctionType::Aggregate(&PercentileCont { median: true }),
+    ));
+
+    registry.register_function(FunctionDefinition::new_with_type_resolver(
+        "percentile_disc",
+        vec![DataType::Null, DataType::Double],
+        |args| args[0],
+        FunctionType::Aggregate(&PercentileDisc {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DECIMAL_SIG: FunctionSignature = FunctionSignature {
+        name: "percentile_cont",
+        args: vec![],
+        ret: DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
+    };
+
+    const DOUBLE_SIG: FunctionSignature = FunctionSignature {
+        name: "percentile_cont",
+        args: vec![],
+        ret: DataType::Double,
+    };
+
+    fn apply_all(
+        function: &dyn AggregateFunction,
+        values: &[Datum<'static>],
+        percentile: f64,
+    ) -> Vec<Datum<'static>> {
+        let mut state = vec![Datum::Null, Datum::Null];
+        function.initialize(&mut state);
+        for value in values {
+            function.apply(
+                &DOUBLE_SIG,
+                &[value.ref_clone(), Datum::from(percentile)],
+                1,
+                &mut state,
+            );
+        }
+        state
+    }
+
+    #[test]
+    fn test_percentile_cont() {
+        let function = PercentileCont { median: false };
+        let values = [1, 2, 3, 4]
+            .iter()
+            .map(|i| Datum::from(Decimal::from(*i)))
+            .collect::<Vec<_>>();
+
+        let state = apply_all(&function, &values, 0.5);
+        assert_eq!(
+            function.finalize(&DECIMAL_SIG, &state),
+            Datum::from(Decimal::new(25, 1))
+        );
+        let state = apply_all(&function, &values, 0.1);
+        assert_eq!(
+            function.finalize(&DECIMAL_SIG, &state),
+            Datum::from(Decimal::new(13, 1))
+        );
+        let state = apply_all(&function, &values, 1.0);
+        assert_eq!(
+            function.finalize(&DECIMAL_SIG, &state),
+            Datum::from(Decimal::from(4))
+        );
+        let state = apply_all(&function, &values, 1.5);
+        assert_eq!(function.finalize(&DECIMAL_SIG, &state), Datum::Null);
+
+        let values = [3.0, 1.0, 2.0]
+            .iter()
+            .map(|d| Datum::from(*d))
+            .collect::<Vec<_>>();
+        let state = apply_all(&function, &values, 0.25);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from(1.5));
+
+        let state = apply_all(&function, &[], 0.5);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::Null);
+    }
+
+    #[test]
+    fn test_median_retract_and_merge() {
+        let function = PercentileCont { median: true };
+        let mut state = vec![Datum::Null, Datum::Null];
+        function.initialize(&mut state);
+        for i in &[1, 2, 2, 10] {
+            function.apply(&DOUBLE_SIG, &[Datum::from(*i as f64)], 1, &mut state);
+        }
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from(2.0));
+
+        function.apply(&DOUBLE_SIG, &[Datum::from(1.0)], -1, &mut state);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from(2.0));
+
+        let mut other = vec![Datum::Null, Datum::Null];
+        function.initialize(&mut other);
+        function.apply(&DOUBLE_SIG, &[Datum::from(20.0)], 1, &mut other);
+        function.merge(&DOUBLE_SIG, &other, &mut state);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from(6.0));
+    }
+
+    #[test]
+    fn test_percentile_disc() {
+        let function = PercentileDisc {};
+        let values = ["d", "b", "a", "c"]
+            .iter()
+            .map(|s| Datum::from(*s))
+            .collect::<Vec<_>>();
+
+        let state = apply_all(&function, &values, 0.5);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from("b"));
+        let state = apply_all(&function, &values, 0.0);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from("a"));
+        let state = apply_all(&function, &values, 0.8);
+        assert_eq!(function.finalize(&DOUBLE_SIG, &state), Datum::from("d"));
+    }
+}
@@ -123,6 +123,45 @@ impl AggregateFunction for DecimalSum {
     }
 }
 
+#[derive(Debug)]
+struct DoubleSum {}
+
+impl AggregateFunction for DoubleSum {
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if let Some(d) = args[0].as_maybe_double() {
+            if state[0].is_null() {
+                state[0] = Datum::from(0.0);
+            }
+            *state[0].as_double_mut() += d * freq as f64;
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        if let Some(d) = input_state[0].as_maybe_double() {
+            if state[0].is_null() {
+                state[0] = input_state[0].as_static()
+            } else {
+                *state[0].as_double_mut() += d
+            }
+        }
+    }
+
+    fn supports_retract(&self) -> bool {
+        true
+    }
+}
+
 pub fn register_builtins(registry: &mut Registry) {
     registry.register_function(FunctionDefinition::new(
         "sum",
@@ -150,6 +189,13 @@ pub fn register_builtins(registry: &mut Registry) {
         },
         FunctionType::Aggregate(&DecimalSum {}),
     ));
+
+    registry.register_function(FunctionDefinition::new(
+        "sum",
+        vec![DataType::Double],
+        DataType::Double,
+        FunctionType::Aggregate(&DoubleSum {}),
+    ));
 }
 
 #[cfg(test)]
@@ -282,4 +328,19 @@ mod tests {
 
         assert_eq!(answer, Datum::from(Decimal::new(85, 1)))
     }
+
+    #[test]
+    fn test_apply_double() {
+        let funct = &DoubleSum {};
+
+        let mut state = vec![Datum::Null];
+        funct.initialize(&mut state);
+
+        funct.apply(&DUMMY_SIG, &[Datum::from(5.0)], 2, &mut state);
+        funct.apply(&DUMMY_SIG, &[Datum::from(1.5)], -1, &mut state);
+
+        let answer = funct.finalize(&DUMMY_SIG, &mut state);
+
+        assert_eq!(answer, Datum::from(8.5))
+    }
 }
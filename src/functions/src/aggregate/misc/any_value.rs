@@ -0,0 +1,77 @@
+use super::min_max::retractable_min;
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum};
+
+/// any_value(value), returns some value from the group, used to select columns that aren't
+/// grouped on but are known to be the same for the whole group.
+/// We just keep the first non null value seen, when retracting we need to know what the other
+/// values are so the retractable version is just min.
+#[derive(Debug)]
+struct AnyValue {}
+
+impl AggregateFunction for AnyValue {
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if freq > 0 && state[0].is_null() {
+            state[0] = args[0].as_static();
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        if state[0].is_null() {
+            state[0] = input_state[0].clone();
+        }
+    }
+
+    fn retractable(&self) -> Option<&'static dyn AggregateFunction> {
+        Some(retractable_min())
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new_with_type_resolver(
+        "any_value",
+        vec![DataType::Null],
+        |args| args[0],
+        FunctionType::Aggregate(&AnyValue {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "any_value",
+        args: vec![],
+        ret: DataType::Integer,
+    };
+
+    #[test]
+    fn test_any_value() {
+        let function = AnyValue {};
+        let mut state = vec![Datum::Null];
+        function.initialize(&mut state);
+        function.apply(&DUMMY_SIG, &[Datum::Null], 1, &mut state);
+        function.apply(&DUMMY_SIG, &[Datum::from(2)], 1, &mut state);
+        function.apply(&DUMMY_SIG, &[Datum::from(1)], 1, &mut state);
+        assert_eq!(function.finalize(&DUMMY_SIG, &state), Datum::from(2));
+
+        let mut other = vec![Datum::Null];
+        function.initialize(&mut other);
+        function.merge(&DUMMY_SIG, &state, &mut other);
+        assert_eq!(function.finalize(&DUMMY_SIG, &other), Datum::from(2));
+        assert!(function.retractable().unwrap().supports_retract());
+    }
+}
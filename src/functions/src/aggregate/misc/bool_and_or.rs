@@ -0,0 +1,118 @@
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum};
+
+/// bool_and(value) and bool_or(value), whether all/any of the (non null) values are true.
+/// The state is the number of trues and falses seen rather than the result so far, this makes
+/// them retractable.
+#[derive(Debug)]
+struct BoolAndOr {
+    or: bool,
+}
+
+impl AggregateFunction for BoolAndOr {
+    fn state_size(&self) -> usize {
+        2
+    }
+
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::from(0 as i64);
+        state[1] = Datum::from(0 as i64);
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        match args[0].as_maybe_boolean() {
+            Some(true) => *state[0].as_bigint_mut() += freq,
+            Some(false) => *state[1].as_bigint_mut() += freq,
+            None => {}
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        *state[0].as_bigint_mut() += input_state[0].as_bigint();
+        *state[1].as_bigint_mut() += input_state[1].as_bigint();
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        let trues = state[0].as_bigint();
+        let falses = state[1].as_bigint();
+        if trues == 0 && falses == 0 {
+            Datum::Null
+        } else if self.or {
+            Datum::from(trues > 0)
+        } else {
+            Datum::from(falses == 0)
+        }
+    }
+
+    fn supports_retract(&self) -> bool {
+        true
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "bool_and",
+        vec![DataType::Boolean],
+        DataType::Boolean,
+        FunctionType::Aggregate(&BoolAndOr { or: false }),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "bool_or",
+        vec![DataType::Boolean],
+        DataType::Boolean,
+        FunctionType::Aggregate(&BoolAndOr { or: true }),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "bool_and",
+        args: vec![],
+        ret: DataType::Boolean,
+    };
+
+    #[test]
+    fn test_bool_and_or() {
+        let functions = [
+            (BoolAndOr { or: false }, false, true),
+            (BoolAndOr { or: true }, true, true),
+        ];
+        for (function, expected, expected_after_retract) in functions.iter() {
+            let mut state = vec![Datum::Null, Datum::Null];
+            function.initialize(&mut state);
+            assert_eq!(function.finalize(&DUMMY_SIG, &state), Datum::Null);
+
+            function.apply(&DUMMY_SIG, &[Datum::from(true)], 2, &mut state);
+            function.apply(&DUMMY_SIG, &[Datum::Null], 1, &mut state);
+            let mut other = vec![Datum::Null, Datum::Null];
+            function.initialize(&mut other);
+            function.apply(&DUMMY_SIG, &[Datum::from(false)], 1, &mut other);
+            function.merge(&DUMMY_SIG, &other, &mut state);
+            assert_eq!(
+                function.finalize(&DUMMY_SIG, &state),
+                Datum::from(*expected)
+            );
+
+            function.apply(&DUMMY_SIG, &[Datum::from(false)], -1, &mut state);
+            assert_eq!(
+                function.finalize(&DUMMY_SIG, &state),
+                Datum::from(*expected_after_retract)
+            );
+        }
+    }
+}
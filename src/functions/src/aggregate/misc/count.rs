@@ -34,6 +34,10 @@ impl AggregateFunction for Count {
     fn supports_retract(&self) -> bool {
         true
     }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -0,0 +1,104 @@
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum};
+
+/// Returns the first value seen for the group, only meaningful when the input
+/// is sorted, used to implement DISTINCT ON.
+#[derive(Debug)]
+struct First {}
+
+impl AggregateFunction for First {
+    // The value and a flag to say whether we've seen a row yet, the value on its own
+    // isn't enough as the first row may be null
+    fn state_size(&self) -> usize {
+        2
+    }
+
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::Null;
+        state[1] = Datum::from(false);
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if freq > 0 && !state[1].as_boolean() {
+            state[0] = args[0].as_static();
+            state[1] = Datum::from(true);
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        if !state[1].as_boolean() && input_state[1].as_boolean() {
+            state[0] = input_state[0].clone();
+            state[1] = Datum::from(true);
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new_with_type_resolver(
+        "first",
+        vec![DataType::Null],
+        |args| args[0],
+        FunctionType::Aggregate(&First {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dummy_sig() -> FunctionSignature<'static> {
+        FunctionSignature {
+            name: "first",
+            args: vec![DataType::Integer],
+            ret: DataType::Integer,
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let funct = &First {};
+
+        let mut state = vec![Datum::Null, Datum::Null];
+        funct.initialize(&mut state);
+
+        funct.apply(&dummy_sig(), &[Datum::Null], 1, &mut state);
+        funct.apply(&dummy_sig(), &[Datum::from(2)], 1, &mut state);
+
+        assert_eq!(funct.finalize(&dummy_sig(), &mut state), Datum::Null);
+
+        funct.initialize(&mut state);
+        funct.apply(&dummy_sig(), &[Datum::from(1)], 0, &mut state);
+        funct.apply(&dummy_sig(), &[Datum::from(2)], 1, &mut state);
+        funct.apply(&dummy_sig(), &[Datum::from(3)], 1, &mut state);
+
+        assert_eq!(funct.finalize(&dummy_sig(), &mut state), Datum::from(2));
+    }
+
+    #[test]
+    fn test_merge() {
+        let funct = &First {};
+
+        let mut state1 = vec![Datum::Null, Datum::Null];
+        funct.initialize(&mut state1);
+
+        let mut state2 = vec![Datum::Null, Datum::Null];
+        funct.initialize(&mut state2);
+        funct.apply(&dummy_sig(), &[Datum::from(2)], 1, &mut state2);
+
+        funct.merge(&dummy_sig(), &state2, &mut state1);
+
+        assert_eq!(funct.finalize(&dummy_sig(), &mut state1), Datum::from(2));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::aggregate::value_counts::{apply_value, merge_counts, present_values};
+use crate::registry::Registry;
+use crate::{AggregateFunction, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum};
+use std::cmp::Ordering;
+
+/// Keeps the smallest (or largest) value seen, nulls are ignored.
+/// Only the current extreme is kept so once it's been retracted there's no way to know what the
+/// next one is, RetractableMinMax should be used when inputs can be retracted.
+#[derive(Debug)]
+struct MinMax {
+    max: bool,
+}
+
+impl AggregateFunction for MinMax {
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        if freq > 0 {
+            self.replace_if_better(&args[0], state);
+        }
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        self.replace_if_better(&input_state[0], state);
+    }
+
+    fn retractable(&self) -> Option<&'static dyn AggregateFunction> {
+        if self.max {
+            Some(&RetractableMinMax { max: true })
+        } else {
+            Some(&RetractableMinMax { max: false })
+        }
+    }
+}
+
+impl MinMax {
+    fn replace_if_better(&self, value: &Datum, state: &mut [Datum<'static>]) {
+        let better = if self.max {
+            Ordering::Greater
+        } else {
+            Ordering::Less
+        };
+        if !value.is_null() && (state[0].is_null() || value.cmp(&state[0]) == better) {
+            state[0] = value.as_static();
+        }
+    }
+}
+
+/// A min/max that supports retraction by keeping a count of each distinct value seen.
+#[derive(Debug)]
+struct RetractableMinMax {
+    max: bool,
+}
+
+impl AggregateFunction for RetractableMinMax {
+    fn initialize(&self, state: &mut [Datum<'static>]) {
+        state[0] = Datum::from(vec![]);
+    }
+
+    fn apply<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        args: &[Datum<'a>],
+        freq: i64,
+        state: &mut [Datum<'static>],
+    ) {
+        apply_value(&args[0], freq, &mut state[0]);
+    }
+
+    fn merge<'a>(
+        &self,
+        _signature: &FunctionSignature<'a>,
+        input_state: &[Datum<'static>],
+        state: &mut [Datum<'static>],
+    ) {
+        merge_counts(&input_state[0], &mut state[0]);
+    }
+
+    fn finalize<'a>(&self, _signature: &FunctionSignature, state: &'a [Datum<'a>]) -> Datum<'a> {
+        let mut present = present_values(&state[0]).into_iter();
+        let extreme = if self.max {
+            present.last()
+        } else {
+            present.next()
+        };
+        extreme.map(|(value, _)| value).unwrap_or(Datum::Null)
+    }
+
+    fn supports_retract(&self) -> bool {
+        true
+    }
+}
+
+/// The retractable min, used as the retractable version of any_value.
+pub(super) fn retractable_min() -> &'static dyn AggregateFunction {
+    &RetractableMinMax { max: false }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new_with_type_resolver(
+        "min",
+        vec![DataType::Null],
+        |args| args[0],
+        FunctionType::Aggregate(&MinMax { max: false }),
+    ));
+    registry.register_function(FunctionDefinition::new_with_type_resolver(
+        "max",
+        vec![DataType::Null],
+        |args| args[0],
+        FunctionType::Aggregate(&MinMax { max: true }),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dummy_sig() -> FunctionSignature<'static> {
+        FunctionSignature {
+            name: "min",
+            args: vec![DataType::Integer],
+            ret: DataType::Integer,
+        }
+    }
+
+    #[test]
+    fn test_min_max() {
+        const MIN: MinMax = MinMax { max: false };
+        let mut state = vec![Datum::Null];
+        MIN.initialize(&mut state);
+        MIN.apply(&dummy_sig(), &[Datum::from(3)], 1, &mut state);
+        MIN.apply(&dummy_sig(), &[Datum::Null], 1, &mut state);
+        MIN.apply(&dummy_sig(), &[Datum::from(1)], 1, &mut state);
+        MIN.apply(&dummy_sig(), &[Datum::from(2)], 1, &mut state);
+        assert_eq!(MIN.finalize(&dummy_sig(), &state), Datum::from(1));
+
+        let mut other = vec![Datum::Null];
+        MIN.initialize(&mut other);
+        MIN.apply(&dummy_sig(), &[Datum::from(-1)], 1, &mut other);
+        MIN.merge(&dummy_sig(), &other, &mut state);
+        assert_eq!(MIN.finalize(&dummy_sig(), &state), Datum::from(-1));
+        assert!(!MIN.supports_retract());
+        assert!(MIN.retractable().unwrap().supports_retract());
+    }
+
+    #[test]
+    fn test_retractable_min_max() {
+        let functions = [
+            (RetractableMinMax { max: false }, 2),
+            (RetractableMinMax { max: true }, 3),
+        ];
+        for (function, expected) in functions.iter() {
+            let mut state = vec![Datum::Null];
+            function.initialize(&mut state);
+            assert_eq!(function.finalize(&dummy_sig(), &state), Datum::Null);
+
+            // The retraction can come first
+            function.apply(&dummy_sig(), &[Datum::from(1)], -1, &mut state);
+            function.apply(&dummy_sig(), &[Datum::from(1)], 2, &mut state);
+            function.apply(&dummy_sig(), &[Datum::from(2)], 1, &mut state);
+            function.apply(&dummy_sig(), &[Datum::from(4)], 1, &mut state);
+            function.apply(&dummy_sig(), &[Datum::from(1)], -1, &mut state);
+            function.apply(&dummy_sig(), &[Datum::from(4)], -1, &mut state);
+
+            let mut other = vec![Datum::Null];
+            function.initialize(&mut other);
+            function.apply(&dummy_sig(), &[Datum::from(3)], 1, &mut other);
+            function.merge(&dummy_sig(), &other, &mut state);
+            assert_eq!(
+                function.finalize(&dummy_sig(), &state),
+                Datum::from(*expected)
+            );
+        }
+    }
+}
@@ -1,7 +1,15 @@
+mod any_value;
+mod bool_and_or;
 mod count;
+mod first;
+mod min_max;
 
 use crate::registry::Registry;
 
 pub fn register_builtins(registry: &mut Registry) {
+    any_value::register_builtins(registry);
+    bool_and_or::register_builtins(registry);
     count::register_builtins(registry);
+    first::register_builtins(registry);
+    min_max::register_builtins(registry);
 }
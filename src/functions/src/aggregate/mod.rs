@@ -1,9 +1,14 @@
 use crate::registry::Registry;
 
+mod approx;
+mod json;
 mod maths;
 mod misc;
+mod value_counts;
 
 pub fn register_builtins(registry: &mut Registry) {
+    approx::register_builtins(registry);
+    json::register_builtins(registry);
     maths::register_builtins(registry);
     misc::register_builtins(registry);
 }
@@ -0,0 +1,78 @@
+//! Aggregate states that keep a count of each distinct value seen, used by the aggregates that
+//! need to know about every value rather than a running total and that need to support
+//! retractions.
+//! The counts are kept in a single bytea, each value encoded as its sortable bytes followed by
+//! its count, in value order.
+use data::encoding_core::SortableEncoding;
+use data::{Datum, SortOrder};
+
+fn decode_counts(mut bytes: &[u8]) -> Vec<(Vec<u8>, i64)> {
+    let mut counts = vec![];
+    let mut datum = Datum::Null;
+    while !bytes.is_empty() {
+        let rest = datum.from_sortable_bytes(bytes);
+        let value = bytes[..(bytes.len() - rest.len())].to_vec();
+        let mut count = 0_i64;
+        bytes = count.read_sortable_bytes(SortOrder::Asc, rest);
+        counts.push((value, count));
+    }
+    counts
+}
+
+fn encode_counts(counts: &[(Vec<u8>, i64)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    for (value, count) in counts {
+        bytes.extend_from_slice(value);
+        count.write_sortable_bytes(SortOrder::Asc, &mut bytes);
+    }
+    bytes
+}
+
+/// Adds to the count of a value, values whose count drops to zero are removed.
+fn add_count(counts: &mut Vec<(Vec<u8>, i64)>, value: Vec<u8>, freq: i64) {
+    match counts.binary_search_by(|(existing, _)| existing.cmp(&value)) {
+        Ok(idx) => {
+            counts[idx].1 += freq;
+            if counts[idx].1 == 0 {
+                counts.remove(idx);
+            }
+        }
+        Err(idx) => counts.insert(idx, (value, freq)),
+    }
+}
+
+/// Adds freq to the count of the value in the state, nulls aren't counted.
+pub(super) fn apply_value(value: &Datum, freq: i64, state: &mut Datum<'static>) {
+    if value.is_null() || freq == 0 {
+        return;
+    }
+    let mut bytes = vec![];
+    value.as_sortable_bytes(SortOrder::Asc, &mut bytes);
+    let mut counts = decode_counts(state.as_bytea());
+    add_count(&mut counts, bytes, freq);
+    *state = Datum::from(encode_counts(&counts));
+}
+
+/// Adds the counts from one state into another.
+pub(super) fn merge_counts(input_state: &Datum, state: &mut Datum<'static>) {
+    let mut counts = decode_counts(state.as_bytea());
+    for (value, count) in decode_counts(input_state.as_bytea()) {
+        add_count(&mut counts, value, count);
+    }
+    *state = Datum::from(encode_counts(&counts));
+}
+
+/// The values with a positive count along with their counts, in value order.
+/// Retractions can come through before the inserts they cancel out so values with a negative
+/// count are skipped.
+pub(super) fn present_values(state: &Datum) -> Vec<(Datum<'static>, i64)> {
+    decode_counts(state.as_bytea())
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(bytes, count)| {
+            let mut datum = Datum::Null;
+            datum.from_sortable_bytes(&bytes);
+            (datum, count)
+        })
+        .collect()
+}
@@ -1,9 +1,11 @@
 mod aggregate;
 pub mod registry;
 mod scalar;
+mod wasm;
 
 use crate::registry::Registry;
 use data::{DataType, Datum, Session};
+pub use registry::UdfError;
 use std::fmt::{Debug, Formatter};
 
 /// The signature for a function. Signatures are scanned to find a match during planning.
@@ -45,6 +47,7 @@ pub struct CompoundFunction {
 pub enum CompoundFunctionArg {
     Function(CompoundFunction),
     Input(usize),
+    Constant(Datum<'static>, DataType),
 }
 
 impl FunctionType {
@@ -111,6 +114,12 @@ pub trait Function: Debug + Sync + 'static {
         signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a>;
+
+    /// Whether the function can return null, given whether each of its args can be null.
+    /// Unless a function says otherwise it's assumed it could return null for any input.
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        true
+    }
 }
 
 /// A function implementation for aggregate functions.
@@ -156,6 +165,18 @@ pub trait AggregateFunction: Debug + Sync + 'static {
     fn supports_retract(&self) -> bool {
         false
     }
+
+    /// A variant of this aggregate that supports retraction for aggregates that don't,
+    /// normally at the cost of keeping more state. Used when maintaining views incrementally.
+    fn retractable(&self) -> Option<&'static dyn AggregateFunction> {
+        None
+    }
+
+    /// Whether the aggregate can return null, given whether each of its args can be null.
+    /// Most aggregates return null when there aren't any input rows.
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        true
+    }
 }
 
 fn register_builtins(registry: &mut Registry) {
@@ -1,3 +1,4 @@
+use crate::wasm::WasmFunction;
 use crate::{
     register_builtins, CompoundFunction, CompoundFunctionArg, FunctionDefinition,
     FunctionSignature, FunctionType,
@@ -5,11 +6,13 @@ use crate::{
 use data::DataType;
 use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
+use std::sync::RwLock;
 
 /// A repository for functions. Used by the planner to resolve the correct functions
 #[derive(Debug)]
 pub struct Registry {
-    functions: BTreeMap<&'static str, Vec<FunctionDefinition>>,
+    // Locked as user defined functions come and go while queries are being planned
+    functions: RwLock<BTreeMap<&'static str, Vec<FunctionDefinition>>>,
 }
 
 impl Default for Registry {
@@ -40,10 +43,45 @@ impl Display for FunctionResolutionError {
     }
 }
 
+/// Errors from creating user defined functions
+#[derive(Debug, Eq, PartialEq)]
+pub enum UdfError {
+    FunctionAlreadyExists(String),
+    UnsupportedType(DataType),
+    InvalidModule(String),
+    InvalidBody(String),
+    RecursiveFunction(String),
+}
+
+impl Display for UdfError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UdfError::FunctionAlreadyExists(name) => {
+                f.write_fmt(format_args!("Function {} already exists", name))
+            }
+            UdfError::UnsupportedType(datatype) => f.write_fmt(format_args!(
+                "{} can't be passed to or returned from a wasm function",
+                datatype
+            )),
+            UdfError::InvalidModule(message) => {
+                f.write_fmt(format_args!("Invalid wasm module: {}", message))
+            }
+            UdfError::InvalidBody(message) => {
+                f.write_fmt(format_args!("Invalid function body: {}", message))
+            }
+            UdfError::RecursiveFunction(name) => {
+                f.write_fmt(format_args!("Function {} can't call itself", name))
+            }
+        }
+    }
+}
+
+impl std::error::Error for UdfError {}
+
 impl Registry {
     pub fn new(with_builtins: bool) -> Self {
         let mut registry = Registry {
-            functions: BTreeMap::new(),
+            functions: RwLock::new(BTreeMap::new()),
         };
 
         if with_builtins {
@@ -55,6 +93,8 @@ impl Registry {
 
     pub(crate) fn register_function(&mut self, function_definition: FunctionDefinition) {
         self.functions
+            .get_mut()
+            .unwrap()
             .entry(function_definition.signature.name)
             .or_insert_with(Vec::new)
             .push(function_definition);
@@ -64,7 +104,8 @@ impl Registry {
         &self,
         function_signature: &FunctionSignature,
     ) -> Result<(FunctionSignature<'static>, FunctionType), FunctionResolutionError> {
-        if let Some(candidates) = self.functions.get(function_signature.name) {
+        let functions = self.functions.read().unwrap();
+        if let Some(candidates) = functions.get(function_signature.name) {
             // Rank and filter candidates.
             let mut matching_candidates: Vec<_> = candidates
                 .iter()
@@ -158,10 +199,108 @@ impl Registry {
         }
     }
 
-    pub fn list_functions(&self) -> impl Iterator<Item = &'static str> + '_ {
+    pub fn list_functions(&self) -> Vec<&'static str> {
+        self.functions.read().unwrap().keys().copied().collect()
+    }
+
+    /// Registers a user defined function implemented by a wasm module, the module must export a
+    /// function of the same name.
+    pub fn register_wasm_function(
+        &self,
+        name: &str,
+        args: Vec<DataType>,
+        ret: DataType,
+        module: Vec<u8>,
+    ) -> Result<(), UdfError> {
+        let mut functions = self.functions.write().unwrap();
+        if functions.contains_key(name) {
+            return Err(UdfError::FunctionAlreadyExists(name.to_string()));
+        }
+        let function = WasmFunction::new(name, args.clone(), ret, module)?;
+        // Compiled expressions hold onto their functions (and names) as static references so user
+        // defined functions have to be leaked, they're not expected to come and go very often.
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        let function: &'static WasmFunction = Box::leak(Box::new(function));
+        functions.insert(
+            name,
+            vec![FunctionDefinition::new(
+                name,
+                args,
+                ret,
+                FunctionType::Scalar(function),
+            )],
+        );
+        Ok(())
+    }
+
+    /// Registers a user defined function whose body is an expression, calls to it are inlined by
+    /// the planner. The result is cast to the return type.
+    pub fn register_sql_function(
+        &self,
+        name: &str,
+        args: Vec<DataType>,
+        ret: DataType,
+        body: CompoundFunctionArg,
+    ) -> Result<(), UdfError> {
+        let mut functions = self.functions.write().unwrap();
+        if functions.contains_key(name) {
+            return Err(UdfError::FunctionAlreadyExists(name.to_string()));
+        }
+        let compound_function = CompoundFunction {
+            function_name: ret.cast_function(),
+            args: vec![body],
+        };
+        // Otherwise the planner would keep on inlining it forever
+        if Registry::calls_function(&functions, &compound_function, name) {
+            return Err(UdfError::RecursiveFunction(name.to_string()));
+        }
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        functions.insert(
+            name,
+            vec![FunctionDefinition::new(
+                name,
+                args,
+                ret,
+                FunctionType::Compound(compound_function),
+            )],
+        );
+        Ok(())
+    }
+
+    /// Returns the registry's copy of the function's name, None if there's no such function.
+    pub fn function_name(&self, name: &str) -> Option<&'static str> {
         self.functions
-            .iter()
-            .map(|(function_name, _defs)| *function_name)
+            .read()
+            .unwrap()
+            .get_key_value(name)
+            .map(|(name, _)| *name)
+    }
+
+    /// Whether the compound function calls the named function, either directly or from within the
+    /// compound functions it calls.
+    fn calls_function(
+        functions: &BTreeMap<&'static str, Vec<FunctionDefinition>>,
+        compound_function: &CompoundFunction,
+        name: &str,
+    ) -> bool {
+        compound_function.function_name == name
+            || compound_function.args.iter().any(|arg| {
+                matches!(arg, CompoundFunctionArg::Function(function)
+                    if Registry::calls_function(functions, function, name))
+            })
+            || functions
+                .get(compound_function.function_name)
+                .into_iter()
+                .flatten()
+                .any(|definition| {
+                    matches!(&definition.function, FunctionType::Compound(function)
+                        if Registry::calls_function(functions, function, name))
+                })
+    }
+
+    /// Removes a user defined function, it's up to the caller to make sure it isn't a builtin.
+    pub fn unregister_function(&self, name: &str) {
+        self.functions.write().unwrap().remove(name);
     }
 
     /// Returns a "closeness" ranking of our desire to type widen
@@ -178,12 +317,41 @@ impl Registry {
             // Special case for decimal, functions that accept decimal
             // accept any sized decimals.
             (DataType::Decimal(_, _), DataType::Decimal(_, _)) => Some(0),
+            // Likewise for vectors, functions check the dimensions line up at runtime.
+            (DataType::Vector(_), DataType::Vector(_)) => Some(0),
+            // The smaller ints all widen to ints and from there on like ints do
+            (DataType::TinyInt, _)
+            | (DataType::SmallInt, _)
+            | (DataType::UnsignedTinyInt, _)
+            | (DataType::UnsignedSmallInt, _)
+                if to != DataType::Integer =>
+            {
+                Registry::datatype_rank(DataType::Integer, to).map(|rank| rank + 1)
+            }
+            (DataType::TinyInt, DataType::Integer)
+            | (DataType::SmallInt, DataType::Integer)
+            | (DataType::UnsignedTinyInt, DataType::Integer)
+            | (DataType::UnsignedSmallInt, DataType::Integer) => Some(1),
+            // Unsigned ints are bigints with a smaller range, while unsigned bigints need decimals
+            (DataType::UnsignedInteger, _) => {
+                Registry::datatype_rank(DataType::BigInt, to).map(|rank| rank + 1)
+            }
+            (DataType::UnsignedBigInt, _) => {
+                Registry::datatype_rank(DataType::Decimal(20, 0), to).map(|rank| rank + 1)
+            }
             // Int can be cast to bigint and decimal safely
             (DataType::Integer, DataType::BigInt) => Some(1),
             (DataType::Integer, DataType::Decimal(_, _)) => Some(2),
             // Bigint can be cast to decimal safely
             (DataType::BigInt, DataType::Decimal(_, _)) => Some(1),
+            // Anything numeric can be widened to a double, though possibly losing precision
+            // so its ranked behind the exact types
+            (DataType::Integer, DataType::Double) => Some(3),
+            (DataType::BigInt, DataType::Double) => Some(2),
+            (DataType::Decimal(_, _), DataType::Double) => Some(1),
             (DataType::Text, DataType::JsonPath) => Some(1),
+            // Text is stored as its utf8 bytes so can be passed to anything expecting bytes
+            (DataType::Text, DataType::ByteA) => Some(1),
             _ => None,
         }
     }
@@ -192,7 +360,7 @@ impl Registry {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use data::DataType;
+    use data::{DataType, Datum};
 
     #[test]
     fn test_registry_resolve() {
@@ -289,4 +457,100 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_registry_resolve_text_numeric_comparison() {
+        let registry = Registry::new(true);
+
+        let sig = FunctionSignature {
+            name: "=",
+            args: vec![DataType::Text, DataType::Decimal(10, 2)],
+            ret: DataType::Null,
+        };
+
+        let (_function_sig, function) = registry.resolve_function(&sig).unwrap();
+
+        let compound_function = if let FunctionType::Compound(c) = function {
+            c
+        } else {
+            panic!()
+        };
+
+        assert_eq!(
+            compound_function,
+            CompoundFunction {
+                function_name: "=",
+                args: vec![
+                    CompoundFunctionArg::Function(CompoundFunction {
+                        function_name: "to_decimal_lenient",
+                        args: vec![CompoundFunctionArg::Input(0)]
+                    }),
+                    CompoundFunctionArg::Input(1)
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn test_register_sql_function() {
+        let registry = Registry::new(true);
+        // double(a) = a * 2
+        let body = CompoundFunctionArg::Function(CompoundFunction {
+            function_name: "*",
+            args: vec![
+                CompoundFunctionArg::Input(0),
+                CompoundFunctionArg::Constant(Datum::from(2), DataType::Integer),
+            ],
+        });
+        registry
+            .register_sql_function("double", vec![DataType::Integer], DataType::Integer, body)
+            .unwrap();
+        assert_eq!(registry.function_name("double"), Some("double"));
+
+        let (signature, function) = registry
+            .resolve_function(&FunctionSignature {
+                name: "double",
+                args: vec![DataType::Integer],
+                ret: DataType::Null,
+            })
+            .unwrap();
+        assert_eq!(signature.ret, DataType::Integer);
+        assert!(matches!(
+            function,
+            FunctionType::Compound(CompoundFunction {
+                function_name: "to_int",
+                ..
+            })
+        ));
+
+        // quadruple(a) = double(double(a)) is fine but double can't be recreated to call
+        // quadruple
+        let double_call = |arg| {
+            CompoundFunctionArg::Function(CompoundFunction {
+                function_name: "double",
+                args: vec![arg],
+            })
+        };
+        registry
+            .register_sql_function(
+                "quadruple",
+                vec![DataType::Integer],
+                DataType::Integer,
+                double_call(double_call(CompoundFunctionArg::Input(0))),
+            )
+            .unwrap();
+        registry.unregister_function("double");
+        assert_eq!(
+            registry.register_sql_function(
+                "double",
+                vec![DataType::Integer],
+                DataType::Integer,
+                CompoundFunctionArg::Function(CompoundFunction {
+                    function_name: "quadruple",
+                    args: vec![CompoundFunctionArg::Input(0)],
+                }),
+            ),
+            Err(UdfError::RecursiveFunction("double".to_string()))
+        );
+    }
 }
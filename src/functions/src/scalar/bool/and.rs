@@ -18,6 +18,10 @@ impl Function for And {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
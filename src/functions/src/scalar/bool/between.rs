@@ -18,6 +18,10 @@ impl Function for Between {
             Datum::from(args[0] >= args[1] && args[0] <= args[2])
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -26,6 +30,7 @@ pub fn register_builtins(registry: &mut Registry) {
         DataType::Integer,
         DataType::BigInt,
         DataType::Decimal(0, 0),
+        DataType::Double,
         DataType::Text,
         DataType::Date,
     ] {
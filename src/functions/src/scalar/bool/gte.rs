@@ -18,6 +18,10 @@ impl Function for Gte {
             Datum::from(args[0] >= args[1])
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -26,7 +30,9 @@ pub fn register_builtins(registry: &mut Registry) {
         DataType::Integer,
         DataType::BigInt,
         DataType::Decimal(0, 0),
+        DataType::Double,
         DataType::Text,
+        DataType::ByteA,
         DataType::Date,
     ] {
         registry.register_function(FunctionDefinition::new(
@@ -36,6 +42,7 @@ pub fn register_builtins(registry: &mut Registry) {
             FunctionType::Scalar(&Gte {}),
         ));
     }
+    super::register_text_numeric_comparisons(registry, ">=");
 }
 
 #[cfg(test)]
@@ -14,6 +14,10 @@ impl Function for IsFalse {
     ) -> Datum<'a> {
         Datum::from(args[0] == Datum::from(false))
     }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -14,6 +14,10 @@ impl Function for IsNull {
     ) -> Datum<'a> {
         Datum::from(args[0].is_null())
     }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -14,6 +14,10 @@ impl Function for IsTrue {
     ) -> Datum<'a> {
         Datum::from(args[0] == Datum::from(true))
     }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -1,4 +1,6 @@
 use crate::registry::Registry;
+use crate::{CompoundFunction, CompoundFunctionArg, FunctionDefinition, FunctionType};
+use data::DataType;
 
 mod and;
 mod between;
@@ -12,6 +14,7 @@ mod lt;
 mod lte;
 mod ne;
 mod not;
+mod null_safe_eq;
 mod or;
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -27,5 +30,47 @@ pub fn register_builtins(registry: &mut Registry) {
     lte::register_builtins(registry);
     ne::register_builtins(registry);
     not::register_builtins(registry);
+    null_safe_eq::register_builtins(registry);
     or::register_builtins(registry);
 }
+
+/// Registers variants of a comparison function that accept text on one side and a number on the
+/// other. Following mysql, the text side is implicitly converted to a number before comparing, ie
+/// `'5' = 5` is true, using its longest numeric prefix (or 0) with a warning if that's not all
+/// of it, ie `'5x' = 5` and `'abc' = 0` are true too. Integer and bigint args will get upcast to
+/// decimal by the normal function resolution rules, doubles get their own variants so the text
+/// is parsed as a double.
+fn register_text_numeric_comparisons(registry: &mut Registry, function_name: &'static str) {
+    for (numeric, conversion) in &[
+        (DataType::Decimal(0, 0), "to_decimal_lenient"),
+        (DataType::Double, "to_double_lenient"),
+    ] {
+        let numeric = *numeric;
+        for args in &[[DataType::Text, numeric], [numeric, DataType::Text]] {
+            let compound_args = args
+                .iter()
+                .enumerate()
+                .map(|(idx, datatype)| {
+                    if *datatype == DataType::Text {
+                        CompoundFunctionArg::Function(CompoundFunction {
+                            function_name: conversion,
+                            args: vec![CompoundFunctionArg::Input(idx)],
+                        })
+                    } else {
+                        CompoundFunctionArg::Input(idx)
+                    }
+                })
+                .collect();
+
+            registry.register_function(FunctionDefinition::new(
+                function_name,
+                args.to_vec(),
+                DataType::Boolean,
+                FunctionType::Compound(CompoundFunction {
+                    function_name,
+                    args: compound_args,
+                }),
+            ));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+/// The <=> operator, like = except null <=> null is true and it never returns null.
+#[derive(Debug)]
+struct NullSafeEq {}
+
+impl Function for NullSafeEq {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        Datum::from(args[0].sql_eq(&args[1], true))
+    }
+
+    fn nullable(&self, _args_nullable: &[bool]) -> bool {
+        false
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for datatype in &[
+        DataType::Boolean,
+        DataType::Integer,
+        DataType::BigInt,
+        DataType::Decimal(0, 0),
+        DataType::Double,
+        DataType::Text,
+        DataType::ByteA,
+        DataType::Date,
+    ] {
+        registry.register_function(FunctionDefinition::new(
+            "<=>",
+            vec![*datatype, *datatype],
+            DataType::Boolean,
+            FunctionType::Scalar(&NullSafeEq {}),
+        ));
+    }
+    super::register_text_numeric_comparisons(registry, "<=>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "<=>",
+        args: vec![],
+        ret: DataType::Boolean,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            NullSafeEq {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null, Datum::Null]),
+            Datum::from(true)
+        );
+        assert_eq!(
+            NullSafeEq {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null, Datum::from(1)]),
+            Datum::from(false)
+        );
+        assert_eq!(
+            NullSafeEq {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1), Datum::Null]),
+            Datum::from(false)
+        );
+    }
+
+    #[test]
+    fn test_eq() {
+        assert_eq!(
+            NullSafeEq {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1), Datum::from(1)]
+            ),
+            Datum::from(true)
+        );
+
+        assert_eq!(
+            NullSafeEq {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1), Datum::from(2)]
+            ),
+            Datum::from(false)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug)]
+struct ToBase64 {}
+
+/// to_base64(bytes), the standard alphabet with padding, text is encoded as its utf8 bytes
+impl Function for ToBase64 {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(bytes) = args[0].as_maybe_bytea() {
+            let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+            for chunk in bytes.chunks(3) {
+                let b = [
+                    chunk[0],
+                    chunk.get(1).copied().unwrap_or(0),
+                    chunk.get(2).copied().unwrap_or(0),
+                ];
+                let indexes = [
+                    b[0] >> 2,
+                    (b[0] & 0x03) << 4 | b[1] >> 4,
+                    (b[1] & 0x0f) << 2 | b[2] >> 6,
+                    b[2] & 0x3f,
+                ];
+                for (idx, sextet) in indexes.iter().enumerate() {
+                    if idx <= chunk.len() {
+                        encoded.push(ALPHABET[*sextet as usize] as char);
+                    } else {
+                        encoded.push('=');
+                    }
+                }
+            }
+            Datum::from(encoded)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FromBase64 {}
+
+/// from_base64(text), whitespace is skipped over and the padding is optional, returns null if
+/// the text isn't valid base64.
+impl Function for FromBase64 {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(text) = args[0].as_maybe_text() {
+            decode(text).map(Datum::from).unwrap_or_default()
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let trimmed = text.trim_end_matches(|c: char| c == '=' || c.is_ascii_whitespace());
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer = 0_u32;
+    let mut bits = 0;
+    for c in trimmed.bytes() {
+        if c.is_ascii_whitespace() {
+            continue;
+        }
+        let sextet = ALPHABET.iter().position(|a| *a == c)? as u32;
+        buffer = buffer << 6 | sextet;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    // A single leftover character can't make up a whole byte
+    if bits >= 6 {
+        None
+    } else {
+        Some(bytes)
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "to_base64",
+        vec![DataType::ByteA],
+        DataType::Text,
+        FunctionType::Scalar(&ToBase64 {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "from_base64",
+        vec![DataType::Text],
+        DataType::ByteA,
+        FunctionType::Scalar(&FromBase64 {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "to_base64",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_null() {
+        let session = Session::new(1);
+        assert_eq!(
+            ToBase64 {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+        assert_eq!(
+            FromBase64 {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_to_base64() {
+        let session = Session::new(1);
+        for (bytes, encoded) in &[
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(
+                ToBase64 {}.execute(
+                    &session,
+                    &DUMMY_SIG,
+                    &[Datum::from(bytes.as_bytes().to_vec())]
+                ),
+                Datum::from(*encoded)
+            );
+        }
+        assert_eq!(
+            ToBase64 {}.execute(&session, &DUMMY_SIG, &[Datum::from(vec![0xfb, 0xff])]),
+            Datum::from("+/8=")
+        );
+    }
+
+    #[test]
+    fn test_from_base64() {
+        let session = Session::new(1);
+        for (encoded, bytes) in &[
+            ("", ""),
+            ("Zg==", "f"),
+            ("Zm8", "fo"),
+            ("Zm9v\nYmFy", "foobar"),
+        ] {
+            assert_eq!(
+                FromBase64 {}.execute(&session, &DUMMY_SIG, &[Datum::from(*encoded)]),
+                Datum::from(bytes.as_bytes().to_vec())
+            );
+        }
+        assert_eq!(
+            FromBase64 {}.execute(&session, &DUMMY_SIG, &[Datum::from("Zm9v!")]),
+            Datum::Null
+        );
+        assert_eq!(
+            FromBase64 {}.execute(&session, &DUMMY_SIG, &[Datum::from("Zm9vY")]),
+            Datum::Null
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+use std::fmt::Write;
+
+#[derive(Debug)]
+struct HexFromBytes {}
+
+/// hex(bytes), text is hexed as its utf8 bytes
+impl Function for HexFromBytes {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(bytes) = args[0].as_maybe_bytea() {
+            let mut hex = String::with_capacity(bytes.len() * 2);
+            for b in bytes {
+                write!(hex, "{:02X}", b).unwrap();
+            }
+            Datum::from(hex)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct HexFromBigint {}
+
+/// hex(bigint), negative numbers are hexed as their 64 bit twos complement like mysql
+impl Function for HexFromBigint {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(i) = args[0].as_maybe_bigint() {
+            Datum::from(format!("{:X}", i as u64))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Unhex {}
+
+/// unhex(text), returns null if the text isn't valid hex. An odd number of digits is treated as
+/// though it had a leading zero.
+impl Function for Unhex {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(text) = args[0].as_maybe_text() {
+            let padded;
+            let digits = if text.len() % 2 == 1 {
+                padded = format!("0{}", text);
+                padded.as_str()
+            } else {
+                text
+            };
+            let bytes: Option<Vec<_>> = (0..digits.len())
+                .step_by(2)
+                .map(|idx| {
+                    digits
+                        .get(idx..idx + 2)
+                        .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                })
+                .collect();
+            bytes.map(Datum::from).unwrap_or_default()
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "hex",
+        vec![DataType::ByteA],
+        DataType::Text,
+        FunctionType::Scalar(&HexFromBytes {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "hex",
+        vec![DataType::BigInt],
+        DataType::Text,
+        FunctionType::Scalar(&HexFromBigint {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "unhex",
+        vec![DataType::Text],
+        DataType::ByteA,
+        FunctionType::Scalar(&Unhex {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "hex",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_null() {
+        let session = Session::new(1);
+        assert_eq!(
+            HexFromBytes {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+        assert_eq!(
+            HexFromBigint {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+        assert_eq!(
+            Unhex {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_hex() {
+        let session = Session::new(1);
+        assert_eq!(
+            HexFromBytes {}.execute(&session, &DUMMY_SIG, &[Datum::from(vec![0xde, 0xad, 0x0f])]),
+            Datum::from("DEAD0F")
+        );
+        assert_eq!(
+            HexFromBigint {}.execute(&session, &DUMMY_SIG, &[Datum::from(255_i64)]),
+            Datum::from("FF")
+        );
+        assert_eq!(
+            HexFromBigint {}.execute(&session, &DUMMY_SIG, &[Datum::from(-1_i64)]),
+            Datum::from("FFFFFFFFFFFFFFFF")
+        );
+    }
+
+    #[test]
+    fn test_unhex() {
+        let session = Session::new(1);
+        assert_eq!(
+            Unhex {}.execute(&session, &DUMMY_SIG, &[Datum::from("DEad0f")]),
+            Datum::from(vec![0xde, 0xad, 0x0f])
+        );
+        assert_eq!(
+            Unhex {}.execute(&session, &DUMMY_SIG, &[Datum::from("abc")]),
+            Datum::from(vec![0x0a, 0xbc])
+        );
+        assert_eq!(
+            Unhex {}.execute(&session, &DUMMY_SIG, &[Datum::from("zz")]),
+            Datum::Null
+        );
+        assert_eq!(
+            Unhex {}.execute(&session, &DUMMY_SIG, &[Datum::from("é")]),
+            Datum::Null
+        );
+    }
+}
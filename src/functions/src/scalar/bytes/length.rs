@@ -0,0 +1,62 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct Length {}
+
+/// length(bytes), the length in bytes, so text is measured by its utf8 encoding like mysql
+impl Function for Length {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(bytes) = args[0].as_maybe_bytea() {
+            Datum::from(bytes.len() as i64)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "length",
+        vec![DataType::ByteA],
+        DataType::BigInt,
+        FunctionType::Scalar(&Length {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "length",
+        args: vec![],
+        ret: DataType::BigInt,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            Length {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_length() {
+        assert_eq!(
+            Length {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(vec![0, 1, 2])]),
+            Datum::from(3_i64)
+        );
+        assert_eq!(
+            Length {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from("é")]),
+            Datum::from(2_i64)
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use crate::registry::Registry;
+
+mod base64;
+mod hex;
+mod length;
+mod url_encode;
+
+pub fn register_builtins(registry: &mut Registry) {
+    base64::register_builtins(registry);
+    hex::register_builtins(registry);
+    length::register_builtins(registry);
+    url_encode::register_builtins(registry);
+}
@@ -0,0 +1,146 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+use std::fmt::Write;
+
+#[derive(Debug)]
+struct UrlEncode {}
+
+/// url_encode(bytes), percent encodes everything other than the unreserved characters
+/// (A-Z a-z 0-9 - _ . ~), text is encoded as its utf8 bytes.
+impl Function for UrlEncode {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(bytes) = args[0].as_maybe_bytea() {
+            let mut encoded = String::with_capacity(bytes.len());
+            for b in bytes {
+                if b.is_ascii_alphanumeric() || b"-_.~".contains(b) {
+                    encoded.push(*b as char);
+                } else {
+                    write!(encoded, "%{:02X}", b).unwrap();
+                }
+            }
+            Datum::from(encoded)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct UrlDecode {}
+
+/// url_decode(text), decodes the percent encoded bytes and like query strings treats + as a
+/// space. Returns null if there's a bad escape or the decoded bytes aren't valid utf8.
+impl Function for UrlDecode {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(text) = args[0].as_maybe_text() {
+            decode(text)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .map(Datum::from)
+                .unwrap_or_default()
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'%' => {
+                let high = (iter.next()? as char).to_digit(16)?;
+                let low = (iter.next()? as char).to_digit(16)?;
+                bytes.push((high << 4 | low) as u8);
+            }
+            b'+' => bytes.push(b' '),
+            b => bytes.push(b),
+        }
+    }
+    Some(bytes)
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "url_encode",
+        vec![DataType::ByteA],
+        DataType::Text,
+        FunctionType::Scalar(&UrlEncode {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "url_decode",
+        vec![DataType::Text],
+        DataType::Text,
+        FunctionType::Scalar(&UrlDecode {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "url_encode",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_null() {
+        let session = Session::new(1);
+        assert_eq!(
+            UrlEncode {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+        assert_eq!(
+            UrlDecode {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_url_encode() {
+        let session = Session::new(1);
+        assert_eq!(
+            UrlEncode {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[Datum::from("a b&c=d/é~".as_bytes().to_vec())]
+            ),
+            Datum::from("a%20b%26c%3Dd%2F%C3%A9~")
+        );
+    }
+
+    #[test]
+    fn test_url_decode() {
+        let session = Session::new(1);
+        assert_eq!(
+            UrlDecode {}.execute(&session, &DUMMY_SIG, &[Datum::from("a%20b+c%3dd%2F%C3%A9")]),
+            Datum::from("a b c=d/é")
+        );
+        assert_eq!(
+            UrlDecode {}.execute(&session, &DUMMY_SIG, &[Datum::from("100%")]),
+            Datum::Null
+        );
+        assert_eq!(
+            UrlDecode {}.execute(&session, &DUMMY_SIG, &[Datum::from("%zz")]),
+            Datum::Null
+        );
+        assert_eq!(
+            UrlDecode {}.execute(&session, &DUMMY_SIG, &[Datum::from("%FF")]),
+            Datum::Null
+        );
+    }
+}
@@ -1,25 +1,128 @@
 use crate::registry::Registry;
+use crate::{Function, FunctionSignature};
+use data::{Datum, Session};
 
 mod to_bigint;
 mod to_bool;
+mod to_bytes;
 mod to_date;
 mod to_decimal;
+mod to_double;
 mod to_int;
 mod to_json;
 mod to_jsonpath;
+mod to_number_lenient;
+mod to_sized_int;
 mod to_text;
 mod to_timestamp;
+mod to_vector;
 mod type_of;
 
+/// Wraps a cast function so that when the session is in strict mode a value that can't be
+/// converted by a sql CAST (ie CAST('abc' AS INT)) raises an error rather than just becoming null.
+/// The planner gives casts compiled from CAST the signature name "cast", TRY_CAST always returns
+/// null (comparing text to numbers uses the lenient conversions instead).
+#[derive(Debug)]
+struct StrictCast<F>(F);
+
+impl<F: Function> Function for StrictCast<F> {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let result = self.0.execute(session, signature, args);
+        if result.is_null()
+            && !args[0].is_null()
+            && signature.name == "cast"
+            && session.strict_mode()
+        {
+            session.raise_out_of_range(format!(
+                "Cannot cast {:#} to {}",
+                args[0].typed_with(signature.args[0]),
+                signature.ret
+            ));
+        }
+        result
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        self.0.nullable(args_nullable)
+    }
+}
+
 pub fn register_builtins(registry: &mut Registry) {
     to_bigint::register_builtins(registry);
     to_bool::register_builtins(registry);
+    to_bytes::register_builtins(registry);
     to_date::register_builtins(registry);
     to_decimal::register_builtins(registry);
+    to_double::register_builtins(registry);
     to_int::register_builtins(registry);
     to_json::register_builtins(registry);
     to_jsonpath::register_builtins(registry);
+    to_number_lenient::register_builtins(registry);
+    to_sized_int::register_builtins(registry);
     to_text::register_builtins(registry);
     to_timestamp::register_builtins(registry);
+    to_vector::register_builtins(registry);
     type_of::register_builtins(registry);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::DataType;
+
+    #[derive(Debug)]
+    struct AlwaysNull {}
+
+    impl Function for AlwaysNull {
+        fn execute<'a>(
+            &self,
+            _session: &Session,
+            _signature: &FunctionSignature,
+            _args: &'a [Datum<'a>],
+        ) -> Datum<'a> {
+            Datum::Null
+        }
+    }
+
+    #[test]
+    fn test_strict_cast() {
+        let session = Session::new(1);
+        let mut signature = FunctionSignature {
+            name: "cast",
+            args: vec![DataType::Text],
+            ret: DataType::Integer,
+        };
+        let cast = StrictCast(AlwaysNull {});
+
+        // Not in strict mode
+        assert_eq!(
+            cast.execute(&session, &signature, &[Datum::from("abc")]),
+            Datum::Null
+        );
+        assert_eq!(session.take_out_of_range(), None);
+
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("sql_mode".to_string(), Datum::from("STRICT_TRANS_TABLES"));
+        cast.execute(&session, &signature, &[Datum::Null]);
+        assert_eq!(session.take_out_of_range(), None);
+
+        cast.execute(&session, &signature, &[Datum::from("abc")]);
+        assert_eq!(
+            session.take_out_of_range(),
+            Some(r#"Cannot cast "abc" to INTEGER"#.to_string())
+        );
+
+        // TRY_CAST or an implicit cast
+        signature.name = "to_int";
+        cast.execute(&session, &signature, &[Datum::from("abc")]);
+        assert_eq!(session.take_out_of_range(), None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::rust_decimal::prelude::ToPrimitive;
@@ -71,6 +72,24 @@ impl Function for ToBigIntFromDecimal {
     }
 }
 
+#[derive(Debug)]
+struct ToBigIntFromDouble {}
+
+impl Function for ToBigIntFromDouble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_double() {
+            a.to_i64().map(Datum::from).unwrap_or(Datum::Null)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ToBigIntFromText {}
 
@@ -126,42 +145,57 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_bigint",
         vec![DataType::Boolean],
         DataType::BigInt,
-        FunctionType::Scalar(&ToBigIntFromBoolean {}),
+        FunctionType::Scalar(&StrictCast(ToBigIntFromBoolean {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bigint",
         vec![DataType::Integer],
         DataType::BigInt,
-        FunctionType::Scalar(&ToBigIntFromInt {}),
+        FunctionType::Scalar(&StrictCast(ToBigIntFromInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bigint",
         vec![DataType::BigInt],
         DataType::BigInt,
-        FunctionType::Scalar(&ToBigIntFromBigInt {}),
+        FunctionType::Scalar(&StrictCast(ToBigIntFromBigInt {})),
+    ));
+
+    // Unsigned ints are stored as bigints already
+    registry.register_function(FunctionDefinition::new(
+        "to_bigint",
+        vec![DataType::UnsignedInteger],
+        DataType::BigInt,
+        FunctionType::Scalar(&StrictCast(ToBigIntFromBigInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bigint",
         vec![DataType::Decimal(0, 0)],
         DataType::BigInt,
-        FunctionType::Scalar(&ToBigIntFromDecimal {}),
+        FunctionType::Scalar(&StrictCast(ToBigIntFromDecimal {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_bigint",
+        vec![DataType::Double],
+        DataType::BigInt,
+        FunctionType::Scalar(&StrictCast(ToBigIntFromDouble {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bigint",
         vec![DataType::Text],
         DataType::BigInt,
-        FunctionType::Scalar(&ToBigIntFromText {}),
+        FunctionType::Scalar(&StrictCast(ToBigIntFromText {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bigint",
         vec![DataType::Json],
         DataType::BigInt,
-        FunctionType::Scalar(&ToBigIntFromJson {}),
+        FunctionType::Scalar(&StrictCast(ToBigIntFromJson {})),
     ));
 }
 
@@ -221,6 +255,18 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_double() {
+        assert_eq!(
+            ToBigIntFromDouble {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1.75)]),
+            Datum::from(1_i64)
+        );
+        assert_eq!(
+            ToBigIntFromDouble {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1e40)]),
+            Datum::Null
+        );
+    }
+
     #[test]
     fn test_from_text() {
         assert_eq!(
@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::{DataType, Datum, Session};
@@ -71,21 +72,21 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_bool",
         vec![DataType::Boolean],
         DataType::Boolean,
-        FunctionType::Scalar(&ToBooleanFromBoolean {}),
+        FunctionType::Scalar(&StrictCast(ToBooleanFromBoolean {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bool",
         vec![DataType::Text],
         DataType::Boolean,
-        FunctionType::Scalar(&ToBooleanFromText {}),
+        FunctionType::Scalar(&StrictCast(ToBooleanFromText {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_bool",
         vec![DataType::Json],
         DataType::Boolean,
-        FunctionType::Scalar(&ToBooleanFromJson {}),
+        FunctionType::Scalar(&StrictCast(ToBooleanFromJson {})),
     ));
 }
 
@@ -0,0 +1,57 @@
+use super::StrictCast;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+/// Text is already stored as its utf8 bytes so there's nothing to do.
+#[derive(Debug)]
+struct ToBytesFromText {}
+
+impl Function for ToBytesFromText {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0].ref_clone()
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for datatype in &[DataType::Text, DataType::ByteA] {
+        registry.register_function(FunctionDefinition::new(
+            "to_bytes",
+            vec![*datatype],
+            DataType::ByteA,
+            FunctionType::Scalar(&StrictCast(ToBytesFromText {})),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "to_bytes",
+        args: vec![],
+        ret: DataType::ByteA,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            ToBytesFromText {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            ToBytesFromText {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from("abc")]),
+            Datum::from(b"abc".to_vec())
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::chrono::NaiveDate;
@@ -30,7 +31,7 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_date",
         vec![DataType::Text],
         DataType::Date,
-        FunctionType::Scalar(&ToDateFromText {}),
+        FunctionType::Scalar(&StrictCast(ToDateFromText {})),
     ));
 }
 
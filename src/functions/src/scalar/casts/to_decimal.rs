@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::rust_decimal::Decimal;
@@ -89,6 +90,34 @@ impl Function for ToDecimalFromDecimal {
     }
 }
 
+#[derive(Debug)]
+struct ToDecimalFromDouble {}
+
+impl Function for ToDecimalFromDouble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        // Going via the shortest round trippable string representation avoids picking up all the
+        // binary noise (ie 0.1 => 0.1000000000000000055511151231)
+        if let (Some(Ok(mut d)), DataType::Decimal(_p, s)) = (
+            args[0]
+                .as_maybe_double()
+                .map(|f| Decimal::from_str(&f.to_string())),
+            signature.ret,
+        ) {
+            if (s as u32) < d.scale() {
+                d.rescale(s as u32);
+            }
+            Datum::from(d)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ToDecimalFromText {}
 
@@ -157,21 +186,21 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_decimal",
         vec![DataType::Boolean],
         DataType::Decimal(1, 0),
-        FunctionType::Scalar(&ToDecimalFromBoolean {}),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromBoolean {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_decimal",
         vec![DataType::Integer],
         DataType::Decimal(10, 0),
-        FunctionType::Scalar(&ToDecimalFromInt {}),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_decimal",
         vec![DataType::BigInt],
         DataType::Decimal(20, 0),
-        FunctionType::Scalar(&ToDecimalFromBigInt {}),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromBigInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new_with_type_resolver(
@@ -180,21 +209,36 @@ pub fn register_builtins(registry: &mut Registry) {
         // Remembering this is just the default that can be overridden in casts, this value will only
         // be used if called as a function
         |args| args[0],
-        FunctionType::Scalar(&ToDecimalFromDecimal {}),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromDecimal {})),
+    ));
+
+    // Unsigned bigints are stored as decimals already
+    registry.register_function(FunctionDefinition::new(
+        "to_decimal",
+        vec![DataType::UnsignedBigInt],
+        DataType::Decimal(20, 0),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromDecimal {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_decimal",
+        vec![DataType::Double],
+        DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromDouble {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_decimal",
         vec![DataType::Text],
         DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
-        FunctionType::Scalar(&ToDecimalFromText {}),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromText {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_decimal",
         vec![DataType::Json],
         DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
-        FunctionType::Scalar(&ToDecimalFromJson {}),
+        FunctionType::Scalar(&StrictCast(ToDecimalFromJson {})),
     ));
 }
 
@@ -254,6 +298,14 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_double() {
+        assert_eq!(
+            ToDecimalFromDouble {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(0.125)]),
+            Datum::from(Decimal::new(13, 2))
+        )
+    }
+
     #[test]
     fn test_from_text() {
         assert_eq!(
@@ -0,0 +1,282 @@
+use super::StrictCast;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::rust_decimal::prelude::ToPrimitive;
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct ToDoubleFromBoolean {}
+
+impl Function for ToDoubleFromBoolean {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_boolean() {
+            Datum::from(if a { 1.0 } else { 0.0 })
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToDoubleFromInt {}
+
+impl Function for ToDoubleFromInt {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_integer() {
+            Datum::from(a as f64)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToDoubleFromBigInt {}
+
+impl Function for ToDoubleFromBigInt {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_bigint() {
+            Datum::from(a as f64)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToDoubleFromDecimal {}
+
+impl Function for ToDoubleFromDecimal {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_decimal().and_then(|d| d.to_f64()) {
+            Datum::from(a)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToDoubleFromDouble {}
+
+impl Function for ToDoubleFromDouble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0].ref_clone()
+    }
+}
+
+#[derive(Debug)]
+struct ToDoubleFromText {}
+
+impl Function for ToDoubleFromText {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_text() {
+            a.trim()
+                .parse::<f64>()
+                .ok()
+                .map(Datum::from)
+                .unwrap_or(Datum::Null)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToDoubleFromJson {}
+
+impl Function for ToDoubleFromJson {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        // We need to try both the json::number and the json::text
+        if let Some(d) = args[0]
+            .as_maybe_json()
+            .and_then(|j| j.get_number())
+            .and_then(|d| d.to_f64())
+        {
+            Datum::from(d)
+        } else if let Some(d) = args[0]
+            .as_maybe_json()
+            .and_then(|j| j.get_string())
+            .and_then(|s| s.parse::<f64>().ok())
+        {
+            Datum::from(d)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::Boolean],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromBoolean {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::Integer],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromInt {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::BigInt],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromBigInt {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::Decimal(0, 0)],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromDecimal {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::Double],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromDouble {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::Text],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromText {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double",
+        vec![DataType::Json],
+        DataType::Double,
+        FunctionType::Scalar(&StrictCast(ToDoubleFromJson {})),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+    use data::rust_decimal::Decimal;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "to_double",
+        args: vec![],
+        ret: DataType::Double,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            ToDoubleFromInt {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_from_bool() {
+        assert_eq!(
+            ToDoubleFromBoolean {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(true)]),
+            Datum::from(1.0)
+        )
+    }
+
+    #[test]
+    fn test_from_int() {
+        assert_eq!(
+            ToDoubleFromInt {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(3)]),
+            Datum::from(3.0)
+        )
+    }
+
+    #[test]
+    fn test_from_bigint() {
+        assert_eq!(
+            ToDoubleFromBigInt {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(3_i64)]),
+            Datum::from(3.0)
+        )
+    }
+
+    #[test]
+    fn test_from_decimal() {
+        assert_eq!(
+            ToDoubleFromDecimal {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(Decimal::new(125, 2))]
+            ),
+            Datum::from(1.25)
+        )
+    }
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            ToDoubleFromText {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from("1.5e2")]),
+            Datum::from(150.0)
+        );
+        assert_eq!(
+            ToDoubleFromText {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from("abc")]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_from_json() {
+        assert_eq!(
+            ToDoubleFromJson {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(OwnedJson::parse("1.25").unwrap())]
+            ),
+            Datum::from(1.25)
+        );
+        assert_eq!(
+            ToDoubleFromJson {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(OwnedJson::parse("\"1.25\"").unwrap())]
+            ),
+            Datum::from(1.25)
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::rust_decimal::prelude::ToPrimitive;
@@ -46,7 +47,7 @@ impl Function for ToIntFromBigInt {
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let Some(a) = args[0].as_maybe_bigint() {
-            Datum::from(a as i32)
+            a.to_i32().map(Datum::from).unwrap_or(Datum::Null)
         } else {
             Datum::Null
         }
@@ -71,6 +72,24 @@ impl Function for ToIntFromDecimal {
     }
 }
 
+#[derive(Debug)]
+struct ToIntFromDouble {}
+
+impl Function for ToIntFromDouble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(a) = args[0].as_maybe_double() {
+            a.to_i32().map(Datum::from).unwrap_or(Datum::Null)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ToIntFromText {}
 
@@ -126,42 +145,64 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_int",
         vec![DataType::Boolean],
         DataType::Integer,
-        FunctionType::Scalar(&ToIntFromBoolean {}),
+        FunctionType::Scalar(&StrictCast(ToIntFromBoolean {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_int",
         vec![DataType::Integer],
         DataType::Integer,
-        FunctionType::Scalar(&ToIntFromInt {}),
+        FunctionType::Scalar(&StrictCast(ToIntFromInt {})),
     ));
 
+    // The smaller ints are all stored as ints already
+    for datatype in &[
+        DataType::TinyInt,
+        DataType::SmallInt,
+        DataType::UnsignedTinyInt,
+        DataType::UnsignedSmallInt,
+    ] {
+        registry.register_function(FunctionDefinition::new(
+            "to_int",
+            vec![*datatype],
+            DataType::Integer,
+            FunctionType::Scalar(&StrictCast(ToIntFromInt {})),
+        ));
+    }
+
     registry.register_function(FunctionDefinition::new(
         "to_int",
         vec![DataType::BigInt],
         DataType::Integer,
-        FunctionType::Scalar(&ToIntFromBigInt {}),
+        FunctionType::Scalar(&StrictCast(ToIntFromBigInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_int",
         vec![DataType::Decimal(0, 0)],
         DataType::Integer,
-        FunctionType::Scalar(&ToIntFromDecimal {}),
+        FunctionType::Scalar(&StrictCast(ToIntFromDecimal {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_int",
+        vec![DataType::Double],
+        DataType::Integer,
+        FunctionType::Scalar(&StrictCast(ToIntFromDouble {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_int",
         vec![DataType::Text],
         DataType::Integer,
-        FunctionType::Scalar(&ToIntFromText {}),
+        FunctionType::Scalar(&StrictCast(ToIntFromText {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_int",
         vec![DataType::Json],
         DataType::Integer,
-        FunctionType::Scalar(&ToIntFromJson {}),
+        FunctionType::Scalar(&StrictCast(ToIntFromJson {})),
     ));
 }
 
@@ -206,6 +247,14 @@ mod tests {
         assert_eq!(
             ToIntFromBigInt {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1_i64)]),
             Datum::from(1)
+        );
+        assert_eq!(
+            ToIntFromBigInt {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(4294967296_i64)]
+            ),
+            Datum::Null
         )
     }
 
@@ -221,6 +270,18 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_double() {
+        assert_eq!(
+            ToIntFromDouble {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1.75)]),
+            Datum::from(1)
+        );
+        assert_eq!(
+            ToIntFromDouble {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1e40)]),
+            Datum::Null
+        );
+    }
+
     #[test]
     fn test_from_text() {
         assert_eq!(
@@ -1,7 +1,24 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::json::{JsonBuilder, OwnedJson};
+use data::rust_decimal::Decimal;
 use data::{DataType, Datum, Session};
+use std::str::FromStr;
+
+#[derive(Debug)]
+struct ToJsonFromJson {}
+
+impl Function for ToJsonFromJson {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0].ref_clone()
+    }
+}
 
 #[derive(Debug)]
 struct ToJsonFromBoolean {}
@@ -75,6 +92,28 @@ impl Function for ToJsonFromDecimal {
     }
 }
 
+#[derive(Debug)]
+struct ToJsonFromDouble {}
+
+impl Function for ToJsonFromDouble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        // Json numbers are stored as decimals, infinities etc can't be represented.
+        if let Some(Ok(d)) = args[0]
+            .as_maybe_double()
+            .map(|f| Decimal::from_str(&f.to_string()))
+        {
+            Datum::from(JsonBuilder::default().decimal(d))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ToJsonFromText {}
 
@@ -98,42 +137,55 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_json",
         vec![DataType::Boolean],
         DataType::Json,
-        FunctionType::Scalar(&ToJsonFromBoolean {}),
+        FunctionType::Scalar(&StrictCast(ToJsonFromBoolean {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_json",
         vec![DataType::Integer],
         DataType::Json,
-        FunctionType::Scalar(&ToJsonFromInt {}),
+        FunctionType::Scalar(&StrictCast(ToJsonFromInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_json",
         vec![DataType::BigInt],
         DataType::Json,
-        FunctionType::Scalar(&ToJsonFromBigInt {}),
+        FunctionType::Scalar(&StrictCast(ToJsonFromBigInt {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_json",
         vec![DataType::Decimal(0, 0)],
         DataType::Json,
-        FunctionType::Scalar(&ToJsonFromDecimal {}),
+        FunctionType::Scalar(&StrictCast(ToJsonFromDecimal {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_json",
+        vec![DataType::Double],
+        DataType::Json,
+        FunctionType::Scalar(&StrictCast(ToJsonFromDouble {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_json",
         vec![DataType::Text],
         DataType::Json,
-        FunctionType::Scalar(&ToJsonFromText {}),
+        FunctionType::Scalar(&StrictCast(ToJsonFromText {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_json",
+        vec![DataType::Json],
+        DataType::Json,
+        FunctionType::Scalar(&StrictCast(ToJsonFromJson {})),
     ));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use data::rust_decimal::Decimal;
 
     const DUMMY_SIG: FunctionSignature = FunctionSignature {
         name: "to_json",
@@ -149,6 +201,15 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_json() {
+        let json = Datum::from(JsonBuilder::default().int(1));
+        assert_eq!(
+            ToJsonFromJson {}.execute(&Session::new(1), &DUMMY_SIG, &[json.ref_clone()]),
+            json
+        )
+    }
+
     #[test]
     fn test_boolean() {
         assert_eq!(
@@ -185,6 +246,22 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_double() {
+        assert_eq!(
+            ToJsonFromDouble {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(12.34)]),
+            Datum::from(JsonBuilder::default().decimal(Decimal::new(1234, 2)))
+        );
+        assert_eq!(
+            ToJsonFromDouble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(std::f64::INFINITY)]
+            ),
+            Datum::Null
+        );
+    }
+
     #[test]
     fn test_text() {
         // Casts from text actually parse the json.
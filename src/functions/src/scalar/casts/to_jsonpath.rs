@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::jsonpath_utils::JsonPathExpression;
@@ -31,7 +32,7 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_jsonpath",
         vec![DataType::Text],
         DataType::JsonPath,
-        FunctionType::Scalar(&ToJsonpath {}),
+        FunctionType::Scalar(&StrictCast(ToJsonpath {})),
     ));
 }
 
@@ -0,0 +1,192 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::rust_decimal::prelude::FromPrimitive;
+use data::rust_decimal::Decimal;
+use data::{DataType, Datum, Session, DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE};
+use std::str::FromStr;
+
+/// The mysql error code for the warning raised when text is truncated to a number.
+const TRUNCATED_WRONG_VALUE: u16 = 1292;
+
+/// Converts text to a decimal the way mysql does when comparing text to a number, the longest
+/// numeric prefix is used (ie '5x' => 5) or 0 if there isn't one. Anything left over raises a
+/// warning, as do numbers too big for a decimal which get clamped to the largest one (ie
+/// '1e400' => 79228162514264337593543950335).
+#[derive(Debug)]
+struct ToDecimalLenient {}
+
+impl Function for ToDecimalLenient {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(text) = args[0].as_maybe_text() {
+            let (prefix, whole) = numeric_prefix(text);
+            let decimal = if prefix.contains(|c| c == 'e' || c == 'E') {
+                prefix.parse::<f64>().ok().and_then(Decimal::from_f64)
+            } else {
+                Decimal::from_str(prefix).ok()
+            };
+            if !whole || decimal.is_none() {
+                truncated_warning(session, text, "DECIMAL");
+            }
+            Datum::from(decimal.unwrap_or_else(|| {
+                if prefix.starts_with('-') {
+                    Decimal::min_value()
+                } else {
+                    Decimal::max_value()
+                }
+            }))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+/// Like ToDecimalLenient but for doubles, numbers too big for a double get clamped to the largest
+/// finite one rather than becoming infinite.
+#[derive(Debug)]
+struct ToDoubleLenient {}
+
+impl Function for ToDoubleLenient {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(text) = args[0].as_maybe_text() {
+            let (prefix, whole) = numeric_prefix(text);
+            let double = prefix.parse::<f64>().unwrap_or(0.0);
+            if !whole || double.is_infinite() {
+                truncated_warning(session, text, "DOUBLE");
+            }
+            Datum::from(double.max(f64::MIN).min(f64::MAX))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+/// Returns the longest prefix of the text (ignoring whitespace) that's a number, "0" if there
+/// isn't one, along with whether that was the whole text.
+fn numeric_prefix(text: &str) -> (&str, bool) {
+    let trimmed = text.trim_start();
+    let bytes = trimmed.as_bytes();
+    let digits = |from: usize| {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let mut end = if matches!(bytes.first(), Some(b'+') | Some(b'-')) {
+        1
+    } else {
+        0
+    };
+    let int_digits = digits(end);
+    end += int_digits;
+    let mut frac_digits = 0;
+    if bytes.get(end) == Some(&b'.') {
+        frac_digits = digits(end + 1);
+        end += 1 + frac_digits;
+    }
+    if int_digits + frac_digits > 0 && matches!(bytes.get(end), Some(b'e') | Some(b'E')) {
+        let sign = matches!(bytes.get(end + 1), Some(b'+') | Some(b'-')) as usize;
+        let exponent_digits = digits(end + 1 + sign);
+        if exponent_digits > 0 {
+            end += 1 + sign + exponent_digits;
+        }
+    }
+
+    let prefix = if int_digits + frac_digits == 0 {
+        "0"
+    } else {
+        trimmed[..end].trim_end_matches('.')
+    };
+    let whole = int_digits + frac_digits > 0 && trimmed[end..].trim().is_empty();
+    (prefix, whole)
+}
+
+fn truncated_warning(session: &Session, text: &str, type_name: &str) {
+    session.raise_warning(
+        TRUNCATED_WRONG_VALUE,
+        format!("Truncated incorrect {} value: '{}'", type_name, text),
+    );
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "to_decimal_lenient",
+        vec![DataType::Text],
+        DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
+        FunctionType::Scalar(&ToDecimalLenient {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_double_lenient",
+        vec![DataType::Text],
+        DataType::Double,
+        FunctionType::Scalar(&ToDoubleLenient {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> (Datum<'static>, usize) {
+        let session = Session::new(1);
+        let signature = FunctionSignature {
+            name: "to_decimal_lenient",
+            args: vec![DataType::Text],
+            ret: DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
+        };
+        let result = ToDecimalLenient {}
+            .execute(&session, &signature, &[Datum::from(text)])
+            .as_static();
+        let warnings = session.warnings.read().unwrap().len();
+        (result, warnings)
+    }
+
+    fn double(text: &str) -> (Datum<'static>, usize) {
+        let session = Session::new(1);
+        let signature = FunctionSignature {
+            name: "to_double_lenient",
+            args: vec![DataType::Text],
+            ret: DataType::Double,
+        };
+        let result = ToDoubleLenient {}
+            .execute(&session, &signature, &[Datum::from(text)])
+            .as_static();
+        let warnings = session.warnings.read().unwrap().len();
+        (result, warnings)
+    }
+
+    #[test]
+    fn test_to_decimal_lenient() {
+        assert_eq!(decimal(" 5 "), (Datum::from(Decimal::new(5, 0)), 0));
+        assert_eq!(decimal("-1.25"), (Datum::from(Decimal::new(-125, 2)), 0));
+        assert_eq!(decimal("5."), (Datum::from(Decimal::new(5, 0)), 0));
+        assert_eq!(decimal(".5"), (Datum::from(Decimal::new(5, 1)), 0));
+        assert_eq!(decimal("5x"), (Datum::from(Decimal::new(5, 0)), 1));
+        assert_eq!(decimal("1e3"), (Datum::from(Decimal::new(1000, 0)), 0));
+        assert_eq!(decimal("1e400"), (Datum::from(Decimal::max_value()), 1));
+        assert_eq!(decimal("-1e400x"), (Datum::from(Decimal::min_value()), 1));
+        assert_eq!(decimal("abc"), (Datum::from(Decimal::new(0, 0)), 1));
+        assert_eq!(decimal(""), (Datum::from(Decimal::new(0, 0)), 1));
+    }
+
+    #[test]
+    fn test_to_double_lenient() {
+        assert_eq!(double("1e3"), (Datum::from(1000.0), 0));
+        assert_eq!(double("1.5e"), (Datum::from(1.5), 1));
+        assert_eq!(double("-.5x"), (Datum::from(-0.5), 1));
+        assert_eq!(double("abc"), (Datum::from(0.0), 1));
+        assert_eq!(double("1e400"), (Datum::from(f64::MAX), 1));
+        assert_eq!(double("-1e400"), (Datum::from(f64::MIN), 1));
+    }
+}
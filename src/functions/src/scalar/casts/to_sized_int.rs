@@ -0,0 +1,306 @@
+use super::StrictCast;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::rust_decimal::prelude::ToPrimitive;
+use data::{DataType, Datum, Session};
+
+/// The tinyint, smallint and unsigned casts all share the same implementations, the target type
+/// comes from the signature and anything that doesn't fit in its range becomes null.
+const SIZED_INTS: [(&str, DataType); 6] = [
+    ("to_tinyint", DataType::TinyInt),
+    ("to_smallint", DataType::SmallInt),
+    ("to_utinyint", DataType::UnsignedTinyInt),
+    ("to_usmallint", DataType::UnsignedSmallInt),
+    ("to_uint", DataType::UnsignedInteger),
+    ("to_ubigint", DataType::UnsignedBigInt),
+];
+
+/// Anything that fits in either an i64 or a u64.
+fn to_i128<N: ToPrimitive>(n: N) -> Option<i128> {
+    n.to_i64()
+        .map(i128::from)
+        .or_else(|| n.to_u64().map(i128::from))
+}
+
+#[derive(Debug)]
+struct ToSizedIntFromInt {}
+
+impl Function for ToSizedIntFromInt {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0]
+            .as_maybe_integer()
+            .and_then(|i| signature.ret.integer_datum(i128::from(i)))
+            .unwrap_or(Datum::Null)
+    }
+}
+
+#[derive(Debug)]
+struct ToSizedIntFromBigInt {}
+
+impl Function for ToSizedIntFromBigInt {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0]
+            .as_maybe_bigint()
+            .and_then(|i| signature.ret.integer_datum(i128::from(i)))
+            .unwrap_or(Datum::Null)
+    }
+}
+
+#[derive(Debug)]
+struct ToSizedIntFromDecimal {}
+
+impl Function for ToSizedIntFromDecimal {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0]
+            .as_maybe_decimal()
+            .and_then(|d| to_i128(d.trunc()))
+            .and_then(|i| signature.ret.integer_datum(i))
+            .unwrap_or(Datum::Null)
+    }
+}
+
+#[derive(Debug)]
+struct ToSizedIntFromDouble {}
+
+impl Function for ToSizedIntFromDouble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0]
+            .as_maybe_double()
+            .and_then(|d| to_i128(d.trunc()))
+            .and_then(|i| signature.ret.integer_datum(i))
+            .unwrap_or(Datum::Null)
+    }
+}
+
+#[derive(Debug)]
+struct ToSizedIntFromText {}
+
+impl Function for ToSizedIntFromText {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        args[0]
+            .as_maybe_text()
+            .and_then(|s| s.parse::<i128>().ok())
+            .and_then(|i| signature.ret.integer_datum(i))
+            .unwrap_or(Datum::Null)
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for (function_name, datatype) in SIZED_INTS.iter() {
+        // The casts have to resolve exactly, the smaller ints are all stored as ints, unsigned
+        // ints as bigints and unsigned bigints as decimals
+        for from in &[
+            DataType::Integer,
+            DataType::TinyInt,
+            DataType::SmallInt,
+            DataType::UnsignedTinyInt,
+            DataType::UnsignedSmallInt,
+        ] {
+            registry.register_function(FunctionDefinition::new(
+                *function_name,
+                vec![*from],
+                *datatype,
+                FunctionType::Scalar(&StrictCast(ToSizedIntFromInt {})),
+            ));
+        }
+
+        for from in &[DataType::BigInt, DataType::UnsignedInteger] {
+            registry.register_function(FunctionDefinition::new(
+                *function_name,
+                vec![*from],
+                *datatype,
+                FunctionType::Scalar(&StrictCast(ToSizedIntFromBigInt {})),
+            ));
+        }
+
+        for from in &[DataType::Decimal(0, 0), DataType::UnsignedBigInt] {
+            registry.register_function(FunctionDefinition::new(
+                *function_name,
+                vec![*from],
+                *datatype,
+                FunctionType::Scalar(&StrictCast(ToSizedIntFromDecimal {})),
+            ));
+        }
+
+        registry.register_function(FunctionDefinition::new(
+            *function_name,
+            vec![DataType::Double],
+            *datatype,
+            FunctionType::Scalar(&StrictCast(ToSizedIntFromDouble {})),
+        ));
+
+        registry.register_function(FunctionDefinition::new(
+            *function_name,
+            vec![DataType::Text],
+            *datatype,
+            FunctionType::Scalar(&StrictCast(ToSizedIntFromText {})),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::rust_decimal::Decimal;
+
+    fn sig(ret: DataType) -> FunctionSignature<'static> {
+        FunctionSignature {
+            name: "to_tinyint",
+            args: vec![],
+            ret,
+        }
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            ToSizedIntFromBigInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::TinyInt),
+                &[Datum::Null]
+            ),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_from_int() {
+        assert_eq!(
+            ToSizedIntFromInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::TinyInt),
+                &[Datum::from(127)]
+            ),
+            Datum::from(127)
+        );
+        assert_eq!(
+            ToSizedIntFromInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedBigInt),
+                &[Datum::from(-1)]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_from_bigint() {
+        assert_eq!(
+            ToSizedIntFromBigInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::TinyInt),
+                &[Datum::from(-128_i64)]
+            ),
+            Datum::from(-128)
+        );
+        assert_eq!(
+            ToSizedIntFromBigInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::TinyInt),
+                &[Datum::from(128_i64)]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            ToSizedIntFromBigInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedInteger),
+                &[Datum::from(-1_i64)]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            ToSizedIntFromBigInt {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedInteger),
+                &[Datum::from(4294967295_i64)]
+            ),
+            Datum::from(4294967295_i64)
+        );
+    }
+
+    #[test]
+    fn test_from_decimal() {
+        assert_eq!(
+            ToSizedIntFromDecimal {}.execute(
+                &Session::new(1),
+                &sig(DataType::SmallInt),
+                &[Datum::from(Decimal::new(327679, 1))]
+            ),
+            Datum::from(32767)
+        );
+        assert_eq!(
+            ToSizedIntFromDecimal {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedBigInt),
+                &[Datum::from(Decimal::from(u64::MAX))]
+            ),
+            Datum::from(Decimal::from(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_from_double() {
+        assert_eq!(
+            ToSizedIntFromDouble {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedTinyInt),
+                &[Datum::from(255.5)]
+            ),
+            Datum::from(255)
+        );
+        assert_eq!(
+            ToSizedIntFromDouble {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedTinyInt),
+                &[Datum::from(256.0)]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            ToSizedIntFromText {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedBigInt),
+                &[Datum::from("18446744073709551615")]
+            ),
+            Datum::from(Decimal::from(u64::MAX))
+        );
+        assert_eq!(
+            ToSizedIntFromText {}.execute(
+                &Session::new(1),
+                &sig(DataType::UnsignedBigInt),
+                &[Datum::from("18446744073709551616")]
+            ),
+            Datum::Null
+        );
+    }
+}
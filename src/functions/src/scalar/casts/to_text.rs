@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::{DataType, Datum, Session};
@@ -56,32 +57,93 @@ impl Function for ToTextFromAny {
     }
 }
 
+#[derive(Debug)]
+struct ToTextFromBytes {}
+
+/// Bytes are reinterpreted as utf8 text, any invalid sequences get replaced
+impl Function for ToTextFromBytes {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(bytes) = args[0].as_maybe_bytea() {
+            if std::str::from_utf8(bytes).is_ok() {
+                args[0].ref_clone()
+            } else {
+                Datum::from(String::from_utf8_lossy(bytes).into_owned())
+            }
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToTextFromTimestamp {}
+
+/// Timestamps are stored in UTC but rendered in the session's time zone
+impl Function for ToTextFromTimestamp {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(timestamp) = args[0].as_maybe_timestamp() {
+            Datum::from(
+                Datum::from(session.to_session_time(timestamp))
+                    .typed_with(DataType::Timestamp)
+                    .to_string(),
+            )
+        } else {
+            Datum::Null
+        }
+    }
+}
+
 pub fn register_builtins(registry: &mut Registry) {
     registry.register_function(FunctionDefinition::new(
         "to_text",
         vec![DataType::Boolean],
         DataType::Text,
-        FunctionType::Scalar(&ToTextFromBoolean {}),
+        FunctionType::Scalar(&StrictCast(ToTextFromBoolean {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_text",
         vec![DataType::Text],
         DataType::Text,
-        FunctionType::Scalar(&ToTextFromText {}),
+        FunctionType::Scalar(&StrictCast(ToTextFromText {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_text",
+        vec![DataType::ByteA],
+        DataType::Text,
+        FunctionType::Scalar(&StrictCast(ToTextFromBytes {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_text",
+        vec![DataType::Timestamp],
+        DataType::Text,
+        FunctionType::Scalar(&StrictCast(ToTextFromTimestamp {})),
     ));
 
     registry.register_function(FunctionDefinition::new(
         "to_text",
         vec![DataType::Null],
         DataType::Text,
-        FunctionType::Scalar(&ToTextFromAny {}),
+        FunctionType::Scalar(&StrictCast(ToTextFromAny {})),
     ));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use data::chrono::NaiveDate;
     use data::rust_decimal::Decimal;
 
     fn sig(input_type: DataType) -> FunctionSignature<'static> {
@@ -153,6 +215,45 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_bytes() {
+        assert_eq!(
+            ToTextFromBytes {}.execute(
+                &Session::new(1),
+                &sig(DataType::ByteA),
+                &[Datum::from(b"abc".to_vec())]
+            ),
+            Datum::from("abc")
+        );
+        assert_eq!(
+            ToTextFromBytes {}.execute(
+                &Session::new(1),
+                &sig(DataType::ByteA),
+                &[Datum::from(vec![b'a', 0xff])]
+            ),
+            Datum::from("a\u{fffd}")
+        );
+    }
+
+    #[test]
+    fn test_from_timestamp() {
+        let session = Session::new(1);
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("time_zone".to_string(), Datum::from("-01:30"));
+        let timestamp = NaiveDate::from_ymd(2010, 10, 1).and_hms(10, 0, 0);
+        assert_eq!(
+            ToTextFromTimestamp {}.execute(
+                &session,
+                &sig(DataType::Timestamp),
+                &[Datum::from(timestamp)]
+            ),
+            Datum::from("2010-10-01 08:30:00")
+        )
+    }
+
     #[test]
     fn test_from_text() {
         // String Ref
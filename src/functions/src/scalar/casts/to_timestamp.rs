@@ -1,3 +1,4 @@
+use super::StrictCast;
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::chrono::NaiveDateTime;
@@ -7,17 +8,20 @@ use std::str::FromStr;
 #[derive(Debug)]
 struct ToTimestampFromText {}
 
+/// Text timestamps are in the session's time zone, they're converted to UTC for storage.
+/// Both the iso 8601 "T" separator and mysql's space separator are accepted.
 impl Function for ToTimestampFromText {
     fn execute<'a>(
         &self,
-        _session: &Session,
+        session: &Session,
         _signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let Some(s) = args[0].as_maybe_text() {
             NaiveDateTime::from_str(s)
+                .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f"))
                 .ok()
-                .map(Datum::from)
+                .map(|timestamp| Datum::from(session.to_utc(timestamp)))
                 .unwrap_or_default()
         } else {
             Datum::Null
@@ -30,7 +34,7 @@ pub fn register_builtins(registry: &mut Registry) {
         "to_timestamp",
         vec![DataType::Text],
         DataType::Timestamp,
-        FunctionType::Scalar(&ToTimestampFromText {}),
+        FunctionType::Scalar(&StrictCast(ToTimestampFromText {})),
     ));
 }
 
@@ -68,6 +72,42 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_from_text_space_separated() {
+        assert_eq!(
+            ToTimestampFromText {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from("2010-10-01 10:00:00.5")]
+            ),
+            Datum::from(NaiveDateTime::new(
+                NaiveDate::from_ymd(2010, 10, 1),
+                NaiveTime::from_hms_milli(10, 0, 0, 500)
+            ))
+        )
+    }
+
+    #[test]
+    fn test_from_text_session_time_zone() {
+        let session = Session::new(1);
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("time_zone".to_string(), Datum::from("+10:00"));
+        assert_eq!(
+            ToTimestampFromText {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[Datum::from("2010-10-01T10:00:00")]
+            ),
+            Datum::from(NaiveDateTime::new(
+                NaiveDate::from_ymd(2010, 10, 1),
+                NaiveTime::from_hms(0, 0, 0)
+            ))
+        )
+    }
+
     #[test]
     fn test_from_text_malformed() {
         assert_eq!(
@@ -0,0 +1,139 @@
+use super::StrictCast;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::vector::Vector;
+use data::{DataType, Datum, Session};
+
+/// Returns true if a vector with the given number of dimensions fits the vector type, a vector
+/// type with zero dimensions is used when the dimensions aren't known ie to_vector(text).
+fn dimensions_match(datatype: DataType, dimensions: usize) -> bool {
+    match datatype {
+        DataType::Vector(0) => dimensions > 0,
+        DataType::Vector(expected) => expected as usize == dimensions,
+        _ => panic!("Vector cast to {}", datatype),
+    }
+}
+
+#[derive(Debug)]
+struct ToVectorFromVector {}
+
+impl Function for ToVectorFromVector {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        match args[0].as_maybe_vector() {
+            Some(v) if dimensions_match(signature.ret, v.dimensions()) => args[0].ref_clone(),
+            _ => Datum::Null,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ToVectorFromText {}
+
+impl Function for ToVectorFromText {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        match args[0].as_maybe_text().and_then(Vector::parse) {
+            Some(elements) if dimensions_match(signature.ret, elements.len()) => {
+                Datum::from(elements.as_slice())
+            }
+            _ => Datum::Null,
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new_with_type_resolver(
+        "to_vector",
+        vec![DataType::Vector(0)],
+        |args| args[0],
+        FunctionType::Scalar(&StrictCast(ToVectorFromVector {})),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "to_vector",
+        vec![DataType::Text],
+        DataType::Vector(0),
+        FunctionType::Scalar(&StrictCast(ToVectorFromText {})),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(input_type: DataType, dimensions: u16) -> FunctionSignature<'static> {
+        FunctionSignature {
+            name: "to_vector",
+            args: vec![input_type],
+            ret: DataType::Vector(dimensions),
+        }
+    }
+
+    #[test]
+    fn test_from_text() {
+        let expected = Datum::from([1.0_f32, 2.0].as_ref());
+        assert_eq!(
+            ToVectorFromText {}.execute(
+                &Session::new(1),
+                &sig(DataType::Text, 2),
+                &[Datum::from("[1, 2]")]
+            ),
+            expected
+        );
+        assert_eq!(
+            ToVectorFromText {}.execute(
+                &Session::new(1),
+                &sig(DataType::Text, 0),
+                &[Datum::from("[1, 2]")]
+            ),
+            expected
+        );
+        // Wrong number of dimensions
+        assert_eq!(
+            ToVectorFromText {}.execute(
+                &Session::new(1),
+                &sig(DataType::Text, 3),
+                &[Datum::from("[1, 2]")]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            ToVectorFromText {}.execute(
+                &Session::new(1),
+                &sig(DataType::Text, 2),
+                &[Datum::from("abc")]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_from_vector() {
+        let vector = Datum::from([1.0_f32, 2.0].as_ref());
+        assert_eq!(
+            ToVectorFromVector {}.execute(
+                &Session::new(1),
+                &sig(DataType::Vector(2), 2),
+                &[vector.clone()]
+            ),
+            vector
+        );
+        assert_eq!(
+            ToVectorFromVector {}.execute(
+                &Session::new(1),
+                &sig(DataType::Vector(2), 3),
+                &[vector]
+            ),
+            Datum::Null
+        );
+    }
+}
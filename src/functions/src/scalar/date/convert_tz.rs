@@ -0,0 +1,105 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{parse_time_zone, DataType, Datum, Session};
+
+#[derive(Debug)]
+struct ConvertTz {}
+
+/// convert_tz(timestamp, from_tz, to_tz), shifts the timestamp from one time zone to another.
+/// Like mysql returns null for any time zones it doesn't know about.
+impl Function for ConvertTz {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(timestamp), Some(from), Some(to)) = (
+            args[0].as_maybe_timestamp(),
+            args[1].as_maybe_text().and_then(parse_time_zone),
+            args[2].as_maybe_text().and_then(parse_time_zone),
+        ) {
+            Datum::from(timestamp - from + to)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "convert_tz",
+        vec![DataType::Timestamp, DataType::Text, DataType::Text],
+        DataType::Timestamp,
+        FunctionType::Scalar(&ConvertTz {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::chrono::NaiveDate;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "convert_tz",
+        args: vec![],
+        ret: DataType::Timestamp,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            ConvertTz {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::Null, Datum::from("+00:00"), Datum::from("+10:00")]
+            ),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_convert_tz() {
+        let timestamp = NaiveDate::from_ymd(2004, 1, 1).and_hms(12, 0, 0);
+        assert_eq!(
+            ConvertTz {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[
+                    Datum::from(timestamp),
+                    Datum::from("+00:00"),
+                    Datum::from("+10:00")
+                ]
+            ),
+            Datum::from(NaiveDate::from_ymd(2004, 1, 1).and_hms(22, 0, 0))
+        );
+        assert_eq!(
+            ConvertTz {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[
+                    Datum::from(timestamp),
+                    Datum::from("+10:00"),
+                    Datum::from("-05:30")
+                ]
+            ),
+            Datum::from(NaiveDate::from_ymd(2003, 12, 31).and_hms(20, 30, 0))
+        );
+    }
+
+    #[test]
+    fn test_unknown_time_zone() {
+        assert_eq!(
+            ConvertTz {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[
+                    Datum::from(NaiveDate::from_ymd(2004, 1, 1).and_hms(12, 0, 0)),
+                    Datum::from("GMT"),
+                    Datum::from("MET")
+                ]
+            ),
+            Datum::Null
+        )
+    }
+}
@@ -0,0 +1,53 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::chrono::Utc;
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct CurrentTimestamp {}
+
+/// current_timestamp(), returns the current utc time
+impl Function for CurrentTimestamp {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        _args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        Datum::from(Utc::now().naive_utc())
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for function_name in &["current_timestamp", "now"] {
+        registry.register_function(FunctionDefinition::new(
+            *function_name,
+            vec![],
+            DataType::Timestamp,
+            FunctionType::Scalar(&CurrentTimestamp {}),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "current_timestamp",
+        args: vec![],
+        ret: DataType::Timestamp,
+    };
+
+    #[test]
+    fn test_current_timestamp() {
+        let before = Utc::now().naive_utc().timestamp_millis();
+        let now = CurrentTimestamp {}
+            .execute(&Session::new(1), &DUMMY_SIG, &[])
+            .as_timestamp()
+            .timestamp_millis();
+        let after = Utc::now().naive_utc().timestamp_millis();
+
+        assert!(before <= now && now <= after);
+    }
+}
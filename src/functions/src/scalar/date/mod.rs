@@ -1,6 +1,12 @@
 use crate::registry::Registry;
+mod convert_tz;
+mod current_timestamp;
 mod date_sub;
+mod windows;
 
 pub fn register_builtins(registry: &mut Registry) {
+    convert_tz::register_builtins(registry);
+    current_timestamp::register_builtins(registry);
     date_sub::register_builtins(registry);
+    windows::register_builtins(registry);
 }
@@ -0,0 +1,154 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::chrono::NaiveDateTime;
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct Tumble {}
+
+/// tumble(timestamp, seconds), returns the start of the fixed size window the timestamp falls
+/// in, windows are aligned to the unix epoch.
+impl Function for Tumble {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(timestamp), Some(width)) =
+            (args[0].as_maybe_timestamp(), args[1].as_maybe_integer())
+        {
+            window_start(timestamp, width as i64, 0)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Hop {}
+
+/// hop(timestamp, slide seconds, size seconds [, n]), returns the start of the nth most
+/// recent window (of size seconds, starting every slide seconds) that the timestamp falls in.
+/// When grouped by, the planner expands each row out into each of the windows it falls in.
+impl Function for Hop {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let n = args.get(3).map_or(Some(0), Datum::as_maybe_integer);
+        if let (Some(timestamp), Some(slide), Some(_size), Some(n)) = (
+            args[0].as_maybe_timestamp(),
+            args[1].as_maybe_integer(),
+            args[2].as_maybe_integer(),
+            n,
+        ) {
+            window_start(timestamp, slide as i64, n as i64)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+/// The start of the window n windows back from the one containing the timestamp.
+fn window_start(timestamp: NaiveDateTime, width: i64, n: i64) -> Datum<'static> {
+    if width <= 0 {
+        return Datum::Null;
+    }
+    let seconds = timestamp.timestamp();
+    let start = (seconds.div_euclid(width) - n) * width;
+    Datum::from(NaiveDateTime::from_timestamp(start, 0))
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "tumble",
+        vec![DataType::Timestamp, DataType::Integer],
+        DataType::Timestamp,
+        FunctionType::Scalar(&Tumble {}),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "hop",
+        vec![DataType::Timestamp, DataType::Integer, DataType::Integer],
+        DataType::Timestamp,
+        FunctionType::Scalar(&Hop {}),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "hop",
+        vec![
+            DataType::Timestamp,
+            DataType::Integer,
+            DataType::Integer,
+            DataType::Integer,
+        ],
+        DataType::Timestamp,
+        FunctionType::Scalar(&Hop {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::chrono::NaiveDate;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "tumble",
+        args: vec![],
+        ret: DataType::Timestamp,
+    };
+
+    fn timestamp(h: u32, m: u32, s: u32) -> Datum<'static> {
+        Datum::from(NaiveDate::from_ymd(2020, 11, 1).and_hms(h, m, s))
+    }
+
+    #[test]
+    fn test_tumble() {
+        assert_eq!(
+            Tumble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[timestamp(12, 34, 56), Datum::from(600)]
+            ),
+            timestamp(12, 30, 0)
+        );
+        assert_eq!(
+            Tumble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[timestamp(12, 34, 56), Datum::Null]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            Tumble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[timestamp(12, 34, 56), Datum::from(0)]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_hop() {
+        let ts = timestamp(12, 34, 56);
+        assert_eq!(
+            Hop {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[ts.clone(), Datum::from(300), Datum::from(900)]
+            ),
+            timestamp(12, 30, 0)
+        );
+        assert_eq!(
+            Hop {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[ts, Datum::from(300), Datum::from(900), Datum::from(2)]
+            ),
+            timestamp(12, 20, 0)
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use super::json_at_path;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::{Json, JsonType};
+use data::{DataType, Datum, Session};
+
+/// json_contains(target, candidate[, path]), whether the candidate is contained within the
+/// target (or the part of it the path points to), see
+/// https://dev.mysql.com/doc/refman/8.0/en/json-search-functions.html#function_json-contains
+#[derive(Debug)]
+struct JsonContains {}
+
+impl Function for JsonContains {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(target), Some(candidate)) =
+            (json_at_path(&args[0], args.get(2)), args[1].as_maybe_json())
+        {
+            Datum::from(contains(target, candidate))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+/// Scalars are contained if they're equal, arrays contain a candidate array if every element
+/// of the candidate is contained in the target array and contain a candidate scalar if it's
+/// contained in any element. Objects contain a candidate object if each of its keys is in the
+/// target with the value being contained.
+fn contains(target: Json, candidate: Json) -> bool {
+    match (target.json_type(), candidate.json_type()) {
+        (JsonType::Array, JsonType::Array) => candidate
+            .iter_array()
+            .unwrap()
+            .all(|c| target.iter_array().unwrap().any(|t| contains(t, c))),
+        (JsonType::Array, _) => target.iter_array().unwrap().any(|t| contains(t, candidate)),
+        (JsonType::Object, JsonType::Object) => {
+            candidate.iter_object().unwrap().all(|(candidate_key, c)| {
+                target
+                    .iter_object()
+                    .unwrap()
+                    .any(|(target_key, t)| target_key == candidate_key && contains(t, c))
+            })
+        }
+        // Numbers with different scales can still be equal
+        (JsonType::Number, JsonType::Number) => target.get_number() == candidate.get_number(),
+        (target_type, candidate_type) => target_type == candidate_type && target == candidate,
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "json_contains",
+        vec![DataType::Json, DataType::Json],
+        DataType::Boolean,
+        FunctionType::Scalar(&JsonContains {}),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "json_contains",
+        vec![DataType::Json, DataType::Json, DataType::JsonPath],
+        DataType::Boolean,
+        FunctionType::Scalar(&JsonContains {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+    use data::jsonpath_utils::JsonPathExpression;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "json_contains",
+        args: vec![],
+        ret: DataType::Boolean,
+    };
+
+    fn json(s: &str) -> Datum<'static> {
+        Datum::from(OwnedJson::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            JsonContains {}.execute(&Session::new(1), &DUMMY_SIG, &[json("1"), Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_json_contains() {
+        let target = r#"{"a": 1, "b": [1, 2, {"c": "x"}], "d": null}"#;
+        for (candidate, expected) in &[
+            (r#"{"a": 1.0}"#, true),
+            (r#"{"a": 2}"#, false),
+            (r#"{"b": [2, 1]}"#, true),
+            (r#"{"b": 2}"#, true),
+            (r#"{"b": [{"c": "x"}], "d": null}"#, true),
+            (r#"{"b": [3]}"#, false),
+            (r#"{"e": 1}"#, false),
+            ("1", false),
+        ] {
+            assert_eq!(
+                JsonContains {}.execute(
+                    &Session::new(1),
+                    &DUMMY_SIG,
+                    &[json(target), json(candidate)]
+                ),
+                Datum::from(*expected),
+                "{}",
+                candidate
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_contains_path() {
+        let path = |s| Datum::Jsonpath(Box::new(JsonPathExpression::parse(s).unwrap()));
+        assert_eq!(
+            JsonContains {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[json(r#"{"a": [1, 2]}"#), json("2"), path("$.a")]
+            ),
+            Datum::from(true)
+        );
+        assert_eq!(
+            JsonContains {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[json(r#"{"a": [1, 2]}"#), json("2"), path("$.b")]
+            ),
+            Datum::Null
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use super::json_at_path;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonBuilder;
+use data::{DataType, Datum, Session};
+
+/// json_keys(json[, path]), the keys of an object as a json array, null for anything that isn't
+/// an object, see
+/// https://dev.mysql.com/doc/refman/8.0/en/json-search-functions.html#function_json-keys
+#[derive(Debug)]
+struct JsonKeys {}
+
+impl Function for JsonKeys {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(kv_iter) =
+            json_at_path(&args[0], args.get(1)).and_then(|json| json.iter_object())
+        {
+            Datum::from(JsonBuilder::default().array(|array| {
+                for (key, _) in kv_iter {
+                    array.push_string(key);
+                }
+            }))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "json_keys",
+        vec![DataType::Json],
+        DataType::Json,
+        FunctionType::Scalar(&JsonKeys {}),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "json_keys",
+        vec![DataType::Json, DataType::JsonPath],
+        DataType::Json,
+        FunctionType::Scalar(&JsonKeys {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+    use data::jsonpath_utils::JsonPathExpression;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "json_keys",
+        args: vec![],
+        ret: DataType::Json,
+    };
+
+    fn json(s: &str) -> Datum<'static> {
+        Datum::from(OwnedJson::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            JsonKeys {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_json_keys() {
+        assert_eq!(
+            JsonKeys {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[json(r#"{"a": 1, "b": {"c": 2}}"#)]
+            ),
+            json(r#"["a", "b"]"#)
+        );
+        assert_eq!(
+            JsonKeys {}.execute(&Session::new(1), &DUMMY_SIG, &[json("[1, 2]")]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_json_keys_path() {
+        assert_eq!(
+            JsonKeys {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[
+                    json(r#"{"a": 1, "b": {"c": 2}}"#),
+                    Datum::Jsonpath(Box::new(JsonPathExpression::parse("$.b").unwrap()))
+                ]
+            ),
+            json(r#"["c"]"#)
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use super::json_at_path;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonType;
+use data::{DataType, Datum, Session};
+
+/// json_length(json[, path]), the number of elements in an array, keys in an object or 1 for
+/// scalars, see
+/// https://dev.mysql.com/doc/refman/8.0/en/json-attribute-functions.html#function_json-length
+#[derive(Debug)]
+struct JsonLength {}
+
+impl Function for JsonLength {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(json) = json_at_path(&args[0], args.get(1)) {
+            let len = match json.json_type() {
+                JsonType::Array => json.iter_array().unwrap().count(),
+                JsonType::Object => json.iter_object().unwrap().count(),
+                _ => 1,
+            };
+            Datum::from(len as i64)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "json_length",
+        vec![DataType::Json],
+        DataType::BigInt,
+        FunctionType::Scalar(&JsonLength {}),
+    ));
+    registry.register_function(FunctionDefinition::new(
+        "json_length",
+        vec![DataType::Json, DataType::JsonPath],
+        DataType::BigInt,
+        FunctionType::Scalar(&JsonLength {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+    use data::jsonpath_utils::JsonPathExpression;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "json_length",
+        args: vec![],
+        ret: DataType::BigInt,
+    };
+
+    fn json(s: &str) -> Datum<'static> {
+        Datum::from(OwnedJson::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            JsonLength {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_json_length() {
+        for (input, expected) in &[("[1, 2, [3]]", 3), (r#"{"a": 1, "b": 2}"#, 2), ("1", 1)] {
+            assert_eq!(
+                JsonLength {}.execute(&Session::new(1), &DUMMY_SIG, &[json(input)]),
+                Datum::from(*expected as i64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_length_path() {
+        let path = |s| Datum::Jsonpath(Box::new(JsonPathExpression::parse(s).unwrap()));
+        assert_eq!(
+            JsonLength {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[json(r#"{"a": [1, 2]}"#), path("$.a")]
+            ),
+            Datum::from(2_i64)
+        );
+        assert_eq!(
+            JsonLength {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[json(r#"{"a": [1, 2]}"#), path("$.b")]
+            ),
+            Datum::Null
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonBuilder;
+use data::jsonpath_utils::JsonModification;
+use data::{DataType, Datum, Session};
+
+/// The number of path/value pairs (or paths for json_remove) we register signatures for.
+const MAX_PATHS: usize = 4;
+
+#[derive(Debug)]
+enum ModifyType {
+    Set,
+    Insert,
+    Replace,
+}
+
+/// json_set(json, path, value[, path, value]...), json_insert and json_replace, see
+/// https://dev.mysql.com/doc/refman/8.0/en/json-modification-functions.html
+/// The values are converted to json the same way json_arrayagg does, the pairs are applied left
+/// to right with each one seeing the result of the last.
+#[derive(Debug)]
+struct JsonModify {
+    modify_type: ModifyType,
+}
+
+impl Function for JsonModify {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let mut json = if let Some(json) = args[0].as_maybe_json() {
+            JsonBuilder::default().json(json)
+        } else {
+            return Datum::Null;
+        };
+
+        for (idx, pair) in args[1..].chunks(2).enumerate() {
+            let path = if let Some(path) = pair[0].as_maybe_jsonpath() {
+                path
+            } else {
+                return Datum::Null;
+            };
+            let value = JsonBuilder::default().datum(&pair[1], signature.args[idx * 2 + 2]);
+            let modification = match self.modify_type {
+                ModifyType::Set => JsonModification::Set(value.as_json()),
+                ModifyType::Insert => JsonModification::Insert(value.as_json()),
+                ModifyType::Replace => JsonModification::Replace(value.as_json()),
+            };
+            json = if let Some(json) = path.modify(json.as_json(), modification) {
+                json
+            } else {
+                return Datum::Null;
+            };
+        }
+        Datum::from(json)
+    }
+}
+
+/// json_remove(json, path[, path]...), removes the values at each path, paths are applied left
+/// to right so later paths see the result of removing the earlier ones.
+#[derive(Debug)]
+struct JsonRemove {}
+
+impl Function for JsonRemove {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let mut json = if let Some(json) = args[0].as_maybe_json() {
+            JsonBuilder::default().json(json)
+        } else {
+            return Datum::Null;
+        };
+
+        for path in &args[1..] {
+            json = if let Some(json) = path
+                .as_maybe_jsonpath()
+                .and_then(|path| path.modify(json.as_json(), JsonModification::Remove))
+            {
+                json
+            } else {
+                return Datum::Null;
+            };
+        }
+        Datum::from(json)
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    let functions: [(&str, &'static JsonModify); 3] = [
+        (
+            "json_set",
+            &JsonModify {
+                modify_type: ModifyType::Set,
+            },
+        ),
+        (
+            "json_insert",
+            &JsonModify {
+                modify_type: ModifyType::Insert,
+            },
+        ),
+        (
+            "json_replace",
+            &JsonModify {
+                modify_type: ModifyType::Replace,
+            },
+        ),
+    ];
+    for (function_name, function) in functions.iter() {
+        for pair_count in 1..=MAX_PATHS {
+            let mut args = vec![DataType::Json];
+            for _ in 0..pair_count {
+                args.push(DataType::JsonPath);
+                args.push(DataType::Null);
+            }
+            registry.register_function(FunctionDefinition::new(
+                *function_name,
+                args,
+                DataType::Json,
+                FunctionType::Scalar(*function),
+            ));
+        }
+    }
+
+    for path_count in 1..=MAX_PATHS {
+        let mut args = vec![DataType::Json];
+        args.extend((0..path_count).map(|_| DataType::JsonPath));
+        registry.register_function(FunctionDefinition::new(
+            "json_remove",
+            args,
+            DataType::Json,
+            FunctionType::Scalar(&JsonRemove {}),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+    use data::jsonpath_utils::JsonPathExpression;
+
+    fn sig(args: Vec<DataType>) -> FunctionSignature<'static> {
+        FunctionSignature {
+            name: "json_set",
+            args,
+            ret: DataType::Json,
+        }
+    }
+
+    fn json(s: &str) -> Datum<'static> {
+        Datum::from(OwnedJson::parse(s).unwrap())
+    }
+
+    fn path(s: &str) -> Datum<'static> {
+        Datum::Jsonpath(Box::new(JsonPathExpression::parse(s).unwrap()))
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            JsonModify {
+                modify_type: ModifyType::Set
+            }
+            .execute(
+                &Session::new(1),
+                &sig(vec![DataType::Json, DataType::JsonPath, DataType::Integer]),
+                &[Datum::Null, path("$.a"), Datum::from(1)]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            JsonRemove {}.execute(
+                &Session::new(1),
+                &sig(vec![DataType::Json, DataType::JsonPath]),
+                &[json(r#"{"a": 1}"#), Datum::Null]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_json_set_insert_replace() {
+        let signature = sig(vec![
+            DataType::Json,
+            DataType::JsonPath,
+            DataType::Integer,
+            DataType::JsonPath,
+            DataType::Text,
+        ]);
+        let args = [
+            json(r#"{"a": 1, "b": [1, 2]}"#),
+            path("$.a"),
+            Datum::from(10),
+            path("$.c"),
+            Datum::from("foo"),
+        ];
+
+        assert_eq!(
+            JsonModify {
+                modify_type: ModifyType::Set
+            }
+            .execute(&Session::new(1), &signature, &args),
+            json(r#"{"a": 10, "b": [1, 2], "c": "foo"}"#)
+        );
+        assert_eq!(
+            JsonModify {
+                modify_type: ModifyType::Insert
+            }
+            .execute(&Session::new(1), &signature, &args),
+            json(r#"{"a": 1, "b": [1, 2], "c": "foo"}"#)
+        );
+        assert_eq!(
+            JsonModify {
+                modify_type: ModifyType::Replace
+            }
+            .execute(&Session::new(1), &signature, &args),
+            json(r#"{"a": 10, "b": [1, 2]}"#)
+        );
+    }
+
+    #[test]
+    fn test_json_set_json_value() {
+        assert_eq!(
+            JsonModify {
+                modify_type: ModifyType::Set
+            }
+            .execute(
+                &Session::new(1),
+                &sig(vec![DataType::Json, DataType::JsonPath, DataType::Json]),
+                &[
+                    json(r#"{"b": [1, 2]}"#),
+                    path("$.b[5]"),
+                    json(r#"{"x": true}"#)
+                ]
+            ),
+            json(r#"{"b": [1, 2, {"x": true}]}"#)
+        );
+    }
+
+    #[test]
+    fn test_json_remove() {
+        assert_eq!(
+            JsonRemove {}.execute(
+                &Session::new(1),
+                &sig(vec![DataType::Json, DataType::JsonPath, DataType::JsonPath]),
+                &[
+                    json(r#"{"a": 1, "b": [1, 2, 3]}"#),
+                    path("$.a"),
+                    path("$.b[0]")
+                ]
+            ),
+            json(r#"{"b": [2, 3]}"#)
+        );
+        // Can't remove the whole document
+        assert_eq!(
+            JsonRemove {}.execute(
+                &Session::new(1),
+                &sig(vec![DataType::Json, DataType::JsonPath]),
+                &[json(r#"{"a": 1}"#), path("$")]
+            ),
+            Datum::Null
+        );
+    }
+}
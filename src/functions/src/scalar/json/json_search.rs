@@ -0,0 +1,315 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::{Json, JsonBuilder, JsonType};
+use data::{DataType, Datum, Session};
+
+/// The number of paths to search under we register signatures for.
+const MAX_PATHS: usize = 4;
+
+/// json_search(json, one_or_all, search_str[, escape_char[, path]...]), returns the paths to
+/// the strings in the document matching the like pattern, see
+/// https://dev.mysql.com/doc/refman/8.0/en/json-search-functions.html#function_json-search
+/// With 'one' the first path found is returned, with 'all' a single match is returned as is and
+/// multiple matches are returned as an array of paths.
+/// The paths to search under can't contain wildcards.
+#[derive(Debug)]
+struct JsonSearch {}
+
+impl Function for JsonSearch {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let (json, one_or_all, search) = if let (Some(json), Some(one_or_all), Some(search)) = (
+            args[0].as_maybe_json(),
+            args[1].as_maybe_text(),
+            args[2].as_maybe_text(),
+        ) {
+            (json, one_or_all, search)
+        } else {
+            return Datum::Null;
+        };
+        let only_one = if one_or_all.eq_ignore_ascii_case("one") {
+            true
+        } else if one_or_all.eq_ignore_ascii_case("all") {
+            false
+        } else {
+            return Datum::Null;
+        };
+        let escape = match args.get(3).and_then(Datum::as_maybe_text) {
+            None | Some("") => '\\',
+            Some(escape) if escape.chars().count() == 1 => escape.chars().next().unwrap(),
+            Some(_) => return Datum::Null,
+        };
+        let pattern = parse_pattern(search, escape);
+
+        let mut matches = vec![];
+        if args.len() > 4 {
+            for path in &args[4..] {
+                let path = if let Some(path) = path.as_maybe_jsonpath() {
+                    path
+                } else {
+                    return Datum::Null;
+                };
+                if path.could_return_many() {
+                    return Datum::Null;
+                }
+                if let Some(json) = path.evaluate_single(json) {
+                    search_json(
+                        json,
+                        &mut path.original().to_string(),
+                        &pattern,
+                        &mut matches,
+                    );
+                }
+            }
+        } else {
+            search_json(json, &mut "$".to_string(), &pattern, &mut matches);
+        }
+
+        // Searching under overlapping paths can find the same string twice
+        let mut found: Vec<String> = vec![];
+        for path in matches {
+            if !found.contains(&path) {
+                found.push(path);
+            }
+        }
+        match found.len() {
+            0 => Datum::Null,
+            1 => Datum::from(JsonBuilder::default().string(&found[0])),
+            _ if only_one => Datum::from(JsonBuilder::default().string(&found[0])),
+            _ => Datum::from(JsonBuilder::default().array(|array| {
+                for path in &found {
+                    array.push_string(path);
+                }
+            })),
+        }
+    }
+}
+
+/// Walks the json collecting the paths of the strings that match the pattern.
+fn search_json(json: Json, path: &mut String, pattern: &[PatternToken], matches: &mut Vec<String>) {
+    let path_len = path.len();
+    match json.json_type() {
+        JsonType::String => {
+            if like(json.get_string().unwrap(), pattern) {
+                matches.push(path.clone());
+            }
+        }
+        JsonType::Array => {
+            for (idx, value) in json.iter_array().unwrap().enumerate() {
+                path.push_str(&format!("[{}]", idx));
+                search_json(value, path, pattern, matches);
+                path.truncate(path_len);
+            }
+        }
+        JsonType::Object => {
+            for (key, value) in json.iter_object().unwrap() {
+                push_key(path, key);
+                search_json(value, path, pattern, matches);
+                path.truncate(path_len);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Keys that our jsonpath parser would trip over get written out in bracket notation.
+fn push_key(path: &mut String, key: &str) {
+    let plain = key
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        path.push('.');
+        path.push_str(key);
+    } else {
+        path.push_str("[\"");
+        for c in key.chars() {
+            if c == '"' || c == '\\' {
+                path.push('\\');
+            }
+            path.push(c);
+        }
+        path.push_str("\"]");
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum PatternToken {
+    /// %
+    AnyString,
+    /// _
+    AnyChar,
+    Char(char),
+}
+
+fn parse_pattern(pattern: &str, escape: char) -> Vec<PatternToken> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            _ if c == escape => PatternToken::Char(chars.next().unwrap_or(escape)),
+            '%' => PatternToken::AnyString,
+            '_' => PatternToken::AnyChar,
+            _ => PatternToken::Char(c),
+        });
+    }
+    tokens
+}
+
+/// Sql like matching, when we hit a mismatch we backtrack to the last % and let it swallow
+/// one more char.
+fn like(input: &str, pattern: &[PatternToken]) -> bool {
+    let input: Vec<char> = input.chars().collect();
+    let (mut i, mut p) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while i < input.len() {
+        match pattern.get(p) {
+            Some(PatternToken::AnyString) => {
+                backtrack = Some((p, i));
+                p += 1;
+            }
+            Some(PatternToken::AnyChar) => {
+                i += 1;
+                p += 1;
+            }
+            Some(PatternToken::Char(c)) if *c == input[i] => {
+                i += 1;
+                p += 1;
+            }
+            _ => {
+                if let Some((star_p, star_i)) = backtrack {
+                    backtrack = Some((star_p, star_i + 1));
+                    p = star_p + 1;
+                    i = star_i + 1;
+                } else {
+                    return false;
+                }
+            }
+        }
+    }
+    pattern[p..]
+        .iter()
+        .all(|token| *token == PatternToken::AnyString)
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for arg_count in 3..(5 + MAX_PATHS) {
+        let mut args = vec![DataType::Json, DataType::Text, DataType::Text];
+        if arg_count > 3 {
+            args.push(DataType::Text);
+        }
+        args.extend((5..=arg_count).map(|_| DataType::JsonPath));
+        registry.register_function(FunctionDefinition::new(
+            "json_search",
+            args,
+            DataType::Json,
+            FunctionType::Scalar(&JsonSearch {}),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+    use data::jsonpath_utils::JsonPathExpression;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "json_search",
+        args: vec![],
+        ret: DataType::Json,
+    };
+
+    fn json(s: &str) -> Datum<'static> {
+        Datum::from(OwnedJson::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_like() {
+        let matches = |input, pattern| like(input, &parse_pattern(pattern, '\\'));
+        assert!(matches("abc", "abc"));
+        assert!(matches("abc", "a%"));
+        assert!(matches("abc", "%c"));
+        assert!(matches("abc", "a_c"));
+        assert!(matches("abcbc", "%bc"));
+        assert!(matches("", "%"));
+        assert!(matches("a%c", "a\\%c"));
+        assert!(!matches("abc", "a\\%c"));
+        assert!(!matches("abc", "ab"));
+        assert!(!matches("abc", "_"));
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            JsonSearch {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::Null, Datum::from("one"), Datum::from("a")]
+            ),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_json_search() {
+        let input = json(r#"["abc", [{"k": "10"}, "def"], {"x": "abc"}, {"y z": "bcd"}]"#);
+        assert_eq!(
+            JsonSearch {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[input.ref_clone(), Datum::from("one"), Datum::from("abc")]
+            ),
+            json(r#""$[0]""#)
+        );
+        assert_eq!(
+            JsonSearch {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[input.ref_clone(), Datum::from("all"), Datum::from("%bc%")]
+            ),
+            json(r#"["$[0]", "$[2].x", "$[3][\"y z\"]"]"#)
+        );
+        assert_eq!(
+            JsonSearch {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[input.ref_clone(), Datum::from("all"), Datum::from("xyz")]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            JsonSearch {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[input, Datum::from("some"), Datum::from("abc")]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_json_search_paths() {
+        let input = json(r#"["abc", [{"k": "10"}, "def"], {"x": "abc"}]"#);
+        assert_eq!(
+            JsonSearch {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[
+                    input,
+                    Datum::from("all"),
+                    Datum::from("abc"),
+                    Datum::Null,
+                    Datum::Jsonpath(Box::new(JsonPathExpression::parse("$[2]").unwrap()))
+                ]
+            ),
+            json(r#""$[2].x""#)
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonType;
+use data::{DataType, Datum, Session};
+
+/// json_type(json), the type of the top level json value as an upper case string, see
+/// https://dev.mysql.com/doc/refman/8.0/en/json-attribute-functions.html#function_json-type
+/// Json numbers are stored as decimals so numbers come out as either INTEGER or DECIMAL.
+#[derive(Debug)]
+struct JsonTypeOf {}
+
+impl Function for JsonTypeOf {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(json) = args[0].as_maybe_json() {
+            Datum::from(match json.json_type() {
+                JsonType::Null => "NULL",
+                JsonType::Boolean => "BOOLEAN",
+                JsonType::Number => {
+                    if json.get_number().unwrap().scale() == 0 {
+                        "INTEGER"
+                    } else {
+                        "DECIMAL"
+                    }
+                }
+                JsonType::String => "STRING",
+                JsonType::Array => "ARRAY",
+                JsonType::Object => "OBJECT",
+            })
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "json_type",
+        vec![DataType::Json],
+        DataType::Text,
+        FunctionType::Scalar(&JsonTypeOf {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "json_type",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            JsonTypeOf {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        )
+    }
+
+    #[test]
+    fn test_json_type() {
+        for (input, expected) in &[
+            ("null", "NULL"),
+            ("true", "BOOLEAN"),
+            ("12", "INTEGER"),
+            ("1.5", "DECIMAL"),
+            (r#""a""#, "STRING"),
+            ("[]", "ARRAY"),
+            (r#"{"a": 1}"#, "OBJECT"),
+        ] {
+            assert_eq!(
+                JsonTypeOf {}.execute(
+                    &Session::new(1),
+                    &DUMMY_SIG,
+                    &[Datum::from(OwnedJson::parse(input).unwrap())]
+                ),
+                Datum::from(*expected)
+            );
+        }
+    }
+}
@@ -1,11 +1,42 @@
 use crate::registry::Registry;
+use data::json::Json;
+use data::Datum;
 
+mod json_contains;
 mod json_extract;
 mod json_extract_unquote;
+mod json_keys;
+mod json_length;
+mod json_modify;
+mod json_search;
+mod json_type;
 mod json_unquote;
 
 pub fn register_builtins(registry: &mut Registry) {
+    json_contains::register_builtins(registry);
     json_extract::register_builtins(registry);
     json_extract_unquote::register_builtins(registry);
+    json_keys::register_builtins(registry);
+    json_length::register_builtins(registry);
+    json_modify::register_builtins(registry);
+    json_search::register_builtins(registry);
+    json_type::register_builtins(registry);
     json_unquote::register_builtins(registry);
 }
+
+/// For the functions that take a json document and an optional path, returns the part of the
+/// document the path points to. Like mysql the path has to point to a single value, for paths
+/// with wildcards or that don't match anything None is returned.
+fn json_at_path<'a>(json: &'a Datum<'a>, path: Option<&'a Datum<'a>>) -> Option<Json<'a>> {
+    let json = json.as_maybe_json()?;
+    if let Some(path) = path {
+        let path = path.as_maybe_jsonpath()?;
+        if path.could_return_many() {
+            None
+        } else {
+            path.evaluate_single(json)
+        }
+    } else {
+        Some(json)
+    }
+}
@@ -1,3 +1,4 @@
+use super::{finite_double, out_of_range};
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::{DataType, Datum, Session, DECIMAL_MAX_PRECISION};
@@ -9,16 +10,22 @@ struct AddInteger {}
 impl Function for AddInteger {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_integer(), args[1].as_maybe_integer()) {
-            Datum::from(a + b)
+            a.checked_add(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "+", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 #[derive(Debug)]
@@ -27,16 +34,22 @@ struct AddBigint {}
 impl Function for AddBigint {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_bigint(), args[1].as_maybe_bigint()) {
-            Datum::from(a + b)
+            a.checked_add(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "+", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 #[derive(Debug)]
@@ -45,16 +58,44 @@ struct AddDecimal {}
 impl Function for AddDecimal {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_decimal(), args[1].as_maybe_decimal()) {
-            Datum::from(a + b)
+            a.checked_add(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "+", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
+}
+
+#[derive(Debug)]
+struct AddDouble {}
+
+impl Function for AddDouble {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(a), Some(b)) = (args[0].as_maybe_double(), args[1].as_maybe_double()) {
+            finite_double(session, signature, a + b, a, "+", b)
+        } else {
+            Datum::Null
+        }
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -90,6 +131,13 @@ pub fn register_builtins(registry: &mut Registry) {
         },
         FunctionType::Scalar(&AddDecimal {}),
     ));
+
+    registry.register_function(FunctionDefinition::new(
+        "+",
+        vec![DataType::Double, DataType::Double],
+        DataType::Double,
+        FunctionType::Scalar(&AddDouble {}),
+    ));
 }
 
 #[cfg(test)]
@@ -123,6 +171,23 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_add_int_overflow() {
+        let session = Session::new(1);
+        assert_eq!(
+            AddInteger {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[Datum::from(i32::MAX), Datum::from(1)]
+            ),
+            Datum::Null
+        );
+        assert_eq!(
+            session.take_out_of_range(),
+            Some("INTEGER value is out of range in '(2147483647 + 1)'".to_string())
+        );
+    }
+
     #[test]
     fn test_add_bigint() {
         assert_eq!(
@@ -149,4 +214,16 @@ mod tests {
             Datum::from(Decimal::new(2464, 2))
         )
     }
+
+    #[test]
+    fn test_add_double() {
+        assert_eq!(
+            AddDouble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1.5), Datum::from(2.25)]
+            ),
+            Datum::from(3.75)
+        )
+    }
 }
@@ -1,5 +1,7 @@
+use super::{finite_double, out_of_range};
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::rust_decimal::prelude::Zero;
 use data::{DataType, Datum, Session, DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE};
 
 #[derive(Debug)]
@@ -8,12 +10,18 @@ struct DivideInteger {}
 impl Function for DivideInteger {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_integer(), args[1].as_maybe_integer()) {
-            Datum::from(a / b)
+            if b == 0 {
+                Datum::Null
+            } else {
+                a.checked_div(b)
+                    .map(Datum::from)
+                    .unwrap_or_else(|| out_of_range(session, signature, a, "/", b))
+            }
         } else {
             Datum::Null
         }
@@ -26,12 +34,18 @@ struct DivideBigint {}
 impl Function for DivideBigint {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_bigint(), args[1].as_maybe_bigint()) {
-            Datum::from(a / b)
+            if b == 0 {
+                Datum::Null
+            } else {
+                a.checked_div(b)
+                    .map(Datum::from)
+                    .unwrap_or_else(|| out_of_range(session, signature, a, "/", b))
+            }
         } else {
             Datum::Null
         }
@@ -44,16 +58,43 @@ struct DivideDecimal {}
 impl Function for DivideDecimal {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_decimal(), args[1].as_maybe_decimal()) {
-            let mut d = a / b;
-            if d.scale() > DECIMAL_MAX_SCALE as u32 {
-                d.rescale(DECIMAL_MAX_SCALE as u32);
+            if b.is_zero() {
+                Datum::Null
+            } else if let Some(mut d) = a.checked_div(b) {
+                if d.scale() > DECIMAL_MAX_SCALE as u32 {
+                    d.rescale(DECIMAL_MAX_SCALE as u32);
+                }
+                Datum::from(d)
+            } else {
+                out_of_range(session, signature, a, "/", b)
+            }
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DivideDouble {}
+
+impl Function for DivideDouble {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(a), Some(b)) = (args[0].as_maybe_double(), args[1].as_maybe_double()) {
+            if b == 0.0 {
+                Datum::Null
+            } else {
+                finite_double(session, signature, a / b, a, "/", b)
             }
-            Datum::from(d)
         } else {
             Datum::Null
         }
@@ -81,6 +122,13 @@ pub fn register_builtins(registry: &mut Registry) {
         DataType::Decimal(DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE),
         FunctionType::Scalar(&DivideDecimal {}),
     ));
+
+    registry.register_function(FunctionDefinition::new(
+        "/",
+        vec![DataType::Double, DataType::Double],
+        DataType::Double,
+        FunctionType::Scalar(&DivideDouble {}),
+    ));
 }
 
 #[cfg(test)]
@@ -114,6 +162,26 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_divide_int_by_zero() {
+        let session = Session::new(1);
+        assert_eq!(
+            DivideInteger {}.execute(&session, &DUMMY_SIG, &[Datum::from(5), Datum::from(0)]),
+            Datum::Null
+        );
+        assert_eq!(session.take_out_of_range(), None);
+
+        assert_eq!(
+            DivideInteger {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[Datum::from(i32::MIN), Datum::from(-1)]
+            ),
+            Datum::Null
+        );
+        assert!(session.take_out_of_range().is_some());
+    }
+
     #[test]
     fn test_divide_bigint() {
         assert_eq!(
@@ -140,4 +208,36 @@ mod tests {
             Datum::from(Decimal::new(333333333333333, 14))
         )
     }
+
+    #[test]
+    fn test_divide_double() {
+        assert_eq!(
+            DivideDouble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1.5), Datum::from(0.5)]
+            ),
+            Datum::from(3.0)
+        )
+    }
+
+    #[test]
+    fn test_divide_double_by_zero() {
+        let session = Session::new(1);
+        assert_eq!(
+            DivideDouble {}.execute(&session, &DUMMY_SIG, &[Datum::from(1.5), Datum::from(0.0)]),
+            Datum::Null
+        );
+        assert_eq!(session.take_out_of_range(), None);
+
+        assert_eq!(
+            DivideDouble {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[Datum::from(f64::MAX), Datum::from(0.5)]
+            ),
+            Datum::Null
+        );
+        assert!(session.take_out_of_range().is_some());
+    }
 }
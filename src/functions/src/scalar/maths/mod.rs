@@ -1,4 +1,7 @@
 use crate::registry::Registry;
+use crate::FunctionSignature;
+use data::{Datum, Session};
+use std::fmt::Display;
 
 mod add;
 mod divide;
@@ -11,3 +14,42 @@ pub fn register_builtins(registry: &mut Registry) {
     multiply::register_builtins(registry);
     subtract::register_builtins(registry);
 }
+
+/// Functions can't fail so when the result doesn't fit the return type we raise the error on the
+/// session for the executor to pick up and return null in the meantime.
+fn out_of_range<'a>(
+    session: &Session,
+    signature: &FunctionSignature,
+    a: impl Display,
+    op: &str,
+    b: impl Display,
+) -> Datum<'a> {
+    session.raise_out_of_range(format!(
+        "{} value is out of range in '({} {} {})'",
+        signature.ret, a, op, b
+    ));
+    Datum::Null
+}
+
+/// Doubles don't fail on overflow but go to infinity instead, which is raised as out of range
+/// the same as for the other numeric types.
+fn finite_double<'a>(
+    session: &Session,
+    signature: &FunctionSignature,
+    result: f64,
+    a: f64,
+    op: &str,
+    b: f64,
+) -> Datum<'a> {
+    if result.is_finite() {
+        Datum::from(result)
+    } else {
+        out_of_range(
+            session,
+            signature,
+            format!("{:e}", a),
+            op,
+            format!("{:e}", b),
+        )
+    }
+}
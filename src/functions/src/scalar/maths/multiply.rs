@@ -1,3 +1,4 @@
+use super::{finite_double, out_of_range};
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::{DataType, Datum, Session, DECIMAL_MAX_PRECISION, DECIMAL_MAX_SCALE};
@@ -9,16 +10,22 @@ struct MultiplyInteger {}
 impl Function for MultiplyInteger {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_integer(), args[1].as_maybe_integer()) {
-            Datum::from(a * b)
+            a.checked_mul(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "*", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 #[derive(Debug)]
@@ -27,16 +34,22 @@ struct MultiplyBigint {}
 impl Function for MultiplyBigint {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_bigint(), args[1].as_maybe_bigint()) {
-            Datum::from(a * b)
+            a.checked_mul(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "*", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 #[derive(Debug)]
@@ -45,16 +58,44 @@ struct MultiplyDecimal {}
 impl Function for MultiplyDecimal {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_decimal(), args[1].as_maybe_decimal()) {
-            Datum::from(a * b)
+            a.checked_mul(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "*", b))
+        } else {
+            Datum::Null
+        }
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
+}
+
+#[derive(Debug)]
+struct MultiplyDouble {}
+
+impl Function for MultiplyDouble {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(a), Some(b)) = (args[0].as_maybe_double(), args[1].as_maybe_double()) {
+            finite_double(session, signature, a * b, a, "*", b)
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -90,6 +131,13 @@ pub fn register_builtins(registry: &mut Registry) {
         },
         FunctionType::Scalar(&MultiplyDecimal {}),
     ));
+
+    registry.register_function(FunctionDefinition::new(
+        "*",
+        vec![DataType::Double, DataType::Double],
+        DataType::Double,
+        FunctionType::Scalar(&MultiplyDouble {}),
+    ));
 }
 
 #[cfg(test)]
@@ -149,4 +197,16 @@ mod tests {
             Datum::from(Decimal::new(6000, 3))
         )
     }
+
+    #[test]
+    fn test_multiply_double() {
+        assert_eq!(
+            MultiplyDouble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1.5), Datum::from(2.5)]
+            ),
+            Datum::from(3.75)
+        )
+    }
 }
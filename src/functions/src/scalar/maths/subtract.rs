@@ -1,3 +1,4 @@
+use super::{finite_double, out_of_range};
 use crate::registry::Registry;
 use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
 use data::{DataType, Datum, Session, DECIMAL_MAX_PRECISION};
@@ -9,16 +10,22 @@ struct SubtractInteger {}
 impl Function for SubtractInteger {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_integer(), args[1].as_maybe_integer()) {
-            Datum::from(a - b)
+            a.checked_sub(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "-", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 #[derive(Debug)]
@@ -27,16 +34,22 @@ struct SubtractBigint {}
 impl Function for SubtractBigint {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_bigint(), args[1].as_maybe_bigint()) {
-            Datum::from(a - b)
+            a.checked_sub(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "-", b))
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 #[derive(Debug)]
@@ -45,16 +58,44 @@ struct SubtractDecimal {}
 impl Function for SubtractDecimal {
     fn execute<'a>(
         &self,
-        _session: &Session,
-        _signature: &FunctionSignature,
+        session: &Session,
+        signature: &FunctionSignature,
         args: &'a [Datum<'a>],
     ) -> Datum<'a> {
         if let (Some(a), Some(b)) = (args[0].as_maybe_decimal(), args[1].as_maybe_decimal()) {
-            Datum::from(a - b)
+            a.checked_sub(b)
+                .map(Datum::from)
+                .unwrap_or_else(|| out_of_range(session, signature, a, "-", b))
+        } else {
+            Datum::Null
+        }
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
+}
+
+#[derive(Debug)]
+struct SubtractDouble {}
+
+impl Function for SubtractDouble {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(a), Some(b)) = (args[0].as_maybe_double(), args[1].as_maybe_double()) {
+            finite_double(session, signature, a - b, a, "-", b)
         } else {
             Datum::Null
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -90,6 +131,13 @@ pub fn register_builtins(registry: &mut Registry) {
         },
         FunctionType::Scalar(&SubtractDecimal {}),
     ));
+
+    registry.register_function(FunctionDefinition::new(
+        "-",
+        vec![DataType::Double, DataType::Double],
+        DataType::Double,
+        FunctionType::Scalar(&SubtractDouble {}),
+    ));
 }
 
 #[cfg(test)]
@@ -149,4 +197,16 @@ mod tests {
             Datum::from(Decimal::new(1230, 2))
         )
     }
+
+    #[test]
+    fn test_sub_double() {
+        assert_eq!(
+            SubtractDouble {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1.5), Datum::from(2.25)]
+            ),
+            Datum::from(-0.75)
+        )
+    }
 }
@@ -19,54 +19,65 @@ impl Function for Coalesce {
             .find(|d| !d.is_null())
             .map_or(Datum::Null, Datum::ref_clone)
     }
-}
 
-pub fn register_builtins(registry: &mut Registry) {
-    for datatype in &[
-        DataType::Boolean,
-        DataType::Integer,
-        DataType::BigInt,
-        DataType::Text,
-        DataType::ByteA,
-        DataType::Date,
-        DataType::Timestamp,
-        DataType::Json,
-        Decimal(0, 0),
-    ] {
-        for arg_count in 1..11 {
-            let args = (0..arg_count).map(|_| *datatype).collect();
-            if *datatype == Decimal(0, 0) {
-                registry.register_function(FunctionDefinition::new_with_type_resolver(
-                    "coalesce",
-                    args,
-                    // Here we're basically change the p & s of decimal to instead represent
-                    // the whole number digits and the frac digits, The resulting decimal
-                    // should contain the max of each and then we turn back into p & s.
-                    |args| {
-                        let (w, s) = args
-                            .iter()
-                            .filter(|d| **d != DataType::Null)
-                            .map(|d| {
-                                if let DataType::Decimal(p, s) = d {
-                                    // The whole_number, and frac parts
-                                    (*p - *s, *s)
-                                } else {
-                                    panic!()
-                                }
-                            })
-                            .fold((0, 0), |(w1, s1), (w2, s2)| (max(w1, w2), max(s1, s2)));
+    /// Only null when all of the args are
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.iter().all(|nullable| *nullable)
+    }
+}
 
-                        DataType::Decimal(min(DECIMAL_MAX_PRECISION, w + s), s)
-                    },
-                    FunctionType::Scalar(&Coalesce {}),
-                ))
+/// Here we're basically change the p & s of decimal to instead represent the whole number digits
+/// and the frac digits, The resulting decimal should contain the max of each and then we turn
+/// back into p & s.
+pub(super) fn decimal_return_type(args: &[DataType]) -> DataType {
+    let (w, s) = args
+        .iter()
+        .filter(|d| **d != DataType::Null)
+        .map(|d| {
+            if let DataType::Decimal(p, s) = d {
+                // The whole_number, and frac parts
+                (*p - *s, *s)
             } else {
-                registry.register_function(FunctionDefinition::new(
-                    "coalesce",
-                    args,
-                    *datatype,
-                    FunctionType::Scalar(&Coalesce {}),
-                ));
+                panic!()
+            }
+        })
+        .fold((0, 0), |(w1, s1), (w2, s2)| (max(w1, w2), max(s1, s2)));
+
+    DataType::Decimal(min(DECIMAL_MAX_PRECISION, w + s), s)
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    // ifnull(a, b) is just mysql's name for a two arg coalesce
+    for (function_name, arg_counts) in &[("coalesce", 1..11), ("ifnull", 2..3)] {
+        for datatype in &[
+            DataType::Boolean,
+            DataType::Integer,
+            DataType::BigInt,
+            DataType::Double,
+            DataType::Text,
+            DataType::ByteA,
+            DataType::Date,
+            DataType::Timestamp,
+            DataType::Json,
+            Decimal(0, 0),
+        ] {
+            for arg_count in arg_counts.clone() {
+                let args = (0..arg_count).map(|_| *datatype).collect();
+                if *datatype == Decimal(0, 0) {
+                    registry.register_function(FunctionDefinition::new_with_type_resolver(
+                        *function_name,
+                        args,
+                        decimal_return_type,
+                        FunctionType::Scalar(&Coalesce {}),
+                    ))
+                } else {
+                    registry.register_function(FunctionDefinition::new(
+                        *function_name,
+                        args,
+                        *datatype,
+                        FunctionType::Scalar(&Coalesce {}),
+                    ));
+                }
             }
         }
     }
@@ -126,4 +137,30 @@ mod tests {
         // Expect 10.4 == (14, 4)
         assert_eq!(sig.ret, Decimal(14, 4))
     }
+
+    #[test]
+    fn test_ifnull() {
+        let registry = Registry::default();
+        let (sig, _function) = registry
+            .resolve_function(&FunctionSignature {
+                name: "ifnull",
+                args: vec![DataType::Integer, DataType::Integer],
+                ret: DataType::Null,
+            })
+            .unwrap();
+        assert_eq!(sig.ret, DataType::Integer);
+        assert!(registry
+            .resolve_function(&FunctionSignature {
+                name: "ifnull",
+                args: vec![DataType::Integer],
+                ret: DataType::Null,
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_nullable() {
+        assert!(Coalesce {}.nullable(&[true, true]));
+        assert!(!Coalesce {}.nullable(&[true, false]));
+    }
 }
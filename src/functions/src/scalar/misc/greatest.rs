@@ -0,0 +1,147 @@
+use super::coalesce::decimal_return_type;
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::DataType::Decimal;
+use data::{DataType, Datum, Session};
+
+/// Returns the largest of its args, or null if any of them are null
+#[derive(Debug)]
+struct Greatest {}
+
+impl Function for Greatest {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if args.iter().any(Datum::is_null) {
+            Datum::Null
+        } else {
+            args.iter().max().map_or(Datum::Null, Datum::ref_clone)
+        }
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
+}
+
+/// Returns the smallest of its args, or null if any of them are null
+#[derive(Debug)]
+struct Least {}
+
+impl Function for Least {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if args.iter().any(Datum::is_null) {
+            Datum::Null
+        } else {
+            args.iter().min().map_or(Datum::Null, Datum::ref_clone)
+        }
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable.contains(&true)
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for (function_name, function) in &[
+        ("greatest", FunctionType::Scalar(&Greatest {})),
+        ("least", FunctionType::Scalar(&Least {})),
+    ] {
+        for datatype in &[
+            DataType::Integer,
+            DataType::BigInt,
+            DataType::Double,
+            DataType::Text,
+            DataType::ByteA,
+            DataType::Date,
+            DataType::Timestamp,
+            Decimal(0, 0),
+        ] {
+            for arg_count in 2..11 {
+                let args = (0..arg_count).map(|_| *datatype).collect();
+                if *datatype == Decimal(0, 0) {
+                    registry.register_function(FunctionDefinition::new_with_type_resolver(
+                        *function_name,
+                        args,
+                        // Same as coalesce, the result has to fit any of the args
+                        decimal_return_type,
+                        function.clone(),
+                    ))
+                } else {
+                    registry.register_function(FunctionDefinition::new(
+                        *function_name,
+                        args,
+                        *datatype,
+                        function.clone(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "greatest",
+        args: vec![],
+        ret: DataType::Integer,
+    };
+
+    #[test]
+    fn test_greatest() {
+        assert_eq!(
+            Greatest {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(2), Datum::from(3), Datum::from(1)]
+            ),
+            Datum::from(3)
+        );
+
+        assert_eq!(
+            Greatest {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(2), Datum::Null]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_least() {
+        assert_eq!(
+            Least {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from("b"), Datum::from("a"), Datum::from("c")]
+            ),
+            Datum::from("a")
+        );
+
+        assert_eq!(
+            Least {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null, Datum::from(1)]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_decimal_type() {
+        let registry = Registry::default();
+        let (sig, _function) = registry
+            .resolve_function(&FunctionSignature {
+                name: "greatest",
+                args: vec![Decimal(10, 0), Decimal(6, 4)],
+                ret: DataType::Null,
+            })
+            .unwrap();
+        assert_eq!(sig.ret, Decimal(14, 4))
+    }
+}
@@ -21,6 +21,10 @@ impl Function for IfFn {
             args[2].ref_clone()
         }
     }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable[1] || args_nullable[2]
+    }
 }
 
 pub fn register_builtins(registry: &mut Registry) {
@@ -28,6 +32,7 @@ pub fn register_builtins(registry: &mut Registry) {
         DataType::Boolean,
         DataType::Integer,
         DataType::BigInt,
+        DataType::Double,
         DataType::Text,
         DataType::ByteA,
         DataType::Date,
@@ -1,9 +1,13 @@
 use crate::registry::Registry;
 
 mod coalesce;
+mod greatest;
 mod if_fn;
+mod nullif;
 
 pub fn register_builtins(registry: &mut Registry) {
     coalesce::register_builtins(registry);
+    greatest::register_builtins(registry);
     if_fn::register_builtins(registry);
+    nullif::register_builtins(registry);
 }
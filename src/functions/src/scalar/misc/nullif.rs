@@ -0,0 +1,93 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::DataType::Decimal;
+use data::{DataType, Datum, Session};
+
+/// Returns null if both args are equal, otherwise the first arg
+#[derive(Debug)]
+struct NullIf {}
+
+impl Function for NullIf {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if args[0].sql_eq(&args[1], false) {
+            Datum::Null
+        } else {
+            args[0].ref_clone()
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for datatype in &[
+        DataType::Boolean,
+        DataType::Integer,
+        DataType::BigInt,
+        DataType::Double,
+        DataType::Text,
+        DataType::ByteA,
+        DataType::Date,
+        DataType::Timestamp,
+        DataType::Json,
+        Decimal(0, 0),
+    ] {
+        let args = vec![*datatype, *datatype];
+        if *datatype == Decimal(0, 0) {
+            registry.register_function(FunctionDefinition::new_with_type_resolver(
+                "nullif",
+                args,
+                // The result is always the first arg
+                |args| args[0],
+                FunctionType::Scalar(&NullIf {}),
+            ))
+        } else {
+            registry.register_function(FunctionDefinition::new(
+                "nullif",
+                args,
+                *datatype,
+                FunctionType::Scalar(&NullIf {}),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "nullif",
+        args: vec![],
+        ret: DataType::Integer,
+    };
+
+    #[test]
+    fn test_nullif() {
+        assert_eq!(
+            NullIf {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1), Datum::from(1)]
+            ),
+            Datum::Null
+        );
+
+        assert_eq!(
+            NullIf {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from(1), Datum::from(2)]
+            ),
+            Datum::from(1)
+        );
+
+        assert_eq!(
+            NullIf {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::from(1), Datum::Null]),
+            Datum::from(1)
+        );
+    }
+}
@@ -1,18 +1,24 @@
 use crate::registry::Registry;
 mod bool;
+mod bytes;
 mod casts;
 mod date;
 mod json;
 mod maths;
 mod misc;
 mod session;
+mod text;
+mod vector;
 
 pub fn register_builtins(registry: &mut Registry) {
     bool::register_builtins(registry);
+    bytes::register_builtins(registry);
     casts::register_builtins(registry);
     date::register_builtins(registry);
     json::register_builtins(registry);
     maths::register_builtins(registry);
     misc::register_builtins(registry);
     session::register_builtins(registry);
+    text::register_builtins(registry);
+    vector::register_builtins(registry);
 }
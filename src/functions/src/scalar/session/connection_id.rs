@@ -0,0 +1,46 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct ConnectionId {}
+
+impl Function for ConnectionId {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        _args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        Datum::from(session.connection_id as i64)
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "connection_id",
+        vec![],
+        DataType::BigInt,
+        FunctionType::Scalar(&ConnectionId {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "connection_id",
+        args: vec![],
+        ret: DataType::BigInt,
+    };
+
+    #[test]
+    fn test_connection_id() {
+        let session = Session::new(42);
+        assert_eq!(
+            ConnectionId {}.execute(&session, &DUMMY_SIG, &[]),
+            Datum::from(42_i64)
+        )
+    }
+}
@@ -0,0 +1,49 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct CurrentUser {}
+
+impl Function for CurrentUser {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        _args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        Datum::from(session.user.read().unwrap().to_string())
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    for function_name in &["current_user", "user"] {
+        registry.register_function(FunctionDefinition::new(
+            *function_name,
+            vec![],
+            DataType::Text,
+            FunctionType::Scalar(&CurrentUser {}),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "current_user",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_current_user() {
+        let session = Session::new(1);
+        *session.user.write().unwrap() = "root".to_string();
+        assert_eq!(
+            CurrentUser {}.execute(&session, &DUMMY_SIG, &[]),
+            Datum::from("root")
+        )
+    }
+}
@@ -17,12 +17,14 @@ impl Function for Database {
 }
 
 pub fn register_builtins(registry: &mut Registry) {
-    registry.register_function(FunctionDefinition::new(
-        "database",
-        vec![],
-        DataType::Text,
-        FunctionType::Scalar(&Database {}),
-    ));
+    for function_name in &["database", "schema"] {
+        registry.register_function(FunctionDefinition::new(
+            *function_name,
+            vec![],
+            DataType::Text,
+            FunctionType::Scalar(&Database {}),
+        ));
+    }
 }
 
 #[cfg(test)]
@@ -1,7 +1,15 @@
 use crate::registry::Registry;
 
+mod connection_id;
+mod current_user;
 mod database;
+mod replication_lag;
+mod version;
 
 pub fn register_builtins(registry: &mut Registry) {
+    connection_id::register_builtins(registry);
+    current_user::register_builtins(registry);
     database::register_builtins(registry);
+    replication_lag::register_builtins(registry);
+    version::register_builtins(registry);
 }
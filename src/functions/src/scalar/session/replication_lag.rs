@@ -0,0 +1,61 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, LogicalTimestamp, Session};
+use std::sync::atomic::Ordering;
+
+#[derive(Debug)]
+struct ReplicationLag {}
+
+impl Function for ReplicationLag {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        _args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        // Only followers read at a timestamp, it's just past the last batch from the leader.
+        let read_timestamp = session.read_timestamp.load(Ordering::Relaxed);
+        if read_timestamp == LogicalTimestamp::MAX.ms {
+            Datum::Null
+        } else {
+            let applied = read_timestamp.saturating_sub(1);
+            Datum::from(LogicalTimestamp::now().ms.saturating_sub(applied) as i64)
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "replication_lag",
+        vec![],
+        DataType::BigInt,
+        FunctionType::Scalar(&ReplicationLag {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "replication_lag",
+        args: vec![],
+        ret: DataType::BigInt,
+    };
+
+    #[test]
+    fn test_replication_lag() {
+        let session = Session::new(1);
+        assert_eq!(
+            ReplicationLag {}.execute(&session, &DUMMY_SIG, &[]),
+            Datum::Null
+        );
+
+        let applied = LogicalTimestamp::now().ms - 5000;
+        session.read_timestamp.store(applied + 1, Ordering::Relaxed);
+        let lag = ReplicationLag {}
+            .execute(&session, &DUMMY_SIG, &[])
+            .as_bigint();
+        assert!(lag >= 5000 && lag < 60_000);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session, SERVER_VERSION};
+
+#[derive(Debug)]
+struct Version {}
+
+impl Function for Version {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        _args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        Datum::from(SERVER_VERSION)
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "version",
+        vec![],
+        DataType::Text,
+        FunctionType::Scalar(&Version {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "version",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_version() {
+        let session = Session::new(1);
+        assert_eq!(
+            Version {}.execute(&session, &DUMMY_SIG, &[]),
+            Datum::from("8.0.0-incresql")
+        )
+    }
+}
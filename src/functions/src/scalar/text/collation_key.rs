@@ -0,0 +1,65 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{Collation, DataType, Datum, Session};
+use std::borrow::Cow;
+
+#[derive(Debug)]
+struct CollationKey {}
+
+/// collation_key(text), the text transformed so it compares, sorts and groups case
+/// insensitively. The planner wraps text in this when the session's collation is case
+/// insensitive.
+impl Function for CollationKey {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(text) = args[0].as_maybe_text() {
+            match Collation::CaseInsensitive.key(text) {
+                Cow::Borrowed(key) => Datum::from(key),
+                Cow::Owned(key) => Datum::from(key),
+            }
+        } else {
+            Datum::Null
+        }
+    }
+
+    fn nullable(&self, args_nullable: &[bool]) -> bool {
+        args_nullable[0]
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "collation_key",
+        vec![DataType::Text],
+        DataType::Text,
+        FunctionType::Scalar(&CollationKey {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "collation_key",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_collation_key() {
+        let session = Session::new(1);
+        assert_eq!(
+            CollationKey {}.execute(&session, &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+        assert_eq!(
+            CollationKey {}.execute(&session, &DUMMY_SIG, &[Datum::from("Hello")]),
+            Datum::from("HELLO")
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::fulltext::tokenize;
+use data::{DataType, Datum, Session};
+
+/// MATCH(col) AGAINST('terms'), a natural language full-text search.
+/// True if any of the terms in the search string appear in the text.
+/// The planner uses this to scan a full-text index rather than the whole table when the column
+/// is indexed, but the function is still evaluated over the rows the index returns.
+#[derive(Debug)]
+pub(super) struct MatchAgainst {}
+
+impl Function for MatchAgainst {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(text), Some(search)) = (args[0].as_maybe_text(), args[1].as_maybe_text()) {
+            let terms = tokenize(text);
+            Datum::from(
+                tokenize(search)
+                    .iter()
+                    .any(|term| terms.binary_search(term).is_ok()),
+            )
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "match",
+        vec![DataType::Text, DataType::Text],
+        DataType::Boolean,
+        FunctionType::Scalar(&MatchAgainst {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "match",
+        args: vec![],
+        ret: DataType::Boolean,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            MatchAgainst {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::Null, Datum::from("abc")]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_match() {
+        let text = Datum::from("The quick brown fox");
+        assert_eq!(
+            MatchAgainst {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[text.clone(), Datum::from("FOX hound")]
+            ),
+            Datum::from(true)
+        );
+        assert_eq!(
+            MatchAgainst {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[text, Datum::from("hound, dog")]
+            ),
+            Datum::from(false)
+        );
+    }
+}
@@ -0,0 +1,13 @@
+use crate::registry::Registry;
+
+mod collation_key;
+mod match_against;
+mod regexp;
+mod split;
+
+pub fn register_builtins(registry: &mut Registry) {
+    collation_key::register_builtins(registry);
+    match_against::register_builtins(registry);
+    regexp::register_builtins(registry);
+    split::register_builtins(registry);
+}
@@ -0,0 +1,257 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// The most patterns each thread holds onto, the cache is simply cleared once it fills up.
+const CACHE_SIZE: usize = 64;
+
+/// The most memory (in bytes) a compiled pattern, and separately its lazily built DFA, can take
+/// up. Along with CACHE_SIZE this bounds the memory the cache holds onto per thread.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+thread_local! {
+    /// The compiled regexes by pattern. Patterns are nearly always constants so this means a
+    /// query only compiles its patterns once per thread rather than once per row. Invalid patterns
+    /// are cached along with their error.
+    static REGEX_CACHE: RefCell<HashMap<String, Result<Regex, String>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Calls the function with the compiled regex for the pattern. Functions can't fail, so like
+/// overflows an invalid pattern is raised as an error on the session and None returned.
+fn with_regex<R, F: FnOnce(&Regex) -> R>(session: &Session, pattern: &str, f: F) -> Option<R> {
+    REGEX_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if !cache.contains_key(pattern) {
+            if cache.len() >= CACHE_SIZE {
+                cache.clear();
+            }
+            let regex = RegexBuilder::new(pattern)
+                .size_limit(REGEX_SIZE_LIMIT)
+                .dfa_size_limit(REGEX_SIZE_LIMIT)
+                .build()
+                .map_err(|err| err.to_string());
+            cache.insert(pattern.to_string(), regex);
+        }
+        match &cache[pattern] {
+            Ok(regex) => Some(f(regex)),
+            Err(err) => {
+                session.raise_out_of_range(format!(
+                    "Illegal argument to a regular expression: {}",
+                    err
+                ));
+                None
+            }
+        }
+    })
+}
+
+#[derive(Debug)]
+struct RegexpExtract {}
+
+/// regexp_extract(text, pattern[, group]), the given capture group of the first match, the
+/// whole match if no group is given. Null if nothing matches or the group didn't take part in the
+/// match.
+impl Function for RegexpExtract {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let group = match args.get(2) {
+            Some(group) => group.as_maybe_bigint(),
+            None => Some(0),
+        };
+        if let (Some(text), Some(pattern), Some(group)) =
+            (args[0].as_maybe_text(), args[1].as_maybe_text(), group)
+        {
+            if group < 0 {
+                return Datum::Null;
+            }
+            with_regex(session, pattern, |regex| {
+                regex
+                    .captures(text)
+                    .and_then(|captures| captures.get(group as usize))
+                    .map(|m| m.as_str())
+            })
+            .flatten()
+            .map(Datum::from)
+            .unwrap_or_default()
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RegexpReplace {}
+
+/// regexp_replace(text, pattern, replacement), replaces every match, the replacement can refer
+/// to capture groups with $1, ${name} etc.
+impl Function for RegexpReplace {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(text), Some(pattern), Some(replacement)) = (
+            args[0].as_maybe_text(),
+            args[1].as_maybe_text(),
+            args[2].as_maybe_text(),
+        ) {
+            with_regex(session, pattern, |regex| {
+                match regex.replace_all(text, replacement) {
+                    Cow::Borrowed(replaced) => Datum::from(replaced),
+                    Cow::Owned(replaced) => Datum::from(replaced),
+                }
+            })
+            .unwrap_or_default()
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "regexp_extract",
+        vec![DataType::Text, DataType::Text],
+        DataType::Text,
+        FunctionType::Scalar(&RegexpExtract {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "regexp_extract",
+        vec![DataType::Text, DataType::Text, DataType::BigInt],
+        DataType::Text,
+        FunctionType::Scalar(&RegexpExtract {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "regexp_replace",
+        vec![DataType::Text, DataType::Text, DataType::Text],
+        DataType::Text,
+        FunctionType::Scalar(&RegexpReplace {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "regexp_extract",
+        args: vec![],
+        ret: DataType::Text,
+    };
+
+    #[test]
+    fn test_null() {
+        let session = Session::new(1);
+        assert_eq!(
+            RegexpExtract {}.execute(&session, &DUMMY_SIG, &[Datum::Null, Datum::from("a")]),
+            Datum::Null
+        );
+        assert_eq!(
+            RegexpReplace {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[Datum::from("a"), Datum::from("a"), Datum::Null]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_regexp_extract() {
+        let session = Session::new(1);
+        let extract = |args: &[Datum]| {
+            RegexpExtract {}
+                .execute(&session, &DUMMY_SIG, args)
+                .as_static()
+        };
+        assert_eq!(
+            extract(&[Datum::from("order-123-x"), Datum::from(r"\d+")]),
+            Datum::from("123")
+        );
+        assert_eq!(
+            extract(&[
+                Datum::from("key=value"),
+                Datum::from(r"(\w+)=(\w+)"),
+                Datum::from(2_i64)
+            ]),
+            Datum::from("value")
+        );
+        assert_eq!(
+            extract(&[
+                Datum::from("key=value"),
+                Datum::from(r"(\w+)=(\w+)"),
+                Datum::from(3_i64)
+            ]),
+            Datum::Null
+        );
+        assert_eq!(
+            extract(&[Datum::from("abc"), Datum::from(r"\d+")]),
+            Datum::Null
+        );
+        assert_eq!(session.take_out_of_range(), None);
+        // Invalid pattern
+        assert_eq!(
+            extract(&[Datum::from("abc"), Datum::from("(")]),
+            Datum::Null
+        );
+        assert!(session.take_out_of_range().is_some());
+    }
+
+    #[test]
+    fn test_regexp_replace() {
+        let session = Session::new(1);
+        assert_eq!(
+            RegexpReplace {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[
+                    Datum::from("2020-01-31"),
+                    Datum::from(r"(\d+)-(\d+)-(\d+)"),
+                    Datum::from("$3/$2/$1")
+                ]
+            ),
+            Datum::from("31/01/2020")
+        );
+        assert_eq!(
+            RegexpReplace {}.execute(
+                &session,
+                &DUMMY_SIG,
+                &[
+                    Datum::from("a  b   c"),
+                    Datum::from(r"\s+"),
+                    Datum::from(" ")
+                ]
+            ),
+            Datum::from("a b c")
+        );
+    }
+
+    #[test]
+    fn test_regex_cache() {
+        let session = Session::new(1);
+        for idx in 0..CACHE_SIZE * 2 {
+            let pattern = format!("a{{{}}}", idx);
+            assert_eq!(
+                with_regex(&session, &pattern, |regex| regex.as_str().len()),
+                Some(pattern.len())
+            );
+        }
+        REGEX_CACHE.with(|cache| assert!(cache.borrow().len() <= CACHE_SIZE));
+        assert_eq!(with_regex(&session, "(", |_| ()), None);
+        // Patterns that compile to something huge are rejected rather than cached
+        assert_eq!(with_regex(&session, r"\w{1000}{1000}", |_| ()), None);
+        assert!(session.take_out_of_range().is_some());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::json::JsonBuilder;
+use data::{DataType, Datum, Session};
+
+#[derive(Debug)]
+struct Split {}
+
+/// split(text, delimiter), the parts of the text between the delimiters as a json array of
+/// strings. An empty delimiter splits the text into its characters.
+impl Function for Split {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let (Some(text), Some(delimiter)) = (args[0].as_maybe_text(), args[1].as_maybe_text()) {
+            Datum::from(JsonBuilder::default().array(|array| {
+                if delimiter.is_empty() {
+                    let mut buf = [0; 4];
+                    for c in text.chars() {
+                        array.push_string(c.encode_utf8(&mut buf));
+                    }
+                } else {
+                    for part in text.split(delimiter) {
+                        array.push_string(part);
+                    }
+                }
+            }))
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "split",
+        vec![DataType::Text, DataType::Text],
+        DataType::Json,
+        FunctionType::Scalar(&Split {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::json::OwnedJson;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "split",
+        args: vec![],
+        ret: DataType::Json,
+    };
+
+    #[test]
+    fn test_null() {
+        assert_eq!(
+            Split {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from("a"), Datum::Null]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        let split = |text: &str, delimiter: &str| {
+            Split {}
+                .execute(
+                    &Session::new(1),
+                    &DUMMY_SIG,
+                    &[Datum::from(text), Datum::from(delimiter)],
+                )
+                .as_static()
+        };
+        let json = |s: &str| Datum::from(OwnedJson::parse(s).unwrap());
+
+        assert_eq!(split("a,b,,c", ","), json(r#"["a", "b", "", "c"]"#));
+        assert_eq!(split("a::b", "::"), json(r#"["a", "b"]"#));
+        assert_eq!(split("", ","), json(r#"[""]"#));
+        assert_eq!(split("hé", ""), json(r#"["h", "é"]"#));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::rust_decimal::prelude::FromPrimitive;
+use data::rust_decimal::Decimal;
+use data::vector::Vector;
+use data::{DataType, Datum, Session, DECIMAL_MAX_PRECISION};
+
+/// The scale distances are returned with, the vectors only hold f32's so there's no point
+/// returning anything more precise.
+const DISTANCE_SCALE: u8 = 6;
+
+/// Applies the distance function to the two vectors and converts the result to a decimal.
+/// Returns null if either vector is null, the dimensions don't match or the distance is undefined.
+fn distance<'a>(
+    args: &'a [Datum<'a>],
+    distance_fn: fn(Vector, Vector) -> Option<f64>,
+) -> Datum<'a> {
+    if let (Some(a), Some(b)) = (args[0].as_maybe_vector(), args[1].as_maybe_vector()) {
+        if a.dimensions() == b.dimensions() {
+            if let Some(d) = distance_fn(a, b).and_then(Decimal::from_f64) {
+                return Datum::from(d.round_dp(DISTANCE_SCALE as u32));
+            }
+        }
+    }
+    Datum::Null
+}
+
+fn dot_product(a: Vector, b: Vector) -> f64 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| a as f64 * b as f64)
+        .sum()
+}
+
+/// 1 - cosine similarity, 0 for vectors pointing the same direction up to 2 for opposite
+/// directions, undefined for zero vectors.
+#[derive(Debug)]
+struct CosineDistance {}
+
+impl Function for CosineDistance {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        distance(args, |a, b| {
+            let magnitudes = (dot_product(a, a) * dot_product(b, b)).sqrt();
+            if magnitudes == 0.0 {
+                None
+            } else {
+                Some(1.0 - dot_product(a, b) / magnitudes)
+            }
+        })
+    }
+}
+
+/// The straight line (L2) distance between two vectors
+#[derive(Debug)]
+struct EuclideanDistance {}
+
+impl Function for EuclideanDistance {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        distance(args, |a, b| {
+            let sum: f64 = a
+                .iter()
+                .zip(b.iter())
+                .map(|(a, b)| (a as f64 - b as f64).powi(2))
+                .sum();
+            Some(sum.sqrt())
+        })
+    }
+}
+
+#[derive(Debug)]
+struct InnerProduct {}
+
+impl Function for InnerProduct {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        distance(args, |a, b| Some(dot_product(a, b)))
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    let ret = DataType::Decimal(DECIMAL_MAX_PRECISION, DISTANCE_SCALE);
+    registry.register_function(FunctionDefinition::new(
+        "cosine_distance",
+        vec![DataType::Vector(0), DataType::Vector(0)],
+        ret,
+        FunctionType::Scalar(&CosineDistance {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "euclidean_distance",
+        vec![DataType::Vector(0), DataType::Vector(0)],
+        ret,
+        FunctionType::Scalar(&EuclideanDistance {}),
+    ));
+
+    registry.register_function(FunctionDefinition::new(
+        "inner_product",
+        vec![DataType::Vector(0), DataType::Vector(0)],
+        ret,
+        FunctionType::Scalar(&InnerProduct {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "distance",
+        args: vec![],
+        ret: DataType::Decimal(DECIMAL_MAX_PRECISION, DISTANCE_SCALE),
+    };
+
+    fn vector(elements: &[f32]) -> Datum<'static> {
+        Datum::from(elements)
+    }
+
+    fn decimal(d: &str) -> Datum<'static> {
+        Datum::from(Decimal::from_str(d).unwrap())
+    }
+
+    #[test]
+    fn test_cosine_distance() {
+        let function = CosineDistance {};
+        let session = Session::new(1);
+        assert_eq!(
+            function.execute(
+                &session,
+                &DUMMY_SIG,
+                &[vector(&[1.0, 0.0]), vector(&[2.0, 0.0])]
+            ),
+            decimal("0")
+        );
+        assert_eq!(
+            function.execute(
+                &session,
+                &DUMMY_SIG,
+                &[vector(&[1.0, 0.0]), vector(&[0.0, 3.0])]
+            ),
+            decimal("1")
+        );
+        assert_eq!(
+            function.execute(
+                &session,
+                &DUMMY_SIG,
+                &[vector(&[1.0, 0.0]), vector(&[0.0, 0.0])]
+            ),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_euclidean_distance() {
+        let function = EuclideanDistance {};
+        let session = Session::new(1);
+        assert_eq!(
+            function.execute(
+                &session,
+                &DUMMY_SIG,
+                &[vector(&[0.0, 0.0]), vector(&[3.0, 4.0])]
+            ),
+            decimal("5")
+        );
+        // Mismatched dimensions
+        assert_eq!(
+            function.execute(&session, &DUMMY_SIG, &[vector(&[0.0]), vector(&[3.0, 4.0])]),
+            Datum::Null
+        );
+        assert_eq!(
+            function.execute(&session, &DUMMY_SIG, &[Datum::Null, vector(&[3.0, 4.0])]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_inner_product() {
+        assert_eq!(
+            InnerProduct {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[vector(&[1.0, 2.0]), vector(&[3.0, 0.5])]
+            ),
+            decimal("4")
+        );
+    }
+}
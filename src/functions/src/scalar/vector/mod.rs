@@ -0,0 +1,9 @@
+use crate::registry::Registry;
+
+mod distance;
+mod vector_dims;
+
+pub fn register_builtins(registry: &mut Registry) {
+    distance::register_builtins(registry);
+    vector_dims::register_builtins(registry);
+}
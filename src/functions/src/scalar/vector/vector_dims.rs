@@ -0,0 +1,58 @@
+use crate::registry::Registry;
+use crate::{Function, FunctionDefinition, FunctionSignature, FunctionType};
+use data::{DataType, Datum, Session};
+
+/// Returns the number of dimensions of a vector
+#[derive(Debug)]
+struct VectorDims {}
+
+impl Function for VectorDims {
+    fn execute<'a>(
+        &self,
+        _session: &Session,
+        _signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        if let Some(vector) = args[0].as_maybe_vector() {
+            Datum::from(vector.dimensions() as i32)
+        } else {
+            Datum::Null
+        }
+    }
+}
+
+pub fn register_builtins(registry: &mut Registry) {
+    registry.register_function(FunctionDefinition::new(
+        "vector_dims",
+        vec![DataType::Vector(0)],
+        DataType::Integer,
+        FunctionType::Scalar(&VectorDims {}),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMMY_SIG: FunctionSignature = FunctionSignature {
+        name: "vector_dims",
+        args: vec![],
+        ret: DataType::Integer,
+    };
+
+    #[test]
+    fn test_vector_dims() {
+        assert_eq!(
+            VectorDims {}.execute(
+                &Session::new(1),
+                &DUMMY_SIG,
+                &[Datum::from([1.0_f32, 2.0, 3.0].as_ref())]
+            ),
+            Datum::from(3)
+        );
+        assert_eq!(
+            VectorDims {}.execute(&Session::new(1), &DUMMY_SIG, &[Datum::Null]),
+            Datum::Null
+        );
+    }
+}
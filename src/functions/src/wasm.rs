@@ -0,0 +1,619 @@
+use crate::registry::UdfError;
+use crate::{Function, FunctionSignature};
+use data::{DataType, Datum, Session};
+use parity_wasm::builder;
+use parity_wasm::elements::{self, Instruction, Internal, Section};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+use wasmi::nan_preserving_float::F64;
+use wasmi::{
+    Error, ExternVal, Externals, FuncInstance, FuncRef, HostError, ImportsBuilder, Module,
+    ModuleImportResolver, ModuleInstance, ModuleRef, RuntimeArgs, RuntimeValue, Signature, Trap,
+    TrapKind, ValueType,
+};
+
+/// The most module instances each thread holds onto, the cache is simply cleared once it fills up.
+const CACHE_SIZE: usize = 64;
+
+/// The most fuel a single call can burn before it's trapped, roughly the number of instructions
+/// it gets to run, see meter.
+const FUEL_PER_CALL: u64 = 10_000_000;
+
+/// The most memory (in 64KiB pages) a module can declare. Modules have to declare a maximum
+/// which bounds how far memory.grow can take them.
+const MAX_MEMORY_PAGES: u32 = 16;
+
+/// The function metered modules import to burn their fuel.
+const GAS_MODULE: &str = "env";
+const GAS_FUNCTION: &str = "gas";
+
+/// Hands out the ids used to key the instance cache, a function that's dropped and recreated
+/// under the same name gets a new id so never sees the old module.
+static NEXT_FUNCTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The statement an instance was instantiated for, (connection id, statement id).
+type StatementKey = (u32, u64);
+
+thread_local! {
+    /// The instantiated modules by function id. Instances aren't thread safe so each thread
+    /// running a function instantiates its own copy of the module the first time it's called.
+    /// They're instantiated afresh for each statement so the module's memory and globals only
+    /// carry over between calls within a statement.
+    static INSTANCES: RefCell<HashMap<u64, (StatementKey, ModuleRef)>> = RefCell::new(HashMap::new());
+}
+
+/// A user defined scalar function implemented by the module's export of the same name.
+/// Modules are run by an interpreter and can't import anything so they have no way of reaching
+/// outside of the sandbox, all they can do is compute their result from their arguments. They're
+/// metered so a call that runs for too long (ie loops forever) traps.
+/// Null arguments give a null result without calling into the module.
+#[derive(Debug)]
+pub(crate) struct WasmFunction {
+    id: u64,
+    export: String,
+    args: Vec<DataType>,
+    ret: DataType,
+    /// The module after metering
+    module: Vec<u8>,
+    fuel: u64,
+}
+
+impl WasmFunction {
+    /// Checks the module can be instantiated and that its export matches the function's
+    /// signature.
+    pub fn new(
+        name: &str,
+        args: Vec<DataType>,
+        ret: DataType,
+        module: Vec<u8>,
+    ) -> Result<Self, UdfError> {
+        let params = args
+            .iter()
+            .map(|datatype| wasm_type(*datatype).ok_or(UdfError::UnsupportedType(*datatype)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let return_type = wasm_type(ret).ok_or(UdfError::UnsupportedType(ret))?;
+
+        let module = meter(&module)?;
+        let instance = instantiate(&module)?;
+        let signature = match instance.export_by_name(name) {
+            Some(ExternVal::Func(func)) => func.signature().clone(),
+            _ => {
+                return Err(UdfError::InvalidModule(format!(
+                    "the module doesn't export a function named {}",
+                    name
+                )))
+            }
+        };
+        if signature.params() != params.as_slice() || signature.return_type() != Some(return_type) {
+            return Err(UdfError::InvalidModule(format!(
+                "expected {} to have the signature {:?} -> {:?} but it's {:?} -> {:?}",
+                name,
+                params,
+                return_type,
+                signature.params(),
+                signature.return_type()
+            )));
+        }
+
+        Ok(WasmFunction {
+            id: NEXT_FUNCTION_ID.fetch_add(1, Ordering::Relaxed),
+            export: name.to_string(),
+            args,
+            ret,
+            module,
+            fuel: FUEL_PER_CALL,
+        })
+    }
+
+    /// Calls the export, returning the error message if the module fails to instantiate or the
+    /// call traps.
+    fn invoke(
+        &self,
+        session: &Session,
+        args: &[RuntimeValue],
+    ) -> Result<Option<RuntimeValue>, String> {
+        let statement = (
+            session.connection_id,
+            session.statement_id.load(Ordering::Relaxed),
+        );
+        INSTANCES.with(|instances| {
+            let mut instances = instances.borrow_mut();
+            let stale = instances
+                .get(&self.id)
+                .map_or(true, |(instance_statement, _)| {
+                    *instance_statement != statement
+                });
+            if stale {
+                if instances.len() >= CACHE_SIZE {
+                    instances.clear();
+                }
+                let instance = instantiate(&self.module).map_err(|err| err.to_string())?;
+                instances.insert(self.id, (statement, instance));
+            }
+            instances[&self.id]
+                .1
+                .invoke_export(&self.export, args, &mut Fuel(self.fuel))
+                .map_err(|err| match err.as_host_error() {
+                    Some(host_error) => host_error.to_string(),
+                    None => err.to_string(),
+                })
+        })
+    }
+}
+
+impl Function for WasmFunction {
+    fn execute<'a>(
+        &self,
+        session: &Session,
+        signature: &FunctionSignature,
+        args: &'a [Datum<'a>],
+    ) -> Datum<'a> {
+        let mut wasm_args = Vec::with_capacity(args.len());
+        for (arg, datatype) in args.iter().zip(&self.args) {
+            if let Some(value) = to_wasm(arg, *datatype) {
+                wasm_args.push(value);
+            } else {
+                return Datum::Null;
+            }
+        }
+        match self.invoke(session, &wasm_args) {
+            Ok(Some(value)) => from_wasm(value, self.ret),
+            Ok(None) => Datum::Null,
+            Err(message) => {
+                // Functions can't fail, so like overflows the error is raised on the session
+                session
+                    .raise_out_of_range(format!("Function {} failed: {}", signature.name, message));
+                Datum::Null
+            }
+        }
+    }
+}
+
+fn instantiate(module: &[u8]) -> Result<ModuleRef, UdfError> {
+    let module =
+        Module::from_buffer(module).map_err(|err| UdfError::InvalidModule(err.to_string()))?;
+    ModuleInstance::new(
+        &module,
+        &ImportsBuilder::new().with_resolver(GAS_MODULE, &GasResolver {}),
+    )
+    .map_err(|err| UdfError::InvalidModule(err.to_string()))?
+    .run_start(&mut Fuel(FUEL_PER_CALL))
+    .map_err(|err| UdfError::InvalidModule(err.to_string()))
+}
+
+/// Instruments the module so that it burns fuel as it runs. On entry to each function and at the
+/// top of each loop iteration it calls out to the gas import with the number of instructions in
+/// the function or loop body. That over counts (not every instruction gets run) but it bounds
+/// how long a call can run for which is all we're after.
+/// Modules also have to declare the most memory they'll use (no more than MAX_MEMORY_PAGES), the
+/// interpreter then fails any memory.grow past that.
+fn meter(module: &[u8]) -> Result<Vec<u8>, UdfError> {
+    let invalid = |err: elements::Error| UdfError::InvalidModule(err.to_string());
+    let module: elements::Module = elements::deserialize_buffer(module).map_err(invalid)?;
+    if module
+        .import_section()
+        .map_or(false, |imports| !imports.entries().is_empty())
+    {
+        return Err(UdfError::InvalidModule(
+            "modules can't import anything".to_string(),
+        ));
+    }
+    for memory in module
+        .memory_section()
+        .map_or(&[][..], |memories| memories.entries())
+    {
+        let limits = memory.limits();
+        if limits.initial() > MAX_MEMORY_PAGES
+            || limits.maximum().map_or(true, |max| max > MAX_MEMORY_PAGES)
+        {
+            return Err(UdfError::InvalidModule(format!(
+                "modules must declare a maximum memory of at most {} pages",
+                MAX_MEMORY_PAGES
+            )));
+        }
+    }
+
+    let mut module_builder = builder::from_module(module);
+    let gas_type = module_builder.push_signature(
+        builder::signature()
+            .with_param(elements::ValueType::I32)
+            .build_sig(),
+    );
+    module_builder.push_import(
+        builder::import()
+            .module(GAS_MODULE)
+            .field(GAS_FUNCTION)
+            .external()
+            .func(gas_type)
+            .build(),
+    );
+    let mut module = module_builder.build();
+
+    // The import is function 0 so all the module's own functions move up one
+    for section in module.sections_mut() {
+        match section {
+            Section::Code(code) => {
+                for body in code.bodies_mut() {
+                    meter_body(body.code_mut().elements_mut());
+                }
+            }
+            Section::Export(exports) => {
+                for export in exports.entries_mut() {
+                    if let Internal::Function(idx) = export.internal_mut() {
+                        *idx += 1;
+                    }
+                }
+            }
+            Section::Element(segments) => {
+                for segment in segments.entries_mut() {
+                    for member in segment.members_mut() {
+                        *member += 1;
+                    }
+                }
+            }
+            Section::Start(idx) => *idx += 1,
+            _ => {}
+        }
+    }
+    elements::serialize(module).map_err(invalid)
+}
+
+fn meter_body(instructions: &mut Vec<Instruction>) {
+    let mut metered = Vec::with_capacity(instructions.len() + 2);
+    metered.extend(gas(instructions.len()));
+    for (idx, instruction) in instructions.iter().enumerate() {
+        let instruction = match instruction {
+            Instruction::Call(function) => Instruction::Call(function + 1),
+            instruction => instruction.clone(),
+        };
+        let is_loop = matches!(instruction, Instruction::Loop(_));
+        metered.push(instruction);
+        if is_loop {
+            metered.extend(gas(loop_length(&instructions[idx + 1..])));
+        }
+    }
+    *instructions = metered;
+}
+
+/// The number of instructions up to the end of the loop.
+fn loop_length(instructions: &[Instruction]) -> usize {
+    let mut depth = 0;
+    for (idx, instruction) in instructions.iter().enumerate() {
+        match instruction {
+            Instruction::Block(_) | Instruction::Loop(_) | Instruction::If(_) => depth += 1,
+            Instruction::End if depth == 0 => return idx,
+            Instruction::End => depth -= 1,
+            _ => {}
+        }
+    }
+    instructions.len()
+}
+
+fn gas(cost: usize) -> Vec<Instruction> {
+    vec![
+        Instruction::I32Const(cost.min(i32::MAX as usize) as i32),
+        Instruction::Call(0),
+    ]
+}
+
+/// Resolves the gas import of metered modules.
+struct GasResolver {}
+
+impl ModuleImportResolver for GasResolver {
+    fn resolve_func(&self, field_name: &str, signature: &Signature) -> Result<FuncRef, Error> {
+        if field_name == GAS_FUNCTION && signature.params() == [ValueType::I32] {
+            Ok(FuncInstance::alloc_host(signature.clone(), 0))
+        } else {
+            Err(Error::Instantiation(format!(
+                "Export {} not found",
+                field_name
+            )))
+        }
+    }
+}
+
+/// The fuel left for a call into a module, the gas import burns through it and traps once it's
+/// gone.
+struct Fuel(u64);
+
+impl Externals for Fuel {
+    fn invoke_index(
+        &mut self,
+        _index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let cost = args.nth_checked::<i32>(0)? as u64;
+        if cost > self.0 {
+            return Err(Trap::new(TrapKind::Host(Box::new(OutOfFuel {}))));
+        }
+        self.0 -= cost;
+        Ok(None)
+    }
+}
+
+#[derive(Debug)]
+struct OutOfFuel {}
+
+impl Display for OutOfFuel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ran out of fuel")
+    }
+}
+
+impl HostError for OutOfFuel {}
+
+/// The wasm type each sql type is passed as, only the numeric types are supported.
+fn wasm_type(datatype: DataType) -> Option<ValueType> {
+    match datatype {
+        DataType::Boolean | DataType::Integer => Some(ValueType::I32),
+        DataType::BigInt => Some(ValueType::I64),
+        DataType::Double => Some(ValueType::F64),
+        _ => None,
+    }
+}
+
+fn to_wasm(datum: &Datum, datatype: DataType) -> Option<RuntimeValue> {
+    match datatype {
+        DataType::Boolean => datum
+            .as_maybe_boolean()
+            .map(|b| RuntimeValue::I32(b as i32)),
+        DataType::Integer => datum.as_maybe_integer().map(RuntimeValue::I32),
+        DataType::BigInt => datum.as_maybe_bigint().map(RuntimeValue::I64),
+        DataType::Double => datum.as_maybe_double().map(|d| RuntimeValue::F64(d.into())),
+        _ => None,
+    }
+}
+
+fn from_wasm(value: RuntimeValue, datatype: DataType) -> Datum<'static> {
+    let datum = match datatype {
+        DataType::Boolean => value.try_into::<i32>().map(|i| Datum::from(i != 0)),
+        DataType::Integer => value.try_into::<i32>().map(Datum::from),
+        DataType::BigInt => value.try_into::<i64>().map(Datum::from),
+        DataType::Double => value.try_into::<F64>().map(|d| Datum::from(d.to_float())),
+        _ => None,
+    };
+    datum.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::Registry;
+
+    // (func (export "add_one") (param i64) (result i64) local.get 0 i64.const 1 i64.add)
+    const ADD_ONE: &str =
+        "0061736d0100000001060160017e017e03020100070b01076164645f6f6e6500000a09010700200042017c0b";
+    // (func (export "div") (param i64 i64) (result i64) local.get 0 local.get 1 i64.div_s)
+    const DIV: &str =
+        "0061736d0100000001070160027e7e017e030201000707010364697600000a09010700200020017f0b";
+    // (func (export "spin") (param i64) (result i64) (loop (br 0)) local.get 0)
+    const SPIN: &str =
+        "0061736d0100000001060160017e017e03020100070801047370696e00000a0b01090003400c000b20000b";
+    // (global (mut i64) (i64.const 0))
+    // (func (export "counter") (param i64) (result i64)
+    //   global.get 0 i64.const 1 i64.add global.set 0 global.get 0)
+    const COUNTER: &str = "0061736d0100000001060160017e017e030201000606017e0142000b070b0107636f\
+                           756e74657200000a0d010b00230042017c240023000b";
+
+    // (memory 1 16)
+    // (func (export "grow") (param i64) (result i64)
+    //   (block (loop (br_if 0 (i32.ne (memory.grow (i32.const 1)) (i32.const -1)))))
+    //   memory.size i64.extend_i32_u)
+    const GROW: &str = "0061736d0100000001060160017e017e030201000504010101100708010467726f7700000a\
+                        160114000240034041014000417f470d000b0b3f00ad0b";
+    // The same but with (memory 1) ie no maximum
+    const GROW_UNBOUNDED: &str = "0061736d0100000001060160017e017e030201000503010001070801046772\
+                                  6f7700000a160114000240034041014000417f470d000b0b3f00ad0b";
+    // The same but with (memory 1 65536)
+    const GROW_HUGE: &str = "0061736d0100000001060160017e017e03020100050601010180800407080104\
+                             67726f7700000a160114000240034041014000417f470d000b0b3f00ad0b";
+
+    fn unhex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn signature(name: &'static str) -> FunctionSignature<'static> {
+        FunctionSignature {
+            name,
+            args: vec![],
+            ret: DataType::BigInt,
+        }
+    }
+
+    #[test]
+    fn test_wasm_function() {
+        let session = Session::new(1);
+        let function = WasmFunction::new(
+            "add_one",
+            vec![DataType::BigInt],
+            DataType::BigInt,
+            unhex(ADD_ONE),
+        )
+        .unwrap();
+        assert_eq!(
+            function.execute(&session, &signature("add_one"), &[Datum::from(41_i64)]),
+            Datum::from(42_i64)
+        );
+        assert_eq!(
+            function.execute(&session, &signature("add_one"), &[Datum::Null]),
+            Datum::Null
+        );
+    }
+
+    #[test]
+    fn test_wasm_function_trap() {
+        let session = Session::new(1);
+        let function = WasmFunction::new(
+            "div",
+            vec![DataType::BigInt, DataType::BigInt],
+            DataType::BigInt,
+            unhex(DIV),
+        )
+        .unwrap();
+        assert_eq!(
+            function.execute(
+                &session,
+                &signature("div"),
+                &[Datum::from(7_i64), Datum::from(2_i64)]
+            ),
+            Datum::from(3_i64)
+        );
+        assert_eq!(session.take_out_of_range(), None);
+
+        function.execute(
+            &session,
+            &signature("div"),
+            &[Datum::from(1_i64), Datum::from(0_i64)],
+        );
+        assert!(session.take_out_of_range().is_some());
+    }
+
+    #[test]
+    fn test_wasm_function_out_of_fuel() {
+        let session = Session::new(1);
+        let mut function = WasmFunction::new(
+            "spin",
+            vec![DataType::BigInt],
+            DataType::BigInt,
+            unhex(SPIN),
+        )
+        .unwrap();
+        // Keeps the test quick, the loop burns 3 fuel a time
+        function.fuel = 10_000;
+        assert_eq!(
+            function.execute(&session, &signature("spin"), &[Datum::from(1_i64)]),
+            Datum::Null
+        );
+        assert_eq!(
+            session.take_out_of_range(),
+            Some("Function spin failed: ran out of fuel".to_string())
+        );
+    }
+
+    #[test]
+    fn test_wasm_function_state_reset_per_statement() {
+        let session = Session::new(1);
+        let function = WasmFunction::new(
+            "counter",
+            vec![DataType::BigInt],
+            DataType::BigInt,
+            unhex(COUNTER),
+        )
+        .unwrap();
+        let args = [Datum::from(0_i64)];
+        let call = || function.execute(&session, &signature("counter"), &args);
+        assert_eq!(call(), Datum::from(1_i64));
+        assert_eq!(call(), Datum::from(2_i64));
+        session.reset_statement_info();
+        assert_eq!(call(), Datum::from(1_i64));
+    }
+
+    #[test]
+    fn test_wasm_function_memory_limit() {
+        let session = Session::new(1);
+        let function = WasmFunction::new(
+            "grow",
+            vec![DataType::BigInt],
+            DataType::BigInt,
+            unhex(GROW),
+        )
+        .unwrap();
+        // Growing stops at the declared maximum
+        assert_eq!(
+            function.execute(&session, &signature("grow"), &[Datum::from(0_i64)]),
+            Datum::from(MAX_MEMORY_PAGES as i64)
+        );
+
+        for module in &[GROW_UNBOUNDED, GROW_HUGE] {
+            assert!(matches!(
+                WasmFunction::new(
+                    "grow",
+                    vec![DataType::BigInt],
+                    DataType::BigInt,
+                    unhex(module)
+                ),
+                Err(UdfError::InvalidModule(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_wasm_function_validation() {
+        assert!(matches!(
+            WasmFunction::new(
+                "nope",
+                vec![DataType::BigInt],
+                DataType::BigInt,
+                unhex(ADD_ONE)
+            ),
+            Err(UdfError::InvalidModule(_))
+        ));
+        assert!(matches!(
+            WasmFunction::new(
+                "add_one",
+                vec![DataType::Integer],
+                DataType::BigInt,
+                unhex(ADD_ONE)
+            ),
+            Err(UdfError::InvalidModule(_))
+        ));
+        assert_eq!(
+            WasmFunction::new(
+                "add_one",
+                vec![DataType::Text],
+                DataType::BigInt,
+                unhex(ADD_ONE)
+            )
+            .unwrap_err(),
+            UdfError::UnsupportedType(DataType::Text)
+        );
+        assert!(matches!(
+            WasmFunction::new("add_one", vec![], DataType::BigInt, b"not wasm".to_vec()),
+            Err(UdfError::InvalidModule(_))
+        ));
+    }
+
+    #[test]
+    fn test_register_wasm_function() {
+        let registry = Registry::new(false);
+        registry
+            .register_wasm_function(
+                "add_one",
+                vec![DataType::BigInt],
+                DataType::BigInt,
+                unhex(ADD_ONE),
+            )
+            .unwrap();
+        assert_eq!(
+            registry.register_wasm_function(
+                "add_one",
+                vec![DataType::BigInt],
+                DataType::BigInt,
+                unhex(ADD_ONE)
+            ),
+            Err(UdfError::FunctionAlreadyExists("add_one".to_string()))
+        );
+
+        let lookup_sig = FunctionSignature {
+            name: "add_one",
+            args: vec![DataType::BigInt],
+            ret: DataType::Null,
+        };
+        let (signature, function) = registry.resolve_function(&lookup_sig).unwrap();
+        assert_eq!(signature.ret, DataType::BigInt);
+        assert_eq!(
+            function
+                .as_scalar()
+                .execute(&Session::new(1), &signature, &[Datum::from(1_i64)]),
+            Datum::from(2_i64)
+        );
+
+        registry.unregister_function("add_one");
+        assert!(registry.resolve_function(&lookup_sig).is_err());
+    }
+}
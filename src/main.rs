@@ -1,8 +1,12 @@
+use runtime::config::{Config, LogLevel};
 use runtime::Runtime;
-use server::Server;
+use server::{replication, Server, ShutdownHandle};
 use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+use tracing::{info, Level};
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
 #[cfg(not(windows))]
 use jemallocator::Jemalloc;
 
@@ -12,20 +16,292 @@ static GLOBAL: Jemalloc = Jemalloc;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let matches = App::new("TPCH")
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .help("Toml file to read the config from, the other flags override it")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("directory")
                 .short("d")
                 .long("directory")
-                .default_value("target/test_db"),
+                .help("Where the database is stored [default: target/test_db]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("listen-address")
+                .long("listen-address")
+                .help("Address to accept mysql connections on [default: 0.0.0.0]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("port")
+                .short("P")
+                .long("port")
+                .help("Port to accept mysql connections on [default: 3307]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("arrow-port")
+                .long("arrow-port")
+                .help("Port to serve query results as arrow ipc streams on, off unless set")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .help("One of error, warn, info, debug or trace [default: info]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("slow-query-log")
+                .long("slow-query-log")
+                .help("Record statements running longer than long-query-time in incresql.slow_log"),
+        )
+        .arg(
+            Arg::with_name("long-query-time")
+                .long("long-query-time")
+                .help("Seconds a statement runs for before it's logged as slow [default: 10]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("interactive-concurrency")
+                .long("interactive-concurrency")
+                .help("Max number of queries to run at once, 0 for no limit [default: 0]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("maintenance-concurrency")
+                .long("maintenance-concurrency")
+                .help("Max number of compactions/analyzes to run at once, 0 for no limit [default: 1]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("worker-threads")
+                .long("worker-threads")
+                .help("Number of threads servicing client connections [default: 8]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-connections")
+                .long("max-connections")
+                .help("Max number of client connections, 0 for no limit [default: 0]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-user-connections")
+                .long("max-user-connections")
+                .help("Max number of client connections per user, 0 for no limit [default: 0]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("wait-timeout")
+                .long("wait-timeout")
+                .help("Seconds before idle connections are closed, 0 for no limit [default: 28800]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-execution-time")
+                .long("max-execution-time")
+                .help("Milliseconds statements can run for before being interrupted, 0 for no limit [default: 0]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("block-cache-size")
+                .long("block-cache-size")
+                .help("Bytes of storage blocks to cache in memory [default: 8388608]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("write-buffer-size")
+                .long("write-buffer-size")
+                .help("Bytes of writes to buffer in memory before flushing to disk [default: 67108864]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("sort-buffer-size")
+                .long("sort-buffer-size")
+                .help("Bytes each sort can buffer in memory before spilling to disk [default: 134217728]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rocksdb-parallelism")
+                .long("rocksdb-parallelism")
+                .help("Threads used for flushes and compactions [default: 4]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rocksdb-max-background-jobs")
+                .long("rocksdb-max-background-jobs")
+                .help("Max number of flushes and compactions to run at once [default: 2]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("rocksdb-max-open-files")
+                .long("rocksdb-max-open-files")
+                .help("Max number of files to keep open, -1 for no limit [default: -1]")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("replication-listen")
+                .long("replication-listen")
+                .help("Address to ship writes to followers from, ie 127.0.0.1:3308")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("follow")
+                .long("follow")
+                .help("Address of the leader to follow, followers only serve reads")
+                .takes_value(true)
+                .conflicts_with("replication-listen"),
+        )
+        .arg(
+            Arg::with_name("follow-password")
+                .long("follow-password")
+                .help("The leader's root password, followers log in as root [default: none]")
+                .env("INCRESQL_FOLLOW_PASSWORD")
+                .takes_value(true)
+                .requires("follow"),
         )
         .get_matches();
-    let listen_address = "0.0.0.0:3307";
-    let path = matches.value_of("directory").unwrap();
-    eprintln!("Initializing Runtime");
-    let runtime = Runtime::new(path)?;
-    eprintln!("Initializing Server");
-    let mut server = Server::new(runtime);
-    eprintln!("Server Running");
-    server.listen(listen_address)?;
+    let config = config_from_args(&matches)?;
+    tracing_subscriber::fmt()
+        .with_max_level(tracing_level(config.log_level))
+        .with_writer(std::io::stderr)
+        .init();
+
+    info!(directory = %config.directory, "Initializing Runtime");
+    let runtime = Runtime::new_with_config(&config)?;
+    if let Some(addr) = matches.value_of("replication-listen") {
+        info!(%addr, "Shipping writes to followers");
+        replication::serve_followers(runtime.replication(), addr)?;
+    }
+    if let Some(leader_addr) = matches.value_of("follow") {
+        info!(%leader_addr, "Following leader");
+        runtime.set_read_only(true);
+        let password = matches.value_of("follow-password").unwrap_or_default();
+        replication::follow(
+            runtime.replication(),
+            leader_addr.to_string(),
+            "root".to_string(),
+            password.to_string(),
+        );
+    }
+    info!("Initializing Server");
+    let mut server = Server::new_with_config(runtime, &config.server);
+    let shutdown = ShutdownHandle::default();
+    let signal_shutdown = shutdown.clone();
+    ctrlc::set_handler(move || {
+        info!("Shutting down");
+        signal_shutdown.shutdown();
+    })?;
+    if let Some(arrow_addr) = config.server.arrow_listen_addr() {
+        info!(%arrow_addr, "Serving arrow results");
+        server.serve_arrow(&arrow_addr, shutdown.clone())?;
+    }
+    let listen_addr = config.server.listen_addr();
+    info!(%listen_addr, "Server Running");
+    server.listen(&listen_addr, shutdown)?;
+    info!("Server Stopped");
+    Ok(())
+}
+
+fn tracing_level(log_level: LogLevel) -> Level {
+    match log_level {
+        LogLevel::Error => Level::ERROR,
+        LogLevel::Warn => Level::WARN,
+        LogLevel::Info => Level::INFO,
+        LogLevel::Debug => Level::DEBUG,
+        LogLevel::Trace => Level::TRACE,
+    }
+}
+
+/// Reads the config file (if there is one) and then overrides it with any flags passed in.
+fn config_from_args(matches: &ArgMatches) -> Result<Config, Box<dyn Error>> {
+    let mut config = match matches.value_of("config") {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    if let Some(directory) = matches.value_of("directory") {
+        config.directory = directory.to_string();
+    }
+    if let Some(listen_address) = matches.value_of("listen-address") {
+        config.server.listen_address = listen_address.to_string();
+    }
+    override_with(matches, "port", &mut config.server.port)?;
+    if matches.is_present("arrow-port") {
+        let mut arrow_port = 0;
+        override_with(matches, "arrow-port", &mut arrow_port)?;
+        config.server.arrow_port = Some(arrow_port);
+    }
+    override_with(matches, "log-level", &mut config.log_level)?;
+    if matches.is_present("slow-query-log") {
+        config.slow_query_log.enabled = true;
+    }
+    override_with(
+        matches,
+        "long-query-time",
+        &mut config.slow_query_log.long_query_time,
+    )?;
+    override_with(matches, "worker-threads", &mut config.server.worker_threads)?;
+    let workload = &mut config.workload;
+    override_with(
+        matches,
+        "interactive-concurrency",
+        &mut workload.interactive_concurrency,
+    )?;
+    override_with(
+        matches,
+        "maintenance-concurrency",
+        &mut workload.maintenance_concurrency,
+    )?;
+    let connections = &mut config.connections;
+    override_with(matches, "max-connections", &mut connections.max_connections)?;
+    override_with(
+        matches,
+        "max-user-connections",
+        &mut connections.max_user_connections,
+    )?;
+    override_with(matches, "wait-timeout", &mut connections.wait_timeout)?;
+    override_with(
+        matches,
+        "max-execution-time",
+        &mut connections.max_execution_time,
+    )?;
+    let memory = &mut config.memory;
+    override_with(matches, "block-cache-size", &mut memory.block_cache_size)?;
+    override_with(matches, "write-buffer-size", &mut memory.write_buffer_size)?;
+    override_with(matches, "sort-buffer-size", &mut memory.sort_buffer_size)?;
+    let rocksdb = &mut config.rocksdb;
+    override_with(matches, "rocksdb-parallelism", &mut rocksdb.parallelism)?;
+    override_with(
+        matches,
+        "rocksdb-max-background-jobs",
+        &mut rocksdb.max_background_jobs,
+    )?;
+    override_with(
+        matches,
+        "rocksdb-max-open-files",
+        &mut rocksdb.max_open_files,
+    )?;
+    Ok(config)
+}
+
+/// Parses the flag into the config value if it was passed.
+fn override_with<T: FromStr>(
+    matches: &ArgMatches,
+    name: &str,
+    value: &mut T,
+) -> Result<(), Box<dyn Error>>
+where
+    T::Err: Display,
+{
+    if let Some(flag) = matches.value_of(name) {
+        *value = flag
+            .parse()
+            .map_err(|err: T::Err| format!("Invalid --{} {}: {}", name, flag, err))?;
+    }
     Ok(())
 }
@@ -0,0 +1,123 @@
+use crate::atoms::{identifier_str, kw, qualified_reference, quoted_string};
+use crate::create::{partition_definition, user_name};
+use crate::whitespace::ws_0;
+use crate::ParserResult;
+use ast::statement::{AlterTable, AlterTableAction, AlterUser, Statement};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{cut, map};
+use nom::sequence::{delimited, pair, preceded, tuple};
+
+/// Parses an alter statement.
+pub fn alter(input: &str) -> ParserResult<Statement> {
+    preceded(kw("ALTER"), cut(alt((alter_table, alter_user))))(input)
+}
+
+/// Currently only adding and dropping partitions is supported.
+/// ALTER TABLE t ADD PARTITION (PARTITION p2 VALUES LESS THAN (20))
+/// ALTER TABLE t DROP PARTITION p0
+fn alter_table(input: &str) -> ParserResult<Statement> {
+    map(
+        tuple((
+            tuple((ws_0, kw("TABLE"), ws_0)),
+            qualified_reference,
+            preceded(ws_0, alter_table_action),
+        )),
+        |(_, (database, name), action)| {
+            Statement::AlterTable(AlterTable {
+                database,
+                name,
+                action,
+            })
+        },
+    )(input)
+}
+
+/// ALTER USER name IDENTIFIED BY 'password'
+fn alter_user(input: &str) -> ParserResult<Statement> {
+    map(
+        tuple((
+            tuple((ws_0, kw("USER"), ws_0)),
+            user_name,
+            preceded(
+                tuple((ws_0, kw("IDENTIFIED"), ws_0, kw("BY"), ws_0)),
+                quoted_string,
+            ),
+        )),
+        |(_, name, password)| {
+            Statement::AlterUser(AlterUser {
+                name: Some(name),
+                password,
+            })
+        },
+    )(input)
+}
+
+fn alter_table_action(input: &str) -> ParserResult<AlterTableAction> {
+    alt((
+        map(
+            preceded(
+                tuple((kw("ADD"), ws_0, kw("PARTITION"), ws_0)),
+                delimited(
+                    pair(tag("("), ws_0),
+                    partition_definition,
+                    pair(ws_0, tag(")")),
+                ),
+            ),
+            AlterTableAction::AddPartition,
+        ),
+        map(
+            preceded(
+                tuple((kw("DROP"), ws_0, kw("PARTITION"), ws_0)),
+                identifier_str,
+            ),
+            AlterTableAction::DropPartition,
+        ),
+    ))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::expr::Expression;
+    use ast::statement::PartitionDefinition;
+
+    #[test]
+    fn test_alter_table() {
+        assert_eq!(
+            alter("ALTER TABLE foo ADD PARTITION (PARTITION p2 VALUES LESS THAN (20))")
+                .unwrap()
+                .1,
+            Statement::AlterTable(AlterTable {
+                database: None,
+                name: "foo".to_string(),
+                action: AlterTableAction::AddPartition(PartitionDefinition {
+                    name: "p2".to_string(),
+                    upper_bound: Some(Expression::from(20))
+                })
+            })
+        );
+
+        assert_eq!(
+            alter("alter table db.foo drop partition p0").unwrap().1,
+            Statement::AlterTable(AlterTable {
+                database: Some("db".to_string()),
+                name: "foo".to_string(),
+                action: AlterTableAction::DropPartition("p0".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_alter_user() {
+        assert_eq!(
+            alter("ALTER USER 'root'@'localhost' IDENTIFIED BY 'secret'")
+                .unwrap()
+                .1,
+            Statement::AlterUser(AlterUser {
+                name: Some("root".to_string()),
+                password: "secret".to_string()
+            })
+        );
+    }
+}
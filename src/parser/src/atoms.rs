@@ -1,3 +1,4 @@
+use crate::error::SyntaxError;
 use crate::whitespace::ws_0;
 use crate::ParserResult;
 use data::rust_decimal::Decimal;
@@ -8,7 +9,7 @@ use nom::bytes::complete::{
 };
 use nom::character::complete::alphanumeric1;
 use nom::combinator::{cut, map, map_res, not, opt, peek, recognize, value};
-use nom::error::{context, ErrorKind, VerboseError, VerboseErrorKind};
+use nom::error::{context, ErrorKind, ParseError};
 use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::{AsChar, Offset};
 use std::str::FromStr;
@@ -25,32 +26,38 @@ pub fn quoted_string(input: &str) -> ParserResult<String> {
         ))(input)
     }
 
-    alt((
-        // is_not wont return anything for zero length string :(
-        value(String::new(), tag_no_case("\"\"")),
-        value(String::new(), tag_no_case("''")),
-        delimited(
-            tag("\""),
-            escaped_transform(is_not("\"\\"), '\\', trans),
-            cut(context("Missing closing double quote", tag("\""))),
-        ),
-        delimited(
-            tag("'"),
-            escaped_transform(is_not("\'\\"), '\\', trans),
-            cut(context("Missing closing quote", tag("'"))),
-        ),
-    ))(input)
+    context(
+        "string",
+        alt((
+            // is_not wont return anything for zero length string :(
+            value(String::new(), tag_no_case("\"\"")),
+            value(String::new(), tag_no_case("''")),
+            delimited(
+                tag("\""),
+                escaped_transform(is_not("\"\\"), '\\', trans),
+                cut(context("closing double quote", tag("\""))),
+            ),
+            delimited(
+                tag("'"),
+                escaped_transform(is_not("\'\\"), '\\', trans),
+                cut(context("closing quote", tag("'"))),
+            ),
+        )),
+    )(input)
 }
 
 /// Parse an integer
 pub fn integer(input: &str) -> ParserResult<i64> {
-    map_res(
-        recognize(pair(
-            opt(tag("-")),
-            // Take .'s and then fail in result so we don't wrongly parse just the start of a float
-            take_while(|c: char| c.is_dec_digit() || c == '.'),
-        )),
-        |s: &str| s.parse(),
+    context(
+        "integer",
+        map_res(
+            recognize(pair(
+                opt(tag("-")),
+                // Take .'s and then fail in result so we don't wrongly parse just the start of a float
+                take_while(|c: char| c.is_dec_digit() || c == '.'),
+            )),
+            |s: &str| s.parse(),
+        ),
     )(input)
 }
 
@@ -74,20 +81,37 @@ pub fn decimal(input: &str) -> ParserResult<Decimal> {
     )(input)
 }
 
+/// Parse a double, to tell them apart from decimals these are required to have an exponent
+pub fn double(input: &str) -> ParserResult<f64> {
+    map_res(
+        recognize(tuple((
+            opt(tag("-")),
+            take_while1(|c: char| c.is_dec_digit()),
+            opt(pair(tag("."), take_while(|c: char| c.is_dec_digit()))),
+            alt((tag("e"), tag("E"))),
+            opt(alt((tag("+"), tag("-")))),
+            take_while1(|c: char| c.is_dec_digit()),
+        ))),
+        f64::from_str,
+    )(input)
+}
+
 /// Eof parser
 fn eof(input: &str) -> ParserResult<()> {
     if input.is_empty() {
         Ok((input, ()))
     } else {
-        Err(nom::Err::Error(VerboseError {
-            errors: vec![(input, VerboseErrorKind::Nom(ErrorKind::NonEmpty))],
-        }))
+        Err(nom::Err::Error(SyntaxError::from_error_kind(
+            input,
+            ErrorKind::NonEmpty,
+        )))
     }
 }
 
 /// A wrapper around tag_no_case that also ensures that we don't just
 /// take half a word, ie to ensure we don't parse "nulls_removed" as NULL
-/// and then have our parser blow up with some error about "s_removed"
+/// and then have our parser blow up with some error about "s_removed".
+/// On failure the keyword is reported as what was expected at the start of the input.
 pub fn kw(keyword: &'static str) -> impl Fn(&str) -> ParserResult<&str> {
     move |input| {
         terminated(
@@ -97,6 +121,7 @@ pub fn kw(keyword: &'static str) -> impl Fn(&str) -> ParserResult<&str> {
                 eof,
             ))),
         )(input)
+        .map_err(|_| nom::Err::Error(SyntaxError::expected(input, keyword)))
     }
 }
 
@@ -107,41 +132,49 @@ pub fn kw(keyword: &'static str) -> impl Fn(&str) -> ParserResult<&str> {
 /// need to exclude these to allow unambiguous parsing.
 /// Alternatively backticks can be used to quote the identifiers, will lowercase all identifiers
 pub fn identifier_str(input: &str) -> ParserResult<String> {
-    map(
-        alt((
-            recognize(preceded(
-                // These basically need to be the list of valid keywords that can appear
-                // after a table name
-                not(peek(alt((
-                    kw("FROM"),
-                    kw("WHERE"),
-                    kw("ORDER"),
-                    kw("UNION"),
-                    kw("LIMIT"),
-                    kw("GROUP"),
-                    kw("JOIN"),
-                    kw("LEFT"),
-                    kw("RIGHT"),
-                    kw("INNER"),
-                    kw("OUTER"),
-                    kw("FULL"),
-                    kw("ON"),
-                    kw("IS"),
-                )))),
-                pair(
-                    take_while_m_n(1, 1, |c: char| {
-                        c.is_alpha() || c == '_' || c == '$' || c == '@'
-                    }),
-                    take_while(|c: char| c.is_alphanumeric() || c == '_' || c == '$' || c == '@'),
+    context(
+        "identifier",
+        map(
+            alt((
+                recognize(preceded(
+                    // These basically need to be the list of valid keywords that can appear
+                    // after a table name
+                    not(peek(alt((
+                        kw("FROM"),
+                        kw("WHERE"),
+                        kw("ORDER"),
+                        kw("UNION"),
+                        kw("INTERSECT"),
+                        kw("EXCEPT"),
+                        kw("LIMIT"),
+                        kw("GROUP"),
+                        kw("JOIN"),
+                        kw("LEFT"),
+                        kw("RIGHT"),
+                        kw("INNER"),
+                        kw("OUTER"),
+                        kw("FULL"),
+                        kw("ON"),
+                        kw("IS"),
+                        kw("INTO"),
+                    )))),
+                    pair(
+                        take_while_m_n(1, 1, |c: char| {
+                            c.is_alpha() || c == '_' || c == '$' || c == '@'
+                        }),
+                        take_while(|c: char| {
+                            c.is_alphanumeric() || c == '_' || c == '$' || c == '@'
+                        }),
+                    ),
+                )),
+                delimited(
+                    tag("`"),
+                    take_until("`"),
+                    cut(context("closing backtick", tag("`"))),
                 ),
             )),
-            delimited(
-                tag("`"),
-                take_until("`"),
-                cut(context("Missing closing backtick on identifier", tag("`"))),
-            ),
-        )),
-        |s| s.to_lowercase(),
+            |s| s.to_lowercase(),
+        ),
     )(input)
 }
 
@@ -218,7 +251,7 @@ mod tests {
         assert!(quoted_string(r#""My String \"\t \n\a"#)
             .unwrap_err()
             .to_string()
-            .contains("Missing closing double quote"));
+            .contains("closing double quote"));
     }
 
     #[test]
@@ -226,7 +259,7 @@ mod tests {
         assert!(quoted_string(r#"""#)
             .unwrap_err()
             .to_string()
-            .contains("Missing closing double quote"));
+            .contains("closing double quote"));
     }
 
     #[test]
@@ -266,6 +299,13 @@ mod tests {
         decimal("").expect_err("Expected to fail");
     }
 
+    #[test]
+    fn test_double() {
+        assert_eq!(double("1e3").unwrap(), ("", 1000.0));
+        assert_eq!(double("-1.25E-2 ").unwrap(), (" ", -0.0125));
+        assert!(double("1.25").is_err());
+    }
+
     #[test]
     fn test_eof() {
         assert_eq!(eof("").unwrap().1, ());
@@ -1,21 +1,39 @@
-use crate::atoms::{and_recognise, identifier_str, kw, qualified_reference};
+use crate::atoms::{
+    and_recognise, identifier_str, integer, kw, qualified_reference, quoted_string,
+};
+use crate::expression::{expression, sort_order};
 use crate::literals::datatype;
 use crate::select::select;
 use crate::whitespace::ws_0;
 use crate::ParserResult;
-use ast::statement::{CreateDatabase, CreateTable, CreateView, Statement};
-use data::DataType;
+use ast::rel::logical::{ColumnDefault, PartitionMethod};
+use ast::statement::{
+    CreateDatabase, CreateExternalTable, CreateFulltextIndex, CreateFunction, CreateIndex,
+    CreateSink, CreateTable, CreateUser, CreateView, FunctionBody, PartitionBy,
+    PartitionDefinition, SinkFormat, Statement,
+};
+use data::{DataType, SortOrder};
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::combinator::{cut, map};
-use nom::multi::separated_list0;
-use nom::sequence::{pair, preceded, separated_pair, tuple};
+use nom::bytes::complete::{tag, take_while1};
+use nom::combinator::{cut, map, opt, value};
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, tuple};
 
 /// Parses a create statement
 pub fn create(input: &str) -> ParserResult<Statement> {
     preceded(
         kw("CREATE"),
-        cut(alt((create_database, create_table, create_view))),
+        cut(alt((
+            create_database,
+            create_table,
+            create_view,
+            create_fulltext_index,
+            create_index,
+            create_user,
+            create_sink,
+            create_external_table,
+            create_function,
+        ))),
     )(input)
 }
 
@@ -34,28 +52,226 @@ fn create_table(input: &str) -> ParserResult<Statement> {
                 ws_0,
                 qualified_reference,
                 tuple((ws_0, tag("("), ws_0)),
-                separated_list0(tuple((ws_0, tag(","), ws_0)), column_spec),
+                separated_list0(tuple((ws_0, tag(","), ws_0)), table_element),
                 tuple((ws_0, tag(")"))),
+                opt(preceded(ws_0, partition_by)),
             ))),
         ),
-        |(_, (db_name, table_name), _, columns, _)| {
+        |(_, (db_name, table_name), _, elements, _, partition_by)| {
+            let mut columns = vec![];
+            let mut column_defaults = vec![];
+            let mut column_not_null = vec![];
+            let mut primary_key = vec![];
+            let mut fulltext_indexes = vec![];
+            for element in elements {
+                match element {
+                    TableElement::Column(alias, datatype, default, not_null) => {
+                        columns.push((alias, datatype));
+                        column_defaults.push(default);
+                        column_not_null.push(not_null);
+                    }
+                    TableElement::PrimaryKey(pk) => primary_key = pk,
+                    TableElement::FulltextIndex(name, column) => {
+                        fulltext_indexes.push((name.unwrap_or_else(|| column.clone()), column))
+                    }
+                }
+            }
             Statement::CreateTable(CreateTable {
                 database: db_name,
                 name: table_name,
                 columns,
+                column_defaults,
+                column_not_null,
+                primary_key,
+                partition_by,
+                fulltext_indexes,
             })
         },
     )(input)
 }
 
-fn column_spec(input: &str) -> ParserResult<(String, DataType)> {
-    separated_pair(identifier_str, ws_0, datatype)(input)
+/// The parts making up the body of a create table statement
+enum TableElement {
+    // name, type, default and whether it's NOT NULL
+    Column(String, DataType, Option<ColumnDefault>, bool),
+    PrimaryKey(Vec<(String, SortOrder)>),
+    FulltextIndex(Option<String>, String),
 }
 
+fn table_element(input: &str) -> ParserResult<TableElement> {
+    alt((
+        map(primary_key, TableElement::PrimaryKey),
+        map(fulltext_index, |(name, column)| {
+            TableElement::FulltextIndex(name, column)
+        }),
+        map(column_spec, |(alias, datatype, default, not_null)| {
+            TableElement::Column(alias, datatype, default, not_null)
+        }),
+    ))(input)
+}
+
+/// PRIMARY KEY (col1 [ASC|DESC], col2 [ASC|DESC] ...)
+fn primary_key(input: &str) -> ParserResult<Vec<(String, SortOrder)>> {
+    preceded(
+        tuple((kw("PRIMARY"), ws_0, kw("KEY"), ws_0)),
+        cut(delimited(
+            pair(tag("("), ws_0),
+            separated_list1(
+                tuple((ws_0, tag(","), ws_0)),
+                map(
+                    pair(identifier_str, opt(preceded(ws_0, sort_order))),
+                    |(column, sort_order)| (column, sort_order.unwrap_or(SortOrder::Asc)),
+                ),
+            ),
+            pair(ws_0, tag(")")),
+        )),
+    )(input)
+}
+
+/// FULLTEXT [INDEX|KEY] [name] (col), the index name defaults to the column name
+fn fulltext_index(input: &str) -> ParserResult<(Option<String>, String)> {
+    map(
+        tuple((
+            kw("FULLTEXT"),
+            opt(preceded(ws_0, alt((kw("INDEX"), kw("KEY"))))),
+            opt(preceded(ws_0, identifier_str)),
+            parenthesized_identifier,
+        )),
+        |(_, _, name, column)| (name, column),
+    )(input)
+}
+
+/// PARTITION BY RANGE (col) (PARTITION p0 VALUES LESS THAN (10), ...)
+/// or PARTITION BY HASH (col) PARTITIONS n
+fn partition_by(input: &str) -> ParserResult<PartitionBy> {
+    preceded(
+        tuple((kw("PARTITION"), ws_0, kw("BY"), ws_0)),
+        cut(alt((
+            map(
+                tuple((
+                    kw("RANGE"),
+                    parenthesized_identifier,
+                    delimited(
+                        tuple((ws_0, tag("("), ws_0)),
+                        separated_list1(tuple((ws_0, tag(","), ws_0)), partition_definition),
+                        tuple((ws_0, tag(")"))),
+                    ),
+                )),
+                |(_, column, partitions)| PartitionBy {
+                    method: PartitionMethod::Range,
+                    column,
+                    partitions,
+                },
+            ),
+            map(
+                tuple((
+                    kw("HASH"),
+                    parenthesized_identifier,
+                    preceded(tuple((ws_0, kw("PARTITIONS"), ws_0)), integer),
+                )),
+                |(_, column, count)| PartitionBy {
+                    method: PartitionMethod::Hash,
+                    column,
+                    partitions: (0..count)
+                        .map(|idx| PartitionDefinition {
+                            name: format!("p{}", idx),
+                            upper_bound: None,
+                        })
+                        .collect(),
+                },
+            ),
+        ))),
+    )(input)
+}
+
+fn parenthesized_identifier(input: &str) -> ParserResult<String> {
+    delimited(
+        tuple((ws_0, tag("("), ws_0)),
+        identifier_str,
+        tuple((ws_0, tag(")"))),
+    )(input)
+}
+
+/// PARTITION name VALUES LESS THAN (expr) or PARTITION name VALUES LESS THAN MAXVALUE
+pub(crate) fn partition_definition(input: &str) -> ParserResult<PartitionDefinition> {
+    map(
+        tuple((
+            kw("PARTITION"),
+            ws_0,
+            identifier_str,
+            tuple((ws_0, kw("VALUES"), ws_0, kw("LESS"), ws_0, kw("THAN"), ws_0)),
+            alt((
+                map(kw("MAXVALUE"), |_| None),
+                delimited(
+                    pair(tag("("), ws_0),
+                    alt((map(kw("MAXVALUE"), |_| None), map(expression, Some))),
+                    pair(ws_0, tag(")")),
+                ),
+            )),
+        )),
+        |(_, _, name, _, upper_bound)| PartitionDefinition { name, upper_bound },
+    )(input)
+}
+
+/// <name> <type> [[NOT] NULL] [<default>] [[NOT] NULL]
+fn column_spec(input: &str) -> ParserResult<(String, DataType, Option<ColumnDefault>, bool)> {
+    map(
+        tuple((
+            identifier_str,
+            ws_0,
+            datatype,
+            opt(preceded(ws_0, nullability)),
+            opt(preceded(ws_0, column_default)),
+            opt(preceded(ws_0, nullability)),
+        )),
+        |(alias, _, datatype, not_null1, default, not_null2)| {
+            let not_null = not_null1.or(not_null2).unwrap_or(false);
+            (alias, datatype, default, not_null)
+        },
+    )(input)
+}
+
+/// Parses "NOT NULL" or "NULL", returning true for NOT NULL
+fn nullability(input: &str) -> ParserResult<bool> {
+    alt((
+        value(true, tuple((kw("NOT"), ws_0, kw("NULL")))),
+        value(false, kw("NULL")),
+    ))(input)
+}
+
+/// Parses either "DEFAULT <expr>" or a generated column ie "[GENERATED ALWAYS] AS (<expr>) [STORED]"
+fn column_default(input: &str) -> ParserResult<ColumnDefault> {
+    alt((
+        map(
+            preceded(pair(kw("DEFAULT"), ws_0), cut(expression)),
+            ColumnDefault::Default,
+        ),
+        map(
+            delimited(
+                tuple((
+                    opt(tuple((kw("GENERATED"), ws_0, kw("ALWAYS"), ws_0))),
+                    kw("AS"),
+                    ws_0,
+                )),
+                cut(delimited(
+                    pair(tag("("), ws_0),
+                    expression,
+                    pair(ws_0, tag(")")),
+                )),
+                opt(pair(ws_0, kw("STORED"))),
+            ),
+            ColumnDefault::Generated,
+        ),
+    ))(input)
+}
+
+/// CREATE [MATERIALIZED] VIEW [db.]name AS query
 fn create_view(input: &str) -> ParserResult<Statement> {
     map(
-        preceded(
-            pair(ws_0, kw("VIEW")),
+        tuple((
+            ws_0,
+            opt(pair(kw("MATERIALIZED"), ws_0)),
+            kw("VIEW"),
             cut(tuple((
                 ws_0,
                 qualified_reference,
@@ -64,11 +280,12 @@ fn create_view(input: &str) -> ParserResult<Statement> {
                 ws_0,
                 and_recognise(select),
             ))),
-        ),
-        |(_, (db_name, table_name), _, _, _, (query, query_sql))| {
+        )),
+        |(_, materialized, _, (_, (db_name, table_name), _, _, _, (query, query_sql)))| {
             Statement::CreateView(CreateView {
                 database: db_name,
                 name: table_name,
+                materialized: materialized.is_some(),
                 sql: query_sql.to_string(),
                 query,
             })
@@ -76,11 +293,273 @@ fn create_view(input: &str) -> ParserResult<Statement> {
     )(input)
 }
 
+/// CREATE FULLTEXT INDEX name ON [db.]table (col)
+fn create_fulltext_index(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            tuple((ws_0, kw("FULLTEXT"), ws_0, kw("INDEX"))),
+            cut(tuple((
+                ws_0,
+                identifier_str,
+                ws_0,
+                kw("ON"),
+                ws_0,
+                qualified_reference,
+                parenthesized_identifier,
+            ))),
+        ),
+        |(_, name, _, _, _, (database, table), column)| {
+            Statement::CreateFulltextIndex(CreateFulltextIndex {
+                database,
+                table,
+                name,
+                column,
+            })
+        },
+    )(input)
+}
+
+/// CREATE INDEX name ON [db.]table (col, ...)
+fn create_index(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            pair(ws_0, kw("INDEX")),
+            cut(tuple((
+                ws_0,
+                identifier_str,
+                ws_0,
+                kw("ON"),
+                ws_0,
+                qualified_reference,
+                delimited(
+                    tuple((ws_0, tag("("), ws_0)),
+                    separated_list1(tuple((ws_0, tag(","), ws_0)), identifier_str),
+                    tuple((ws_0, tag(")"))),
+                ),
+            ))),
+        ),
+        |(_, name, _, _, _, (database, table), columns)| {
+            Statement::CreateIndex(CreateIndex {
+                database,
+                table,
+                name,
+                columns,
+            })
+        },
+    )(input)
+}
+
+fn create_user(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            pair(ws_0, kw("USER")),
+            cut(pair(
+                preceded(ws_0, user_name),
+                opt(preceded(
+                    tuple((ws_0, kw("IDENTIFIED"), ws_0, kw("BY"), ws_0)),
+                    quoted_string,
+                )),
+            )),
+        ),
+        |(name, password)| Statement::CreateUser(CreateUser { name, password }),
+    )(input)
+}
+
+/// CREATE SINK name FROM t INTO FILE 'path' [FORMAT JSON|CSV]
+fn create_sink(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            pair(ws_0, kw("SINK")),
+            cut(tuple((
+                preceded(ws_0, qualified_reference),
+                preceded(tuple((ws_0, kw("FROM"), ws_0)), qualified_reference),
+                preceded(
+                    tuple((ws_0, kw("INTO"), ws_0, kw("FILE"), ws_0)),
+                    quoted_string,
+                ),
+                opt(preceded(
+                    tuple((ws_0, kw("FORMAT"), ws_0)),
+                    alt((
+                        value(SinkFormat::Json, kw("JSON")),
+                        value(SinkFormat::Csv, kw("CSV")),
+                    )),
+                )),
+            ))),
+        ),
+        |((database, name), (source_database, source), path, format)| {
+            Statement::CreateSink(CreateSink {
+                database,
+                name,
+                source_database,
+                source,
+                path,
+                format: format.unwrap_or(SinkFormat::Json),
+            })
+        },
+    )(input)
+}
+
+/// CREATE EXTERNAL TABLE [db.]t (col type, ...) [PARTITIONED BY (col type, ...)] LOCATION 'dir'
+/// [FORMAT CSV] [(HEADER, DELIMITER 'x')]
+fn create_external_table(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            tuple((ws_0, kw("EXTERNAL"), ws_0, kw("TABLE"))),
+            cut(tuple((
+                preceded(ws_0, qualified_reference),
+                preceded(ws_0, external_columns),
+                opt(preceded(
+                    tuple((ws_0, kw("PARTITIONED"), ws_0, kw("BY"), ws_0)),
+                    external_columns,
+                )),
+                preceded(tuple((ws_0, kw("LOCATION"), ws_0)), quoted_string),
+                opt(tuple((ws_0, kw("FORMAT"), ws_0, kw("CSV")))),
+                opt(delimited(
+                    tuple((ws_0, tag("("), ws_0)),
+                    separated_list0(tuple((ws_0, tag(","), ws_0)), external_option),
+                    tuple((ws_0, tag(")"))),
+                )),
+            ))),
+        ),
+        |((database, name), columns, partition_columns, location, _, options)| {
+            let mut delimiter = b',';
+            let mut header = false;
+            for option in options.unwrap_or_default() {
+                match option {
+                    ExternalOption::Header => header = true,
+                    ExternalOption::Delimiter(d) => delimiter = d,
+                }
+            }
+            Statement::CreateExternalTable(CreateExternalTable {
+                database,
+                name,
+                columns,
+                partition_columns: partition_columns.unwrap_or_default(),
+                location,
+                delimiter,
+                header,
+            })
+        },
+    )(input)
+}
+
+fn external_columns(input: &str) -> ParserResult<Vec<(String, DataType)>> {
+    delimited(
+        pair(tag("("), ws_0),
+        separated_list1(
+            tuple((ws_0, tag(","), ws_0)),
+            pair(identifier_str, preceded(ws_0, datatype)),
+        ),
+        pair(ws_0, tag(")")),
+    )(input)
+}
+
+/// CREATE FUNCTION name(arg type, ...) RETURNS type [LANGUAGE SQL] AS 'expression'
+/// CREATE FUNCTION name(arg type, ...) RETURNS type LANGUAGE WASM AS module
+fn create_function(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            pair(ws_0, kw("FUNCTION")),
+            cut(tuple((
+                preceded(ws_0, identifier_str),
+                delimited(
+                    tuple((ws_0, tag("("), ws_0)),
+                    separated_list0(
+                        tuple((ws_0, tag(","), ws_0)),
+                        pair(identifier_str, preceded(ws_0, datatype)),
+                    ),
+                    pair(ws_0, tag(")")),
+                ),
+                preceded(tuple((ws_0, kw("RETURNS"), ws_0)), datatype),
+                alt((
+                    map(
+                        preceded(
+                            tuple((ws_0, kw("LANGUAGE"), ws_0, kw("WASM"), ws_0, kw("AS"), ws_0)),
+                            expression,
+                        ),
+                        FunctionBody::Wasm,
+                    ),
+                    map(
+                        preceded(
+                            tuple((
+                                opt(tuple((ws_0, kw("LANGUAGE"), ws_0, kw("SQL")))),
+                                ws_0,
+                                kw("AS"),
+                                ws_0,
+                            )),
+                            quoted_string,
+                        ),
+                        FunctionBody::Sql,
+                    ),
+                )),
+            ))),
+        ),
+        |(name, args, returns, body)| {
+            Statement::CreateFunction(CreateFunction {
+                name,
+                args,
+                returns,
+                body,
+            })
+        },
+    )(input)
+}
+
+/// The options that can be given for the csv files of an external table
+#[derive(Clone)]
+enum ExternalOption {
+    Header,
+    Delimiter(u8),
+}
+
+fn external_option(input: &str) -> ParserResult<ExternalOption> {
+    alt((
+        value(ExternalOption::Header, kw("HEADER")),
+        map(
+            preceded(pair(kw("DELIMITER"), ws_0), quoted_string),
+            |delimiter| {
+                ExternalOption::Delimiter(delimiter.as_bytes().first().copied().unwrap_or(b','))
+            },
+        ),
+    ))(input)
+}
+
+/// Parses a user name, we accept mysql's user@host syntax but don't do anything with the
+/// host part.
+pub(crate) fn user_name(input: &str) -> ParserResult<String> {
+    map(
+        pair(
+            // Not identifier_str as that allows @'s (for user variables) and would swallow the
+            // host
+            alt((
+                quoted_string,
+                map(
+                    take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '$'),
+                    str::to_lowercase,
+                ),
+                identifier_str,
+            )),
+            opt(preceded(
+                tuple((ws_0, tag("@"), ws_0)),
+                alt((
+                    quoted_string,
+                    map(
+                        take_while1(|c: char| c.is_alphanumeric() || "_$.%-".contains(c)),
+                        str::to_string,
+                    ),
+                )),
+            )),
+        ),
+        |(name, _host)| name,
+    )(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ast::expr::{Expression, NamedExpression};
+    use ast::expr::{ColumnReference, Expression, FunctionCall, NamedExpression};
     use ast::rel::logical::{LogicalOperator, Project};
+    use data::Datum;
 
     #[test]
     fn test_create_database() {
@@ -104,11 +583,355 @@ mod tests {
                 columns: vec![
                     ("c1".to_string(), DataType::Integer),
                     ("c2".to_string(), DataType::Boolean)
+                ],
+                column_defaults: vec![None, None],
+                column_not_null: vec![false, false],
+                primary_key: vec![],
+                partition_by: None,
+                fulltext_indexes: vec![]
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_table_column_defaults() {
+        assert_eq!(
+            create("Create table foo (c1 INT DEFAULT 1, c2 INT AS (c1 + 1), c3 TIMESTAMP DEFAULT CURRENT_TIMESTAMP)")
+                .unwrap()
+                .1,
+            Statement::CreateTable(CreateTable {
+                database: None,
+                name: "foo".to_string(),
+                columns: vec![
+                    ("c1".to_string(), DataType::Integer),
+                    ("c2".to_string(), DataType::Integer),
+                    ("c3".to_string(), DataType::Timestamp)
+                ],
+                column_defaults: vec![
+                    Some(ColumnDefault::Default(Expression::from(1))),
+                    Some(ColumnDefault::Generated(Expression::FunctionCall(FunctionCall {
+                        function_name: "+".to_string(),
+                        args: vec![
+                            Expression::ColumnReference(ColumnReference {
+                                qualifier: None,
+                                alias: "c1".to_string(),
+                                star: false
+                            }),
+                            Expression::from(1)
+                        ]
+                    }))),
+                    Some(ColumnDefault::Default(Expression::FunctionCall(FunctionCall {
+                        function_name: "current_timestamp".to_string(),
+                        args: vec![]
+                    })))
+                ],
+                column_not_null: vec![false, false, false],
+                primary_key: vec![],
+                partition_by: None,
+                fulltext_indexes: vec![]
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_table_not_null() {
+        assert_eq!(
+            create("Create table foo (c1 INT NOT NULL DEFAULT 0, c2 INT NULL, c3 INT DEFAULT 1 NOT NULL)")
+                .unwrap()
+                .1,
+            Statement::CreateTable(CreateTable {
+                database: None,
+                name: "foo".to_string(),
+                columns: vec![
+                    ("c1".to_string(), DataType::Integer),
+                    ("c2".to_string(), DataType::Integer),
+                    ("c3".to_string(), DataType::Integer)
+                ],
+                column_defaults: vec![
+                    Some(ColumnDefault::Default(Expression::from(0))),
+                    None,
+                    Some(ColumnDefault::Default(Expression::from(1)))
+                ],
+                column_not_null: vec![true, false, true],
+                primary_key: vec![],
+                partition_by: None,
+                fulltext_indexes: vec![]
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_table_primary_key() {
+        assert_eq!(
+            create("Create table foo (c1 INT, c2 INT, PRIMARY KEY (c1, c2 DESC))")
+                .unwrap()
+                .1,
+            Statement::CreateTable(CreateTable {
+                database: None,
+                name: "foo".to_string(),
+                columns: vec![
+                    ("c1".to_string(), DataType::Integer),
+                    ("c2".to_string(), DataType::Integer)
+                ],
+                column_defaults: vec![None, None],
+                column_not_null: vec![false, false],
+                primary_key: vec![
+                    ("c1".to_string(), SortOrder::Asc),
+                    ("c2".to_string(), SortOrder::Desc)
+                ],
+                partition_by: None,
+                fulltext_indexes: vec![]
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_table_partition_by() {
+        let create_table = |partition_by| {
+            Statement::CreateTable(CreateTable {
+                database: None,
+                name: "foo".to_string(),
+                columns: vec![("c1".to_string(), DataType::Integer)],
+                column_defaults: vec![None],
+                column_not_null: vec![false],
+                primary_key: vec![],
+                partition_by: Some(partition_by),
+                fulltext_indexes: vec![],
+            })
+        };
+
+        assert_eq!(
+            create(
+                "Create table foo (c1 INT) PARTITION BY RANGE (c1) (
+                  PARTITION p0 VALUES LESS THAN (10),
+                  PARTITION p1 VALUES LESS THAN MAXVALUE
+                )"
+            )
+            .unwrap()
+            .1,
+            create_table(PartitionBy {
+                method: PartitionMethod::Range,
+                column: "c1".to_string(),
+                partitions: vec![
+                    PartitionDefinition {
+                        name: "p0".to_string(),
+                        upper_bound: Some(Expression::from(10))
+                    },
+                    PartitionDefinition {
+                        name: "p1".to_string(),
+                        upper_bound: None
+                    }
+                ]
+            })
+        );
+
+        assert_eq!(
+            create("Create table foo (c1 INT) PARTITION BY HASH (c1) PARTITIONS 2")
+                .unwrap()
+                .1,
+            create_table(PartitionBy {
+                method: PartitionMethod::Hash,
+                column: "c1".to_string(),
+                partitions: vec![
+                    PartitionDefinition {
+                        name: "p0".to_string(),
+                        upper_bound: None
+                    },
+                    PartitionDefinition {
+                        name: "p1".to_string(),
+                        upper_bound: None
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_table_fulltext() {
+        assert_eq!(
+            create("Create table foo (c1 INT, c2 TEXT, FULLTEXT (c2), FULLTEXT INDEX idx (c2))")
+                .unwrap()
+                .1,
+            Statement::CreateTable(CreateTable {
+                database: None,
+                name: "foo".to_string(),
+                columns: vec![
+                    ("c1".to_string(), DataType::Integer),
+                    ("c2".to_string(), DataType::Text)
+                ],
+                column_defaults: vec![None, None],
+                column_not_null: vec![false, false],
+                primary_key: vec![],
+                partition_by: None,
+                fulltext_indexes: vec![
+                    ("c2".to_string(), "c2".to_string()),
+                    ("idx".to_string(), "c2".to_string())
                 ]
             })
         );
     }
 
+    #[test]
+    fn test_create_fulltext_index() {
+        assert_eq!(
+            create("Create fulltext index idx on foo.bar (c2)")
+                .unwrap()
+                .1,
+            Statement::CreateFulltextIndex(CreateFulltextIndex {
+                database: Some("foo".to_string()),
+                table: "bar".to_string(),
+                name: "idx".to_string(),
+                column: "c2".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_index() {
+        assert_eq!(
+            create("Create index idx on bar (c1, c2)").unwrap().1,
+            Statement::CreateIndex(CreateIndex {
+                database: None,
+                table: "bar".to_string(),
+                name: "idx".to_string(),
+                columns: vec!["c1".to_string(), "c2".to_string()]
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_sink() {
+        assert_eq!(
+            create("Create sink orders_sink from foo.orders into file '/tmp/orders.json'")
+                .unwrap()
+                .1,
+            Statement::CreateSink(CreateSink {
+                database: None,
+                name: "orders_sink".to_string(),
+                source_database: Some("foo".to_string()),
+                source: "orders".to_string(),
+                path: "/tmp/orders.json".to_string(),
+                format: SinkFormat::Json
+            })
+        );
+
+        assert_eq!(
+            create("CREATE SINK s FROM orders INTO FILE 'orders.csv' FORMAT CSV")
+                .unwrap()
+                .1,
+            Statement::CreateSink(CreateSink {
+                database: None,
+                name: "s".to_string(),
+                source_database: None,
+                source: "orders".to_string(),
+                path: "orders.csv".to_string(),
+                format: SinkFormat::Csv
+            })
+        );
+
+        assert!(create("CREATE SINK s FROM orders INTO KAFKA 'orders'").is_err());
+    }
+
+    #[test]
+    fn test_create_function() {
+        assert_eq!(
+            create("CREATE FUNCTION add_one(x BIGINT) RETURNS BIGINT LANGUAGE WASM AS x'0061'")
+                .unwrap()
+                .1,
+            Statement::CreateFunction(CreateFunction {
+                name: "add_one".to_string(),
+                args: vec![("x".to_string(), DataType::BigInt)],
+                returns: DataType::BigInt,
+                body: FunctionBody::Wasm(Expression::Constant(
+                    Datum::from(vec![0x00, 0x61]),
+                    DataType::ByteA
+                ))
+            })
+        );
+
+        assert_eq!(
+            create("create function answer() returns int language wasm as null")
+                .unwrap()
+                .1,
+            Statement::CreateFunction(CreateFunction {
+                name: "answer".to_string(),
+                args: vec![],
+                returns: DataType::Integer,
+                body: FunctionBody::Wasm(Expression::Constant(Datum::Null, DataType::Null))
+            })
+        );
+
+        assert_eq!(
+            create("CREATE FUNCTION f(a INT, b INT) RETURNS INT AS 'a * 2 + b'")
+                .unwrap()
+                .1,
+            Statement::CreateFunction(CreateFunction {
+                name: "f".to_string(),
+                args: vec![
+                    ("a".to_string(), DataType::Integer),
+                    ("b".to_string(), DataType::Integer)
+                ],
+                returns: DataType::Integer,
+                body: FunctionBody::Sql("a * 2 + b".to_string())
+            })
+        );
+
+        assert_eq!(
+            create("CREATE FUNCTION f() RETURNS TEXT LANGUAGE SQL AS 'upper(\"a\")'")
+                .unwrap()
+                .1,
+            Statement::CreateFunction(CreateFunction {
+                name: "f".to_string(),
+                args: vec![],
+                returns: DataType::Text,
+                body: FunctionBody::Sql("upper(\"a\")".to_string())
+            })
+        );
+
+        assert!(create("CREATE FUNCTION f(x INT) RETURNS INT LANGUAGE PYTHON AS 'x'").is_err());
+    }
+
+    #[test]
+    fn test_create_external_table() {
+        assert_eq!(
+            create("CREATE EXTERNAL TABLE foo.bar (a INT, b TEXT) LOCATION '/data/bar'")
+                .unwrap()
+                .1,
+            Statement::CreateExternalTable(CreateExternalTable {
+                database: Some("foo".to_string()),
+                name: "bar".to_string(),
+                columns: vec![
+                    ("a".to_string(), DataType::Integer),
+                    ("b".to_string(), DataType::Text)
+                ],
+                partition_columns: vec![],
+                location: "/data/bar".to_string(),
+                delimiter: b',',
+                header: false
+            })
+        );
+
+        assert_eq!(
+            create(
+                "create external table bar (a INT) partitioned by (dt DATE) location 'bar' \
+                format csv (header, delimiter '|')"
+            )
+            .unwrap()
+            .1,
+            Statement::CreateExternalTable(CreateExternalTable {
+                database: None,
+                name: "bar".to_string(),
+                columns: vec![("a".to_string(), DataType::Integer)],
+                partition_columns: vec![("dt".to_string(), DataType::Date)],
+                location: "bar".to_string(),
+                delimiter: b'|',
+                header: true
+            })
+        );
+
+        assert!(create("CREATE EXTERNAL TABLE bar (a INT)").is_err());
+    }
+
     #[test]
     fn test_create_view() {
         assert_eq!(
@@ -116,6 +939,7 @@ mod tests {
             Statement::CreateView(CreateView {
                 database: Some("foo".to_string()),
                 name: "bar".to_string(),
+                materialized: false,
                 sql: "select 1".to_string(),
                 query: LogicalOperator::Project(Project {
                     distinct: false,
@@ -128,4 +952,48 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_create_materialized_view() {
+        assert_eq!(
+            create("Create materialized view bar as select 1")
+                .unwrap()
+                .1,
+            Statement::CreateView(CreateView {
+                database: None,
+                name: "bar".to_string(),
+                materialized: true,
+                sql: "select 1".to_string(),
+                query: LogicalOperator::Project(Project {
+                    distinct: false,
+                    expressions: vec![NamedExpression {
+                        alias: None,
+                        expression: Expression::from(1)
+                    }],
+                    source: Box::new(Default::default())
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_user() {
+        assert_eq!(
+            create("Create user bob").unwrap().1,
+            Statement::CreateUser(CreateUser {
+                name: "bob".to_string(),
+                password: None
+            })
+        );
+
+        assert_eq!(
+            create("CREATE USER 'bob'@'%' IDENTIFIED BY 'secret'")
+                .unwrap()
+                .1,
+            Statement::CreateUser(CreateUser {
+                name: "bob".to_string(),
+                password: Some("secret".to_string())
+            })
+        );
+    }
 }
@@ -1,31 +1,47 @@
-use crate::atoms::{kw, qualified_reference};
-use crate::select::{limit_clause, where_clause};
+use crate::atoms::{identifier_str, kw, qualified_reference};
+use crate::select::{from_items, limit_clause, where_clause};
 use crate::whitespace::ws_0;
 use crate::ParserResult;
-use ast::rel::logical::{Filter, Limit, LogicalOperator, TableAlias, TableInsert, TableReference};
-use nom::combinator::{cut, map, opt};
+use ast::expr::{ColumnReference, Expression, NamedExpression};
+use ast::rel::logical::{
+    Filter, Join, JoinType, Limit, LogicalOperator, Project, TableAlias, TableInsert,
+    TableReference,
+};
+use nom::branch::alt;
+use nom::combinator::{cut, map, map_opt, opt};
 use nom::sequence::{pair, preceded, tuple};
 
 /// Parses a delete statement
 /// A delete is really like a subset of select (from below the from), supporting
 /// only where and limit.
+/// Deletes driven by joins against other tables are also supported, either as
+/// DELETE FROM t1 USING t2 WHERE ... or DELETE t1 FROM t1 JOIN t2 ON ... WHERE ...
 pub fn delete(input: &str) -> ParserResult<LogicalOperator> {
+    preceded(
+        kw("DELETE"),
+        cut(preceded(ws_0, alt((delete_from, multi_table_delete)))),
+    )(input)
+}
+
+/// DELETE FROM table [USING from_items] [WHERE ...] [LIMIT ...]
+fn delete_from(input: &str) -> ParserResult<LogicalOperator> {
     map(
         preceded(
-            kw("DELETE"),
+            kw("FROM"),
             pair(
-                cut(preceded(tuple((ws_0, kw("FROM"), ws_0)), table_reference)),
+                cut(preceded(ws_0, table_reference)),
                 cut(tuple((
+                    opt(preceded(tuple((ws_0, kw("USING"), ws_0)), cut(from_items))),
                     opt(preceded(ws_0, where_clause)),
                     opt(preceded(ws_0, limit_clause)),
                 ))),
             ),
         ),
-        |(table_reference, (where_option, limit_option))| {
+        |(table_reference, (using_option, where_option, limit_option))| {
             // For the from portion of the delete we should wrap the table in an alias to support
             // qualified references in the where clauses
             let table_alias =
-                if let LogicalOperator::TableReference(TableReference { database: _, table }) =
+                if let LogicalOperator::TableReference(TableReference { table, .. }) =
                     &table_reference
                 {
                     table
@@ -38,6 +54,15 @@ pub fn delete(input: &str) -> ParserResult<LogicalOperator> {
                 source: Box::new(table_reference.clone()),
             });
 
+            if let Some(using) = &using_option {
+                query = LogicalOperator::Join(Join {
+                    left: Box::new(query),
+                    right: Box::new(using.clone()),
+                    on: Expression::from(true),
+                    join_type: JoinType::Inner,
+                });
+            }
+
             if let Some(predicate) = where_option {
                 query = LogicalOperator::Filter(Filter {
                     predicate,
@@ -53,18 +78,111 @@ pub fn delete(input: &str) -> ParserResult<LogicalOperator> {
                 });
             }
 
-            LogicalOperator::TableInsert(TableInsert {
-                table: Box::new(table_reference),
-                source: Box::new(LogicalOperator::NegateFreq(Box::from(query))),
-            })
+            if using_option.is_some() {
+                let alias = table_alias.to_string();
+                matching_rows_delete(table_reference, alias, query)
+            } else {
+                LogicalOperator::TableInsert(TableInsert {
+                    table: Box::new(table_reference),
+                    columns: vec![],
+                    source: Box::new(LogicalOperator::NegateFreq(Box::from(query))),
+                    delete_matching: false,
+                    bulk_load: None,
+                    upsert: None,
+                })
+            }
+        },
+    )(input)
+}
+
+/// DELETE alias FROM from_items [WHERE ...], the alias must refer to one of the tables in
+/// the from items.
+fn multi_table_delete(input: &str) -> ParserResult<LogicalOperator> {
+    map_opt(
+        tuple((
+            identifier_str,
+            preceded(tuple((ws_0, kw("FROM"), ws_0)), from_items),
+            opt(preceded(ws_0, where_clause)),
+        )),
+        |(alias, from, where_option)| {
+            let table_reference = find_aliased_table(&from, &alias)?.clone();
+
+            let query = if let Some(predicate) = where_option {
+                LogicalOperator::Filter(Filter {
+                    predicate,
+                    source: Box::new(from),
+                })
+            } else {
+                from
+            };
+
+            Some(matching_rows_delete(table_reference, alias, query))
         },
     )(input)
 }
 
+/// Builds the delete for a delete driven by a join, the rows of the target table (referred
+/// to by alias) output by the query have all their copies removed from the table.
+fn matching_rows_delete(
+    table_reference: LogicalOperator,
+    alias: String,
+    query: LogicalOperator,
+) -> LogicalOperator {
+    LogicalOperator::TableInsert(TableInsert {
+        table: Box::new(table_reference),
+        columns: vec![],
+        source: Box::new(LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: vec![NamedExpression {
+                alias: None,
+                expression: Expression::ColumnReference(ColumnReference {
+                    qualifier: Some(alias),
+                    alias: "*".to_string(),
+                    star: true,
+                }),
+            }],
+            source: Box::new(query),
+        })),
+        delete_matching: true,
+        bulk_load: None,
+        upsert: None,
+    })
+}
+
+/// Walks the joins in a from clause looking for the table with the given alias.
+fn find_aliased_table<'a>(
+    operator: &'a LogicalOperator,
+    alias: &str,
+) -> Option<&'a LogicalOperator> {
+    match operator {
+        LogicalOperator::TableAlias(table_alias) if table_alias.alias == alias => {
+            // Tables in from clauses are always aliased with their name, so an explicitly
+            // aliased table will be wrapped twice.
+            let mut source = table_alias.source.as_ref();
+            while let LogicalOperator::TableAlias(inner) = source {
+                source = inner.source.as_ref();
+            }
+            if let LogicalOperator::TableReference(_) = source {
+                Some(source)
+            } else {
+                None
+            }
+        }
+        LogicalOperator::Join(join) => {
+            find_aliased_table(&join.left, alias).or_else(|| find_aliased_table(&join.right, alias))
+        }
+        _ => None,
+    }
+}
+
 /// Parse as a table_reference
 fn table_reference(input: &str) -> ParserResult<LogicalOperator> {
     map(qualified_reference, |(database, table)| {
-        LogicalOperator::TableReference(TableReference { database, table })
+        LogicalOperator::TableReference(TableReference {
+            database,
+            table,
+            as_of: None,
+        })
     })(input)
 }
 
@@ -77,12 +195,14 @@ mod tests {
         let table_ref = LogicalOperator::TableReference(TableReference {
             database: None,
             table: "foo".to_string(),
+            as_of: None,
         });
 
         assert_eq!(
             delete("delete from foo limit 1").unwrap().1,
             LogicalOperator::TableInsert(TableInsert {
                 table: Box::new(table_ref.clone()),
+                columns: vec![],
                 source: Box::new(LogicalOperator::NegateFreq(Box::new(
                     LogicalOperator::Limit(Limit {
                         offset: 0,
@@ -92,8 +212,93 @@ mod tests {
                             source: Box::new(table_ref)
                         }))
                     })
-                )))
+                ))),
+                delete_matching: false,
+                bulk_load: None,
+                upsert: None
             })
         );
     }
+
+    fn aliased_table(table: &str) -> LogicalOperator {
+        LogicalOperator::TableAlias(TableAlias {
+            alias: table.to_string(),
+            source: Box::new(LogicalOperator::TableReference(TableReference {
+                database: None,
+                table: table.to_string(),
+                as_of: None,
+            })),
+        })
+    }
+
+    fn expected_delete(table: &str, alias: &str, query: LogicalOperator) -> LogicalOperator {
+        LogicalOperator::TableInsert(TableInsert {
+            table: Box::new(LogicalOperator::TableReference(TableReference {
+                database: None,
+                table: table.to_string(),
+                as_of: None,
+            })),
+            columns: vec![],
+            source: Box::new(LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    alias: None,
+                    expression: Expression::ColumnReference(ColumnReference {
+                        qualifier: Some(alias.to_string()),
+                        alias: "*".to_string(),
+                        star: true,
+                    }),
+                }],
+                source: Box::new(query),
+            })),
+            delete_matching: true,
+            bulk_load: None,
+            upsert: None,
+        })
+    }
+
+    #[test]
+    fn test_delete_using() {
+        assert_eq!(
+            delete("delete from foo using bar where true").unwrap().1,
+            expected_delete(
+                "foo",
+                "foo",
+                LogicalOperator::Filter(Filter {
+                    predicate: Expression::from(true),
+                    source: Box::new(LogicalOperator::Join(Join {
+                        left: Box::new(aliased_table("foo")),
+                        right: Box::new(aliased_table("bar")),
+                        on: Expression::from(true),
+                        join_type: JoinType::Inner,
+                    }))
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn test_multi_table_delete() {
+        assert_eq!(
+            delete("delete f from foo f left join bar on true")
+                .unwrap()
+                .1,
+            expected_delete(
+                "foo",
+                "f",
+                LogicalOperator::Join(Join {
+                    left: Box::new(LogicalOperator::TableAlias(TableAlias {
+                        alias: "f".to_string(),
+                        source: Box::new(aliased_table("foo")),
+                    })),
+                    right: Box::new(aliased_table("bar")),
+                    on: Expression::from(true),
+                    join_type: JoinType::LeftOuter,
+                })
+            )
+        );
+
+        // Target must be one of the tables
+        assert!(delete("delete baz from foo join bar on true").is_err());
+    }
 }
@@ -1,14 +1,18 @@
 use crate::atoms::{identifier_str, kw, qualified_reference};
+use crate::create::user_name;
 use crate::whitespace::ws_0;
 use crate::ParserResult;
-use ast::statement::{DropTable, Statement};
+use ast::statement::{DropSink, DropTable, Statement};
 use nom::branch::alt;
 use nom::combinator::{cut, map};
 use nom::sequence::{preceded, tuple};
 
 /// Parses a drop statement
 pub fn drop_(input: &str) -> ParserResult<Statement> {
-    preceded(kw("DROP"), cut(alt((database, table))))(input)
+    preceded(
+        kw("DROP"),
+        cut(alt((database, table, user, sink, function))),
+    )(input)
 }
 
 fn database(input: &str) -> ParserResult<Statement> {
@@ -30,6 +34,27 @@ fn table(input: &str) -> ParserResult<Statement> {
     )(input)
 }
 
+fn user(input: &str) -> ParserResult<Statement> {
+    map(
+        tuple((ws_0, kw("USER"), ws_0, user_name)),
+        |(_, _, _, name)| Statement::DropUser(name),
+    )(input)
+}
+
+fn sink(input: &str) -> ParserResult<Statement> {
+    map(
+        tuple((ws_0, kw("SINK"), ws_0, qualified_reference)),
+        |(_, _, _, (database, name))| Statement::DropSink(DropSink { database, name }),
+    )(input)
+}
+
+fn function(input: &str) -> ParserResult<Statement> {
+    map(
+        tuple((ws_0, kw("FUNCTION"), ws_0, identifier_str)),
+        |(_, _, _, name)| Statement::DropFunction(name),
+    )(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,4 +85,31 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_drop_user() {
+        assert_eq!(
+            drop_("drop user bob@localhost").unwrap().1,
+            Statement::DropUser("bob".to_string())
+        );
+    }
+
+    #[test]
+    fn test_drop_sink() {
+        assert_eq!(
+            drop_("drop sink foo.bar").unwrap().1,
+            Statement::DropSink(DropSink {
+                database: Some("foo".to_string()),
+                name: "bar".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_drop_function() {
+        assert_eq!(
+            drop_("drop function Add_One").unwrap().1,
+            Statement::DropFunction("add_one".to_string())
+        );
+    }
 }
@@ -0,0 +1,195 @@
+use nom::error::{ContextError, ErrorKind, FromExternalError};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// The error type threaded through all the parsers.
+/// Rather than keeping a stack of errors like nom's VerboseError we only track the furthest
+/// point into the sql any of the parsers got to along with what they'd have accepted there, as
+/// that's almost always where the actual mistake is.
+/// Parsers register what they accept using nom's context combinator, ie
+/// context("expression", ...), keywords register themselves.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SyntaxError<'a> {
+    // The remaining input at the point of failure
+    input: &'a str,
+    expected: Vec<&'static str>,
+}
+
+impl<'a> SyntaxError<'a> {
+    pub fn expected(input: &'a str, expected: &'static str) -> Self {
+        SyntaxError {
+            input,
+            expected: vec![expected],
+        }
+    }
+}
+
+impl<'a> nom::error::ParseError<&'a str> for SyntaxError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        SyntaxError {
+            input,
+            expected: vec![],
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    /// Called with the errors of alternatives, we keep whichever got the furthest, or if they
+    /// both failed at the same point everything either of them would have accepted.
+    fn or(mut self, other: Self) -> Self {
+        match self.input.len().cmp(&other.input.len()) {
+            Ordering::Less => self,
+            Ordering::Greater => other,
+            Ordering::Equal => {
+                for expected in other.expected {
+                    if !self.expected.contains(&expected) {
+                        self.expected.push(expected);
+                    }
+                }
+                self
+            }
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for SyntaxError<'a> {
+    /// If the parser failed without getting anywhere then it's the whole construct that's
+    /// missing, otherwise the error from further into it is more useful.
+    fn add_context(input: &'a str, context: &'static str, other: Self) -> Self {
+        if other.input.len() == input.len() {
+            SyntaxError::expected(input, context)
+        } else {
+            other
+        }
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for SyntaxError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _e: E) -> Self {
+        nom::error::ParseError::from_error_kind(input, kind)
+    }
+}
+
+/// An error from parsing sql, the message points out where in the sql the error is and what was
+/// expected there, ie
+/// Syntax error at line 1, column 8, expected expression
+/// SELECT !!
+///        ^
+#[derive(Debug)]
+pub struct ParseError {
+    pub(crate) error: String,
+}
+
+impl ParseError {
+    pub(crate) fn new(sql: &str, error: SyntaxError) -> Self {
+        let offset = sql.len() - error.input.len();
+        let line_start = sql[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = sql[offset..]
+            .find('\n')
+            .map_or(sql.len(), |idx| offset + idx);
+        let line = sql[..offset].matches('\n').count() + 1;
+        let column = sql[line_start..offset].chars().count() + 1;
+
+        let problem = match error.expected.as_slice() {
+            [] if error.input.trim().is_empty() => "unexpected end of input".to_string(),
+            [] => format!(
+                "unexpected \"{}\"",
+                error.input.split_whitespace().next().unwrap_or_default()
+            ),
+            [expected] => format!("expected {}", expected),
+            [expected @ .., last] => format!("expected one of {} or {}", expected.join(", "), last),
+        };
+
+        ParseError {
+            error: format!(
+                "Syntax error at line {}, column {}, {}\n{}\n{}^",
+                line,
+                column,
+                problem,
+                &sql[line_start..line_end],
+                " ".repeat(column - 1)
+            ),
+        }
+    }
+}
+
+impl From<String> for ParseError {
+    fn from(error: String) -> Self {
+        ParseError { error }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.error, f)
+    }
+}
+
+impl Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nom::error::ParseError as _;
+
+    #[test]
+    fn test_syntax_error_or() {
+        let sql = "SELECT foo";
+        let at_start = SyntaxError::expected(sql, "SHOW");
+        let further = SyntaxError::expected(&sql[7..], "expression");
+        assert_eq!(at_start.clone().or(further.clone()), further);
+        assert_eq!(further.clone().or(at_start.clone()), further);
+        assert_eq!(
+            at_start.or(SyntaxError::expected(sql, "SELECT")),
+            SyntaxError {
+                input: sql,
+                expected: vec!["SHOW", "SELECT"]
+            }
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_context() {
+        let sql = "SELECT foo";
+        let missing = SyntaxError::from_error_kind(sql, ErrorKind::Tag);
+        assert_eq!(
+            SyntaxError::add_context(sql, "statement", missing),
+            SyntaxError::expected(sql, "statement")
+        );
+        let inner = SyntaxError::expected(&sql[7..], "FROM");
+        assert_eq!(
+            SyntaxError::add_context(sql, "statement", inner.clone()),
+            inner
+        );
+    }
+
+    #[test]
+    fn test_parse_error_message() {
+        let sql = "SELECT 1\nFROM !";
+        assert_eq!(
+            ParseError::new(sql, SyntaxError::expected(&sql[14..], "identifier")).to_string(),
+            "Syntax error at line 2, column 6, expected identifier\nFROM !\n     ^"
+        );
+        let error = SyntaxError {
+            input: &sql[14..],
+            expected: vec!["(", "identifier", "VALUES"],
+        };
+        assert!(ParseError::new(sql, error).to_string().starts_with(
+            "Syntax error at line 2, column 6, expected one of (, identifier or VALUES"
+        ));
+        assert!(ParseError::new(
+            sql,
+            SyntaxError::from_error_kind(&sql[14..], ErrorKind::Eof)
+        )
+        .to_string()
+        .starts_with("Syntax error at line 2, column 6, unexpected \"!\""));
+        assert!(
+            ParseError::new(sql, SyntaxError::from_error_kind("", ErrorKind::Eof))
+                .to_string()
+                .starts_with("Syntax error at line 2, column 7, unexpected end of input")
+        );
+    }
+}
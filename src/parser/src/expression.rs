@@ -1,20 +1,24 @@
 use crate::atoms::{as_clause, identifier_str, kw};
+use crate::error::SyntaxError;
 use crate::literals::{datatype, literal};
+use crate::select::select;
 use crate::whitespace::ws_0;
 use crate::ParserResult;
-use ast::expr::{Cast, ColumnReference, Expression, FunctionCall, NamedExpression, SortExpression};
-use data::SortOrder;
+use ast::expr::{
+    Cast, ColumnReference, Expression, FunctionCall, NamedExpression, SortExpression, Subquery,
+};
+use data::{NullOrder, SortOrder};
 use nom::branch::{alt, Alt};
 use nom::bytes::complete::tag;
 use nom::combinator::{cut, map, opt, value};
-use nom::error::VerboseError;
-use nom::multi::{many0, separated_list0};
-use nom::sequence::{delimited, pair, preceded, separated_pair, tuple};
+use nom::error::context;
+use nom::multi::{many0, separated_list0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, tuple};
 
 /// Parses a bog standard expression, ie 1 + 2
 /// operators precedence according to https://dev.mysql.com/doc/refman/8.0/en/operator-precedence.html
 pub fn expression(input: &str) -> ParserResult<Expression> {
-    expression_0(input)
+    context("expression", expression_0)(input)
 }
 
 /// Parses a named expression, ie 1 as one
@@ -24,30 +28,39 @@ pub fn named_expression(input: &str) -> ParserResult<NamedExpression> {
     })(input)
 }
 
-/// Parses a sort expression, ie 1 desc
+/// Parses a sort expression, ie 1 desc nulls first
 pub fn sort_expression(input: &str) -> ParserResult<SortExpression> {
-    alt((
-        map(
-            separated_pair(expression, ws_0, sort_order),
-            |(expression, ordering)| SortExpression {
-                ordering,
-                expression,
-            },
-        ),
-        map(expression, |expression| SortExpression {
-            ordering: SortOrder::Asc,
+    map(
+        tuple((
             expression,
-        }),
-    ))(input)
+            opt(preceded(ws_0, sort_order)),
+            opt(preceded(ws_0, null_order)),
+        )),
+        |(expression, ordering, nulls)| SortExpression {
+            ordering: ordering.unwrap_or(SortOrder::Asc),
+            nulls,
+            expression,
+        },
+    )(input)
 }
 
-fn sort_order(input: &str) -> ParserResult<SortOrder> {
+pub(crate) fn sort_order(input: &str) -> ParserResult<SortOrder> {
     alt((
         value(SortOrder::Asc, kw("ASC")),
         value(SortOrder::Desc, kw("DESC")),
     ))(input)
 }
 
+fn null_order(input: &str) -> ParserResult<NullOrder> {
+    preceded(
+        pair(kw("NULLS"), ws_0),
+        alt((
+            value(NullOrder::First, kw("FIRST")),
+            value(NullOrder::Last, kw("LAST")),
+        )),
+    )(input)
+}
+
 /// Parse a comma separated list of expressions ie 1,2+2
 pub fn comma_sep_expressions(input: &str) -> ParserResult<Vec<Expression>> {
     separated_list0(tuple((ws_0, tag(","), ws_0)), expression)(input)
@@ -115,54 +128,90 @@ fn expression_5(input: &str) -> ParserResult<Expression> {
         tag("!="),
         tag(">="),
         tag(">"),
+        tag("<=>"),
         tag("<="),
         tag("<"),
     );
 
-    // Parser to support the is [not] true|false|null
+    // Parser to support the is [not] true|false|null|distinct from
+    // These will return function_name: &str, negate: bool, right_operators: Vec<expr>
     let is = preceded(
         kw("IS"),
         cut(tuple((
             ws_0,
             opt(pair(kw("NOT"), ws_0)),
             alt((
-                value(None, kw("NULL")),
-                value(Some(true), kw("TRUE")),
-                value(Some(false), kw("FALSE")),
+                value(("isnull", false, vec![]), kw("NULL")),
+                value(("istrue", false, vec![]), kw("TRUE")),
+                value(("isfalse", false, vec![]), kw("FALSE")),
+                // a IS DISTINCT FROM b is the same as NOT (a <=> b)
+                map(
+                    preceded(
+                        tuple((kw("DISTINCT"), ws_0, kw("FROM"), ws_0)),
+                        expression_6,
+                    ),
+                    |right| ("<=>", true, vec![right]),
+                ),
             )),
         ))),
     );
 
-    // These will return function_name: &str, not: bool, right_operator: Option<expr>
+    // These will return function_name: &str, not: bool, right_operators: Vec<expr>
     let op_parser = map(
         tuple((ws_0, alt(operators), ws_0, expression_6)),
-        |(_, op, _, right)| (op, false, Some(right)),
+        |(_, op, _, right)| (op, false, vec![right]),
+    );
+    let is_parser = map(
+        preceded(ws_0, is),
+        |(_, not, (function_name, negate, right))| (function_name, not.is_some() != negate, right),
+    );
+    // a [NOT] IN (b, c, ...) or a [NOT] IN (SELECT ...), no cut here as "IN" also shows up in
+    // MATCH ... AGAINST
+    let in_parser = map(
+        tuple((
+            ws_0,
+            opt(pair(kw("NOT"), ws_0)),
+            kw("IN"),
+            tuple((ws_0, tag("("), ws_0)),
+            alt((
+                map(select, |query| {
+                    vec![Expression::Subquery(Subquery {
+                        operand: None,
+                        query: Box::new(query),
+                    })]
+                }),
+                separated_list1(tuple((ws_0, tag(","), ws_0)), expression),
+            )),
+            pair(ws_0, tag(")")),
+        )),
+        |(_, not, _, _, list, _)| ("in", not.is_some(), list),
     );
-    let is_parser = map(preceded(ws_0, is), |(_, not, like)| {
-        let function_name = match like {
-            Some(true) => "istrue",
-            Some(false) => "isfalse",
-            None => "isnull",
-        };
-        (function_name, not.is_some(), None)
-    });
 
     // Hacked up version of infix_many to also support the is null etc operators
     map(
-        tuple((expression_6, many0(alt((op_parser, is_parser))))),
+        tuple((expression_6, many0(alt((op_parser, is_parser, in_parser))))),
         |(start, ops)| {
-            ops.into_iter().fold(start, |acc, (op, not, right)| {
-                let args = if let Some(r) = right {
-                    vec![acc, r]
-                } else {
-                    vec![acc]
+            ops.into_iter().fold(start, |acc, (op, not, mut right)| {
+                let funct = match right.as_mut_slice() {
+                    // a IN (SELECT ...), the subquery just takes on the left as its operand
+                    [Expression::Subquery(Subquery {
+                        operand: operand @ None,
+                        ..
+                    })] => {
+                        *operand = Some(Box::new(acc));
+                        right.pop().unwrap()
+                    }
+                    _ => {
+                        let mut args = vec![acc];
+                        args.extend(right);
+
+                        Expression::FunctionCall(FunctionCall {
+                            function_name: op.to_lowercase(),
+                            args,
+                        })
+                    }
                 };
 
-                let funct = Expression::FunctionCall(FunctionCall {
-                    function_name: op.to_lowercase(),
-                    args,
-                });
-
                 if not {
                     Expression::FunctionCall(FunctionCall {
                         function_name: "not".to_string(),
@@ -191,17 +240,20 @@ fn expression_8(input: &str) -> ParserResult<Expression> {
 fn expression_9(input: &str) -> ParserResult<Expression> {
     alt((
         count_star,
+        exists_subquery,
+        match_against,
         function_call,
+        bare_function_call,
         cast,
         literal,
         column_reference,
-        brackets,
+        brackets_or_row,
     ))(input)
 }
 
 /// Used to reduce boilerplate at each precedence level for infix operators
 /// Takes a tuple of operator tags, and the parser function for the higher precedence layer
-fn infix_many<'a, List: Alt<&'a str, &'a str, VerboseError<&'a str>>>(
+fn infix_many<'a, List: Alt<&'a str, &'a str, SyntaxError<'a>>>(
     operators: List,
     higher: fn(&'a str) -> ParserResult<Expression>,
 ) -> impl FnMut(&'a str) -> ParserResult<Expression> {
@@ -245,19 +297,80 @@ fn infix<'a, Op: Fn(&'a str) -> ParserResult<&'a str>>(
     )
 }
 
+/// A function call, aggregates may also be called with DISTINCT ie count(DISTINCT a), these are
+/// passed through to the planner as count(distinct(a))
 fn function_call(input: &str) -> ParserResult<Expression> {
     map(
         tuple((
             identifier_str,
             tuple((ws_0, tag("("), ws_0)),
+            opt(pair(kw("DISTINCT"), ws_0)),
             comma_sep_expressions,
             ws_0,
             tag(")"),
         )),
-        |(function_name, _, params, _, _)| {
+        |(function_name, _, distinct, params, _, _)| {
+            let args = if distinct.is_some() {
+                vec![Expression::FunctionCall(FunctionCall {
+                    function_name: "distinct".to_string(),
+                    args: params,
+                })]
+            } else {
+                params
+            };
             Expression::FunctionCall(FunctionCall {
                 function_name,
-                args: params,
+                args,
+            })
+        },
+    )(input)
+}
+
+/// MATCH (col) AGAINST ('terms' [IN NATURAL LANGUAGE MODE]), becomes match(col, 'terms')
+fn match_against(input: &str) -> ParserResult<Expression> {
+    map(
+        tuple((
+            kw("MATCH"),
+            ws_0,
+            brackets,
+            ws_0,
+            kw("AGAINST"),
+            cut(tuple((
+                tuple((ws_0, tag("("), ws_0)),
+                expression,
+                opt(tuple((
+                    ws_0,
+                    kw("IN"),
+                    ws_0,
+                    kw("NATURAL"),
+                    ws_0,
+                    kw("LANGUAGE"),
+                    ws_0,
+                    kw("MODE"),
+                ))),
+                pair(ws_0, tag(")")),
+            ))),
+        )),
+        |(_, _, column, _, _, (_, search, _, _))| {
+            Expression::FunctionCall(FunctionCall {
+                function_name: "match".to_string(),
+                args: vec![column, search],
+            })
+        },
+    )(input)
+}
+
+/// EXISTS (SELECT ...)
+fn exists_subquery(input: &str) -> ParserResult<Expression> {
+    map(
+        preceded(
+            pair(kw("EXISTS"), ws_0),
+            delimited(pair(tag("("), ws_0), select, pair(ws_0, tag(")"))),
+        ),
+        |query| {
+            Expression::Subquery(Subquery {
+                operand: None,
+                query: Box::new(query),
             })
         },
     )(input)
@@ -277,24 +390,59 @@ fn count_star(input: &str) -> ParserResult<Expression> {
     )(input)
 }
 
+/// Some sql functions can be called without any brackets, ie CURRENT_TIMESTAMP
+fn bare_function_call(input: &str) -> ParserResult<Expression> {
+    map(
+        alt((kw("CURRENT_TIMESTAMP"), kw("CURRENT_USER"))),
+        |function_name: &str| {
+            Expression::FunctionCall(FunctionCall {
+                function_name: function_name.to_lowercase(),
+                args: vec![],
+            })
+        },
+    )(input)
+}
+
+/// CAST(expr AS type) or TRY_CAST(expr AS type)
 fn cast(input: &str) -> ParserResult<Expression> {
-    preceded(
-        kw("CAST"),
-        cut(map(
-            tuple((
-                tuple((ws_0, tag("("), ws_0)),
-                expression,
-                tuple((ws_0, kw("AS"), ws_0)),
+    let (input, try_cast) = alt((value(false, kw("CAST")), value(true, kw("TRY_CAST"))))(input)?;
+    cut(map(
+        tuple((
+            tuple((ws_0, tag("("), ws_0)),
+            expression,
+            tuple((ws_0, kw("AS"), ws_0)),
+            datatype,
+            pair(ws_0, tag(")")),
+        )),
+        move |(_, expr, _, datatype, _)| {
+            Expression::Cast(Cast {
+                expr: Box::new(expr),
                 datatype,
-                pair(ws_0, tag(")")),
-            )),
-            |(_, expr, _, datatype, _)| {
-                Expression::Cast(Cast {
-                    expr: Box::new(expr),
-                    datatype,
+                try_cast,
+            })
+        },
+    ))(input)
+}
+
+/// Either an expression in brackets or a row value, ie (a, b). Row values can only be used in
+/// comparisons and IN lists.
+fn brackets_or_row(input: &str) -> ParserResult<Expression> {
+    map(
+        delimited(
+            pair(tag("("), ws_0),
+            separated_list1(tuple((ws_0, tag(","), ws_0)), expression),
+            pair(ws_0, tag(")")),
+        ),
+        |mut exprs| {
+            if exprs.len() == 1 {
+                exprs.pop().unwrap()
+            } else {
+                Expression::FunctionCall(FunctionCall {
+                    function_name: "row".to_string(),
+                    args: exprs,
                 })
-            },
-        )),
+            }
+        },
     )(input)
 }
 
@@ -384,6 +532,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_distinct_function_call_expression() {
+        assert_eq!(
+            expression("count(DISTINCT a)").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "count".to_string(),
+                args: vec![Expression::FunctionCall(FunctionCall {
+                    function_name: "distinct".to_string(),
+                    args: vec![Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: "a".to_string(),
+                        star: false
+                    })]
+                })]
+            })
+        );
+    }
+
+    #[test]
+    fn test_bare_function_call_expression() {
+        assert_eq!(
+            expression("CURRENT_TIMESTAMP").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "current_timestamp".to_string(),
+                args: vec![]
+            })
+        );
+
+        assert_eq!(
+            expression("current_user").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "current_user".to_string(),
+                args: vec![]
+            })
+        );
+    }
+
     #[test]
     fn test_bedmath_expression() {
         assert_eq!(
@@ -451,7 +636,17 @@ mod tests {
             expression("cast( null as decimal(1,2))").unwrap().1,
             Expression::Cast(Cast {
                 expr: Box::new(expr),
-                datatype: DataType::Decimal(1, 2)
+                datatype: DataType::Decimal(1, 2),
+                try_cast: false
+            })
+        );
+
+        assert_eq!(
+            expression("TRY_CAST('abc' AS INT)").unwrap().1,
+            Expression::Cast(Cast {
+                expr: Box::new(Expression::from("abc")),
+                datatype: DataType::Integer,
+                try_cast: true
             })
         );
     }
@@ -473,6 +668,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_row_values() {
+        let row = |args| {
+            Expression::FunctionCall(FunctionCall {
+                function_name: "row".to_string(),
+                args,
+            })
+        };
+        assert_eq!(
+            expression("(1, 2) >= (3,4)").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: ">=".to_string(),
+                args: vec![
+                    row(vec![Expression::from(1), Expression::from(2)]),
+                    row(vec![Expression::from(3), Expression::from(4)]),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_in() {
+        assert_eq!(
+            expression("1 in (2, 3)").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "in".to_string(),
+                args: vec![
+                    Expression::from(1),
+                    Expression::from(2),
+                    Expression::from(3)
+                ]
+            })
+        );
+
+        assert_eq!(
+            expression("1 NOT IN (2)").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "not".to_string(),
+                args: vec![Expression::FunctionCall(FunctionCall {
+                    function_name: "in".to_string(),
+                    args: vec![Expression::from(1), Expression::from(2)]
+                })]
+            })
+        );
+    }
+
+    #[test]
+    fn test_in_subquery() {
+        assert_eq!(
+            expression("1 IN (SELECT 2)").unwrap().1,
+            Expression::Subquery(Subquery {
+                operand: Some(Box::new(Expression::from(1))),
+                query: Box::new(select("SELECT 2").unwrap().1)
+            })
+        );
+
+        assert_eq!(
+            expression("1 NOT IN ( SELECT 2 )").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "not".to_string(),
+                args: vec![Expression::Subquery(Subquery {
+                    operand: Some(Box::new(Expression::from(1))),
+                    query: Box::new(select("SELECT 2").unwrap().1)
+                })]
+            })
+        );
+    }
+
+    #[test]
+    fn test_exists() {
+        assert_eq!(
+            expression("EXISTS (SELECT 2)").unwrap().1,
+            Expression::Subquery(Subquery {
+                operand: None,
+                query: Box::new(select("SELECT 2").unwrap().1)
+            })
+        );
+
+        assert_eq!(
+            expression("NOT exists(SELECT 2)").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "not".to_string(),
+                args: vec![Expression::Subquery(Subquery {
+                    operand: None,
+                    query: Box::new(select("SELECT 2").unwrap().1)
+                })]
+            })
+        );
+    }
+
     #[test]
     fn test_between() {
         assert_eq!(
@@ -531,6 +816,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_match_against() {
+        let match_fn = Expression::FunctionCall(FunctionCall {
+            function_name: "match".to_string(),
+            args: vec![
+                Expression::ColumnReference(ColumnReference {
+                    qualifier: None,
+                    alias: "a".to_string(),
+                    star: false,
+                }),
+                Expression::from("foo bar"),
+            ],
+        });
+        assert_eq!(
+            expression("MATCH (a) AGAINST ('foo bar')").unwrap().1,
+            match_fn
+        );
+        assert_eq!(
+            expression("match(a) against('foo bar' in natural language mode)")
+                .unwrap()
+                .1,
+            match_fn
+        );
+    }
+
+    #[test]
+    fn test_null_safe_eq() {
+        let null_safe_eq = Expression::FunctionCall(FunctionCall {
+            function_name: "<=>".to_string(),
+            args: vec![Expression::from(1), Expression::from(2)],
+        });
+        assert_eq!(expression("1 <=> 2").unwrap().1, null_safe_eq);
+        assert_eq!(
+            expression("1 is not distinct from 2").unwrap().1,
+            null_safe_eq
+        );
+        assert_eq!(
+            expression("1 IS DISTINCT FROM 2").unwrap().1,
+            Expression::FunctionCall(FunctionCall {
+                function_name: "not".to_string(),
+                args: vec![null_safe_eq]
+            })
+        );
+    }
+
     #[test]
     fn test_column_reference() {
         assert_eq!(
@@ -621,6 +951,7 @@ mod tests {
             sort_expression("foo").unwrap().1,
             SortExpression {
                 ordering: SortOrder::Asc,
+                nulls: None,
                 expression: expr.clone()
             }
         );
@@ -629,6 +960,7 @@ mod tests {
             sort_expression("foo Asc").unwrap().1,
             SortExpression {
                 ordering: SortOrder::Asc,
+                nulls: None,
                 expression: expr.clone()
             }
         );
@@ -637,6 +969,25 @@ mod tests {
             sort_expression("foo Desc").unwrap().1,
             SortExpression {
                 ordering: SortOrder::Desc,
+                nulls: None,
+                expression: expr.clone()
+            }
+        );
+
+        assert_eq!(
+            sort_expression("foo nulls last").unwrap().1,
+            SortExpression {
+                ordering: SortOrder::Asc,
+                nulls: Some(NullOrder::Last),
+                expression: expr.clone()
+            }
+        );
+
+        assert_eq!(
+            sort_expression("foo DESC NULLS FIRST").unwrap().1,
+            SortExpression {
+                ordering: SortOrder::Desc,
+                nulls: Some(NullOrder::First),
                 expression: expr.clone()
             }
         );
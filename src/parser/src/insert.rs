@@ -1,41 +1,126 @@
-use crate::atoms::{kw, qualified_reference};
+use crate::atoms::{identifier_str, kw, qualified_reference};
+use crate::expression::expression;
 use crate::literals::literal;
 use crate::select::select;
 use crate::whitespace::ws_0;
 use crate::ParserResult;
 use ast::expr::Expression;
-use ast::rel::logical::{LogicalOperator, TableInsert, TableReference, Values};
+use ast::rel::logical::{LogicalOperator, TableInsert, TableReference, Upsert, Values};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::combinator::{cut, map};
-use nom::multi::separated_list0;
-use nom::sequence::{pair, preceded, tuple};
+use nom::combinator::{cut, map, opt};
+use nom::multi::{separated_list0, separated_list1};
+use nom::sequence::{pair, preceded, separated_pair, tuple};
 
-/// Parses an insert statement
+/// Parses an insert statement, optionally followed by ON DUPLICATE KEY UPDATE col = expr, ...
 pub fn insert(input: &str) -> ParserResult<LogicalOperator> {
     map(
         preceded(
             kw("INSERT"),
-            pair(
+            tuple((
                 cut(preceded(
                     tuple((ws_0, kw("INTO"), ws_0)),
-                    qualified_reference,
+                    pair(qualified_reference, opt(preceded(ws_0, column_list))),
+                )),
+                cut(preceded(ws_0, alt((select, values)))),
+                opt(preceded(ws_0, on_duplicate_key_update)),
+            )),
+        ),
+        |(((database, table_name), columns), select, assignments)| {
+            table_insert(
+                database,
+                table_name,
+                columns,
+                select,
+                assignments.map(|assignments| Upsert { assignments }),
+            )
+        },
+    )(input)
+}
+
+/// Parses a replace statement, ie REPLACE [INTO] t ..., an insert where the inserted rows
+/// replace any existing rows with the same primary key.
+pub fn replace(input: &str) -> ParserResult<LogicalOperator> {
+    map(
+        preceded(
+            kw("REPLACE"),
+            pair(
+                cut(preceded(
+                    tuple((ws_0, opt(pair(kw("INTO"), ws_0)))),
+                    pair(qualified_reference, opt(preceded(ws_0, column_list))),
                 )),
                 cut(preceded(ws_0, alt((select, values)))),
             ),
         ),
-        |((database, table_name), select)| {
-            LogicalOperator::TableInsert(TableInsert {
-                table: Box::new(LogicalOperator::TableReference(TableReference {
-                    database,
-                    table: table_name,
-                })),
-                source: Box::new(select),
-            })
+        |(((database, table_name), columns), select)| {
+            table_insert(
+                database,
+                table_name,
+                columns,
+                select,
+                Some(Upsert {
+                    assignments: vec![],
+                }),
+            )
         },
     )(input)
 }
 
+fn table_insert(
+    database: Option<String>,
+    table_name: String,
+    columns: Option<Vec<String>>,
+    source: LogicalOperator,
+    upsert: Option<Upsert>,
+) -> LogicalOperator {
+    LogicalOperator::TableInsert(TableInsert {
+        table: Box::new(LogicalOperator::TableReference(TableReference {
+            database,
+            table: table_name,
+            as_of: None,
+        })),
+        columns: columns.unwrap_or_default(),
+        source: Box::new(source),
+        delete_matching: false,
+        bulk_load: None,
+        upsert,
+    })
+}
+
+/// ON DUPLICATE KEY UPDATE col = expr, ...
+fn on_duplicate_key_update(input: &str) -> ParserResult<Vec<(String, Expression)>> {
+    preceded(
+        tuple((
+            kw("ON"),
+            ws_0,
+            kw("DUPLICATE"),
+            ws_0,
+            kw("KEY"),
+            ws_0,
+            kw("UPDATE"),
+            ws_0,
+        )),
+        cut(separated_list1(
+            tuple((ws_0, tag(","), ws_0)),
+            separated_pair(identifier_str, tuple((ws_0, tag("="), ws_0)), expression),
+        )),
+    )(input)
+}
+
+/// Parses the list of columns to insert into, ie "(c1, c2)"
+fn column_list(input: &str) -> ParserResult<Vec<String>> {
+    map(
+        tuple((
+            tag("("),
+            ws_0,
+            separated_list0(tuple((ws_0, tag(","), ws_0)), identifier_str),
+            ws_0,
+            tag(")"),
+        )),
+        |(_, _, list, _, _)| list,
+    )(input)
+}
+
 /// Parses a values clause.
 fn values(input: &str) -> ParserResult<LogicalOperator> {
     map(
@@ -72,7 +157,7 @@ fn values_row(input: &str) -> ParserResult<Vec<Expression>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ast::expr::{Expression, NamedExpression};
+    use ast::expr::{ColumnReference, Expression, FunctionCall, NamedExpression};
     use ast::rel::logical::Project;
 
     #[test]
@@ -82,8 +167,10 @@ mod tests {
             LogicalOperator::TableInsert(TableInsert {
                 table: Box::new(LogicalOperator::TableReference(TableReference {
                     database: None,
-                    table: "foo".to_string()
+                    table: "foo".to_string(),
+                    as_of: None
                 })),
+                columns: vec![],
                 source: Box::new(LogicalOperator::Project(Project {
                     distinct: false,
                     expressions: vec![NamedExpression {
@@ -91,7 +178,10 @@ mod tests {
                         expression: Expression::from(1)
                     }],
                     source: Box::new(LogicalOperator::Single)
-                }))
+                })),
+                delete_matching: false,
+                bulk_load: None,
+                upsert: None
             })
         );
     }
@@ -103,16 +193,113 @@ mod tests {
             LogicalOperator::TableInsert(TableInsert {
                 table: Box::new(LogicalOperator::TableReference(TableReference {
                     database: None,
-                    table: "foo".to_string()
+                    table: "foo".to_string(),
+                    as_of: None
                 })),
+                columns: vec![],
                 source: Box::new(LogicalOperator::Values(Values {
                     fields: vec![],
                     data: vec![
                         vec![Expression::from(1), Expression::from(2)],
                         vec![Expression::from(3), Expression::from(4)]
                     ]
-                }))
+                })),
+                delete_matching: false,
+                bulk_load: None,
+                upsert: None
             })
         );
     }
+
+    #[test]
+    fn test_insert_columns() {
+        assert_eq!(
+            insert("insert into foo (c1, c2) values (1,2)").unwrap().1,
+            LogicalOperator::TableInsert(TableInsert {
+                table: Box::new(LogicalOperator::TableReference(TableReference {
+                    database: None,
+                    table: "foo".to_string(),
+                    as_of: None
+                })),
+                columns: vec!["c1".to_string(), "c2".to_string()],
+                source: Box::new(LogicalOperator::Values(Values {
+                    fields: vec![],
+                    data: vec![vec![Expression::from(1), Expression::from(2)]]
+                })),
+                delete_matching: false,
+                bulk_load: None,
+                upsert: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_insert_on_duplicate_key_update() {
+        assert_eq!(
+            insert("insert into foo values (1,2) on duplicate key update c2 = c2 + values(c2)")
+                .unwrap()
+                .1,
+            LogicalOperator::TableInsert(TableInsert {
+                table: Box::new(LogicalOperator::TableReference(TableReference {
+                    database: None,
+                    table: "foo".to_string(),
+                    as_of: None
+                })),
+                columns: vec![],
+                source: Box::new(LogicalOperator::Values(Values {
+                    fields: vec![],
+                    data: vec![vec![Expression::from(1), Expression::from(2)]]
+                })),
+                delete_matching: false,
+                bulk_load: None,
+                upsert: Some(Upsert {
+                    assignments: vec![(
+                        "c2".to_string(),
+                        Expression::FunctionCall(FunctionCall {
+                            function_name: "+".to_string(),
+                            args: vec![
+                                column("c2"),
+                                Expression::FunctionCall(FunctionCall {
+                                    function_name: "values".to_string(),
+                                    args: vec![column("c2")]
+                                })
+                            ]
+                        })
+                    )]
+                })
+            })
+        );
+    }
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(
+            replace("replace foo values (1,2)").unwrap().1,
+            LogicalOperator::TableInsert(TableInsert {
+                table: Box::new(LogicalOperator::TableReference(TableReference {
+                    database: None,
+                    table: "foo".to_string(),
+                    as_of: None
+                })),
+                columns: vec![],
+                source: Box::new(LogicalOperator::Values(Values {
+                    fields: vec![],
+                    data: vec![vec![Expression::from(1), Expression::from(2)]]
+                })),
+                delete_matching: false,
+                bulk_load: None,
+                upsert: Some(Upsert {
+                    assignments: vec![]
+                })
+            })
+        );
+    }
+
+    fn column(alias: &str) -> Expression {
+        Expression::ColumnReference(ColumnReference {
+            qualifier: None,
+            alias: alias.to_string(),
+            star: false,
+        })
+    }
 }
@@ -1,37 +1,46 @@
+use crate::error::SyntaxError;
 use crate::whitespace::ws_0;
 use ast::expr::Expression;
 use ast::statement::Statement;
 use nom::combinator::all_consuming;
-use nom::error::{convert_error, VerboseError};
-use nom::lib::std::fmt::{Display, Formatter};
 use nom::sequence::delimited;
 use nom::IResult;
-use std::error::Error;
 
+mod alter;
 mod atoms;
 mod create;
 mod delete;
 mod drop;
+mod error;
 mod expression;
 mod insert;
 mod literals;
+mod load;
+mod parameters;
 mod select;
+mod set;
 mod show;
+mod split;
 mod statement;
 mod whitespace;
 
-type ParserResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+pub use error::ParseError;
+pub use parameters::{bind_parameters, parameter_offsets};
+pub use split::split_statements;
+
+type ParserResult<'a, T> = IResult<&'a str, T, SyntaxError<'a>>;
 
 // The top level entry to parse a sql statement.
 // By forming sub parsers into a tree with branches in the tree being common prefixes it allows us
-// To give better contextual error messages in the future.
+// To give better contextual error messages, any errors point at the furthest any of the sub parsers
+// got along with what they would have accepted there.
 pub fn parse(input: &str) -> Result<Statement, ParseError> {
     let parser_result = all_consuming(delimited(ws_0, statement::statement, ws_0))(input);
 
     parser_result.map(|(_, command)| command).map_err(|err| {
         match err {
-            nom::Err::Error(e) => ParseError::from(convert_error(input, e)),
-            nom::Err::Failure(e) => ParseError::from(convert_error(input, e)),
+            nom::Err::Error(e) => ParseError::new(input, e),
+            nom::Err::Failure(e) => ParseError::new(input, e),
             // We should only get an incomplete if we used the streaming parsers
             nom::Err::Incomplete(_) => ParseError::from(String::from("Incomplete parsing")),
         }
@@ -44,33 +53,14 @@ pub fn parse_expression(input: &str) -> Result<Expression, ParseError> {
 
     parser_result.map(|(_, command)| command).map_err(|err| {
         match err {
-            nom::Err::Error(e) => ParseError::from(convert_error(input, e)),
-            nom::Err::Failure(e) => ParseError::from(convert_error(input, e)),
+            nom::Err::Error(e) => ParseError::new(input, e),
+            nom::Err::Failure(e) => ParseError::new(input, e),
             // We should only get an incomplete if we used the streaming parsers
             nom::Err::Incomplete(_) => ParseError::from(String::from("Incomplete parsing")),
         }
     })
 }
 
-#[derive(Debug)]
-pub struct ParseError {
-    error: String,
-}
-
-impl From<String> for ParseError {
-    fn from(error: String) -> Self {
-        ParseError { error }
-    }
-}
-
-impl Display for ParseError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        Display::fmt(&self.error, f)
-    }
-}
-
-impl Error for ParseError {}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,7 +86,15 @@ mod tests {
     fn test_statement_err() {
         assert_eq!(
             parse("SELECT !!").unwrap_err().error,
-            "0: at line 1, in Eof:\nSELECT !!\n       ^\n\n"
+            "Syntax error at line 1, column 8, expected expression\nSELECT !!\n       ^"
+        );
+        assert_eq!(
+            parse("SELEC 1").unwrap_err().error,
+            "Syntax error at line 1, column 1, expected statement\nSELEC 1\n^"
+        );
+        assert_eq!(
+            parse("SELECT 1\nFROM foo\nORDER BY").unwrap_err().error,
+            "Syntax error at line 3, column 9, expected expression\nORDER BY\n        ^"
         );
     }
 }
@@ -1,30 +1,38 @@
-use crate::atoms::{decimal, integer, kw, quoted_string};
+use crate::atoms::{decimal, double, integer, kw, quoted_string};
 use crate::whitespace::ws_0;
 use crate::ParserResult;
 use ast::expr::{Cast, Expression};
+use data::vector::{Vector, VECTOR_MAX_DIMENSIONS};
 use data::DataType::Decimal;
 use data::{DataType, Datum, DECIMAL_MAX_PRECISION};
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::combinator::{cut, map, value};
-use nom::sequence::{preceded, tuple};
+use nom::bytes::complete::{is_not, tag, tag_no_case};
+use nom::character::complete::{hex_digit0, hex_digit1};
+use nom::combinator::{cut, map, map_opt, opt, recognize, value};
+use nom::sequence::{delimited, pair, preceded, tuple};
 
 pub fn literal(input: &str) -> ParserResult<Expression> {
     alt((
         null_literal,
         boolean_literal,
+        hex_literal,
         number_literal,
         text_literal,
         date_literal,
+        vector_literal,
     ))(input)
 }
 
 pub fn datatype(input: &str) -> ParserResult<DataType> {
     alt((
         value(DataType::Boolean, kw("BOOLEAN")),
-        value(DataType::Integer, kw("INTEGER")),
-        value(DataType::Integer, kw("INT")),
-        value(DataType::BigInt, kw("BIGINT")),
+        integer_datatype,
+        value(
+            DataType::Double,
+            pair(kw("DOUBLE"), opt(preceded(ws_0, kw("PRECISION")))),
+        ),
+        value(DataType::Double, kw("FLOAT")),
+        value(DataType::Double, kw("REAL")),
         map(
             tuple((
                 tuple((kw("DECIMAL"), ws_0, tag("("), ws_0)),
@@ -47,12 +55,111 @@ pub fn datatype(input: &str) -> ParserResult<DataType> {
         ),
         value(DataType::Decimal(DECIMAL_MAX_PRECISION, 0), kw("DECIMAL")),
         value(DataType::Text, kw("TEXT")),
+        bytes_datatype,
         value(DataType::Json, kw("JSON")),
         value(DataType::Date, kw("DATE")),
         value(DataType::Timestamp, kw("TIMESTAMP")),
+        map_opt(
+            tuple((
+                tuple((kw("VECTOR"), ws_0, tag("("), ws_0)),
+                integer,
+                ws_0,
+                tag(")"),
+            )),
+            |(_, dimensions, _, _)| {
+                if 0 < dimensions && dimensions <= VECTOR_MAX_DIMENSIONS as i64 {
+                    Some(DataType::Vector(dimensions as u16))
+                } else {
+                    None
+                }
+            },
+        ),
     ))(input)
 }
 
+/// The integer types along with mysql's display widths, UNSIGNED and ZEROFILL modifiers, the
+/// display widths are accepted but ignored. Mediumints don't get a type of their own, they're
+/// stored as ints. A bare SIGNED or UNSIGNED (as used in mysql casts) is a 64 bit int.
+fn integer_datatype(input: &str) -> ParserResult<DataType> {
+    fn display_width(input: &str) -> ParserResult<i64> {
+        map(
+            tuple((ws_0, tag("("), ws_0, integer, ws_0, tag(")"))),
+            |(_, _, _, width, _, _)| width,
+        )(input)
+    }
+
+    fn int_keyword(input: &str) -> ParserResult<&str> {
+        alt((kw("INTEGER"), kw("INT")))(input)
+    }
+
+    alt((
+        map(
+            tuple((
+                alt((
+                    value(
+                        (DataType::TinyInt, DataType::UnsignedTinyInt),
+                        kw("TINYINT"),
+                    ),
+                    value(
+                        (DataType::SmallInt, DataType::UnsignedSmallInt),
+                        kw("SMALLINT"),
+                    ),
+                    value(
+                        (DataType::Integer, DataType::UnsignedInteger),
+                        kw("MEDIUMINT"),
+                    ),
+                    value((DataType::Integer, DataType::UnsignedInteger), int_keyword),
+                    value((DataType::BigInt, DataType::UnsignedBigInt), kw("BIGINT")),
+                )),
+                opt(display_width),
+                opt(preceded(
+                    ws_0,
+                    alt((value(false, kw("SIGNED")), value(true, kw("UNSIGNED")))),
+                )),
+                opt(preceded(ws_0, kw("ZEROFILL"))),
+            )),
+            |((signed, unsigned), _, is_unsigned, zerofill)| {
+                if is_unsigned == Some(true) || zerofill.is_some() {
+                    unsigned
+                } else {
+                    signed
+                }
+            },
+        ),
+        value(
+            DataType::BigInt,
+            pair(kw("SIGNED"), opt(preceded(ws_0, int_keyword))),
+        ),
+        value(
+            DataType::UnsignedBigInt,
+            pair(kw("UNSIGNED"), opt(preceded(ws_0, int_keyword))),
+        ),
+    ))(input)
+}
+
+/// BINARY(n), VARBINARY(n), BYTEA and the BLOB types, the lengths are accepted but not enforced
+fn bytes_datatype(input: &str) -> ParserResult<DataType> {
+    fn length(input: &str) -> ParserResult<i64> {
+        map(
+            tuple((ws_0, tag("("), ws_0, integer, ws_0, tag(")"))),
+            |(_, _, _, length, _, _)| length,
+        )(input)
+    }
+
+    value(
+        DataType::ByteA,
+        alt((
+            map(pair(kw("VARBINARY"), length), |_| ()),
+            map(pair(kw("BINARY"), opt(length)), |_| ()),
+            value((), kw("BYTEA")),
+            value((), kw("TINYBLOB")),
+            value((), kw("MEDIUMBLOB")),
+            value((), kw("LONGBLOB")),
+            value((), kw("BLOB")),
+        )),
+    )(input)
+}
+
 fn null_literal(input: &str) -> ParserResult<Expression> {
     value(
         Expression::Constant(Datum::Null, DataType::Null),
@@ -69,8 +176,10 @@ fn boolean_literal(input: &str) -> ParserResult<Expression> {
 
 fn number_literal(input: &str) -> ParserResult<Expression> {
     // Our casts will promote ints -> bigints -> decimals -> floats so that should be the preference
-    // for parsing numbers, we may not actually need to parse floats unless we support NaN/Inf etc
+    // for parsing numbers, doubles are only parsed when written with an exponent (ie 1.5e3) so
+    // trying them first doesn't steal plain numbers from the exact types
     alt((
+        map(double, Expression::from),
         map(integer, |i| {
             if std::i32::MIN as i64 <= i && i <= std::i32::MAX as i64 {
                 Expression::from(i as i32)
@@ -82,6 +191,37 @@ fn number_literal(input: &str) -> ParserResult<Expression> {
     ))(input)
 }
 
+/// A hex literal for bytes, ie x'DEADBEEF' or 0xDEADBEEF
+fn hex_literal(input: &str) -> ParserResult<Expression> {
+    map_opt(
+        alt((
+            map(
+                delimited(tag_no_case("x'"), hex_digit0, cut(tag("'"))),
+                str::to_string,
+            ),
+            // Following mysql an odd number of digits is padded with a leading zero but only for
+            // the 0x form
+            map(preceded(tag("0x"), hex_digit1), |digits: &str| {
+                if digits.len() % 2 == 1 {
+                    format!("0{}", digits)
+                } else {
+                    digits.to_string()
+                }
+            }),
+        )),
+        |digits: String| {
+            if digits.len() % 2 == 1 {
+                return None;
+            }
+            let bytes: Option<Vec<_>> = (0..digits.len())
+                .step_by(2)
+                .map(|idx| u8::from_str_radix(&digits[idx..idx + 2], 16).ok())
+                .collect();
+            Some(Expression::Constant(Datum::from(bytes?), DataType::ByteA))
+        },
+    )(input)
+}
+
 fn text_literal(input: &str) -> ParserResult<Expression> {
     map(quoted_string, Expression::from)(input)
 }
@@ -95,11 +235,29 @@ fn date_literal(input: &str) -> ParserResult<Expression> {
             Expression::Cast(Cast {
                 expr: Box::new(expr),
                 datatype: DataType::Date,
+                try_cast: false,
             })
         },
     )(input)
 }
 
+/// A vector literal, ie [1, 2.5, 3]
+fn vector_literal(input: &str) -> ParserResult<Expression> {
+    map_opt(
+        recognize(tuple((tag("["), is_not("]"), tag("]")))),
+        |text| {
+            let elements = Vector::parse(text)?;
+            if elements.len() > VECTOR_MAX_DIMENSIONS as usize {
+                return None;
+            }
+            Some(Expression::Constant(
+                Datum::from(elements.as_slice()),
+                DataType::Vector(elements.len() as u16),
+            ))
+        },
+    )(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,6 +290,8 @@ mod tests {
             literal("123.456").unwrap().1,
             Expression::from(Decimal::from_str("123.456").unwrap())
         );
+        assert_eq!(literal("1.5e3").unwrap().1, Expression::from(1500.0));
+        assert_eq!(literal("-2E-1").unwrap().1, Expression::from(-0.2));
     }
 
     #[test]
@@ -142,6 +302,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_hex_literal() {
+        let bytes =
+            |bytes: &[u8]| Expression::Constant(Datum::from(bytes.to_vec()), DataType::ByteA);
+        assert_eq!(
+            literal("x'DEADbeef'").unwrap().1,
+            bytes(&[0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(literal("X''").unwrap().1, bytes(&[]));
+        assert_eq!(literal("0x0aff").unwrap().1, bytes(&[0x0a, 0xff]));
+        assert_eq!(literal("0xabc").unwrap().1, bytes(&[0x0a, 0xbc]));
+        assert!(literal("x'abc'").is_err());
+        assert!(literal("x'abcg'").is_err());
+    }
+
+    #[test]
+    fn test_bytes_datatype_literals() {
+        assert_eq!(datatype("binary").unwrap().1, DataType::ByteA);
+        assert_eq!(datatype("binary(16)").unwrap().1, DataType::ByteA);
+        assert_eq!(datatype("varbinary (255)").unwrap().1, DataType::ByteA);
+        assert_eq!(datatype("blob").unwrap().1, DataType::ByteA);
+        assert_eq!(datatype("longblob").unwrap().1, DataType::ByteA);
+        assert_eq!(datatype("bytea").unwrap().1, DataType::ByteA);
+    }
+
     #[test]
     fn test_simple_datatype_literals() {
         assert_eq!(datatype("boolean").unwrap().1, DataType::Boolean);
@@ -152,9 +337,47 @@ mod tests {
 
         assert_eq!(datatype("bigint").unwrap().1, DataType::BigInt);
 
+        assert_eq!(datatype("double").unwrap().1, DataType::Double);
+
+        assert_eq!(datatype("double precision").unwrap().1, DataType::Double);
+
+        assert_eq!(datatype("float").unwrap().1, DataType::Double);
+
         assert_eq!(datatype("text").unwrap().1, DataType::Text);
     }
 
+    #[test]
+    fn test_integer_datatype_literals() {
+        assert_eq!(datatype("int").unwrap().1, DataType::Integer);
+        assert_eq!(datatype("integer").unwrap().1, DataType::Integer);
+        assert_eq!(datatype("bigint").unwrap().1, DataType::BigInt);
+        assert_eq!(datatype("tinyint(4)").unwrap().1, DataType::TinyInt);
+        assert_eq!(datatype("smallint").unwrap().1, DataType::SmallInt);
+        assert_eq!(datatype("mediumint").unwrap().1, DataType::Integer);
+        assert_eq!(
+            datatype("tinyint(3) unsigned").unwrap().1,
+            DataType::UnsignedTinyInt
+        );
+        assert_eq!(
+            datatype("smallint UNSIGNED").unwrap().1,
+            DataType::UnsignedSmallInt
+        );
+        assert_eq!(
+            datatype("int(10) unsigned zerofill").unwrap().1,
+            DataType::UnsignedInteger
+        );
+        assert_eq!(datatype("int signed").unwrap().1, DataType::Integer);
+        assert_eq!(
+            datatype("bigint unsigned").unwrap().1,
+            DataType::UnsignedBigInt
+        );
+        assert_eq!(datatype("signed").unwrap().1, DataType::BigInt);
+        assert_eq!(
+            datatype("unsigned integer").unwrap().1,
+            DataType::UnsignedBigInt
+        );
+    }
+
     #[test]
     fn test_decimal_datatype_literals() {
         assert_eq!(
@@ -170,13 +393,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_vector_literals() {
+        assert_eq!(datatype("vector(3)").unwrap().1, DataType::Vector(3));
+        assert!(datatype("vector(0)").is_err());
+
+        assert_eq!(
+            literal("[1, 2.5, -3]").unwrap().1,
+            Expression::Constant(
+                Datum::from([1.0_f32, 2.5, -3.0].as_ref()),
+                DataType::Vector(3)
+            )
+        );
+        assert!(literal("[1, a]").is_err());
+    }
+
     #[test]
     fn test_date_literals() {
         assert_eq!(
             literal(r#"date "2010-01-01""#).unwrap().1,
             Expression::Cast(Cast {
                 expr: Box::new(Expression::from("2010-01-01")),
-                datatype: DataType::Date
+                datatype: DataType::Date,
+                try_cast: false
             })
         );
     }
@@ -0,0 +1,173 @@
+use crate::atoms::{identifier_str, kw, qualified_reference, quoted_string};
+use crate::whitespace::ws_0;
+use crate::ParserResult;
+use ast::rel::logical::SerdeOptions;
+use ast::statement::{LoadData, Statement};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::combinator::{cut, map, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, tuple};
+
+/// Parses a bulk load statement, either mysql's
+/// LOAD DATA [LOCAL] INFILE 'path' INTO TABLE t [FIELDS TERMINATED BY ','] [(c1, c2)]
+/// or postgres's COPY t [(c1, c2)] FROM 'path' [DELIMITER ','].
+/// Both can be followed by WITHOUT SYNC to skip fsyncing the writes until the load is done.
+pub fn load_data(input: &str) -> ParserResult<Statement> {
+    map(
+        pair(
+            alt((load_data_infile, copy_from)),
+            opt(preceded(
+                tuple((ws_0, kw("WITHOUT"), ws_0)),
+                cut(kw("SYNC")),
+            )),
+        ),
+        |(mut load_data, without_sync)| {
+            load_data.sync = without_sync.is_none();
+            Statement::LoadData(load_data)
+        },
+    )(input)
+}
+
+fn load_data_infile(input: &str) -> ParserResult<LoadData> {
+    map(
+        preceded(
+            tuple((kw("LOAD"), ws_0, kw("DATA"))),
+            cut(tuple((
+                opt(preceded(ws_0, kw("LOCAL"))),
+                preceded(tuple((ws_0, kw("INFILE"), ws_0)), quoted_string),
+                preceded(
+                    tuple((ws_0, kw("INTO"), ws_0, kw("TABLE"), ws_0)),
+                    qualified_reference,
+                ),
+                opt(preceded(
+                    tuple((
+                        ws_0,
+                        alt((kw("FIELDS"), kw("COLUMNS"))),
+                        ws_0,
+                        kw("TERMINATED"),
+                        ws_0,
+                        kw("BY"),
+                        ws_0,
+                    )),
+                    delimiter,
+                )),
+                opt(preceded(ws_0, column_list)),
+            ))),
+        ),
+        |(_local, path, (database, table), delimiter, columns)| LoadData {
+            path,
+            database,
+            table,
+            columns: columns.unwrap_or_default(),
+            serde_options: delimiter
+                .map(|delimiter| SerdeOptions {
+                    delimiter,
+                    ..SerdeOptions::default()
+                })
+                .unwrap_or_default(),
+            sync: true,
+        },
+    )(input)
+}
+
+fn copy_from(input: &str) -> ParserResult<LoadData> {
+    map(
+        preceded(
+            kw("COPY"),
+            cut(tuple((
+                preceded(ws_0, qualified_reference),
+                opt(preceded(ws_0, column_list)),
+                preceded(tuple((ws_0, kw("FROM"), ws_0)), quoted_string),
+                opt(preceded(tuple((ws_0, kw("DELIMITER"), ws_0)), delimiter)),
+            ))),
+        ),
+        |((database, table), columns, path, delimiter)| LoadData {
+            path,
+            database,
+            table,
+            columns: columns.unwrap_or_default(),
+            serde_options: delimiter
+                .map(|delimiter| SerdeOptions {
+                    delimiter,
+                    ..SerdeOptions::default()
+                })
+                .unwrap_or_default(),
+            sync: true,
+        },
+    )(input)
+}
+
+fn delimiter(input: &str) -> ParserResult<u8> {
+    map(quoted_string, |s| {
+        s.as_bytes().first().copied().unwrap_or(b',')
+    })(input)
+}
+
+/// Parses the list of columns to load into, ie "(c1, c2)"
+fn column_list(input: &str) -> ParserResult<Vec<String>> {
+    delimited(
+        pair(tag("("), ws_0),
+        separated_list1(tuple((ws_0, tag(","), ws_0)), identifier_str),
+        pair(ws_0, tag(")")),
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_data_infile() {
+        assert_eq!(
+            load_data("LOAD DATA INFILE 'data/t.csv' INTO TABLE foo.t")
+                .unwrap()
+                .1,
+            Statement::LoadData(LoadData {
+                path: "data/t.csv".to_string(),
+                database: Some("foo".to_string()),
+                table: "t".to_string(),
+                columns: vec![],
+                serde_options: SerdeOptions::default(),
+                sync: true
+            })
+        );
+
+        assert_eq!(
+            load_data(
+                "load data local infile 'data' into table t fields terminated by '|' (a, b) without sync"
+            )
+            .unwrap()
+            .1,
+            Statement::LoadData(LoadData {
+                path: "data".to_string(),
+                database: None,
+                table: "t".to_string(),
+                columns: vec!["a".to_string(), "b".to_string()],
+                serde_options: SerdeOptions {
+                    delimiter: b'|',
+                    ..SerdeOptions::default()
+                },
+                sync: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_copy_from() {
+        assert_eq!(
+            load_data("COPY t (a) FROM 'data' DELIMITER '|'").unwrap().1,
+            Statement::LoadData(LoadData {
+                path: "data".to_string(),
+                database: None,
+                table: "t".to_string(),
+                columns: vec!["a".to_string()],
+                serde_options: SerdeOptions {
+                    delimiter: b'|',
+                    ..SerdeOptions::default()
+                },
+                sync: true
+            })
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use crate::atoms::{identifier_str, quoted_string};
+use crate::whitespace::{block_comment, line_comment};
+use crate::ParseError;
+use ast::expr::Expression;
+use nom::branch::alt;
+use nom::combinator::recognize;
+
+/// Returns the byte offsets of all the ? parameter placeholders in a sql statement.
+/// Question marks inside of strings, quoted identifiers and comments aren't placeholders.
+pub fn parameter_offsets(input: &str) -> Vec<usize> {
+    let mut offsets = vec![];
+    let mut remaining = input;
+    while let Some(c) = remaining.chars().next() {
+        let skipped = alt((
+            recognize(quoted_string),
+            recognize(identifier_str),
+            recognize(line_comment),
+            recognize(block_comment),
+        ))(remaining);
+
+        remaining = match skipped {
+            Ok((rest, _)) => rest,
+            Err(_) => {
+                if c == '?' {
+                    offsets.push(input.len() - remaining.len());
+                }
+                &remaining[c.len_utf8()..]
+            }
+        };
+    }
+    offsets
+}
+
+/// Substitutes the values in for the ? placeholders, the result being plain sql that can be
+/// parsed and planned like any other statement.
+pub fn bind_parameters(input: &str, parameters: &[Expression]) -> Result<String, ParseError> {
+    let offsets = parameter_offsets(input);
+    if offsets.len() != parameters.len() {
+        return Err(ParseError::from(format!(
+            "Expected {} parameters but got {}",
+            offsets.len(),
+            parameters.len()
+        )));
+    }
+
+    let mut sql = String::with_capacity(input.len());
+    let mut last = 0;
+    for (offset, parameter) in offsets.into_iter().zip(parameters) {
+        sql.push_str(&input[last..offset]);
+        sql.push_str(&parameter.to_string());
+        last = offset + 1;
+    }
+    sql.push_str(&input[last..]);
+    Ok(sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_expression;
+
+    #[test]
+    fn test_parameter_offsets() {
+        assert_eq!(parameter_offsets("SELECT 1"), Vec::<usize>::new());
+        assert_eq!(parameter_offsets("SELECT ?, a+?"), vec![7, 12]);
+        assert_eq!(
+            parameter_offsets("SELECT '?', \"?\", `a?` -- ?\n FROM t /* ? */ WHERE a=?"),
+            vec![51]
+        );
+    }
+
+    #[test]
+    fn test_bind_parameters() -> Result<(), ParseError> {
+        assert_eq!(
+            bind_parameters(
+                "SELECT * FROM t WHERE a = ? AND b = ?",
+                &[Expression::from(1), Expression::from("it's")]
+            )?,
+            "SELECT * FROM t WHERE a = 1 AND b = \"it's\""
+        );
+        assert!(bind_parameters("SELECT ?", &[]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind_parameters_round_trip() -> Result<(), ParseError> {
+        for value in &[
+            "it's",
+            "say \"hi\"",
+            "back\\slash\\",
+            "\\n isn't a newline",
+            "new\nline\ttab\r",
+            "nul\0 esc\u{1b} del\u{7f}",
+            "unicode ☃ \u{200b}",
+            "\\",
+            "",
+        ] {
+            let bound = bind_parameters("?", &[Expression::from(*value)])?;
+            assert_eq!(parse_expression(&bound)?, Expression::from(*value));
+        }
+        Ok(())
+    }
+}
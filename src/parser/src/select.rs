@@ -1,55 +1,129 @@
-use crate::atoms::{as_clause, integer, kw, qualified_reference, quoted_string};
+use crate::atoms::{as_clause, identifier_str, integer, kw, qualified_reference, quoted_string};
 use crate::expression::{comma_sep_expressions, expression, named_expression, sort_expression};
+use crate::literals::datatype;
 use crate::whitespace::ws_0;
 use crate::ParserResult;
-use ast::expr::{Expression, NamedExpression, SortExpression};
+use ast::expr::{Cast, ColumnReference, Expression, NamedExpression, SortExpression};
 use ast::rel::logical::{
-    FileScan, Filter, GroupBy, Join, JoinType, Limit, LogicalOperator, Project, SerdeOptions, Sort,
-    TableAlias, TableReference, UnionAll,
+    FileScan, Filter, GroupBy, Join, JoinType, JsonTable, JsonTableColumn, Limit, LogicalOperator,
+    Project, SerdeOptions, SetOperation, SetOperator, Sort, TableAlias, TableReference, UnionAll,
 };
+use data::chrono::NaiveDateTime;
+use data::jsonpath_utils::JsonPathExpression;
+use data::{DataType, LogicalTimestamp};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::combinator::{cut, map, opt, value};
-use nom::multi::{many0, separated_list0, separated_list1};
-use nom::sequence::{delimited, pair, preceded, separated_pair, tuple};
+use nom::combinator::{cut, map, map_opt, opt, value, verify};
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use std::str::FromStr;
 
 /// Parses a select statement, a select statement consists of potentially multiple
-/// select expressions unioned together
+/// select expressions combined with set operators. Like standard sql INTERSECT binds tighter
+/// than UNION and EXCEPT.
 pub fn select(input: &str) -> ParserResult<LogicalOperator> {
+    map(
+        pair(
+            intersect_expr,
+            many0(pair(
+                delimited(ws_0, union_or_except, ws_0),
+                cut(intersect_expr),
+            )),
+        ),
+        |(first, rest)| {
+            rest.into_iter()
+                .fold(first, |left, (operator, right)| match (operator, left) {
+                    // Keep chains of UNION ALLs flat
+                    (None, LogicalOperator::UnionAll(mut union_all)) => {
+                        union_all.sources.push(right);
+                        LogicalOperator::UnionAll(union_all)
+                    }
+                    (None, left) => LogicalOperator::UnionAll(UnionAll {
+                        sources: vec![left, right],
+                    }),
+                    (Some(operator), left) => set_operation(operator, left, right),
+                })
+        },
+    )(input)
+}
+
+/// Parses UNION ALL, UNION [DISTINCT] or EXCEPT [DISTINCT], None being UNION ALL
+fn union_or_except(input: &str) -> ParserResult<Option<SetOperator>> {
+    alt((
+        value(None, tuple((kw("UNION"), ws_0, kw("ALL")))),
+        value(
+            Some(SetOperator::Union),
+            pair(kw("UNION"), opt(pair(ws_0, kw("DISTINCT")))),
+        ),
+        value(
+            Some(SetOperator::Except),
+            pair(kw("EXCEPT"), opt(pair(ws_0, kw("DISTINCT")))),
+        ),
+    ))(input)
+}
+
+/// Parses select expressions joined by INTERSECT [DISTINCT]
+fn intersect_expr(input: &str) -> ParserResult<LogicalOperator> {
     map(
         pair(
             select_expr,
             many0(preceded(
-                tuple((ws_0, kw("UNION"), ws_0, kw("ALL"), ws_0)),
-                select_expr,
+                tuple((ws_0, kw("INTERSECT"), opt(pair(ws_0, kw("DISTINCT"))), ws_0)),
+                cut(select_expr),
             )),
         ),
-        |(first, mut rest)| {
-            if rest.is_empty() {
-                first
-            } else {
-                rest.insert(0, first);
-                LogicalOperator::UnionAll(UnionAll { sources: rest })
-            }
+        |(first, rest)| {
+            rest.into_iter().fold(first, |left, right| {
+                set_operation(SetOperator::Intersect, left, right)
+            })
         },
     )(input)
 }
 
+fn set_operation(
+    operator: SetOperator,
+    left: LogicalOperator,
+    right: LogicalOperator,
+) -> LogicalOperator {
+    LogicalOperator::SetOperation(SetOperation {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+    })
+}
+
 /// Parses a singular select expression
 fn select_expr(input: &str) -> ParserResult<LogicalOperator> {
     map(
         preceded(
             kw("SELECT"),
-            cut(tuple((
-                preceded(ws_0, comma_sep_named_expressions),
-                opt(preceded(ws_0, from_clause)),
-                opt(preceded(ws_0, where_clause)),
-                opt(preceded(ws_0, group_by_clause)),
-                opt(preceded(ws_0, order_clause)),
-                opt(preceded(ws_0, limit_clause)),
-            ))),
+            cut(verify(
+                tuple((
+                    opt(preceded(ws_0, distinct_on_clause)),
+                    opt(preceded(ws_0, kw("DISTINCT"))),
+                    preceded(ws_0, comma_sep_named_expressions),
+                    opt(preceded(ws_0, from_clause)),
+                    opt(preceded(ws_0, where_clause)),
+                    opt(preceded(ws_0, group_by_clause)),
+                    opt(preceded(ws_0, order_clause)),
+                    opt(preceded(ws_0, limit_clause)),
+                )),
+                // We don't support mixing distinct on with a group by
+                |(distinct_on_option, _, _, _, _, group_option, _, _)| {
+                    distinct_on_option.is_none() || group_option.is_none()
+                },
+            )),
         ),
-        |(expressions, from_option, where_option, group_option, order_option, limit_option)| {
+        |(
+            distinct_on_option,
+            distinct_option,
+            expressions,
+            from_option,
+            where_option,
+            group_option,
+            order_option,
+            limit_option,
+        )| {
             let mut query = from_option.unwrap_or(LogicalOperator::Single);
 
             if let Some(predicate) = where_option {
@@ -60,14 +134,40 @@ fn select_expr(input: &str) -> ParserResult<LogicalOperator> {
             }
 
             query = if let Some(group_keys) = group_option {
-                LogicalOperator::GroupBy(GroupBy {
+                let group_by = LogicalOperator::GroupBy(GroupBy {
                     expressions,
                     key_expressions: group_keys,
+                    distinct_on: false,
+                    source: Box::from(query),
+                });
+                if distinct_option.is_some() {
+                    // The rows coming out of the group by can still have duplicates if not all
+                    // the keys are selected.
+                    LogicalOperator::Project(Project {
+                        distinct: true,
+                        expressions: vec![NamedExpression {
+                            expression: Expression::ColumnReference(ColumnReference {
+                                qualifier: None,
+                                alias: "*".to_string(),
+                                star: true,
+                            }),
+                            alias: None,
+                        }],
+                        source: Box::from(group_by),
+                    })
+                } else {
+                    group_by
+                }
+            } else if let Some(distinct_keys) = distinct_on_option {
+                LogicalOperator::GroupBy(GroupBy {
+                    expressions,
+                    key_expressions: distinct_keys,
+                    distinct_on: true,
                     source: Box::from(query),
                 })
             } else {
                 LogicalOperator::Project(Project {
-                    distinct: false,
+                    distinct: distinct_option.is_some(),
                     expressions,
                     source: Box::from(query),
                 })
@@ -93,8 +193,16 @@ fn select_expr(input: &str) -> ParserResult<LogicalOperator> {
     )(input)
 }
 
+/// Parse the DISTINCT ON (...) prefix of the select list, returns the distinct keys.
+fn distinct_on_clause(input: &str) -> ParserResult<Vec<Expression>> {
+    preceded(
+        tuple((kw("DISTINCT"), ws_0, kw("ON"), ws_0, tag("("), ws_0)),
+        cut(terminated(comma_sep_expressions, pair(ws_0, tag(")")))),
+    )(input)
+}
+
 fn comma_sep_named_expressions(input: &str) -> ParserResult<Vec<NamedExpression>> {
-    separated_list0(tuple((ws_0, tag(","), ws_0)), named_expression)(input)
+    separated_list1(tuple((ws_0, tag(","), ws_0)), named_expression)(input)
 }
 
 // The from clause of a query can get a bit tricky...
@@ -115,25 +223,20 @@ fn comma_sep_named_expressions(input: &str) -> ParserResult<Vec<NamedExpression>
 
 /// Parse the from clause of a query.
 fn from_clause(input: &str) -> ParserResult<LogicalOperator> {
+    preceded(kw("FROM"), cut(from_items))(input)
+}
+
+/// Parse the comma separated items of a from clause (ie everything after the FROM keyword),
+/// these get cross joined together.
+pub(crate) fn from_items(input: &str) -> ParserResult<LogicalOperator> {
     map(
-        preceded(
-            kw("FROM"),
-            cut(separated_list1(
-                tuple((ws_0, tag(","), ws_0)),
-                preceded(ws_0, join),
-            )),
-        ),
+        separated_list1(tuple((ws_0, tag(","), ws_0)), preceded(ws_0, join)),
         |items| {
             // Grab the first and then cross join with each join as we go.
             let mut iter = items.into_iter();
             let first = iter.next().unwrap();
             iter.fold(first, |left, right| {
-                LogicalOperator::Join(Join {
-                    left: Box::new(left),
-                    right: Box::new(right),
-                    on: Expression::from(true),
-                    join_type: JoinType::Inner,
-                })
+                join_items(left, right, Expression::from(true), JoinType::Inner)
             })
         },
     )(input)
@@ -152,17 +255,42 @@ fn join(input: &str) -> ParserResult<LogicalOperator> {
             joins
                 .into_iter()
                 .fold(first, |left, ((join_type, right), condition)| {
-                    LogicalOperator::Join(Join {
-                        left: Box::new(left),
-                        right: Box::new(right),
-                        on: condition,
-                        join_type,
-                    })
+                    join_items(left, right, condition, join_type)
                 })
         },
     )(input)
 }
 
+/// Json tables are lateral, so rather than being joined to the items before them those items
+/// become the json table's source with any join condition applied as a filter on top.
+fn join_items(
+    left: LogicalOperator,
+    right: LogicalOperator,
+    on: Expression,
+    join_type: JoinType,
+) -> LogicalOperator {
+    match right {
+        LogicalOperator::JsonTable(mut json_table) if join_type == JoinType::Inner => {
+            json_table.source = Box::new(left);
+            let json_table = LogicalOperator::JsonTable(json_table);
+            if on == Expression::from(true) {
+                json_table
+            } else {
+                LogicalOperator::Filter(Filter {
+                    predicate: on,
+                    source: Box::new(json_table),
+                })
+            }
+        }
+        right => LogicalOperator::Join(Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            on,
+            join_type,
+        }),
+    }
+}
+
 fn join_type(input: &str) -> ParserResult<JoinType> {
     alt((
         value(
@@ -177,19 +305,23 @@ fn join_type(input: &str) -> ParserResult<JoinType> {
 }
 
 fn join_item(input: &str) -> ParserResult<LogicalOperator> {
-    map(
-        pair(unaliased_join_item, as_clause),
-        |(sub_query, alias_opt)| {
-            if let Some(alias) = alias_opt {
-                LogicalOperator::TableAlias(TableAlias {
-                    alias,
-                    source: Box::from(sub_query),
-                })
-            } else {
-                sub_query
-            }
-        },
-    )(input)
+    alt((
+        // The json table's alias only applies to its own columns so it holds onto it itself
+        json_table,
+        map(
+            pair(unaliased_join_item, as_clause),
+            |(sub_query, alias_opt)| {
+                if let Some(alias) = alias_opt {
+                    LogicalOperator::TableAlias(TableAlias {
+                        alias,
+                        source: Box::from(sub_query),
+                    })
+                } else {
+                    sub_query
+                }
+            },
+        ),
+    ))(input)
 }
 
 fn unaliased_join_item(input: &str) -> ParserResult<LogicalOperator> {
@@ -201,6 +333,66 @@ fn unaliased_join_item(input: &str) -> ParserResult<LogicalOperator> {
     ))(input)
 }
 
+/// JSON_TABLE(doc, 'path' COLUMNS (name type PATH 'path', name FOR ORDINALITY, ...)) [AS] alias
+fn json_table(input: &str) -> ParserResult<LogicalOperator> {
+    map(
+        preceded(
+            tuple((kw("JSON_TABLE"), ws_0, tag("("), ws_0)),
+            cut(tuple((
+                expression,
+                preceded(tuple((ws_0, tag(","), ws_0)), jsonpath),
+                delimited(
+                    tuple((ws_0, kw("COLUMNS"), ws_0, tag("("), ws_0)),
+                    separated_list1(tuple((ws_0, tag(","), ws_0)), json_table_column),
+                    tuple((ws_0, tag(")"), ws_0, tag(")"))),
+                ),
+                as_clause,
+            ))),
+        ),
+        |(document, path, columns, alias)| {
+            LogicalOperator::JsonTable(JsonTable {
+                // Text documents get parsed into json
+                document: Expression::Cast(Cast {
+                    expr: Box::new(document),
+                    datatype: DataType::Json,
+                    try_cast: false,
+                }),
+                path,
+                columns,
+                alias,
+                source: Box::new(LogicalOperator::Single),
+            })
+        },
+    )(input)
+}
+
+fn json_table_column(input: &str) -> ParserResult<JsonTableColumn> {
+    map(
+        separated_pair(
+            identifier_str,
+            ws_0,
+            alt((
+                map(tuple((kw("FOR"), ws_0, kw("ORDINALITY"))), |_| {
+                    (DataType::BigInt, None)
+                }),
+                map(
+                    separated_pair(datatype, tuple((ws_0, kw("PATH"), ws_0)), jsonpath),
+                    |(datatype, path)| (datatype, Some(path)),
+                ),
+            )),
+        ),
+        |(name, (datatype, path))| JsonTableColumn {
+            name,
+            datatype,
+            path,
+        },
+    )(input)
+}
+
+fn jsonpath(input: &str) -> ParserResult<JsonPathExpression> {
+    map_opt(quoted_string, |path| JsonPathExpression::parse(&path))(input)
+}
+
 /// Parse the where clause of a query.
 pub(crate) fn where_clause(input: &str) -> ParserResult<Expression> {
     preceded(kw("WHERE"), cut(preceded(ws_0, expression)))(input)
@@ -223,7 +415,7 @@ pub(crate) fn order_clause(input: &str) -> ParserResult<Vec<SortExpression>> {
         tuple((kw("ORDER"), ws_0, kw("BY"))),
         cut(preceded(
             ws_0,
-            separated_list0(tuple((ws_0, tag(","), ws_0)), sort_expression),
+            separated_list1(tuple((ws_0, tag(","), ws_0)), sort_expression),
         )),
     )(input)
 }
@@ -255,16 +447,33 @@ pub(crate) fn limit_clause(input: &str) -> ParserResult<(i64, i64)> {
 
 /// Parse as a table AND wrap in a Table Alias
 fn table_reference_with_alias(input: &str) -> ParserResult<LogicalOperator> {
-    map(qualified_reference, |(database, table)| {
-        let table_source = LogicalOperator::TableReference(TableReference {
-            database,
-            table: table.clone(),
-        });
-        LogicalOperator::TableAlias(TableAlias {
-            alias: table,
-            source: Box::new(table_source),
-        })
-    })(input)
+    map(
+        pair(qualified_reference, opt(as_of_clause)),
+        |((database, table), as_of)| {
+            let table_source = LogicalOperator::TableReference(TableReference {
+                database,
+                table: table.clone(),
+                as_of,
+            });
+            LogicalOperator::TableAlias(TableAlias {
+                alias: table,
+                source: Box::new(table_source),
+            })
+        },
+    )(input)
+}
+
+/// Parse the AS OF TIMESTAMP clause of a time travel query, ie AS OF TIMESTAMP '2020-01-01 10:00:00'
+fn as_of_clause(input: &str) -> ParserResult<LogicalTimestamp> {
+    preceded(
+        tuple((ws_0, kw("AS"), ws_0, kw("OF"), ws_0, kw("TIMESTAMP"), ws_0)),
+        cut(map_opt(quoted_string, |text| {
+            let timestamp = NaiveDateTime::from_str(&text.replacen(' ', "T", 1)).ok()?;
+            Some(LogicalTimestamp::new(
+                timestamp.timestamp_millis().max(0) as u64
+            ))
+        })),
+    )(input)
 }
 
 /// Parse a file source
@@ -281,22 +490,53 @@ fn directory_source(input: &str) -> ParserResult<LogicalOperator> {
             LogicalOperator::FileScan(FileScan {
                 directory,
                 serde_options: serde_options.unwrap_or_default(),
+                columns: vec![],
+                partition_columns: 0,
+                partitions: vec![],
             })
         },
     )(input)
 }
 
+/// WITH (DELIMITER = 'x', RECURSIVE = TRUE|FALSE)
 fn serde_options(input: &str) -> ParserResult<SerdeOptions> {
     map(
         delimited(
             tuple((kw("WITH"), ws_0, tag("("), ws_0)),
-            delimiter_option,
+            separated_list1(tuple((ws_0, tag(","), ws_0)), directory_option),
             tuple((ws_0, tag(")"))),
         ),
-        |delimiter| SerdeOptions { delimiter },
+        |options| {
+            let mut serde_options = SerdeOptions::default();
+            for option in options {
+                match option {
+                    DirectoryOption::Delimiter(delimiter) => serde_options.delimiter = delimiter,
+                    DirectoryOption::Recursive(recursive) => serde_options.recursive = recursive,
+                }
+            }
+            serde_options
+        },
     )(input)
 }
 
+enum DirectoryOption {
+    Delimiter(u8),
+    Recursive(bool),
+}
+
+fn directory_option(input: &str) -> ParserResult<DirectoryOption> {
+    alt((
+        map(delimiter_option, DirectoryOption::Delimiter),
+        map(
+            preceded(
+                tuple((kw("RECURSIVE"), ws_0, tag("="), ws_0)),
+                alt((value(true, kw("TRUE")), value(false, kw("FALSE")))),
+            ),
+            DirectoryOption::Recursive,
+        ),
+    ))(input)
+}
+
 fn delimiter_option(input: &str) -> ParserResult<u8> {
     map(
         preceded(
@@ -428,9 +668,79 @@ mod tests {
                     alias: "a".to_string(),
                     star: false
                 })],
+                distinct_on: false,
+                source: Box::new(LogicalOperator::Single)
+            })
+        );
+    }
+
+    #[test]
+    fn test_distinct_on() {
+        assert_eq!(
+            select("SELECT DISTINCT ON (a) 1").unwrap().1,
+            LogicalOperator::GroupBy(GroupBy {
+                expressions: vec![NamedExpression {
+                    expression: Expression::from(1),
+                    alias: None
+                },],
+                key_expressions: vec![Expression::ColumnReference(ColumnReference {
+                    qualifier: None,
+                    alias: "a".to_string(),
+                    star: false
+                })],
+                distinct_on: true,
+                source: Box::new(LogicalOperator::Single)
+            })
+        );
+
+        assert!(select("SELECT DISTINCT ON (a) 1 GROUP BY a").is_err());
+    }
+
+    #[test]
+    fn test_distinct() {
+        assert_eq!(
+            select("SELECT DISTINCT a").unwrap().1,
+            LogicalOperator::Project(Project {
+                distinct: true,
+                expressions: vec![NamedExpression {
+                    expression: Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: "a".to_string(),
+                        star: false
+                    }),
+                    alias: None
+                },],
                 source: Box::new(LogicalOperator::Single)
             })
         );
+
+        assert_eq!(
+            select("SELECT DISTINCT 1 GROUP BY a").unwrap().1,
+            LogicalOperator::Project(Project {
+                distinct: true,
+                expressions: vec![NamedExpression {
+                    expression: Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: "*".to_string(),
+                        star: true
+                    }),
+                    alias: None
+                },],
+                source: Box::new(LogicalOperator::GroupBy(GroupBy {
+                    expressions: vec![NamedExpression {
+                        expression: Expression::from(1),
+                        alias: None
+                    },],
+                    key_expressions: vec![Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: "a".to_string(),
+                        star: false
+                    })],
+                    distinct_on: false,
+                    source: Box::new(LogicalOperator::Single)
+                }))
+            })
+        );
     }
 
     #[test]
@@ -448,14 +758,16 @@ mod tests {
                         alias: "a".to_string(),
                         source: Box::new(LogicalOperator::TableReference(TableReference {
                             database: None,
-                            table: "a".to_string()
+                            table: "a".to_string(),
+                            as_of: None
                         }))
                     })),
                     right: Box::new(LogicalOperator::TableAlias(TableAlias {
                         alias: "b".to_string(),
                         source: Box::new(LogicalOperator::TableReference(TableReference {
                             database: None,
-                            table: "b".to_string()
+                            table: "b".to_string(),
+                            as_of: None
                         }))
                     })),
                     on: Expression::from(true),
@@ -465,6 +777,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_json_table() {
+        assert_eq!(
+            select(
+                r#"SELECT 1 FROM a, JSON_TABLE(a.doc, '$.items[*]' COLUMNS (idx FOR ORDINALITY, name TEXT PATH '$.name')) AS jt"#
+            )
+            .unwrap()
+            .1,
+            LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    expression: Expression::from(1),
+                    alias: None
+                },],
+                source: Box::new(LogicalOperator::JsonTable(JsonTable {
+                    document: Expression::Cast(Cast {
+                        expr: Box::new(Expression::ColumnReference(ColumnReference {
+                            qualifier: Some("a".to_string()),
+                            alias: "doc".to_string(),
+                            star: false
+                        })),
+                        datatype: DataType::Json,
+                        try_cast: false
+                    }),
+                    path: JsonPathExpression::parse("$.items[*]").unwrap(),
+                    columns: vec![
+                        JsonTableColumn {
+                            name: "idx".to_string(),
+                            datatype: DataType::BigInt,
+                            path: None
+                        },
+                        JsonTableColumn {
+                            name: "name".to_string(),
+                            datatype: DataType::Text,
+                            path: JsonPathExpression::parse("$.name")
+                        }
+                    ],
+                    alias: Some("jt".to_string()),
+                    source: Box::new(LogicalOperator::TableAlias(TableAlias {
+                        alias: "a".to_string(),
+                        source: Box::new(LogicalOperator::TableReference(TableReference {
+                            database: None,
+                            table: "a".to_string(),
+                            as_of: None
+                        }))
+                    })),
+                }))
+            })
+        );
+    }
+
     #[test]
     fn test_new_style_join() {
         assert_eq!(
@@ -480,14 +843,16 @@ mod tests {
                         alias: "a".to_string(),
                         source: Box::new(LogicalOperator::TableReference(TableReference {
                             database: None,
-                            table: "a".to_string()
+                            table: "a".to_string(),
+                            as_of: None
                         }))
                     })),
                     right: Box::new(LogicalOperator::TableAlias(TableAlias {
                         alias: "b".to_string(),
                         source: Box::new(LogicalOperator::TableReference(TableReference {
                             database: None,
-                            table: "b".to_string()
+                            table: "b".to_string(),
+                            as_of: None
                         }))
                     })),
                     on: Expression::from(3),
@@ -497,6 +862,78 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_derived_table_join() {
+        let derived = LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: vec![NamedExpression {
+                expression: Expression::from(1),
+                alias: Some("c".to_string()),
+            }],
+            source: Box::from(LogicalOperator::Single),
+        });
+
+        assert_eq!(
+            select("SELECT 1 FROM a JOIN (SELECT 1 AS c) AS b ON 3")
+                .unwrap()
+                .1,
+            LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    expression: Expression::from(1),
+                    alias: None
+                },],
+                source: Box::new(LogicalOperator::Join(Join {
+                    left: Box::new(LogicalOperator::TableAlias(TableAlias {
+                        alias: "a".to_string(),
+                        source: Box::new(LogicalOperator::TableReference(TableReference {
+                            database: None,
+                            table: "a".to_string(),
+                            as_of: None
+                        }))
+                    })),
+                    right: Box::new(LogicalOperator::TableAlias(TableAlias {
+                        alias: "b".to_string(),
+                        source: Box::new(derived)
+                    })),
+                    on: Expression::from(3),
+                    join_type: JoinType::Inner
+                }))
+            })
+        );
+    }
+
+    #[test]
+    fn test_table_as_of() {
+        assert_eq!(
+            select("SELECT 1 FROM a AS OF TIMESTAMP '2020-01-01 00:00:01.5' AS b")
+                .unwrap()
+                .1,
+            LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    expression: Expression::from(1),
+                    alias: None
+                },],
+                source: Box::new(LogicalOperator::TableAlias(TableAlias {
+                    alias: "b".to_string(),
+                    source: Box::new(LogicalOperator::TableAlias(TableAlias {
+                        alias: "a".to_string(),
+                        source: Box::new(LogicalOperator::TableReference(TableReference {
+                            database: None,
+                            table: "a".to_string(),
+                            as_of: Some(LogicalTimestamp::new(1_577_836_801_500))
+                        }))
+                    }))
+                }))
+            })
+        );
+
+        // Plain aliases named "of" still work
+        assert!(select("SELECT 1 FROM a AS of").is_ok());
+        assert!(select("SELECT 1 FROM a AS OF TIMESTAMP 'yesterday'").is_err());
+    }
+
     #[test]
     fn test_order_by() {
         let project = LogicalOperator::Project(Project {
@@ -513,6 +950,7 @@ mod tests {
             LogicalOperator::Sort(Sort {
                 sort_expressions: vec![SortExpression {
                     ordering: SortOrder::Desc,
+                    nulls: None,
                     expression: Expression::from(1)
                 }],
                 source: Box::new(project)
@@ -582,6 +1020,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_set_operations() {
+        let select_n = |n: i32| {
+            LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    expression: Expression::from(n),
+                    alias: None,
+                }],
+                source: Box::from(LogicalOperator::Single),
+            })
+        };
+
+        assert_eq!(
+            select("SELECT 1 UNION SELECT 2 UNION ALL SELECT 3")
+                .unwrap()
+                .1,
+            LogicalOperator::UnionAll(UnionAll {
+                sources: vec![
+                    set_operation(SetOperator::Union, select_n(1), select_n(2)),
+                    select_n(3)
+                ]
+            })
+        );
+
+        // Intersect binds tighter
+        assert_eq!(
+            select("SELECT 1 EXCEPT SELECT 2 INTERSECT DISTINCT SELECT 3")
+                .unwrap()
+                .1,
+            set_operation(
+                SetOperator::Except,
+                select_n(1),
+                set_operation(SetOperator::Intersect, select_n(2), select_n(3))
+            )
+        );
+    }
+
     #[test]
     fn test_table_reference() {
         assert_eq!(
@@ -590,7 +1066,8 @@ mod tests {
                 alias: "foo".to_string(),
                 source: Box::new(LogicalOperator::TableReference(TableReference {
                     database: None,
-                    table: "foo".to_string()
+                    table: "foo".to_string(),
+                    as_of: None
                 })),
             })
         );
@@ -601,7 +1078,8 @@ mod tests {
                 alias: "bar".to_string(),
                 source: Box::new(LogicalOperator::TableReference(TableReference {
                     database: Some("foo".to_string()),
-                    table: "bar".to_string()
+                    table: "bar".to_string(),
+                    as_of: None
                 })),
             })
         );
@@ -619,7 +1097,10 @@ mod tests {
                 }],
                 source: Box::new(LogicalOperator::FileScan(FileScan {
                     directory: "test".to_string(),
-                    serde_options: SerdeOptions::default()
+                    serde_options: SerdeOptions::default(),
+                    columns: vec![],
+                    partition_columns: 0,
+                    partitions: vec![]
                 })),
             })
         );
@@ -639,9 +1120,42 @@ mod tests {
                 }],
                 source: Box::new(LogicalOperator::FileScan(FileScan {
                     directory: "test".to_string(),
-                    serde_options: SerdeOptions { delimiter: b'|' }
+                    serde_options: SerdeOptions {
+                        delimiter: b'|',
+                        ..SerdeOptions::default()
+                    },
+                    columns: vec![],
+                    partition_columns: 0,
+                    partitions: vec![]
                 })),
             })
         );
     }
+
+    #[test]
+    fn test_directory_src_recursive_option() {
+        if let LogicalOperator::Project(project) = select(
+            r#"SELECT 1 FROM DIRECTORY "test/*.csv" WITH (RECURSIVE = FALSE, DELIMITER = '|')"#,
+        )
+        .unwrap()
+        .1
+        {
+            assert_eq!(
+                *project.source,
+                LogicalOperator::FileScan(FileScan {
+                    directory: "test/*.csv".to_string(),
+                    serde_options: SerdeOptions {
+                        delimiter: b'|',
+                        header: false,
+                        recursive: false
+                    },
+                    columns: vec![],
+                    partition_columns: 0,
+                    partitions: vec![]
+                })
+            );
+        } else {
+            panic!()
+        }
+    }
 }
@@ -0,0 +1,271 @@
+use crate::atoms::{identifier_str, kw, quoted_string};
+use crate::create::user_name;
+use crate::expression::expression;
+use crate::whitespace::ws_0;
+use crate::ParserResult;
+use ast::expr::Expression;
+use ast::statement::{AlterUser, SetVariable, Statement};
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::combinator::{cut, map, opt, value};
+use nom::multi::separated_list1;
+use nom::sequence::{pair, preceded, terminated, tuple};
+
+/// Parses a set statement, ie SET NAMES utf8mb4, SET CHARACTER SET utf8,
+/// SET autocommit = 1, @@session.sql_mode = '', @x = 5 or SET PASSWORD = 'secret'
+pub fn set(input: &str) -> ParserResult<Statement> {
+    preceded(
+        kw("SET"),
+        cut(preceded(
+            ws_0,
+            alt((
+                set_password,
+                map(
+                    alt((
+                        set_names,
+                        set_character_set,
+                        separated_list1(tuple((ws_0, tag(","), ws_0)), variable_assignment),
+                    )),
+                    Statement::SetVariables,
+                ),
+            )),
+        )),
+    )(input)
+}
+
+/// SET PASSWORD [FOR user] = 'password'
+fn set_password(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            kw("PASSWORD"),
+            pair(
+                opt(preceded(tuple((ws_0, kw("FOR"), ws_0)), user_name)),
+                preceded(tuple((ws_0, tag("="), ws_0)), quoted_string),
+            ),
+        ),
+        |(name, password)| Statement::AlterUser(AlterUser { name, password }),
+    )(input)
+}
+
+/// SET NAMES charset [COLLATE collation]
+fn set_names(input: &str) -> ParserResult<Vec<SetVariable>> {
+    map(
+        preceded(
+            pair(kw("NAMES"), ws_0),
+            pair(
+                charset_name,
+                opt(preceded(tuple((ws_0, kw("COLLATE"), ws_0)), charset_name)),
+            ),
+        ),
+        |(charset, collation)| {
+            let mut assignments: Vec<_> = [
+                "character_set_client",
+                "character_set_connection",
+                "character_set_results",
+            ]
+            .iter()
+            .map(|name| SetVariable {
+                name: name.to_string(),
+                value: Expression::from(charset.clone()),
+            })
+            .collect();
+            if let Some(collation) = collation {
+                assignments.push(SetVariable {
+                    name: "collation_connection".to_string(),
+                    value: Expression::from(collation),
+                })
+            }
+            assignments
+        },
+    )(input)
+}
+
+/// SET CHARACTER SET charset or SET CHARSET charset
+fn set_character_set(input: &str) -> ParserResult<Vec<SetVariable>> {
+    map(
+        preceded(
+            pair(
+                alt((
+                    value((), tuple((kw("CHARACTER"), ws_0, kw("SET")))),
+                    value((), kw("CHARSET")),
+                )),
+                ws_0,
+            ),
+            charset_name,
+        ),
+        |charset| {
+            ["character_set_client", "character_set_results"]
+                .iter()
+                .map(|name| SetVariable {
+                    name: name.to_string(),
+                    value: Expression::from(charset.clone()),
+                })
+                .collect()
+        },
+    )(input)
+}
+
+fn charset_name(input: &str) -> ParserResult<String> {
+    alt((identifier_str, map(quoted_string, |s| s.to_lowercase())))(input)
+}
+
+/// [SESSION|GLOBAL|@@session.|@@]name = expression or @name = expression for user variables,
+/// user variables get parsed as identifiers, @ and all.
+fn variable_assignment(input: &str) -> ParserResult<SetVariable> {
+    map(
+        tuple((
+            opt(alt((
+                terminated(kw("SESSION"), ws_0),
+                terminated(kw("GLOBAL"), ws_0),
+                terminated(kw("LOCAL"), ws_0),
+                tag_no_case("@@session."),
+                tag_no_case("@@global."),
+                tag_no_case("@@local."),
+                tag("@@"),
+            ))),
+            identifier_str,
+            tuple((ws_0, alt((tag(":="), tag("="))), ws_0)),
+            // ON is a keyword so wont get parsed as a bare word by the expression parser
+            alt((value(Expression::from("on"), kw("ON")), expression)),
+        )),
+        |(_scope, name, _, value)| SetVariable { name, value },
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::expr::ColumnReference;
+
+    #[test]
+    fn test_set_names() {
+        assert_eq!(
+            set("SET NAMES utf8mb4").unwrap().1,
+            Statement::SetVariables(vec![
+                SetVariable {
+                    name: "character_set_client".to_string(),
+                    value: Expression::from("utf8mb4")
+                },
+                SetVariable {
+                    name: "character_set_connection".to_string(),
+                    value: Expression::from("utf8mb4")
+                },
+                SetVariable {
+                    name: "character_set_results".to_string(),
+                    value: Expression::from("utf8mb4")
+                },
+            ])
+        );
+
+        assert_eq!(
+            set("set names 'UTF8' collate utf8_general_ci").unwrap().1,
+            Statement::SetVariables(vec![
+                SetVariable {
+                    name: "character_set_client".to_string(),
+                    value: Expression::from("utf8")
+                },
+                SetVariable {
+                    name: "character_set_connection".to_string(),
+                    value: Expression::from("utf8")
+                },
+                SetVariable {
+                    name: "character_set_results".to_string(),
+                    value: Expression::from("utf8")
+                },
+                SetVariable {
+                    name: "collation_connection".to_string(),
+                    value: Expression::from("utf8_general_ci")
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_set_character_set() {
+        let expected = Statement::SetVariables(vec![
+            SetVariable {
+                name: "character_set_client".to_string(),
+                value: Expression::from("latin1"),
+            },
+            SetVariable {
+                name: "character_set_results".to_string(),
+                value: Expression::from("latin1"),
+            },
+        ]);
+        assert_eq!(set("SET CHARACTER SET latin1").unwrap().1, expected);
+        assert_eq!(set("SET CHARSET latin1").unwrap().1, expected);
+    }
+
+    #[test]
+    fn test_set_variables() {
+        assert_eq!(
+            set("SET autocommit = ON, SESSION sql_mode = '', @@session.character_set_results = NULL, @@net_write_timeout := 600, collation_connection = utf8mb4_general_ci").unwrap().1,
+            Statement::SetVariables(vec![
+                SetVariable {
+                    name: "autocommit".to_string(),
+                    value: Expression::from("on")
+                },
+                SetVariable {
+                    name: "sql_mode".to_string(),
+                    value: Expression::from("")
+                },
+                SetVariable {
+                    name: "character_set_results".to_string(),
+                    value: Expression::Constant(data::Datum::Null, data::DataType::Null)
+                },
+                SetVariable {
+                    name: "net_write_timeout".to_string(),
+                    value: Expression::from(600)
+                },
+                SetVariable {
+                    name: "collation_connection".to_string(),
+                    value: Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: "utf8mb4_general_ci".to_string(),
+                        star: false
+                    })
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_set_password() {
+        assert_eq!(
+            set("SET PASSWORD = 'secret'").unwrap().1,
+            Statement::AlterUser(AlterUser {
+                name: None,
+                password: "secret".to_string()
+            })
+        );
+
+        assert_eq!(
+            set("set password for bob@'%' = 'secret'").unwrap().1,
+            Statement::AlterUser(AlterUser {
+                name: Some("bob".to_string()),
+                password: "secret".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_user_variables() {
+        assert_eq!(
+            set("SET @X = 5, @y := @x").unwrap().1,
+            Statement::SetVariables(vec![
+                SetVariable {
+                    name: "@x".to_string(),
+                    value: Expression::from(5)
+                },
+                SetVariable {
+                    name: "@y".to_string(),
+                    value: Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: "@x".to_string(),
+                        star: false
+                    })
+                },
+            ])
+        );
+    }
+}
@@ -1,10 +1,10 @@
-use crate::atoms::kw;
+use crate::atoms::{identifier_str, kw, qualified_reference, quoted_string};
 use crate::whitespace::ws_0;
 use crate::ParserResult;
-use ast::statement::Statement;
+use ast::statement::{ShowColumns, ShowIndex, Statement};
 use nom::branch::alt;
-use nom::combinator::{cut, value};
-use nom::sequence::preceded;
+use nom::combinator::{cut, map, opt, value};
+use nom::sequence::{pair, preceded, tuple};
 
 /// Parses a show statement
 pub fn show(input: &str) -> ParserResult<Statement> {
@@ -13,11 +13,121 @@ pub fn show(input: &str) -> ParserResult<Statement> {
         cut(alt((
             value(Statement::ShowFunctions, preceded(ws_0, kw("FUNCTIONS"))),
             value(Statement::ShowDatabases, preceded(ws_0, kw("DATABASES"))),
-            value(Statement::ShowTables, preceded(ws_0, kw("TABLES"))),
+            value(Statement::ShowWarnings, preceded(ws_0, kw("WARNINGS"))),
+            value(
+                Statement::ShowProcesslist,
+                tuple((ws_0, opt(pair(kw("FULL"), ws_0)), kw("PROCESSLIST"))),
+            ),
+            map(
+                preceded(
+                    pair(ws_0, kw("TABLES")),
+                    opt(preceded(tuple((ws_0, from_or_in, ws_0)), identifier_str)),
+                ),
+                Statement::ShowTables,
+            ),
+            show_columns,
+            show_index,
+            map(
+                preceded(
+                    tuple((
+                        ws_0,
+                        opt(pair(alt((kw("SESSION"), kw("GLOBAL"))), ws_0)),
+                        kw("VARIABLES"),
+                    )),
+                    like_clause,
+                ),
+                Statement::ShowVariables,
+            ),
+            map(
+                preceded(pair(ws_0, kw("COLLATION")), like_clause),
+                Statement::ShowCollation,
+            ),
+            map(
+                preceded(
+                    pair(
+                        ws_0,
+                        alt((
+                            value((), tuple((kw("CHARACTER"), ws_0, kw("SET")))),
+                            value((), kw("CHARSET")),
+                        )),
+                    ),
+                    like_clause,
+                ),
+                Statement::ShowCharacterSet,
+            ),
         ))),
     )(input)
 }
 
+/// DESCRIBE|DESC [db.]table
+pub fn describe(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            pair(alt((kw("DESCRIBE"), kw("DESC"))), ws_0),
+            qualified_reference,
+        ),
+        |(database, name)| Statement::ShowColumns(ShowColumns { database, name }),
+    )(input)
+}
+
+/// SHOW COLUMNS|FIELDS FROM|IN [db.]table [FROM|IN db]
+fn show_columns(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            tuple((
+                ws_0,
+                alt((kw("COLUMNS"), kw("FIELDS"))),
+                ws_0,
+                from_or_in,
+                ws_0,
+            )),
+            pair(
+                qualified_reference,
+                opt(preceded(tuple((ws_0, from_or_in, ws_0)), identifier_str)),
+            ),
+        ),
+        |((database, name), from_database)| {
+            Statement::ShowColumns(ShowColumns {
+                database: from_database.or(database),
+                name,
+            })
+        },
+    )(input)
+}
+
+/// SHOW INDEX|INDEXES|KEYS FROM|IN [db.]table [FROM|IN db]
+fn show_index(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            tuple((
+                ws_0,
+                alt((kw("INDEXES"), kw("INDEX"), kw("KEYS"))),
+                ws_0,
+                from_or_in,
+                ws_0,
+            )),
+            pair(
+                qualified_reference,
+                opt(preceded(tuple((ws_0, from_or_in, ws_0)), identifier_str)),
+            ),
+        ),
+        |((database, name), from_database)| {
+            Statement::ShowIndex(ShowIndex {
+                database: from_database.or(database),
+                name,
+            })
+        },
+    )(input)
+}
+
+fn from_or_in(input: &str) -> ParserResult<&str> {
+    alt((kw("FROM"), kw("IN")))(input)
+}
+
+fn like_clause(input: &str) -> ParserResult<Option<String>> {
+    opt(preceded(tuple((ws_0, kw("LIKE"), ws_0)), quoted_string))(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,7 +139,121 @@ mod tests {
 
     #[test]
     fn test_show_tables() {
-        assert_eq!(show("Show tables").unwrap().1, Statement::ShowTables);
+        assert_eq!(show("Show tables").unwrap().1, Statement::ShowTables(None));
+        assert_eq!(
+            show("SHOW TABLES FROM incresql").unwrap().1,
+            Statement::ShowTables(Some("incresql".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_show_columns() {
+        assert_eq!(
+            show("SHOW COLUMNS FROM t").unwrap().1,
+            Statement::ShowColumns(ShowColumns {
+                database: None,
+                name: "t".to_string()
+            })
+        );
+        assert_eq!(
+            show("show fields in t from db").unwrap().1,
+            Statement::ShowColumns(ShowColumns {
+                database: Some("db".to_string()),
+                name: "t".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            describe("DESCRIBE db.t").unwrap().1,
+            Statement::ShowColumns(ShowColumns {
+                database: Some("db".to_string()),
+                name: "t".to_string()
+            })
+        );
+        assert_eq!(
+            describe("desc t").unwrap().1,
+            Statement::ShowColumns(ShowColumns {
+                database: None,
+                name: "t".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_show_index() {
+        assert_eq!(
+            show("SHOW INDEX FROM t").unwrap().1,
+            Statement::ShowIndex(ShowIndex {
+                database: None,
+                name: "t".to_string()
+            })
+        );
+        assert_eq!(
+            show("show keys in t from db").unwrap().1,
+            Statement::ShowIndex(ShowIndex {
+                database: Some("db".to_string()),
+                name: "t".to_string()
+            })
+        );
+        assert_eq!(
+            show("SHOW INDEXES FROM db.t").unwrap().1,
+            Statement::ShowIndex(ShowIndex {
+                database: Some("db".to_string()),
+                name: "t".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_show_variables() {
+        assert_eq!(
+            show("SHOW VARIABLES").unwrap().1,
+            Statement::ShowVariables(None)
+        );
+        assert_eq!(
+            show("show session variables like 'sql_%'").unwrap().1,
+            Statement::ShowVariables(Some("sql_%".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_show_collation() {
+        assert_eq!(
+            show("SHOW COLLATION").unwrap().1,
+            Statement::ShowCollation(None)
+        );
+    }
+
+    #[test]
+    fn test_show_character_set() {
+        assert_eq!(
+            show("SHOW CHARACTER SET LIKE 'utf8%'").unwrap().1,
+            Statement::ShowCharacterSet(Some("utf8%".to_string()))
+        );
+        assert_eq!(
+            show("SHOW CHARSET").unwrap().1,
+            Statement::ShowCharacterSet(None)
+        );
+    }
+
+    #[test]
+    fn test_show_processlist() {
+        assert_eq!(
+            show("SHOW PROCESSLIST").unwrap().1,
+            Statement::ShowProcesslist
+        );
+        assert_eq!(
+            show("SHOW FULL PROCESSLIST").unwrap().1,
+            Statement::ShowProcesslist
+        );
+    }
+
+    #[test]
+    fn test_show_warnings() {
+        assert_eq!(show("SHOW WARNINGS").unwrap().1, Statement::ShowWarnings);
     }
 
     #[test]
@@ -0,0 +1,96 @@
+use crate::whitespace::ws_0;
+use nom::combinator::all_consuming;
+
+/// Splits sql containing several statements (ie "SELECT 1; SELECT 2;") into the individual
+/// statements. Semicolons inside of quotes and comments don't end a statement, statements that
+/// are just whitespace or comments are dropped.
+pub fn split_statements(input: &str) -> Vec<&str> {
+    let bytes = input.as_bytes();
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b';' => {
+                push_statement(&input[start..idx], &mut statements);
+                start = idx + 1;
+                idx += 1;
+            }
+            quote @ b'\'' | quote @ b'"' | quote @ b'`' => {
+                idx = skip_quoted(bytes, idx, quote);
+            }
+            b'-' if bytes.get(idx + 1) == Some(&b'-') => {
+                idx = bytes[idx..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map_or(bytes.len(), |offset| idx + offset);
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                idx = input[idx + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |offset| idx + 2 + offset + 2);
+            }
+            _ => idx += 1,
+        }
+    }
+    push_statement(&input[start..], &mut statements);
+    statements
+}
+
+fn push_statement<'a>(statement: &'a str, statements: &mut Vec<&'a str>) {
+    if all_consuming(ws_0)(statement).is_err() {
+        statements.push(statement);
+    }
+}
+
+/// Returns the index just past the closing quote, a backslash escapes the next char within
+/// strings while a quote char can always be escaped by doubling it up.
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut idx = start + 1;
+    while idx < bytes.len() {
+        if bytes[idx] == b'\\' && quote != b'`' {
+            idx += 2;
+        } else if bytes[idx] == quote {
+            if bytes.get(idx + 1) == Some(&quote) {
+                idx += 2;
+            } else {
+                return idx + 1;
+            }
+        } else {
+            idx += 1;
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(split_statements("SELECT 1"), vec!["SELECT 1"]);
+        assert_eq!(split_statements("SELECT 1;"), vec!["SELECT 1"]);
+        assert_eq!(
+            split_statements("SELECT 1; SELECT 2 ;\n"),
+            vec!["SELECT 1", " SELECT 2 "]
+        );
+        assert_eq!(split_statements("SELECT 1;; -- done\n"), vec!["SELECT 1"]);
+        assert_eq!(split_statements("  ; /* nothing */"), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_split_statements_quoted() {
+        assert_eq!(
+            split_statements(r#"SELECT "a;b", 'c\';d', `e;f`; SELECT 'g'';h'"#),
+            vec![r#"SELECT "a;b", 'c\';d', `e;f`"#, r#" SELECT 'g'';h'"#]
+        );
+        assert_eq!(
+            split_statements("SELECT 1 -- a;b\n; SELECT /* c;d */ 2"),
+            vec!["SELECT 1 -- a;b\n", " SELECT /* c;d */ 2"]
+        );
+        // Unterminated quotes run to the end so the parser can complain about them
+        assert_eq!(split_statements("SELECT 'a;b"), vec!["SELECT 'a;b"]);
+    }
+}
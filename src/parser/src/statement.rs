@@ -1,40 +1,166 @@
-use crate::atoms::{identifier_str, kw, qualified_reference};
+use crate::alter::alter;
+use crate::atoms::{identifier_str, integer, kw, qualified_reference, quoted_string};
 use crate::create::create;
 use crate::delete::delete;
 use crate::drop::drop_;
-use crate::insert::insert;
+use crate::insert::{insert, replace};
+use crate::load::load_data;
 use crate::select::select;
-use crate::show::show;
+use crate::set::set;
+use crate::show::{describe, show};
 use crate::whitespace::ws_0;
 use crate::ParserResult;
 use ast::rel::logical::LogicalOperator;
-use ast::statement::{CompactTable, Explain, Statement};
+use ast::statement::{
+    AnalyzeTable, CompactTable, Compression, Explain, ExplainFormat, Kill, Savepoint,
+    SelectIntoOutfile, SinkFormat, Statement, Tail, Transaction,
+};
 use nom::branch::alt;
-use nom::combinator::{cut, map};
-use nom::sequence::{preceded, tuple};
+use nom::bytes::complete::tag;
+use nom::combinator::{cut, map, opt, value};
+use nom::error::context;
+use nom::sequence::{pair, preceded, tuple};
 
 pub fn statement(input: &str) -> ParserResult<Statement> {
-    alt((
-        map(logical_operator, Statement::Query),
-        show,
-        explain,
-        use_,
-        create,
-        drop_,
-        compact,
-    ))(input)
+    context(
+        "statement",
+        alt((
+            query,
+            show,
+            describe,
+            explain,
+            use_,
+            create,
+            drop_,
+            compact,
+            analyze,
+            alter,
+            set,
+            savepoint,
+            transaction,
+            load_data,
+            kill,
+            tail,
+            subscribe,
+        )),
+    )(input)
+}
+
+/// A query, optionally followed by INTO OUTFILE to write the results to a file.
+fn query(input: &str) -> ParserResult<Statement> {
+    map(
+        pair(logical_operator, opt(preceded(ws_0, into_outfile))),
+        |(query, outfile)| match outfile {
+            Some((path, format, (delimiter, quote), compression)) => {
+                Statement::SelectIntoOutfile(SelectIntoOutfile {
+                    query,
+                    path,
+                    format: format.unwrap_or(SinkFormat::Json),
+                    delimiter: delimiter.unwrap_or(b','),
+                    quote: quote.unwrap_or(b'"'),
+                    compression: compression.unwrap_or(Compression::None),
+                })
+            }
+            None => Statement::Query(query),
+        },
+    )(input)
+}
+
+/// INTO OUTFILE 'path' [FORMAT JSON|CSV] [FIELDS TERMINATED BY ',' [OPTIONALLY] ENCLOSED BY '"']
+/// [COMPRESSION GZIP|NONE]
+#[allow(clippy::type_complexity)]
+fn into_outfile(
+    input: &str,
+) -> ParserResult<(
+    String,
+    Option<SinkFormat>,
+    (Option<u8>, Option<u8>),
+    Option<Compression>,
+)> {
+    preceded(
+        tuple((kw("INTO"), ws_0, kw("OUTFILE"), ws_0)),
+        cut(tuple((
+            quoted_string,
+            opt(preceded(
+                tuple((ws_0, kw("FORMAT"), ws_0)),
+                cut(alt((
+                    value(SinkFormat::Json, kw("JSON")),
+                    value(SinkFormat::Csv, kw("CSV")),
+                ))),
+            )),
+            map(
+                opt(preceded(
+                    tuple((ws_0, alt((kw("FIELDS"), kw("COLUMNS"))))),
+                    pair(
+                        opt(preceded(
+                            tuple((ws_0, kw("TERMINATED"), ws_0, kw("BY"), ws_0)),
+                            single_char,
+                        )),
+                        opt(preceded(
+                            tuple((
+                                ws_0,
+                                opt(pair(kw("OPTIONALLY"), ws_0)),
+                                kw("ENCLOSED"),
+                                ws_0,
+                                kw("BY"),
+                                ws_0,
+                            )),
+                            single_char,
+                        )),
+                    ),
+                )),
+                Option::unwrap_or_default,
+            ),
+            opt(preceded(
+                tuple((ws_0, kw("COMPRESSION"), ws_0)),
+                cut(alt((
+                    value(Compression::Gzip, kw("GZIP")),
+                    value(Compression::None, kw("NONE")),
+                ))),
+            )),
+        ))),
+    )(input)
+}
+
+/// A quoted string that's used as a single byte, ie a delimiter
+fn single_char(input: &str) -> ParserResult<u8> {
+    map(quoted_string, |s| {
+        s.as_bytes().first().copied().unwrap_or(0)
+    })(input)
 }
 
 /// The logical operator statements, these can be used both as a standalone
 /// statement and as input to the explain operator
 fn logical_operator(input: &str) -> ParserResult<LogicalOperator> {
-    alt((select, insert, delete))(input)
+    alt((select, insert, replace, delete))(input)
 }
 
 fn explain(input: &str) -> ParserResult<Statement> {
     map(
-        preceded(kw("EXPLAIN"), cut(preceded(ws_0, logical_operator))),
-        |query| Statement::Explain(Explain { operator: query }),
+        preceded(
+            kw("EXPLAIN"),
+            cut(pair(
+                opt(preceded(ws_0, explain_format)),
+                preceded(ws_0, logical_operator),
+            )),
+        ),
+        |(format, query)| {
+            Statement::Explain(Explain {
+                operator: query,
+                format: format.unwrap_or(ExplainFormat::Text),
+            })
+        },
+    )(input)
+}
+
+/// FORMAT = TEXT|DOT
+fn explain_format(input: &str) -> ParserResult<ExplainFormat> {
+    preceded(
+        tuple((kw("FORMAT"), ws_0, tag("="), ws_0)),
+        cut(alt((
+            value(ExplainFormat::Text, kw("TEXT")),
+            value(ExplainFormat::Dot, kw("DOT")),
+        ))),
     )(input)
 }
 
@@ -49,12 +175,138 @@ fn compact(input: &str) -> ParserResult<Statement> {
     map(
         preceded(
             kw("COMPACT"),
+            cut(preceded(
+                tuple((ws_0, kw("TABLE"), ws_0)),
+                pair(
+                    qualified_reference,
+                    opt(preceded(
+                        tuple((ws_0, kw("PARTITION"), ws_0)),
+                        identifier_str,
+                    )),
+                ),
+            )),
+        ),
+        |((database, name), partition)| {
+            Statement::CompactTable(CompactTable {
+                database,
+                name,
+                partition,
+            })
+        },
+    )(input)
+}
+
+fn analyze(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            kw("ANALYZE"),
             cut(preceded(
                 tuple((ws_0, kw("TABLE"), ws_0)),
                 qualified_reference,
             )),
         ),
-        |(database, name)| Statement::CompactTable(CompactTable { database, name }),
+        |(database, name)| Statement::AnalyzeTable(AnalyzeTable { database, name }),
+    )(input)
+}
+
+/// BEGIN [WORK], START TRANSACTION, COMMIT [WORK] and ROLLBACK [WORK].
+/// Comes after savepoint so that ROLLBACK TO isn't taken as a plain ROLLBACK.
+fn transaction(input: &str) -> ParserResult<Statement> {
+    map(
+        alt((
+            value(
+                Transaction::Begin,
+                pair(kw("BEGIN"), opt(pair(ws_0, kw("WORK")))),
+            ),
+            value(
+                Transaction::Begin,
+                tuple((kw("START"), ws_0, kw("TRANSACTION"))),
+            ),
+            value(
+                Transaction::Commit,
+                pair(kw("COMMIT"), opt(pair(ws_0, kw("WORK")))),
+            ),
+            value(
+                Transaction::Rollback,
+                pair(kw("ROLLBACK"), opt(pair(ws_0, kw("WORK")))),
+            ),
+        )),
+        Statement::Transaction,
+    )(input)
+}
+
+/// SAVEPOINT name, ROLLBACK [WORK] TO [SAVEPOINT] name, RELEASE SAVEPOINT name
+fn savepoint(input: &str) -> ParserResult<Statement> {
+    map(
+        alt((
+            map(
+                preceded(pair(kw("SAVEPOINT"), ws_0), cut(identifier_str)),
+                Savepoint::Create,
+            ),
+            map(
+                preceded(
+                    tuple((
+                        kw("ROLLBACK"),
+                        opt(pair(ws_0, kw("WORK"))),
+                        ws_0,
+                        kw("TO"),
+                        ws_0,
+                        opt(pair(kw("SAVEPOINT"), ws_0)),
+                    )),
+                    cut(identifier_str),
+                ),
+                Savepoint::RollbackTo,
+            ),
+            map(
+                preceded(
+                    tuple((kw("RELEASE"), ws_0, kw("SAVEPOINT"), ws_0)),
+                    cut(identifier_str),
+                ),
+                Savepoint::Release,
+            ),
+        )),
+        Statement::Savepoint,
+    )(input)
+}
+
+/// KILL [CONNECTION | QUERY] id
+fn kill(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            kw("KILL"),
+            cut(pair(
+                opt(preceded(
+                    ws_0,
+                    alt((value(false, kw("CONNECTION")), value(true, kw("QUERY")))),
+                )),
+                preceded(ws_0, integer),
+            )),
+        ),
+        |(query_only, connection_id)| {
+            Statement::Kill(Kill {
+                connection_id: connection_id as u32,
+                query_only: query_only.unwrap_or(false),
+            })
+        },
+    )(input)
+}
+
+/// TAIL t
+fn tail(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(kw("TAIL"), cut(preceded(ws_0, qualified_reference))),
+        |(database, name)| Statement::Tail(Tail { database, name }),
+    )(input)
+}
+
+/// SUBSCRIBE TO query
+fn subscribe(input: &str) -> ParserResult<Statement> {
+    map(
+        preceded(
+            kw("SUBSCRIBE"),
+            cut(preceded(tuple((ws_0, kw("TO"), ws_0)), select)),
+        ),
+        Statement::Subscribe,
     )(input)
 }
 
@@ -100,6 +352,22 @@ mod tests {
                     },],
                     source: Box::from(LogicalOperator::Single)
                 }),
+                format: ExplainFormat::Text
+            })
+        );
+
+        assert_eq!(
+            statement("EXPLAIN FORMAT = DOT SELECT 1").unwrap().1,
+            Statement::Explain(Explain {
+                operator: LogicalOperator::Project(Project {
+                    distinct: false,
+                    expressions: vec![NamedExpression {
+                        expression: Expression::from(1),
+                        alias: None
+                    },],
+                    source: Box::from(LogicalOperator::Single)
+                }),
+                format: ExplainFormat::Dot
             })
         );
     }
@@ -117,9 +385,164 @@ mod tests {
         assert_eq!(
             statement("Compact table foo.bar").unwrap().1,
             Statement::CompactTable(CompactTable {
+                database: Some("foo".to_string()),
+                name: "bar".to_string(),
+                partition: None
+            })
+        );
+
+        assert_eq!(
+            statement("COMPACT TABLE bar PARTITION p0").unwrap().1,
+            Statement::CompactTable(CompactTable {
+                database: None,
+                name: "bar".to_string(),
+                partition: Some("p0".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_analyze() {
+        assert_eq!(
+            statement("ANALYZE TABLE bar").unwrap().1,
+            Statement::AnalyzeTable(AnalyzeTable {
+                database: None,
+                name: "bar".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_savepoint() {
+        assert_eq!(
+            statement("SAVEPOINT s1").unwrap().1,
+            Statement::Savepoint(Savepoint::Create("s1".to_string()))
+        );
+        assert_eq!(
+            statement("rollback to savepoint s1").unwrap().1,
+            Statement::Savepoint(Savepoint::RollbackTo("s1".to_string()))
+        );
+        assert_eq!(
+            statement("ROLLBACK WORK TO s1").unwrap().1,
+            Statement::Savepoint(Savepoint::RollbackTo("s1".to_string()))
+        );
+        assert_eq!(
+            statement("RELEASE SAVEPOINT s1").unwrap().1,
+            Statement::Savepoint(Savepoint::Release("s1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_transaction() {
+        assert_eq!(
+            statement("BEGIN").unwrap().1,
+            Statement::Transaction(Transaction::Begin)
+        );
+        assert_eq!(
+            statement("start transaction").unwrap().1,
+            Statement::Transaction(Transaction::Begin)
+        );
+        assert_eq!(
+            statement("COMMIT WORK").unwrap().1,
+            Statement::Transaction(Transaction::Commit)
+        );
+        assert_eq!(
+            statement("ROLLBACK").unwrap().1,
+            Statement::Transaction(Transaction::Rollback)
+        );
+    }
+
+    #[test]
+    fn test_kill() {
+        assert_eq!(
+            statement("KILL 12").unwrap().1,
+            Statement::Kill(Kill {
+                connection_id: 12,
+                query_only: false
+            })
+        );
+        assert_eq!(
+            statement("kill connection 3").unwrap().1,
+            Statement::Kill(Kill {
+                connection_id: 3,
+                query_only: false
+            })
+        );
+        assert_eq!(
+            statement("KILL QUERY 4").unwrap().1,
+            Statement::Kill(Kill {
+                connection_id: 4,
+                query_only: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_tail() {
+        assert_eq!(
+            statement("TAIL foo.bar").unwrap().1,
+            Statement::Tail(Tail {
                 database: Some("foo".to_string()),
                 name: "bar".to_string()
             })
         );
     }
+
+    #[test]
+    fn test_subscribe() {
+        assert_eq!(
+            statement("SUBSCRIBE TO SELECT 1").unwrap().1,
+            Statement::Subscribe(LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    expression: Expression::from(1),
+                    alias: None
+                },],
+                source: Box::from(LogicalOperator::Single)
+            }))
+        );
+        assert!(statement("SUBSCRIBE TO INSERT INTO t VALUES (1)").is_err());
+    }
+
+    #[test]
+    fn test_select_into_outfile() {
+        let query = LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: vec![NamedExpression {
+                expression: Expression::from(1),
+                alias: None,
+            }],
+            source: Box::from(LogicalOperator::Single),
+        });
+        assert_eq!(
+            statement("SELECT 1 INTO OUTFILE '/tmp/out.json'")
+                .unwrap()
+                .1,
+            Statement::SelectIntoOutfile(SelectIntoOutfile {
+                query: query.clone(),
+                path: "/tmp/out.json".to_string(),
+                format: SinkFormat::Json,
+                delimiter: b',',
+                quote: b'"',
+                compression: Compression::None
+            })
+        );
+        assert_eq!(
+            statement(
+                "SELECT 1 into outfile 'out.csv.gz' format csv fields terminated by '|' \
+                optionally enclosed by \"'\" compression gzip"
+            )
+            .unwrap()
+            .1,
+            Statement::SelectIntoOutfile(SelectIntoOutfile {
+                query,
+                path: "out.csv.gz".to_string(),
+                format: SinkFormat::Csv,
+                delimiter: b'|',
+                quote: b'\'',
+                compression: Compression::Gzip
+            })
+        );
+        assert!(statement("SELECT 1 INTO OUTFILE '/tmp/out' FORMAT XML").is_err());
+    }
 }
@@ -16,6 +16,28 @@ pub enum PlannerError {
     InsertMismatch(Vec<DataType>, Vec<DataType>),
     // function name, location name(ie where clause, sort expression)
     AggregateNotAllowed(&'static str, &'static str),
+    // expected column count, actual column count
+    RowValueMismatch(usize, usize),
+    SubqueryNotSupported(&'static str),
+    DistinctAggregateNotSupported(&'static str),
+    HopWindowNotSupported(&'static str),
+    // The name of the view or external table
+    AsOfNotSupported(String),
+    // Inserting into a view or external table
+    InsertNotSupported,
+    FileScanError(String),
+    // Inserting a null into a NOT NULL column, the column name
+    NullNotAllowed(String),
+    // Inserting an integer literal that doesn't fit the column's type, the column name
+    OutOfRange(String),
+    // Reading a table only root can see, the user and table names
+    TableAccessDenied(String, String),
+    // Reading files directly when not root, the user
+    FileAccessDenied(String),
+    // A column default or generated column that doesn't compile, the column name and why
+    InvalidColumnDefault(String, String),
+    // Inserting a value into a generated column, the column and table names
+    GeneratedColumnValue(String, String),
 }
 
 impl From<FunctionResolutionError> for PlannerError {
@@ -87,6 +109,51 @@ impl Display for PlannerError {
             PlannerError::AggregateNotAllowed(function_name, location) => {
                 f.write_fmt(format_args!("Aggregate function {} found in {},\nAggregate functions can only be used in select clauses", function_name, location))
             }
+            PlannerError::RowValueMismatch(expected, actual) => f.write_fmt(format_args!(
+                "Operand should contain {} column(s), found {}",
+                expected, actual
+            )),
+            PlannerError::SubqueryNotSupported(reason) => {
+                f.write_fmt(format_args!("Subquery not supported, {}", reason))
+            }
+            PlannerError::DistinctAggregateNotSupported(reason) => {
+                f.write_fmt(format_args!("DISTINCT aggregate not supported, {}", reason))
+            }
+            PlannerError::HopWindowNotSupported(reason) => {
+                f.write_fmt(format_args!("Hopping window not supported, {}", reason))
+            }
+            PlannerError::AsOfNotSupported(view) => f.write_fmt(format_args!(
+                "AS OF TIMESTAMP is only supported on tables, {} is not a table",
+                view
+            )),
+            PlannerError::InsertNotSupported => {
+                f.write_str("Only tables can be inserted into or deleted from")
+            }
+            PlannerError::FileScanError(err) => {
+                f.write_fmt(format_args!("Error listing files, {}", err))
+            }
+            PlannerError::NullNotAllowed(column) => {
+                f.write_fmt(format_args!("Column '{}' cannot be null", column))
+            }
+            PlannerError::OutOfRange(column) => {
+                f.write_fmt(format_args!("Out of range value for column '{}'", column))
+            }
+            PlannerError::TableAccessDenied(user, table) => f.write_fmt(format_args!(
+                "SELECT command denied to user '{}' for table '{}'",
+                user, table
+            )),
+            PlannerError::FileAccessDenied(user) => f.write_fmt(format_args!(
+                "Access denied for user '{}'; you need the FILE privilege to read files",
+                user
+            )),
+            PlannerError::InvalidColumnDefault(column, reason) => f.write_fmt(format_args!(
+                "Invalid default value for '{}' - {}",
+                column, reason
+            )),
+            PlannerError::GeneratedColumnValue(column, table) => f.write_fmt(format_args!(
+                "The value specified for generated column '{}' in table '{}' is not allowed",
+                column, table
+            )),
         }
     }
 }
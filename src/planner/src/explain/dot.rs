@@ -0,0 +1,109 @@
+use crate::explain::{ExplainNode, TableScans};
+use crate::Planner;
+use ast::expr::Expression;
+use ast::rel::logical::{LogicalOperator, Values};
+use data::DataType;
+
+impl Planner {
+    /// Produce a plan that prints out the execution plan as a graphviz dot graph, one line per
+    /// row so it can be piped straight into `dot`.
+    pub fn explain_dot<N: ExplainNode>(&self, node: &N) -> LogicalOperator {
+        let mut lines = vec![
+            "digraph plan {".to_string(),
+            "  node [shape=box, fontname=\"monospace\"];".to_string(),
+        ];
+        let mut next_id = 0;
+        let mut scans = node.table_scans(&self.function_registry);
+        render_node(node, &mut lines, &mut next_id, &mut scans);
+        lines.push("}".to_string());
+
+        LogicalOperator::Values(Values {
+            fields: vec![(DataType::Text, String::from("dot"))],
+            data: lines
+                .into_iter()
+                .map(|line| vec![Expression::from(line)])
+                .collect(),
+        })
+    }
+}
+
+/// Renders the node and its children, returning the id given to the node.
+fn render_node<N: ExplainNode>(
+    node: &N,
+    lines: &mut Vec<String>,
+    next_id: &mut usize,
+    scans: &mut TableScans,
+) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+
+    let mut label = vec![node.node_name()];
+    for expr in node.expressions() {
+        label.push(format!(
+            "{}: {}",
+            expr.alias.as_deref().unwrap_or(""),
+            expr.expression
+        ));
+    }
+    for (key, value) in node.access_details(scans) {
+        label.push(format!("{}: {}", key, value));
+    }
+    if let Some((limit, offset)) = node.limit_offset() {
+        label.push(format!("limit: {}", limit));
+        label.push(format!("offset: {}", offset));
+    }
+    if let Some(predicate) = node.predicate() {
+        label.push(format!("predicate: {}", predicate));
+    }
+    if !node.grouping_keys().is_empty() {
+        let keys: Vec<_> = node.grouping_keys().iter().map(|k| k.to_string()).collect();
+        label.push(format!("group_keys: {}", keys.join(", ")));
+    }
+    if !node.sort_expressions().is_empty() {
+        let sort_exprs: Vec<_> = node
+            .sort_expressions()
+            .iter()
+            .map(|se| match se.nulls {
+                Some(nulls) => format!("{} ({} {})", se.expression, se.ordering, nulls),
+                None => format!("{} ({})", se.expression, se.ordering),
+            })
+            .collect();
+        label.push(format!("sort_exprs: {}", sort_exprs.join(", ")));
+    }
+    if let Some(rows) = node.estimated_rows() {
+        label.push(format!("rows: ~{:.0}", rows));
+    }
+
+    // Each line is left justified with \l
+    let label: String = label
+        .iter()
+        .map(|line| format!("{}\\l", escape(line)))
+        .collect();
+    lines.push(format!("  n{} [label=\"{}\"];", id, label));
+
+    for (source_name, source) in node.child_nodes() {
+        let source_id = render_node(source, lines, next_id, scans);
+        lines.push(format!(
+            "  n{} -> n{} [label=\"{}\"];",
+            source_id,
+            id,
+            escape(&source_name)
+        ));
+    }
+    id
+}
+
+/// Escapes a string for use within a quoted dot string.
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"a "b" \c"#), r#"a \"b\" \\c"#);
+    }
+}
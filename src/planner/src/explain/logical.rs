@@ -1,9 +1,105 @@
-use crate::explain::ExplainNode;
+use crate::explain::{ExplainNode, TableScans};
+use crate::p4_pit_planning::build_point_in_time;
+use crate::utils::selectivity::{
+    estimate_row_count, estimate_selectivity, statistics_for_operator,
+};
 use ast::expr::{Expression, NamedExpression, SortExpression};
-use ast::rel::logical::LogicalOperator;
-use data::DataType;
+use ast::rel::logical::{JoinType, LogicalOperator, ResolvedTable, SetOperator};
+use ast::rel::point_in_time::{KeyRange, PointInTimeOperator};
+use data::{DataType, Datum};
+use functions::registry::Registry;
 use std::borrow::Cow;
 
+/// The type of scan used to read the table along with any key ranges, the index used and the
+/// columns decoded (when not all of them are).
+fn scan_details(table: &ResolvedTable, scans: &mut TableScans) -> Vec<(String, String)> {
+    let mut table_ids = vec![table.table.id()];
+    if let Some(partitioning) = &table.partitioning {
+        table_ids.extend(partitioning.partitions.iter().map(|p| p.table.id()));
+    }
+    table_ids.extend(table.secondary_indexes.iter().map(|index| index.table.id()));
+    table_ids.extend(table.fulltext_indexes.iter().map(|index| index.table.id()));
+
+    let mut details = vec![];
+    match scans.take(&table_ids) {
+        Some(PointInTimeOperator::TableScan(scan)) => {
+            let index = table
+                .secondary_indexes
+                .iter()
+                .find(|index| index.table.id() == scan.table.id());
+            let access = match (index, &scan.ranges) {
+                (Some(_), _) => "INDEX_SCAN",
+                (None, Some(_)) => "RANGE_SCAN",
+                (None, None) => "FULL_SCAN",
+            };
+            details.push(("access".to_string(), access.to_string()));
+            if let Some(index) = index {
+                details.push(("index".to_string(), index.name.clone()));
+            }
+            // The columns of the table being scanned, an index's rows are the indexed columns
+            // followed by the table's row.
+            let scanned_columns: Vec<_> = index
+                .iter()
+                .flat_map(|index| index.columns.iter().map(|offset| &table.columns[*offset]))
+                .chain(table.columns.iter())
+                .collect();
+            let key_types: Vec<_> = scanned_columns
+                .iter()
+                .map(|(_, datatype)| *datatype)
+                .collect();
+            for range in scan.ranges.iter().flatten() {
+                details.push(("range".to_string(), render_range(range, &key_types)));
+            }
+            if let Some(column_mask) = &scan.column_mask {
+                let columns: Vec<_> = scanned_columns
+                    .iter()
+                    .zip(column_mask)
+                    .filter(|(_, needed)| **needed)
+                    .map(|((alias, _), _)| alias.as_str())
+                    .collect();
+                let columns = if columns.is_empty() {
+                    "NONE".to_string()
+                } else {
+                    columns.join(", ")
+                };
+                details.push(("decoded_columns".to_string(), columns));
+            }
+        }
+        Some(PointInTimeOperator::FulltextScan(scan)) => {
+            details.push(("access".to_string(), "FULLTEXT_SCAN".to_string()));
+            details.push(("index".to_string(), scan.index.name.clone()));
+            details.push(("terms".to_string(), scan.terms.join(", ")));
+        }
+        // Every partition has been pruned away
+        _ => details.push(("access".to_string(), "NONE".to_string())),
+    }
+    details
+}
+
+/// Renders the range as ie "from (1, 'a') to (1)", open bounds are left out.
+fn render_range(range: &KeyRange, key_types: &[DataType]) -> String {
+    let render_key = |key: &[Datum]| {
+        let datums: Vec<_> = key
+            .iter()
+            .zip(key_types)
+            .map(|(datum, datatype)| format!("{:#}", datum.typed_with(*datatype)))
+            .collect();
+        format!("({})", datums.join(", "))
+    };
+    let mut bounds = vec![];
+    if let Some(from) = &range.from {
+        bounds.push(format!("from {}", render_key(from)));
+    }
+    if let Some(to) = &range.to {
+        bounds.push(format!("to {}", render_key(to)));
+    }
+    if bounds.is_empty() {
+        "all".to_string()
+    } else {
+        bounds.join(" ")
+    }
+}
+
 impl ExplainNode for LogicalOperator {
     fn node_name(&self) -> String {
         match self {
@@ -21,10 +117,24 @@ impl ExplainNode for LogicalOperator {
                 format!("{}({})", table_alias.source.node_name(), table_alias.alias)
             }
             LogicalOperator::UnionAll(_) => "UNION_ALL".to_string(),
+            LogicalOperator::SetOperation(set_operation) => match set_operation.operator {
+                SetOperator::Union => "UNION".to_string(),
+                SetOperator::Intersect => "INTERSECT".to_string(),
+                SetOperator::Except => "EXCEPT".to_string(),
+            },
+            LogicalOperator::TableInsert(insert) if insert.delete_matching => "DELETE".to_string(),
             LogicalOperator::TableInsert(_) => "INSERT".to_string(),
             LogicalOperator::NegateFreq(_) => "NEGATE".to_string(),
             LogicalOperator::FileScan(_) => "FILE_SCAN".to_string(),
-            LogicalOperator::Join(_) => "JOIN".to_string(),
+            LogicalOperator::JsonTable(json_table) => match &json_table.alias {
+                Some(alias) => format!("JSON_TABLE({})", alias),
+                None => "JSON_TABLE".to_string(),
+            },
+            LogicalOperator::Join(join) => match join.join_type {
+                JoinType::Semi => "SEMI_JOIN".to_string(),
+                JoinType::Anti => "ANTI_JOIN".to_string(),
+                JoinType::Inner | JoinType::LeftOuter => "JOIN".to_string(),
+            },
         }
     }
 
@@ -41,11 +151,82 @@ impl ExplainNode for LogicalOperator {
         match self {
             LogicalOperator::TableAlias(table_alias) => table_alias.source.table_columns(),
             LogicalOperator::ResolvedTable(table) => Cow::from(&table.columns),
+            LogicalOperator::FileScan(file_scan) if !file_scan.columns.is_empty() => {
+                Cow::from(&file_scan.columns)
+            }
             LogicalOperator::FileScan(_) => Cow::from(vec![("data".to_string(), DataType::Json)]),
+            LogicalOperator::JsonTable(json_table) => Cow::from(
+                json_table
+                    .columns
+                    .iter()
+                    .map(|column| (column.name.clone(), column.datatype))
+                    .collect::<Vec<_>>(),
+            ),
             _ => Cow::from(vec![]),
         }
     }
 
+    fn table_scans(&self, function_registry: &Registry) -> TableScans {
+        TableScans::new(&build_point_in_time(self.clone(), function_registry))
+    }
+
+    fn access_details(&self, scans: &mut TableScans) -> Vec<(String, String)> {
+        match self {
+            LogicalOperator::TableAlias(table_alias) => table_alias.source.access_details(scans),
+            LogicalOperator::ResolvedTable(table) => {
+                let timestamp = match table.as_of {
+                    Some(as_of) => as_of.ms.to_string(),
+                    None => "LATEST".to_string(),
+                };
+                let mut details = scan_details(table, scans);
+                details.push(("timestamp".to_string(), timestamp));
+                // The partitions left to scan after pruning
+                if let Some(partitioning) = &table.partitioning {
+                    let partitions: Vec<_> = partitioning
+                        .partitions
+                        .iter()
+                        .filter(|partition| !partition.pruned)
+                        .map(|partition| partition.name.as_str())
+                        .collect();
+                    details.push(("partitions".to_string(), partitions.join(", ")));
+                }
+                details
+            }
+            LogicalOperator::FileScan(file_scan) => {
+                let mut details = vec![
+                    ("access".to_string(), "FULL_SCAN".to_string()),
+                    ("directory".to_string(), file_scan.directory.clone()),
+                ];
+                // The partition directories left to scan after pruning
+                if file_scan.partition_columns > 0 {
+                    let partitions: Vec<_> = file_scan
+                        .partitions
+                        .iter()
+                        .filter(|partition| !partition.pruned)
+                        .map(|partition| partition.name.as_str())
+                        .collect();
+                    details.push(("partitions".to_string(), partitions.join(", ")));
+                }
+                details
+            }
+            LogicalOperator::JsonTable(json_table) => vec![
+                ("document".to_string(), json_table.document.to_string()),
+                ("path".to_string(), json_table.path.original().to_string()),
+            ],
+            // Only shown once the tables have been analyzed, without stats it's just a guess.
+            LogicalOperator::Filter(filter) => {
+                let statistics = statistics_for_operator(&filter.source);
+                if statistics.iter().any(Option::is_some) {
+                    let selectivity = estimate_selectivity(&filter.predicate, &statistics);
+                    vec![("selectivity".to_string(), format!("{:.4}", selectivity))]
+                } else {
+                    vec![]
+                }
+            }
+            _ => vec![],
+        }
+    }
+
     fn limit_offset(&self) -> Option<(i64, i64)> {
         match self {
             LogicalOperator::TableAlias(table_alias) => table_alias.source.limit_offset(),
@@ -79,6 +260,10 @@ impl ExplainNode for LogicalOperator {
         }
     }
 
+    fn estimated_rows(&self) -> Option<f64> {
+        estimate_row_count(self)
+    }
+
     fn child_nodes(&self) -> Vec<(String, &Self)> {
         match self {
             LogicalOperator::GroupBy(group) => vec![("source".to_string(), group.source.as_ref())],
@@ -90,6 +275,10 @@ impl ExplainNode for LogicalOperator {
                 ("left".to_string(), join.left.as_ref()),
                 ("right".to_string(), join.right.as_ref()),
             ],
+            LogicalOperator::SetOperation(set_operation) => vec![
+                ("left".to_string(), set_operation.left.as_ref()),
+                ("right".to_string(), set_operation.right.as_ref()),
+            ],
             LogicalOperator::Filter(filter) => vec![("source".to_string(), filter.source.as_ref())],
             LogicalOperator::Limit(limit) => vec![("source".to_string(), limit.source.as_ref())],
             LogicalOperator::TableAlias(table_alias) => table_alias.source.child_nodes(),
@@ -103,6 +292,9 @@ impl ExplainNode for LogicalOperator {
                 vec![("source".to_string(), insert.source.as_ref())]
             }
             LogicalOperator::NegateFreq(source) => vec![("source".to_string(), source.as_ref())],
+            LogicalOperator::JsonTable(json_table) => {
+                vec![("source".to_string(), json_table.source.as_ref())]
+            }
             LogicalOperator::Values(_)
             | LogicalOperator::ResolvedTable(_)
             | LogicalOperator::Single
@@ -1,10 +1,13 @@
+mod dot;
 mod logical;
 
 use crate::utils::expr::type_for_expression;
 use crate::Planner;
 use ast::expr::{Expression, NamedExpression, SortExpression};
 use ast::rel::logical::{LogicalOperator, Values};
+use ast::rel::point_in_time::PointInTimeOperator;
 use data::DataType;
+use functions::registry::Registry;
 use std::borrow::Cow;
 use std::fmt::{Display, Formatter};
 
@@ -21,6 +24,14 @@ pub trait ExplainNode {
     // the expression itself
     fn table_columns(&self) -> Cow<[(String, DataType)]>;
 
+    // How the tables are actually read, taken from the point in time plan built from this node.
+    fn table_scans(&self, function_registry: &Registry) -> TableScans;
+
+    // Details of how data is read from storage, ie the type of scan, any scan bounds and the
+    // timestamp the data is read at. Rendered as "key: value" lines. Table nodes take their
+    // scan from scans.
+    fn access_details(&self, scans: &mut TableScans) -> Vec<(String, String)>;
+
     fn limit_offset(&self) -> Option<(i64, i64)>;
 
     fn predicate(&self) -> Option<&Expression>;
@@ -29,6 +40,9 @@ pub trait ExplainNode {
 
     fn grouping_keys(&self) -> &[Expression];
 
+    // The estimated number of rows output, None if we don't have the statistics to tell.
+    fn estimated_rows(&self) -> Option<f64>;
+
     // The "sources" for this node, most nodes will return a single source, but things
     // like unions may return many sources.
     fn child_nodes(&self) -> Vec<(String, &Self)>;
@@ -44,7 +58,8 @@ impl Planner {
     pub fn explain<N: ExplainNode>(&self, node: &N) -> LogicalOperator {
         let mut lines = vec![];
         let mut padding = Padding::default();
-        render_node(node, &mut lines, &mut padding);
+        let mut scans = node.table_scans(&self.function_registry);
+        render_node(node, &mut lines, &mut padding, &mut scans);
 
         let data = lines
             .into_iter()
@@ -82,6 +97,73 @@ impl Planner {
     }
 }
 
+/// The table and full-text scans of a point in time plan in the order they appear, the table
+/// nodes of the logical plan claim theirs as they're rendered (which is in the same order).
+#[derive(Debug, Default)]
+pub struct TableScans {
+    scans: Vec<PointInTimeOperator>,
+}
+
+impl TableScans {
+    pub fn new(plan: &PointInTimeOperator) -> Self {
+        let mut scans = TableScans::default();
+        scans.collect(plan);
+        scans
+    }
+
+    fn collect(&mut self, plan: &PointInTimeOperator) {
+        match plan {
+            PointInTimeOperator::TableScan(_) | PointInTimeOperator::FulltextScan(_) => {
+                self.scans.push(plan.clone())
+            }
+            PointInTimeOperator::Single
+            | PointInTimeOperator::Values(_)
+            | PointInTimeOperator::FileScan(_) => {}
+            PointInTimeOperator::Project(project) => self.collect(&project.source),
+            PointInTimeOperator::Filter(filter) => self.collect(&filter.source),
+            PointInTimeOperator::JsonTable(json_table) => self.collect(&json_table.source),
+            PointInTimeOperator::Limit(limit) => self.collect(&limit.source),
+            PointInTimeOperator::Sort(sort) => self.collect(&sort.source),
+            PointInTimeOperator::NegateFreq(source) => self.collect(source),
+            PointInTimeOperator::Gather(gather) => self.collect(&gather.source),
+            PointInTimeOperator::TableInsert(insert) => self.collect(&insert.source),
+            PointInTimeOperator::SortedGroup(group) | PointInTimeOperator::HashGroup(group) => {
+                self.collect(&group.source)
+            }
+            PointInTimeOperator::HashJoin(join) => {
+                self.collect(&join.left);
+                self.collect(&join.right);
+            }
+            PointInTimeOperator::UnionAll(union_all) => {
+                for source in &union_all.sources {
+                    self.collect(source);
+                }
+            }
+        }
+    }
+
+    /// Takes the first scan reading from any of the tables (a table along with its partitions
+    /// and indexes), along with the scans of the table's other partitions. None if the table
+    /// isn't read at all, ie all its partitions have been pruned.
+    pub fn take(&mut self, table_ids: &[u32]) -> Option<PointInTimeOperator> {
+        let mut taken: Vec<(u32, PointInTimeOperator)> = vec![];
+        let mut idx = 0;
+        while idx < self.scans.len() {
+            let id = match &self.scans[idx] {
+                PointInTimeOperator::TableScan(scan) => scan.table.id(),
+                PointInTimeOperator::FulltextScan(scan) => scan.index.table.id(),
+                _ => unreachable!(),
+            };
+            if table_ids.contains(&id) && taken.iter().all(|(taken_id, _)| *taken_id != id) {
+                taken.push((id, self.scans.remove(idx)));
+            } else {
+                idx += 1;
+            }
+        }
+        taken.into_iter().next().map(|(_, scan)| scan)
+    }
+}
+
 /// A single explain line
 struct ExplainLine {
     tree: String,
@@ -125,7 +207,12 @@ impl ExplainLine {
     }
 }
 
-fn render_node<N: ExplainNode>(node: &N, lines: &mut Vec<ExplainLine>, padding: &mut Padding) {
+fn render_node<N: ExplainNode>(
+    node: &N,
+    lines: &mut Vec<ExplainLine>,
+    padding: &mut Padding,
+    scans: &mut TableScans,
+) {
     lines.push(ExplainLine::tree_only(padding, &node.node_name()));
     padding.push(" |");
     // output cols
@@ -154,6 +241,14 @@ fn render_node<N: ExplainNode>(node: &N, lines: &mut Vec<ExplainLine>, padding:
         padding.pop();
     }
 
+    // storage access
+    for (key, value) in node.access_details(scans) {
+        lines.push(ExplainLine::tree_only(
+            padding,
+            &format!("{}: {}", key, value),
+        ));
+    }
+
     // limit/offset
     if let Some((limit, offset)) = node.limit_offset() {
         lines.push(ExplainLine::tree_only(
@@ -196,7 +291,10 @@ fn render_node<N: ExplainNode>(node: &N, lines: &mut Vec<ExplainLine>, padding:
             lines.push(ExplainLine::expr_only(
                 padding,
                 type_for_expression(&se.expression),
-                format!("{} ({})", &se.expression, se.ordering),
+                match se.nulls {
+                    Some(nulls) => format!("{} ({} {})", &se.expression, se.ordering, nulls),
+                    None => format!("{} ({})", &se.expression, se.ordering),
+                },
             ));
         }
         padding.pop();
@@ -209,7 +307,7 @@ fn render_node<N: ExplainNode>(node: &N, lines: &mut Vec<ExplainLine>, padding:
             &format!("{}:", source_name),
         ));
         padding.push("  ");
-        render_node(*source, lines, padding);
+        render_node(*source, lines, padding, scans);
         padding.pop();
     }
 
@@ -2,6 +2,7 @@ mod p1_validation;
 mod p2_optimization;
 mod p3_common_transforms;
 mod p4_pit_planning;
+mod plan_cache;
 mod utils;
 
 use data::{DataType, Session};
@@ -14,12 +15,14 @@ use catalog::Catalog;
 pub use error::*;
 use functions::registry::Registry;
 pub use p4_pit_planning::PointInTimePlan;
+use plan_cache::{PlanCache, PLAN_CACHE_ENTRIES};
 use std::sync::RwLock;
 
 #[derive(Debug)]
 pub struct Planner {
     pub function_registry: Registry,
     pub catalog: RwLock<Catalog>,
+    plan_cache: PlanCache,
 }
 
 impl Planner {
@@ -27,6 +30,7 @@ impl Planner {
         Planner {
             function_registry,
             catalog: RwLock::new(catalog),
+            plan_cache: PlanCache::new(PLAN_CACHE_ENTRIES),
         }
     }
 
@@ -43,6 +47,15 @@ impl Planner {
         session: &Session,
     ) -> Result<(Vec<Field>, LogicalOperator), PlannerError> {
         let query = self.validate(query, session)?;
+        self.plan_validated(query, session)
+    }
+
+    /// Runs the optimization phases over an already validated query
+    fn plan_validated(
+        &self,
+        query: LogicalOperator,
+        session: &Session,
+    ) -> Result<(Vec<Field>, LogicalOperator), PlannerError> {
         let query = self.optimize(query, session)?;
         let query = self.common_transforms(query, session)?;
         let fields = fields_for_operator(&query).collect();
@@ -58,4 +71,18 @@ pub struct Field {
     pub qualifier: Option<String>,
     pub alias: String,
     pub data_type: DataType,
+    // False only when we know the column can never contain nulls
+    pub nullable: bool,
+    // Set when the field is read straight out of a table column
+    pub origin: Option<FieldOrigin>,
+}
+
+/// The table column a field was read from, clients get sent this as part of the column metadata.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FieldOrigin {
+    pub database: String,
+    // The name the table goes by in the query
+    pub table_alias: String,
+    pub table: String,
+    pub column: String,
 }
@@ -0,0 +1,177 @@
+use crate::utils::expr::type_for_expression;
+use crate::PlannerError;
+use ast::expr::{CompiledFunctionCall, Expression};
+use ast::rel::logical::LogicalOperator;
+use data::{Collation, DataType, Session};
+use functions::registry::Registry;
+use functions::{FunctionSignature, FunctionType};
+
+/// The comparisons that need to follow the collation when comparing text.
+const COMPARISONS: &[&str] = &["=", "!=", "<=>", "<", "<=", ">", ">=", "between"];
+
+/// Makes text comparisons, sorts and group bys follow the session's collation.
+/// Text is stored (and indexed) as is, so for a case insensitive collation any text being
+/// compared, sorted or grouped on is wrapped in collation_key which transforms it into a form
+/// that compares case insensitively.
+/// ie
+/// Filter(name = "bob")
+/// becomes
+/// Filter(collation_key(name) = collation_key("bob"))
+/// As the key ranges only work for binary comparisons, wrapping the columns also stops them
+/// being used to narrow down table scans.
+pub(super) fn apply_collation(
+    operator: &mut LogicalOperator,
+    function_registry: &Registry,
+    session: &Session,
+) -> Result<(), PlannerError> {
+    if session.collation() == Collation::Binary {
+        return Ok(());
+    }
+    apply_collation_impl(operator, function_registry)
+}
+
+fn apply_collation_impl(
+    operator: &mut LogicalOperator,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    for child in operator.children_mut() {
+        apply_collation_impl(child, function_registry)?;
+    }
+    for subquery in operator.subqueries_mut() {
+        apply_collation_impl(subquery, function_registry)?;
+    }
+
+    for expression in operator.expressions_mut() {
+        collate_comparisons(expression, function_registry)?;
+    }
+    match operator {
+        LogicalOperator::GroupBy(group_by) => {
+            for expression in &mut group_by.key_expressions {
+                collate(expression, function_registry)?;
+            }
+        }
+        LogicalOperator::Sort(sort) => {
+            for sort_expression in &mut sort.sort_expressions {
+                collate(&mut sort_expression.expression, function_registry)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn collate_comparisons(
+    expression: &mut Expression,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    for child in expression.children_mut() {
+        collate_comparisons(child, function_registry)?;
+    }
+    if let Expression::CompiledFunctionCall(function_call) = expression {
+        if COMPARISONS.contains(&function_call.signature.name)
+            && function_call
+                .signature
+                .args
+                .iter()
+                .all(|datatype| *datatype == DataType::Text)
+        {
+            for arg in function_call.args.iter_mut() {
+                collate(arg, function_registry)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Wraps the expression in collation_key if it's text.
+fn collate(expression: &mut Expression, function_registry: &Registry) -> Result<(), PlannerError> {
+    if type_for_expression(expression) != DataType::Text || is_collated(expression) {
+        return Ok(());
+    }
+    let lookup_sig = FunctionSignature {
+        name: "collation_key",
+        args: vec![DataType::Text],
+        ret: DataType::Text,
+    };
+    if let (signature, FunctionType::Scalar(function)) =
+        function_registry.resolve_function(&lookup_sig)?
+    {
+        let arg = std::mem::take(expression);
+        *expression = Expression::CompiledFunctionCall(CompiledFunctionCall {
+            function,
+            args: Box::from(vec![arg]),
+            expr_buffer: Box::from(vec![]),
+            signature: Box::new(signature),
+        });
+    }
+    Ok(())
+}
+
+fn is_collated(expression: &Expression) -> bool {
+    matches!(expression, Expression::CompiledFunctionCall(function_call)
+        if function_call.signature.name == "collation_key")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Planner;
+    use data::Datum;
+
+    fn validate(planner: &Planner, session: &Session, sql: &str) -> LogicalOperator {
+        let query = match parser::parse(sql).unwrap() {
+            ast::statement::Statement::Query(query) => query,
+            _ => panic!(),
+        };
+        planner.validate(query, session).unwrap()
+    }
+
+    fn first_expression(planner: &Planner, session: &Session, sql: &str) -> String {
+        match validate(planner, session, sql) {
+            LogicalOperator::Project(project) => project.expressions[0].expression.to_string(),
+            operator => panic!("Unexpected operator {:?}", operator),
+        }
+    }
+
+    fn sort_expression(operator: &mut LogicalOperator) -> Option<String> {
+        if let LogicalOperator::Sort(sort) = operator {
+            Some(sort.sort_expressions[0].expression.to_string())
+        } else {
+            operator.children_mut().find_map(sort_expression)
+        }
+    }
+
+    #[test]
+    fn test_apply_collation() {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+
+        assert_eq!(
+            first_expression(&planner, &session, r#"SELECT "a" = "A""#),
+            r#"`=`(collation_key("a"), collation_key("A"))"#
+        );
+        // Only text gets wrapped
+        assert_eq!(
+            first_expression(&planner, &session, r#"SELECT 1 < 2"#),
+            "`<`(1, 2)"
+        );
+        let mut sorted = validate(
+            &planner,
+            &session,
+            r#"SELECT foo FROM (SELECT "a" AS foo) AS t ORDER BY foo"#,
+        );
+        assert_eq!(
+            sort_expression(&mut sorted),
+            Some("collation_key(<OFFSET 0>)".to_string())
+        );
+
+        session.variables.write().unwrap().insert(
+            "collation_connection".to_string(),
+            Datum::from("utf8mb4_bin"),
+        );
+        assert_eq!(
+            first_expression(&planner, &session, r#"SELECT "a" = "A""#),
+            r#"`=`("a", "A")"#
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::p1_validation::compile_functions_and_refs::compile_functions_in_expr;
+use crate::utils::expr::type_for_expression;
+use crate::utils::logical::{fields_for_operator, insert_columns};
+use crate::{Field, PlannerError};
+use ast::expr::{Cast, CompiledColumnReference, Expression, NamedExpression};
+use ast::rel::logical::{ColumnDefault, LogicalOperator, Project};
+use data::{DataType, Datum};
+use functions::registry::Registry;
+
+/// Rewrites the source of inserts into tables with column defaults/generated columns or inserts
+/// with an explicit column list so that the source lines up with all of the tables columns.
+/// Columns not inserted into get their default (or null) and generated columns are computed from
+/// the inserted values.
+pub(super) fn apply_column_defaults(
+    query: &mut LogicalOperator,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    for child in query.children_mut() {
+        apply_column_defaults(child, function_registry)?;
+    }
+
+    if let LogicalOperator::TableInsert(table_insert) = query {
+        if let LogicalOperator::ResolvedTable(table) = table_insert.table.as_ref() {
+            if table_insert.columns.is_empty() && table.column_defaults.iter().all(Option::is_none)
+            {
+                return Ok(());
+            }
+
+            // Deletes read every column back from the table, generated columns included
+            let insert_columns = if table_insert.is_delete() {
+                table.columns.clone()
+            } else {
+                insert_columns(&table_insert.columns, table)?
+            };
+            let source_fields: Vec<_> = fields_for_operator(&table_insert.source).collect();
+            if insert_columns.len() != source_fields.len() {
+                return Err(PlannerError::InsertMismatch(
+                    insert_columns.iter().map(|(_, dt)| *dt).collect(),
+                    source_fields.iter().map(|field| field.data_type).collect(),
+                ));
+            }
+
+            // The inserted values named after the columns they're being inserted into, this is
+            // what the default and generated expressions get to see.
+            let visible_fields: Vec<_> = insert_columns
+                .into_iter()
+                .zip(source_fields)
+                .map(|((alias, _), field)| Field {
+                    qualifier: None,
+                    alias,
+                    data_type: field.data_type,
+                    nullable: field.nullable,
+                    origin: None,
+                })
+                .collect();
+
+            let mut expressions = Vec::with_capacity(table.columns.len());
+            for ((alias, datatype), default) in
+                table.columns.iter().zip(table.column_defaults.iter())
+            {
+                let offset = visible_fields
+                    .iter()
+                    .position(|field| &field.alias == alias);
+                let expression = match (default, offset) {
+                    (Some(ColumnDefault::Generated(expr)), _)
+                    | (Some(ColumnDefault::Default(expr)), None) => compile_column_default(
+                        expr.clone(),
+                        *datatype,
+                        &visible_fields,
+                        function_registry,
+                    )?,
+                    (_, Some(offset)) => {
+                        Expression::CompiledColumnReference(CompiledColumnReference {
+                            offset,
+                            datatype: visible_fields[offset].data_type,
+                        })
+                    }
+                    (None, None) => Expression::Constant(Datum::Null, *datatype),
+                };
+
+                expressions.push(NamedExpression {
+                    alias: Some(alias.clone()),
+                    expression,
+                });
+            }
+
+            let source = std::mem::take(&mut table_insert.source);
+            table_insert.source = Box::new(LogicalOperator::Project(Project {
+                distinct: false,
+                expressions,
+                source,
+            }));
+        }
+    }
+    Ok(())
+}
+
+/// Compiles a default/generated column expression against the fields it can see, casting it to
+/// the column's type if need be.
+pub(super) fn compile_column_default(
+    mut expr: Expression,
+    datatype: DataType,
+    fields: &[Field],
+    function_registry: &Registry,
+) -> Result<Expression, PlannerError> {
+    compile_functions_in_expr(&mut expr, fields, function_registry)?;
+    if type_for_expression(&expr) != datatype {
+        expr = Expression::Cast(Cast {
+            expr: Box::new(expr),
+            datatype,
+            try_cast: false,
+        });
+        compile_functions_in_expr(&mut expr, fields, function_registry)?;
+    }
+    Ok(expr)
+}
@@ -0,0 +1,27 @@
+use crate::PlannerError;
+use ast::rel::logical::LogicalOperator;
+use data::Session;
+
+/// Only root gets to read files directly off the server, ie `SELECT * FROM DIRECTORY "..."`.
+/// Views and external tables over files are created by root so reading those is fine, this
+/// runs before they're inlined.
+pub(super) fn check_file_access(
+    operator: &mut LogicalOperator,
+    session: &Session,
+) -> Result<(), PlannerError> {
+    for child in operator.children_mut() {
+        check_file_access(child, session)?;
+    }
+    for subquery in operator.subqueries_mut() {
+        check_file_access(subquery, session)?;
+    }
+
+    if let LogicalOperator::FileScan(_) = operator {
+        if !session.is_root() {
+            return Err(PlannerError::FileAccessDenied(
+                session.user.read().unwrap().clone(),
+            ));
+        }
+    }
+    Ok(())
+}
@@ -1,14 +1,23 @@
 use crate::utils::logical::fields_for_operator;
 use crate::PlannerError;
+use ast::expr::Expression;
 use ast::rel::logical::LogicalOperator;
+use data::Datum;
 
-/// Checks to make sure we're inserting rows with the right datatypes/length
+/// Checks to make sure we're inserting rows with the right datatypes/length, and that we're not
+/// inserting null literals into NOT NULL columns. Nulls that only show up at runtime are caught
+/// by the insert executor.
 pub(super) fn check_inserts(operator: &mut LogicalOperator) -> Result<(), PlannerError> {
     for child in operator.children_mut() {
         check_inserts(child)?;
     }
 
     if let LogicalOperator::TableInsert(table_insert) = operator {
+        let table = if let LogicalOperator::ResolvedTable(table) = table_insert.table.as_ref() {
+            table
+        } else {
+            return Err(PlannerError::InsertNotSupported);
+        };
         let table_fields: Vec<_> = fields_for_operator(&table_insert.table)
             .map(|f| f.data_type)
             .collect();
@@ -17,11 +26,39 @@ pub(super) fn check_inserts(operator: &mut LogicalOperator) -> Result<(), Planne
             .collect();
 
         if table_fields != source_fields {
-            Err(PlannerError::InsertMismatch(table_fields, source_fields))
-        } else {
-            Ok(())
+            return Err(PlannerError::InsertMismatch(table_fields, source_fields));
+        }
+
+        if !table_insert.delete_matching {
+            for (idx, (alias, _)) in table.columns.iter().enumerate() {
+                if table.column_not_null.get(idx) == Some(&true)
+                    && inserts_null_literal(&table_insert.source, idx)
+                {
+                    return Err(PlannerError::NullNotAllowed(alias.clone()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if the source has a null literal for the given column, ie VALUES (NULL) or a
+/// column left out of the insert without a default.
+fn inserts_null_literal(source: &LogicalOperator, offset: usize) -> bool {
+    match source {
+        LogicalOperator::Project(project) => {
+            match project.expressions.get(offset).map(|ne| &ne.expression) {
+                Some(Expression::Constant(Datum::Null, _)) => true,
+                Some(Expression::CompiledColumnReference(column_ref)) => {
+                    inserts_null_literal(&project.source, column_ref.offset)
+                }
+                _ => false,
+            }
         }
-    } else {
-        Ok(())
+        LogicalOperator::Values(values) => values
+            .data
+            .iter()
+            .any(|row| matches!(row.get(offset), Some(Expression::Constant(Datum::Null, _)))),
+        _ => false,
     }
 }
@@ -8,6 +8,10 @@ pub(super) fn normalize_column_aliases(query: &mut LogicalOperator) {
         normalize_column_aliases(child);
     }
 
+    for subquery in query.subqueries_mut() {
+        normalize_column_aliases(subquery);
+    }
+
     // Column Aliases
     for (idx, ne) in query.named_expressions_mut().enumerate() {
         if ne.alias.is_none() {
@@ -22,13 +22,24 @@ pub(super) fn compile_functions(
     Ok(())
 }
 
-fn compile_functions_in_expr(
+pub(super) fn compile_functions_in_expr(
     expression: &mut Expression,
     source_fields: &[Field],
     function_registry: &Registry,
 ) -> Result<(), PlannerError> {
     match expression {
         Expression::FunctionCall(function_call) => {
+            // The parser hands us count(DISTINCT a) as count(distinct(a))
+            let distinct = matches!(
+                function_call.args.as_slice(),
+                [Expression::FunctionCall(marker)] if marker.function_name == "distinct"
+            );
+            if distinct {
+                if let Some(Expression::FunctionCall(marker)) = function_call.args.pop() {
+                    function_call.args = marker.args;
+                }
+            }
+
             for arg in function_call.args.iter_mut() {
                 compile_functions_in_expr(arg, source_fields, function_registry)?;
             }
@@ -47,6 +58,11 @@ fn compile_functions_in_expr(
             std::mem::swap(&mut args, &mut function_call.args);
 
             *expression = match function {
+                FunctionType::Scalar(_) if distinct => {
+                    return Err(PlannerError::DistinctAggregateNotSupported(
+                        "DISTINCT can only be used with aggregate functions",
+                    ))
+                }
                 FunctionType::Scalar(function) => {
                     Expression::CompiledFunctionCall(CompiledFunctionCall {
                         function,
@@ -59,6 +75,7 @@ fn compile_functions_in_expr(
                     Expression::CompiledAggregate(CompiledAggregate {
                         function,
                         args: Box::from(args),
+                        distinct,
                         expr_buffer: Box::from(vec![]),
                         signature: Box::new(signature),
                     })
@@ -70,6 +87,9 @@ fn compile_functions_in_expr(
                         source_fields,
                         function_registry,
                     )?;
+                    if distinct {
+                        mark_aggregates_distinct(&mut exploded_expr);
+                    }
                     exploded_expr
                 }
             };
@@ -87,7 +107,12 @@ fn compile_functions_in_expr(
                 ret: cast.datatype,
             };
 
-            let (signature, function) = function_registry.resolve_function(&lookup_sig)?;
+            let (mut signature, function) = function_registry.resolve_function(&lookup_sig)?;
+            // The casts check the name to tell whether to raise errors for values that can't be
+            // converted when in strict mode, TRY_CAST never does.
+            if !cast.try_cast {
+                signature.name = "cast";
+            }
 
             // Just an "empty" value to swap
             let mut expr = Expression::Constant(Datum::Null, DataType::Null);
@@ -143,6 +168,9 @@ fn compile_functions_in_expr(
             }
         }
 
+        // These have been rewritten into joins by this point.
+        Expression::Subquery(_) => panic!("Subqueries should be gone by now!"),
+
         // These are already good and for the ref/function call probably shouldn't exist yet.
         Expression::Constant(..)
         | Expression::CompiledFunctionCall(_)
@@ -152,6 +180,16 @@ fn compile_functions_in_expr(
     Ok(())
 }
 
+/// For compound functions ie avg(DISTINCT a) => sum(DISTINCT a) / count(DISTINCT a)
+fn mark_aggregates_distinct(expression: &mut Expression) {
+    if let Expression::CompiledAggregate(aggregate) = expression {
+        aggregate.distinct = true;
+    }
+    for child in expression.children_mut() {
+        mark_aggregates_distinct(child);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,71 @@
+use crate::p1_validation::compile_functions_and_refs::compile_functions_in_expr;
+use crate::utils::expr::type_for_expression;
+use crate::utils::logical::{fields_for_operator, insert_columns};
+use crate::PlannerError;
+use ast::expr::{CompiledColumnReference, Expression};
+use ast::rel::logical::{LogicalOperator, ResolvedTable};
+use functions::registry::Registry;
+
+/// Compiles the ON DUPLICATE KEY UPDATE assignments of upserts. The expressions see the columns
+/// of the existing row, while VALUES(col) is rewritten to point at the row being inserted which
+/// follows the existing row.
+pub(super) fn compile_upserts(
+    query: &mut LogicalOperator,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    for child in query.children_mut() {
+        compile_upserts(child, function_registry)?;
+    }
+
+    if let LogicalOperator::TableInsert(table_insert) = query {
+        if let (Some(upsert), LogicalOperator::ResolvedTable(table)) =
+            (&mut table_insert.upsert, table_insert.table.as_ref())
+        {
+            let table_fields: Vec<_> = fields_for_operator(&table_insert.table).collect();
+            let column_names: Vec<_> = upsert
+                .assignments
+                .iter()
+                .map(|(column, _)| column.clone())
+                .collect();
+            let columns = insert_columns(&column_names, table)?;
+
+            for ((_, expression), (_, datatype)) in upsert.assignments.iter_mut().zip(columns) {
+                sub_in_inserted_values(expression, table);
+                compile_functions_in_expr(expression, &table_fields, function_registry)?;
+                let expression_type = type_for_expression(expression);
+                if expression_type != datatype {
+                    return Err(PlannerError::InsertMismatch(
+                        vec![datatype],
+                        vec![expression_type],
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces VALUES(col) with a reference to the column of the inserted row.
+fn sub_in_inserted_values(expression: &mut Expression, table: &ResolvedTable) {
+    if let Expression::FunctionCall(function_call) = expression {
+        if function_call.function_name == "values" {
+            if let [Expression::ColumnReference(column_ref)] = function_call.args.as_slice() {
+                if let Some(idx) = table
+                    .columns
+                    .iter()
+                    .position(|(alias, _)| alias == &column_ref.alias)
+                {
+                    *expression = Expression::CompiledColumnReference(CompiledColumnReference {
+                        offset: table.columns.len() + idx,
+                        datatype: table.columns[idx].1,
+                    });
+                    return;
+                }
+            }
+        }
+    }
+
+    for child in expression.children_mut() {
+        sub_in_inserted_values(child, table);
+    }
+}
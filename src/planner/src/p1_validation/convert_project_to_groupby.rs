@@ -1,12 +1,17 @@
 use crate::utils::expr::contains_aggregate;
+use crate::utils::logical::create_wrapping_project;
 use ast::rel::logical::*;
 
 /// Detects projects using aggregate functions and turns them into a group by.
+/// SELECT DISTINCT projects become a group by over all of their columns.
 pub(super) fn project_to_groupby(operator: &mut LogicalOperator) {
     for child in operator.children_mut() {
         project_to_groupby(child);
     }
     if let LogicalOperator::Project(project) = operator {
+        let distinct = project.distinct;
+        project.distinct = false;
+
         if project
             .expressions
             .iter()
@@ -20,6 +25,21 @@ pub(super) fn project_to_groupby(operator: &mut LogicalOperator) {
             *operator = LogicalOperator::GroupBy(GroupBy {
                 expressions,
                 key_expressions: vec![],
+                distinct_on: false,
+                source,
+            })
+        }
+
+        if distinct {
+            let Project {
+                expressions,
+                source,
+                ..
+            } = create_wrapping_project(std::mem::take(operator));
+            *operator = LogicalOperator::GroupBy(GroupBy {
+                key_expressions: expressions.iter().map(|ne| ne.expression.clone()).collect(),
+                expressions,
+                distinct_on: false,
                 source,
             })
         }
@@ -0,0 +1,210 @@
+use crate::PlannerError;
+use ast::expr::*;
+use ast::rel::logical::LogicalOperator;
+
+/// Lowers row values and IN lists down into plain comparisons, ie
+/// (a, b) IN ((1, 2), (3, 4)) becomes (a = 1 AND b = 2) OR (a = 3 AND b = 4)
+/// (a, b) >= (1, 2) becomes a >= 1 AND (a > 1 OR (a = 1 AND b >= 2))
+/// The leading a >= 1 is redundant but it's what lets the table scans narrow down to a key range.
+pub(super) fn expand_row_values(query: &mut LogicalOperator) -> Result<(), PlannerError> {
+    for child in query.children_mut() {
+        expand_row_values(child)?;
+    }
+
+    for expression in query.expressions_mut() {
+        expand_row_values_for_expr(expression)?;
+    }
+    Ok(())
+}
+
+fn expand_row_values_for_expr(expression: &mut Expression) -> Result<(), PlannerError> {
+    match expression {
+        Expression::FunctionCall(function_call) => {
+            for arg in function_call.args.iter_mut() {
+                expand_row_values_for_expr(arg)?;
+            }
+            let args = &function_call.args;
+            let expanded = match function_call.function_name.as_str() {
+                "in" => {
+                    let (left, list) = args.split_first().unwrap();
+                    let comparisons = list
+                        .iter()
+                        .map(|item| compare("=", left, item))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    combine("or", comparisons)
+                }
+                "=" | "!=" | "<=>" | "<" | "<=" | ">" | ">=" if args.iter().any(is_row) => {
+                    compare(&function_call.function_name, &args[0], &args[1])?
+                }
+                _ => return Ok(()),
+            };
+            *expression = expanded;
+        }
+        Expression::Cast(cast) => expand_row_values_for_expr(&mut cast.expr)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Builds the comparison between two expressions, expanding it out if they're row values.
+fn compare(op: &str, left: &Expression, right: &Expression) -> Result<Expression, PlannerError> {
+    let (left, right) = match (row_values(left), row_values(right)) {
+        (None, None) => return Ok(call(op, vec![left.clone(), right.clone()])),
+        (Some(left), Some(right)) if left.len() == right.len() => (left, right),
+        (left, right) => {
+            return Err(PlannerError::RowValueMismatch(
+                left.map_or(1, <[Expression]>::len),
+                right.map_or(1, <[Expression]>::len),
+            ))
+        }
+    };
+
+    let pairs = left.iter().zip(right.iter());
+    match op {
+        "=" | "<=>" => Ok(combine(
+            "and",
+            pairs
+                .map(|(l, r)| compare(op, l, r))
+                .collect::<Result<_, _>>()?,
+        )),
+        "!=" => Ok(combine(
+            "or",
+            pairs
+                .map(|(l, r)| compare(op, l, r))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => {
+            // Lexicographic ordering, built up from the last column backwards
+            let strict = &op[..1];
+            let last = left.len() - 1;
+            let mut expr = compare(op, &left[last], &right[last])?;
+            for idx in (0..last).rev() {
+                let tie_break = call("and", vec![compare("=", &left[idx], &right[idx])?, expr]);
+                expr = call(
+                    "or",
+                    vec![compare(strict, &left[idx], &right[idx])?, tie_break],
+                );
+            }
+            let leading = compare(&format!("{}=", strict), &left[0], &right[0])?;
+            Ok(call("and", vec![leading, expr]))
+        }
+    }
+}
+
+fn row_values(expression: &Expression) -> Option<&[Expression]> {
+    match expression {
+        Expression::FunctionCall(function_call) if function_call.function_name == "row" => {
+            Some(&function_call.args)
+        }
+        _ => None,
+    }
+}
+
+fn is_row(expression: &Expression) -> bool {
+    row_values(expression).is_some()
+}
+
+fn call(function_name: &str, args: Vec<Expression>) -> Expression {
+    Expression::FunctionCall(FunctionCall {
+        function_name: function_name.to_string(),
+        args,
+    })
+}
+
+/// Chains the expressions together with and/or, there must be at least one expression.
+fn combine(function_name: &str, expressions: Vec<Expression>) -> Expression {
+    let mut iter = expressions.into_iter();
+    let first = iter.next().unwrap();
+    iter.fold(first, |acc, expr| call(function_name, vec![acc, expr]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::logical::Filter;
+
+    fn column(alias: &str) -> Expression {
+        Expression::ColumnReference(ColumnReference {
+            qualifier: None,
+            alias: alias.to_string(),
+            star: false,
+        })
+    }
+
+    fn filter(predicate: Expression) -> LogicalOperator {
+        LogicalOperator::Filter(Filter {
+            predicate,
+            source: Box::new(LogicalOperator::Single),
+        })
+    }
+
+    #[test]
+    fn test_expand_in() -> Result<(), PlannerError> {
+        let row = |a: i32, b: i32| call("row", vec![Expression::from(a), Expression::from(b)]);
+        let mut operator = filter(call(
+            "in",
+            vec![
+                call("row", vec![column("a"), column("b")]),
+                row(1, 2),
+                row(3, 4),
+            ],
+        ));
+        expand_row_values(&mut operator)?;
+
+        let eq = |col: &str, val: i32| call("=", vec![column(col), Expression::from(val)]);
+        let expected = filter(call(
+            "or",
+            vec![
+                call("and", vec![eq("a", 1), eq("b", 2)]),
+                call("and", vec![eq("a", 3), eq("b", 4)]),
+            ],
+        ));
+        assert_eq!(operator, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_row_comparison() -> Result<(), PlannerError> {
+        let mut operator = filter(call(
+            ">",
+            vec![
+                call("row", vec![column("a"), column("b")]),
+                call("row", vec![Expression::from(1), Expression::from(2)]),
+            ],
+        ));
+        expand_row_values(&mut operator)?;
+
+        let cmp =
+            |op: &str, col: &str, val: i32| call(op, vec![column(col), Expression::from(val)]);
+        let expected = filter(call(
+            "and",
+            vec![
+                cmp(">=", "a", 1),
+                call(
+                    "or",
+                    vec![
+                        cmp(">", "a", 1),
+                        call("and", vec![cmp("=", "a", 1), cmp(">", "b", 2)]),
+                    ],
+                ),
+            ],
+        ));
+        assert_eq!(operator, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_row_mismatch() {
+        let mut operator = filter(call(
+            "=",
+            vec![
+                call("row", vec![column("a"), column("b")]),
+                Expression::from(1),
+            ],
+        ));
+        assert!(matches!(
+            expand_row_values(&mut operator),
+            Err(PlannerError::RowValueMismatch(2, 1))
+        ));
+    }
+}
@@ -9,6 +9,10 @@ pub(super) fn expand_stars(operator: &mut LogicalOperator) {
         expand_stars(child);
     }
 
+    for subquery in operator.subqueries_mut() {
+        expand_stars(subquery);
+    }
+
     let (source_expressions, source) = match operator {
         LogicalOperator::Project(project) => (&mut project.expressions, &project.source),
         LogicalOperator::GroupBy(group_by) => (&mut group_by.expressions, &group_by.source),
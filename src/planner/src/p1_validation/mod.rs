@@ -1,15 +1,26 @@
-use crate::{Planner, PlannerError};
-use ast::rel::logical::LogicalOperator;
-use data::Session;
+use crate::{Field, Planner, PlannerError};
+use ast::rel::logical::{ColumnDefault, LogicalOperator};
+use data::{DataType, Session};
+mod apply_collation;
+mod apply_column_defaults;
 mod check_aggregates_usage;
+mod check_file_access;
 mod check_inserts;
 mod check_predicates;
 mod check_unions;
 mod column_aliases;
 mod compile_functions_and_refs;
+mod compile_upserts;
 mod convert_project_to_groupby;
+mod expand_row_values;
 mod expand_stars;
+mod resolve_file_partitions;
 mod resolve_tables;
+mod rewrite_distinct_aggregates;
+mod rewrite_distinct_on;
+mod rewrite_hop_windows;
+mod rewrite_set_operations;
+mod rewrite_subqueries;
 mod sub_in_special_vars;
 mod validate_values_types;
 
@@ -24,21 +35,37 @@ impl Planner {
         // NOTE if adding phases before the resolve table step you will need to
         // add them inside the resolve table where it inlines any views.
         // Populate column aliases
-        sub_in_special_vars::sub_in_special_vars(&mut query);
+        sub_in_special_vars::sub_in_special_vars(&mut query, session);
         column_aliases::normalize_column_aliases(&mut query);
+        check_file_access::check_file_access(&mut query, session)?;
         // Grab a read lock on the catalog and look up the tables
         {
             let catalog = self.catalog.read().unwrap();
             resolve_tables::resolve_tables(&catalog, &mut query, session)?;
         }
+        resolve_file_partitions::resolve_file_partitions(
+            &mut query,
+            &self.function_registry,
+            session,
+        )?;
         // Now that all the fields are there we can expand all the stars
         expand_stars::expand_stars(&mut query);
+        // Turn any IN/EXISTS subqueries into joins so the rest of the phases just see plain operators
+        rewrite_subqueries::rewrite_subqueries(&mut query)?;
         validate_values_types::validate_values_types(&mut query)?;
+        expand_row_values::expand_row_values(&mut query)?;
         compile_functions_and_refs::compile_functions(&mut query, &self.function_registry)?;
+        apply_column_defaults::apply_column_defaults(&mut query, &self.function_registry)?;
+        compile_upserts::compile_upserts(&mut query, &self.function_registry)?;
+        rewrite_distinct_on::rewrite_distinct_on(&mut query, &self.function_registry)?;
+        rewrite_set_operations::rewrite_set_operations(&mut query, &self.function_registry)?;
 
         // At this point the ast's are sane enough that we can ask expressions what types they
         // return etc.
         convert_project_to_groupby::project_to_groupby(&mut query);
+        rewrite_hop_windows::rewrite_hop_windows(&mut query, &self.function_registry)?;
+        rewrite_distinct_aggregates::rewrite_distinct_aggregates(&mut query)?;
+        apply_collation::apply_collation(&mut query, &self.function_registry, session)?;
         // Type checks etc
         check_aggregates_usage::check_for_aggregates(&mut query)?;
         check_predicates::check_predicates(&mut query)?;
@@ -47,4 +74,45 @@ impl Planner {
 
         Ok(query)
     }
+
+    /// Checks the column defaults/generated columns for a table being created parse back from
+    /// their sql and compile to something that can be cast to the column's type, so bad ones
+    /// are caught now rather than on every insert.
+    pub fn validate_column_defaults(
+        &self,
+        columns: &[(String, DataType)],
+        column_defaults: &[Option<catalog::ColumnDefault>],
+    ) -> Result<(), PlannerError> {
+        let column_defaults = resolve_tables::parse_column_defaults(columns, column_defaults)?;
+        // Defaults and generated columns get to see the inserted columns, generated columns
+        // are never inserted into.
+        let fields: Vec<_> = columns
+            .iter()
+            .zip(&column_defaults)
+            .filter(|(_, default)| !matches!(default, Some(ColumnDefault::Generated(_))))
+            .map(|((alias, data_type), _)| Field {
+                qualifier: None,
+                alias: alias.clone(),
+                data_type: *data_type,
+                nullable: true,
+                origin: None,
+            })
+            .collect();
+        for ((alias, datatype), default) in columns.iter().zip(&column_defaults) {
+            if let Some(ColumnDefault::Default(expr)) | Some(ColumnDefault::Generated(expr)) =
+                default
+            {
+                apply_column_defaults::compile_column_default(
+                    expr.clone(),
+                    *datatype,
+                    &fields,
+                    &self.function_registry,
+                )
+                .map_err(|err| {
+                    PlannerError::InvalidColumnDefault(alias.clone(), err.to_string())
+                })?;
+            }
+        }
+        Ok(())
+    }
 }
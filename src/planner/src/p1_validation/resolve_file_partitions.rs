@@ -0,0 +1,171 @@
+use crate::PlannerError;
+use ast::rel::logical::{FilePartition, FileScan, LogicalOperator};
+use data::{DataType, Datum, Session};
+use functions::registry::Registry;
+use functions::{FunctionSignature, FunctionType};
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// The directory name hive uses for null partition values
+const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Lists the key=value subdirectories (ie hive style partitioning) of partitioned file scans,
+/// parsing each value into the type of its column so later phases can prune the partitions.
+pub(super) fn resolve_file_partitions(
+    operator: &mut LogicalOperator,
+    function_registry: &Registry,
+    session: &Session,
+) -> Result<(), PlannerError> {
+    for child in operator.children_mut() {
+        resolve_file_partitions(child, function_registry, session)?;
+    }
+    for subquery in operator.subqueries_mut() {
+        resolve_file_partitions(subquery, function_registry, session)?;
+    }
+
+    if let LogicalOperator::FileScan(file_scan) = operator {
+        if file_scan.partition_columns > 0 {
+            file_scan.partitions = list_partitions(file_scan, function_registry, session)?;
+        }
+    }
+    Ok(())
+}
+
+/// Walks down a level of subdirectories for each partition column, directories that don't
+/// match the column's key are skipped.
+fn list_partitions(
+    file_scan: &FileScan,
+    function_registry: &Registry,
+    session: &Session,
+) -> Result<Vec<FilePartition>, PlannerError> {
+    let first_partition_column = file_scan.columns.len() - file_scan.partition_columns;
+    let mut partitions = vec![FilePartition {
+        name: String::new(),
+        values: vec![],
+        pruned: false,
+    }];
+
+    for (column_name, datatype) in &file_scan.columns[first_partition_column..] {
+        let mut next_level = vec![];
+        for partition in partitions {
+            let directory = Path::new(&file_scan.directory).join(&partition.name);
+            let entries = match directory.read_dir() {
+                Ok(entries) => entries,
+                // Nothing there yet just means there's no rows
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(PlannerError::FileScanError(err.to_string())),
+            };
+            for entry in entries {
+                let entry = entry.map_err(|err| PlannerError::FileScanError(err.to_string()))?;
+                if !entry.path().is_dir() {
+                    continue;
+                }
+                let directory_name = entry.file_name().to_string_lossy().to_string();
+                let mut key_value = directory_name.splitn(2, '=');
+                let (key, value) = match (key_value.next(), key_value.next()) {
+                    (Some(key), Some(value)) if key.eq_ignore_ascii_case(column_name) => {
+                        (key, value)
+                    }
+                    _ => continue,
+                };
+                let mut values = partition.values.clone();
+                values.push(parse_value(value, *datatype, function_registry, session)?);
+                let name = if partition.name.is_empty() {
+                    format!("{}={}", key, value)
+                } else {
+                    format!("{}/{}={}", partition.name, key, value)
+                };
+                next_level.push(FilePartition {
+                    name,
+                    values,
+                    pruned: false,
+                });
+            }
+        }
+        partitions = next_level;
+    }
+
+    // The directory listings come back in no particular order
+    partitions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(partitions)
+}
+
+/// Parses the text of a partition value using the same cast functions used for the fields
+/// within the files.
+fn parse_value(
+    value: &str,
+    datatype: DataType,
+    function_registry: &Registry,
+    session: &Session,
+) -> Result<Datum<'static>, PlannerError> {
+    if value == NULL_PARTITION {
+        return Ok(Datum::Null);
+    }
+    let text = [Datum::from(value)];
+    if datatype == DataType::Text {
+        return Ok(text[0].as_static());
+    }
+    let lookup_sig = FunctionSignature {
+        name: datatype.cast_function(),
+        args: vec![DataType::Text],
+        ret: datatype,
+    };
+    match function_registry.resolve_function(&lookup_sig)? {
+        (signature, FunctionType::Scalar(function)) => {
+            Ok(function.execute(session, &signature, &text).into_static())
+        }
+        _ => panic!("Cast needs to be a scalar function"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::logical::SerdeOptions;
+    use data::chrono::NaiveDate;
+
+    #[test]
+    fn test_resolve_file_partitions() -> Result<(), PlannerError> {
+        let mut operator = LogicalOperator::FileScan(FileScan {
+            directory: "../../test_data/partitioned".to_string(),
+            serde_options: SerdeOptions::default(),
+            columns: vec![
+                ("a".to_string(), DataType::Integer),
+                ("dt".to_string(), DataType::Date),
+                ("region".to_string(), DataType::Text),
+            ],
+            partition_columns: 2,
+            partitions: vec![],
+        });
+        resolve_file_partitions(&mut operator, &Registry::default(), &Session::new(1))?;
+
+        let date = |d| Datum::from(NaiveDate::from_ymd(2020, 11, d));
+        if let LogicalOperator::FileScan(file_scan) = operator {
+            let partitions: Vec<_> = file_scan
+                .partitions
+                .into_iter()
+                .map(|partition| (partition.name, partition.values))
+                .collect();
+            assert_eq!(
+                partitions,
+                vec![
+                    (
+                        "dt=2020-11-01/region=eu".to_string(),
+                        vec![date(1), Datum::from("eu")]
+                    ),
+                    (
+                        "dt=2020-11-01/region=us".to_string(),
+                        vec![date(1), Datum::from("us")]
+                    ),
+                    (
+                        "dt=2020-11-02/region=us".to_string(),
+                        vec![date(2), Datum::from("us")]
+                    ),
+                ]
+            );
+        } else {
+            panic!()
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,13 @@
 use crate::p1_validation::{column_aliases, sub_in_special_vars};
 use crate::PlannerError;
-use ast::rel::logical::{LogicalOperator, ResolvedTable};
+use ast::rel::logical::{
+    ColumnDefault, FileScan, FulltextIndex, LogicalOperator, PartitionMethod, Partitioning,
+    ResolvedTable, SecondaryIndex, SerdeOptions, TablePartition,
+};
 use ast::statement::Statement;
 use catalog::{Catalog, TableOrView};
-use data::Session;
+use data::{DataType, Session};
+use parser::ParseError;
 
 pub(super) fn resolve_tables(
     catalog: &Catalog,
@@ -14,30 +18,100 @@ pub(super) fn resolve_tables(
         resolve_tables(catalog, child, session)?;
     }
 
+    for subquery in operator.subqueries_mut() {
+        resolve_tables(catalog, subquery, session)?;
+    }
+
     if let LogicalOperator::TableReference(table_ref) = operator {
+        let as_of = table_ref.as_of;
         // In a block to drop the lock as we need  to get write access to it further down for
         // views
-        let item = {
+        let (database, item) = {
             let current_db = session.current_database.read().unwrap();
             let database = table_ref.database.as_ref().unwrap_or(&current_db);
             let table_name = &table_ref.table;
 
-            catalog.item(database, table_name)?
+            (database.clone(), catalog.item(database, table_name)?)
         };
+        // The users table holds everyone's password hashes
+        if database == "incresql" && table_ref.table == "users" && !session.is_root() {
+            return Err(PlannerError::TableAccessDenied(
+                session.user.read().unwrap().clone(),
+                table_ref.table.clone(),
+            ));
+        }
         match item.item {
             TableOrView::Table(table) => {
-                *operator = LogicalOperator::ResolvedTable(ResolvedTable {
+                let column_defaults = parse_column_defaults(&item.columns, &item.column_defaults)?;
+
+                let column_names: Vec<_> = item.columns.iter().map(|(name, _)| name).collect();
+                let statistics = catalog.column_statistics(table.id(), &column_names)?;
+
+                let columns = &item.columns;
+                let partitioning = item.partitioning.map(|partitioning| Partitioning {
+                    method: match partitioning.method {
+                        catalog::PartitionMethod::Range => PartitionMethod::Range,
+                        catalog::PartitionMethod::Hash => PartitionMethod::Hash,
+                    },
+                    column: partitioning.column,
+                    column_type: columns[partitioning.column].1,
+                    partitions: partitioning
+                        .partitions
+                        .into_iter()
+                        .map(|partition| TablePartition {
+                            name: partition.name,
+                            upper_bound: partition.upper_bound,
+                            table: partition.table,
+                            pruned: false,
+                        })
+                        .collect(),
+                });
+
+                let fulltext_indexes = item
+                    .fulltext_indexes
+                    .into_iter()
+                    .map(|index| FulltextIndex {
+                        name: index.name,
+                        column: index.column,
+                        table: index.table,
+                    })
+                    .collect();
+
+                let secondary_indexes = item
+                    .secondary_indexes
+                    .into_iter()
+                    .map(|index| SecondaryIndex {
+                        name: index.name,
+                        columns: index.columns,
+                        table: index.table,
+                    })
+                    .collect();
+
+                *operator = LogicalOperator::ResolvedTable(Box::new(ResolvedTable {
+                    database,
+                    name: table_ref.table.clone(),
                     columns: item.columns,
+                    column_defaults,
+                    column_not_null: item.column_not_null,
                     table,
-                })
+                    statistics,
+                    partitioning,
+                    fulltext_indexes,
+                    secondary_indexes,
+                    primary_key_len: item.primary_key_len,
+                    as_of,
+                }))
             }
             TableOrView::View(view) => {
+                if as_of.is_some() {
+                    return Err(PlannerError::AsOfNotSupported(table_ref.table.clone()));
+                }
                 if let Statement::Query(op) =
                     parser::parse(&view.sql).expect("Parse failed for view?")
                 {
                     *operator = op;
                     // Run the planner over the subbed-in sql up to the current phase
-                    sub_in_special_vars::sub_in_special_vars(operator);
+                    sub_in_special_vars::sub_in_special_vars(operator, session);
                     column_aliases::normalize_column_aliases(operator);
                     // Use a session with the "current" db being the same as the one the
                     let mut current_db = view.db_context;
@@ -50,6 +124,9 @@ pub(super) fn resolve_tables(
                     for child in operator.children_mut() {
                         resolve_tables(catalog, child, session)?;
                     }
+                    for subquery in operator.subqueries_mut() {
+                        resolve_tables(catalog, subquery, session)?;
+                    }
                     // TODO on a failure this will leave the current db changed...
                     std::mem::swap(
                         &mut current_db,
@@ -59,17 +136,60 @@ pub(super) fn resolve_tables(
                     panic!("Bogus view")
                 }
             }
+            TableOrView::External(external_table) => {
+                if as_of.is_some() {
+                    return Err(PlannerError::AsOfNotSupported(table_ref.table.clone()));
+                }
+                *operator = LogicalOperator::FileScan(FileScan {
+                    directory: external_table.location,
+                    serde_options: SerdeOptions {
+                        delimiter: external_table.delimiter,
+                        header: external_table.header,
+                        ..SerdeOptions::default()
+                    },
+                    columns: item.columns,
+                    partition_columns: external_table.partition_columns,
+                    partitions: vec![],
+                })
+            }
         }
     }
 
     Ok(())
 }
 
+/// The catalog just stores the sql for column defaults/generated columns, this parses them back.
+pub(super) fn parse_column_defaults(
+    columns: &[(String, DataType)],
+    column_defaults: &[Option<catalog::ColumnDefault>],
+) -> Result<Vec<Option<ColumnDefault>>, PlannerError> {
+    columns
+        .iter()
+        .zip(column_defaults)
+        .map(|((alias, _), default)| {
+            let invalid = |err: ParseError| {
+                PlannerError::InvalidColumnDefault(alias.clone(), err.to_string())
+            };
+            default
+                .as_ref()
+                .map(|default| match default {
+                    catalog::ColumnDefault::Default(sql) => parser::parse_expression(sql)
+                        .map(ColumnDefault::Default)
+                        .map_err(invalid),
+                    catalog::ColumnDefault::Generated(sql) => parser::parse_expression(sql)
+                        .map(ColumnDefault::Generated)
+                        .map_err(invalid),
+                })
+                .transpose()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::utils::logical::fields_for_operator;
-    use crate::Field;
+    use crate::{Field, FieldOrigin};
     use ast::rel::logical::TableReference;
     use data::DataType;
 
@@ -80,6 +200,7 @@ mod tests {
         let mut operator = LogicalOperator::TableReference(TableReference {
             database: Some("incresql".to_string()),
             table: "databases".to_string(),
+            as_of: None,
         });
 
         resolve_tables(&catalog, &mut operator, &session)?;
@@ -90,7 +211,14 @@ mod tests {
             vec![Field {
                 qualifier: None,
                 alias: "name".to_string(),
-                data_type: DataType::Text
+                data_type: DataType::Text,
+                nullable: true,
+                origin: Some(FieldOrigin {
+                    database: "incresql".to_string(),
+                    table_alias: "databases".to_string(),
+                    table: "databases".to_string(),
+                    column: "name".to_string(),
+                }),
             }]
         );
 
@@ -105,6 +233,7 @@ mod tests {
         let mut operator = LogicalOperator::TableReference(TableReference {
             database: None,
             table: "databases".to_string(),
+            as_of: None,
         });
 
         resolve_tables(&catalog, &mut operator, &session)?;
@@ -115,7 +244,14 @@ mod tests {
             vec![Field {
                 qualifier: None,
                 alias: "name".to_string(),
-                data_type: DataType::Text
+                data_type: DataType::Text,
+                nullable: true,
+                origin: Some(FieldOrigin {
+                    database: "incresql".to_string(),
+                    table_alias: "databases".to_string(),
+                    table: "databases".to_string(),
+                    column: "name".to_string(),
+                }),
             }]
         );
 
@@ -0,0 +1,155 @@
+use crate::utils::expr::type_for_expression;
+use crate::PlannerError;
+use ast::expr::{CompiledAggregate, CompiledColumnReference, Expression, NamedExpression};
+use ast::rel::logical::{GroupBy, LogicalOperator};
+
+/// Rewrites group bys with DISTINCT aggregates into a group by that dedupes the keys and the
+/// aggregate arguments, followed by a group by running the plain aggregates.
+/// ie
+/// GroupBy(k, count(DISTINCT a)) -> source
+/// becomes
+/// GroupBy(k, count(a)) -> GroupBy(k, a) -> source
+/// As there's only the one dedupe, all the distinct aggregates must share the same arguments and
+/// can't be mixed with non distinct aggregates.
+pub(super) fn rewrite_distinct_aggregates(
+    operator: &mut LogicalOperator,
+) -> Result<(), PlannerError> {
+    for child in operator.children_mut() {
+        rewrite_distinct_aggregates(child)?;
+    }
+
+    if let LogicalOperator::GroupBy(group_by) = operator {
+        let mut aggregates = vec![];
+        for named_expression in &group_by.expressions {
+            collect_aggregates(&named_expression.expression, &mut aggregates);
+        }
+
+        if let Some(first) = aggregates.iter().find(|aggregate| aggregate.distinct) {
+            if aggregates
+                .iter()
+                .any(|aggregate| !aggregate.distinct || aggregate.args != first.args)
+            {
+                return Err(PlannerError::DistinctAggregateNotSupported(
+                    "all aggregates must be DISTINCT over the same arguments",
+                ));
+            }
+            let distinct_args = first.args.to_vec();
+            rewrite_group_by(group_by, distinct_args);
+        }
+    }
+    Ok(())
+}
+
+fn collect_aggregates<'a>(expression: &'a Expression, aggregates: &mut Vec<&'a CompiledAggregate>) {
+    if let Expression::CompiledAggregate(aggregate) = expression {
+        aggregates.push(aggregate);
+    } else {
+        for child in expression.children() {
+            collect_aggregates(child, aggregates);
+        }
+    }
+}
+
+fn rewrite_group_by(group_by: &mut GroupBy, distinct_args: Vec<Expression>) {
+    let key_expressions = std::mem::take(&mut group_by.key_expressions);
+    let source = std::mem::take(&mut group_by.source);
+
+    // Any other columns referenced outside of the aggregates get passed through the dedupe
+    let mut passthrough = vec![];
+    for named_expression in &mut group_by.expressions {
+        rewrite_expression(
+            &mut named_expression.expression,
+            &key_expressions,
+            &distinct_args,
+            &mut passthrough,
+        );
+    }
+
+    let inner_keys: Vec<_> = key_expressions
+        .iter()
+        .chain(distinct_args.iter())
+        .cloned()
+        .collect();
+    let mut inner_expressions = vec![];
+    for (idx, expression) in key_expressions.iter().enumerate() {
+        inner_expressions.push(NamedExpression {
+            alias: Some(format!("_distinct_key{}", idx + 1)),
+            expression: expression.clone(),
+        });
+    }
+    for (idx, expression) in distinct_args.into_iter().enumerate() {
+        inner_expressions.push(NamedExpression {
+            alias: Some(format!("_distinct_arg{}", idx + 1)),
+            expression,
+        });
+    }
+    for (idx, expression) in passthrough.into_iter().enumerate() {
+        inner_expressions.push(NamedExpression {
+            alias: Some(format!("_distinct_col{}", idx + 1)),
+            expression,
+        });
+    }
+
+    group_by.key_expressions = key_expressions
+        .iter()
+        .enumerate()
+        .map(|(offset, expression)| column(offset, expression))
+        .collect();
+    group_by.source = Box::new(LogicalOperator::GroupBy(GroupBy {
+        expressions: inner_expressions,
+        key_expressions: inner_keys,
+        distinct_on: false,
+        source,
+    }));
+}
+
+/// Repoints an expression from the source of the original group by to the output of the dedupe
+fn rewrite_expression(
+    expression: &mut Expression,
+    key_expressions: &[Expression],
+    distinct_args: &[Expression],
+    passthrough: &mut Vec<Expression>,
+) {
+    if let Some(offset) = key_expressions.iter().position(|key| key == expression) {
+        *expression = column(offset, expression);
+        return;
+    }
+
+    match expression {
+        Expression::CompiledAggregate(aggregate) => {
+            aggregate.args = distinct_args
+                .iter()
+                .enumerate()
+                .map(|(idx, arg)| column(key_expressions.len() + idx, arg))
+                .collect();
+            aggregate.expr_buffer = Box::from(vec![]);
+            aggregate.distinct = false;
+        }
+        Expression::CompiledColumnReference(_) => {
+            let idx = match passthrough.iter().position(|column| column == expression) {
+                Some(idx) => idx,
+                None => {
+                    passthrough.push(expression.clone());
+                    passthrough.len() - 1
+                }
+            };
+            *expression = column(
+                key_expressions.len() + distinct_args.len() + idx,
+                expression,
+            );
+        }
+        _ => {
+            for child in expression.children_mut() {
+                rewrite_expression(child, key_expressions, distinct_args, passthrough);
+            }
+        }
+    }
+}
+
+/// A reference to the given offset of the dedupe, typed the same as the expression it replaces
+fn column(offset: usize, expression: &Expression) -> Expression {
+    Expression::CompiledColumnReference(CompiledColumnReference {
+        offset,
+        datatype: type_for_expression(expression),
+    })
+}
@@ -0,0 +1,99 @@
+use crate::p1_validation::compile_functions_and_refs::compile_functions_in_expr;
+use crate::utils::logical::fields_for_operator;
+use crate::PlannerError;
+use ast::expr::{
+    CompiledColumnReference, Expression, FunctionCall, NamedExpression, SortExpression,
+};
+use ast::rel::logical::{GroupBy, LogicalOperator, Project, Sort};
+use functions::registry::Registry;
+
+/// Rewrites DISTINCT ON group bys into a group by taking the first value of each column per key.
+/// If the distinct on is directly below a sort, the sort is also pushed below the group by so
+/// that the first row seen for each key is the first as per the sort order.
+/// ie
+/// Sort(s) -> GroupBy(distinct on k, e) -> source
+/// becomes
+/// Sort(s) -> GroupBy(k, first(e)) -> Sort(s) -> Project(e, k) -> source
+pub(super) fn rewrite_distinct_on(
+    operator: &mut LogicalOperator,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    match operator {
+        LogicalOperator::Sort(sort) => {
+            if let LogicalOperator::GroupBy(group_by) = sort.source.as_mut() {
+                if group_by.distinct_on {
+                    rewrite_group_by(group_by, Some(&sort.sort_expressions), function_registry)?;
+                }
+            }
+        }
+        LogicalOperator::GroupBy(group_by) if group_by.distinct_on => {
+            rewrite_group_by(group_by, None, function_registry)?;
+        }
+        _ => {}
+    }
+
+    for child in operator.children_mut() {
+        rewrite_distinct_on(child, function_registry)?;
+    }
+    Ok(())
+}
+
+fn rewrite_group_by(
+    group_by: &mut GroupBy,
+    sort_expressions: Option<&Vec<SortExpression>>,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    let expressions = std::mem::take(&mut group_by.expressions);
+    let key_expressions = std::mem::take(&mut group_by.key_expressions);
+    let source = std::mem::take(&mut group_by.source);
+    let expression_count = expressions.len();
+
+    // The output expressions come first so that the sort expressions (which were compiled
+    // against the output of the group by) still line up.
+    let mut project_expressions = expressions.clone();
+    for (idx, expression) in key_expressions.into_iter().enumerate() {
+        project_expressions.push(NamedExpression {
+            alias: Some(format!("_distinct_key{}", idx + 1)),
+            expression,
+        });
+    }
+
+    let mut new_source = LogicalOperator::Project(Project {
+        distinct: false,
+        expressions: project_expressions,
+        source,
+    });
+
+    if let Some(sort_expressions) = sort_expressions {
+        new_source = LogicalOperator::Sort(Sort {
+            sort_expressions: sort_expressions.clone(),
+            source: Box::new(new_source),
+        });
+    }
+
+    let source_fields: Vec<_> = fields_for_operator(&new_source).collect();
+    let column = |offset: usize| {
+        Expression::CompiledColumnReference(CompiledColumnReference {
+            offset,
+            datatype: source_fields[offset].data_type,
+        })
+    };
+
+    for (offset, named_expression) in expressions.into_iter().enumerate() {
+        let mut expression = Expression::FunctionCall(FunctionCall {
+            function_name: "first".to_string(),
+            args: vec![column(offset)],
+        });
+        compile_functions_in_expr(&mut expression, &source_fields, function_registry)?;
+        group_by.expressions.push(NamedExpression {
+            alias: named_expression.alias,
+            expression,
+        });
+    }
+    group_by.key_expressions = (expression_count..source_fields.len())
+        .map(column)
+        .collect();
+    group_by.source = Box::new(new_source);
+    group_by.distinct_on = false;
+    Ok(())
+}
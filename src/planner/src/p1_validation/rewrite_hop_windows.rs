@@ -0,0 +1,152 @@
+use crate::utils::logical::fields_for_operator;
+use crate::PlannerError;
+use ast::expr::{CompiledColumnReference, CompiledFunctionCall, Expression, NamedExpression};
+use ast::rel::logical::{GroupBy, LogicalOperator, Project, UnionAll};
+use data::{DataType, Datum};
+use functions::registry::Registry;
+use functions::{FunctionSignature, FunctionType};
+
+/// Rewrites group bys over hop(ts, slide, size) so that each row is counted in every one of the
+/// size/slide windows it falls in, the source is repeated once per window with the window start
+/// tacked on the end, and the hop calls are replaced with a reference to it.
+/// ie
+/// GroupBy(hop(ts, 5, 15), e) -> source
+/// becomes
+/// GroupBy(w, e) -> UnionAll(
+///   Project(source.*, hop(ts, 5, 15, 0) AS w) -> source,
+///   Project(source.*, hop(ts, 5, 15, 1) AS w) -> source,
+///   Project(source.*, hop(ts, 5, 15, 2) AS w) -> source,
+/// )
+pub(super) fn rewrite_hop_windows(
+    operator: &mut LogicalOperator,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    for child in operator.children_mut() {
+        rewrite_hop_windows(child, function_registry)?;
+    }
+
+    if let LogicalOperator::GroupBy(group_by) = operator {
+        let mut hops = group_by.key_expressions.iter().filter(|key| is_hop(key));
+        if let Some(hop) = hops.next() {
+            if hops.any(|other| other != hop) {
+                return Err(PlannerError::HopWindowNotSupported(
+                    "only one hopping window can be grouped by",
+                ));
+            }
+            let hop = hop.clone();
+            rewrite_group_by(group_by, &hop, function_registry)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_hop(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::CompiledFunctionCall(function_call)
+            if function_call.signature.name == "hop" && function_call.args.len() == 3
+    )
+}
+
+fn rewrite_group_by(
+    group_by: &mut GroupBy,
+    hop: &Expression,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    let args = if let Expression::CompiledFunctionCall(function_call) = hop {
+        function_call.args.to_vec()
+    } else {
+        panic!("Expected a hop function call")
+    };
+    let (slide, size) = match (&args[1], &args[2]) {
+        (Expression::Constant(slide, _), Expression::Constant(size, _)) => {
+            (slide.as_maybe_integer(), size.as_maybe_integer())
+        }
+        _ => {
+            return Err(PlannerError::HopWindowNotSupported(
+                "the slide and size must be constants",
+            ))
+        }
+    };
+    let window_count = match (slide, size) {
+        (Some(slide), Some(size)) if slide > 0 && size > 0 && size % slide == 0 => size / slide,
+        _ => {
+            return Err(PlannerError::HopWindowNotSupported(
+                "the size must be a positive multiple of the slide",
+            ))
+        }
+    };
+
+    let lookup_sig = FunctionSignature {
+        name: "hop",
+        args: vec![
+            DataType::Timestamp,
+            DataType::Integer,
+            DataType::Integer,
+            DataType::Integer,
+        ],
+        ret: DataType::Null,
+    };
+    let (signature, function) = match function_registry.resolve_function(&lookup_sig)? {
+        (signature, FunctionType::Scalar(function)) => (signature, function),
+        _ => panic!("Expected hop to be a scalar function"),
+    };
+
+    let source = std::mem::take(&mut group_by.source);
+    let source_fields: Vec<_> = fields_for_operator(&source).collect();
+    let window = Expression::CompiledColumnReference(CompiledColumnReference {
+        offset: source_fields.len(),
+        datatype: DataType::Timestamp,
+    });
+
+    let sources = (0..window_count)
+        .map(|n| {
+            let mut expressions: Vec<_> = source_fields
+                .iter()
+                .enumerate()
+                .map(|(offset, field)| NamedExpression {
+                    alias: Some(field.alias.clone()),
+                    expression: Expression::CompiledColumnReference(CompiledColumnReference {
+                        offset,
+                        datatype: field.data_type,
+                    }),
+                })
+                .collect();
+            let mut window_args = args.clone();
+            window_args.push(Expression::Constant(Datum::from(n), DataType::Integer));
+            expressions.push(NamedExpression {
+                alias: Some("_hop_window".to_string()),
+                expression: Expression::CompiledFunctionCall(CompiledFunctionCall {
+                    function,
+                    args: Box::from(window_args),
+                    expr_buffer: Box::from(vec![]),
+                    signature: Box::new(signature.clone()),
+                }),
+            });
+            LogicalOperator::Project(Project {
+                distinct: false,
+                expressions,
+                source: source.clone(),
+            })
+        })
+        .collect();
+
+    for key in &mut group_by.key_expressions {
+        replace_hop(key, hop, &window);
+    }
+    for named_expression in &mut group_by.expressions {
+        replace_hop(&mut named_expression.expression, hop, &window);
+    }
+    group_by.source = Box::new(LogicalOperator::UnionAll(UnionAll { sources }));
+    Ok(())
+}
+
+fn replace_hop(expression: &mut Expression, hop: &Expression, window: &Expression) {
+    if expression == hop {
+        *expression = window.clone();
+    } else {
+        for child in expression.children_mut() {
+            replace_hop(child, hop, window);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::p1_validation::compile_functions_and_refs::compile_functions_in_expr;
+use crate::utils::logical::fields_for_operator;
+use crate::{Field, PlannerError};
+use ast::expr::{CompiledColumnReference, Expression, FunctionCall, NamedExpression};
+use ast::rel::logical::{Filter, GroupBy, LogicalOperator, Project, SetOperator, UnionAll};
+use functions::registry::Registry;
+
+/// Rewrites UNION, INTERSECT and EXCEPT into group bys over a union all.
+/// UNION simply groups by every column
+/// ie
+/// Union(l, r)
+/// becomes
+/// GroupBy(c) -> UnionAll(l, r)
+/// INTERSECT and EXCEPT tag each row with which side it came from and then filter on the counts
+/// ie
+/// Intersect(l, r)
+/// becomes
+/// Project(c) -> Filter(left > 0 and right > 0) -> GroupBy(c, sum(left), sum(right))
+///   -> UnionAll(Project(c, 1, 0) -> l, Project(c, 0, 1) -> r)
+pub(super) fn rewrite_set_operations(
+    operator: &mut LogicalOperator,
+    function_registry: &Registry,
+) -> Result<(), PlannerError> {
+    for child in operator.children_mut() {
+        rewrite_set_operations(child, function_registry)?;
+    }
+
+    if let LogicalOperator::SetOperation(set_operation) = operator {
+        let left = std::mem::take(set_operation.left.as_mut());
+        let right = std::mem::take(set_operation.right.as_mut());
+        *operator = rewrite_set_operation(set_operation.operator, left, right, function_registry)?;
+    }
+    Ok(())
+}
+
+fn rewrite_set_operation(
+    operator: SetOperator,
+    left: LogicalOperator,
+    right: LogicalOperator,
+    function_registry: &Registry,
+) -> Result<LogicalOperator, PlannerError> {
+    let fields: Vec<_> = fields_for_operator(&left).collect();
+    let column_count = fields.len();
+
+    if operator == SetOperator::Union {
+        return Ok(LogicalOperator::GroupBy(GroupBy {
+            expressions: named_columns(&fields),
+            key_expressions: columns(&fields),
+            distinct_on: false,
+            source: Box::new(LogicalOperator::UnionAll(UnionAll {
+                sources: vec![left, right],
+            })),
+        }));
+    }
+
+    let source = LogicalOperator::UnionAll(UnionAll {
+        sources: vec![tag_side(left, 1, 0), tag_side(right, 0, 1)],
+    });
+    let source_fields: Vec<_> = fields_for_operator(&source).collect();
+
+    let mut expressions = named_columns(&fields);
+    for (offset, alias) in [
+        (column_count, "_set_left"),
+        (column_count + 1, "_set_right"),
+    ]
+    .iter()
+    {
+        let mut expression = Expression::FunctionCall(FunctionCall {
+            function_name: "sum".to_string(),
+            args: vec![column(*offset, &source_fields)],
+        });
+        compile_functions_in_expr(&mut expression, &source_fields, function_registry)?;
+        expressions.push(NamedExpression {
+            alias: Some(alias.to_string()),
+            expression,
+        });
+    }
+    let group_by = LogicalOperator::GroupBy(GroupBy {
+        expressions,
+        key_expressions: columns(&fields),
+        distinct_on: false,
+        source: Box::new(source),
+    });
+
+    let group_by_fields: Vec<_> = fields_for_operator(&group_by).collect();
+    let compare = |offset: usize, function_name: &str| {
+        Expression::FunctionCall(FunctionCall {
+            function_name: function_name.to_string(),
+            args: vec![column(offset, &group_by_fields), Expression::from(0)],
+        })
+    };
+    let right_predicate = if operator == SetOperator::Intersect {
+        compare(column_count + 1, ">")
+    } else {
+        compare(column_count + 1, "=")
+    };
+    let mut predicate = Expression::FunctionCall(FunctionCall {
+        function_name: "and".to_string(),
+        args: vec![compare(column_count, ">"), right_predicate],
+    });
+    compile_functions_in_expr(&mut predicate, &group_by_fields, function_registry)?;
+
+    Ok(LogicalOperator::Project(Project {
+        distinct: false,
+        expressions: named_columns(&fields),
+        source: Box::new(LogicalOperator::Filter(Filter {
+            predicate,
+            source: Box::new(group_by),
+        })),
+    }))
+}
+
+/// Wraps one side of an intersect/except in a project that adds the left and right markers
+fn tag_side(source: LogicalOperator, left: i32, right: i32) -> LogicalOperator {
+    let fields: Vec<_> = fields_for_operator(&source).collect();
+    let mut expressions = named_columns(&fields);
+    expressions.push(NamedExpression {
+        alias: Some("_set_left".to_string()),
+        expression: Expression::from(left),
+    });
+    expressions.push(NamedExpression {
+        alias: Some("_set_right".to_string()),
+        expression: Expression::from(right),
+    });
+    LogicalOperator::Project(Project {
+        distinct: false,
+        expressions,
+        source: Box::new(source),
+    })
+}
+
+fn column(offset: usize, fields: &[Field]) -> Expression {
+    Expression::CompiledColumnReference(CompiledColumnReference {
+        offset,
+        datatype: fields[offset].data_type,
+    })
+}
+
+fn columns(fields: &[Field]) -> Vec<Expression> {
+    (0..fields.len()).map(|idx| column(idx, fields)).collect()
+}
+
+fn named_columns(fields: &[Field]) -> Vec<NamedExpression> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| NamedExpression {
+            alias: Some(field.alias.clone()),
+            expression: column(idx, fields),
+        })
+        .collect()
+}
@@ -0,0 +1,430 @@
+use crate::utils::expr::decompose_predicate;
+use crate::utils::logical::fieldnames_for_operator;
+use crate::PlannerError;
+use ast::expr::{ColumnReference, Expression, FunctionCall, NamedExpression, Subquery};
+use ast::rel::logical::{Filter, Join, JoinType, LogicalOperator, Project};
+
+/// Rewrites IN/EXISTS subqueries in where clauses into semi/anti joins.
+/// ie
+/// Filter(a IN (SELECT b FROM t2 WHERE t2.c = t1.c)) -> t1
+/// becomes
+/// Join(semi, a = _subquery1_1 and _subquery1_key1 = t1.c)
+///   -> t1
+///   -> Project(_subquery1_1, _subquery1_key1) -> Project(b, c as _subquery1_key1) -> Filter -> t2
+/// Any correlated conditions are pulled up out of the subquery's where clause and into the join
+/// condition.
+pub(super) fn rewrite_subqueries(query: &mut LogicalOperator) -> Result<(), PlannerError> {
+    rewrite_subqueries_impl(query, &mut 0)
+}
+
+fn rewrite_subqueries_impl(
+    operator: &mut LogicalOperator,
+    counter: &mut usize,
+) -> Result<(), PlannerError> {
+    if let LogicalOperator::Filter(filter) = operator {
+        if contains_subquery(&filter.predicate) {
+            let mut source = std::mem::take(&mut filter.source);
+            let mut remaining = vec![];
+
+            for predicate in decompose_predicate(std::mem::take(&mut filter.predicate)) {
+                let (subquery, join_type) = match predicate {
+                    Expression::Subquery(subquery) => (subquery, JoinType::Semi),
+                    Expression::FunctionCall(FunctionCall {
+                        function_name,
+                        mut args,
+                    }) if function_name == "not"
+                        && matches!(args.as_slice(), [Expression::Subquery(_)]) =>
+                    {
+                        if let Some(Expression::Subquery(subquery)) = args.pop() {
+                            (subquery, JoinType::Anti)
+                        } else {
+                            unreachable!()
+                        }
+                    }
+                    predicate if contains_subquery(&predicate) => {
+                        return Err(PlannerError::SubqueryNotSupported(
+                            "subqueries can only be used as (NOT) IN/EXISTS conditions and'ed together in a where clause",
+                        ))
+                    }
+                    predicate => {
+                        remaining.push(predicate);
+                        continue;
+                    }
+                };
+
+                *counter += 1;
+                let (right, on) = build_join_right(subquery, *counter)?;
+                source = Box::new(LogicalOperator::Join(Join {
+                    left: source,
+                    right: Box::new(right),
+                    on,
+                    join_type,
+                }));
+            }
+
+            *operator = if remaining.is_empty() {
+                *source
+            } else {
+                LogicalOperator::Filter(Filter {
+                    predicate: and(remaining),
+                    source,
+                })
+            };
+        }
+    } else if operator.subqueries_mut().next().is_some() {
+        return Err(PlannerError::SubqueryNotSupported(
+            "subqueries can only be used in where clauses",
+        ));
+    }
+
+    for child in operator.children_mut() {
+        rewrite_subqueries_impl(child, counter)?;
+    }
+    Ok(())
+}
+
+/// Builds the right hand side of the join along with the join condition
+fn build_join_right(
+    subquery: Subquery,
+    n: usize,
+) -> Result<(LogicalOperator, Expression), PlannerError> {
+    let mut query = *subquery.query;
+    // Order doesn't matter for a semi/anti join
+    while let LogicalOperator::Sort(sort) = query {
+        query = *sort.source;
+    }
+
+    let value_fields: Vec<_> = fieldnames_for_operator(&query)
+        .map(|(_qualifier, alias)| alias.to_string())
+        .collect();
+
+    let mut conditions = vec![];
+    let is_in = subquery.operand.is_some();
+    if let Some(operand) = subquery.operand {
+        let operands = match *operand {
+            Expression::FunctionCall(FunctionCall {
+                function_name,
+                args,
+            }) if function_name == "row" => args,
+            operand => vec![operand],
+        };
+        if operands.len() != value_fields.len() {
+            return Err(PlannerError::RowValueMismatch(
+                operands.len(),
+                value_fields.len(),
+            ));
+        }
+        for (idx, operand) in operands.into_iter().enumerate() {
+            conditions.push(Expression::FunctionCall(FunctionCall {
+                function_name: "=".to_string(),
+                args: vec![operand, column(&format!("_subquery{}_{}", n, idx + 1))],
+            }));
+        }
+    }
+
+    let key_fields = decorrelate(&mut query, n, &mut conditions)?;
+
+    // Rename the subquery's columns so they can't clash with the columns from the outer query,
+    // EXISTS doesn't care about the values so they're dropped.
+    let mut expressions = vec![];
+    if is_in {
+        for (idx, alias) in value_fields.into_iter().enumerate() {
+            expressions.push(NamedExpression {
+                alias: Some(format!("_subquery{}_{}", n, idx + 1)),
+                expression: column(&alias),
+            });
+        }
+    }
+    for alias in key_fields {
+        expressions.push(NamedExpression {
+            expression: column(&alias),
+            alias: Some(alias),
+        });
+    }
+
+    let right = LogicalOperator::Project(Project {
+        distinct: false,
+        expressions,
+        source: Box::new(query),
+    });
+    Ok((right, and(conditions)))
+}
+
+/// Pulls the correlated conditions (those referencing columns from the outer query) out of the
+/// subquery's where clause, exposing the inner columns they need as extra columns.
+/// Returns the names of the extra columns.
+fn decorrelate(
+    query: &mut LogicalOperator,
+    n: usize,
+    conditions: &mut Vec<Expression>,
+) -> Result<Vec<String>, PlannerError> {
+    let (expressions, filter) = match query {
+        LogicalOperator::Project(Project {
+            expressions,
+            source,
+            ..
+        }) => match source.as_mut() {
+            LogicalOperator::Filter(filter) => (expressions, filter),
+            _ => return Ok(vec![]),
+        },
+        LogicalOperator::GroupBy(group_by) => {
+            if let LogicalOperator::Filter(filter) = group_by.source.as_ref() {
+                if !correlated_predicates(filter).is_empty() {
+                    return Err(PlannerError::SubqueryNotSupported(
+                        "correlated subqueries can't contain aggregates",
+                    ));
+                }
+            }
+            return Ok(vec![]);
+        }
+        _ => return Ok(vec![]),
+    };
+
+    let correlated = correlated_predicates(filter);
+    if correlated.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let inner_fields: Vec<_> = fieldnames_for_operator(&filter.source)
+        .map(|(qualifier, alias)| (qualifier.map(str::to_string), alias.to_string()))
+        .collect();
+
+    let mut key_fields = vec![];
+    let mut uncorrelated = vec![];
+    for (idx, mut predicate) in
+        decompose_predicate(std::mem::take(&mut filter.predicate)).enumerate()
+    {
+        if !correlated.contains(&idx) {
+            uncorrelated.push(predicate);
+            continue;
+        }
+        replace_inner_references(&mut predicate, &inner_fields, &mut |column_reference| {
+            let alias = format!("_subquery{}_key{}", n, key_fields.len() + 1);
+            expressions.push(NamedExpression {
+                alias: Some(alias.clone()),
+                expression: Expression::ColumnReference(column_reference),
+            });
+            key_fields.push(alias.clone());
+            column(&alias)
+        });
+        conditions.push(predicate);
+    }
+    filter.predicate = and(uncorrelated);
+    Ok(key_fields)
+}
+
+/// Returns the indexes of the predicates (as per decompose_predicate) that reference columns not
+/// provided by the filter's source.
+fn correlated_predicates(filter: &Filter) -> Vec<usize> {
+    let inner_fields: Vec<_> = fieldnames_for_operator(&filter.source).collect();
+    decompose_predicate(filter.predicate.clone())
+        .enumerate()
+        .filter(|(_idx, predicate)| !only_inner_references(predicate, &inner_fields))
+        .map(|(idx, _predicate)| idx)
+        .collect()
+}
+
+fn only_inner_references(expression: &Expression, inner_fields: &[(Option<&str>, &str)]) -> bool {
+    if let Expression::ColumnReference(column_reference) = expression {
+        resolves(column_reference, inner_fields.iter().copied())
+    } else {
+        expression
+            .children()
+            .all(|child| only_inner_references(child, inner_fields))
+    }
+}
+
+fn replace_inner_references<F: FnMut(ColumnReference) -> Expression>(
+    expression: &mut Expression,
+    inner_fields: &[(Option<String>, String)],
+    replace: &mut F,
+) {
+    if let Expression::ColumnReference(column_reference) = expression {
+        let fields = inner_fields
+            .iter()
+            .map(|(qualifier, alias)| (qualifier.as_deref(), alias.as_str()));
+        if resolves(column_reference, fields) {
+            *expression = replace(column_reference.clone());
+        }
+    } else {
+        for child in expression.children_mut() {
+            replace_inner_references(child, inner_fields, replace);
+        }
+    }
+}
+
+fn resolves<'a, I: IntoIterator<Item = (Option<&'a str>, &'a str)>>(
+    column_reference: &ColumnReference,
+    fields: I,
+) -> bool {
+    fields.into_iter().any(|(qualifier, alias)| {
+        alias == column_reference.alias
+            && (column_reference.qualifier.is_none()
+                || column_reference.qualifier.as_deref() == qualifier)
+    })
+}
+
+fn contains_subquery(expression: &Expression) -> bool {
+    if let Expression::Subquery(_) = expression {
+        true
+    } else {
+        expression.children().any(contains_subquery)
+    }
+}
+
+fn column(alias: &str) -> Expression {
+    Expression::ColumnReference(ColumnReference {
+        qualifier: None,
+        alias: alias.to_string(),
+        star: false,
+    })
+}
+
+fn and(predicates: Vec<Expression>) -> Expression {
+    let mut iter = predicates.into_iter();
+    match iter.next() {
+        Some(first) => iter.fold(first, |a, b| {
+            Expression::FunctionCall(FunctionCall {
+                function_name: "and".to_string(),
+                args: vec![a, b],
+            })
+        }),
+        None => Expression::from(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::logical::{TableAlias, Values};
+    use data::DataType;
+    use parser::parse_expression;
+
+    fn filter(predicate: &str, source: LogicalOperator) -> LogicalOperator {
+        LogicalOperator::Filter(Filter {
+            predicate: parse_expression(predicate).unwrap(),
+            source: Box::new(source),
+        })
+    }
+
+    fn project(columns: &[(&str, &str)], source: LogicalOperator) -> LogicalOperator {
+        LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: columns
+                .iter()
+                .map(|(expression, alias)| NamedExpression {
+                    expression: parse_expression(expression).unwrap(),
+                    alias: Some(alias.to_string()),
+                })
+                .collect(),
+            source: Box::new(source),
+        })
+    }
+
+    fn table(alias: &str) -> LogicalOperator {
+        LogicalOperator::TableAlias(TableAlias {
+            alias: alias.to_string(),
+            source: Box::new(LogicalOperator::Values(Values {
+                fields: vec![
+                    (DataType::Integer, "c".to_string()),
+                    (DataType::Integer, "d".to_string()),
+                ],
+                data: vec![],
+            })),
+        })
+    }
+
+    #[test]
+    fn test_rewrite_in() -> Result<(), PlannerError> {
+        let subquery = project(&[("d", "d")], table("t2"));
+        let mut operator = LogicalOperator::Filter(Filter {
+            predicate: Expression::FunctionCall(FunctionCall {
+                function_name: "and".to_string(),
+                args: vec![
+                    Expression::Subquery(Subquery {
+                        operand: Some(Box::new(parse_expression("c").unwrap())),
+                        query: Box::new(subquery.clone()),
+                    }),
+                    parse_expression("d > 1").unwrap(),
+                ],
+            }),
+            source: Box::new(table("t1")),
+        });
+        rewrite_subqueries(&mut operator)?;
+
+        let expected = filter(
+            "d > 1",
+            LogicalOperator::Join(Join {
+                left: Box::new(table("t1")),
+                right: Box::new(project(&[("d", "_subquery1_1")], subquery)),
+                on: parse_expression("c = _subquery1_1").unwrap(),
+                join_type: JoinType::Semi,
+            }),
+        );
+        assert_eq!(operator, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_correlated_not_exists() -> Result<(), PlannerError> {
+        let subquery = project(
+            &[("1", "one")],
+            filter("t2.c = t1.c and t2.d = 1", table("t2")),
+        );
+        let mut operator = LogicalOperator::Filter(Filter {
+            predicate: Expression::FunctionCall(FunctionCall {
+                function_name: "not".to_string(),
+                args: vec![Expression::Subquery(Subquery {
+                    operand: None,
+                    query: Box::new(subquery),
+                })],
+            }),
+            source: Box::new(table("t1")),
+        });
+        rewrite_subqueries(&mut operator)?;
+
+        let expected = LogicalOperator::Join(Join {
+            left: Box::new(table("t1")),
+            right: Box::new(project(
+                &[("_subquery1_key1", "_subquery1_key1")],
+                project(
+                    &[("1", "one"), ("t2.c", "_subquery1_key1")],
+                    filter("t2.d = 1", table("t2")),
+                ),
+            )),
+            on: parse_expression("_subquery1_key1 = t1.c").unwrap(),
+            join_type: JoinType::Anti,
+        });
+        assert_eq!(operator, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_errors() {
+        let subquery = Expression::Subquery(Subquery {
+            operand: Some(Box::new(parse_expression("(c, d)").unwrap())),
+            query: Box::new(project(&[("d", "d")], table("t2"))),
+        });
+
+        let mut operator = LogicalOperator::Filter(Filter {
+            predicate: subquery.clone(),
+            source: Box::new(table("t1")),
+        });
+        assert!(matches!(
+            rewrite_subqueries(&mut operator),
+            Err(PlannerError::RowValueMismatch(2, 1))
+        ));
+
+        let mut operator = LogicalOperator::Filter(Filter {
+            predicate: Expression::FunctionCall(FunctionCall {
+                function_name: "or".to_string(),
+                args: vec![subquery, Expression::from(true)],
+            }),
+            source: Box::new(table("t1")),
+        });
+        assert!(matches!(
+            rewrite_subqueries(&mut operator),
+            Err(PlannerError::SubqueryNotSupported(_))
+        ));
+    }
+}
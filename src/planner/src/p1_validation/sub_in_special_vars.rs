@@ -1,33 +1,58 @@
 use ast::expr::*;
 use ast::rel::logical::LogicalOperator;
-use data::{DataType, Datum};
+use data::{DataType, Datum, Session};
 
-/// Mysql uses some @@ magic variables that they can select.
-/// This is here to replace some of them with Constants
-pub(super) fn sub_in_special_vars(query: &mut LogicalOperator) {
+/// Mysql uses some @@ magic variables that they can select, ie @@version_comment or
+/// @@session.transaction_isolation.
+/// This is here to replace them with Constants from the session's system variables.
+/// User variables (ie @x) are replaced too, these are null until they're SET
+pub(super) fn sub_in_special_vars(query: &mut LogicalOperator, session: &Session) {
     for child in query.children_mut() {
-        sub_in_special_vars(child);
+        sub_in_special_vars(child, session);
+    }
+
+    for subquery in query.subqueries_mut() {
+        sub_in_special_vars(subquery, session);
     }
 
     for expression in query.expressions_mut() {
-        if let Expression::ColumnReference(ColumnReference {
-            qualifier: _,
+        sub_in_special_vars_for_expr(expression, session);
+    }
+}
+
+fn sub_in_special_vars_for_expr(expression: &mut Expression, session: &Session) {
+    match expression {
+        Expression::ColumnReference(ColumnReference {
+            qualifier,
             alias,
-            star: _,
-        }) = expression
-        {
-            if alias.starts_with("@@") {
-                let constant = match alias.as_str() {
-                    "@@max_allowed_packet" => {
-                        Expression::Constant(Datum::from(0xffffff), DataType::Integer)
-                    }
-                    "@@socket" => Expression::Constant(Datum::from(""), DataType::Text),
-
-                    _ => continue,
-                };
-                *expression = constant;
+            star: false,
+        }) => {
+            let value = match qualifier.as_deref() {
+                None if alias.starts_with("@@") => session.variable(&alias[2..]),
+                // User variables are kept along with the system variables, @ and all
+                None if alias.starts_with('@') => Some(
+                    session
+                        .variable(alias)
+                        .unwrap_or((Datum::Null, DataType::Null)),
+                ),
+                Some("@@session") | Some("@@global") | Some("@@local") => session.variable(alias),
+                _ => return,
+            };
+            if let Some((datum, datatype)) = value {
+                *expression = Expression::Constant(datum, datatype);
+            }
+        }
+        Expression::FunctionCall(function_call) => {
+            for arg in function_call.args.iter_mut() {
+                sub_in_special_vars_for_expr(arg, session);
             }
         }
+        Expression::Cast(cast) => sub_in_special_vars_for_expr(&mut cast.expr, session),
+        Expression::Subquery(Subquery {
+            operand: Some(operand),
+            ..
+        }) => sub_in_special_vars_for_expr(operand, session),
+        _ => {}
     }
 }
 
@@ -36,7 +61,6 @@ mod tests {
     use super::*;
     use ast::expr::{Expression, NamedExpression};
     use ast::rel::logical::Project;
-    use data::{DataType, Datum};
 
     #[test]
     fn test_sub_in_special_vars() {
@@ -53,11 +77,82 @@ mod tests {
             source: Box::new(LogicalOperator::Single),
         });
 
-        sub_in_special_vars(&mut operator);
+        sub_in_special_vars(&mut operator, &Session::new(1));
 
         assert_eq!(
             operator.expressions_mut().next().unwrap(),
             &mut Expression::Constant(Datum::from(0xffffff), DataType::Integer)
         );
     }
+
+    #[test]
+    fn test_sub_in_special_vars_qualified() {
+        let session = Session::new(1);
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("sql_mode".to_string(), Datum::from("ANSI"));
+        let mut operator = LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: vec![NamedExpression {
+                alias: Some(String::from("1")),
+                expression: Expression::FunctionCall(FunctionCall {
+                    function_name: "concat".to_string(),
+                    args: vec![Expression::ColumnReference(ColumnReference {
+                        qualifier: Some("@@session".to_string()),
+                        alias: "sql_mode".to_string(),
+                        star: false,
+                    })],
+                }),
+            }],
+            source: Box::new(LogicalOperator::Single),
+        });
+
+        sub_in_special_vars(&mut operator, &session);
+
+        assert_eq!(
+            operator.expressions_mut().next().unwrap(),
+            &mut Expression::FunctionCall(FunctionCall {
+                function_name: "concat".to_string(),
+                args: vec![Expression::Constant(Datum::from("ANSI"), DataType::Text)],
+            })
+        );
+    }
+
+    #[test]
+    fn test_sub_in_user_vars() {
+        let session = Session::new(1);
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("@x".to_string(), Datum::from(5));
+        let user_var = |alias: &str| {
+            let mut operator = LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    alias: None,
+                    expression: Expression::ColumnReference(ColumnReference {
+                        qualifier: None,
+                        alias: alias.to_string(),
+                        star: false,
+                    }),
+                }],
+                source: Box::new(LogicalOperator::Single),
+            });
+            sub_in_special_vars(&mut operator, &session);
+            let expression = operator.expressions_mut().next().unwrap().clone();
+            expression
+        };
+
+        assert_eq!(
+            user_var("@x"),
+            Expression::Constant(Datum::from(5), DataType::Integer)
+        );
+        assert_eq!(
+            user_var("@y"),
+            Expression::Constant(Datum::Null, DataType::Null)
+        );
+    }
 }
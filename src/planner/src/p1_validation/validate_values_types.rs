@@ -1,6 +1,9 @@
 use crate::utils::expr::type_for_expression;
+use crate::utils::logical::insert_columns;
 use crate::PlannerError;
+use ast::expr::Expression;
 use ast::rel::logical::{LogicalOperator, TableInsert};
+use data::rust_decimal::prelude::ToPrimitive;
 use data::DataType;
 
 /// Walks "values" (ie insert .. values ()) and populates types in the header,
@@ -10,14 +13,19 @@ pub(super) fn validate_values_types(query: &mut LogicalOperator) -> Result<(), P
         validate_values_types(child)?;
     }
 
-    if let LogicalOperator::TableInsert(TableInsert { table, source }) = query {
+    if let LogicalOperator::TableInsert(TableInsert {
+        table,
+        source,
+        columns,
+        ..
+    }) = query
+    {
         if let (LogicalOperator::Values(values), LogicalOperator::ResolvedTable(resolved_tables)) =
             (source.as_mut(), table.as_mut())
         {
-            values.fields = resolved_tables
-                .columns
-                .iter()
-                .map(|(alias, dt)| (*dt, alias.clone()))
+            values.fields = insert_columns(columns, resolved_tables)?
+                .into_iter()
+                .map(|(alias, dt)| (dt, alias))
                 .collect();
 
             let table_types: Vec<_> = values
@@ -25,12 +33,16 @@ pub(super) fn validate_values_types(query: &mut LogicalOperator) -> Result<(), P
                 .iter()
                 .map(|(datatype, _)| *datatype)
                 .collect();
-            for row in &values.data {
+            for row in &mut values.data {
+                for (expr, field) in row.iter_mut().zip(values.fields.iter()) {
+                    coerce_integer_literal(expr, field.0, &field.1)?;
+                }
                 let row_types: Vec<_> = row.iter().map(type_for_expression).collect();
-                let is_match = row_types
-                    .iter()
-                    .zip(table_types.iter())
-                    .all(|(row, table)| row == table || *row == DataType::Null);
+                let is_match = row_types.len() == table_types.len()
+                    && row_types
+                        .iter()
+                        .zip(table_types.iter())
+                        .all(|(row, table)| row == table || *row == DataType::Null);
                 if !is_match {
                     return Err(PlannerError::InsertMismatch(table_types, row_types));
                 }
@@ -39,3 +51,32 @@ pub(super) fn validate_values_types(query: &mut LogicalOperator) -> Result<(), P
     }
     Ok(())
 }
+
+/// Integer literals are parsed as ints or bigints, when they're inserted into one of the other
+/// integer types we'll convert them over as long as they're in range for the column.
+fn coerce_integer_literal(
+    expr: &mut Expression,
+    datatype: DataType,
+    column: &str,
+) -> Result<(), PlannerError> {
+    if let Expression::Constant(datum, literal_type) = expr {
+        let value = match literal_type {
+            _ if *literal_type == datatype || datatype.integer_range().is_none() => return Ok(()),
+            DataType::Integer => datum.as_maybe_integer().map(i128::from),
+            DataType::BigInt => datum.as_maybe_bigint().map(i128::from),
+            // Too big for a bigint, ie unsigned bigints past i64::MAX
+            DataType::Decimal(_, 0) => datum
+                .as_maybe_decimal()
+                .and_then(|d| d.to_u64())
+                .map(i128::from),
+            _ => return Ok(()),
+        };
+        if let Some(value) = value {
+            let datum = datatype
+                .integer_datum(value)
+                .ok_or_else(|| PlannerError::OutOfRange(column.to_string()))?;
+            *expr = Expression::Constant(datum, datatype);
+        }
+    }
+    Ok(())
+}
@@ -13,7 +13,7 @@ pub(super) fn fold_constants(query: &mut LogicalOperator, session: &Session) {
     }
 }
 
-fn fold_constants_for_expr(expr: &mut Expression, session: &Session) {
+pub(super) fn fold_constants_for_expr(expr: &mut Expression, session: &Session) {
     match expr {
         Expression::CompiledFunctionCall(function_call) => {
             for arg in function_call.args.iter_mut() {
@@ -44,6 +44,12 @@ fn fold_constants_for_expr(expr: &mut Expression, session: &Session) {
                     .function
                     .execute(session, &function_call.signature, &function_input)
                     .into_static();
+                // Anything that raised an error (ie an overflow) is left to be evaluated, and
+                // raise the error again, when the query is run. Otherwise a cached plan would
+                // quietly return null.
+                if session.take_out_of_range().is_some() {
+                    return;
+                }
 
                 *expr = Expression::Constant(constant, function_call.signature.ret);
             }
@@ -61,12 +67,13 @@ fn fold_constants_for_expr(expr: &mut Expression, session: &Session) {
         // Already a constant
         Expression::Constant(..) => {}
         // These should be gone by now.
-        Expression::Cast(_) | Expression::FunctionCall(_) | Expression::ColumnReference(_) => {
-            panic!(
-                "Hit {:?} in constant fold, this should be gone by now!",
-                expr
-            )
-        }
+        Expression::Cast(_)
+        | Expression::FunctionCall(_)
+        | Expression::ColumnReference(_)
+        | Expression::Subquery(_) => panic!(
+            "Hit {:?} in constant fold, this should be gone by now!",
+            expr
+        ),
     }
 }
 
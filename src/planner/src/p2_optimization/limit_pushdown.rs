@@ -0,0 +1,118 @@
+use ast::rel::logical::{Limit, LogicalOperator};
+
+/// Pushes limits down below projects and into each branch of a union all (keeping the limit
+/// above the union) so we can stop reading from the sources as early as possible.
+/// ie
+/// Limit 10 -> Project -> UnionAll(a, b)
+/// becomes
+/// Project -> Limit 10 -> UnionAll(Limit 10 -> a, Limit 10 -> b)
+pub(super) fn limit_pushdown(operator: &mut LogicalOperator) {
+    if let LogicalOperator::Limit(limit) = operator {
+        match limit.source.as_mut() {
+            LogicalOperator::Project(project) if !project.distinct => {
+                if let LogicalOperator::Limit(Limit {
+                    offset,
+                    limit,
+                    source,
+                }) = std::mem::take(operator)
+                {
+                    if let LogicalOperator::Project(mut project) = *source {
+                        project.source = Box::new(LogicalOperator::Limit(Limit {
+                            offset,
+                            limit,
+                            source: project.source,
+                        }));
+                        *operator = LogicalOperator::Project(project);
+                    }
+                }
+            }
+            LogicalOperator::UnionAll(union_all) => {
+                // Each branch has to give up enough rows to get past the offset
+                let branch_limit = limit.offset.saturating_add(limit.limit);
+                for source in &mut union_all.sources {
+                    let already_limited = match source {
+                        LogicalOperator::Limit(inner) => {
+                            inner.offset == 0 && inner.limit <= branch_limit
+                        }
+                        _ => false,
+                    };
+                    if !already_limited {
+                        *source = LogicalOperator::Limit(Limit {
+                            offset: 0,
+                            limit: branch_limit,
+                            source: Box::new(std::mem::take(source)),
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for child in operator.children_mut() {
+        limit_pushdown(child);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::expr::{Expression, NamedExpression};
+    use ast::rel::logical::{Project, UnionAll};
+
+    fn project(source: LogicalOperator) -> LogicalOperator {
+        LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: vec![NamedExpression {
+                alias: None,
+                expression: Expression::from(1),
+            }],
+            source: Box::new(source),
+        })
+    }
+
+    fn limit(offset: i64, limit: i64, source: LogicalOperator) -> LogicalOperator {
+        LogicalOperator::Limit(Limit {
+            offset,
+            limit,
+            source: Box::new(source),
+        })
+    }
+
+    #[test]
+    fn test_limit_pushdown_project() {
+        let mut operator = limit(1, 2, project(project(LogicalOperator::Single)));
+        limit_pushdown(&mut operator);
+        assert_eq!(
+            operator,
+            project(project(limit(1, 2, LogicalOperator::Single)))
+        );
+    }
+
+    #[test]
+    fn test_limit_pushdown_union_all() {
+        let union_all = |sources| LogicalOperator::UnionAll(UnionAll { sources });
+        let mut operator = limit(
+            1,
+            2,
+            union_all(vec![
+                LogicalOperator::Single,
+                project(LogicalOperator::Single),
+                limit(0, 1, LogicalOperator::Single),
+            ]),
+        );
+        limit_pushdown(&mut operator);
+        assert_eq!(
+            operator,
+            limit(
+                1,
+                2,
+                union_all(vec![
+                    limit(0, 3, LogicalOperator::Single),
+                    project(limit(0, 3, LogicalOperator::Single)),
+                    limit(0, 1, LogicalOperator::Single),
+                ])
+            )
+        );
+    }
+}
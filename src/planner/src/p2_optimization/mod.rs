@@ -4,7 +4,10 @@ use data::Session;
 
 pub(crate) mod collapse_projects;
 mod fold_constants;
+mod limit_pushdown;
 mod predicate_pushdown;
+mod prune_partitions;
+mod simplify_expressions;
 
 impl Planner {
     /// Optimizes the query by rewriting parts of it to be more efficient.
@@ -17,7 +20,10 @@ impl Planner {
         predicate_pushdown::predicate_pushdown(&mut query, &self.function_registry);
         // After pushing down the predicates it can open up some more options for constant folding
         fold_constants::fold_constants(&mut query, session);
+        simplify_expressions::simplify_expressions(&mut query, &self.function_registry);
+        prune_partitions::prune_partitions(&mut query, session);
         collapse_projects::collapse_projects(&mut query);
+        limit_pushdown::limit_pushdown(&mut query);
         Ok(query)
     }
 }
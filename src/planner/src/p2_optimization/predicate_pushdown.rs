@@ -137,6 +137,25 @@ fn pushdown_predicates_from_above(
                         _ => keep.push(condition),
                     }
                 }
+            } else if join.join_type == JoinType::Semi || join.join_type == JoinType::Anti {
+                // Semi and anti joins only output the left side so anything coming from above
+                // can only be about the left side.
+                left.extend(predicates);
+
+                for mut condition in join_predicates {
+                    match min_max_column_deps_for_expression(&mut condition) {
+                        // Conditions on just the right side restrict the rows we look for a
+                        // match in.
+                        Some((min, _max)) if min >= left_len => right.push(condition),
+                        // For a semi join a left row failing a condition on only the left can
+                        // never match so it's just a filter, for anti joins these rows would be
+                        // kept so we can't do the same.
+                        Some((_min, max)) if max < left_len && join.join_type == JoinType::Semi => {
+                            left.push(condition)
+                        }
+                        _ => keep.push(condition),
+                    }
+                }
             } else {
                 // Default implementation to play it safe for newly added join types
                 keep.extend(join_predicates);
@@ -164,6 +183,30 @@ fn pushdown_predicates_from_above(
             }
         }
 
+        LogicalOperator::JsonTable(json_table) => {
+            // Predicates on just the source's columns can filter the source rows before their
+            // documents get exploded, the rest have to stay above.
+            let source_len = fieldnames_for_operator(&json_table.source).count();
+            let mut below = vec![];
+            let mut above = vec![];
+            for mut predicate in predicates {
+                match min_max_column_deps_for_expression(&mut predicate) {
+                    Some((_min, max)) if max >= source_len => above.push(predicate),
+                    _ => below.push(predicate),
+                }
+            }
+            pushdown_predicates_from_above(&mut json_table.source, below, function_registry);
+
+            if !above.is_empty() {
+                let source = std::mem::take(operator);
+
+                *operator = LogicalOperator::Filter(Filter {
+                    predicate: combine_predicates(above, function_registry),
+                    source: Box::new(source),
+                });
+            }
+        }
+
         // The remaining operators we can never push through, (we technically could with
         // limit but it would have the opposite effect in actually creating more work
         // for the query engine)
@@ -0,0 +1,346 @@
+use crate::p2_optimization::fold_constants::fold_constants_for_expr;
+use crate::utils::expr::{decompose_predicate, min_max_column_deps_for_expression};
+use ast::expr::{CompiledColumnReference, Expression};
+use ast::rel::logical::{FileScan, LogicalOperator, PartitionMethod, Partitioning};
+use data::{Datum, Session};
+
+/// Marks the partitions of partitioned tables (and partitioned file scans) that can't contain
+/// any rows matching the filters directly above them as pruned so we don't bother scanning them.
+/// This needs to run after the predicates have been pushed down and constants folded.
+pub(super) fn prune_partitions(operator: &mut LogicalOperator, session: &Session) {
+    for child in operator.children_mut() {
+        prune_partitions(child, session);
+    }
+
+    if let LogicalOperator::Filter(filter) = operator {
+        let source = match filter.source.as_mut() {
+            LogicalOperator::TableAlias(table_alias) => table_alias.source.as_mut(),
+            source => source,
+        };
+        match source {
+            LogicalOperator::ResolvedTable(table) => {
+                if let Some(partitioning) = &mut table.partitioning {
+                    prune_for_predicate(&filter.predicate, partitioning);
+                }
+            }
+            LogicalOperator::FileScan(file_scan) if file_scan.partition_columns > 0 => {
+                prune_files_for_predicate(&filter.predicate, file_scan, session);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// For each of the predicates that only look at the partition columns we sub in each
+/// partition's values, if that folds down to anything but true the partition has no matching
+/// rows.
+fn prune_files_for_predicate(predicate: &Expression, file_scan: &mut FileScan, session: &Session) {
+    let first_partition_column = file_scan.columns.len() - file_scan.partition_columns;
+    let partition_predicates: Vec<_> = decompose_predicate(predicate.clone())
+        .filter_map(
+            |mut predicate| match min_max_column_deps_for_expression(&mut predicate) {
+                Some((min, _max)) if min >= first_partition_column => Some(predicate),
+                _ => None,
+            },
+        )
+        .collect();
+
+    for partition in &mut file_scan.partitions {
+        for predicate in &partition_predicates {
+            let mut predicate = predicate.clone();
+            sub_in_partition_values(&mut predicate, first_partition_column, &partition.values);
+            fold_constants_for_expr(&mut predicate, session);
+            if let Expression::Constant(value, _) = &predicate {
+                if value.as_maybe_boolean() != Some(true) {
+                    partition.pruned = true;
+                }
+            }
+        }
+    }
+}
+
+fn sub_in_partition_values(
+    expression: &mut Expression,
+    first_partition_column: usize,
+    values: &[Datum<'static>],
+) {
+    if let Expression::CompiledColumnReference(column) = expression {
+        let value = values[column.offset - first_partition_column].clone();
+        *expression = Expression::Constant(value, column.datatype);
+    } else {
+        for child in expression.children_mut() {
+            sub_in_partition_values(child, first_partition_column, values);
+        }
+    }
+}
+
+fn prune_for_predicate(predicate: &Expression, partitioning: &mut Partitioning) {
+    let function_call = if let Expression::CompiledFunctionCall(function_call) = predicate {
+        function_call
+    } else {
+        return;
+    };
+
+    match (function_call.signature.name, &*function_call.args) {
+        ("and", [left, right]) => {
+            prune_for_predicate(left, partitioning);
+            prune_for_predicate(right, partitioning);
+        }
+        (op, [left, right]) => {
+            // Normalize to column op constant
+            let (op, value) = match (left, right) {
+                (Expression::CompiledColumnReference(column), Expression::Constant(value, _))
+                    if is_partition_column(column, right, partitioning) =>
+                {
+                    (op, value)
+                }
+                (Expression::Constant(value, _), Expression::CompiledColumnReference(column))
+                    if is_partition_column(column, left, partitioning) =>
+                {
+                    (flip_comparison(op), value)
+                }
+                _ => return,
+            };
+
+            let partitions = &partitioning.partitions;
+            let keep: Vec<_> = (0..partitions.len())
+                .map(|idx| {
+                    // A range partition holds everything from the previous partition's bound
+                    // up to its own.
+                    let lower_bound = idx
+                        .checked_sub(1)
+                        .and_then(|prev| partitions[prev].upper_bound.as_ref());
+                    let upper_bound = partitions[idx].upper_bound.as_ref();
+                    if value.is_null() {
+                        // Comparisons against null never match anything
+                        return false;
+                    }
+                    match (partitioning.method, op) {
+                        (_, "=") => partitioning.partition_for(value) == Some(idx),
+                        (PartitionMethod::Range, "<") => lower_bound.map_or(true, |l| l < value),
+                        (PartitionMethod::Range, "<=") => lower_bound.map_or(true, |l| l <= value),
+                        (PartitionMethod::Range, ">") | (PartitionMethod::Range, ">=") => {
+                            upper_bound.map_or(true, |u| u > value)
+                        }
+                        _ => true,
+                    }
+                })
+                .collect();
+
+            for (partition, keep) in partitioning.partitions.iter_mut().zip(keep) {
+                if !keep {
+                    partition.pruned = true;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks the column is the partition column, and that the constant is of the same type so
+/// comparing the datums directly gives the same result as the comparison function would.
+fn is_partition_column(
+    column: &CompiledColumnReference,
+    constant: &Expression,
+    partitioning: &Partitioning,
+) -> bool {
+    matches!(constant, Expression::Constant(_, datatype) if *datatype == column.datatype)
+        && column.offset == partitioning.column
+}
+
+fn flip_comparison(op: &str) -> &str {
+    match op {
+        "<" => ">",
+        "<=" => ">=",
+        ">" => "<",
+        ">=" => "<=",
+        op => op,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::logical::{FilePartition, Filter, ResolvedTable, SerdeOptions, TablePartition};
+    use data::{DataType, SortOrder};
+    use functions::registry::Registry;
+    use functions::FunctionSignature;
+    use storage::Storage;
+
+    fn call(name: &'static str, args: Vec<Expression>, arg_type: DataType) -> Expression {
+        let function_registry = Registry::default();
+        let signature = FunctionSignature {
+            name,
+            args: args.iter().map(|_| arg_type).collect(),
+            ret: DataType::Boolean,
+        };
+        let (signature, function) = function_registry.resolve_function(&signature).unwrap();
+        Expression::CompiledFunctionCall(ast::expr::CompiledFunctionCall {
+            function: function.as_scalar(),
+            args: Box::from(args),
+            expr_buffer: Box::from(vec![]),
+            signature: Box::new(signature),
+        })
+    }
+
+    fn column() -> Expression {
+        Expression::CompiledColumnReference(CompiledColumnReference {
+            offset: 0,
+            datatype: DataType::Integer,
+        })
+    }
+
+    fn unpruned(predicate: Expression) -> Vec<String> {
+        let storage = Storage::new_in_mem().unwrap();
+        let partition = |name: &str, upper_bound: Option<i32>| TablePartition {
+            name: name.to_string(),
+            upper_bound: upper_bound.map(Datum::from),
+            table: storage.table(10, 1, vec![SortOrder::Asc]),
+            pruned: false,
+        };
+        let mut operator = LogicalOperator::Filter(Filter {
+            predicate,
+            source: Box::new(LogicalOperator::ResolvedTable(Box::new(ResolvedTable {
+                database: "default".to_string(),
+                name: "t".to_string(),
+                columns: vec![("a".to_string(), DataType::Integer)],
+                column_defaults: vec![None],
+                column_not_null: vec![false],
+                table: storage.table(8, 1, vec![SortOrder::Asc]),
+                statistics: vec![None],
+                partitioning: Some(Partitioning {
+                    method: PartitionMethod::Range,
+                    column: 0,
+                    column_type: DataType::Integer,
+                    partitions: vec![
+                        partition("p0", Some(10)),
+                        partition("p1", Some(20)),
+                        partition("p2", None),
+                    ],
+                }),
+                fulltext_indexes: vec![],
+                secondary_indexes: vec![],
+                primary_key_len: 1,
+                as_of: None,
+            }))),
+        });
+        prune_partitions(&mut operator, &Session::new(1));
+
+        if let LogicalOperator::Filter(filter) = operator {
+            if let LogicalOperator::ResolvedTable(table) = *filter.source {
+                return table
+                    .partitioning
+                    .unwrap()
+                    .partitions
+                    .into_iter()
+                    .filter(|partition| !partition.pruned)
+                    .map(|partition| partition.name)
+                    .collect();
+            }
+        }
+        panic!()
+    }
+
+    #[test]
+    fn test_prune_partitions() {
+        let int = DataType::Integer;
+        assert_eq!(
+            unpruned(call("=", vec![column(), Expression::from(15)], int)),
+            vec!["p1"]
+        );
+        assert_eq!(
+            unpruned(call("<", vec![column(), Expression::from(10)], int)),
+            vec!["p0"]
+        );
+        assert_eq!(
+            unpruned(call("<=", vec![column(), Expression::from(10)], int)),
+            vec!["p0", "p1"]
+        );
+        // Constant on the left
+        assert_eq!(
+            unpruned(call("<", vec![Expression::from(20), column()], int)),
+            vec!["p2"]
+        );
+
+        let between = call(
+            "and",
+            vec![
+                call(">=", vec![column(), Expression::from(10)], int),
+                call("<", vec![column(), Expression::from(20)], int),
+            ],
+            DataType::Boolean,
+        );
+        assert_eq!(unpruned(between), vec!["p1"]);
+
+        // Can't prune for other functions
+        assert_eq!(
+            unpruned(call("!=", vec![column(), Expression::from(15)], int)),
+            vec!["p0", "p1", "p2"]
+        );
+    }
+
+    fn unpruned_files(predicate: Expression) -> Vec<String> {
+        let partition = |p: i32| FilePartition {
+            name: format!("p={}", p),
+            values: vec![Datum::from(p)],
+            pruned: false,
+        };
+        let mut operator = LogicalOperator::Filter(Filter {
+            predicate,
+            source: Box::new(LogicalOperator::FileScan(FileScan {
+                directory: "data".to_string(),
+                serde_options: SerdeOptions::default(),
+                columns: vec![
+                    ("a".to_string(), DataType::Integer),
+                    ("p".to_string(), DataType::Integer),
+                ],
+                partition_columns: 1,
+                partitions: vec![partition(1), partition(2), partition(3)],
+            })),
+        });
+        prune_partitions(&mut operator, &Session::new(1));
+
+        if let LogicalOperator::Filter(filter) = operator {
+            if let LogicalOperator::FileScan(file_scan) = *filter.source {
+                return file_scan
+                    .partitions
+                    .into_iter()
+                    .filter(|partition| !partition.pruned)
+                    .map(|partition| partition.name)
+                    .collect();
+            }
+        }
+        panic!()
+    }
+
+    #[test]
+    fn test_prune_file_partitions() {
+        let int = DataType::Integer;
+        let column = |offset| {
+            Expression::CompiledColumnReference(CompiledColumnReference {
+                offset,
+                datatype: DataType::Integer,
+            })
+        };
+        assert_eq!(
+            unpruned_files(call("=", vec![column(1), Expression::from(2)], int)),
+            vec!["p=2"]
+        );
+
+        let predicate = call(
+            "and",
+            vec![
+                call(">", vec![column(1), Expression::from(1)], int),
+                call("=", vec![column(0), Expression::from(1)], int),
+            ],
+            DataType::Boolean,
+        );
+        assert_eq!(unpruned_files(predicate), vec!["p=2", "p=3"]);
+
+        // Predicates mixing file and partition columns can't be used
+        assert_eq!(
+            unpruned_files(call("=", vec![column(0), column(1)], int)),
+            vec!["p=1", "p=2", "p=3"]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use crate::utils::expr::{
+    combine_predicates, decompose_predicate, nullable_for_expression, type_for_expression,
+};
+use crate::utils::logical::source_fields_for_operator;
+use ast::expr::{CompiledFunctionCall, Expression};
+use ast::rel::logical::LogicalOperator;
+use data::DataType;
+use functions::registry::Registry;
+use functions::FunctionSignature;
+
+/// Rewrites expressions into simpler forms that are cheaper to evaluate for every row, ie
+/// `x AND TRUE` -> `x`, `NOT NOT x` -> `x`, `IF(TRUE, a, b)` -> `a`.
+/// This needs to run after constants have been folded.
+pub(super) fn simplify_expressions(query: &mut LogicalOperator, function_registry: &Registry) {
+    for child in query.children_mut() {
+        simplify_expressions(child, function_registry);
+    }
+
+    let source_nullable: Vec<_> = source_fields_for_operator(query)
+        .map(|field| field.nullable)
+        .collect();
+
+    for expr in query.expressions_mut() {
+        simplify_expression(expr, &source_nullable);
+    }
+
+    match query {
+        LogicalOperator::Filter(filter) => {
+            simplify_predicate(&mut filter.predicate, function_registry)
+        }
+        LogicalOperator::Join(join) => simplify_predicate(&mut join.on, function_registry),
+        _ => {}
+    }
+}
+
+fn simplify_expression(expr: &mut Expression, source_nullable: &[bool]) {
+    for child in expr.children_mut() {
+        simplify_expression(child, source_nullable);
+    }
+
+    let simplified = if let Expression::CompiledFunctionCall(function_call) = expr {
+        match (function_call.signature.name, function_call.args.as_ref()) {
+            ("and", [a, b]) if is_true(b) => Some(a.clone()),
+            ("and", [a, b]) if is_true(a) => Some(b.clone()),
+            ("or", [a, b]) if is_false(b) => Some(a.clone()),
+            ("or", [a, b]) if is_false(a) => Some(b.clone()),
+            ("not", [Expression::CompiledFunctionCall(inner)]) if inner.signature.name == "not" => {
+                Some(inner.args[0].clone())
+            }
+            // The if function may widen decimals, in which case we leave it be.
+            ("if", [condition @ Expression::Constant(..), a, b]) => {
+                let branch = if is_true(condition) { a } else { b };
+                if type_for_expression(branch) == function_call.signature.ret {
+                    Some(branch.clone())
+                } else {
+                    None
+                }
+            }
+            ("isnull", [a]) if !nullable_for_expression(a, source_nullable) => {
+                Some(Expression::from(false))
+            }
+            ("=", [a, b]) if a == b && !nullable_for_expression(a, source_nullable) => {
+                Some(Expression::from(true))
+            }
+            _ => None,
+        }
+    } else {
+        None
+    };
+
+    if let Some(simplified) = simplified {
+        *expr = simplified;
+    }
+}
+
+/// Filters and join conditions only care about whether the predicate is true, so here null and
+/// false are interchangeable.
+fn simplify_predicate(predicate: &mut Expression, function_registry: &Registry) {
+    let mut changed = false;
+    let mut conjuncts = vec![];
+    for conjunct in decompose_predicate(predicate.clone()) {
+        match conjunct {
+            Expression::Constant(value, _) if value.as_maybe_boolean() != Some(true) => {
+                *predicate = Expression::from(false);
+                return;
+            }
+            // x = x is true unless x is null
+            Expression::CompiledFunctionCall(function_call)
+                if function_call.signature.name == "="
+                    && function_call.args[0] == function_call.args[1] =>
+            {
+                changed = true;
+                let operand = function_call.args[0].clone();
+                let is_null = compile_call("isnull", vec![operand], function_registry);
+                conjuncts.push(compile_call("not", vec![is_null], function_registry));
+            }
+            conjunct => conjuncts.push(conjunct),
+        }
+    }
+
+    if changed {
+        *predicate = combine_predicates(conjuncts, function_registry);
+    }
+}
+
+fn compile_call(name: &str, args: Vec<Expression>, function_registry: &Registry) -> Expression {
+    let (signature, function) = function_registry
+        .resolve_function(&FunctionSignature {
+            name,
+            args: args.iter().map(type_for_expression).collect(),
+            ret: DataType::Null,
+        })
+        .unwrap();
+    Expression::CompiledFunctionCall(CompiledFunctionCall {
+        function: function.as_scalar(),
+        args: Box::from(args),
+        expr_buffer: Box::from(vec![]),
+        signature: Box::new(signature),
+    })
+}
+
+fn is_true(expr: &Expression) -> bool {
+    matches!(expr, Expression::Constant(value, _) if value.as_maybe_boolean() == Some(true))
+}
+
+fn is_false(expr: &Expression) -> bool {
+    matches!(expr, Expression::Constant(value, _) if value.as_maybe_boolean() == Some(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Planner, PlannerError};
+    use ast::expr::Expression;
+    use ast::rel::logical::LogicalOperator;
+    use data::{DataType, Session, SortOrder};
+
+    #[test]
+    fn test_simplify_expressions() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+        planner.catalog.write().unwrap().create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Boolean),
+            ],
+            &[None, None],
+            &[],
+            &[("a".to_string(), SortOrder::Asc)],
+        )?;
+
+        let plan = |sql: &str| -> Result<LogicalOperator, PlannerError> {
+            let query = match parser::parse(sql).unwrap() {
+                ast::statement::Statement::Query(query) => query,
+                _ => panic!(),
+            };
+            Ok(planner.plan_common(query, &session)?.1)
+        };
+        let expressions = |sql: &str| -> Result<Vec<Expression>, PlannerError> {
+            let mut expressions = vec![];
+            for expr in plan(sql)?.expressions_mut() {
+                expressions.push(expr.clone());
+            }
+            Ok(expressions)
+        };
+        let predicate = |sql: &str| -> Result<Expression, PlannerError> {
+            match plan(sql)? {
+                LogicalOperator::Project(project) => match *project.source {
+                    LogicalOperator::Filter(filter) => Ok(filter.predicate),
+                    operator => panic!("Unexpected operator {:?}", operator),
+                },
+                operator => panic!("Unexpected operator {:?}", operator),
+            }
+        };
+
+        assert_eq!(
+            expressions(
+                "SELECT b AND TRUE, FALSE OR b, NOT NOT b, IF(TRUE, a, 2), IF(FALSE, 1, a), \
+                 b IS NULL IS NULL FROM t"
+            )?,
+            expressions("SELECT b, b, b, a, a, FALSE FROM t")?
+        );
+        // The columns could be null so these need to be left as they are
+        assert!(expressions("SELECT a = a, a IS NULL FROM t")?
+            .iter()
+            .all(|expr| matches!(expr, Expression::CompiledFunctionCall(_))));
+        // Count never returns null
+        assert_eq!(
+            expressions("SELECT c IS NULL, c = c FROM (SELECT count(*) AS c FROM t) s")?,
+            expressions("SELECT FALSE, TRUE FROM (SELECT count(*) AS c FROM t) s")?
+        );
+
+        assert_eq!(
+            predicate("SELECT a FROM t WHERE a = a AND b")?,
+            predicate("SELECT a FROM t WHERE a IS NOT NULL AND b")?
+        );
+        assert_eq!(
+            predicate("SELECT a FROM t WHERE b AND FALSE")?,
+            Expression::from(false)
+        );
+        Ok(())
+    }
+}
@@ -22,6 +22,8 @@ pub(crate) fn normalize_joins(
         // can evaluate join expressions and one after
         // the join to remove the expressions we've inserted
 
+        let outputs_right = join.join_type.outputs_right();
+
         // Grap the current condition out, and the left and right
         let mut condition = Expression::from(true);
         std::mem::swap(&mut condition, &mut join.on);
@@ -130,9 +132,12 @@ pub(crate) fn normalize_joins(
             wrapping_project.expressions.remove(0);
         }
 
-        // we now want to remove equi_join count columns at the start of the right side
-        for _ in 0..equi_len {
-            wrapping_project.expressions.remove(left_len);
+        // we now want to remove equi_join count columns at the start of the right side (semi
+        // and anti joins don't output the right side at all)
+        if outputs_right {
+            for _ in 0..equi_len {
+                wrapping_project.expressions.remove(left_len);
+            }
         }
 
         *query = LogicalOperator::Project(wrapping_project)
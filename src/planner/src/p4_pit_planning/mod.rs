@@ -1,13 +1,24 @@
-use crate::utils::expr::{combine_predicates, decompose_predicate, move_column_references};
+use crate::utils::expr::{
+    combine_predicates, decompose_predicate, move_column_references, type_for_expression,
+};
 use crate::utils::logical::fields_for_operator;
 use crate::{Field, Planner, PlannerError};
 use ast::expr::*;
 use ast::rel::logical::*;
 use ast::rel::point_in_time;
-use ast::rel::point_in_time::{Group, PointInTimeOperator};
-use data::{LogicalTimestamp, Session};
+use ast::rel::point_in_time::{AggregateMode, Group, KeyRange, PointInTimeOperator};
+use data::fulltext::tokenize;
+use data::{DataType, Datum, LogicalTimestamp, Session, SortOrder};
 use functions::registry::Registry;
+use functions::{FunctionSignature, FunctionType};
+use parallelize::{parallel_workers, parallelize};
+use prune_columns::prune_columns;
+use std::path::Path;
 
+mod parallelize;
+mod prune_columns;
+
+#[derive(Debug, Clone)]
 pub struct PointInTimePlan {
     pub fields: Vec<Field>,
     pub operator: PointInTimeOperator,
@@ -21,12 +32,310 @@ impl Planner {
         query: LogicalOperator,
         session: &Session,
     ) -> Result<PointInTimePlan, PlannerError> {
-        let (fields, operator) = self.plan_common(query, session)?;
-        let operator = build_operator(operator, &self.function_registry);
+        let query = self.validate(query, session)?;
+        self.plan_validated_for_point_in_time(query, session)
+    }
+
+    /// Like plan_for_point_in_time but for a query that's already been through validation.
+    pub(crate) fn plan_validated_for_point_in_time(
+        &self,
+        query: LogicalOperator,
+        session: &Session,
+    ) -> Result<PointInTimePlan, PlannerError> {
+        let (fields, operator) = self.plan_validated(query, session)?;
+        let mut operator = build_point_in_time(operator, &self.function_registry);
+        if let Some(workers) = parallel_workers(session) {
+            parallelize(&mut operator, workers);
+        }
         Ok(PointInTimePlan { fields, operator })
     }
 }
 
+/// Builds the point in time operator for an already optimized logical operator, all but the
+/// parallelizing. Also used by explain to show how the tables end up being read.
+pub(crate) fn build_point_in_time(
+    query: LogicalOperator,
+    function_registry: &Registry,
+) -> PointInTimeOperator {
+    let mut operator = build_operator(query, function_registry);
+    prune_columns(&mut operator, None);
+    operator
+}
+
+/// Returns the table being read from if the source is a (possibly aliased) table.
+fn resolved_table(source: &LogicalOperator) -> Option<&ResolvedTable> {
+    match source {
+        LogicalOperator::ResolvedTable(table) => Some(table),
+        LogicalOperator::TableAlias(TableAlias { source, .. }) => {
+            if let LogicalOperator::ResolvedTable(table) = source.as_ref() {
+                Some(table)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The timestamp to read the table at, the latest data unless it's a time travel query.
+/// Having a timestamp in the future gives us read after write within the same ms
+/// Rockdb already gives us atomic writes so I can't think of any downsides with this
+fn scan_timestamp(table: &ResolvedTable) -> LogicalTimestamp {
+    table.as_of.unwrap_or(LogicalTimestamp::MAX)
+}
+
+/// Returns a scan of a full-text index if the source is a table and one of the predicate's
+/// conjuncts is a MATCH against one of its indexed columns.
+fn fulltext_scan(predicate: &Expression, source: &LogicalOperator) -> Option<PointInTimeOperator> {
+    let table = resolved_table(source)?;
+    if table.fulltext_indexes.is_empty() {
+        return None;
+    }
+
+    decompose_predicate(predicate.clone()).find_map(|expr| {
+        if let Expression::CompiledFunctionCall(function) = expr {
+            if function.signature.name == "match" {
+                if let (
+                    Expression::CompiledColumnReference(column),
+                    Expression::Constant(search, _),
+                ) = (&function.args[0], &function.args[1])
+                {
+                    let index = table
+                        .fulltext_indexes
+                        .iter()
+                        .find(|index| index.column == column.offset)?;
+                    return Some(PointInTimeOperator::FulltextScan(
+                        point_in_time::FulltextScan {
+                            index: index.clone(),
+                            terms: search.as_maybe_text().map_or(vec![], tokenize),
+                            timestamp: scan_timestamp(table),
+                        },
+                    ));
+                }
+            }
+        }
+        None
+    })
+}
+
+/// Returns a range scan of a secondary index if the source is a table and the predicate pins
+/// down the leading columns of one of its indexes. The index rows end with the table's row so
+/// it's just a project to turn them back into the table's rows.
+fn index_scan(predicate: &Expression, source: &LogicalOperator) -> Option<PointInTimeOperator> {
+    let table = resolved_table(source)?;
+    table.secondary_indexes.iter().find_map(|index| {
+        let key_len = index.columns.len();
+        let ranges = key_ranges(predicate, &index.columns, &index.table.pk()[..key_len])?;
+        let expressions = table
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, datatype))| {
+                Expression::CompiledColumnReference(CompiledColumnReference {
+                    offset: key_len + idx,
+                    datatype: *datatype,
+                })
+            })
+            .collect();
+        Some(PointInTimeOperator::Project(point_in_time::Project {
+            expressions,
+            source: Box::new(PointInTimeOperator::TableScan(point_in_time::TableScan {
+                table: index.table.clone(),
+                timestamp: scan_timestamp(table),
+                ranges: Some(ranges),
+                column_mask: None,
+            })),
+        }))
+    })
+}
+
+/// The bounds the predicate puts on a single column.
+#[derive(Debug, Default, Clone)]
+struct ColumnBounds {
+    eq: Option<Datum<'static>>,
+    lower: Option<Datum<'static>>,
+    upper: Option<Datum<'static>>,
+}
+
+/// Works out the key ranges a table scan can be narrowed down to when the predicate pins down
+/// the leading columns of the table's key, ie a = 1, a > 1 AND a < 5 or the expanded forms of
+/// (a, b) IN ((1, 2), (3, 4)) and (a, b) >= (1, 2).
+/// key_columns are the offsets of the columns making up the key (in key order) and pk their
+/// sort orders.
+/// The ranges may include rows that don't match so the filter is still needed on top.
+fn key_ranges(
+    predicate: &Expression,
+    key_columns: &[usize],
+    pk: &[SortOrder],
+) -> Option<Vec<KeyRange>> {
+    let conjuncts: Vec<_> = decompose_predicate(predicate.clone()).collect();
+    let mut bounds = vec![ColumnBounds::default(); pk.len()];
+    for conjunct in &conjuncts {
+        add_bound(conjunct, key_columns, &mut bounds);
+    }
+
+    // IN lists end up as OR's, if every branch pins down more of the key we can do point lookups
+    let prefix_len = leading_equalities(&bounds).len();
+    for conjunct in &conjuncts {
+        if let Some(points) = key_points(conjunct, key_columns, &bounds, prefix_len) {
+            return Some(
+                points
+                    .into_iter()
+                    .map(|point| KeyRange {
+                        from: Some(point.clone()),
+                        to: Some(point),
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    key_range(&bounds, pk).map(|range| vec![range])
+}
+
+/// Returns the distinct key prefixes for each branch of an OR, only if they're all the same length
+/// and longer than min_len.
+fn key_points(
+    expression: &Expression,
+    key_columns: &[usize],
+    bounds: &[ColumnBounds],
+    min_len: usize,
+) -> Option<Vec<Vec<Datum<'static>>>> {
+    let mut branches = vec![];
+    disjuncts(expression, &mut branches);
+    if branches.len() < 2 {
+        return None;
+    }
+
+    let mut points: Vec<Vec<Datum<'static>>> = vec![];
+    for branch in branches {
+        let mut branch_bounds = bounds.to_vec();
+        for conjunct in decompose_predicate(branch.clone()) {
+            add_bound(&conjunct, key_columns, &mut branch_bounds);
+        }
+        let point = leading_equalities(&branch_bounds);
+        if point.len() <= min_len || points.first().map_or(false, |p| p.len() != point.len()) {
+            return None;
+        }
+        if !points.contains(&point) {
+            points.push(point);
+        }
+    }
+    Some(points)
+}
+
+/// Builds a single range from the leading equalities followed by any bounds on the next column.
+fn key_range(bounds: &[ColumnBounds], pk: &[SortOrder]) -> Option<KeyRange> {
+    let prefix = leading_equalities(bounds);
+    let (lower, upper) = bounds
+        .get(prefix.len())
+        .map_or((None, None), |b| (b.lower.clone(), b.upper.clone()));
+    if prefix.is_empty() && lower.is_none() && upper.is_none() {
+        return None;
+    }
+
+    // Descending columns are stored largest first
+    let (first, last) = if pk.get(prefix.len()) == Some(&SortOrder::Desc) {
+        (upper, lower)
+    } else {
+        (lower, upper)
+    };
+    let bound = |datum: Option<Datum<'static>>| match datum {
+        Some(datum) => {
+            let mut key = prefix.clone();
+            key.push(datum);
+            Some(key)
+        }
+        None if !prefix.is_empty() => Some(prefix.clone()),
+        None => None,
+    };
+    Some(KeyRange {
+        from: bound(first),
+        to: bound(last),
+    })
+}
+
+fn leading_equalities(bounds: &[ColumnBounds]) -> Vec<Datum<'static>> {
+    bounds
+        .iter()
+        .take_while(|bound| bound.eq.is_some())
+        .filter_map(|bound| bound.eq.clone())
+        .collect()
+}
+
+/// Records the bound if the expression compares a key column against a constant.
+fn add_bound(expression: &Expression, key_columns: &[usize], bounds: &mut [ColumnBounds]) {
+    if let Expression::CompiledFunctionCall(function) = expression {
+        let (column, datum, datatype, op) = match (&function.args[..], function.signature.name) {
+            (
+                [Expression::CompiledColumnReference(column), Expression::Constant(datum, datatype)],
+                op,
+            ) => (column, datum, datatype, op),
+            (
+                [Expression::Constant(datum, datatype), Expression::CompiledColumnReference(column)],
+                op,
+            ) => {
+                let flipped = match op {
+                    "<" => ">",
+                    "<=" => ">=",
+                    ">" => "<",
+                    ">=" => "<=",
+                    op => op,
+                };
+                (column, datum, datatype, flipped)
+            }
+            _ => return,
+        };
+        // The key encoding depends on the datatype so they have to match exactly
+        if *datatype != column.datatype || datum.is_null() {
+            return;
+        }
+        let key_idx = key_columns
+            .iter()
+            .position(|offset| *offset == column.offset);
+        if let Some(bound) = key_idx.and_then(|idx| bounds.get_mut(idx)) {
+            // Text is compared using its collation so we can't tell which bound is tighter
+            let tighten = *datatype != DataType::Text;
+            match op {
+                "=" => bound.eq = Some(datum.clone()),
+                ">" | ">=" => match &bound.lower {
+                    Some(lower) if tighten && lower >= datum => {}
+                    _ => bound.lower = Some(datum.clone()),
+                },
+                "<" | "<=" => match &bound.upper {
+                    Some(upper) if tighten && upper <= datum => {}
+                    _ => bound.upper = Some(datum.clone()),
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+fn disjuncts<'a>(expression: &'a Expression, branches: &mut Vec<&'a Expression>) {
+    match expression {
+        Expression::CompiledFunctionCall(function) if function.signature.name == "or" => {
+            for arg in function.args.iter() {
+                disjuncts(arg, branches);
+            }
+        }
+        expression => branches.push(expression),
+    }
+}
+
+/// Limits the table scans (including those of each partition) to the key ranges.
+fn restrict_scans(operator: &mut PointInTimeOperator, ranges: &[KeyRange]) {
+    match operator {
+        PointInTimeOperator::TableScan(table_scan) => table_scan.ranges = Some(ranges.to_vec()),
+        PointInTimeOperator::UnionAll(union_all) => {
+            for source in union_all.sources.iter_mut() {
+                restrict_scans(source, ranges);
+            }
+        }
+        _ => {}
+    }
+}
+
 fn build_operator(query: LogicalOperator, function_registry: &Registry) -> PointInTimeOperator {
     match query {
         LogicalOperator::Single => PointInTimeOperator::Single,
@@ -44,18 +353,27 @@ fn build_operator(query: LogicalOperator, function_registry: &Registry) -> Point
         LogicalOperator::GroupBy(GroupBy {
             expressions,
             key_expressions,
+            distinct_on,
             source,
         }) => {
+            assert!(
+                !distinct_on,
+                "Distinct on should not be true at this point!"
+            );
             if key_expressions.is_empty() {
-                PointInTimeOperator::SortedGroup(Group {
-                    source: Box::new(build_operator(*source, function_registry)),
-                    expressions: expressions.into_iter().map(|ne| ne.expression).collect(),
-                    key_len: 0,
-                })
+                let source = build_operator(*source, function_registry);
+                let expressions = expressions.into_iter().map(|ne| ne.expression).collect();
+                group_operator(
+                    PointInTimeOperator::SortedGroup,
+                    source,
+                    expressions,
+                    vec![],
+                )
             } else {
                 // The key expr's have to be in the group by source.
                 // We'll create a new project to do this.
                 let key_len = key_expressions.len();
+                let key_types: Vec<_> = key_expressions.iter().map(type_for_expression).collect();
                 let mut project_exprs = key_expressions;
                 for (idx, field) in fields_for_operator(&source).enumerate() {
                     project_exprs.push(Expression::CompiledColumnReference(
@@ -66,9 +384,27 @@ fn build_operator(query: LogicalOperator, function_registry: &Registry) -> Point
                     ));
                 }
 
-                let project = point_in_time::Project {
-                    expressions: project_exprs,
-                    source: Box::new(build_operator(*source, function_registry)),
+                // For unions the project gets pushed into each branch so we can do partial
+                // aggregations below the union.
+                let source = match build_operator(*source, function_registry) {
+                    PointInTimeOperator::UnionAll(union_all) if union_all.sources.len() > 1 => {
+                        PointInTimeOperator::UnionAll(point_in_time::UnionAll {
+                            sources: union_all
+                                .sources
+                                .into_iter()
+                                .map(|source| {
+                                    PointInTimeOperator::Project(point_in_time::Project {
+                                        expressions: project_exprs.clone(),
+                                        source: Box::new(source),
+                                    })
+                                })
+                                .collect(),
+                        })
+                    }
+                    source => PointInTimeOperator::Project(point_in_time::Project {
+                        expressions: project_exprs,
+                        source: Box::new(source),
+                    }),
                 };
 
                 let group_exprs = expressions
@@ -79,33 +415,61 @@ fn build_operator(query: LogicalOperator, function_registry: &Registry) -> Point
                     })
                     .collect();
 
-                PointInTimeOperator::HashGroup(Group {
-                    source: Box::new(PointInTimeOperator::Project(project)),
-                    expressions: group_exprs,
-                    key_len,
-                })
+                group_operator(
+                    PointInTimeOperator::HashGroup,
+                    source,
+                    group_exprs,
+                    key_types,
+                )
             }
         }
         LogicalOperator::Filter(Filter { predicate, source }) => {
+            // The index/key ranges only narrow down the rows scanned, the filter is still needed
+            // on top.
+            let ranges = resolved_table(&source).and_then(|table| {
+                let key_columns: Vec<_> = (0..table.columns.len()).collect();
+                key_ranges(&predicate, &key_columns, table.table.pk())
+            });
+            // Only bother with secondary indexes when the table's own key doesn't help
+            let mut source = fulltext_scan(&predicate, &source)
+                .or_else(|| {
+                    if ranges.is_none() {
+                        index_scan(&predicate, &source)
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or_else(|| build_operator(*source, function_registry));
+            if let Some(ranges) = ranges {
+                restrict_scans(&mut source, &ranges);
+            }
             PointInTimeOperator::Filter(point_in_time::Filter {
                 predicate,
-                source: Box::new(build_operator(*source, function_registry)),
+                source: Box::new(source),
             })
         }
         LogicalOperator::Limit(Limit {
             offset,
             limit,
             source,
-        }) => PointInTimeOperator::Limit(point_in_time::Limit {
-            offset,
-            limit,
-            source: Box::new(build_operator(*source, function_registry)),
-        }),
+        }) => {
+            let mut source = build_operator(*source, function_registry);
+            // The sort below us only has to hang onto the rows that make it through the limit.
+            if let Some(sort) = sort_below_projects(&mut source) {
+                sort.limit = Some(offset.saturating_add(limit));
+            }
+            PointInTimeOperator::Limit(point_in_time::Limit {
+                offset,
+                limit,
+                source: Box::new(source),
+            })
+        }
         LogicalOperator::Sort(Sort {
             sort_expressions,
             source,
         }) => PointInTimeOperator::Sort(point_in_time::Sort {
             sort_expressions,
+            limit: None,
             source: Box::new(build_operator(*source, function_registry)),
         }),
         LogicalOperator::Values(values) => {
@@ -132,26 +496,108 @@ fn build_operator(query: LogicalOperator, function_registry: &Registry) -> Point
                     .collect(),
             })
         }
-        LogicalOperator::ResolvedTable(ResolvedTable { columns: _, table }) => {
-            PointInTimeOperator::TableScan(point_in_time::TableScan {
+        LogicalOperator::ResolvedTable(resolved_table) => {
+            let timestamp = scan_timestamp(&resolved_table);
+            let ResolvedTable {
                 table,
-                // Having a timestamp in the future gives us read after write within the same ms
-                // Rockdb already gives us atomic writes so I can't think of any downsides with this
-                timestamp: LogicalTimestamp::MAX,
-            })
+                partitioning,
+                columns,
+                ..
+            } = *resolved_table;
+            let table_scan = |table| {
+                PointInTimeOperator::TableScan(point_in_time::TableScan {
+                    table,
+                    timestamp,
+                    ranges: None,
+                    column_mask: None,
+                })
+            };
+
+            if let Some(partitioning) = partitioning {
+                // Partitioned tables are just the union of their (unpruned) partitions
+                let mut sources: Vec<_> = partitioning
+                    .partitions
+                    .into_iter()
+                    .filter(|partition| !partition.pruned)
+                    .map(|partition| table_scan(partition.table))
+                    .collect();
+                match sources.len() {
+                    0 => PointInTimeOperator::Values(point_in_time::Values {
+                        data: vec![],
+                        column_count: columns.len(),
+                    }),
+                    1 => sources.pop().unwrap(),
+                    _ => PointInTimeOperator::UnionAll(point_in_time::UnionAll { sources }),
+                }
+            } else {
+                table_scan(table)
+            }
         }
-        LogicalOperator::TableInsert(TableInsert { table, source }) => {
-            let actual_table =
-                if let LogicalOperator::ResolvedTable(ResolvedTable { columns: _, table }) = *table
-                {
-                    table
+        LogicalOperator::TableInsert(TableInsert {
+            table,
+            source,
+            delete_matching,
+            bulk_load,
+            upsert,
+            ..
+        }) => {
+            let not_null_columns = match table.as_ref() {
+                LogicalOperator::ResolvedTable(resolved_table) => resolved_table
+                    .columns
+                    .iter()
+                    .zip(&resolved_table.column_not_null)
+                    .enumerate()
+                    .filter(|(_, (_, not_null))| **not_null)
+                    .map(|(idx, ((alias, _), _))| (idx, alias.clone()))
+                    .collect(),
+                _ => vec![],
+            };
+            let (actual_table, partitioning, fulltext_indexes, secondary_indexes, upsert) =
+                if let LogicalOperator::ResolvedTable(resolved_table) = *table {
+                    let ResolvedTable {
+                        table,
+                        partitioning,
+                        fulltext_indexes,
+                        secondary_indexes,
+                        columns,
+                        primary_key_len,
+                        ..
+                    } = *resolved_table;
+                    let upsert = upsert.map(|upsert| point_in_time::Upsert {
+                        key_len: primary_key_len,
+                        assignments: upsert
+                            .assignments
+                            .into_iter()
+                            .map(|(column, expression)| {
+                                let offset = columns
+                                    .iter()
+                                    .position(|(alias, _)| alias == &column)
+                                    .unwrap();
+                                (offset, expression)
+                            })
+                            .collect(),
+                    });
+                    (
+                        table,
+                        partitioning,
+                        fulltext_indexes,
+                        secondary_indexes,
+                        upsert,
+                    )
                 } else {
                     panic!("Can not insert into anything other than a resolved table")
                 };
 
             PointInTimeOperator::TableInsert(point_in_time::TableInsert {
                 table: actual_table,
+                partitioning,
+                fulltext_indexes,
+                secondary_indexes,
                 source: Box::new(build_operator(*source, function_registry)),
+                delete_matching,
+                bulk_load,
+                upsert,
+                not_null_columns,
             })
         }
         LogicalOperator::NegateFreq(source) => {
@@ -160,11 +606,62 @@ fn build_operator(query: LogicalOperator, function_registry: &Registry) -> Point
         LogicalOperator::TableAlias(table_alias) => {
             build_operator(*table_alias.source, function_registry)
         }
-        LogicalOperator::FileScan(file_scan) => {
-            PointInTimeOperator::FileScan(point_in_time::FileScan {
-                directory: file_scan.directory,
-                serde_options: file_scan.serde_options,
-            })
+        LogicalOperator::FileScan(FileScan {
+            directory,
+            serde_options,
+            columns,
+            partition_columns,
+            partitions,
+        }) => {
+            let first_partition_column = columns.len() - partition_columns;
+            let file_columns: Vec<_> = columns[..first_partition_column]
+                .iter()
+                .enumerate()
+                .map(|(offset, (_alias, datatype))| {
+                    parse_text_column(offset, *datatype, function_registry)
+                })
+                .collect();
+            if partition_columns == 0 {
+                return PointInTimeOperator::FileScan(point_in_time::FileScan {
+                    directory,
+                    serde_options,
+                    columns: file_columns,
+                });
+            }
+
+            // Partitioned scans are the union of a scan per (unpruned) partition directory, with
+            // the partition values tacked on the end as constants
+            let partition_scan = |partition: FilePartition| {
+                let mut scan_columns = file_columns.clone();
+                for (value, column) in partition
+                    .values
+                    .into_iter()
+                    .zip(&columns[first_partition_column..])
+                {
+                    scan_columns.push(Expression::Constant(value, column.1));
+                }
+                PointInTimeOperator::FileScan(point_in_time::FileScan {
+                    directory: Path::new(&directory)
+                        .join(&partition.name)
+                        .to_string_lossy()
+                        .to_string(),
+                    serde_options: serde_options.clone(),
+                    columns: scan_columns,
+                })
+            };
+            let mut sources: Vec<_> = partitions
+                .into_iter()
+                .filter(|partition| !partition.pruned)
+                .map(partition_scan)
+                .collect();
+            match sources.len() {
+                0 => PointInTimeOperator::Values(point_in_time::Values {
+                    data: vec![],
+                    column_count: columns.len(),
+                }),
+                1 => sources.pop().unwrap(),
+                _ => PointInTimeOperator::UnionAll(point_in_time::UnionAll { sources }),
+            }
         }
         LogicalOperator::Join(join) => {
             let mut non_equi = vec![];
@@ -193,10 +690,192 @@ fn build_operator(query: LogicalOperator, function_registry: &Registry) -> Point
                 join_type: join.join_type,
             })
         }
+        LogicalOperator::JsonTable(JsonTable {
+            document,
+            path,
+            columns,
+            alias: _,
+            source,
+        }) => PointInTimeOperator::JsonTable(point_in_time::JsonTable {
+            document,
+            path,
+            columns: columns
+                .into_iter()
+                .map(|column| json_table_column(column, function_registry))
+                .collect(),
+            source: Box::new(build_operator(*source, function_registry)),
+        }),
+        LogicalOperator::SetOperation(_) => {
+            panic!("Set operations should have been rewritten by now!")
+        }
         LogicalOperator::TableReference(_) => panic!(),
     }
 }
 
+/// Builds a group by operator, when the source is a union all the group by is split into a
+/// partial aggregation below each branch of the union and a final aggregation above it.
+/// This collapses duplicate keys early so far fewer rows make their way into the final group by.
+/// The source is expected to be prefixed by the grouping keys.
+fn group_operator(
+    group: fn(Group) -> PointInTimeOperator,
+    source: PointInTimeOperator,
+    expressions: Vec<Expression>,
+    key_types: Vec<DataType>,
+) -> PointInTimeOperator {
+    let key_len = key_types.len();
+    match source {
+        PointInTimeOperator::UnionAll(union_all) if union_all.sources.len() > 1 => {
+            // The final group by needs the keys to group on, so the partial group bys carry them
+            // through in their state ahead of the states for the actual expressions.
+            let mut partial_exprs: Vec<_> = key_types
+                .into_iter()
+                .enumerate()
+                .map(|(offset, datatype)| {
+                    Expression::CompiledColumnReference(CompiledColumnReference {
+                        offset,
+                        datatype,
+                    })
+                })
+                .collect();
+            partial_exprs.extend(expressions.iter().cloned());
+
+            let sources = union_all
+                .sources
+                .into_iter()
+                .map(|source| {
+                    group(Group {
+                        source: Box::new(source),
+                        expressions: partial_exprs.clone(),
+                        key_len,
+                        mode: AggregateMode::Partial,
+                    })
+                })
+                .collect();
+
+            group(Group {
+                source: Box::new(PointInTimeOperator::UnionAll(point_in_time::UnionAll {
+                    sources,
+                })),
+                expressions,
+                key_len,
+                mode: AggregateMode::Final,
+            })
+        }
+        source => group(Group {
+            source: Box::new(source),
+            expressions,
+            key_len,
+            mode: AggregateMode::Complete,
+        }),
+    }
+}
+
+/// The expression to parse the text of a field read from a file into its column's type.
+fn parse_text_column(
+    offset: usize,
+    datatype: DataType,
+    function_registry: &Registry,
+) -> Expression {
+    let text = Expression::CompiledColumnReference(CompiledColumnReference {
+        offset,
+        datatype: DataType::Text,
+    });
+    if datatype == DataType::Text {
+        return text;
+    }
+    let lookup_sig = FunctionSignature {
+        name: datatype.cast_function(),
+        args: vec![DataType::Text],
+        ret: datatype,
+    };
+    match function_registry.resolve_function(&lookup_sig) {
+        Ok((signature, FunctionType::Scalar(function))) => {
+            Expression::CompiledFunctionCall(CompiledFunctionCall {
+                function,
+                args: Box::from(vec![text]),
+                expr_buffer: Box::from(vec![]),
+                signature: Box::new(signature),
+            })
+        }
+        _ => panic!("No cast from text to {}", datatype),
+    }
+}
+
+/// Builds the expression reading a json table column out of a match, these are evaluated against
+/// a tuple of the match followed by its ordinality.
+/// Like file scans everything other than json columns is parsed from the (unquoted) text.
+fn json_table_column(column: JsonTableColumn, function_registry: &Registry) -> Expression {
+    let path = if let Some(path) = column.path {
+        path
+    } else {
+        return Expression::CompiledColumnReference(CompiledColumnReference {
+            offset: 1,
+            datatype: DataType::BigInt,
+        });
+    };
+    let args = vec![
+        Expression::CompiledColumnReference(CompiledColumnReference {
+            offset: 0,
+            datatype: DataType::Json,
+        }),
+        Expression::Constant(Datum::Jsonpath(Box::new(path)), DataType::JsonPath),
+    ];
+    let extract = scalar_function("json_extract", args, DataType::Json, function_registry);
+    // ->> is a compound function so we spell out the json_unquote(json_extract(..)) ourselves
+    let unquoted = |extract| {
+        scalar_function(
+            "json_unquote",
+            vec![extract],
+            DataType::Text,
+            function_registry,
+        )
+    };
+    match column.datatype {
+        DataType::Json => extract,
+        DataType::Text => unquoted(extract),
+        datatype => scalar_function(
+            datatype.cast_function(),
+            vec![unquoted(extract)],
+            datatype,
+            function_registry,
+        ),
+    }
+}
+
+fn scalar_function(
+    name: &str,
+    args: Vec<Expression>,
+    ret: DataType,
+    function_registry: &Registry,
+) -> Expression {
+    let lookup_sig = FunctionSignature {
+        name,
+        args: args.iter().map(type_for_expression).collect(),
+        ret,
+    };
+    match function_registry.resolve_function(&lookup_sig) {
+        Ok((signature, FunctionType::Scalar(function))) => {
+            Expression::CompiledFunctionCall(CompiledFunctionCall {
+                function,
+                args: Box::from(args),
+                expr_buffer: Box::from(vec![]),
+                signature: Box::new(signature),
+            })
+        }
+        _ => panic!("No scalar function {} for {:?}", name, lookup_sig.args),
+    }
+}
+
+/// Finds the sort feeding the operator, looking through any projects in between (ie from
+/// selecting out of an ordered subquery) as they don't change the number or order of the rows.
+fn sort_below_projects(operator: &mut PointInTimeOperator) -> Option<&mut point_in_time::Sort> {
+    match operator {
+        PointInTimeOperator::Sort(sort) => Some(sort),
+        PointInTimeOperator::Project(project) => sort_below_projects(&mut project.source),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -204,6 +883,16 @@ mod tests {
     use ast::expr::{Expression, NamedExpression};
     use data::{DataType, Datum};
 
+    /// The key ranges only work for text with a binary collation.
+    fn binary_session() -> Session {
+        let session = Session::new(1);
+        session.variables.write().unwrap().insert(
+            "collation_connection".to_string(),
+            Datum::from("utf8mb4_bin"),
+        );
+        session
+    }
+
     #[test]
     fn test_plan_for_point_in_time() -> Result<(), PlannerError> {
         let planner = Planner::new_for_test();
@@ -230,4 +919,174 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_key_range_scans() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = binary_session();
+        planner.catalog.write().unwrap().create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Text),
+            ],
+            &[None, None],
+            &[],
+            &[("a".to_string(), SortOrder::Desc)],
+        )?;
+
+        let ranges = |sql: &str| -> Result<Option<Vec<KeyRange>>, PlannerError> {
+            let query = match parser::parse(sql).unwrap() {
+                ast::statement::Statement::Query(query) => query,
+                _ => panic!(),
+            };
+            let mut operator = planner.plan_for_point_in_time(query, &session)?.operator;
+            loop {
+                match operator {
+                    PointInTimeOperator::TableScan(table_scan) => return Ok(table_scan.ranges),
+                    PointInTimeOperator::Filter(filter) => operator = *filter.source,
+                    PointInTimeOperator::Project(project) => operator = *project.source,
+                    operator => panic!("Unexpected operator {:?}", operator),
+                }
+            }
+        };
+        let point = |a: i32, b: &'static str| KeyRange {
+            from: Some(vec![Datum::from(a), Datum::from(b)]),
+            to: Some(vec![Datum::from(a), Datum::from(b)]),
+        };
+
+        assert_eq!(
+            ranges(r#"SELECT * FROM t WHERE (a, b) IN ((1, "x"), (2, "y"), (1, "x"))"#)?,
+            Some(vec![point(1, "x"), point(2, "y")])
+        );
+        // a is descending so the range runs from the top down to 1
+        assert_eq!(
+            ranges(r#"SELECT * FROM t WHERE (a, b) >= (1, "x")"#)?,
+            Some(vec![KeyRange {
+                from: None,
+                to: Some(vec![Datum::from(1)]),
+            }])
+        );
+        assert_eq!(
+            ranges(r#"SELECT * FROM t WHERE a = 1 AND b < "x""#)?,
+            Some(vec![KeyRange {
+                from: Some(vec![Datum::from(1)]),
+                to: Some(vec![Datum::from(1), Datum::from("x")]),
+            }])
+        );
+        // Only the tightest bounds are kept, a > 1 loses out to a >= 5 and a < 20 to a <= 10.
+        // As a is descending the range runs from the upper bound down to the lower one.
+        assert_eq!(
+            ranges(r#"SELECT * FROM t WHERE a > 1 AND a >= 5 AND a < 20 AND a <= 10"#)?,
+            Some(vec![KeyRange {
+                from: Some(vec![Datum::from(10)]),
+                to: Some(vec![Datum::from(5)]),
+            }])
+        );
+        assert_eq!(ranges(r#"SELECT * FROM t WHERE b = "x""#)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit_fused_into_sort() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+        planner.catalog.write().unwrap().create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Text),
+            ],
+            &[None, None],
+            &[],
+            &[("a".to_string(), SortOrder::Asc)],
+        )?;
+
+        let sort_limit = |sql: &str| -> Result<Option<i64>, PlannerError> {
+            let query = match parser::parse(sql).unwrap() {
+                ast::statement::Statement::Query(query) => query,
+                _ => panic!(),
+            };
+            let mut operator = planner.plan_for_point_in_time(query, &session)?.operator;
+            loop {
+                match operator {
+                    PointInTimeOperator::Sort(sort) => return Ok(sort.limit),
+                    PointInTimeOperator::Limit(limit) => operator = *limit.source,
+                    PointInTimeOperator::Project(project) => operator = *project.source,
+                    operator => panic!("Unexpected operator {:?}", operator),
+                }
+            }
+        };
+
+        assert_eq!(sort_limit("SELECT b FROM t ORDER BY b LIMIT 3")?, Some(3));
+        assert_eq!(
+            sort_limit("SELECT b FROM (SELECT a, b FROM t ORDER BY b) AS s LIMIT 3 OFFSET 2")?,
+            Some(5)
+        );
+        assert_eq!(sort_limit("SELECT b FROM t ORDER BY b")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_scans() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = binary_session();
+        {
+            let mut catalog = planner.catalog.write().unwrap();
+            catalog.create_table(
+                "default",
+                "t",
+                &[
+                    ("a".to_string(), DataType::Integer),
+                    ("b".to_string(), DataType::Text),
+                ],
+                &[None, None],
+                &[],
+                &[],
+            )?;
+            catalog.create_index("default", "t", "b_idx", &["b".to_string()])?;
+        }
+
+        let scan = |sql: &str| -> Result<point_in_time::TableScan, PlannerError> {
+            let query = match parser::parse(sql).unwrap() {
+                ast::statement::Statement::Query(query) => query,
+                _ => panic!(),
+            };
+            let mut operator = planner.plan_for_point_in_time(query, &session)?.operator;
+            loop {
+                match operator {
+                    PointInTimeOperator::TableScan(table_scan) => return Ok(table_scan),
+                    PointInTimeOperator::Filter(filter) => operator = *filter.source,
+                    PointInTimeOperator::Project(project) => operator = *project.source,
+                    operator => panic!("Unexpected operator {:?}", operator),
+                }
+            }
+        };
+
+        let index_scan = scan(r#"SELECT * FROM t WHERE b = "x""#)?;
+        assert_eq!(index_scan.table.pk().len(), 3);
+        assert_eq!(
+            index_scan.ranges,
+            Some(vec![KeyRange {
+                from: Some(vec![Datum::from("x")]),
+                to: Some(vec![Datum::from("x")]),
+            }])
+        );
+
+        // The table's own key wins when it can be used
+        let table_scan = scan(r#"SELECT * FROM t WHERE a = 1 AND b = "x""#)?;
+        assert_eq!(table_scan.table.pk().len(), 2);
+
+        // Case insensitive comparisons can't make use of the index
+        session.variables.write().unwrap().insert(
+            "collation_connection".to_string(),
+            Datum::from("utf8mb4_general_ci"),
+        );
+        let table_scan = scan(r#"SELECT * FROM t WHERE b = "x""#)?;
+        assert_eq!(table_scan.table.pk().len(), 2);
+        assert_eq!(table_scan.ranges, None);
+        Ok(())
+    }
 }
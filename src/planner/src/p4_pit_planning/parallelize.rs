@@ -0,0 +1,229 @@
+use crate::utils::expr::type_for_expression;
+use ast::expr::{CompiledColumnReference, Expression};
+use ast::rel::point_in_time::{AggregateMode, Gather, Group, PointInTimeOperator};
+use data::{DataType, Datum, Session};
+
+/// The session variable holding the number of threads a query can run across
+pub const PARALLEL_WORKERS_VARIABLE: &str = "parallel_workers";
+
+/// The number of worker threads the session's queries should be run with, None to run them on
+/// the connection's thread as usual.
+pub(super) fn parallel_workers(session: &Session) -> Option<usize> {
+    let workers = match session.variable(PARALLEL_WORKERS_VARIABLE) {
+        Some((Datum::Integer(workers), _)) => workers as i64,
+        Some((Datum::BigInt(workers), _)) => workers,
+        _ => 1,
+    };
+    if workers > 1 {
+        Some(workers as usize)
+    } else {
+        None
+    }
+}
+
+/// Runs the filters and projects over each scan across the workers, group bys over them are
+/// split into a partial group by run by each worker and a final group by merging their states.
+/// The output order of the parallel parts is arbitrary so anything relying on its source being
+/// in order (ie sorted group bys with keys) is left alone.
+/// ie
+/// HashGroup -> Project -> Filter -> TableScan
+/// becomes
+/// HashGroup(final) -> Gather -> HashGroup(partial) -> Project -> Filter -> TableScan
+pub(super) fn parallelize(operator: &mut PointInTimeOperator, workers: usize) {
+    let parallel_group = match operator {
+        PointInTimeOperator::HashGroup(group) if group.mode == AggregateMode::Complete => {
+            parallel_group(group, PointInTimeOperator::HashGroup, workers)
+        }
+        PointInTimeOperator::SortedGroup(group)
+            if group.mode == AggregateMode::Complete && group.key_len == 0 =>
+        {
+            parallel_group(group, PointInTimeOperator::SortedGroup, workers)
+        }
+        PointInTimeOperator::SortedGroup(group) if group.key_len > 0 => {
+            // We need our source to stay in order, but anything below it is fair game.
+            if !is_pipeline(&group.source) {
+                parallelize(&mut group.source, workers);
+            }
+            return;
+        }
+        // Inserts are left on the connection's thread
+        PointInTimeOperator::TableInsert(_) => return,
+        _ => None,
+    };
+
+    if let Some(parallel_group) = parallel_group {
+        *operator = parallel_group;
+    } else if is_pipeline(operator) {
+        let source = std::mem::take(operator);
+        *operator = PointInTimeOperator::Gather(Gather {
+            source: Box::new(source),
+            workers,
+        });
+    } else {
+        for child in children_mut(operator) {
+            parallelize(child, workers);
+        }
+    }
+}
+
+/// Filters and projects all the way down to a table or file scan.
+fn is_pipeline(operator: &PointInTimeOperator) -> bool {
+    let is_pipeline_source = |source: &PointInTimeOperator| match source {
+        PointInTimeOperator::TableScan(_) | PointInTimeOperator::FileScan(_) => true,
+        source => is_pipeline(source),
+    };
+    match operator {
+        PointInTimeOperator::Project(project) => is_pipeline_source(&project.source),
+        PointInTimeOperator::Filter(filter) => is_pipeline_source(&filter.source),
+        _ => false,
+    }
+}
+
+/// The types of the grouping keys if the group by is over a pipeline (or scan) that can be run
+/// in parallel.
+fn parallel_key_types(group: &Group) -> Option<Vec<DataType>> {
+    let source = group.source.as_ref();
+    if !is_pipeline(source) && !matches!(source, PointInTimeOperator::FileScan(_)) {
+        return None;
+    }
+    let types = output_types(source)?;
+    Some(types.into_iter().take(group.key_len).collect())
+}
+
+fn output_types(operator: &PointInTimeOperator) -> Option<Vec<DataType>> {
+    match operator {
+        PointInTimeOperator::Project(project) => Some(
+            project
+                .expressions
+                .iter()
+                .map(type_for_expression)
+                .collect(),
+        ),
+        PointInTimeOperator::Filter(filter) => output_types(&filter.source),
+        PointInTimeOperator::FileScan(file_scan) => {
+            Some(file_scan.columns.iter().map(type_for_expression).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Splits the group by into a final group by over the partial group bys run by each worker,
+/// the partial group bys carry the keys through in their state for the final group by to group
+/// on. Returns None if the group by's source can't be run in parallel.
+fn parallel_group(
+    group: &mut Group,
+    constructor: fn(Group) -> PointInTimeOperator,
+    workers: usize,
+) -> Option<PointInTimeOperator> {
+    let key_types = parallel_key_types(group)?;
+    let mut partial_exprs: Vec<_> = key_types
+        .into_iter()
+        .enumerate()
+        .map(|(offset, datatype)| {
+            Expression::CompiledColumnReference(CompiledColumnReference { offset, datatype })
+        })
+        .collect();
+    partial_exprs.extend(group.expressions.iter().cloned());
+
+    let partial = constructor(Group {
+        source: std::mem::take(&mut group.source),
+        expressions: partial_exprs,
+        key_len: group.key_len,
+        mode: AggregateMode::Partial,
+    });
+    Some(constructor(Group {
+        source: Box::new(PointInTimeOperator::Gather(Gather {
+            source: Box::new(partial),
+            workers,
+        })),
+        expressions: std::mem::take(&mut group.expressions),
+        key_len: group.key_len,
+        mode: AggregateMode::Final,
+    }))
+}
+
+fn children_mut(operator: &mut PointInTimeOperator) -> Vec<&mut PointInTimeOperator> {
+    match operator {
+        PointInTimeOperator::Project(project) => vec![&mut project.source],
+        PointInTimeOperator::Filter(filter) => vec![&mut filter.source],
+        PointInTimeOperator::Limit(limit) => vec![&mut limit.source],
+        PointInTimeOperator::Sort(sort) => vec![&mut sort.source],
+        PointInTimeOperator::NegateFreq(source) => vec![source],
+        PointInTimeOperator::SortedGroup(group) | PointInTimeOperator::HashGroup(group) => {
+            vec![&mut group.source]
+        }
+        PointInTimeOperator::HashJoin(join) => vec![&mut join.left, &mut join.right],
+        PointInTimeOperator::UnionAll(union_all) => union_all.sources.iter_mut().collect(),
+        PointInTimeOperator::Gather(gather) => vec![&mut gather.source],
+        PointInTimeOperator::TableInsert(insert) => vec![&mut insert.source],
+        PointInTimeOperator::JsonTable(json_table) => vec![&mut json_table.source],
+        PointInTimeOperator::Single
+        | PointInTimeOperator::Values(_)
+        | PointInTimeOperator::TableScan(_)
+        | PointInTimeOperator::FulltextScan(_)
+        | PointInTimeOperator::FileScan(_) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Planner, PlannerError};
+    use data::SortOrder;
+
+    #[test]
+    fn test_parallelize() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert(PARALLEL_WORKERS_VARIABLE.to_string(), Datum::from(4));
+        planner.catalog.write().unwrap().create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Text),
+            ],
+            &[None, None],
+            &[],
+            &[("a".to_string(), SortOrder::Asc)],
+        )?;
+
+        let plan = |sql: &str| -> Result<PointInTimeOperator, PlannerError> {
+            let query = match parser::parse(sql).unwrap() {
+                ast::statement::Statement::Query(query) => query,
+                _ => panic!(),
+            };
+            Ok(planner.plan_for_point_in_time(query, &session)?.operator)
+        };
+
+        match plan("SELECT a + 1 FROM t WHERE b = 'x'")? {
+            PointInTimeOperator::Gather(gather) => {
+                assert_eq!(gather.workers, 4);
+                assert!(is_pipeline(&gather.source));
+            }
+            operator => panic!("Unexpected operator {:?}", operator),
+        }
+
+        match plan("SELECT b, count(*) FROM t WHERE a > 1 GROUP BY b")? {
+            PointInTimeOperator::HashGroup(group) => {
+                assert_eq!(group.mode, AggregateMode::Final);
+                match *group.source {
+                    PointInTimeOperator::Gather(gather) => match *gather.source {
+                        PointInTimeOperator::HashGroup(partial) => {
+                            assert_eq!(partial.mode, AggregateMode::Partial);
+                            assert!(is_pipeline(&partial.source));
+                        }
+                        operator => panic!("Unexpected operator {:?}", operator),
+                    },
+                    operator => panic!("Unexpected operator {:?}", operator),
+                }
+            }
+            operator => panic!("Unexpected operator {:?}", operator),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::utils::expr::{column_references, type_for_expression};
+use ast::expr::Expression;
+use ast::rel::point_in_time::{AggregateMode, PointInTimeOperator};
+use data::Datum;
+use std::collections::HashSet;
+
+/// Works out which columns are actually needed from each table scan so the rest can be skipped
+/// over rather than decoded, projected expressions that nothing reads are swapped for nulls.
+/// Required is the set of the operator's output columns needed by its parent, None for all of
+/// them.
+pub(super) fn prune_columns(operator: &mut PointInTimeOperator, required: Option<&HashSet<usize>>) {
+    match operator {
+        PointInTimeOperator::Project(project) => {
+            let mut source_required = HashSet::new();
+            for (idx, expr) in project.expressions.iter_mut().enumerate() {
+                if required.map_or(true, |required| required.contains(&idx)) {
+                    column_references(expr, &mut source_required);
+                } else if !matches!(expr, Expression::Constant(..)) {
+                    *expr = Expression::Constant(Datum::Null, type_for_expression(expr));
+                }
+            }
+            prune_columns(&mut project.source, Some(&source_required));
+        }
+        PointInTimeOperator::Filter(filter) => {
+            let source_required = required.map(|required| {
+                let mut source_required = required.clone();
+                column_references(&filter.predicate, &mut source_required);
+                source_required
+            });
+            prune_columns(&mut filter.source, source_required.as_ref());
+        }
+        PointInTimeOperator::Sort(sort) => {
+            let source_required = required.map(|required| {
+                let mut source_required = required.clone();
+                for sort_expr in &sort.sort_expressions {
+                    column_references(&sort_expr.expression, &mut source_required);
+                }
+                source_required
+            });
+            prune_columns(&mut sort.source, source_required.as_ref());
+        }
+        PointInTimeOperator::Limit(limit) => prune_columns(&mut limit.source, required),
+        PointInTimeOperator::NegateFreq(source) => prune_columns(source, required),
+        PointInTimeOperator::Gather(gather) => prune_columns(&mut gather.source, required),
+        PointInTimeOperator::UnionAll(union_all) => {
+            for source in &mut union_all.sources {
+                prune_columns(source, required);
+            }
+        }
+        PointInTimeOperator::SortedGroup(group) | PointInTimeOperator::HashGroup(group) => {
+            if group.mode == AggregateMode::Final {
+                // The source is the partial states, those are all needed.
+                prune_columns(&mut group.source, None);
+            } else {
+                // The grouping keys are the leading columns of the source
+                let mut source_required: HashSet<usize> = (0..group.key_len).collect();
+                for expr in &group.expressions {
+                    column_references(expr, &mut source_required);
+                }
+                prune_columns(&mut group.source, Some(&source_required));
+            }
+        }
+        PointInTimeOperator::HashJoin(join) => {
+            prune_columns(&mut join.left, None);
+            prune_columns(&mut join.right, None);
+        }
+        PointInTimeOperator::TableInsert(insert) => prune_columns(&mut insert.source, None),
+        // The source's columns are passed straight through ahead of the json columns.
+        PointInTimeOperator::JsonTable(json_table) => prune_columns(&mut json_table.source, None),
+        PointInTimeOperator::TableScan(table_scan) => {
+            if let Some(required) = required {
+                let column_mask: Vec<_> = (0..table_scan.table.column_count())
+                    .map(|idx| required.contains(&idx))
+                    .collect();
+                if column_mask.iter().any(|needed| !needed) {
+                    table_scan.column_mask = Some(column_mask);
+                }
+            }
+        }
+        PointInTimeOperator::Single
+        | PointInTimeOperator::Values(_)
+        | PointInTimeOperator::FulltextScan(_)
+        | PointInTimeOperator::FileScan(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Planner, PlannerError};
+    use data::{DataType, Session, SortOrder};
+
+    #[test]
+    fn test_prune_columns() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+        planner.catalog.write().unwrap().create_table(
+            "default",
+            "t",
+            &[
+                ("a".to_string(), DataType::Integer),
+                ("b".to_string(), DataType::Text),
+                ("c".to_string(), DataType::Text),
+                ("d".to_string(), DataType::Integer),
+            ],
+            &[None, None, None, None],
+            &[],
+            &[("a".to_string(), SortOrder::Asc)],
+        )?;
+
+        let column_mask = |sql: &str| -> Result<Option<Vec<bool>>, PlannerError> {
+            let query = match parser::parse(sql).unwrap() {
+                ast::statement::Statement::Query(query) => query,
+                _ => panic!(),
+            };
+            let mut operator = planner.plan_for_point_in_time(query, &session)?.operator;
+            loop {
+                match operator {
+                    PointInTimeOperator::TableScan(table_scan) => return Ok(table_scan.column_mask),
+                    PointInTimeOperator::Filter(filter) => operator = *filter.source,
+                    PointInTimeOperator::Project(project) => operator = *project.source,
+                    PointInTimeOperator::Sort(sort) => operator = *sort.source,
+                    PointInTimeOperator::Limit(limit) => operator = *limit.source,
+                    PointInTimeOperator::HashGroup(group)
+                    | PointInTimeOperator::SortedGroup(group) => operator = *group.source,
+                    operator => panic!("Unexpected operator {:?}", operator),
+                }
+            }
+        };
+
+        assert_eq!(column_mask("SELECT * FROM t")?, None);
+        assert_eq!(
+            column_mask("SELECT b FROM t WHERE d = 1")?,
+            Some(vec![false, true, false, true])
+        );
+        assert_eq!(
+            column_mask("SELECT a, c FROM t ORDER BY c LIMIT 1")?,
+            Some(vec![true, false, true, false])
+        );
+        assert_eq!(
+            column_mask("SELECT b, sum(d) FROM t GROUP BY b")?,
+            Some(vec![false, true, false, true])
+        );
+        assert_eq!(
+            column_mask("SELECT count(*) FROM t")?,
+            Some(vec![false, false, false, false])
+        );
+        Ok(())
+    }
+}
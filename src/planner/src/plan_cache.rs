@@ -0,0 +1,271 @@
+use crate::{Planner, PlannerError, PointInTimePlan};
+use ast::expr::Expression;
+use ast::rel::logical::LogicalOperator;
+use data::{Datum, Session};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The number of plans held onto by the plan cache.
+pub(crate) const PLAN_CACHE_ENTRIES: usize = 1024;
+
+/// Functions whose results depend on when or by whom the query is run, these get folded into
+/// the plan so the plans of any queries calling them can't be reused.
+const UNCACHEABLE_FUNCTIONS: &[&str] = &[
+    "current_timestamp",
+    "now",
+    "current_user",
+    "user",
+    "connection_id",
+    "replication_lag",
+];
+
+/// A cache of point in time plans, keyed by the sql text, the session's current database and its
+/// user (as privileges are only checked at planning).
+/// Entries are only valid while the catalog and the session's variables (which the plan may
+/// depend on) are unchanged, so any DDL invalidates all the entries.
+#[derive(Debug)]
+pub(crate) struct PlanCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    tick: u64,
+}
+
+/// (sql, current database, user)
+type CacheKey = (String, String, String);
+
+#[derive(Debug)]
+struct CacheEntry {
+    plan: PointInTimePlan,
+    catalog_version: u64,
+    variables: HashMap<String, Datum<'static>>,
+    last_used: u64,
+}
+
+impl PlanCache {
+    pub fn new(capacity: usize) -> Self {
+        PlanCache {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    fn get(
+        &self,
+        key: &CacheKey,
+        catalog_version: u64,
+        session: &Session,
+    ) -> Option<PointInTimePlan> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(key)?;
+        if entry.catalog_version != catalog_version {
+            // The catalog's moved on, it's never going to be valid again.
+            state.entries.remove(key);
+            return None;
+        }
+        if entry.variables != *session.variables.read().unwrap() {
+            return None;
+        }
+        entry.last_used = tick;
+        Some(entry.plan.clone())
+    }
+
+    fn insert(
+        &self,
+        key: CacheKey,
+        catalog_version: u64,
+        session: &Session,
+        plan: PointInTimePlan,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+        let variables = session.variables.read().unwrap().clone();
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let last_used = state.tick;
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let lru = state
+                .entries
+                .iter()
+                .min_by_key(|(_key, entry)| entry.last_used)
+                .map(|(key, _entry)| key.clone());
+            if let Some(lru) = lru {
+                state.entries.remove(&lru);
+            }
+        }
+        state.entries.insert(
+            key,
+            CacheEntry {
+                plan,
+                catalog_version,
+                variables,
+                last_used,
+            },
+        );
+    }
+}
+
+impl Planner {
+    /// Returns the plan cached for the sql text if there's one that's still valid for the
+    /// session, saving us from parsing and planning the query again.
+    pub fn cached_plan(&self, sql: &str, session: &Session) -> Option<PointInTimePlan> {
+        let catalog_version = self.catalog.read().unwrap().version();
+        self.plan_cache
+            .get(&cache_key(sql, session), catalog_version, session)
+    }
+
+    /// Plans the query parsed from the sql text for point in time, caching the plan against the
+    /// sql text if it only depends on the catalog and the session's database and variables.
+    pub fn plan_for_point_in_time_cached(
+        &self,
+        sql: &str,
+        query: LogicalOperator,
+        session: &Session,
+    ) -> Result<PointInTimePlan, PlannerError> {
+        // Read up front so any DDL racing the planning invalidates the entry
+        let catalog_version = self.catalog.read().unwrap().version();
+        let key = cache_key(sql, session);
+        let mut query = self.validate(query, session)?;
+        // By now any views have been expanded and the functions resolved
+        let cacheable = is_cacheable(&mut query);
+        let plan = self.plan_validated_for_point_in_time(query, session)?;
+        if cacheable {
+            self.plan_cache
+                .insert(key, catalog_version, session, plan.clone());
+        }
+        Ok(plan)
+    }
+}
+
+/// Only surrounding whitespace and trailing semicolons are stripped, anything more could merge
+/// queries differing by whitespace within quoted strings.
+fn cache_key(sql: &str, session: &Session) -> CacheKey {
+    let sql = sql.trim().trim_end_matches(';').trim_end().to_string();
+    let database = session.current_database.read().unwrap().to_string();
+    let user = session.user.read().unwrap().to_string();
+    (sql, database, user)
+}
+
+/// Plans can only be reused if they're read only and don't read from files (which come and go)
+/// or call any time or user dependant functions.
+fn is_cacheable(query: &mut LogicalOperator) -> bool {
+    if let LogicalOperator::TableInsert(_) | LogicalOperator::FileScan(_) = query {
+        return false;
+    }
+    let expressions_cacheable = query.expressions_mut().all(|expr| is_cacheable_expr(expr));
+    expressions_cacheable
+        && query.subqueries_mut().all(is_cacheable)
+        && query.children_mut().all(is_cacheable)
+}
+
+fn is_cacheable_expr(expr: &Expression) -> bool {
+    match expr {
+        Expression::CompiledFunctionCall(function_call)
+            if UNCACHEABLE_FUNCTIONS.contains(&function_call.signature.name) =>
+        {
+            false
+        }
+        expr => expr.children().all(is_cacheable_expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::{DataType, SortOrder};
+
+    fn parse(sql: &str) -> LogicalOperator {
+        match parser::parse(sql).unwrap() {
+            ast::statement::Statement::Query(query) => query,
+            _ => panic!(),
+        }
+    }
+
+    fn plan(planner: &Planner, session: &Session, sql: &str) -> Result<(), PlannerError> {
+        planner.plan_for_point_in_time_cached(sql, parse(sql), session)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_cache() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+        let create_table = |name: &str| {
+            planner.catalog.write().unwrap().create_table(
+                "default",
+                name,
+                &[("a".to_string(), DataType::Integer)],
+                &[None],
+                &[],
+                &[("a".to_string(), SortOrder::Asc)],
+            )
+        };
+        create_table("t")?;
+
+        let sql = "SELECT a FROM t WHERE a = 1";
+        assert!(planner.cached_plan(sql, &session).is_none());
+        plan(&planner, &session, sql)?;
+        assert!(planner.cached_plan(sql, &session).is_some());
+        assert!(planner
+            .cached_plan(" SELECT a FROM t WHERE a = 1; ", &session)
+            .is_some());
+        assert!(planner
+            .cached_plan("SELECT a FROM t WHERE a = 2", &session)
+            .is_none());
+
+        // Different database
+        *session.current_database.write().unwrap() = "incresql".to_string();
+        assert!(planner.cached_plan(sql, &session).is_none());
+        *session.current_database.write().unwrap() = "default".to_string();
+
+        // Different user
+        *session.user.write().unwrap() = "bob".to_string();
+        assert!(planner.cached_plan(sql, &session).is_none());
+        *session.user.write().unwrap() = String::new();
+
+        // Different variables
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("parallel_workers".to_string(), Datum::from(4));
+        assert!(planner.cached_plan(sql, &session).is_none());
+        session
+            .variables
+            .write()
+            .unwrap()
+            .insert("parallel_workers".to_string(), Datum::from(1));
+        assert!(planner.cached_plan(sql, &session).is_some());
+
+        // DDL
+        create_table("u")?;
+        assert!(planner.cached_plan(sql, &session).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_cache_uncacheable() -> Result<(), PlannerError> {
+        let planner = Planner::new_for_test();
+        let session = Session::new(1);
+
+        let sql = "SELECT now()";
+        plan(&planner, &session, sql)?;
+        assert!(planner.cached_plan(sql, &session).is_none());
+
+        let sql = "SELECT 1 + connection_id()";
+        plan(&planner, &session, sql)?;
+        assert!(planner.cached_plan(sql, &session).is_none());
+
+        let sql = "SELECT 1 + 1";
+        plan(&planner, &session, sql)?;
+        assert!(planner.cached_plan(sql, &session).is_some());
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use data::DataType;
 use functions::registry::Registry;
 use functions::{CompoundFunction, CompoundFunctionArg, FunctionSignature};
 use std::cmp::{max, min};
+use std::collections::HashSet;
 use std::iter::once;
 
 /// Returns the datatype for an expression, will panic if called before query is normalized
@@ -15,12 +16,41 @@ pub(crate) fn type_for_expression(expr: &Expression) -> DataType {
         Expression::CompiledColumnReference(column_reference) => column_reference.datatype,
 
         // These should be gone by now!
-        Expression::FunctionCall(_) | Expression::ColumnReference(_) => {
+        Expression::FunctionCall(_) | Expression::ColumnReference(_) | Expression::Subquery(_) => {
             panic!("These should be gone by now!")
         }
     }
 }
 
+/// Returns whether the expression could evaluate to null, given the nullability of each of the
+/// source's columns.
+pub(crate) fn nullable_for_expression(expr: &Expression, source_nullable: &[bool]) -> bool {
+    match expr {
+        Expression::Constant(datum, _) => datum.is_null(),
+        Expression::CompiledColumnReference(column_ref) => source_nullable
+            .get(column_ref.offset)
+            .copied()
+            .unwrap_or(true),
+        Expression::CompiledFunctionCall(function_call) => {
+            let args_nullable: Vec<_> = function_call
+                .args
+                .iter()
+                .map(|arg| nullable_for_expression(arg, source_nullable))
+                .collect();
+            function_call.function.nullable(&args_nullable)
+        }
+        Expression::CompiledAggregate(function_call) => {
+            let args_nullable: Vec<_> = function_call
+                .args
+                .iter()
+                .map(|arg| nullable_for_expression(arg, source_nullable))
+                .collect();
+            function_call.function.nullable(&args_nullable)
+        }
+        _ => true,
+    }
+}
+
 /// Returns true if the expression contains an aggregate anywhere in its expressions.
 pub(crate) fn contains_aggregate(expr: &Expression) -> bool {
     if let Expression::CompiledAggregate(_) = expr {
@@ -30,6 +60,16 @@ pub(crate) fn contains_aggregate(expr: &Expression) -> bool {
     }
 }
 
+/// Adds the offsets of all the column references within the expression to the set.
+pub(crate) fn column_references(expression: &Expression, columns: &mut HashSet<usize>) {
+    if let Expression::CompiledColumnReference(column_ref) = expression {
+        columns.insert(column_ref.offset);
+    }
+    for expr in expression.children() {
+        column_references(expr, columns);
+    }
+}
+
 /// This bumps all the column references up or down by some amount.
 /// To be used when inserting addition columns into some source, then this can be
 /// used to rewrite the offsets above
@@ -54,6 +94,9 @@ pub(crate) fn assemble_compound_function(
         .iter()
         .map(|compound_arg| match compound_arg {
             CompoundFunctionArg::Input(i) => input_args[*i].clone(),
+            CompoundFunctionArg::Constant(datum, datatype) => {
+                Expression::Constant(datum.clone(), *datatype)
+            }
             CompoundFunctionArg::Function(function) => {
                 assemble_compound_function(function, input_args)
             }
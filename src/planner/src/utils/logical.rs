@@ -1,7 +1,7 @@
-use crate::utils::expr::type_for_expression;
-use crate::Field;
-use ast::expr::{CompiledColumnReference, Expression, NamedExpression};
-use ast::rel::logical::{LogicalOperator, Project};
+use crate::utils::expr::{nullable_for_expression, type_for_expression};
+use crate::{Field, FieldOrigin, FieldResolutionError, PlannerError};
+use ast::expr::{ColumnReference, CompiledColumnReference, Expression, NamedExpression};
+use ast::rel::logical::{ColumnDefault, JoinType, LogicalOperator, Project, ResolvedTable};
 use data::DataType;
 use std::iter::{empty, once};
 
@@ -11,49 +11,126 @@ pub(crate) fn fields_for_operator(
 ) -> Box<dyn Iterator<Item = Field> + '_> {
     match operator {
         LogicalOperator::Project(_) | LogicalOperator::GroupBy(_) => {
-            Box::from(operator.named_expressions().map(|ne| Field {
+            let source_fields: Vec<_> = source_fields_for_operator(operator).collect();
+            let source_nullable: Vec<_> =
+                source_fields.iter().map(|field| field.nullable).collect();
+            Box::from(operator.named_expressions().map(move |ne| Field {
                 qualifier: None,
                 alias: ne.alias.as_ref().unwrap().clone(),
                 data_type: type_for_expression(&ne.expression),
+                nullable: nullable_for_expression(&ne.expression, &source_nullable),
+                // Only columns passed straight through keep their origin
+                origin: match &ne.expression {
+                    Expression::CompiledColumnReference(column) => {
+                        source_fields[column.offset].origin.clone()
+                    }
+                    _ => None,
+                },
             }))
         }
         LogicalOperator::Filter(filter) => fields_for_operator(&filter.source),
         LogicalOperator::Limit(limit) => fields_for_operator(&limit.source),
         LogicalOperator::Sort(sort) => fields_for_operator(&sort.source),
-        LogicalOperator::Values(values) => {
-            Box::from(values.fields.iter().map(|(data_type, alias)| Field {
+        LogicalOperator::Values(values) => Box::from(values.fields.iter().enumerate().map(
+            move |(idx, (data_type, alias))| Field {
                 qualifier: None,
                 alias: alias.clone(),
                 data_type: *data_type,
-            }))
-        }
+                nullable: values.data.iter().any(|row| {
+                    row.get(idx)
+                        .map_or(true, |expr| nullable_for_expression(expr, &[]))
+                }),
+                origin: None,
+            },
+        )),
         LogicalOperator::TableAlias(table_alias) => Box::from(
             fields_for_operator(&table_alias.source).map(move |f| Field {
                 qualifier: Some(table_alias.alias.clone()),
+                origin: f.origin.map(|origin| FieldOrigin {
+                    table_alias: table_alias.alias.clone(),
+                    ..origin
+                }),
                 ..f
             }),
         ),
         LogicalOperator::UnionAll(union_all) => {
-            fields_for_operator(union_all.sources.first().unwrap())
+            let mut sources = union_all.sources.iter();
+            let mut fields: Vec<_> = fields_for_operator(sources.next().unwrap()).collect();
+            for source in sources {
+                for (field, other) in fields.iter_mut().zip(fields_for_operator(source)) {
+                    field.nullable |= other.nullable;
+                    field.origin = None;
+                }
+            }
+            Box::from(fields.into_iter())
         }
-        LogicalOperator::ResolvedTable(table) => {
-            Box::from(table.columns.iter().map(|(alias, datatype)| Field {
+        LogicalOperator::SetOperation(set_operation) => {
+            let mut fields: Vec<_> = fields_for_operator(&set_operation.left).collect();
+            for (field, other) in fields
+                .iter_mut()
+                .zip(fields_for_operator(&set_operation.right))
+            {
+                field.nullable |= other.nullable;
+                field.origin = None;
+            }
+            Box::from(fields.into_iter())
+        }
+        LogicalOperator::ResolvedTable(table) => Box::from(table.columns.iter().enumerate().map(
+            move |(idx, (alias, datatype))| Field {
                 qualifier: None,
                 alias: alias.clone(),
                 data_type: *datatype,
-            }))
-        }
+                nullable: table.column_not_null.get(idx) != Some(&true),
+                origin: Some(FieldOrigin {
+                    database: table.database.clone(),
+                    table_alias: table.name.clone(),
+                    table: table.name.clone(),
+                    column: alias.clone(),
+                }),
+            },
+        )),
         LogicalOperator::NegateFreq(source) => fields_for_operator(source),
         LogicalOperator::Single | LogicalOperator::TableInsert(_) => Box::from(empty()),
+        LogicalOperator::FileScan(file_scan) if !file_scan.columns.is_empty() => {
+            Box::from(file_scan.columns.iter().map(|(alias, datatype)| Field {
+                qualifier: None,
+                alias: alias.clone(),
+                data_type: *datatype,
+                nullable: true,
+                origin: None,
+            }))
+        }
         LogicalOperator::FileScan(_) => Box::from(once(Field {
             qualifier: None,
             alias: "data".to_string(),
             data_type: DataType::Json,
+            nullable: false,
+            origin: None,
         })),
+        LogicalOperator::JsonTable(json_table) => Box::from(
+            fields_for_operator(&json_table.source).chain(json_table.columns.iter().map(
+                move |column| Field {
+                    qualifier: json_table.alias.clone(),
+                    alias: column.name.clone(),
+                    data_type: column.datatype,
+                    nullable: column.path.is_some(),
+                    origin: None,
+                },
+            )),
+        ),
         LogicalOperator::TableReference(_) => panic!(),
-        LogicalOperator::Join(join) => {
+        LogicalOperator::Join(join) if join.join_type == JoinType::LeftOuter => Box::from(
+            fields_for_operator(&join.left).chain(fields_for_operator(&join.right).map(|field| {
+                Field {
+                    nullable: true,
+                    ..field
+                }
+            })),
+        ),
+        LogicalOperator::Join(join) if join.join_type.outputs_right() => {
             Box::from(fields_for_operator(&join.left).chain(fields_for_operator(&join.right)))
         }
+        LogicalOperator::Join(join) => fields_for_operator(&join.left),
     }
 }
 
@@ -84,6 +161,9 @@ pub(crate) fn fieldnames_for_operator(
         LogicalOperator::UnionAll(union_all) => {
             fieldnames_for_operator(union_all.sources.first().unwrap())
         }
+        LogicalOperator::SetOperation(set_operation) => {
+            fieldnames_for_operator(&set_operation.left)
+        }
         LogicalOperator::ResolvedTable(table) => Box::from(
             table
                 .columns
@@ -91,11 +171,26 @@ pub(crate) fn fieldnames_for_operator(
                 .map(|(alias, _datatype)| (None, alias.as_str())),
         ),
         LogicalOperator::NegateFreq(source) => fieldnames_for_operator(source),
+        LogicalOperator::FileScan(file_scan) if !file_scan.columns.is_empty() => Box::from(
+            file_scan
+                .columns
+                .iter()
+                .map(|(alias, _datatype)| (None, alias.as_str())),
+        ),
         LogicalOperator::FileScan(_) => Box::from(once((None, "data"))),
+        LogicalOperator::JsonTable(json_table) => Box::from(
+            fieldnames_for_operator(&json_table.source).chain(
+                json_table
+                    .columns
+                    .iter()
+                    .map(move |column| (json_table.alias.as_deref(), column.name.as_str())),
+            ),
+        ),
         LogicalOperator::Single | LogicalOperator::TableInsert(_) => Box::from(empty()),
-        LogicalOperator::Join(join) => Box::from(
+        LogicalOperator::Join(join) if join.join_type.outputs_right() => Box::from(
             fieldnames_for_operator(&join.left).chain(fieldnames_for_operator(&join.right)),
         ),
+        LogicalOperator::Join(join) => fieldnames_for_operator(&join.left),
         LogicalOperator::TableReference(_) => panic!(),
     }
 }
@@ -116,10 +211,16 @@ pub(crate) fn source_fields_for_operator(
         LogicalOperator::UnionAll(union_all) => {
             fields_for_operator(union_all.sources.first().unwrap())
         }
+        LogicalOperator::SetOperation(set_operation) => fields_for_operator(&set_operation.left),
         LogicalOperator::TableInsert(table_insert) => fields_for_operator(&table_insert.source),
         LogicalOperator::NegateFreq(source) => fields_for_operator(source),
-        // The on clause see's the columns the same as the operators above do.
-        LogicalOperator::Join(_) => fields_for_operator(operator),
+        // The document is evaluated against the source rows
+        LogicalOperator::JsonTable(json_table) => fields_for_operator(&json_table.source),
+        // The on clause see's the columns of both sides, even for semi joins where the operators
+        // above only see the left.
+        LogicalOperator::Join(join) => {
+            Box::from(fields_for_operator(&join.left).chain(fields_for_operator(&join.right)))
+        }
         LogicalOperator::Values(_)
         | LogicalOperator::Single
         | LogicalOperator::TableReference(_)
@@ -147,13 +248,68 @@ pub(crate) fn create_wrapping_project(operator: LogicalOperator) -> Project {
     }
 }
 
+/// Returns the table columns populated by an insert, ie the columns explicitly listed as part of
+/// the insert, or all the table's columns (bar generated columns) if none were listed.
+/// Generated columns are always computed so listing one is an error.
+pub(crate) fn insert_columns(
+    columns: &[String],
+    table: &ResolvedTable,
+) -> Result<Vec<(String, DataType)>, PlannerError> {
+    let is_generated = |idx: usize| {
+        matches!(
+            table.column_defaults.get(idx),
+            Some(Some(ColumnDefault::Generated(_)))
+        )
+    };
+    if columns.is_empty() {
+        return Ok(table
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !is_generated(*idx))
+            .map(|(_, column)| column.clone())
+            .collect());
+    }
+    columns
+        .iter()
+        .map(
+            |column| match table.columns.iter().position(|(alias, _)| alias == column) {
+                Some(idx) if is_generated(idx) => Err(PlannerError::GeneratedColumnValue(
+                    column.clone(),
+                    table.name.clone(),
+                )),
+                Some(idx) => Ok(table.columns[idx].clone()),
+                None => Err(FieldResolutionError::NotFound(
+                    ColumnReference {
+                        qualifier: None,
+                        alias: column.clone(),
+                        star: false,
+                    },
+                    table
+                        .columns
+                        .iter()
+                        .map(|(alias, data_type)| Field {
+                            qualifier: None,
+                            alias: alias.clone(),
+                            data_type: *data_type,
+                            nullable: true,
+                            origin: None,
+                        })
+                        .collect(),
+                )
+                .into()),
+            },
+        )
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ast::expr::{Expression, NamedExpression};
-    use ast::rel::logical::{Project, TableAlias};
+    use ast::rel::logical::{Project, TableAlias, UnionAll};
     use data::rust_decimal::Decimal;
-    use data::DataType;
+    use data::{DataType, Datum};
     use std::str::FromStr;
 
     #[test]
@@ -172,7 +328,9 @@ mod tests {
             vec![Field {
                 qualifier: None,
                 alias: "bar".to_string(),
-                data_type: DataType::Decimal(3, 2)
+                data_type: DataType::Decimal(3, 2),
+                nullable: false,
+                origin: None,
             }]
         );
 
@@ -186,11 +344,47 @@ mod tests {
             vec![Field {
                 qualifier: Some("foo".to_string()),
                 alias: "bar".to_string(),
-                data_type: DataType::Decimal(3, 2)
+                data_type: DataType::Decimal(3, 2),
+                nullable: false,
+                origin: None,
             }]
         );
     }
 
+    #[test]
+    fn test_fields_for_operator_nullable() {
+        let project = |expression: Expression| {
+            LogicalOperator::Project(Project {
+                distinct: false,
+                expressions: vec![NamedExpression {
+                    alias: Some("a".to_string()),
+                    expression,
+                }],
+                source: Box::new(LogicalOperator::Single),
+            })
+        };
+        let nullable = |operator: &LogicalOperator| -> Vec<bool> {
+            fields_for_operator(operator).map(|f| f.nullable).collect()
+        };
+
+        assert_eq!(nullable(&project(Expression::from(1))), vec![false]);
+        assert_eq!(
+            nullable(&project(Expression::Constant(
+                Datum::Null,
+                DataType::Integer
+            ))),
+            vec![true]
+        );
+
+        let union_all = LogicalOperator::UnionAll(UnionAll {
+            sources: vec![
+                project(Expression::from(1)),
+                project(Expression::Constant(Datum::Null, DataType::Integer)),
+            ],
+        });
+        assert_eq!(nullable(&union_all), vec![true]);
+    }
+
     #[test]
     fn test_fieldnames_for_operator() {
         let projection = LogicalOperator::Project(Project {
@@ -1,2 +1,3 @@
 pub(crate) mod expr;
 pub(crate) mod logical;
+pub(crate) mod selectivity;
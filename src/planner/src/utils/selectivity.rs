@@ -0,0 +1,353 @@
+use crate::utils::logical::fields_for_operator;
+use ast::expr::{CompiledColumnReference, CompiledFunctionCall, Expression};
+use ast::rel::logical::{JoinType, LogicalOperator};
+use data::statistics::ColumnStatistics;
+use data::Datum;
+
+/// Selectivity used for equality predicates on columns we don't have stats for.
+const DEFAULT_EQ_SELECTIVITY: f64 = 0.1;
+/// Selectivity used for range predicates on columns we don't have stats for.
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+/// Selectivity used for everything else.
+const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+/// Returns the column statistics for each of the columns output by the operator, tracing the
+/// columns back through to the underlying tables where possible.
+pub(crate) fn statistics_for_operator(
+    operator: &LogicalOperator,
+) -> Vec<Option<&ColumnStatistics>> {
+    match operator {
+        LogicalOperator::ResolvedTable(table) => {
+            table.statistics.iter().map(Option::as_ref).collect()
+        }
+        LogicalOperator::TableAlias(table_alias) => statistics_for_operator(&table_alias.source),
+        LogicalOperator::Filter(filter) => statistics_for_operator(&filter.source),
+        LogicalOperator::Limit(limit) => statistics_for_operator(&limit.source),
+        LogicalOperator::Sort(sort) => statistics_for_operator(&sort.source),
+        LogicalOperator::Join(join) => {
+            let mut statistics = statistics_for_operator(&join.left);
+            statistics.extend(statistics_for_operator(&join.right));
+            statistics
+        }
+        LogicalOperator::JsonTable(json_table) => {
+            let mut statistics = statistics_for_operator(&json_table.source);
+            statistics.extend(json_table.columns.iter().map(|_| None));
+            statistics
+        }
+        LogicalOperator::Project(project) => {
+            let source_statistics = statistics_for_operator(&project.source);
+            project
+                .expressions
+                .iter()
+                .map(|ne| match &ne.expression {
+                    Expression::CompiledColumnReference(CompiledColumnReference {
+                        offset, ..
+                    }) => source_statistics.get(*offset).copied().flatten(),
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => fields_for_operator(operator).map(|_| None).collect(),
+    }
+}
+
+/// Estimates the number of rows output by the operator, None if the tables feeding into it
+/// haven't been analyzed.
+pub(crate) fn estimate_row_count(operator: &LogicalOperator) -> Option<f64> {
+    match operator {
+        LogicalOperator::Single => Some(1.0),
+        LogicalOperator::Values(values) => Some(values.data.len() as f64),
+        LogicalOperator::ResolvedTable(table) => table
+            .statistics
+            .iter()
+            .flatten()
+            .next()
+            .map(|stats| stats.row_count as f64),
+        LogicalOperator::TableAlias(table_alias) => estimate_row_count(&table_alias.source),
+        LogicalOperator::Project(project) => estimate_row_count(&project.source),
+        LogicalOperator::Sort(sort) => estimate_row_count(&sort.source),
+        LogicalOperator::NegateFreq(source) => estimate_row_count(source),
+        LogicalOperator::Filter(filter) => {
+            let statistics = statistics_for_operator(&filter.source);
+            estimate_row_count(&filter.source)
+                .map(|rows| rows * estimate_selectivity(&filter.predicate, &statistics))
+        }
+        LogicalOperator::Limit(limit) => estimate_row_count(&limit.source).map(|rows| {
+            (rows - limit.offset as f64)
+                .max(0.0)
+                .min(limit.limit as f64)
+        }),
+        LogicalOperator::Join(join) => {
+            let left = estimate_row_count(&join.left)?;
+            let right = estimate_row_count(&join.right)?;
+            let statistics = statistics_for_operator(operator);
+            let rows = left * right * estimate_selectivity(&join.on, &statistics);
+            match join.join_type {
+                JoinType::Inner => Some(rows),
+                JoinType::LeftOuter => Some(rows.max(left)),
+                // Each left row is output at most once
+                JoinType::Semi => Some(rows.min(left)),
+                JoinType::Anti => Some((left - rows).max(0.0)),
+            }
+        }
+        LogicalOperator::UnionAll(union) => union
+            .sources
+            .iter()
+            .map(estimate_row_count)
+            .sum::<Option<f64>>(),
+        LogicalOperator::GroupBy(group_by) => {
+            let rows = estimate_row_count(&group_by.source)?;
+            if group_by.key_expressions.is_empty() {
+                return Some(1.0);
+            }
+            // Assume the keys are independent, so the number of groups is the product of the
+            // distinct values of each key.
+            let statistics = statistics_for_operator(&group_by.source);
+            let groups = group_by
+                .key_expressions
+                .iter()
+                .map(|key| column_statistics(key, &statistics).map(|stats| stats.ndv as f64))
+                .product::<Option<f64>>()
+                .unwrap_or(rows);
+            Some(groups.min(rows))
+        }
+        // There's no telling how many matches each document has
+        LogicalOperator::TableInsert(_)
+        | LogicalOperator::SetOperation(_)
+        | LogicalOperator::FileScan(_)
+        | LogicalOperator::JsonTable(_)
+        | LogicalOperator::TableReference(_) => None,
+    }
+}
+
+/// Estimates the fraction of rows from the source that will pass the predicate.
+pub(crate) fn estimate_selectivity(
+    predicate: &Expression,
+    statistics: &[Option<&ColumnStatistics>],
+) -> f64 {
+    let selectivity = match predicate {
+        Expression::Constant(Datum::Boolean(true), _) => 1.0,
+        Expression::Constant(_, _) => 0.0,
+        Expression::CompiledFunctionCall(function_call) => {
+            estimate_function_call(function_call, statistics)
+        }
+        _ => DEFAULT_SELECTIVITY,
+    };
+    selectivity.max(0.0).min(1.0)
+}
+
+fn estimate_function_call(
+    function_call: &CompiledFunctionCall,
+    statistics: &[Option<&ColumnStatistics>],
+) -> f64 {
+    match (function_call.signature.name, &*function_call.args) {
+        ("and", [left, right]) => {
+            estimate_selectivity(left, statistics) * estimate_selectivity(right, statistics)
+        }
+        ("or", [left, right]) => {
+            let left = estimate_selectivity(left, statistics);
+            let right = estimate_selectivity(right, statistics);
+            left + right - left * right
+        }
+        ("not", [expr]) => 1.0 - estimate_selectivity(expr, statistics),
+        ("isnull", [expr]) => match column_statistics(expr, statistics) {
+            Some(stats) => stats.null_fraction(),
+            None => DEFAULT_EQ_SELECTIVITY,
+        },
+        ("=", [left, right]) => estimate_eq(left, right, statistics),
+        ("!=", [left, right]) => match column_and_constant(left, right, statistics) {
+            Some((stats, _)) => 1.0 - stats.null_fraction() - stats.eq_selectivity(),
+            None => 1.0 - DEFAULT_EQ_SELECTIVITY,
+        },
+        ("<", [left, right]) => estimate_range(left, right, false, false, statistics),
+        ("<=", [left, right]) => estimate_range(left, right, false, true, statistics),
+        (">", [left, right]) => estimate_range(left, right, true, false, statistics),
+        (">=", [left, right]) => estimate_range(left, right, true, true, statistics),
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+fn estimate_eq(
+    left: &Expression,
+    right: &Expression,
+    statistics: &[Option<&ColumnStatistics>],
+) -> f64 {
+    if let Some((stats, _)) = column_and_constant(left, right, statistics) {
+        return stats.eq_selectivity();
+    }
+    // Column to column comparisons, ie join conditions, assume the values of the column with the
+    // fewest distinct values are all found in the other column.
+    match (
+        column_statistics(left, statistics),
+        column_statistics(right, statistics),
+    ) {
+        (Some(left), Some(right)) if left.ndv > 0 && right.ndv > 0 => {
+            1.0 / left.ndv.max(right.ndv) as f64
+        }
+        _ => DEFAULT_EQ_SELECTIVITY,
+    }
+}
+
+/// Estimates left < right (or left > right if greater), inclusive meaning <= or >=.
+fn estimate_range(
+    left: &Expression,
+    right: &Expression,
+    greater: bool,
+    inclusive: bool,
+    statistics: &[Option<&ColumnStatistics>],
+) -> f64 {
+    let (stats, value, greater) =
+        if let Some((stats, value)) = column_and_constant(left, right, statistics) {
+            // For constant op column we need to flip the comparison around
+            let column_on_left = column_statistics(left, statistics).is_some();
+            (stats, value, greater == column_on_left)
+        } else {
+            return DEFAULT_RANGE_SELECTIVITY;
+        };
+
+    if value.is_null() {
+        return 0.0;
+    }
+
+    if greater {
+        // column > value is everything non null that isn't <= value
+        1.0 - stats.null_fraction() - stats.less_than_selectivity(value, !inclusive)
+    } else {
+        stats.less_than_selectivity(value, inclusive)
+    }
+}
+
+/// Matches column op constant or constant op column where the column has statistics.
+fn column_and_constant<'a>(
+    left: &'a Expression,
+    right: &'a Expression,
+    statistics: &[Option<&'a ColumnStatistics>],
+) -> Option<(&'a ColumnStatistics, &'a Datum<'static>)> {
+    match (left, right) {
+        (column, Expression::Constant(value, _)) | (Expression::Constant(value, _), column) => {
+            column_statistics(column, statistics).map(|stats| (stats, value))
+        }
+        _ => None,
+    }
+}
+
+fn column_statistics<'a>(
+    expression: &Expression,
+    statistics: &[Option<&'a ColumnStatistics>],
+) -> Option<&'a ColumnStatistics> {
+    if let Expression::CompiledColumnReference(CompiledColumnReference { offset, .. }) = expression
+    {
+        statistics.get(*offset).copied().flatten()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::logical::{Filter, GroupBy, ResolvedTable, TableReference};
+    use data::statistics::ColumnStatisticsBuilder;
+    use data::DataType;
+    use functions::registry::Registry;
+    use functions::FunctionSignature;
+
+    fn call(name: &'static str, args: Vec<Expression>, arg_type: DataType) -> Expression {
+        let function_registry = Registry::default();
+        let signature = FunctionSignature {
+            name,
+            args: args.iter().map(|_| arg_type).collect(),
+            ret: DataType::Boolean,
+        };
+        let (signature, function) = function_registry.resolve_function(&signature).unwrap();
+        Expression::CompiledFunctionCall(CompiledFunctionCall {
+            function: function.as_scalar(),
+            args: Box::from(args),
+            expr_buffer: Box::from(vec![]),
+            signature: Box::new(signature),
+        })
+    }
+
+    fn column(offset: usize) -> Expression {
+        Expression::CompiledColumnReference(CompiledColumnReference {
+            offset,
+            datatype: DataType::Integer,
+        })
+    }
+
+    #[test]
+    fn test_estimate_row_count() {
+        let mut builder = ColumnStatisticsBuilder::default();
+        for i in 0..100 {
+            builder.add(&Datum::from(i), 1);
+        }
+        let storage = storage::Storage::new_in_mem().unwrap();
+        let table = LogicalOperator::ResolvedTable(Box::new(ResolvedTable {
+            database: "default".to_string(),
+            name: "t".to_string(),
+            columns: vec![("a".to_string(), DataType::Integer)],
+            column_defaults: vec![None],
+            column_not_null: vec![false],
+            table: storage.table(8, 1, vec![data::SortOrder::Asc]),
+            statistics: vec![Some(builder.build())],
+            partitioning: None,
+            fulltext_indexes: vec![],
+            secondary_indexes: vec![],
+            primary_key_len: 1,
+            as_of: None,
+        }));
+        assert_eq!(estimate_row_count(&table), Some(100.0));
+
+        let filter = LogicalOperator::Filter(Filter {
+            predicate: call("=", vec![column(0), Expression::from(5)], DataType::Integer),
+            source: Box::new(table.clone()),
+        });
+        assert!((estimate_row_count(&filter).unwrap() - 1.0).abs() < 0.1);
+
+        let group_by = LogicalOperator::GroupBy(GroupBy {
+            expressions: vec![],
+            key_expressions: vec![column(0)],
+            distinct_on: false,
+            source: Box::new(table),
+        });
+        assert!((estimate_row_count(&group_by).unwrap() - 100.0).abs() < 5.0);
+
+        // Unresolved tables don't have stats
+        let table_reference = LogicalOperator::TableReference(TableReference {
+            database: None,
+            table: "t".to_string(),
+            as_of: None,
+        });
+        assert_eq!(estimate_row_count(&table_reference), None);
+    }
+
+    #[test]
+    fn test_estimate_selectivity() {
+        let mut builder = ColumnStatisticsBuilder::default();
+        for i in 0..100 {
+            builder.add(&Datum::from(i), 1);
+        }
+        let stats = builder.build();
+        let statistics = [Some(&stats), None];
+        let estimate = |expr: &Expression| estimate_selectivity(expr, &statistics);
+        let int = DataType::Integer;
+
+        let eq = call("=", vec![column(0), Expression::from(5)], int);
+        assert!((estimate(&eq) - 0.01).abs() < 0.001);
+
+        let lt = call("<", vec![column(0), Expression::from(50)], int);
+        assert!((estimate(&lt) - 0.5).abs() < 0.02);
+        let flipped = call(">", vec![Expression::from(75), column(0)], int);
+        assert!((estimate(&flipped) - 0.75).abs() < 0.02);
+        let gte = call(">=", vec![column(0), Expression::from(75)], int);
+        assert!((estimate(&gte) - 0.25).abs() < 0.02);
+
+        // Falls back to defaults without stats
+        let no_stats = call("=", vec![column(1), Expression::from(5)], int);
+        assert_eq!(estimate(&no_stats), DEFAULT_EQ_SELECTIVITY);
+
+        let and = call("and", vec![lt, no_stats], DataType::Boolean);
+        assert!((estimate(&and) - 0.05).abs() < 0.002);
+        assert_eq!(estimate(&Expression::from(true)), 1.0);
+    }
+}
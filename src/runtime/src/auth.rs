@@ -0,0 +1,121 @@
+use sha1::Sha1;
+
+/// Hashes a password into the authentication string stored in the users table.
+/// This is the same format as mysql_native_password, "*" followed by the hex encoded
+/// SHA1(SHA1(password)), an empty password is stored as an empty string.
+pub fn hash_password(password: &str) -> String {
+    if password.is_empty() {
+        return String::new();
+    }
+    let stage1 = Sha1::from(password.as_bytes()).digest().bytes();
+    let stage2 = Sha1::from(&stage1[..]).digest().bytes();
+    let mut hashed = String::with_capacity(41);
+    hashed.push('*');
+    for byte in stage2.iter() {
+        hashed.push_str(&format!("{:02X}", byte));
+    }
+    hashed
+}
+
+/// Checks a mysql_native_password challenge response against the stored authentication string.
+/// The client sends SHA1(password) XOR SHA1(scramble + SHA1(SHA1(password))), so we can recover
+/// SHA1(password) using the stored hash and then check it hashes back to the stored value.
+pub fn verify_native_password(
+    authentication_string: &str,
+    scramble: &[u8],
+    response: &[u8],
+) -> bool {
+    if authentication_string.is_empty() {
+        return response.is_empty();
+    }
+    let stage2 = match decode_hash(authentication_string) {
+        Some(stage2) => stage2,
+        None => return false,
+    };
+    if response.len() != stage2.len() {
+        return false;
+    }
+
+    let mut hasher = Sha1::new();
+    hasher.update(scramble);
+    hasher.update(&stage2);
+    let mask = hasher.digest().bytes();
+
+    let stage1: Vec<_> = response
+        .iter()
+        .zip(mask.iter())
+        .map(|(a, b)| a ^ b)
+        .collect();
+    Sha1::from(&stage1[..]).digest().bytes() == stage2
+}
+
+/// The client side of the challenge, what a mysql client would send as its auth response. Used
+/// by followers logging into their leader.
+pub fn native_password_response(password: &str, scramble: &[u8]) -> Vec<u8> {
+    if password.is_empty() {
+        return vec![];
+    }
+    let stage1 = Sha1::from(password.as_bytes()).digest().bytes();
+    let stage2 = Sha1::from(&stage1[..]).digest().bytes();
+    let mut hasher = Sha1::new();
+    hasher.update(scramble);
+    hasher.update(&stage2);
+    let mask = hasher.digest().bytes();
+    stage1.iter().zip(mask.iter()).map(|(a, b)| a ^ b).collect()
+}
+
+/// Decodes the "*" prefixed hex string back into the 20 byte hash
+fn decode_hash(authentication_string: &str) -> Option<[u8; 20]> {
+    let hex = authentication_string.strip_prefix('*')?;
+    if hex.len() != 40 {
+        return None;
+    }
+    let mut hash = [0_u8; 20];
+    for (idx, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(idx * 2..idx * 2 + 2)?, 16).ok()?;
+    }
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_password() {
+        // Matches mysql's PASSWORD('password')
+        assert_eq!(
+            hash_password("password"),
+            "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"
+        );
+        assert_eq!(hash_password(""), "");
+    }
+
+    #[test]
+    fn test_verify_native_password() {
+        let scramble = b"abcdefghijklmnopqrst";
+        let stored = hash_password("secret");
+        assert!(verify_native_password(
+            &stored,
+            scramble,
+            &native_password_response("secret", scramble)
+        ));
+        assert!(!verify_native_password(
+            &stored,
+            scramble,
+            &native_password_response("wrong", scramble)
+        ));
+        assert!(!verify_native_password(&stored, scramble, &[]));
+    }
+
+    #[test]
+    fn test_verify_no_password() {
+        let scramble = b"abcdefghijklmnopqrst";
+        assert!(verify_native_password("", scramble, &[]));
+        assert!(!verify_native_password(
+            "",
+            scramble,
+            &native_password_response("secret", scramble)
+        ));
+    }
+}
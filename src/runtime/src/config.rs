@@ -0,0 +1,284 @@
+use crate::workload::WorkloadConfig;
+use crate::ConnectionLimits;
+use serde::Deserialize;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use storage::StorageConfig;
+
+/// The settings incresql is started with, read from a toml file (with the command line flags
+/// layered over the top), ie
+/// ```toml
+/// directory = "/var/lib/incresql"
+/// log_level = "warn"
+///
+/// [server]
+/// port = 3307
+///
+/// [memory]
+/// block_cache_size = 536870912
+/// ```
+/// Anything left out keeps its default.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where the database is stored.
+    pub directory: String,
+    pub log_level: LogLevel,
+    pub slow_query_log: SlowQueryLogConfig,
+    pub server: ServerConfig,
+    pub workload: WorkloadConfig,
+    pub connections: ConnectionLimits,
+    pub memory: MemoryConfig,
+    pub rocksdb: RocksDbConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            directory: "target/test_db".to_string(),
+            log_level: LogLevel::Info,
+            slow_query_log: SlowQueryLogConfig::default(),
+            server: ServerConfig::default(),
+            workload: WorkloadConfig::default(),
+            connections: ConnectionLimits::default(),
+            memory: MemoryConfig::default(),
+            rocksdb: RocksDbConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config from a toml file.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read config file {}: {}", path, err))?;
+        Ok(Config::from_toml(&contents)
+            .map_err(|err| format!("Invalid config file {}: {}", path, err))?)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// The options the storage is opened with.
+    pub fn storage_config(&self) -> StorageConfig {
+        StorageConfig {
+            block_cache_size: self.memory.block_cache_size,
+            write_buffer_size: self.memory.write_buffer_size,
+            parallelism: self.rocksdb.parallelism,
+            max_background_jobs: self.rocksdb.max_background_jobs,
+            max_open_files: self.rocksdb.max_open_files,
+            bloom_filter_bits: self.rocksdb.bloom_filter_bits,
+        }
+    }
+}
+
+/// The initial slow_query_log and long_query_time given to each session, statements running
+/// for longer than long_query_time (in seconds) get recorded in incresql.slow_log.
+#[derive(Debug, Copy, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlowQueryLogConfig {
+    pub enabled: bool,
+    pub long_query_time: f64,
+}
+
+impl Default for SlowQueryLogConfig {
+    fn default() -> Self {
+        SlowQueryLogConfig {
+            enabled: false,
+            long_query_time: 10.0,
+        }
+    }
+}
+
+/// Where and how the server accepts mysql connections.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub listen_address: String,
+    pub port: u16,
+    /// Number of threads servicing client connections.
+    pub worker_threads: usize,
+    /// Port to serve query results as arrow ipc streams on, off unless set.
+    pub arrow_port: Option<u16>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            listen_address: "0.0.0.0".to_string(),
+            port: 3307,
+            worker_threads: 8,
+            arrow_port: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// The address to bind to, ie 0.0.0.0:3307
+    pub fn listen_addr(&self) -> String {
+        self.addr(self.port)
+    }
+
+    /// The address to serve arrow results on, if enabled.
+    pub fn arrow_listen_addr(&self) -> Option<String> {
+        self.arrow_port.map(|port| self.addr(port))
+    }
+
+    fn addr(&self, port: u16) -> String {
+        if self.listen_address.contains(':') {
+            // ipv6
+            format!("[{}]:{}", self.listen_address, port)
+        } else {
+            format!("{}:{}", self.listen_address, port)
+        }
+    }
+}
+
+/// Caps on the memory used, all in bytes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    /// Shared by everything reading from the storage.
+    pub block_cache_size: usize,
+    /// Per memtable, rocksdb keeps a couple of these around.
+    pub write_buffer_size: usize,
+    /// The initial sort_buffer_size given to each session, how much each sort can buffer before
+    /// spilling out to disk.
+    pub sort_buffer_size: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        let storage_config = StorageConfig::default();
+        MemoryConfig {
+            block_cache_size: storage_config.block_cache_size,
+            write_buffer_size: storage_config.write_buffer_size,
+            sort_buffer_size: 128 * 1024 * 1024,
+        }
+    }
+}
+
+/// Rocksdb tuning, see StorageConfig.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RocksDbConfig {
+    pub parallelism: i32,
+    pub max_background_jobs: i32,
+    pub max_open_files: i32,
+    pub bloom_filter_bits: i32,
+}
+
+impl Default for RocksDbConfig {
+    fn default() -> Self {
+        let storage_config = StorageConfig::default();
+        RocksDbConfig {
+            parallelism: storage_config.parallelism,
+            max_background_jobs: storage_config.max_background_jobs,
+            max_open_files: storage_config.max_open_files,
+            bloom_filter_bits: storage_config.bloom_filter_bits,
+        }
+    }
+}
+
+/// How chatty the server is, each level includes the ones before it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!("Unknown log level {}", s)),
+        }
+    }
+}
+
+impl Display for LogLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_from_toml() {
+        assert_eq!(Config::from_toml("").unwrap(), Config::default());
+
+        let config = Config::from_toml(
+            r#"
+            directory = "/var/lib/incresql"
+            log_level = "debug"
+
+            [server]
+            port = 3306
+
+            [slow_query_log]
+            enabled = true
+
+            [connections]
+            max_connections = 100
+
+            [memory]
+            block_cache_size = 1024
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.directory, "/var/lib/incresql");
+        assert_eq!(config.log_level, LogLevel::Debug);
+        assert_eq!(
+            config.slow_query_log,
+            SlowQueryLogConfig {
+                enabled: true,
+                long_query_time: 10.0
+            }
+        );
+        assert_eq!(config.server.listen_addr(), "0.0.0.0:3306");
+        assert_eq!(config.server.worker_threads, 8);
+        assert_eq!(config.connections.max_connections, 100);
+        assert_eq!(config.connections.wait_timeout, 28800);
+        assert_eq!(config.storage_config().block_cache_size, 1024);
+        assert_eq!(config.storage_config().parallelism, 4);
+    }
+
+    #[test]
+    fn test_config_from_toml_errors() {
+        assert!(Config::from_toml("prot = 3306").is_err());
+        assert!(Config::from_toml("[server]\nprot = 3306").is_err());
+        assert!(Config::from_toml("log_level = \"loud\"").is_err());
+    }
+
+    #[test]
+    fn test_listen_addr() {
+        let mut config = ServerConfig::default();
+        assert_eq!(config.listen_addr(), "0.0.0.0:3307");
+        config.listen_address = "::1".to_string();
+        assert_eq!(config.listen_addr(), "[::1]:3307");
+        assert_eq!(config.arrow_listen_addr(), None);
+        config.arrow_port = Some(3309);
+        assert_eq!(config.arrow_listen_addr(), Some("[::1]:3309".to_string()));
+    }
+}
@@ -1,13 +1,51 @@
+use crate::auth::hash_password;
+use crate::materialized_views::{self, delta_operator, maintain_views, populate_view, TableChange};
+use crate::query_cache::{normalize_sql, table_versions};
+use crate::subscriptions::select_star;
+use crate::transactions::{written_tables, OpenTransaction};
+use crate::user_functions::register_user_function;
+use crate::workload::{PermittedExecutor, WorkloadClass, WorkloadPermit};
 use crate::{QueryError, Runtime};
-use ast::expr::Expression;
-use ast::rel::logical::{LogicalOperator, Values};
-use ast::statement::Statement;
-use catalog::TableOrView;
-use data::{empty_tuple_iter, DataType, Session};
+use ast::expr::{Cast, ColumnReference, Expression, NamedExpression};
+use ast::rel::logical::{
+    BulkLoad, ColumnDefault, LogicalOperator, PartitionMethod, Project, Values,
+};
+use ast::rel::point_in_time::{PointInTimeOperator, TableInsert};
+use ast::statement::{AlterTableAction, ExplainFormat, SetVariable, SinkFormat, Statement};
+use catalog::{
+    CatalogError, ExternalTable, FunctionBody, ObjectLock, Sink, TableOrView, UserFunction,
+};
+use data::statistics::ColumnStatisticsBuilder;
+use data::{
+    empty_tuple_iter, parse_time_zone, DataType, Datum, LogicalTimestamp, Session, TupleIter,
+};
 use executor::point_in_time::{build_executor, BoxedExecutor};
-use parser::parse;
-use planner::Field;
-use std::sync::Arc;
+use executor::ExecutionError;
+use functions::{FunctionSignature, UdfError};
+use parser::{bind_parameters, parameter_offsets, parse, split_statements};
+use planner::{Field, PlannerError, PointInTimePlan};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+/// The collations reported by SHOW COLLATION, (collation, charset, id).
+/// Everything is stored as utf8 bytes, the _ci collations just compare case insensitively.
+const COLLATIONS: [(&str, &str, i32); 4] = [
+    ("utf8mb4_general_ci", "utf8mb4", 45),
+    ("utf8mb4_bin", "utf8mb4", 46),
+    ("utf8_general_ci", "utf8", 33),
+    ("binary", "binary", 63),
+];
+
+/// The character sets reported by SHOW CHARACTER SET,
+/// (charset, description, default collation, maxlen)
+const CHARACTER_SETS: [(&str, &str, &str, i32); 3] = [
+    ("utf8mb4", "UTF-8 Unicode", "utf8mb4_general_ci", 4),
+    ("utf8", "UTF-8 Unicode", "utf8_general_ci", 3),
+    ("binary", "Binary pseudo charset", "binary", 1),
+];
+
+/// The number of times a delete will be attempted when it keeps hitting write conflicts.
+const MAX_DELETE_ATTEMPTS: usize = 3;
 
 /// Represents a connection to the database.  Note this is the logical connection, not the physical
 /// tcp connection.
@@ -16,6 +54,7 @@ pub struct Connection<'a> {
     pub connection_id: u32,
     pub session: Arc<Session>,
     pub runtime: &'a Runtime,
+    pub(crate) transaction: Mutex<Option<OpenTransaction>>,
 }
 
 impl Drop for Connection<'_> {
@@ -29,7 +68,35 @@ impl Connection<'_> {
         &self,
         query: &str,
     ) -> Result<(Vec<Field>, BoxedExecutor), QueryError> {
+        // SHOW WARNINGS is about the statement before it
+        let previous_warnings = self.session.warnings.read().unwrap().clone();
+        self.session.reset_statement_info();
+        self.session
+            .read_timestamp
+            .store(self.runtime.read_timestamp().ms, Ordering::Relaxed);
+        if self.runtime.read_only() {
+            self.runtime.sync_user_functions();
+        }
+        // Only read only queries make it into the plan cache
+        if let Some(plan) = self.runtime.planner.cached_plan(query, &self.session) {
+            return self.execute_plan(query, plan, true, false);
+        }
         let parse_tree = parse(query)?;
+        let is_query = matches!(parse_tree, Statement::Query(_));
+        let is_delete = is_delete(&parse_tree);
+        if self.runtime.read_only() && is_write(&parse_tree) {
+            return Err(QueryError::ReadOnly);
+        }
+        // Only inserts are run as part of a transaction, anything else that writes (ie DDL)
+        // commits the open transaction first like it would in mysql.
+        if is_write(&parse_tree)
+            && !matches!(
+                parse_tree,
+                Statement::Query(LogicalOperator::TableInsert(_))
+            )
+        {
+            self.commit()?;
+        }
 
         // For almost everything we'll rewrite into some kinda logical operator
         let logical_operator = match parse_tree {
@@ -39,6 +106,7 @@ impl Connection<'_> {
                     .planner
                     .function_registry
                     .list_functions()
+                    .into_iter()
                     .map(|name| vec![Expression::from(name)])
                     .collect();
 
@@ -50,10 +118,209 @@ impl Connection<'_> {
             Statement::ShowDatabases => {
                 return self.execute_statement("SELECT name as database FROM incresql.databases")
             }
-            Statement::ShowTables => {
-                return self.execute_statement(
-                    "SELECT name as table FROM incresql.tables WHERE database_name = database()",
+            Statement::ShowTables(database) => {
+                let database = database.map_or_else(
+                    || "database()".to_string(),
+                    |database| format!("{:#}", Datum::from(database).typed_with(DataType::Text)),
                 );
+                return self.execute_statement(&format!(
+                    "SELECT name as table FROM incresql.tables WHERE database_name = {}",
+                    database
+                ));
+            }
+            Statement::ShowColumns(show_columns) => {
+                let database = show_columns
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                // Fail on unknown tables rather than just returning no rows
+                self.runtime
+                    .planner
+                    .catalog
+                    .read()
+                    .unwrap()
+                    .item(&database, &show_columns.name)?;
+
+                return self.execute_statement(&format!(
+                    "SELECT column_name as field, data_type as type, is_nullable as `null`, \
+                     column_key as `key`, column_default as `default`, \
+                     if(generation_expression IS NULL, \"\", \"STORED GENERATED\") as extra \
+                     FROM incresql.columns \
+                     WHERE database_name = {:#} AND table_name = {:#}",
+                    Datum::from(database).typed_with(DataType::Text),
+                    Datum::from(show_columns.name).typed_with(DataType::Text),
+                ));
+            }
+            Statement::ShowIndex(show_index) => {
+                let database = show_index
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                // Fail on unknown tables rather than just returning no rows
+                self.runtime
+                    .planner
+                    .catalog
+                    .read()
+                    .unwrap()
+                    .item(&database, &show_index.name)?;
+
+                return self.execute_statement(&format!(
+                    "SELECT table_name as table, non_unique, index_name as key_name, seq_in_index, \
+                     column_name, collation, index_table_id FROM incresql.indexes \
+                     WHERE database_name = {:#} AND table_name = {:#}",
+                    Datum::from(database).typed_with(DataType::Text),
+                    Datum::from(show_index.name).typed_with(DataType::Text),
+                ));
+            }
+            Statement::ShowVariables(pattern) => {
+                let mut names: Vec<_> = self
+                    .session
+                    .variables
+                    .read()
+                    .unwrap()
+                    .keys()
+                    // User variables (ie @x) live alongside the system variables
+                    .filter(|name| !name.starts_with('@'))
+                    .filter(|name| pattern.as_ref().map_or(true, |p| like_match(p, name)))
+                    .cloned()
+                    .collect();
+                names.sort();
+                let data = names
+                    .into_iter()
+                    .filter_map(|name| {
+                        let (datum, datatype) = self.session.variable(&name)?;
+                        let value = datum.typed_with(datatype).to_string();
+                        Some(vec![Expression::from(name), Expression::from(value)])
+                    })
+                    .collect();
+
+                LogicalOperator::Values(Values {
+                    fields: vec![
+                        (DataType::Text, String::from("variable_name")),
+                        (DataType::Text, String::from("value")),
+                    ],
+                    data,
+                })
+            }
+            Statement::ShowCollation(pattern) => {
+                let data = COLLATIONS
+                    .iter()
+                    .filter(|(collation, ..)| {
+                        pattern.as_ref().map_or(true, |p| like_match(p, collation))
+                    })
+                    .map(|(collation, charset, id)| {
+                        let default = CHARACTER_SETS
+                            .iter()
+                            .any(|(_, _, default_collation, _)| default_collation == collation);
+                        vec![
+                            Expression::from(*collation),
+                            Expression::from(*charset),
+                            Expression::from(*id),
+                            Expression::from(if default { "Yes" } else { "" }),
+                            Expression::from("Yes"),
+                            Expression::from(1),
+                        ]
+                    })
+                    .collect();
+
+                LogicalOperator::Values(Values {
+                    fields: vec![
+                        (DataType::Text, String::from("collation")),
+                        (DataType::Text, String::from("charset")),
+                        (DataType::Integer, String::from("id")),
+                        (DataType::Text, String::from("default")),
+                        (DataType::Text, String::from("compiled")),
+                        (DataType::Integer, String::from("sortlen")),
+                    ],
+                    data,
+                })
+            }
+            Statement::ShowCharacterSet(pattern) => {
+                let data = CHARACTER_SETS
+                    .iter()
+                    .filter(|(charset, ..)| {
+                        pattern.as_ref().map_or(true, |p| like_match(p, charset))
+                    })
+                    .map(|(charset, description, collation, maxlen)| {
+                        vec![
+                            Expression::from(*charset),
+                            Expression::from(*description),
+                            Expression::from(*collation),
+                            Expression::from(*maxlen),
+                        ]
+                    })
+                    .collect();
+
+                LogicalOperator::Values(Values {
+                    fields: vec![
+                        (DataType::Text, String::from("charset")),
+                        (DataType::Text, String::from("description")),
+                        (DataType::Text, String::from("default collation")),
+                        (DataType::Integer, String::from("maxlen")),
+                    ],
+                    data,
+                })
+            }
+            Statement::ShowProcesslist => {
+                let data = self
+                    .runtime
+                    .sessions()
+                    .iter()
+                    .map(|session| {
+                        let running_statement = session.running_statement.read().unwrap();
+                        let (command, time, info) = match running_statement.as_ref() {
+                            Some((statement, started)) => (
+                                "Query",
+                                started.elapsed().as_secs() as i64,
+                                Expression::from(statement.clone()),
+                            ),
+                            None => (
+                                "Sleep",
+                                0,
+                                Expression::Constant(Datum::Null, DataType::Text),
+                            ),
+                        };
+                        vec![
+                            Expression::from(session.connection_id as i64),
+                            Expression::from(session.user.read().unwrap().clone()),
+                            Expression::from(session.current_database.read().unwrap().clone()),
+                            Expression::from(command),
+                            Expression::from(time),
+                            info,
+                        ]
+                    })
+                    .collect();
+
+                LogicalOperator::Values(Values {
+                    fields: vec![
+                        (DataType::BigInt, String::from("id")),
+                        (DataType::Text, String::from("user")),
+                        (DataType::Text, String::from("db")),
+                        (DataType::Text, String::from("command")),
+                        (DataType::BigInt, String::from("time")),
+                        (DataType::Text, String::from("info")),
+                    ],
+                    data,
+                })
+            }
+            Statement::ShowWarnings => {
+                let data = previous_warnings
+                    .into_iter()
+                    .map(|(code, message)| {
+                        vec![
+                            Expression::from("Warning"),
+                            Expression::from(code as i64),
+                            Expression::from(message),
+                        ]
+                    })
+                    .collect();
+
+                LogicalOperator::Values(Values {
+                    fields: vec![
+                        (DataType::Text, String::from("Level")),
+                        (DataType::BigInt, String::from("Code")),
+                        (DataType::Text, String::from("Message")),
+                    ],
+                    data,
+                })
             }
             Statement::UseDatabase(database) => {
                 *self.session.current_database.write().unwrap() = database;
@@ -65,28 +332,188 @@ impl Connection<'_> {
                     .runtime
                     .planner
                     .plan_common(explain.operator, &self.session)?;
-                self.runtime.planner.explain(&operator)
+                match explain.format {
+                    ExplainFormat::Text => self.runtime.planner.explain(&operator),
+                    ExplainFormat::Dot => self.runtime.planner.explain_dot(&operator),
+                }
             }
             Statement::CreateDatabase(create_database) => {
+                let _lock = self.lock_database(&create_database.name)?;
                 let mut catalog = self.runtime.planner.catalog.write().unwrap();
                 catalog.create_database(&create_database.name)?;
                 return Ok((vec![], empty_tuple_iter()));
             }
             Statement::DropDatabase(database) => {
+                let _lock = self.lock_database(&database)?;
                 let mut catalog = self.runtime.planner.catalog.write().unwrap();
                 catalog.drop_database(&database)?;
                 return Ok((vec![], empty_tuple_iter()));
             }
-            Statement::CreateTable(create_table) => {
+            Statement::CreateUser(create_user) => {
+                self.check_root("CREATE USER")?;
+                let authentication_string =
+                    hash_password(create_user.password.as_deref().unwrap_or_default());
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.create_user(&create_user.name, &authentication_string)?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::DropUser(user) => {
+                self.check_root("CREATE USER")?;
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.drop_user(&user)?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::AlterUser(alter_user) => {
+                let current_user = self.session.user.read().unwrap().clone();
+                let user = alter_user.name.unwrap_or_else(|| current_user.clone());
+                // Anyone can change their own password
+                if user != current_user {
+                    self.check_root("CREATE USER")?;
+                }
+                let authentication_string = hash_password(&alter_user.password);
                 let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.alter_user(&user, &authentication_string)?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::CreateTable(create_table) => {
                 let database = create_table
                     .database
                     .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let _lock = self.lock_table(&database, &create_table.name)?;
 
-                catalog.create_table(&database, &create_table.name, &create_table.columns)?;
+                // Partition bounds need evaluating before we lock the catalog
+                let partition_by = if let Some(partition_by) = create_table.partition_by {
+                    let datatype = create_table
+                        .columns
+                        .iter()
+                        .find(|(alias, _)| alias == &partition_by.column)
+                        .map(|(_, datatype)| *datatype)
+                        .ok_or_else(|| {
+                            CatalogError::InvalidPartitioning(format!(
+                                "Unknown partition column {}",
+                                partition_by.column
+                            ))
+                        })?;
+                    let mut partitions = vec![];
+                    for partition in partition_by.partitions {
+                        let upper_bound = self.partition_bound(partition.upper_bound, datatype)?;
+                        partitions.push((partition.name, upper_bound));
+                    }
+                    Some(catalog::PartitionBy {
+                        method: partition_method(partition_by.method),
+                        column: partition_by.column,
+                        partitions,
+                    })
+                } else {
+                    None
+                };
+
+                // Check the full-text indexes up front so a bad index doesn't leave the table
+                // behind.
+                for (_, column) in &create_table.fulltext_indexes {
+                    if partition_by.is_some() {
+                        return Err(CatalogError::InvalidIndex(
+                            "Full-text indexes are not supported on partitioned tables".to_string(),
+                        )
+                        .into());
+                    }
+                    if !create_table
+                        .columns
+                        .iter()
+                        .any(|(alias, datatype)| alias == column && *datatype == DataType::Text)
+                    {
+                        return Err(CatalogError::InvalidIndex(format!(
+                            "Full-text index column {} must be a TEXT column of the table",
+                            column
+                        ))
+                        .into());
+                    }
+                }
+
+                // The catalog just stores the sql for any defaults, we'll reparse them at insert
+                // time.
+                let column_defaults: Vec<_> = create_table
+                    .column_defaults
+                    .iter()
+                    .map(|default| {
+                        default.as_ref().map(|default| match default {
+                            ColumnDefault::Default(expr) => {
+                                catalog::ColumnDefault::Default(expr.to_string())
+                            }
+                            ColumnDefault::Generated(expr) => {
+                                catalog::ColumnDefault::Generated(expr.to_string())
+                            }
+                        })
+                    })
+                    .collect();
+                self.runtime
+                    .planner
+                    .validate_column_defaults(&create_table.columns, &column_defaults)?;
+
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                if let Some(partition_by) = partition_by {
+                    catalog.create_partitioned_table(
+                        &database,
+                        &create_table.name,
+                        &create_table.columns,
+                        &column_defaults,
+                        &create_table.column_not_null,
+                        &create_table.primary_key,
+                        &partition_by,
+                    )?;
+                } else {
+                    catalog.create_table(
+                        &database,
+                        &create_table.name,
+                        &create_table.columns,
+                        &column_defaults,
+                        &create_table.column_not_null,
+                        &create_table.primary_key,
+                    )?;
+                }
+                for (index_name, column) in &create_table.fulltext_indexes {
+                    catalog.create_fulltext_index(
+                        &database,
+                        &create_table.name,
+                        index_name,
+                        column,
+                    )?;
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::CreateFulltextIndex(create_index) => {
+                let database = create_index
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let _lock = self.lock_table(&database, &create_index.table)?;
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.create_fulltext_index(
+                    &database,
+                    &create_index.table,
+                    &create_index.name,
+                    &create_index.column,
+                )?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::CreateIndex(create_index) => {
+                let database = create_index
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let _lock = self.lock_table(&database, &create_index.table)?;
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.create_index(
+                    &database,
+                    &create_index.table,
+                    &create_index.name,
+                    &create_index.columns,
+                )?;
                 return Ok((vec![], empty_tuple_iter()));
             }
             Statement::CreateView(create_view) => {
+                let current_db = self.session.current_database.read().unwrap().to_string();
+                let database = create_view.database.as_ref().unwrap_or_else(|| &current_db);
+                let _lock = self.lock_table(&database, &create_view.name)?;
+
                 // For now we're just doing this to be helpful by throwing errors now rather than
                 // delaying until we use the view for the first time.
                 let (fields, _operator) = self
@@ -98,19 +525,102 @@ impl Connection<'_> {
                 let columns: Vec<_> = fields.into_iter().map(|f| (f.alias, f.data_type)).collect();
 
                 let mut catalog = self.runtime.planner.catalog.write().unwrap();
-                let current_db = self.session.current_database.read().unwrap().to_string();
-                let database = create_view.database.as_ref().unwrap_or_else(|| &current_db);
+                if !create_view.materialized {
+                    catalog.create_view(
+                        &database,
+                        &create_view.name,
+                        &columns,
+                        &create_view.sql,
+                        &current_db,
+                    )?;
+                    return Ok((vec![], empty_tuple_iter()));
+                }
 
-                catalog.create_view(
+                catalog.create_materialized_view(
                     &database,
                     &create_view.name,
                     &columns,
                     &create_view.sql,
                     &current_db,
                 )?;
+                let view = catalog
+                    .materialized_view(&database, &create_view.name)?
+                    .unwrap();
+                // Populating the view needs to plan its sql, which needs the catalog
+                drop(catalog);
+                if let Err(err) = populate_view(self.runtime, &self.session, &view) {
+                    let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                    catalog.drop_table(&database, &create_view.name)?;
+                    return Err(err);
+                }
                 return Ok((vec![], empty_tuple_iter()));
             }
+            Statement::LoadData(load_data) => {
+                self.check_root("FILE")?;
+                let database = load_data
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let item = self
+                    .runtime
+                    .planner
+                    .catalog
+                    .read()
+                    .unwrap()
+                    .item(&database, &load_data.table)?;
+
+                // Default to loading all the columns that aren't generated
+                let columns = if load_data.columns.is_empty() {
+                    item.columns
+                        .iter()
+                        .zip(item.column_defaults.iter())
+                        .filter(|(_, default)| {
+                            !matches!(default, Some(catalog::ColumnDefault::Generated(_)))
+                        })
+                        .map(|((name, _), _)| name.clone())
+                        .collect()
+                } else {
+                    load_data.columns
+                };
+
+                // Each csv line comes through as a json array of strings, so we just need to
+                // pull out each column and cast it to the right type.
+                let expressions: Vec<_> = columns
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, column)| {
+                        let value = format!("data->>\"$[{}]\"", idx);
+                        match item.columns.iter().find(|(name, _)| name == column) {
+                            Some((_, DataType::Text)) | None => value,
+                            Some((_, datatype)) => format!("CAST({} AS {})", value, datatype),
+                        }
+                    })
+                    .collect();
+                let column_list: Vec<_> = columns
+                    .iter()
+                    .map(|column| format!("`{}`", column))
+                    .collect();
+
+                let sql = format!(
+                    "INSERT INTO `{}`.`{}` ({}) SELECT {} FROM directory {:#} with(delimiter={:#})",
+                    database,
+                    load_data.table,
+                    column_list.join(", "),
+                    expressions.join(", "),
+                    Datum::from(load_data.path).typed_with(DataType::Text),
+                    Datum::from((load_data.serde_options.delimiter as char).to_string())
+                        .typed_with(DataType::Text),
+                );
+                if let Statement::Query(LogicalOperator::TableInsert(mut insert)) = parse(&sql)? {
+                    insert.bulk_load = Some(BulkLoad {
+                        sync: load_data.sync,
+                    });
+                    LogicalOperator::TableInsert(insert)
+                } else {
+                    panic!("Bulk loads should always be planned as an insert")
+                }
+            }
             Statement::CompactTable(compact_table) => {
+                let _permit = self.runtime.scheduler.admit(WorkloadClass::Maintenance);
                 let database = compact_table
                     .database
                     .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
@@ -119,40 +629,756 @@ impl Connection<'_> {
                     let catalog = self.runtime.planner.catalog.read().unwrap();
                     catalog.item(&database, &compact_table.name)?
                 };
-                if let TableOrView::Table(table) = item.item {
+                let table_name = &compact_table.name;
+                let partitions = item.partitioning.map_or(vec![], |p| p.partitions);
+                if let Some(partition_name) = compact_table.partition {
+                    let partition = partitions
+                        .into_iter()
+                        .find(|partition| partition.name == partition_name)
+                        .ok_or_else(|| {
+                            CatalogError::PartitionNotFound(
+                                database.clone(),
+                                table_name.clone(),
+                                partition_name.clone(),
+                            )
+                        })?;
+                    partition.table.force_rocks_compaction();
+                } else if let TableOrView::Table(table) = item.item {
                     table.force_rocks_compaction();
+                    for partition in partitions {
+                        partition.table.force_rocks_compaction();
+                    }
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::AlterTable(alter_table) => {
+                let database = alter_table
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+
+                let table_name = alter_table.name;
+                let _lock = self.lock_table(&database, &table_name)?;
+                match alter_table.action {
+                    AlterTableAction::AddPartition(partition) => {
+                        let datatype = {
+                            let catalog = self.runtime.planner.catalog.read().unwrap();
+                            let item = catalog.item(&database, &table_name)?;
+                            let partitioning = item.partitioning.ok_or_else(|| {
+                                CatalogError::InvalidPartitioning(format!(
+                                    "Table {}.{} is not range partitioned",
+                                    database, table_name
+                                ))
+                            })?;
+                            item.columns[partitioning.column].1
+                        };
+                        let upper_bound = self.partition_bound(partition.upper_bound, datatype)?;
+                        let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                        catalog.add_partition(
+                            &database,
+                            &table_name,
+                            &partition.name,
+                            upper_bound,
+                        )?;
+                    }
+                    AlterTableAction::DropPartition(partition_name) => {
+                        let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                        catalog.drop_partition(&database, &table_name, &partition_name)?;
+                    }
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::AnalyzeTable(analyze_table) => {
+                let database = analyze_table
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let _lock = self.lock_table(&database, &analyze_table.name)?;
+                let _permit = self.runtime.scheduler.admit(WorkloadClass::Maintenance);
+
+                let item = {
+                    let catalog = self.runtime.planner.catalog.read().unwrap();
+                    catalog.item(&database, &analyze_table.name)?
+                };
+                if let TableOrView::Table(table) = item.item {
+                    let mut builders: Vec<_> = item
+                        .columns
+                        .iter()
+                        .map(|_| ColumnStatisticsBuilder::default())
+                        .collect();
+                    // The data for partitioned tables lives in the partitions
+                    let tables = if let Some(partitioning) = item.partitioning {
+                        partitioning
+                            .partitions
+                            .into_iter()
+                            .map(|p| p.table)
+                            .collect()
+                    } else {
+                        vec![table.clone()]
+                    };
+                    for scan_table in &tables {
+                        let mut iter = scan_table.full_scan(LogicalTimestamp::MAX);
+                        while let Some((tuple, freq)) = iter.next().map_err(ExecutionError::from)? {
+                            for (builder, datum) in builders.iter_mut().zip(tuple) {
+                                builder.add(datum, freq);
+                            }
+                        }
+                    }
+                    let statistics: Vec<_> = item
+                        .columns
+                        .into_iter()
+                        .zip(builders)
+                        .map(|((column_name, datatype), builder)| {
+                            (column_name, datatype, builder.build())
+                        })
+                        .collect();
+
+                    let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                    catalog.set_column_statistics(table.id(), &statistics)?;
                 }
                 return Ok((vec![], empty_tuple_iter()));
             }
             Statement::DropTable(drop_table) => {
-                let mut catalog = self.runtime.planner.catalog.write().unwrap();
                 let database = drop_table
                     .database
                     .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let _lock = self.lock_table(&database, &drop_table.name)?;
 
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
                 catalog.drop_table(&database, &drop_table.name)?;
                 return Ok((vec![], empty_tuple_iter()));
             }
+            Statement::Transaction(transaction) => {
+                self.execute_transaction(transaction)?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::Savepoint(savepoint) => {
+                self.execute_savepoint(savepoint)?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::Kill(kill) => {
+                let killed = if kill.query_only {
+                    self.runtime.kill_query(kill.connection_id)
+                } else {
+                    self.runtime.kill_connection(kill.connection_id)
+                };
+                if !killed {
+                    return Err(QueryError::UnknownConnection(kill.connection_id));
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::CreateExternalTable(create_external) => {
+                self.check_root("FILE")?;
+                let database = create_external
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let _lock = self.lock_table(&database, &create_external.name)?;
+                // The partition columns come after the columns read from the files
+                let partition_columns = create_external.partition_columns.len();
+                let mut columns = create_external.columns;
+                columns.extend(create_external.partition_columns);
+                // Every field is read in as text so there has to be a way to cast it
+                for (_, datatype) in &columns {
+                    if *datatype != DataType::Text {
+                        let lookup_sig = FunctionSignature {
+                            name: datatype.cast_function(),
+                            args: vec![DataType::Text],
+                            ret: *datatype,
+                        };
+                        self.runtime
+                            .planner
+                            .function_registry
+                            .resolve_function(&lookup_sig)
+                            .map_err(PlannerError::from)?;
+                    }
+                }
+                let external_table = ExternalTable {
+                    location: create_external.location,
+                    delimiter: create_external.delimiter,
+                    header: create_external.header,
+                    partition_columns,
+                };
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.create_external_table(
+                    &database,
+                    &create_external.name,
+                    &columns,
+                    &external_table,
+                )?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::CreateSink(create_sink) => {
+                self.check_root("FILE")?;
+                let current_database = self.session.current_database.read().unwrap().to_string();
+                let sink = Sink {
+                    database: create_sink
+                        .database
+                        .unwrap_or_else(|| current_database.clone()),
+                    name: create_sink.name,
+                    source_database: create_sink.source_database.unwrap_or(current_database),
+                    source_name: create_sink.source,
+                    path: create_sink.path,
+                    format: sink_format(create_sink.format),
+                };
+                self.runtime
+                    .planner
+                    .catalog
+                    .write()
+                    .unwrap()
+                    .create_sink(&sink)?;
+                if let Err(err) = self.runtime.start_sink(&sink) {
+                    // Don't leave a sink behind that isn't exporting anything
+                    let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                    catalog.drop_sink(&sink.database, &sink.name)?;
+                    return Err(err);
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::DropSink(drop_sink) => {
+                let database = drop_sink
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.drop_sink(&database, &drop_sink.name)?;
+                self.runtime.stop_sink(&database, &drop_sink.name);
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::CreateFunction(create_function) => {
+                let body = match create_function.body {
+                    ast::statement::FunctionBody::Wasm(module) => {
+                        let module = self.evaluate_expression(Expression::Cast(Cast {
+                            expr: Box::new(module),
+                            datatype: DataType::ByteA,
+                            try_cast: false,
+                        }))?;
+                        if module.is_null() {
+                            return Err(UdfError::InvalidModule(
+                                "the module can not be null".to_string(),
+                            )
+                            .into());
+                        }
+                        FunctionBody::Wasm(module.as_bytea().to_vec())
+                    }
+                    ast::statement::FunctionBody::Sql(sql) => FunctionBody::Sql(sql),
+                };
+                let function = UserFunction {
+                    name: create_function.name,
+                    args: create_function.args,
+                    ret: create_function.returns,
+                    body,
+                };
+                let registry = &self.runtime.planner.function_registry;
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                register_user_function(registry, &function)?;
+                if let Err(err) = catalog.create_function(&function) {
+                    registry.unregister_function(&function.name);
+                    return Err(err.into());
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::DropFunction(name) => {
+                let mut catalog = self.runtime.planner.catalog.write().unwrap();
+                catalog.drop_function(&name)?;
+                self.runtime
+                    .planner
+                    .function_registry
+                    .unregister_function(&name);
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::Tail(tail) => {
+                let database = tail
+                    .database
+                    .unwrap_or_else(|| self.session.current_database.read().unwrap().to_string());
+                let (fields, subscription) = self.runtime.subscribe_to_table(
+                    Arc::clone(&self.session),
+                    database,
+                    tail.name,
+                )?;
+                return Ok((fields, Box::from(subscription)));
+            }
+            Statement::Subscribe(query) => {
+                let (fields, subscription) = self
+                    .runtime
+                    .subscribe_to_query(Arc::clone(&self.session), query)?;
+                return Ok((fields, Box::from(subscription)));
+            }
+            Statement::SelectIntoOutfile(outfile) => {
+                self.check_root("FILE")?;
+                self.select_into_outfile(outfile)?;
+                return Ok((vec![], empty_tuple_iter()));
+            }
+            Statement::SetVariables(assignments) => {
+                for SetVariable { name, value } in assignments {
+                    let value = match value {
+                        // Bare words, ie SET NAMES utf8mb4 are treated as strings
+                        Expression::ColumnReference(ColumnReference {
+                            qualifier: None,
+                            alias,
+                            ..
+                        }) if !alias.starts_with('@') && !name.starts_with('@') => {
+                            Datum::from(alias)
+                        }
+                        expression if name.starts_with('@') => {
+                            self.evaluate_user_variable(expression)?
+                        }
+                        expression => self.evaluate_expression(expression)?,
+                    };
+                    if name == "time_zone" {
+                        let time_zone = value.as_maybe_text().unwrap_or_default();
+                        if parse_time_zone(time_zone).is_none() {
+                            return Err(QueryError::UnknownTimeZone(time_zone.to_string()));
+                        }
+                    }
+                    self.session.variables.write().unwrap().insert(name, value);
+                }
+                return Ok((vec![], empty_tuple_iter()));
+            }
+        };
+
+        let planner = &self.runtime.planner;
+        let plan = if is_query {
+            planner.plan_for_point_in_time_cached(query, logical_operator, &self.session)?
+        } else {
+            planner.plan_for_point_in_time(logical_operator, &self.session)?
         };
+        self.execute_plan(query, plan, is_query, is_delete)
+    }
 
+    /// Runs the planned statement, the sql is that of the statement for the query cache.
+    fn execute_plan(
+        &self,
+        query: &str,
+        plan: PointInTimePlan,
+        is_query: bool,
+        is_delete: bool,
+    ) -> Result<(Vec<Field>, BoxedExecutor), QueryError> {
+        // The permit is held until the results have been read
+        let permit = self.runtime.scheduler.admit(WorkloadClass::Interactive);
+
+        if let PointInTimeOperator::TableInsert(insert) = &plan.operator {
+            self.take_snapshot();
+            let views = self
+                .runtime
+                .planner
+                .catalog
+                .read()
+                .unwrap()
+                .materialized_views()?;
+            if let Some(view) = views
+                .iter()
+                .find(|view| view.table.id() == insert.table.id())
+            {
+                return Err(QueryError::MaterializedViewWrite(
+                    view.database.clone(),
+                    view.name.clone(),
+                ));
+            }
+            // Views are maintained when the transaction is committed
+            if self.execute_transactional_insert(insert)? {
+                return Ok((plan.fields, empty_tuple_iter()));
+            }
+            if !views.is_empty() {
+                self.execute_maintained_insert(insert, is_delete)?;
+                return Ok((plan.fields, empty_tuple_iter()));
+            }
+        }
+
+        // Deletes are run straight away so that they can be transparently retried on conflict,
+        // rerunning a delete is safe as anything already deleted won't match the second time.
+        if is_delete {
+            let mut attempt = 1;
+            let mut executor = build_executor(&self.session, &plan.operator);
+            loop {
+                match executor.next() {
+                    Ok(_) => break,
+                    Err(err) if err.is_write_conflict() && attempt < MAX_DELETE_ATTEMPTS => {}
+                    Err(err) => return Err(err.into()),
+                }
+                attempt += 1;
+                self.take_snapshot();
+                executor = build_executor(&self.session, &plan.operator);
+            }
+            return Ok((plan.fields, empty_tuple_iter()));
+        }
+
+        if is_query && self.query_cache_enabled() {
+            if let Some(versions) = table_versions(&plan.operator) {
+                let cache = &self.runtime.query_cache;
+                let sql = normalize_sql(query);
+                if let Some(executor) = cache.get(&sql, &plan.operator, &plan.fields, &versions) {
+                    return Ok((plan.fields, permitted(executor, permit)));
+                }
+                let executor = build_executor(&self.session, &plan.operator);
+                let fields = plan.fields.clone();
+                let executor =
+                    cache.populate(sql, plan.operator, plan.fields, versions, executor)?;
+                return Ok((fields, permitted(executor, permit)));
+            }
+        }
+
+        let executor = build_executor(&self.session, &plan.operator);
+        Ok((plan.fields, permitted(executor, permit)))
+    }
+
+    /// Runs an insert when there are materialized views that may need maintaining.
+    /// Where possible the rows to write are read up front so the same rows can then be fed
+    /// through the views rather than having to recompute them.
+    fn execute_maintained_insert(
+        &self,
+        insert: &TableInsert,
+        is_delete: bool,
+    ) -> Result<(), QueryError> {
+        let tables = written_tables(insert);
+        // What gets deleted by delete_matching depends on what's in the table, and rows for
+        // partitioned tables get split up across the partitions so neither can be captured.
+        let capture_rows = !insert.delete_matching && insert.partitioning.is_none();
+
+        let mut attempt = 1;
+        let delta = loop {
+            let mut insert = insert.clone();
+            let delta = if capture_rows {
+                let delta = materialized_views::execute(&self.session, &insert.source)?;
+                insert.source = Box::new(delta_operator(&delta));
+                Some(delta)
+            } else {
+                None
+            };
+            let mut executor =
+                build_executor(&self.session, &PointInTimeOperator::TableInsert(insert));
+            match executor.next() {
+                Ok(_) => break delta,
+                Err(err)
+                    if is_delete && err.is_write_conflict() && attempt < MAX_DELETE_ATTEMPTS => {}
+                Err(err) => return Err(err.into()),
+            }
+            attempt += 1;
+            self.take_snapshot();
+        };
+
+        // The views need to see the rows just written
+        self.session
+            .read_timestamp
+            .store(self.runtime.read_timestamp().ms, Ordering::Relaxed);
+        maintain_views(self.runtime, &self.session, TableChange { tables, delta })
+    }
+
+    /// Writes read as of a snapshot taken as they're planned, so that anything written after
+    /// the rows they're based on were read can be detected as a write conflict.
+    fn take_snapshot(&self) {
+        self.session
+            .read_timestamp
+            .store(LogicalTimestamp::snapshot().ms, Ordering::Relaxed);
+    }
+
+    /// The query cache is opt in per session, via SET query_cache_type = ON
+    fn query_cache_enabled(&self) -> bool {
+        match self
+            .session
+            .variables
+            .read()
+            .unwrap()
+            .get("query_cache_type")
+        {
+            Some(Datum::Null) | None => false,
+            Some(datum) => {
+                datum.as_maybe_integer() == Some(1)
+                    || datum
+                        .as_maybe_text()
+                        .map_or(false, |value| value.eq_ignore_ascii_case("ON"))
+            }
+        }
+    }
+
+    /// Evaluates a standalone expression, ie the value in a SET statement.
+    fn evaluate_expression(&self, expression: Expression) -> Result<Datum<'static>, QueryError> {
+        self.evaluate_typed_expression(expression)
+            .map(|(datum, _datatype)| datum)
+    }
+
+    /// Evaluates the value to store in a user variable. The variable's type gets inferred from
+    /// the value when it's used, so values of types that can't be told apart by their datum
+    /// alone (ie dates) are stored as text.
+    fn evaluate_user_variable(&self, expression: Expression) -> Result<Datum<'static>, QueryError> {
+        let (datum, datatype) = self.evaluate_typed_expression(expression)?;
+        Ok(match datatype {
+            DataType::Null
+            | DataType::Boolean
+            | DataType::Decimal(..)
+            | DataType::Double
+            | DataType::Text => datum,
+            _ if datatype.integer_range().is_some() => datum,
+            _ if datum.is_null() => datum,
+            _ => Datum::from(datum.typed_with(datatype).to_string()),
+        })
+    }
+
+    fn evaluate_typed_expression(
+        &self,
+        expression: Expression,
+    ) -> Result<(Datum<'static>, DataType), QueryError> {
+        let logical_operator = LogicalOperator::Project(Project {
+            distinct: false,
+            expressions: vec![NamedExpression {
+                alias: None,
+                expression,
+            }],
+            source: Box::new(LogicalOperator::Single),
+        });
         let plan = self
             .runtime
             .planner
             .plan_for_point_in_time(logical_operator, &self.session)?;
-        let executor = build_executor(&self.session, &plan.operator);
-        Ok((plan.fields, executor))
+        let mut executor = build_executor(&self.session, &plan.operator);
+        let datum = executor
+            .next()?
+            .map(|(tuple, _freq)| tuple[0].as_static())
+            .unwrap_or(Datum::Null);
+        Ok((datum, plan.fields[0].data_type))
+    }
+
+    /// Takes out a DDL lock on a table, held until the returned lock is dropped.
+    fn lock_table(&self, database: &str, table: &str) -> Result<ObjectLock, QueryError> {
+        let catalog = self.runtime.planner.catalog.read().unwrap();
+        Ok(catalog.lock_table(database, table)?)
+    }
+
+    /// Takes out a DDL lock on a database, held until the returned lock is dropped.
+    fn lock_database(&self, database: &str) -> Result<ObjectLock, QueryError> {
+        let catalog = self.runtime.planner.catalog.read().unwrap();
+        Ok(catalog.lock_database(database)?)
+    }
+
+    /// Evaluates the upper bound of a range partition, casting it to the type of the partition
+    /// column.
+    fn partition_bound(
+        &self,
+        upper_bound: Option<Expression>,
+        datatype: DataType,
+    ) -> Result<Option<Datum<'static>>, QueryError> {
+        if let Some(expr) = upper_bound {
+            let datum = self.evaluate_expression(Expression::Cast(Cast {
+                expr: Box::new(expr),
+                datatype,
+                try_cast: false,
+            }))?;
+            if datum.is_null() {
+                return Err(CatalogError::InvalidPartitioning(
+                    "Partition bounds can not be null".to_string(),
+                )
+                .into());
+            }
+            Ok(Some(datum))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Executes sql that may contain several statements separated by semicolons.
+    /// The statements are executed one at a time as the iterator is advanced, so the results of
+    /// each statement should be consumed before moving onto the next.
+    pub fn execute_statements<'b>(
+        &'b self,
+        query: &'b str,
+    ) -> impl Iterator<Item = Result<(Vec<Field>, BoxedExecutor), QueryError>> + 'b {
+        let mut statements = split_statements(query);
+        // Let the parser report on sql with no statements in it
+        if statements.is_empty() {
+            statements.push(query);
+        }
+        statements
+            .into_iter()
+            .map(move |statement| self.execute_statement(statement))
+    }
+
+    /// The fields of a table (or view) in the current database whose names match the LIKE style
+    /// wildcard, an empty wildcard matching all of them. Used for mysql's COM_FIELD_LIST.
+    pub fn table_fields(&self, table: &str, wildcard: &str) -> Result<Vec<Field>, QueryError> {
+        let database = self.session.current_database.read().unwrap().to_string();
+        let table = table.to_lowercase();
+        // Fail on unknown tables with a catalog error rather than from deep in the planner
+        self.runtime
+            .planner
+            .catalog
+            .read()
+            .unwrap()
+            .item(&database, &table)?;
+        let plan = self
+            .runtime
+            .planner
+            .plan_for_point_in_time(select_star(database, table), &self.session)?;
+        Ok(plan
+            .fields
+            .into_iter()
+            .filter(|field| wildcard.is_empty() || like_match(wildcard, &field.alias))
+            .collect())
+    }
+
+    /// Logs the connection in as the given user, this fails if the user is already at their
+    /// max_user_connections.
+    pub fn login(&self, user: String) -> Result<(), QueryError> {
+        self.runtime.login(&self.session, user)
+    }
+
+    /// Fails unless the connection is logged in as root.
+    fn check_root(&self, privilege: &'static str) -> Result<(), QueryError> {
+        if self.session.is_root() {
+            Ok(())
+        } else {
+            Err(QueryError::AccessDenied(privilege))
+        }
     }
 
     pub fn change_database(&self, database: &str) -> Result<(), QueryError> {
         *self.session.current_database.write().unwrap() = String::from(database);
         Ok(())
     }
+
+    /// Prepares a statement containing ? placeholders, for queries the result fields are also
+    /// worked out so they can be sent to the client ahead of the first execution.
+    pub fn prepare_statement(&self, query: &str) -> Result<PreparedStatement, QueryError> {
+        let parameter_count = parameter_offsets(query).len();
+        // Zero is a valid literal anywhere a parameter can appear (ie LIMIT ?) so we stand it in
+        // for the parameters to check the statement parses.
+        let placeholders = vec![Expression::from(0); parameter_count];
+        let placeholder_query = bind_parameters(query, &placeholders)?;
+        let parse_tree = parse(&placeholder_query)?;
+
+        // The types of the placeholders won't always line up with the real parameters, in which
+        // case we'll just leave the client to pick up the fields when executing.
+        let fields = match parse_tree {
+            Statement::Query(LogicalOperator::TableInsert(_)) => vec![],
+            Statement::Query(_) => self
+                .execute_statement(&placeholder_query)
+                .map(|(fields, _)| fields)
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+
+        Ok(PreparedStatement {
+            sql: query.to_string(),
+            parameter_count,
+            fields,
+        })
+    }
+
+    /// Executes a prepared statement, the parameters are bound in as literals.
+    pub fn execute_prepared_statement(
+        &self,
+        statement: &PreparedStatement,
+        parameters: &[(Datum<'static>, DataType)],
+    ) -> Result<(Vec<Field>, BoxedExecutor), QueryError> {
+        let parameters: Vec<_> = parameters
+            .iter()
+            .map(|(datum, datatype)| parameter_expression(datum.clone(), *datatype))
+            .collect();
+        let query = bind_parameters(&statement.sql, &parameters)?;
+        self.execute_statement(&query)
+    }
+}
+
+/// A statement prepared via the binary protocol.
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    pub sql: String,
+    pub parameter_count: usize,
+    pub fields: Vec<Field>,
+}
+
+/// Turns a parameter value into a literal, dates, timestamps and doubles don't have a literal
+/// syntax of their own so go through a cast from text.
+fn parameter_expression(datum: Datum<'static>, datatype: DataType) -> Expression {
+    match datatype {
+        DataType::Date | DataType::Timestamp | DataType::Double if !datum.is_null() => {
+            Expression::Cast(Cast {
+                expr: Box::new(Expression::from(datum.typed_with(datatype).to_string())),
+                datatype,
+                try_cast: false,
+            })
+        }
+        _ => Expression::Constant(datum, datatype),
+    }
+}
+
+/// Delete statements, ie inserts of retractions
+fn is_delete(statement: &Statement) -> bool {
+    if let Statement::Query(LogicalOperator::TableInsert(insert)) = statement {
+        insert.is_delete()
+    } else {
+        false
+    }
+}
+
+/// Statements that modify the data or catalog, these can't be run on followers.
+fn is_write(statement: &Statement) -> bool {
+    matches!(
+        statement,
+        Statement::Query(LogicalOperator::TableInsert(_))
+            | Statement::CreateDatabase(_)
+            | Statement::DropDatabase(_)
+            | Statement::CreateUser(_)
+            | Statement::DropUser(_)
+            | Statement::AlterUser(_)
+            | Statement::LoadData(_)
+            | Statement::CreateTable(_)
+            | Statement::CreateView(_)
+            | Statement::CreateFulltextIndex(_)
+            | Statement::CreateIndex(_)
+            | Statement::CreateSink(_)
+            | Statement::CreateExternalTable(_)
+            | Statement::DropSink(_)
+            | Statement::CreateFunction(_)
+            | Statement::DropFunction(_)
+            | Statement::AnalyzeTable(_)
+            | Statement::CompactTable(_)
+            | Statement::AlterTable(_)
+            | Statement::DropTable(_)
+    )
+}
+
+/// Holds onto the permit until the executor is done with.
+fn permitted(executor: BoxedExecutor, permit: WorkloadPermit) -> BoxedExecutor {
+    Box::from(PermittedExecutor::new(executor, permit))
+}
+
+fn partition_method(method: PartitionMethod) -> catalog::PartitionMethod {
+    match method {
+        PartitionMethod::Range => catalog::PartitionMethod::Range,
+        PartitionMethod::Hash => catalog::PartitionMethod::Hash,
+    }
+}
+
+fn sink_format(format: SinkFormat) -> catalog::SinkFormat {
+    match format {
+        SinkFormat::Json => catalog::SinkFormat::Json,
+        SinkFormat::Csv => catalog::SinkFormat::Csv,
+    }
+}
+
+/// Case insensitive matching of a sql LIKE pattern, ie "character_set_%"
+fn like_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<_> = pattern.to_lowercase().chars().collect();
+    let value: Vec<_> = value.to_lowercase().chars().collect();
+
+    fn matches(pattern: &[char], value: &[char]) -> bool {
+        match pattern.split_first() {
+            None => value.is_empty(),
+            Some(('%', rest)) => (0..=value.len()).any(|idx| matches(rest, &value[idx..])),
+            Some(('\\', rest)) if !rest.is_empty() => {
+                value.first() == rest.first() && matches(&rest[1..], &value[1..])
+            }
+            Some((c, rest)) => match value.split_first() {
+                Some((v, value_rest)) => (*c == '_' || c == v) && matches(rest, value_rest),
+                None => false,
+            },
+        }
+    }
+    matches(&pattern, &value)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::FieldOrigin;
     use data::{DataType, Datum};
+    use std::sync::atomic::Ordering;
 
     #[test]
     fn test_execute_statement() -> Result<(), QueryError> {
@@ -164,13 +1390,36 @@ mod tests {
             vec![Field {
                 qualifier: None,
                 alias: "_col1".to_string(),
-                data_type: DataType::Integer
+                data_type: DataType::Integer,
+                nullable: false,
+                origin: None,
             }]
         );
         assert_eq!(executor.next()?, Some(([Datum::from(1)].as_ref(), 1)));
         Ok(())
     }
 
+    #[test]
+    fn test_execute_statements() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let mut results = connection.execute_statements("SET autocommit = 2; SELECT @@autocommit;");
+
+        let (fields, mut executor) = results.next().unwrap()?;
+        assert!(fields.is_empty());
+        while executor.next()?.is_some() {}
+
+        let (_fields, mut executor) = results.next().unwrap()?;
+        assert_eq!(executor.next()?, Some(([Datum::from(2)].as_ref(), 1)));
+        assert!(results.next().is_none());
+
+        assert!(matches!(
+            connection.execute_statements(" ; ").next(),
+            Some(Err(QueryError::ParseError(_)))
+        ));
+        Ok(())
+    }
+
     #[test]
     fn test_execute_statement_rewrite() -> Result<(), QueryError> {
         let runtime = Runtime::new_for_test();
@@ -181,12 +1430,263 @@ mod tests {
             vec![Field {
                 qualifier: None,
                 alias: "function_name".to_string(),
-                data_type: DataType::Text
+                data_type: DataType::Text,
+                nullable: false,
+                origin: None,
             }]
         );
         Ok(())
     }
 
+    #[test]
+    fn test_read_only_follower() -> Result<(), QueryError> {
+        let leader = Runtime::new_for_test();
+        let follower = Runtime::new_for_test();
+        follower.set_read_only(true);
+        leader.replication().enable();
+        let replication = follower.replication();
+        let receiver = leader
+            .replication()
+            .subscribe(|batch| replication.apply(&batch))?;
+
+        let connection = leader.new_connection();
+        connection.execute_statement("create table t (a int)")?;
+        connection.execute_statement(
+            "create function plus_one(x bigint) returns bigint language sql as 'x + 1'",
+        )?;
+        let (_fields, mut executor) =
+            connection.execute_statement("insert into t values (1), (2)")?;
+        assert_eq!(executor.next()?, None);
+
+        while let Ok(batch) = receiver.try_recv() {
+            replication.apply(&batch)?;
+        }
+
+        let connection = follower.new_connection();
+        let (_fields, mut executor) = connection.execute_statement("select a from t")?;
+        assert_eq!(executor.next()?, Some(([Datum::from(1)].as_ref(), 1)));
+        assert_eq!(executor.next()?, Some(([Datum::from(2)].as_ref(), 1)));
+        assert_eq!(executor.next()?, None);
+
+        let (_fields, mut executor) = connection.execute_statement("select plus_one(1)")?;
+        assert_eq!(executor.next()?, Some(([Datum::from(2_i64)].as_ref(), 1)));
+
+        assert!(matches!(
+            connection.execute_statement("insert into t values (3)"),
+            Err(QueryError::ReadOnly)
+        ));
+        assert!(matches!(
+            connection.execute_statement("compact table t"),
+            Err(QueryError::ReadOnly)
+        ));
+        assert!(leader.replication_lag().is_none());
+        assert!(follower.replication_lag().is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_statement_affected_rows() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        connection.execute_statement("create table t (a int)")?;
+        let (_fields, mut executor) =
+            connection.execute_statement("insert into t values (1), (2), (3)")?;
+        assert_eq!(executor.next()?, None);
+        assert_eq!(connection.session.affected_rows.load(Ordering::Relaxed), 3);
+
+        assert_eq!(
+            *connection.session.info.read().unwrap(),
+            "Records: 3  Duplicates: 0  Warnings: 0"
+        );
+
+        let (_fields, mut executor) = connection.execute_statement("delete from t where a > 1")?;
+        assert_eq!(executor.next()?, None);
+        assert_eq!(connection.session.affected_rows.load(Ordering::Relaxed), 2);
+        assert_eq!(*connection.session.info.read().unwrap(), "");
+
+        let (_fields, mut executor) = connection.execute_statement("insert into t values (4)")?;
+        assert_eq!(executor.next()?, None);
+        assert_eq!(connection.session.affected_rows.load(Ordering::Relaxed), 1);
+        assert_eq!(*connection.session.info.read().unwrap(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_statement_set() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let (fields, _executor) = connection.execute_statement("SET NAMES latin1")?;
+        assert!(fields.is_empty());
+        connection.execute_statement("SET autocommit = 1 + 1")?;
+        let variables = connection.session.variables.read().unwrap();
+        assert_eq!(variables["character_set_client"], Datum::from("latin1"));
+        assert_eq!(variables["character_set_results"], Datum::from("latin1"));
+        assert_eq!(variables["autocommit"], Datum::from(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_statement_ddl_locked() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        connection.execute_statement("create table t (a int)")?;
+
+        // Simulate another connection part way through altering the table
+        let lock = runtime
+            .planner
+            .catalog
+            .read()
+            .unwrap()
+            .lock_table("default", "t")?;
+        assert!(matches!(
+            connection.execute_statement("drop table t"),
+            Err(QueryError::CatalogError(CatalogError::ObjectLocked(_)))
+        ));
+        assert!(matches!(
+            connection.execute_statement("create table t (a int)"),
+            Err(QueryError::CatalogError(CatalogError::ObjectLocked(_)))
+        ));
+        // Queries aren't blocked
+        connection.execute_statement("select * from t")?;
+
+        drop(lock);
+        connection.execute_statement("drop table t")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_statement_savepoint() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let (fields, _executor) = connection.execute_statement("SAVEPOINT s1")?;
+        assert!(fields.is_empty());
+        assert!(matches!(
+            connection.execute_statement("ROLLBACK TO SAVEPOINT s1"),
+            Err(QueryError::SavepointNotFound(_))
+        ));
+        assert!(matches!(
+            connection.execute_statement("RELEASE SAVEPOINT s1"),
+            Err(QueryError::SavepointNotFound(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_execute_statement_transaction() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let rows = || -> Result<Vec<Datum<'static>>, QueryError> {
+            let (_fields, mut executor) =
+                connection.execute_statement("select a from t order by a")?;
+            let mut rows = vec![];
+            while let Some((tuple, _freq)) = executor.next()? {
+                rows.push(tuple[0].as_static());
+            }
+            Ok(rows)
+        };
+        connection.execute_statement("create table t (a int)")?;
+
+        connection.execute_statement("BEGIN")?;
+        connection.execute_statement("insert into t values (1)")?;
+        connection.execute_statement("SAVEPOINT s1")?;
+        connection.execute_statement("insert into t values (2)")?;
+        // Nothing's visible until commit
+        assert_eq!(rows()?, vec![]);
+        connection.execute_statement("ROLLBACK TO SAVEPOINT s1")?;
+        connection.execute_statement("insert into t values (3)")?;
+        connection.execute_statement("COMMIT")?;
+        assert_eq!(rows()?, vec![Datum::from(1), Datum::from(3)]);
+
+        connection.execute_statement("START TRANSACTION")?;
+        connection.execute_statement("insert into t values (4)")?;
+        connection.execute_statement("SAVEPOINT s1")?;
+        connection.execute_statement("RELEASE SAVEPOINT s1")?;
+        assert!(matches!(
+            connection.execute_statement("ROLLBACK TO s1"),
+            Err(QueryError::SavepointNotFound(_))
+        ));
+        connection.execute_statement("ROLLBACK")?;
+        assert_eq!(rows()?, vec![Datum::from(1), Datum::from(3)]);
+
+        // DDL commits the open transaction
+        connection.execute_statement("BEGIN")?;
+        connection.execute_statement("insert into t values (5)")?;
+        connection.execute_statement("create table t2 (a int)")?;
+        connection.execute_statement("ROLLBACK")?;
+        assert_eq!(
+            rows()?,
+            vec![Datum::from(1), Datum::from(3), Datum::from(5)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_like_match() {
+        assert!(like_match("sql_mode", "SQL_MODE"));
+        assert!(like_match("character_set_%", "character_set_client"));
+        assert!(like_match("%_mode", "sql_mode"));
+        assert!(like_match("utf8_", "utf8a"));
+        assert!(!like_match("utf8_", "utf8"));
+        assert!(!like_match("character\\_set%", "characterXset_client"));
+    }
+
+    #[test]
+    fn test_execute_statement_show_variables() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let (_fields, mut executor) =
+            connection.execute_statement("SHOW VARIABLES LIKE 'version_comment'")?;
+        assert_eq!(
+            executor.next()?,
+            Some((
+                [Datum::from("version_comment"), Datum::from("incresql")].as_ref(),
+                1
+            ))
+        );
+        assert_eq!(executor.next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_fields() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        connection.execute_statement("create table t (a int, ab text, b bigint)")?;
+        let names = |wildcard: &str| -> Result<Vec<String>, QueryError> {
+            Ok(connection
+                .table_fields("T", wildcard)?
+                .into_iter()
+                .map(|field| field.alias)
+                .collect())
+        };
+        assert_eq!(names("")?, vec!["a", "ab", "b"]);
+        assert_eq!(names("a%")?, vec!["a", "ab"]);
+        assert!(connection
+            .table_fields("missing", "")
+            .unwrap_err()
+            .is_table_not_found());
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_origins() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        connection.execute_statement("create table t (a int, b int)")?;
+        let (fields, _executor) = connection.execute_statement("select a, a + b from t as x")?;
+        assert_eq!(
+            fields[0].origin,
+            Some(FieldOrigin {
+                database: "default".to_string(),
+                table_alias: "x".to_string(),
+                table: "t".to_string(),
+                column: "a".to_string(),
+            })
+        );
+        assert_eq!(fields[1].origin, None);
+        Ok(())
+    }
+
     #[test]
     fn test_change_database() -> Result<(), QueryError> {
         let runtime = Runtime::new_for_test();
@@ -198,4 +1698,33 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_prepared_statement() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let statement = connection.prepare_statement("select ? + 1, ? LIMIT ?")?;
+        assert_eq!(statement.parameter_count, 3);
+        assert_eq!(statement.fields.len(), 2);
+
+        let (_fields, mut executor) = connection.execute_prepared_statement(
+            &statement,
+            &[
+                (Datum::from(2), DataType::Integer),
+                (Datum::from("a?"), DataType::Text),
+                (Datum::from(5), DataType::Integer),
+            ],
+        )?;
+        assert_eq!(
+            executor.next()?,
+            Some(([Datum::from(3), Datum::from("a?")].as_ref(), 1))
+        );
+        assert_eq!(executor.next()?, None);
+
+        assert!(connection.prepare_statement("select ? +").is_err());
+        assert!(connection
+            .execute_prepared_statement(&statement, &[(Datum::Null, DataType::Null)])
+            .is_err());
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 use catalog::CatalogError;
 use executor::ExecutionError;
+use functions::UdfError;
 use parser::ParseError;
 use planner::PlannerError;
 use std::fmt::{Debug, Display, Formatter};
@@ -9,6 +10,17 @@ pub enum QueryError {
     PlannerError(PlannerError),
     ExecutionError(ExecutionError),
     CatalogError(CatalogError),
+    UdfError(UdfError),
+    SavepointNotFound(String),
+    ReadOnly,
+    MaterializedViewWrite(String, String),
+    UnknownConnection(u32),
+    SubscriptionNotSupported(&'static str),
+    UnknownTimeZone(String),
+    TooManyConnections,
+    TooManyUserConnections(String),
+    // The privilege the statement needs
+    AccessDenied(&'static str),
 }
 
 impl Display for QueryError {
@@ -18,10 +30,74 @@ impl Display for QueryError {
             QueryError::PlannerError(err) => Display::fmt(err, f),
             QueryError::ExecutionError(err) => Display::fmt(err, f),
             QueryError::CatalogError(err) => Display::fmt(err, f),
+            QueryError::UdfError(err) => Display::fmt(err, f),
+            QueryError::SavepointNotFound(name) => {
+                f.write_fmt(format_args!("SAVEPOINT {} does not exist", name))
+            }
+            QueryError::ReadOnly => {
+                f.write_str("This server is a read only follower, writes must go to the leader")
+            }
+            QueryError::MaterializedViewWrite(db, view) => f.write_fmt(format_args!(
+                "{}.{} is a materialized view and can't be written to directly",
+                db, view
+            )),
+            QueryError::UnknownConnection(connection_id) => {
+                f.write_fmt(format_args!("Unknown thread id: {}", connection_id))
+            }
+            QueryError::SubscriptionNotSupported(reason) => {
+                f.write_fmt(format_args!("Can't subscribe to {}", reason))
+            }
+            QueryError::UnknownTimeZone(time_zone) => f.write_fmt(format_args!(
+                "Unknown or incorrect time zone: '{}'",
+                time_zone
+            )),
+            QueryError::TooManyConnections => f.write_str("Too many connections"),
+            QueryError::TooManyUserConnections(user) => f.write_fmt(format_args!(
+                "User {} already has more than 'max_user_connections' active connections",
+                user
+            )),
+            QueryError::AccessDenied(privilege) => f.write_fmt(format_args!(
+                "Access denied; you need (at least one of) the {} privilege(s) for this operation",
+                privilege
+            )),
         }
     }
 }
 
+impl QueryError {
+    /// Write conflicts are transient, the client can retry the statement.
+    pub fn is_write_conflict(&self) -> bool {
+        if let QueryError::ExecutionError(err) = self {
+            err.is_write_conflict()
+        } else {
+            false
+        }
+    }
+
+    /// The statement was killed part way through.
+    pub fn is_interrupted(&self) -> bool {
+        matches!(self, QueryError::ExecutionError(err) if err.is_interrupted())
+    }
+
+    /// The statement ran for longer than the session's max_execution_time.
+    pub fn is_timed_out(&self) -> bool {
+        matches!(self, QueryError::ExecutionError(err) if err.is_timed_out())
+    }
+
+    /// The statement referenced a table that doesn't exist.
+    pub fn is_table_not_found(&self) -> bool {
+        matches!(
+            self,
+            QueryError::CatalogError(CatalogError::TableNotFound(..))
+        )
+    }
+
+    /// An arithmetic overflow etc while running the statement.
+    pub fn is_out_of_range(&self) -> bool {
+        matches!(self, QueryError::ExecutionError(err) if err.is_out_of_range())
+    }
+}
+
 impl Debug for QueryError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         Display::fmt(self, f)
@@ -51,3 +127,9 @@ impl From<CatalogError> for QueryError {
         QueryError::CatalogError(catalog_error)
     }
 }
+
+impl From<UdfError> for QueryError {
+    fn from(udf_error: UdfError) -> Self {
+        QueryError::UdfError(udf_error)
+    }
+}
@@ -0,0 +1,171 @@
+use crate::connection::Connection;
+use crate::workload::WorkloadClass;
+use crate::QueryError;
+use ast::statement::{Compression, SelectIntoOutfile, SinkFormat};
+use data::json::JsonBuilder;
+use data::rust_decimal::Decimal;
+use data::{DataType, Datum};
+use executor::point_in_time::build_executor;
+use executor::ExecutionError;
+use flate2::write::GzEncoder;
+use planner::Field;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+
+impl Connection<'_> {
+    /// Runs the query streaming the results out to the file rather than back to the client,
+    /// like mysql it's an error for the file to already exist.
+    pub(crate) fn select_into_outfile(&self, outfile: SelectIntoOutfile) -> Result<(), QueryError> {
+        let SelectIntoOutfile {
+            query,
+            path,
+            format,
+            delimiter,
+            quote,
+            compression,
+        } = outfile;
+        let plan = self
+            .runtime
+            .planner
+            .plan_for_point_in_time(query, &self.session)?;
+        let _permit = self.runtime.scheduler.admit(WorkloadClass::Interactive);
+        let mut executor = build_executor(&self.session, &plan.operator);
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(ExecutionError::from)?;
+        let writer = BufWriter::new(file);
+        let mut write_rows = |writer: &mut dyn Write| -> Result<(), ExecutionError> {
+            while let Some((tuple, freq)) = executor.next()? {
+                let record = match format {
+                    SinkFormat::Json => json_record(tuple, &plan.fields),
+                    SinkFormat::Csv => csv_record(tuple, &plan.fields, delimiter, quote),
+                };
+                for _ in 0..freq {
+                    writeln!(writer, "{}", record)?;
+                }
+            }
+            Ok(())
+        };
+
+        match compression {
+            Compression::None => {
+                let mut writer = writer;
+                write_rows(&mut writer)?;
+                writer.flush().map_err(ExecutionError::from)?;
+            }
+            Compression::Gzip => {
+                let mut writer = GzEncoder::new(writer, flate2::Compression::default());
+                write_rows(&mut writer)?;
+                // The gzip trailer only gets written on finish
+                writer
+                    .finish()
+                    .and_then(|mut writer| writer.flush())
+                    .map_err(ExecutionError::from)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A json object keyed by the field names
+pub(crate) fn json_record(tuple: &[Datum], fields: &[Field]) -> String {
+    let json = JsonBuilder::default().object(|object| {
+        for (datum, field) in tuple.iter().zip(fields) {
+            let key = field.alias.as_str();
+            match (datum, field.data_type) {
+                (Datum::Null, _) => object.push_null(key),
+                (_, DataType::Boolean) => object.push_bool(key, datum.as_boolean()),
+                (_, DataType::Integer) => object.push_int(key, datum.as_integer() as i64),
+                (_, DataType::BigInt) => object.push_int(key, datum.as_bigint()),
+                (_, DataType::Decimal(..)) => object.push_decimal(key, datum.as_decimal()),
+                (_, DataType::Json) => object.push_json(key, datum.as_json()),
+                (_, data_type) => {
+                    let text = datum.typed_with(data_type).to_string();
+                    // Json numbers are stored as decimals, infinities etc can't be represented.
+                    match (data_type, Decimal::from_str(&text)) {
+                        (DataType::Double, Ok(d)) => object.push_decimal(key, d),
+                        _ => object.push_string(key, &text),
+                    }
+                }
+            }
+        }
+    });
+    Datum::from(json).typed_with(DataType::Json).to_string()
+}
+
+/// Delimited fields, nulls are written as empty fields. Fields containing the delimiter, quote or
+/// newlines are quoted with any quotes within doubled up.
+pub(crate) fn csv_record(tuple: &[Datum], fields: &[Field], delimiter: u8, quote: u8) -> String {
+    let delimiter = delimiter as char;
+    let quote = quote as char;
+    tuple
+        .iter()
+        .zip(fields)
+        .map(|(datum, field)| {
+            if let Datum::Null = datum {
+                return String::new();
+            }
+            let text = datum.typed_with(field.data_type).to_string();
+            if text.contains(|c| c == delimiter || c == quote || c == '\n' || c == '\r') {
+                let doubled: String = [quote, quote].iter().collect();
+                format!("{}{}{}", quote, text.replace(quote, &doubled), quote)
+            } else {
+                text
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(data_types: &[DataType]) -> Vec<Field> {
+        data_types
+            .iter()
+            .enumerate()
+            .map(|(idx, data_type)| Field {
+                qualifier: None,
+                alias: format!("c{}", idx + 1),
+                data_type: *data_type,
+                nullable: true,
+                origin: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_json_record() {
+        let fields = fields(&[
+            DataType::Integer,
+            DataType::Text,
+            DataType::Boolean,
+            DataType::Double,
+            DataType::Integer,
+        ]);
+        let tuple = [
+            Datum::from(1),
+            Datum::from("a\"b"),
+            Datum::from(true),
+            Datum::from(1.5),
+            Datum::Null,
+        ];
+        assert_eq!(
+            json_record(&tuple, &fields),
+            r#"{"c1":1,"c2":"a\"b","c3":true,"c4":1.5,"c5":null}"#
+        );
+    }
+
+    #[test]
+    fn test_csv_record() {
+        let fields = fields(&[DataType::Integer, DataType::Text, DataType::Text]);
+        let tuple = [Datum::from(1), Datum::from("a,\"b\""), Datum::Null];
+        assert_eq!(csv_record(&tuple, &fields, b',', b'"'), r#"1,"a,""b""","#);
+        assert_eq!(csv_record(&tuple, &fields, b'|', b'\''), r#"1|a,"b"|"#);
+    }
+}
@@ -1,18 +1,42 @@
+pub mod auth;
+pub mod config;
 pub mod connection;
 mod error;
+mod export;
+mod materialized_views;
+mod query_cache;
+mod sinks;
+mod slow_log;
+mod subscriptions;
+mod transactions;
+mod user_functions;
+pub mod workload;
 
+pub use catalog::Replication;
 pub use error::QueryError;
+pub use executor::point_in_time::BoxedExecutor;
+pub use planner::{Field, FieldOrigin};
+pub use storage::ReplicatedBatch;
 
+use crate::config::{Config, SlowQueryLogConfig};
 use crate::connection::Connection;
+use crate::query_cache::QueryCache;
+use crate::slow_log::{LONG_QUERY_TIME_VARIABLE, SLOW_QUERY_LOG_VARIABLE};
+use crate::user_functions::register_user_function;
+use crate::workload::{Scheduler, WorkloadConfig};
 use catalog::Catalog;
-use data::Session;
+use data::{Datum, LogicalTimestamp, Session};
 use functions::registry::Registry;
 use planner::Planner;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::error::Error;
-use std::sync::atomic::Ordering;
-use std::sync::{Arc, RwLock, Weak};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
 use storage::Storage;
+use tracing::error;
 
 /// Wraps all the runtime services of incresql.
 /// connections are created from a runtime and then sql can then be run against a connection.
@@ -20,24 +44,92 @@ use storage::Storage;
 pub struct Runtime {
     connections_state: RwLock<ConnectionsState>,
     planner: Planner,
+    query_cache: QueryCache,
+    scheduler: Arc<Scheduler>,
+    // Held while materialized views are being brought up to date so that refreshes don't race
+    view_maintenance: Mutex<()>,
+    // Followers only serve reads, the writes come from the leader
+    read_only: AtomicBool,
+    // The sessions of the running sinks keyed by (database, name), killing them stops the export
+    sinks: Mutex<HashMap<(String, String), Arc<Session>>>,
+    started: Instant,
+    connection_limits: ConnectionLimits,
+    // The initial sort_buffer_size given to each connection
+    sort_buffer_size: usize,
+    slow_query_log: SlowQueryLogConfig,
+    // The user defined functions registered from the catalog along with the version of the
+    // catalog's functions they came from, followers re-sync when the leader changes them
+    user_functions: Mutex<(u64, Vec<String>)>,
 }
 
+/// The max number of query results held by the query cache.
+const QUERY_CACHE_ENTRIES: usize = 256;
+
 #[derive(Debug)]
 struct ConnectionsState {
     connection_id_counter: u32,
     connections: HashMap<u32, Weak<Connection<'static>>>,
 }
 
+/// Caps on the number of client connections along with the initial timeouts given to each
+/// connection, the timeouts can be changed per session by setting the variables.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConnectionLimits {
+    /// The max number of open connections, 0 for no limit.
+    pub max_connections: usize,
+    /// The max number of open connections logged in as any one user, 0 for no limit.
+    pub max_user_connections: usize,
+    /// Seconds a connection can sit idle before it's closed (wait_timeout), 0 for no limit.
+    pub wait_timeout: u32,
+    /// Milliseconds a statement can run for before it's interrupted (max_execution_time), 0 for
+    /// no limit.
+    pub max_execution_time: u32,
+}
+
+impl Default for ConnectionLimits {
+    fn default() -> Self {
+        ConnectionLimits {
+            max_connections: 0,
+            max_user_connections: 0,
+            wait_timeout: 28800,
+            max_execution_time: 0,
+        }
+    }
+}
+
 impl Runtime {
     /// Create a new runtime
     pub fn new(db_path: &str) -> Result<Runtime, Box<dyn Error>> {
-        let storage = Storage::new_with_path(db_path)?;
-        Runtime::new_with_storage(storage)
+        Runtime::new_with_config(&Config {
+            directory: db_path.to_string(),
+            ..Config::default()
+        })
+    }
+
+    /// Create a new runtime stored in the config's directory, with the storage tuning,
+    /// concurrency caps and connection limits etc all taken from the config.
+    pub fn new_with_config(config: &Config) -> Result<Runtime, Box<dyn Error>> {
+        let storage = Storage::new_with_config(&config.directory, &config.storage_config())?;
+        let mut runtime = Runtime::new_with_storage(storage, config.workload)?
+            .with_connection_limits(config.connections)
+            .with_slow_query_log(config.slow_query_log);
+        runtime.sort_buffer_size = config.memory.sort_buffer_size;
+        Ok(runtime)
     }
 
-    fn new_with_storage(storage: Storage) -> Result<Runtime, Box<dyn Error>> {
+    fn new_with_storage(
+        storage: Storage,
+        workload_config: WorkloadConfig,
+    ) -> Result<Runtime, Box<dyn Error>> {
         let function_registry = Registry::new(true);
         let catalog = Catalog::new(storage)?;
+        let mut user_functions = vec![];
+        for function in catalog.functions()? {
+            register_user_function(&function_registry, &function)?;
+            user_functions.push(function.name);
+        }
+        let user_functions = Mutex::new((catalog.functions_version(), user_functions));
         let planner = Planner::new(function_registry, catalog);
 
         let connections_state = RwLock::from(ConnectionsState {
@@ -45,29 +137,168 @@ impl Runtime {
             connections: HashMap::new(),
         });
 
-        Ok(Runtime {
+        let runtime = Runtime {
             connections_state,
             planner,
-        })
+            query_cache: QueryCache::new(QUERY_CACHE_ENTRIES),
+            scheduler: Arc::new(Scheduler::new(workload_config)),
+            view_maintenance: Mutex::new(()),
+            read_only: AtomicBool::new(false),
+            sinks: Mutex::new(HashMap::new()),
+            started: Instant::now(),
+            connection_limits: ConnectionLimits::default(),
+            sort_buffer_size: Config::default().memory.sort_buffer_size,
+            slow_query_log: SlowQueryLogConfig::default(),
+            user_functions,
+        };
+        runtime.start_sinks()?;
+        Ok(runtime)
     }
 
     /// Creates a new runtime with in-memory storage etc to be used during tests
     pub fn new_for_test() -> Runtime {
-        Runtime::new_with_storage(Storage::new_in_mem().unwrap()).unwrap()
+        Runtime::new_with_storage(Storage::new_in_mem().unwrap(), WorkloadConfig::default())
+            .unwrap()
     }
 }
 
 impl Runtime {
+    /// Sets the limits enforced on client connections.
+    pub fn with_connection_limits(mut self, connection_limits: ConnectionLimits) -> Self {
+        self.connection_limits = connection_limits;
+        self
+    }
+
+    pub fn connection_limits(&self) -> ConnectionLimits {
+        self.connection_limits
+    }
+
+    /// Sets whether slow queries are logged (and what counts as slow) for new connections.
+    pub fn with_slow_query_log(mut self, slow_query_log: SlowQueryLogConfig) -> Self {
+        self.slow_query_log = slow_query_log;
+        self
+    }
+
+    /// Returns the handle used to replicate the storage between leader and followers
+    pub fn replication(&self) -> Replication {
+        self.planner.catalog.read().unwrap().replication()
+    }
+
+    /// When set statements that write are rejected, used for followers.
+    pub fn set_read_only(&self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// The timestamp statements starting now should read as of. Followers read as of the last
+    /// batch they've applied from the leader, leaders just read the latest data.
+    pub fn read_timestamp(&self) -> LogicalTimestamp {
+        if self.read_only() {
+            LogicalTimestamp::new(self.replication().timestamp().ms + 1)
+        } else {
+            LogicalTimestamp::MAX
+        }
+    }
+
+    /// Followers get their user defined functions from the leader, so the function registry
+    /// needs bringing back into line with the catalog whenever the leader changes them.
+    pub(crate) fn sync_user_functions(&self) {
+        let catalog = self.planner.catalog.read().unwrap();
+        let version = catalog.functions_version();
+        let mut user_functions = self.user_functions.lock().unwrap();
+        if user_functions.0 == version {
+            return;
+        }
+        let registry = &self.planner.function_registry;
+        for name in user_functions.1.drain(..) {
+            registry.unregister_function(&name);
+        }
+        match catalog.functions() {
+            Ok(functions) => {
+                for function in functions {
+                    match register_user_function(registry, &function) {
+                        Ok(()) => user_functions.1.push(function.name),
+                        Err(err) => {
+                            error!(name = %function.name, %err, "Couldn't register function")
+                        }
+                    }
+                }
+            }
+            Err(err) => error!(%err, "Couldn't load user functions"),
+        }
+        user_functions.0 = version;
+    }
+
+    /// How far behind the leader a follower is, None when we're not following.
+    pub fn replication_lag(&self) -> Option<Duration> {
+        if self.read_only() {
+            let applied = self.replication().timestamp().ms;
+            Some(Duration::from_millis(
+                LogicalTimestamp::now().ms.saturating_sub(applied),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// How long the runtime's been up for.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
     /// Returns a new connection on which to execute sql commands
     pub fn new_connection(&self) -> Arc<Connection<'_>> {
         let mut connection_state = self.connections_state.write().unwrap();
+        self.register_connection(&mut connection_state)
+    }
+
+    /// Like new_connection but fails once max_connections connections are open, used for
+    /// client connections.
+    pub fn try_new_connection(&self) -> Result<Arc<Connection<'_>>, QueryError> {
+        let mut connection_state = self.connections_state.write().unwrap();
+        let max_connections = self.connection_limits.max_connections;
+        if max_connections > 0 && connection_state.connections.len() >= max_connections {
+            return Err(QueryError::TooManyConnections);
+        }
+        Ok(self.register_connection(&mut connection_state))
+    }
+
+    fn register_connection(&self, connection_state: &mut ConnectionsState) -> Arc<Connection<'_>> {
         connection_state.connection_id_counter += 1;
         let connection_id = connection_state.connection_id_counter;
         let session = Arc::new(Session::new(connection_id));
+        {
+            let limits = self.connection_limits;
+            let mut variables = session.variables.write().unwrap();
+            for (name, value) in &[
+                ("wait_timeout", limits.wait_timeout as usize),
+                ("max_execution_time", limits.max_execution_time as usize),
+                ("max_connections", limits.max_connections),
+                ("max_user_connections", limits.max_user_connections),
+                ("sort_buffer_size", self.sort_buffer_size),
+            ] {
+                variables.insert(
+                    name.to_string(),
+                    Datum::from((*value).min(i32::MAX as usize) as i32),
+                );
+            }
+            variables.insert(
+                SLOW_QUERY_LOG_VARIABLE.to_string(),
+                Datum::from(self.slow_query_log.enabled as i32),
+            );
+            variables.insert(
+                LONG_QUERY_TIME_VARIABLE.to_string(),
+                Datum::from(self.slow_query_log.long_query_time),
+            );
+        }
         let connection = Arc::from(Connection {
             connection_id,
             session,
             runtime: &self,
+            transaction: Mutex::default(),
         });
 
         connection_state.connections.insert(
@@ -78,17 +309,136 @@ impl Runtime {
         connection
     }
 
-    /// Marks the connection_id passed as killed, its then up to the executors to bail out.
-    pub fn kill_connection(&self, connection_id: u32) {
-        let mut connection_state = self.connections_state.write().unwrap();
-        connection_state
+    /// Records the user the connection has logged in as, failing if there's already
+    /// max_user_connections connections logged in as that user.
+    pub(crate) fn login(&self, session: &Session, user: String) -> Result<(), QueryError> {
+        // Declared before the lock so they're dropped after it, dropping the last reference to
+        // a connection takes the lock to remove it.
+        let mut connections = vec![];
+        // Held so that concurrent logins can't both squeeze in under the limit
+        let connection_state = self.connections_state.write().unwrap();
+        let max_user_connections = self.connection_limits.max_user_connections;
+        if max_user_connections > 0 {
+            connections.extend(
+                connection_state
+                    .connections
+                    .values()
+                    .filter_map(Weak::upgrade),
+            );
+            let user_connections = connections
+                .iter()
+                .filter(|connection| {
+                    connection.connection_id != session.connection_id
+                        && *connection.session.user.read().unwrap() == user
+                })
+                .count();
+            if user_connections >= max_user_connections {
+                return Err(QueryError::TooManyUserConnections(user));
+            }
+        }
+        *session.user.write().unwrap() = user;
+        Ok(())
+    }
+
+    /// Checks a mysql_native_password login attempt against the users table, the scramble
+    /// being the random challenge sent to the client during the handshake.
+    pub fn authenticate(&self, user: &str, scramble: &[u8], auth_response: &[u8]) -> bool {
+        // The empty user is reserved for embedded connections that never log in
+        if user.is_empty() {
+            return false;
+        }
+        let catalog = self.planner.catalog.read().unwrap();
+        match catalog.user_authentication_string(user) {
+            Ok(Some(authentication_string)) => {
+                auth::verify_native_password(&authentication_string, scramble, auth_response)
+            }
+            _ => false,
+        }
+    }
+
+    /// Marks the connection_id passed as killed, its then up to the executors to bail out and
+    /// the server to close the connection. Returns false if there's no such connection.
+    pub fn kill_connection(&self, connection_id: u32) -> bool {
+        self.kill(connection_id, false)
+    }
+
+    /// Like kill_connection but only the running statement is killed, the connection stays open.
+    pub fn kill_query(&self, connection_id: u32) -> bool {
+        self.kill(connection_id, true)
+    }
+
+    fn kill(&self, connection_id: u32, query_only: bool) -> bool {
+        // Take our own reference so we don't end up dropping the last one (and removing the
+        // connection) while holding the lock.
+        let connection = self
+            .connections_state
+            .read()
+            .unwrap()
             .connections
-            .get_mut(&connection_id)
-            .map(|connection| {
+            .get(&connection_id)
+            .and_then(Weak::upgrade);
+        if let Some(connection) = connection {
+            connection.session.kill_flag.store(true, Ordering::Relaxed);
+            if !query_only {
                 connection
-                    .upgrade()
-                    .map(|connection| connection.session.kill_flag.store(true, Ordering::Relaxed))
-            });
+                    .session
+                    .connection_killed
+                    .store(true, Ordering::Relaxed);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the sessions of all the open connections ordered by connection id.
+    pub fn sessions(&self) -> Vec<Arc<Session>> {
+        let connections: Vec<_> = self
+            .connections_state
+            .read()
+            .unwrap()
+            .connections
+            .values()
+            .filter_map(Weak::upgrade)
+            .collect();
+        let mut sessions: Vec<_> = connections
+            .iter()
+            .map(|connection| Arc::clone(&connection.session))
+            .collect();
+        sessions.sort_by_key(|session| session.connection_id);
+        sessions
+    }
+
+    /// Shuts down the runtime, every connection is marked as killed so running statements bail
+    /// out, once the connections have gone away (or the timeout passes) the storage is flushed.
+    pub fn shutdown(&self, timeout: Duration) -> Result<(), QueryError> {
+        let connection_ids: Vec<_> = self
+            .connections_state
+            .read()
+            .unwrap()
+            .connections
+            .keys()
+            .copied()
+            .collect();
+        for connection_id in connection_ids {
+            self.kill_connection(connection_id);
+        }
+        self.stop_sinks();
+
+        let deadline = Instant::now() + timeout;
+        while !self
+            .connections_state
+            .read()
+            .unwrap()
+            .connections
+            .is_empty()
+            && Instant::now() < deadline
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        self.planner.catalog.read().unwrap().flush()?;
+        Ok(())
     }
 
     /// Used by connections when they're dropped to clean up any state
@@ -98,6 +448,12 @@ impl Runtime {
     }
 }
 
+impl Drop for Runtime {
+    fn drop(&mut self) {
+        self.stop_sinks();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,6 +472,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_max_connections() {
+        let runtime = Runtime::new_for_test().with_connection_limits(ConnectionLimits {
+            max_connections: 1,
+            ..ConnectionLimits::default()
+        });
+        let connection_1 = runtime.try_new_connection().unwrap();
+        assert!(matches!(
+            runtime.try_new_connection(),
+            Err(QueryError::TooManyConnections)
+        ));
+        drop(connection_1);
+        assert!(runtime.try_new_connection().is_ok());
+    }
+
+    #[test]
+    fn test_max_user_connections() {
+        let runtime = Runtime::new_for_test().with_connection_limits(ConnectionLimits {
+            max_user_connections: 1,
+            ..ConnectionLimits::default()
+        });
+        let connection_1 = runtime.new_connection();
+        let connection_2 = runtime.new_connection();
+        connection_1.login("root".to_string()).unwrap();
+        connection_2.login("bob".to_string()).unwrap();
+
+        let connection_3 = runtime.new_connection();
+        assert!(matches!(
+            connection_3.login("root".to_string()),
+            Err(QueryError::TooManyUserConnections(_))
+        ));
+        drop(connection_1);
+        assert!(connection_3.login("root".to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_connection_limit_variables() {
+        let runtime = Runtime::new_for_test().with_connection_limits(ConnectionLimits {
+            wait_timeout: 60,
+            max_execution_time: 1000,
+            ..ConnectionLimits::default()
+        });
+        let connection = runtime.new_connection();
+        let session = &connection.session;
+        assert_eq!(
+            session.variable("wait_timeout").map(|(datum, _)| datum),
+            Some(Datum::from(60))
+        );
+        assert_eq!(
+            session
+                .variable("max_execution_time")
+                .map(|(datum, _)| datum),
+            Some(Datum::from(1000))
+        );
+    }
+
     #[test]
     fn test_connection_kill() {
         let runtime = Runtime::new_for_test();
@@ -126,9 +538,38 @@ mod tests {
             false
         );
 
-        runtime.kill_connection(connection_1.connection_id);
+        assert!(runtime.kill_query(connection_1.connection_id));
+        assert_eq!(connection_1.session.kill_flag.load(Ordering::Acquire), true);
+        assert_eq!(
+            connection_1
+                .session
+                .connection_killed
+                .load(Ordering::Acquire),
+            false
+        );
+
+        assert!(runtime.kill_connection(connection_1.connection_id));
+        assert_eq!(
+            connection_1
+                .session
+                .connection_killed
+                .load(Ordering::Acquire),
+            true
+        );
+
+        assert!(!runtime.kill_connection(12345));
+    }
 
+    #[test]
+    fn test_shutdown() {
+        let runtime = Runtime::new_for_test();
+        let connection_1 = runtime.new_connection();
+
+        runtime.shutdown(Duration::from_millis(20)).unwrap();
         assert_eq!(connection_1.session.kill_flag.load(Ordering::Acquire), true);
+
+        drop(connection_1);
+        runtime.shutdown(Duration::from_secs(60)).unwrap();
     }
 
     #[test]
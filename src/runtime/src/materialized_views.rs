@@ -0,0 +1,517 @@
+use crate::{QueryError, Runtime};
+use ast::expr::Expression;
+use ast::rel::logical::JoinType;
+use ast::rel::point_in_time::{
+    AggregateMode, Filter, Group, Join, JsonTable, PointInTimeOperator, Project, Sort, UnionAll,
+    Values,
+};
+use ast::statement::Statement;
+use catalog::MaterializedView;
+use data::{Datum, LogicalTimestamp, Session, TupleIter};
+use executor::point_in_time::build_executor;
+use executor::ExecutionError;
+use parser::parse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use storage::Table;
+
+/// A change to the contents of a table, each tuple along with the freq it's being added (or
+/// retracted when negative) with.
+pub(crate) type Delta = Vec<(Vec<Datum<'static>>, i64)>;
+
+/// The tables touched by a write along with what was written.
+pub(crate) struct TableChange {
+    /// The ids of all the tables written to, the target table always comes first followed by
+    /// any partitions and index tables.
+    pub tables: Vec<u32>,
+    /// The rows written to the target table, None when they're not known up front (ie deletes
+    /// of all matching rows), in which case the dependent views are fully refreshed.
+    pub delta: Option<Delta>,
+}
+
+/// Brings the materialized views reading from the changed tables up to date.
+/// Where possible the change to a view is worked out by running its plan over just the delta
+/// (for group bys just the groups in the delta are recomputed), otherwise (limits etc) the view
+/// is recomputed and diffed against what's stored.
+/// Views built on top of other materialized views are maintained in turn.
+pub(crate) fn maintain_views(
+    runtime: &Runtime,
+    session: &Arc<Session>,
+    change: TableChange,
+) -> Result<(), QueryError> {
+    let _guard = runtime.view_maintenance.lock().unwrap();
+    let views = runtime
+        .planner
+        .catalog
+        .read()
+        .unwrap()
+        .materialized_views()?;
+    maintain_views_impl(runtime, &maintenance_session(session), &views, &change)
+}
+
+/// The write has already gone through by the time the views are maintained, so killing the
+/// statement (or it timing out) mustn't stop the views being brought up to date. The views are
+/// maintained with a copy of the session that can't be interrupted.
+fn maintenance_session(session: &Session) -> Arc<Session> {
+    let maintenance_session = Session::new(session.connection_id);
+    *maintenance_session.user.write().unwrap() = session.user.read().unwrap().clone();
+    *maintenance_session.current_database.write().unwrap() =
+        session.current_database.read().unwrap().clone();
+    *maintenance_session.variables.write().unwrap() = session.variables.read().unwrap().clone();
+    Arc::new(maintenance_session)
+}
+
+/// Populates a newly created materialized view.
+pub(crate) fn populate_view(
+    runtime: &Runtime,
+    session: &Arc<Session>,
+    view: &MaterializedView,
+) -> Result<(), QueryError> {
+    let _guard = runtime.view_maintenance.lock().unwrap();
+    let plan = plan_view(runtime, session, view)?;
+    let delta = refresh_delta(session, &plan, &view.table)?;
+    write_delta(&view.table, &delta)
+}
+
+fn maintain_views_impl(
+    runtime: &Runtime,
+    session: &Arc<Session>,
+    views: &[MaterializedView],
+    change: &TableChange,
+) -> Result<(), QueryError> {
+    if change.delta.as_ref().map_or(false, Vec::is_empty) {
+        return Ok(());
+    }
+
+    for view in views {
+        // A view whose sql no longer plans (ie one of its tables has been dropped) can't be
+        // maintained, reads will just see its last contents.
+        let plan = match plan_view(runtime, session, view) {
+            Ok(plan) => plan,
+            Err(_) => continue,
+        };
+        if !reads_tables(&plan, &change.tables) {
+            continue;
+        }
+
+        let incremental = match &change.delta {
+            Some(delta) => delta_plan(session, &plan, &change.tables, &delta_operator(delta))?,
+            None => DeltaPlan::NotIncremental,
+        };
+        let view_delta = match incremental {
+            DeltaPlan::Unchanged => continue,
+            DeltaPlan::Changed(delta_plan) => execute(session, &delta_plan)?,
+            DeltaPlan::NotIncremental => refresh_delta(session, &plan, &view.table)?,
+        };
+        if view_delta.is_empty() {
+            continue;
+        }
+
+        write_delta(&view.table, &view_delta)?;
+        let view_change = TableChange {
+            tables: vec![view.table.id()],
+            delta: Some(view_delta),
+        };
+        maintain_views_impl(runtime, session, views, &view_change)?;
+    }
+    Ok(())
+}
+
+/// Plans the view's sql as it would've been planned at creation time.
+fn plan_view(
+    runtime: &Runtime,
+    session: &Arc<Session>,
+    view: &MaterializedView,
+) -> Result<PointInTimeOperator, QueryError> {
+    let view_session = Session::new(session.connection_id);
+    *view_session.current_database.write().unwrap() = view.view.db_context.clone();
+    if let Statement::Query(query) = parse(&view.view.sql)? {
+        Ok(runtime
+            .planner
+            .plan_for_point_in_time(query, &view_session)?
+            .operator)
+    } else {
+        panic!("Bogus materialized view")
+    }
+}
+
+/// Runs the plan, summing up the freqs of each distinct tuple.
+pub(crate) fn execute(
+    session: &Arc<Session>,
+    plan: &PointInTimeOperator,
+) -> Result<Delta, ExecutionError> {
+    let mut freqs = HashMap::new();
+    let mut executor = build_executor(session, plan);
+    while let Some((tuple, freq)) = executor.next()? {
+        let tuple: Vec<_> = tuple.iter().map(|datum| datum.as_static()).collect();
+        *freqs.entry(tuple).or_insert(0) += freq;
+    }
+    Ok(freqs.into_iter().filter(|(_, freq)| *freq != 0).collect())
+}
+
+/// Recomputes the view, returning the difference between that and what's stored in the table.
+fn refresh_delta(
+    session: &Arc<Session>,
+    plan: &PointInTimeOperator,
+    table: &Table,
+) -> Result<Delta, QueryError> {
+    let mut freqs: HashMap<_, _> = execute(session, plan)?.into_iter().collect();
+    let mut iter = table.full_scan(LogicalTimestamp::MAX);
+    while let Some((tuple, freq)) = iter.next().map_err(ExecutionError::from)? {
+        let tuple: Vec<_> = tuple.iter().map(|datum| datum.as_static()).collect();
+        *freqs.entry(tuple).or_insert(0) -= freq;
+    }
+    Ok(freqs.into_iter().filter(|(_, freq)| *freq != 0).collect())
+}
+
+fn write_delta(table: &Table, delta: &[(Vec<Datum<'static>>, i64)]) -> Result<(), QueryError> {
+    let timestamp = LogicalTimestamp::now();
+    table.atomic_write::<_, ExecutionError>(|batch| {
+        for (tuple, freq) in delta {
+            batch.write_tuple(table, tuple, timestamp, *freq)?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Builds an operator that feeds out the delta, with the retractions coming through with
+/// negative freqs.
+pub(crate) fn delta_operator(delta: &[(Vec<Datum<'static>>, i64)]) -> PointInTimeOperator {
+    let column_count = delta.first().map_or(0, |(tuple, _)| tuple.len());
+    let mut inserts = vec![];
+    let mut retractions = vec![];
+    for (tuple, freq) in delta {
+        let rows = if *freq > 0 {
+            &mut inserts
+        } else {
+            &mut retractions
+        };
+        for _ in 0..freq.abs() {
+            rows.push(tuple.clone());
+        }
+    }
+    PointInTimeOperator::UnionAll(UnionAll {
+        sources: vec![
+            PointInTimeOperator::Values(Values {
+                data: inserts,
+                column_count,
+            }),
+            PointInTimeOperator::NegateFreq(Box::new(PointInTimeOperator::Values(Values {
+                data: retractions,
+                column_count,
+            }))),
+        ],
+    })
+}
+
+/// The plan to work out the change in an operator's output from a change to one of its tables.
+#[derive(Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+enum DeltaPlan {
+    /// The operator doesn't read from the table so its output is unchanged.
+    Unchanged,
+    /// The operator is linear in the table, this plan gives the change in its output.
+    Changed(PointInTimeOperator),
+    /// The change can't be worked out incrementally, ie for limits.
+    NotIncremental,
+}
+
+impl DeltaPlan {
+    fn map<F: FnOnce(PointInTimeOperator) -> PointInTimeOperator>(self, f: F) -> Self {
+        match self {
+            DeltaPlan::Changed(operator) => DeltaPlan::Changed(f(operator)),
+            other => other,
+        }
+    }
+}
+
+/// Builds the delta plan for an operator, the delta is for the first of the changed tables, any
+/// reads of the rest of the changed tables can't be done incrementally.
+fn delta_plan(
+    session: &Arc<Session>,
+    operator: &PointInTimeOperator,
+    changed: &[u32],
+    delta: &PointInTimeOperator,
+) -> Result<DeltaPlan, ExecutionError> {
+    Ok(match operator {
+        PointInTimeOperator::TableScan(scan) if scan.table.id() == changed[0] => {
+            DeltaPlan::Changed(delta.clone())
+        }
+        PointInTimeOperator::Project(project) => {
+            delta_plan(session, &project.source, changed, delta)?.map(|source| {
+                PointInTimeOperator::Project(Project {
+                    source: Box::new(source),
+                    ..project.clone()
+                })
+            })
+        }
+        PointInTimeOperator::Filter(filter) => delta_plan(session, &filter.source, changed, delta)?
+            .map(|source| {
+                PointInTimeOperator::Filter(Filter {
+                    source: Box::new(source),
+                    ..filter.clone()
+                })
+            }),
+        PointInTimeOperator::JsonTable(json_table) => {
+            delta_plan(session, &json_table.source, changed, delta)?.map(|source| {
+                PointInTimeOperator::JsonTable(JsonTable {
+                    source: Box::new(source),
+                    ..json_table.clone()
+                })
+            })
+        }
+        PointInTimeOperator::Sort(sort) if sort.limit.is_none() => {
+            delta_plan(session, &sort.source, changed, delta)?.map(|source| {
+                PointInTimeOperator::Sort(Sort {
+                    source: Box::new(source),
+                    ..sort.clone()
+                })
+            })
+        }
+        PointInTimeOperator::NegateFreq(source) => delta_plan(session, source, changed, delta)?
+            .map(|source| PointInTimeOperator::NegateFreq(Box::new(source))),
+        PointInTimeOperator::UnionAll(union_all) => {
+            let mut sources = vec![];
+            for source in &union_all.sources {
+                match delta_plan(session, source, changed, delta)? {
+                    DeltaPlan::Unchanged => {}
+                    DeltaPlan::Changed(source) => sources.push(source),
+                    DeltaPlan::NotIncremental => return Ok(DeltaPlan::NotIncremental),
+                }
+            }
+            if sources.is_empty() {
+                DeltaPlan::Unchanged
+            } else {
+                DeltaPlan::Changed(PointInTimeOperator::UnionAll(UnionAll { sources }))
+            }
+        }
+        // Joins are linear in each side as long as the other side isn't changing, except for
+        // the right side of left joins where new rows can take the place of the null padded ones,
+        // and of semi/anti joins where a new match can add or remove rows that matched before.
+        PointInTimeOperator::HashJoin(join) => {
+            match (
+                delta_plan(session, &join.left, changed, delta)?,
+                delta_plan(session, &join.right, changed, delta)?,
+            ) {
+                (DeltaPlan::Unchanged, DeltaPlan::Unchanged) => DeltaPlan::Unchanged,
+                (DeltaPlan::Changed(left), DeltaPlan::Unchanged) => {
+                    DeltaPlan::Changed(PointInTimeOperator::HashJoin(Join {
+                        left: Box::new(left),
+                        ..join.clone()
+                    }))
+                }
+                (DeltaPlan::Unchanged, DeltaPlan::Changed(right))
+                    if join.join_type == JoinType::Inner =>
+                {
+                    DeltaPlan::Changed(PointInTimeOperator::HashJoin(Join {
+                        right: Box::new(right),
+                        ..join.clone()
+                    }))
+                }
+                _ => DeltaPlan::NotIncremental,
+            }
+        }
+        PointInTimeOperator::SortedGroup(group) | PointInTimeOperator::HashGroup(group)
+            if group.key_len > 0 && group.mode == AggregateMode::Complete =>
+        {
+            match delta_plan(session, &group.source, changed, delta)? {
+                DeltaPlan::Changed(source_delta) => {
+                    group_delta_plan(session, group, &source_delta)?
+                }
+                other => other,
+            }
+        }
+        operator if reads_tables(operator, changed) => DeltaPlan::NotIncremental,
+        _ => DeltaPlan::Unchanged,
+    })
+}
+
+/// Group bys are maintained by recomputing just the groups whose keys appear in the change to
+/// their source, once as they are now and once as they were before, and retracting the old
+/// results. The aggregates have to support retraction to be able to take the change back out.
+fn group_delta_plan(
+    session: &Arc<Session>,
+    group: &Group,
+    source_delta: &PointInTimeOperator,
+) -> Result<DeltaPlan, ExecutionError> {
+    let mut expressions = group.expressions.clone();
+    if !expressions.iter_mut().all(use_retractable_aggregates) {
+        return Ok(DeltaPlan::NotIncremental);
+    }
+    let key_len = group.key_len;
+    let source_delta = execute(session, source_delta)?;
+    let keys: HashSet<_> = source_delta
+        .iter()
+        .map(|(tuple, _)| tuple[..key_len].to_vec())
+        .collect();
+
+    let mut rows: Delta = vec![];
+    let mut executor = build_executor(session, &group.source);
+    while let Some((tuple, freq)) = executor.next()? {
+        if keys.contains(&tuple[..key_len]) {
+            rows.push((tuple.iter().map(Datum::as_static).collect(), freq));
+        }
+    }
+
+    // Groups made up of only the changed rows didn't exist beforehand, their rows are left out
+    // otherwise we'd be retracting results that were never there.
+    let mut previous_counts = HashMap::new();
+    for (tuple, freq) in &rows {
+        *previous_counts.entry(&tuple[..key_len]).or_insert(0) += freq;
+    }
+    for (tuple, freq) in &source_delta {
+        *previous_counts.entry(&tuple[..key_len]).or_insert(0) -= freq;
+    }
+    let previous_rows: Delta = rows
+        .iter()
+        .cloned()
+        .chain(
+            source_delta
+                .iter()
+                .map(|(tuple, freq)| (tuple.clone(), -freq)),
+        )
+        .filter(|(tuple, _)| previous_counts[&tuple[..key_len]] > 0)
+        .collect();
+
+    let regroup = |rows: &Delta| {
+        PointInTimeOperator::HashGroup(Group {
+            source: Box::new(delta_operator(rows)),
+            expressions: expressions.clone(),
+            key_len,
+            mode: AggregateMode::Complete,
+        })
+    };
+    Ok(DeltaPlan::Changed(PointInTimeOperator::UnionAll(
+        UnionAll {
+            sources: vec![
+                regroup(&rows),
+                PointInTimeOperator::NegateFreq(Box::new(regroup(&previous_rows))),
+            ],
+        },
+    )))
+}
+
+/// Swaps any aggregates that can't be retracted for their retractable variants, returns false if
+/// there's an aggregate without one.
+fn use_retractable_aggregates(expression: &mut Expression) -> bool {
+    if let Expression::CompiledAggregate(aggregate) = expression {
+        if !aggregate.function.supports_retract() {
+            match aggregate.function.retractable() {
+                Some(function) => aggregate.function = function,
+                None => return false,
+            }
+        }
+    }
+    expression.children_mut().all(use_retractable_aggregates)
+}
+
+/// Returns true if the operator reads from any of the tables.
+fn reads_tables(operator: &PointInTimeOperator, tables: &[u32]) -> bool {
+    match operator {
+        PointInTimeOperator::TableScan(scan) => tables.contains(&scan.table.id()),
+        PointInTimeOperator::FulltextScan(scan) => tables.contains(&scan.index.table.id()),
+        PointInTimeOperator::Single
+        | PointInTimeOperator::Values(_)
+        | PointInTimeOperator::FileScan(_) => false,
+        PointInTimeOperator::Project(project) => reads_tables(&project.source, tables),
+        PointInTimeOperator::Filter(filter) => reads_tables(&filter.source, tables),
+        PointInTimeOperator::JsonTable(json_table) => reads_tables(&json_table.source, tables),
+        PointInTimeOperator::Limit(limit) => reads_tables(&limit.source, tables),
+        PointInTimeOperator::Sort(sort) => reads_tables(&sort.source, tables),
+        PointInTimeOperator::NegateFreq(source) => reads_tables(source, tables),
+        PointInTimeOperator::Gather(gather) => reads_tables(&gather.source, tables),
+        PointInTimeOperator::SortedGroup(group) | PointInTimeOperator::HashGroup(group) => {
+            reads_tables(&group.source, tables)
+        }
+        PointInTimeOperator::HashJoin(join) => {
+            reads_tables(&join.left, tables) || reads_tables(&join.right, tables)
+        }
+        PointInTimeOperator::UnionAll(union_all) => union_all
+            .sources
+            .iter()
+            .any(|source| reads_tables(source, tables)),
+        PointInTimeOperator::TableInsert(insert) => {
+            tables.contains(&insert.table.id()) || reads_tables(&insert.source, tables)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::point_in_time::TableScan;
+    use catalog::{Catalog, TableOrView};
+    use data::DataType;
+
+    fn scan(catalog: &Catalog, name: &str) -> PointInTimeOperator {
+        if let TableOrView::Table(table) = catalog.item("default", name).unwrap().item {
+            PointInTimeOperator::TableScan(TableScan {
+                table,
+                timestamp: LogicalTimestamp::MAX,
+                ranges: None,
+                column_mask: None,
+            })
+        } else {
+            panic!()
+        }
+    }
+
+    #[test]
+    fn test_delta_plan() -> Result<(), QueryError> {
+        let session = Arc::new(Session::new(1));
+        let mut catalog = Catalog::new_for_test().unwrap();
+        let columns = [("a".to_string(), DataType::Integer)];
+        catalog
+            .create_table("default", "t1", &columns, &[], &[], &[])
+            .unwrap();
+        catalog
+            .create_table("default", "t2", &columns, &[], &[], &[])
+            .unwrap();
+        let t1 = scan(&catalog, "t1");
+        let t2 = scan(&catalog, "t2");
+        let changed = if let PointInTimeOperator::TableScan(scan) = &t1 {
+            vec![scan.table.id()]
+        } else {
+            panic!()
+        };
+        let delta = delta_operator(&[(vec![Datum::from(1)], 2)]);
+
+        let union_all = PointInTimeOperator::UnionAll(UnionAll {
+            sources: vec![t1.clone(), t2.clone()],
+        });
+        assert_eq!(
+            delta_plan(&session, &union_all, &changed, &delta)?,
+            DeltaPlan::Changed(PointInTimeOperator::UnionAll(UnionAll {
+                sources: vec![delta.clone()]
+            }))
+        );
+
+        assert_eq!(
+            delta_plan(&session, &t2, &changed, &delta)?,
+            DeltaPlan::Unchanged
+        );
+
+        let group = PointInTimeOperator::HashGroup(Group {
+            key_len: 0,
+            expressions: vec![],
+            source: Box::new(t1),
+            mode: AggregateMode::Complete,
+        });
+        assert_eq!(
+            delta_plan(&session, &group, &changed, &delta)?,
+            DeltaPlan::NotIncremental
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_delta_operator() -> Result<(), QueryError> {
+        let delta = vec![(vec![Datum::from(1)], 2), (vec![Datum::from(2)], -1)];
+        let session = Arc::new(Session::new(1));
+        let mut result = execute(&session, &delta_operator(&delta))?;
+        result.sort();
+        assert_eq!(result, delta);
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+use ast::rel::point_in_time::PointInTimeOperator;
+use data::{Datum, TupleIter};
+use executor::point_in_time::BoxedExecutor;
+use executor::ExecutionError;
+use planner::Field;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use storage::Table;
+
+type Rows = Arc<Vec<(Vec<Datum<'static>>, i64)>>;
+
+/// A cache of query results, keyed by the normalized sql text.
+/// Entries are only valid while the plan (which has all the session dependant and time based
+/// functions folded into it) and the versions of all the tables read by it are unchanged, so
+/// any write to any of the tables invalidates the results.
+#[derive(Debug)]
+pub(crate) struct QueryCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    plan: PointInTimeOperator,
+    fields: Vec<Field>,
+    versions: Vec<u64>,
+    rows: Rows,
+    column_count: usize,
+    last_used: u64,
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize) -> Self {
+        QueryCache {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Returns the cached results for the query if there's still a valid entry.
+    pub fn get(
+        &self,
+        sql: &str,
+        plan: &PointInTimeOperator,
+        fields: &[Field],
+        versions: &[u64],
+    ) -> Option<BoxedExecutor> {
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let entry = state.entries.get_mut(sql)?;
+        if &entry.plan != plan || entry.fields != fields || entry.versions != versions {
+            return None;
+        }
+        entry.last_used = tick;
+        Some(Box::from(CachedResultIter {
+            rows: Arc::clone(&entry.rows),
+            position: None,
+            column_count: entry.column_count,
+        }))
+    }
+
+    /// Runs the executor to completion, caching its results and returning an executor that will
+    /// replay them.
+    /// The versions passed in should be read before the query is executed so that any writes
+    /// racing the query will cause the entry to be invalid.
+    pub fn populate(
+        &self,
+        sql: String,
+        plan: PointInTimeOperator,
+        fields: Vec<Field>,
+        versions: Vec<u64>,
+        mut executor: BoxedExecutor,
+    ) -> Result<BoxedExecutor, ExecutionError> {
+        let column_count = executor.column_count();
+        let mut rows = vec![];
+        while let Some((tuple, freq)) = executor.next()? {
+            rows.push((tuple.iter().map(Datum::as_static).collect(), freq));
+        }
+        let rows = Arc::new(rows);
+
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let last_used = state.tick;
+        if !state.entries.contains_key(&sql) && state.entries.len() >= self.capacity {
+            let lru = state
+                .entries
+                .iter()
+                .min_by_key(|(_sql, entry)| entry.last_used)
+                .map(|(sql, _entry)| sql.clone());
+            if let Some(lru) = lru {
+                state.entries.remove(&lru);
+            }
+        }
+        if self.capacity > 0 {
+            state.entries.insert(
+                sql,
+                CacheEntry {
+                    plan,
+                    fields,
+                    versions,
+                    rows: Arc::clone(&rows),
+                    column_count,
+                    last_used,
+                },
+            );
+        }
+
+        Ok(Box::from(CachedResultIter {
+            rows,
+            position: None,
+            column_count,
+        }))
+    }
+}
+
+/// Returns the versions of all the tables read by the plan, or None if the results of the plan
+/// can't be cached, ie it writes to tables or reads from files.
+pub(crate) fn table_versions(plan: &PointInTimeOperator) -> Option<Vec<u64>> {
+    Some(scanned_tables(plan)?.iter().map(Table::version).collect())
+}
+
+/// Returns all the tables read by the plan, or None if the plan does more than just read from
+/// tables, ie it writes to tables or reads from files.
+pub(crate) fn scanned_tables(plan: &PointInTimeOperator) -> Option<Vec<Table>> {
+    fn collect(plan: &PointInTimeOperator, tables: &mut Vec<Table>) -> Option<()> {
+        match plan {
+            PointInTimeOperator::TableScan(scan) => tables.push(scan.table.clone()),
+            PointInTimeOperator::FulltextScan(scan) => tables.push(scan.index.table.clone()),
+            PointInTimeOperator::TableInsert(_) | PointInTimeOperator::FileScan(_) => return None,
+            PointInTimeOperator::Single | PointInTimeOperator::Values(_) => {}
+            PointInTimeOperator::Project(project) => collect(&project.source, tables)?,
+            PointInTimeOperator::Filter(filter) => collect(&filter.source, tables)?,
+            PointInTimeOperator::JsonTable(json_table) => collect(&json_table.source, tables)?,
+            PointInTimeOperator::Limit(limit) => collect(&limit.source, tables)?,
+            PointInTimeOperator::Sort(sort) => collect(&sort.source, tables)?,
+            PointInTimeOperator::NegateFreq(source) => collect(source, tables)?,
+            PointInTimeOperator::Gather(gather) => collect(&gather.source, tables)?,
+            PointInTimeOperator::SortedGroup(group) | PointInTimeOperator::HashGroup(group) => {
+                collect(&group.source, tables)?
+            }
+            PointInTimeOperator::HashJoin(join) => {
+                collect(&join.left, tables)?;
+                collect(&join.right, tables)?
+            }
+            PointInTimeOperator::UnionAll(union_all) => {
+                for source in &union_all.sources {
+                    collect(source, tables)?;
+                }
+            }
+        }
+        Some(())
+    }
+
+    let mut tables = vec![];
+    collect(plan, &mut tables)?;
+    Some(tables)
+}
+
+/// Normalizes the sql text used as the cache key, collapsing all whitespace.
+/// This may merge strings that differ only by whitespace within quotes but any constants will
+/// have ended up in the plan which is also checked.
+pub(crate) fn normalize_sql(sql: &str) -> String {
+    sql.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(';')
+        .to_string()
+}
+
+/// Replays the rows of a cached result.
+struct CachedResultIter {
+    rows: Rows,
+    position: Option<usize>,
+    column_count: usize,
+}
+
+impl TupleIter for CachedResultIter {
+    type E = ExecutionError;
+
+    fn advance(&mut self) -> Result<(), ExecutionError> {
+        self.position = Some(self.position.map_or(0, |position| position + 1));
+        Ok(())
+    }
+
+    fn get(&self) -> Option<(&[Datum], i64)> {
+        self.position
+            .and_then(|position| self.rows.get(position))
+            .map(|(tuple, freq)| (tuple.as_slice(), *freq))
+    }
+
+    fn column_count(&self) -> usize {
+        self.column_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::rel::point_in_time::Values;
+    use data::{DataType, Session};
+    use executor::point_in_time::build_executor;
+
+    fn values_plan(value: i32) -> PointInTimeOperator {
+        PointInTimeOperator::Values(Values {
+            data: vec![vec![Datum::from(value)], vec![Datum::from(value + 1)]],
+            column_count: 1,
+        })
+    }
+
+    fn drain(mut executor: BoxedExecutor) -> Vec<(Vec<Datum<'static>>, i64)> {
+        let mut rows = vec![];
+        while let Some((tuple, freq)) = executor.next().unwrap() {
+            rows.push((tuple.iter().map(Datum::as_static).collect(), freq));
+        }
+        rows
+    }
+
+    #[test]
+    fn test_query_cache() {
+        let session = Arc::new(Session::new(1));
+        let cache = QueryCache::new(1);
+        let fields = vec![Field {
+            qualifier: None,
+            alias: "a".to_string(),
+            data_type: DataType::Integer,
+            nullable: true,
+            origin: None,
+        }];
+        let plan = values_plan(1);
+        let expected = vec![(vec![Datum::from(1)], 1), (vec![Datum::from(2)], 1)];
+
+        assert!(cache.get("select 1", &plan, &fields, &[1]).is_none());
+        let executor = cache
+            .populate(
+                "select 1".to_string(),
+                plan.clone(),
+                fields.clone(),
+                vec![1],
+                build_executor(&session, &plan),
+            )
+            .unwrap();
+        assert_eq!(drain(executor), expected);
+
+        let cached = cache.get("select 1", &plan, &fields, &[1]).unwrap();
+        assert_eq!(drain(cached), expected);
+
+        // Changes to the tables or plan invalidate the entry
+        assert!(cache.get("select 1", &plan, &fields, &[2]).is_none());
+        assert!(cache
+            .get("select 1", &values_plan(2), &fields, &[1])
+            .is_none());
+
+        // Capacity of 1 evicts the old entry
+        cache
+            .populate(
+                "select 2".to_string(),
+                plan.clone(),
+                fields.clone(),
+                vec![1],
+                build_executor(&session, &plan),
+            )
+            .unwrap();
+        assert!(cache.get("select 1", &plan, &fields, &[1]).is_none());
+        assert!(cache.get("select 2", &plan, &fields, &[1]).is_some());
+    }
+
+    #[test]
+    fn test_normalize_sql() {
+        assert_eq!(
+            normalize_sql("  select 1,\n    2 from  foo; "),
+            "select 1, 2 from foo"
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::export::{csv_record, json_record};
+use crate::subscriptions::Subscription;
+use crate::{QueryError, Runtime};
+use catalog::{CatalogError, Sink, SinkFormat};
+use data::{Session, TupleIter};
+use executor::ExecutionError;
+use planner::Field;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+
+/// Sinks don't belong to a connection, their sessions all use this connection id.
+const SINK_CONNECTION_ID: u32 = 0;
+
+impl Runtime {
+    /// Starts all the sinks in the catalog, called on startup.
+    /// Sinks that can't be started (ie their source has since been dropped) are skipped rather
+    /// than stopping the server from starting, they can still be dropped.
+    pub(crate) fn start_sinks(&self) -> Result<(), CatalogError> {
+        let sinks = self.planner.catalog.read().unwrap().sinks()?;
+        for sink in sinks {
+            let _ = self.start_sink(&sink);
+        }
+        Ok(())
+    }
+
+    /// Starts exporting the changes to the source of the sink on a background thread, the
+    /// export runs until the sink is dropped or the runtime is shut down.
+    /// Only changes made from now on are exported.
+    pub(crate) fn start_sink(&self, sink: &Sink) -> Result<(), QueryError> {
+        let session = Arc::new(Session::new(SINK_CONNECTION_ID));
+        *session.current_database.write().unwrap() = sink.database.clone();
+        let (fields, subscription) = self.subscribe_to_table(
+            Arc::clone(&session),
+            sink.source_database.clone(),
+            sink.source_name.clone(),
+        )?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&sink.path)
+            .map_err(ExecutionError::from)?;
+
+        let format = sink.format;
+        thread::spawn(move || {
+            // There's no one to report errors to, they just stop the export
+            let _ = export(subscription, &fields, format, file);
+        });
+
+        let key = (sink.database.clone(), sink.name.clone());
+        self.sinks.lock().unwrap().insert(key, session);
+        Ok(())
+    }
+
+    /// Stops the export of a sink if it's running.
+    pub(crate) fn stop_sink(&self, database: &str, name: &str) {
+        let key = (database.to_string(), name.to_string());
+        if let Some(session) = self.sinks.lock().unwrap().remove(&key) {
+            session.kill_flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Stops the exports of all the sinks, used when shutting down.
+    pub(crate) fn stop_sinks(&self) {
+        for (_, session) in self.sinks.lock().unwrap().drain() {
+            session.kill_flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Appends a record to the file for each change, killing the subscription's session ends the
+/// export.
+fn export(
+    mut subscription: Subscription,
+    fields: &[Field],
+    format: SinkFormat,
+    file: File,
+) -> Result<(), ExecutionError> {
+    let mut writer = BufWriter::new(file);
+    while let Some((tuple, _)) = subscription.next()? {
+        let record = match format {
+            SinkFormat::Json => json_record(tuple, fields),
+            SinkFormat::Csv => csv_record(tuple, fields, b',', b'"'),
+        };
+        writeln!(writer, "{}", record)?;
+        // The subscription blocks until there's more changes so flush as we go
+        writer.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use crate::connection::Connection;
+use catalog::SlowQuery;
+use data::chrono::Utc;
+use data::rust_decimal::prelude::ToPrimitive;
+use data::{Datum, Session};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tracing::{error, warn};
+
+/// The session variable turning the slow query log on or off.
+pub(crate) const SLOW_QUERY_LOG_VARIABLE: &str = "slow_query_log";
+
+/// The session variable holding the number of seconds (fractions allowed) a statement has to run
+/// for to be counted as slow.
+pub(crate) const LONG_QUERY_TIME_VARIABLE: &str = "long_query_time";
+
+impl Connection<'_> {
+    /// Called once the results of the running statement have been sent back to the client,
+    /// statements that took longer than long_query_time are logged and recorded in
+    /// incresql.slow_log.
+    pub fn statement_finished(&self) {
+        if let Some((sql, started)) = self.session.statement_finished() {
+            let duration = started.elapsed();
+            if let Some(long_query_time) = long_query_time(&self.session) {
+                if duration >= long_query_time {
+                    self.log_slow_query(sql, duration);
+                }
+            }
+        }
+    }
+
+    fn log_slow_query(&self, sql: String, duration: Duration) {
+        let duration_ms = duration.as_millis() as i64;
+        let slow_query = SlowQuery {
+            start_time: Utc::now().naive_utc() - data::chrono::Duration::milliseconds(duration_ms),
+            connection_id: self.connection_id,
+            user: self.session.user.read().unwrap().clone(),
+            database: self.session.current_database.read().unwrap().clone(),
+            duration_ms,
+            rows_examined: self.session.rows_examined.load(Ordering::Relaxed) as i64,
+            rows_sent: self.session.rows_sent.load(Ordering::Relaxed) as i64,
+            sql,
+        };
+        warn!(
+            target: "slow_query",
+            connection_id = slow_query.connection_id,
+            user = %slow_query.user,
+            db = %slow_query.database,
+            duration_ms = slow_query.duration_ms,
+            rows_examined = slow_query.rows_examined,
+            rows_sent = slow_query.rows_sent,
+            sql = %slow_query.sql,
+            "Slow query"
+        );
+        // Followers get incresql.slow_log from the leader, writing to it would diverge from it
+        if self.runtime.read_only() {
+            return;
+        }
+        let catalog = self.runtime.planner.catalog.read().unwrap();
+        if let Err(err) = catalog.log_slow_query(&slow_query) {
+            error!(connection_id = self.connection_id, %err, "Couldn't record slow query");
+        }
+    }
+}
+
+/// How long statements can run for before they're slow, None if the slow query log is off.
+fn long_query_time(session: &Session) -> Option<Duration> {
+    let enabled = match session.variable(SLOW_QUERY_LOG_VARIABLE) {
+        Some((Datum::Boolean(enabled), _)) => enabled,
+        Some((Datum::Integer(enabled), _)) => enabled != 0,
+        Some((Datum::BigInt(enabled), _)) => enabled != 0,
+        Some((datum, _)) => datum
+            .as_maybe_text()
+            .map_or(false, |text| text.eq_ignore_ascii_case("ON")),
+        None => false,
+    };
+    if !enabled {
+        return None;
+    }
+    let seconds = match session.variable(LONG_QUERY_TIME_VARIABLE) {
+        Some((Datum::Integer(seconds), _)) => seconds as f64,
+        Some((Datum::BigInt(seconds), _)) => seconds as f64,
+        Some((Datum::Decimal(seconds), _)) => seconds.to_f64().unwrap_or_default(),
+        Some((Datum::Double(seconds), _)) => seconds,
+        _ => return None,
+    };
+    // from_secs_f64 panics on overflow, a year is long enough for anything
+    Some(Duration::from_secs_f64(
+        seconds.max(0.0).min(365.0 * 86400.0),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{QueryError, Runtime};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_slow_query_log() -> Result<(), QueryError> {
+        let runtime = Runtime::new_for_test();
+        let connection = runtime.new_connection();
+        let slow_queries = || {
+            runtime
+                .planner
+                .catalog
+                .read()
+                .unwrap()
+                .slow_queries()
+                .unwrap()
+        };
+
+        // Off by default
+        connection.session.statement_started("SELECT 1");
+        connection.statement_finished();
+        assert!(slow_queries().is_empty());
+
+        let run = |sql: &str| -> Result<(), QueryError> {
+            connection.session.statement_started(sql);
+            let (_fields, mut executor) = connection.execute_statement(sql)?;
+            while executor.next()?.is_some() {
+                connection.session.rows_sent.fetch_add(1, Ordering::Relaxed);
+            }
+            drop(executor);
+            connection.statement_finished();
+            Ok(())
+        };
+        run("SET slow_query_log = 1, long_query_time = 0.5")?;
+        run("SELECT 1")?;
+        assert!(slow_queries().is_empty());
+
+        // Everything's slow from here on, including the SET itself
+        run("SET long_query_time = 0")?;
+        let sql = "SELECT * FROM incresql.databases";
+        run(sql)?;
+
+        let logged = slow_queries();
+        assert_eq!(logged.len(), 2);
+        let slow_query = logged.iter().find(|q| q.sql == sql).unwrap();
+        assert_eq!(slow_query.connection_id, connection.connection_id);
+        assert_eq!(slow_query.rows_examined, 3);
+        assert_eq!(slow_query.rows_sent, 3);
+        Ok(())
+    }
+}
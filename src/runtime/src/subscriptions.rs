@@ -0,0 +1,299 @@
+use crate::materialized_views::execute;
+use crate::query_cache::scanned_tables;
+use crate::{QueryError, Runtime};
+use ast::expr::{ColumnReference, Expression, NamedExpression};
+use ast::rel::logical::{LogicalOperator, Project, TableReference};
+use ast::rel::point_in_time::PointInTimeOperator;
+use catalog::TableOrView;
+use data::{DataType, Datum, LogicalTimestamp, Session, TupleIter};
+use executor::ExecutionError;
+use planner::{Field, FieldOrigin};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::Duration;
+use storage::Table;
+
+/// How often subscriptions check the tables they're watching for writes.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Writes are timestamped before they're committed, so changes can turn up in the log behind
+/// ones that have already been sent. Each time we look we rescan this far back to pick them up.
+const LATE_WRITE_WINDOW_MS: u64 = 10_000;
+
+/// A long lived executor that streams out the changes to a table (or the results of a query) as
+/// they're written, until the statement is killed. Each change comes out as the tuple followed
+/// by the change in its freq and the timestamp of the change.
+/// Rather than blocking on writes we poll the versions of the tables, only looking for changes
+/// when they've been written to. Polling sleeps the thread, so like any other long running
+/// statement subscriptions mustn't be run on an async worker (the server runs statements on
+/// blocking threads).
+pub(crate) struct Subscription {
+    session: Arc<Session>,
+    changes: Changes,
+    tables: Vec<Table>,
+    // The versions of the tables as of the last time we looked for changes
+    versions: Vec<u64>,
+    pending: VecDeque<Vec<Datum<'static>>>,
+    current: Option<Vec<Datum<'static>>>,
+    column_count: usize,
+}
+
+/// Where the changes of a subscription come from.
+enum Changes {
+    /// Read straight out of the log sections of the tables
+    Log {
+        from: LogicalTimestamp,
+        // The changes sent from within the late write window, keyed by timestamp and tuple
+        sent: HashMap<(u64, Vec<Datum<'static>>), i64>,
+    },
+    /// Worked out by rerunning the query and diffing against the previous results
+    Query {
+        plan: Box<PointInTimeOperator>,
+        results: HashMap<Vec<Datum<'static>>, i64>,
+    },
+}
+
+impl Subscription {
+    /// Subscribes to the changes made to the tables, ie a table and/or its partitions.
+    pub(crate) fn tail(session: Arc<Session>, tables: Vec<Table>, column_count: usize) -> Self {
+        let versions = tables.iter().map(Table::version).collect();
+        Subscription {
+            session,
+            changes: Changes::Log {
+                from: LogicalTimestamp::now(),
+                sent: HashMap::new(),
+            },
+            tables,
+            versions,
+            pending: VecDeque::new(),
+            current: None,
+            column_count,
+        }
+    }
+
+    /// Subscribes to the changes in the results of the plan, tables being the tables it reads.
+    pub(crate) fn query(
+        session: Arc<Session>,
+        plan: PointInTimeOperator,
+        tables: Vec<Table>,
+        column_count: usize,
+    ) -> Result<Self, ExecutionError> {
+        // The versions are read first so anything written while we run the query is picked up
+        let versions = tables.iter().map(Table::version).collect();
+        let results = execute(&session, &plan)?.into_iter().collect();
+        Ok(Subscription {
+            session,
+            changes: Changes::Query {
+                plan: Box::new(plan),
+                results,
+            },
+            tables,
+            versions,
+            pending: VecDeque::new(),
+            current: None,
+            column_count,
+        })
+    }
+
+    /// Queues up any changes since we last looked.
+    fn find_changes(&mut self) -> Result<(), ExecutionError> {
+        let now = LogicalTimestamp::now();
+        match &mut self.changes {
+            Changes::Log { from, sent } => {
+                let mut changes = vec![];
+                for table in &self.tables {
+                    let mut iter = table.log_scan(*from);
+                    while let Some((tuple, freq)) = iter.next()? {
+                        let tuple: Vec<_> = tuple.iter().map(|datum| datum.as_static()).collect();
+                        let timestamp = iter.timestamp();
+                        let sent_freq = sent.entry((timestamp.ms, tuple.clone())).or_insert(0);
+                        if freq != *sent_freq {
+                            changes.push((timestamp, tuple, freq - *sent_freq));
+                            *sent_freq = freq;
+                        }
+                    }
+                }
+                // The changes for partitioned tables are spread across the partitions
+                changes.sort_by_key(|(timestamp, _, _)| *timestamp);
+                self.pending.extend(
+                    changes
+                        .into_iter()
+                        .map(|(timestamp, tuple, freq)| change(tuple, freq, timestamp)),
+                );
+
+                let horizon = LogicalTimestamp::new(now.ms.saturating_sub(LATE_WRITE_WINDOW_MS));
+                sent.retain(|(ms, _), _| *ms >= horizon.ms);
+                *from = (*from).max(horizon);
+            }
+            Changes::Query { plan, results } => {
+                let new_results: HashMap<_, _> =
+                    execute(&self.session, plan)?.into_iter().collect();
+                let mut diff = new_results.clone();
+                for (tuple, freq) in results.iter() {
+                    *diff.entry(tuple.clone()).or_insert(0) -= freq;
+                }
+                self.pending.extend(
+                    diff.into_iter()
+                        .filter(|(_, freq)| *freq != 0)
+                        .map(|(tuple, freq)| change(tuple, freq, now)),
+                );
+                *results = new_results;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TupleIter for Subscription {
+    type E = ExecutionError;
+
+    fn advance(&mut self) -> Result<(), ExecutionError> {
+        loop {
+            // Killing the statement is the only way a subscription ends
+            if self.session.kill_flag.load(Ordering::Relaxed) {
+                self.current = None;
+                return Ok(());
+            }
+            if let Some(tuple) = self.pending.pop_front() {
+                self.current = Some(tuple);
+                return Ok(());
+            }
+
+            let versions: Vec<_> = self.tables.iter().map(Table::version).collect();
+            if versions == self.versions {
+                sleep(POLL_INTERVAL);
+            } else {
+                self.versions = versions;
+                self.find_changes()?;
+            }
+        }
+    }
+
+    fn get(&self) -> Option<(&[Datum], i64)> {
+        self.current.as_ref().map(|tuple| (tuple.as_slice(), 1))
+    }
+
+    fn column_count(&self) -> usize {
+        self.column_count + 2
+    }
+}
+
+impl Runtime {
+    /// Subscribes to the changes made to a table or view, returning the fields of the changes
+    /// along with the subscription.
+    pub(crate) fn subscribe_to_table(
+        &self,
+        session: Arc<Session>,
+        database: String,
+        name: String,
+    ) -> Result<(Vec<Field>, Subscription), QueryError> {
+        let item = {
+            let catalog = self.planner.catalog.read().unwrap();
+            catalog.item(&database, &name)?
+        };
+        // Plain views and external tables have no storage of their own so we fall back to
+        // rerunning them
+        let table = match item.item {
+            TableOrView::Table(table) => table,
+            TableOrView::View(_) | TableOrView::External(_) => {
+                return self.subscribe_to_query(session, select_star(database, name))
+            }
+        };
+        let tables = match item.partitioning {
+            Some(partitioning) => partitioning
+                .partitions
+                .into_iter()
+                .map(|partition| partition.table)
+                .collect(),
+            None => vec![table],
+        };
+        let column_not_null = &item.column_not_null;
+        let fields = item
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, (alias, data_type))| Field {
+                qualifier: None,
+                alias: alias.clone(),
+                data_type: *data_type,
+                nullable: column_not_null.get(idx) != Some(&true),
+                origin: Some(FieldOrigin {
+                    database: database.clone(),
+                    table_alias: name.clone(),
+                    table: name.clone(),
+                    column: alias.clone(),
+                }),
+            })
+            .collect();
+        let subscription = Subscription::tail(session, tables, item.columns.len());
+        Ok((change_fields(fields), subscription))
+    }
+
+    /// Subscribes to the changes to the results of the query as the tables it reads are
+    /// written to.
+    pub(crate) fn subscribe_to_query(
+        &self,
+        session: Arc<Session>,
+        query: LogicalOperator,
+    ) -> Result<(Vec<Field>, Subscription), QueryError> {
+        let plan = self.planner.plan_for_point_in_time(query, &session)?;
+        let tables = scanned_tables(&plan.operator).ok_or(QueryError::SubscriptionNotSupported(
+            "queries reading from files",
+        ))?;
+        let subscription = Subscription::query(session, plan.operator, tables, plan.fields.len())?;
+        Ok((change_fields(plan.fields), subscription))
+    }
+}
+
+/// The fields returned by a subscription, the fields of the table/query followed by the
+/// change in freq and the timestamp.
+fn change_fields(mut fields: Vec<Field>) -> Vec<Field> {
+    fields.push(Field {
+        qualifier: None,
+        alias: "freq".to_string(),
+        data_type: DataType::BigInt,
+        nullable: false,
+        origin: None,
+    });
+    fields.push(Field {
+        qualifier: None,
+        alias: "timestamp".to_string(),
+        data_type: DataType::Timestamp,
+        nullable: false,
+        origin: None,
+    });
+    fields
+}
+
+fn change(
+    mut tuple: Vec<Datum<'static>>,
+    freq: i64,
+    timestamp: LogicalTimestamp,
+) -> Vec<Datum<'static>> {
+    tuple.push(Datum::from(freq));
+    // Timestamps are stored as ms since the epoch
+    tuple.push(Datum::from(timestamp.ms as i64));
+    tuple
+}
+
+/// SELECT * FROM database.table
+pub(crate) fn select_star(database: String, table: String) -> LogicalOperator {
+    LogicalOperator::Project(Project {
+        distinct: false,
+        expressions: vec![NamedExpression {
+            alias: None,
+            expression: Expression::ColumnReference(ColumnReference {
+                qualifier: None,
+                alias: "*".to_string(),
+                star: true,
+            }),
+        }],
+        source: Box::new(LogicalOperator::TableReference(TableReference {
+            database: Some(database),
+            table,
+            as_of: None,
+        })),
+    })
+}
@@ -0,0 +1,162 @@
+use crate::connection::Connection;
+use crate::materialized_views::{maintain_views, TableChange};
+use crate::QueryError;
+use ast::rel::point_in_time::TableInsert;
+use ast::statement::{Savepoint, Transaction};
+use executor::point_in_time::build_transactional_insert;
+use executor::ExecutionError;
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+/// A transaction opened with BEGIN, rather than being committed as they run the writes made by
+/// inserts are buffered up and only committed (all at once) on COMMIT.
+/// Reads within the transaction don't see its uncommitted writes.
+#[derive(Default)]
+pub(crate) struct OpenTransaction {
+    writes: Arc<Mutex<storage::Transaction>>,
+    // In the order they were created, along with the number of writes made before each
+    savepoints: Vec<(String, usize)>,
+    // The ids of the tables written to, for maintaining any materialized views on commit
+    tables: Vec<u32>,
+}
+
+impl Debug for OpenTransaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenTransaction")
+            .field("savepoints", &self.savepoints)
+            .field("tables", &self.tables)
+            .finish()
+    }
+}
+
+impl Connection<'_> {
+    pub(crate) fn execute_transaction(&self, transaction: Transaction) -> Result<(), QueryError> {
+        match transaction {
+            // Like mysql, beginning a transaction commits any already open
+            Transaction::Begin => {
+                self.commit()?;
+                *self.transaction.lock().unwrap() = Some(OpenTransaction::default());
+            }
+            Transaction::Commit => self.commit()?,
+            Transaction::Rollback => *self.transaction.lock().unwrap() = None,
+        }
+        Ok(())
+    }
+
+    /// Without an open transaction every statement is committed as it runs, so like mysql in
+    /// autocommit mode creating a savepoint is a no-op and there's never a savepoint to roll
+    /// back to or release.
+    pub(crate) fn execute_savepoint(&self, savepoint: Savepoint) -> Result<(), QueryError> {
+        let mut transaction = self.transaction.lock().unwrap();
+        let transaction = match transaction.as_mut() {
+            Some(transaction) => transaction,
+            None => {
+                return match savepoint {
+                    Savepoint::Create(_) => Ok(()),
+                    Savepoint::RollbackTo(name) | Savepoint::Release(name) => {
+                        Err(QueryError::SavepointNotFound(name))
+                    }
+                }
+            }
+        };
+        match savepoint {
+            Savepoint::Create(name) => {
+                // Reusing a name replaces the old savepoint
+                transaction
+                    .savepoints
+                    .retain(|(savepoint, _)| !savepoint.eq_ignore_ascii_case(&name));
+                let offset = transaction.writes.lock().unwrap().savepoint();
+                transaction.savepoints.push((name, offset));
+            }
+            Savepoint::RollbackTo(name) => {
+                let idx = savepoint_idx(transaction, name)?;
+                let offset = transaction.savepoints[idx].1;
+                transaction.writes.lock().unwrap().rollback_to(offset);
+                // The savepoint itself is kept, only the ones made after it are removed
+                transaction.savepoints.truncate(idx + 1);
+            }
+            Savepoint::Release(name) => {
+                let idx = savepoint_idx(transaction, name)?;
+                transaction.savepoints.truncate(idx);
+            }
+        }
+        Ok(())
+    }
+
+    /// Commits the open transaction (if there is one) and brings any materialized views up to
+    /// date with what it wrote.
+    pub(crate) fn commit(&self) -> Result<(), QueryError> {
+        let transaction = match self.transaction.lock().unwrap().take() {
+            Some(transaction) => transaction,
+            None => return Ok(()),
+        };
+        let writes = std::mem::take(&mut *transaction.writes.lock().unwrap());
+        writes.commit().map_err(ExecutionError::from)?;
+        if transaction.tables.is_empty() {
+            return Ok(());
+        }
+        // The views need to see the rows just written
+        self.session
+            .read_timestamp
+            .store(self.runtime.read_timestamp().ms, Ordering::Relaxed);
+        maintain_views(
+            self.runtime,
+            &self.session,
+            TableChange {
+                tables: transaction.tables,
+                delta: None,
+            },
+        )
+    }
+
+    /// Runs the insert as part of the open transaction, returning false if there isn't one.
+    /// A failed insert has its writes rolled back, leaving those of earlier statements alone.
+    pub(crate) fn execute_transactional_insert(
+        &self,
+        insert: &TableInsert,
+    ) -> Result<bool, QueryError> {
+        let mut transaction = self.transaction.lock().unwrap();
+        let transaction = match transaction.as_mut() {
+            Some(transaction) => transaction,
+            None => return Ok(false),
+        };
+        let offset = transaction.writes.lock().unwrap().savepoint();
+        let mut executor = build_transactional_insert(&self.session, insert, &transaction.writes);
+        if let Err(err) = executor.next() {
+            transaction.writes.lock().unwrap().rollback_to(offset);
+            return Err(err.into());
+        }
+        for table in written_tables(insert) {
+            if !transaction.tables.contains(&table) {
+                transaction.tables.push(table);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// The ids of all the tables an insert writes to, the target table first followed by any
+/// partitions and index tables.
+pub(crate) fn written_tables(insert: &TableInsert) -> Vec<u32> {
+    let mut tables = vec![insert.table.id()];
+    if let Some(partitioning) = &insert.partitioning {
+        tables.extend(partitioning.partitions.iter().map(|p| p.table.id()));
+    }
+    tables.extend(insert.fulltext_indexes.iter().map(|index| index.table.id()));
+    tables.extend(
+        insert
+            .secondary_indexes
+            .iter()
+            .map(|index| index.table.id()),
+    );
+    tables
+}
+
+fn savepoint_idx(transaction: &OpenTransaction, name: String) -> Result<usize, QueryError> {
+    transaction
+        .savepoints
+        .iter()
+        .position(|(savepoint, _)| savepoint.eq_ignore_ascii_case(&name))
+        .ok_or(QueryError::SavepointNotFound(name))
+}
@@ -0,0 +1,114 @@
+use ast::expr::{Cast, ColumnReference, Expression, FunctionCall};
+use catalog::{FunctionBody, UserFunction};
+use data::DataType;
+use functions::registry::Registry;
+use functions::{CompoundFunction, CompoundFunctionArg, UdfError};
+use parser::parse_expression;
+
+/// Registers a user defined function from the catalog with the function registry.
+pub(crate) fn register_user_function(
+    registry: &Registry,
+    function: &UserFunction,
+) -> Result<(), UdfError> {
+    let arg_types = function
+        .args
+        .iter()
+        .map(|(_name, datatype)| *datatype)
+        .collect();
+    match &function.body {
+        FunctionBody::Wasm(module) => {
+            registry.register_wasm_function(&function.name, arg_types, function.ret, module.clone())
+        }
+        FunctionBody::Sql(sql) => {
+            let expression =
+                parse_expression(sql).map_err(|err| UdfError::InvalidBody(err.to_string()))?;
+            let body = compound_arg(registry, &function.args, expression)?;
+            registry.register_sql_function(&function.name, arg_types, function.ret, body)
+        }
+    }
+}
+
+/// Converts the body of a sql function into the form the planner inlines, references to the
+/// args become the inputs.
+fn compound_arg(
+    registry: &Registry,
+    args: &[(String, DataType)],
+    expression: Expression,
+) -> Result<CompoundFunctionArg, UdfError> {
+    match expression {
+        Expression::Constant(datum, datatype) => Ok(CompoundFunctionArg::Constant(datum, datatype)),
+        Expression::ColumnReference(ColumnReference {
+            qualifier: None,
+            alias,
+            ..
+        }) => args
+            .iter()
+            .position(|(name, _)| name == &alias)
+            .map(CompoundFunctionArg::Input)
+            .ok_or_else(|| UdfError::InvalidBody(format!("Unknown argument {}", alias))),
+        Expression::FunctionCall(FunctionCall {
+            function_name,
+            args: function_args,
+        }) => Ok(CompoundFunctionArg::Function(CompoundFunction {
+            function_name: registry.function_name(&function_name).ok_or_else(|| {
+                UdfError::InvalidBody(format!("Function {} not found", function_name))
+            })?,
+            args: function_args
+                .into_iter()
+                .map(|arg| compound_arg(registry, args, arg))
+                .collect::<Result<_, _>>()?,
+        })),
+        // Casts go through the cast functions directly, so like TRY_CAST they give null rather
+        // than an error in strict mode
+        Expression::Cast(Cast { expr, datatype, .. }) => {
+            Ok(CompoundFunctionArg::Function(CompoundFunction {
+                function_name: datatype.cast_function(),
+                args: vec![compound_arg(registry, args, *expr)?],
+            }))
+        }
+        expression => Err(UdfError::InvalidBody(format!(
+            "{} can't be used in a function",
+            expression
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::Datum;
+
+    #[test]
+    fn test_compound_arg() {
+        let registry = Registry::new(true);
+        let args = [("a".to_string(), DataType::Integer)];
+        let compound = |sql: &str| compound_arg(&registry, &args, parse_expression(sql).unwrap());
+
+        assert_eq!(
+            compound("a * 2"),
+            Ok(CompoundFunctionArg::Function(CompoundFunction {
+                function_name: "*",
+                args: vec![
+                    CompoundFunctionArg::Input(0),
+                    CompoundFunctionArg::Constant(Datum::from(2), DataType::Integer)
+                ]
+            }))
+        );
+        assert_eq!(
+            compound("CAST(a AS TEXT)"),
+            Ok(CompoundFunctionArg::Function(CompoundFunction {
+                function_name: "to_text",
+                args: vec![CompoundFunctionArg::Input(0)]
+            }))
+        );
+        assert_eq!(
+            compound("b + 1"),
+            Err(UdfError::InvalidBody("Unknown argument b".to_string()))
+        );
+        assert_eq!(
+            compound("nope(a)"),
+            Err(UdfError::InvalidBody("Function nope not found".to_string()))
+        );
+        assert!(compound("t.a").is_err());
+    }
+}
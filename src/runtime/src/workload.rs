@@ -0,0 +1,239 @@
+use data::{Datum, TupleIter};
+use executor::point_in_time::BoxedExecutor;
+use executor::ExecutionError;
+use serde::Deserialize;
+use std::sync::{Arc, Condvar, Mutex};
+
+/// The classes of work the runtime schedules.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WorkloadClass {
+    /// Statements run by connections, these always take priority.
+    Interactive,
+    /// Background style work, ie compactions and table analysis.
+    Maintenance,
+}
+
+/// The concurrency caps for each of the workload classes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkloadConfig {
+    /// The max number of interactive statements running at once, 0 for no limit.
+    pub interactive_concurrency: usize,
+    /// The max number of maintenance tasks running at once, 0 for no limit.
+    pub maintenance_concurrency: usize,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        WorkloadConfig {
+            interactive_concurrency: 0,
+            maintenance_concurrency: 1,
+        }
+    }
+}
+
+/// Hands out permits to run work, blocking when a class is at its concurrency cap.
+/// Maintenance work is also held back while there's interactive work waiting for a permit so
+/// large compactions etc don't starve connections.
+#[derive(Debug)]
+pub struct Scheduler {
+    config: WorkloadConfig,
+    state: Mutex<SchedulerState>,
+    condvar: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct SchedulerState {
+    interactive_running: usize,
+    interactive_waiting: usize,
+    maintenance_running: usize,
+}
+
+impl Scheduler {
+    pub fn new(config: WorkloadConfig) -> Self {
+        Scheduler {
+            config,
+            state: Mutex::new(SchedulerState::default()),
+            condvar: Condvar::new(),
+        }
+    }
+
+    pub fn config(&self) -> WorkloadConfig {
+        self.config
+    }
+
+    /// Waits until there's capacity to run work of the given class.
+    /// The capacity is given back once the returned permit is dropped.
+    pub fn admit(self: &Arc<Self>, class: WorkloadClass) -> WorkloadPermit {
+        let mut state = self.state.lock().unwrap();
+        if class == WorkloadClass::Interactive {
+            state.interactive_waiting += 1;
+        }
+        while !self.has_capacity(&state, class) {
+            state = self.condvar.wait(state).unwrap();
+        }
+        if class == WorkloadClass::Interactive {
+            state.interactive_waiting -= 1;
+        }
+        self.take(&mut state, class)
+    }
+
+    /// Like admit but returns None instead of waiting.
+    pub fn try_admit(self: &Arc<Self>, class: WorkloadClass) -> Option<WorkloadPermit> {
+        let mut state = self.state.lock().unwrap();
+        if self.has_capacity(&state, class) {
+            Some(self.take(&mut state, class))
+        } else {
+            None
+        }
+    }
+
+    fn has_capacity(&self, state: &SchedulerState, class: WorkloadClass) -> bool {
+        match class {
+            WorkloadClass::Interactive => under_cap(
+                state.interactive_running,
+                self.config.interactive_concurrency,
+            ),
+            WorkloadClass::Maintenance => {
+                state.interactive_waiting == 0
+                    && under_cap(
+                        state.maintenance_running,
+                        self.config.maintenance_concurrency,
+                    )
+            }
+        }
+    }
+
+    fn take(self: &Arc<Self>, state: &mut SchedulerState, class: WorkloadClass) -> WorkloadPermit {
+        match class {
+            WorkloadClass::Interactive => state.interactive_running += 1,
+            WorkloadClass::Maintenance => state.maintenance_running += 1,
+        }
+        WorkloadPermit {
+            scheduler: Arc::clone(self),
+            class,
+        }
+    }
+
+    fn release(&self, class: WorkloadClass) {
+        let mut state = self.state.lock().unwrap();
+        match class {
+            WorkloadClass::Interactive => state.interactive_running -= 1,
+            WorkloadClass::Maintenance => state.maintenance_running -= 1,
+        }
+        self.condvar.notify_all();
+    }
+}
+
+fn under_cap(running: usize, cap: usize) -> bool {
+    cap == 0 || running < cap
+}
+
+/// A slot to run work in, given back to the scheduler on drop.
+#[derive(Debug)]
+pub struct WorkloadPermit {
+    scheduler: Arc<Scheduler>,
+    class: WorkloadClass,
+}
+
+impl Drop for WorkloadPermit {
+    fn drop(&mut self) {
+        self.scheduler.release(self.class);
+    }
+}
+
+/// Wraps the executor for a query so that its permit is held until the results have been
+/// consumed (or the executor dropped).
+pub struct PermittedExecutor {
+    executor: BoxedExecutor,
+    _permit: WorkloadPermit,
+}
+
+impl PermittedExecutor {
+    pub fn new(executor: BoxedExecutor, permit: WorkloadPermit) -> Self {
+        PermittedExecutor {
+            executor,
+            _permit: permit,
+        }
+    }
+}
+
+impl TupleIter for PermittedExecutor {
+    type E = ExecutionError;
+
+    fn advance(&mut self) -> Result<(), ExecutionError> {
+        self.executor.advance()
+    }
+
+    fn get(&self) -> Option<(&[Datum], i64)> {
+        self.executor.get()
+    }
+
+    fn column_count(&self) -> usize {
+        self.executor.column_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_concurrency_caps() {
+        let scheduler = Arc::new(Scheduler::new(WorkloadConfig {
+            interactive_concurrency: 2,
+            maintenance_concurrency: 1,
+        }));
+
+        let maintenance = scheduler.try_admit(WorkloadClass::Maintenance);
+        assert!(maintenance.is_some());
+        assert!(scheduler.try_admit(WorkloadClass::Maintenance).is_none());
+
+        let interactive_1 = scheduler.try_admit(WorkloadClass::Interactive);
+        let interactive_2 = scheduler.try_admit(WorkloadClass::Interactive);
+        assert!(interactive_1.is_some() && interactive_2.is_some());
+        assert!(scheduler.try_admit(WorkloadClass::Interactive).is_none());
+
+        drop(maintenance);
+        drop(interactive_1);
+        assert!(scheduler.try_admit(WorkloadClass::Maintenance).is_some());
+        assert!(scheduler.try_admit(WorkloadClass::Interactive).is_some());
+    }
+
+    #[test]
+    fn test_no_limit() {
+        let scheduler = Arc::new(Scheduler::new(WorkloadConfig {
+            interactive_concurrency: 0,
+            maintenance_concurrency: 0,
+        }));
+        let permits: Vec<_> = (0..100)
+            .map(|_| scheduler.admit(WorkloadClass::Maintenance))
+            .collect();
+        assert_eq!(permits.len(), 100);
+    }
+
+    #[test]
+    fn test_interactive_waiting_blocks_maintenance() {
+        let scheduler = Arc::new(Scheduler::new(WorkloadConfig {
+            interactive_concurrency: 1,
+            maintenance_concurrency: 1,
+        }));
+        let interactive = scheduler.admit(WorkloadClass::Interactive);
+
+        let waiting_scheduler = Arc::clone(&scheduler);
+        let waiter = thread::spawn(move || {
+            waiting_scheduler.admit(WorkloadClass::Interactive);
+        });
+        while scheduler.state.lock().unwrap().interactive_waiting == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        assert!(scheduler.try_admit(WorkloadClass::Maintenance).is_none());
+
+        drop(interactive);
+        waiter.join().unwrap();
+        assert!(scheduler.try_admit(WorkloadClass::Maintenance).is_some());
+    }
+}
@@ -0,0 +1,391 @@
+use data::chrono::{NaiveDate, NaiveDateTime};
+use data::rust_decimal::prelude::ToPrimitive;
+use data::{DataType, Datum};
+use runtime::Field;
+
+/// The arrow types our types are sent as.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArrowType {
+    Boolean,
+    Int { bit_width: u8, signed: bool },
+    Float64,
+    Binary,
+    Utf8,
+    // Days since the epoch
+    Date32,
+    // Milliseconds since the epoch, without a time zone
+    TimestampMillisecond,
+}
+
+impl ArrowType {
+    /// The width in bytes of each value, None for the variable width types and booleans which
+    /// are stored as a bitmap.
+    fn byte_width(self) -> Option<usize> {
+        match self {
+            ArrowType::Int { bit_width, .. } => Some(bit_width as usize / 8),
+            ArrowType::Float64 | ArrowType::TimestampMillisecond => Some(8),
+            ArrowType::Date32 => Some(4),
+            ArrowType::Boolean | ArrowType::Binary | ArrowType::Utf8 => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ArrowField {
+    pub name: String,
+    pub arrow_type: ArrowType,
+    pub nullable: bool,
+}
+
+/// The arrow type each of our types is sent as. The sized ints map straight across, decimals
+/// are sent as text so they don't lose any precision and anything without an arrow
+/// equivalent (json, vectors etc) is sent as the text a mysql client would see.
+pub fn arrow_type(datatype: DataType) -> ArrowType {
+    let int = |bit_width, signed| ArrowType::Int { bit_width, signed };
+    match datatype {
+        DataType::Boolean => ArrowType::Boolean,
+        DataType::TinyInt => int(8, true),
+        DataType::SmallInt => int(16, true),
+        DataType::Integer => int(32, true),
+        DataType::BigInt => int(64, true),
+        DataType::UnsignedTinyInt => int(8, false),
+        DataType::UnsignedSmallInt => int(16, false),
+        DataType::UnsignedInteger => int(32, false),
+        DataType::UnsignedBigInt => int(64, false),
+        DataType::Double => ArrowType::Float64,
+        DataType::ByteA => ArrowType::Binary,
+        DataType::Date => ArrowType::Date32,
+        DataType::Timestamp => ArrowType::TimestampMillisecond,
+        DataType::Null
+        | DataType::Decimal(..)
+        | DataType::Text
+        | DataType::Json
+        | DataType::JsonPath
+        | DataType::Vector(_) => ArrowType::Utf8,
+    }
+}
+
+/// The arrow schema for a query's results.
+pub fn arrow_schema(fields: &[Field]) -> Vec<ArrowField> {
+    fields
+        .iter()
+        .map(|field| ArrowField {
+            name: field.alias.clone(),
+            arrow_type: arrow_type(field.data_type),
+            nullable: field.nullable,
+        })
+        .collect()
+}
+
+/// A column of a record batch laid out as per the arrow columnar format.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Column {
+    pub null_count: usize,
+    // A bit per row, set for the rows that aren't null
+    pub validity: Vec<u8>,
+    // The values for the fixed width types (a bitmap for booleans) or the i32 offsets into
+    // data for binary and utf8.
+    pub values: Vec<u8>,
+    // The bytes of the binary and utf8 values
+    pub data: Vec<u8>,
+    variable_width: bool,
+}
+
+impl Column {
+    /// The buffers in the order arrow expects them.
+    pub fn buffers(&self) -> Vec<&[u8]> {
+        if self.variable_width {
+            vec![&self.validity, &self.values, &self.data]
+        } else {
+            vec![&self.validity, &self.values]
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct RecordBatch {
+    pub rows: usize,
+    pub columns: Vec<Column>,
+}
+
+/// Builds up record batches from tuples, rows are transposed into columns as they're pushed.
+pub struct BatchBuilder {
+    schema: Vec<ArrowField>,
+    datatypes: Vec<DataType>,
+    columns: Vec<Column>,
+    capacity: usize,
+    rows: usize,
+}
+
+impl BatchBuilder {
+    /// Creates a builder for batches of up to capacity rows.
+    pub fn new(fields: &[Field], capacity: usize) -> Self {
+        let schema = arrow_schema(fields);
+        let columns = schema
+            .iter()
+            .map(|field| new_column(field.arrow_type))
+            .collect();
+        BatchBuilder {
+            schema,
+            datatypes: fields.iter().map(|field| field.data_type).collect(),
+            columns,
+            capacity,
+            rows: 0,
+        }
+    }
+
+    pub fn schema(&self) -> &[ArrowField] {
+        &self.schema
+    }
+
+    /// Appends the tuple freq times, negative frequencies (ie from TAIL) are skipped.
+    pub fn push(&mut self, tuple: &[Datum], freq: i64) {
+        for _ in 0..freq {
+            for (((column, field), datum), datatype) in self
+                .columns
+                .iter_mut()
+                .zip(&self.schema)
+                .zip(tuple)
+                .zip(&self.datatypes)
+            {
+                append(column, field.arrow_type, self.rows, datum, *datatype);
+            }
+            self.rows += 1;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Whether the batch has reached its capacity and should be sent on its way.
+    pub fn is_full(&self) -> bool {
+        self.rows >= self.capacity
+    }
+
+    /// Takes the rows pushed so far as a record batch, leaving the builder empty.
+    pub fn finish(&mut self) -> RecordBatch {
+        let columns = self
+            .columns
+            .iter_mut()
+            .zip(&self.schema)
+            .map(|(column, field)| std::mem::replace(column, new_column(field.arrow_type)))
+            .collect();
+        let rows = std::mem::replace(&mut self.rows, 0);
+        RecordBatch { rows, columns }
+    }
+}
+
+fn new_column(arrow_type: ArrowType) -> Column {
+    match arrow_type {
+        // The offsets start off with the offset of the first value
+        ArrowType::Binary | ArrowType::Utf8 => Column {
+            values: 0_i32.to_le_bytes().to_vec(),
+            variable_width: true,
+            ..Column::default()
+        },
+        _ => Column::default(),
+    }
+}
+
+/// Appends the datum as the row'th value of the column, the sized ints are stored in the
+/// smallest of int, bigint or decimal that fits them so they need narrowing back down.
+fn append(
+    column: &mut Column,
+    arrow_type: ArrowType,
+    row: usize,
+    datum: &Datum,
+    datatype: DataType,
+) {
+    let unsigned_bigint = ArrowType::Int {
+        bit_width: 64,
+        signed: false,
+    };
+    let valid = match datum {
+        Datum::Null => false,
+        // Unsigned bigints too big for a u64 have nowhere to go
+        datum if arrow_type == unsigned_bigint => datum.as_decimal().to_u64().is_some(),
+        _ => true,
+    };
+    push_bit(&mut column.validity, row, valid);
+    if !valid {
+        column.null_count += 1;
+        // Nulls still take up a slot
+        match arrow_type.byte_width() {
+            Some(byte_width) => column.values.resize(column.values.len() + byte_width, 0),
+            None if arrow_type == ArrowType::Boolean => push_bit(&mut column.values, row, false),
+            None => push_bytes(column, &[]),
+        }
+        return;
+    }
+
+    match arrow_type {
+        ArrowType::Boolean => push_bit(&mut column.values, row, datum.as_boolean()),
+        ArrowType::Int { bit_width: 8, .. } => column.values.push(datum.as_integer() as u8),
+        ArrowType::Int { bit_width: 16, .. } => column
+            .values
+            .extend_from_slice(&(datum.as_integer() as u16).to_le_bytes()),
+        ArrowType::Int {
+            bit_width: 32,
+            signed: true,
+        } => column
+            .values
+            .extend_from_slice(&datum.as_integer().to_le_bytes()),
+        ArrowType::Int { bit_width: 32, .. } => column
+            .values
+            .extend_from_slice(&(datum.as_bigint() as u32).to_le_bytes()),
+        ArrowType::Int { signed: true, .. } => column
+            .values
+            .extend_from_slice(&datum.as_bigint().to_le_bytes()),
+        ArrowType::Int { .. } => column
+            .values
+            .extend_from_slice(&datum.as_decimal().to_u64().unwrap().to_le_bytes()),
+        ArrowType::Float64 => column
+            .values
+            .extend_from_slice(&datum.as_double().to_le_bytes()),
+        ArrowType::Date32 => column
+            .values
+            .extend_from_slice(&days_since_epoch(datum.as_date()).to_le_bytes()),
+        ArrowType::TimestampMillisecond => column
+            .values
+            .extend_from_slice(&millis_since_epoch(datum.as_timestamp()).to_le_bytes()),
+        ArrowType::Binary => push_bytes(column, datum.as_bytea()),
+        ArrowType::Utf8 => match datum.as_maybe_text() {
+            Some(text) if datatype == DataType::Text => push_bytes(column, text.as_bytes()),
+            _ => push_bytes(column, datum.typed_with(datatype).to_string().as_bytes()),
+        },
+    }
+}
+
+/// Sets the idx'th bit of the bitmap, growing the bitmap as needed.
+fn push_bit(bitmap: &mut Vec<u8>, idx: usize, set: bool) {
+    if idx % 8 == 0 {
+        bitmap.push(0);
+    }
+    if set {
+        bitmap[idx / 8] |= 1 << (idx % 8);
+    }
+}
+
+fn push_bytes(column: &mut Column, bytes: &[u8]) {
+    column.data.extend_from_slice(bytes);
+    column
+        .values
+        .extend_from_slice(&(column.data.len() as i32).to_le_bytes());
+}
+
+fn days_since_epoch(date: NaiveDate) -> i32 {
+    date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+        .num_days() as i32
+}
+
+fn millis_since_epoch(timestamp: NaiveDateTime) -> i64 {
+    timestamp.timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::rust_decimal::Decimal;
+
+    fn field(alias: &str, data_type: DataType) -> Field {
+        Field {
+            qualifier: None,
+            alias: alias.to_string(),
+            data_type,
+            nullable: true,
+            origin: None,
+        }
+    }
+
+    #[test]
+    fn test_arrow_schema() {
+        let schema = arrow_schema(&[
+            field("a", DataType::SmallInt),
+            field("b", DataType::Decimal(10, 2)),
+            field("c", DataType::Timestamp),
+        ]);
+        assert_eq!(
+            schema,
+            vec![
+                ArrowField {
+                    name: "a".to_string(),
+                    arrow_type: ArrowType::Int {
+                        bit_width: 16,
+                        signed: true
+                    },
+                    nullable: true
+                },
+                ArrowField {
+                    name: "b".to_string(),
+                    arrow_type: ArrowType::Utf8,
+                    nullable: true
+                },
+                ArrowField {
+                    name: "c".to_string(),
+                    arrow_type: ArrowType::TimestampMillisecond,
+                    nullable: true
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_batch_builder() {
+        let fields = [
+            field("a", DataType::SmallInt),
+            field("b", DataType::Decimal(10, 2)),
+            field("c", DataType::Date),
+            field("d", DataType::UnsignedBigInt),
+            field("e", DataType::Boolean),
+        ];
+        let mut builder = BatchBuilder::new(&fields, 2);
+        assert!(builder.is_empty());
+        builder.push(
+            &[
+                Datum::from(-5),
+                Datum::from(Decimal::new(12345, 2)),
+                Datum::from(NaiveDate::from_ymd(1970, 1, 11)),
+                Datum::from(Decimal::from(u64::MAX)),
+                Datum::from(true),
+            ],
+            2,
+        );
+        assert!(builder.is_full());
+        let nulls = vec![Datum::Null; 5];
+        builder.push(&nulls, -1);
+
+        let batch = builder.finish();
+        assert!(builder.is_empty());
+        assert_eq!(batch.rows, 2);
+        assert_eq!(
+            batch.columns[0].values,
+            [(-5_i16).to_le_bytes(); 2].concat()
+        );
+        assert_eq!(
+            batch.columns[1].values,
+            [0, 0, 0, 0, 6, 0, 0, 0, 12, 0, 0, 0]
+        );
+        assert_eq!(batch.columns[1].data, b"123.45123.45");
+        assert_eq!(batch.columns[2].values, [10_i32.to_le_bytes(); 2].concat());
+        assert_eq!(
+            batch.columns[3].values,
+            [u64::MAX.to_le_bytes(); 2].concat()
+        );
+        assert_eq!(batch.columns[4].values, [0b11]);
+        assert!(batch
+            .columns
+            .iter()
+            .all(|column| column.validity == [0b11] && column.null_count == 0));
+
+        builder.push(&nulls, 1);
+        let batch = builder.finish();
+        assert_eq!(batch.rows, 1);
+        assert_eq!(batch.columns[0].values, [0, 0]);
+        assert_eq!(batch.columns[1].values, [0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(batch
+            .columns
+            .iter()
+            .all(|column| column.validity == [0] && column.null_count == 1));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::arrow::batches::{ArrowField, ArrowType, RecordBatch};
+use flatbuffers::field_index_to_field_offset as slot;
+use flatbuffers::{FlatBufferBuilder, Push, TableFinishedWIPOffset, UnionWIPOffset, WIPOffset};
+use std::io::Write;
+
+/// Marks the start of each message, from arrow 0.15 on.
+const CONTINUATION: u32 = 0xFFFF_FFFF;
+
+// The enum/union values from the arrow format's Schema.fbs and Message.fbs
+const METADATA_VERSION_V4: i16 = 3;
+const HEADER_SCHEMA: u8 = 1;
+const HEADER_RECORD_BATCH: u8 = 3;
+const TYPE_INT: u8 = 2;
+const TYPE_FLOATING_POINT: u8 = 3;
+const TYPE_BINARY: u8 = 4;
+const TYPE_UTF8: u8 = 5;
+const TYPE_BOOL: u8 = 6;
+const TYPE_DATE: u8 = 8;
+const TYPE_TIMESTAMP: u8 = 10;
+const PRECISION_DOUBLE: i16 = 2;
+const DATE_UNIT_DAY: i16 = 0;
+const TIME_UNIT_MILLISECOND: i16 = 1;
+
+/// Writes record batches out as an arrow ipc stream, the schema first followed by a message per
+/// batch. Messages are a flatbuffer of metadata followed by the body holding the batch's
+/// buffers, everything padded out to 8 bytes.
+pub struct StreamWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> StreamWriter<W> {
+    /// Starts the stream off by writing out the schema.
+    pub fn try_new(mut writer: W, schema: &[ArrowField]) -> Result<Self, std::io::Error> {
+        write_message(&mut writer, &schema_message(schema), &[])?;
+        Ok(StreamWriter { writer })
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<(), std::io::Error> {
+        let mut body = vec![];
+        let mut nodes = vec![];
+        let mut buffers = vec![];
+        for column in &batch.columns {
+            nodes.push(FieldNode {
+                length: batch.rows as i64,
+                null_count: column.null_count as i64,
+            });
+            for buffer in column.buffers() {
+                buffers.push(Buffer {
+                    offset: body.len() as i64,
+                    length: buffer.len() as i64,
+                });
+                body.extend_from_slice(buffer);
+                body.resize(padded(body.len()), 0);
+            }
+        }
+        let metadata = record_batch_message(batch.rows, &nodes, &buffers, body.len());
+        write_message(&mut self.writer, &metadata, &body)
+    }
+
+    /// Ends the stream, the writer is handed back.
+    pub fn finish(mut self) -> Result<W, std::io::Error> {
+        self.writer.write_all(&CONTINUATION.to_le_bytes())?;
+        self.writer.write_all(&0_u32.to_le_bytes())?;
+        Ok(self.writer)
+    }
+}
+
+/// The length rounded up to a multiple of 8.
+fn padded(len: usize) -> usize {
+    (len + 7) / 8 * 8
+}
+
+/// Writes <continuation><metadata len as u32 le><metadata><padding><body>, the body is already
+/// padded.
+fn write_message<W: Write>(writer: &mut W, metadata: &[u8], body: &[u8]) -> std::io::Result<()> {
+    let metadata_len = padded(metadata.len());
+    writer.write_all(&CONTINUATION.to_le_bytes())?;
+    writer.write_all(&(metadata_len as u32).to_le_bytes())?;
+    writer.write_all(metadata)?;
+    writer.write_all(&vec![0; metadata_len - metadata.len()])?;
+    writer.write_all(body)
+}
+
+fn schema_message(schema: &[ArrowField]) -> Vec<u8> {
+    let mut fbb = FlatBufferBuilder::new();
+    let fields: Vec<_> = schema
+        .iter()
+        .map(|field| {
+            let name = fbb.create_string(&field.name);
+            let (type_type, type_table) = type_table(&mut fbb, field.arrow_type);
+            let children = fbb.create_vector::<WIPOffset<TableFinishedWIPOffset>>(&[]);
+            let start = fbb.start_table();
+            fbb.push_slot_always(slot(0), name);
+            fbb.push_slot(slot(1), field.nullable, false);
+            fbb.push_slot_always(slot(2), type_type);
+            fbb.push_slot_always(slot(3), type_table);
+            fbb.push_slot_always(slot(5), children);
+            fbb.end_table(start)
+        })
+        .collect();
+    let fields = fbb.create_vector(&fields);
+    let start = fbb.start_table();
+    fbb.push_slot_always(slot(1), fields);
+    let schema = fbb.end_table(start).as_union_value();
+    message(fbb, HEADER_SCHEMA, schema, 0)
+}
+
+/// Builds the table describing the type, returning it along with its entry in the Type union.
+fn type_table(
+    fbb: &mut FlatBufferBuilder,
+    arrow_type: ArrowType,
+) -> (u8, WIPOffset<UnionWIPOffset>) {
+    let start = fbb.start_table();
+    let type_type = match arrow_type {
+        ArrowType::Boolean => TYPE_BOOL,
+        ArrowType::Int { bit_width, signed } => {
+            fbb.push_slot_always(slot(0), bit_width as i32);
+            fbb.push_slot_always(slot(1), signed);
+            TYPE_INT
+        }
+        ArrowType::Float64 => {
+            fbb.push_slot_always(slot(0), PRECISION_DOUBLE);
+            TYPE_FLOATING_POINT
+        }
+        ArrowType::Binary => TYPE_BINARY,
+        ArrowType::Utf8 => TYPE_UTF8,
+        ArrowType::Date32 => {
+            fbb.push_slot_always(slot(0), DATE_UNIT_DAY);
+            TYPE_DATE
+        }
+        ArrowType::TimestampMillisecond => {
+            fbb.push_slot_always(slot(0), TIME_UNIT_MILLISECOND);
+            TYPE_TIMESTAMP
+        }
+    };
+    (type_type, fbb.end_table(start).as_union_value())
+}
+
+fn record_batch_message(
+    rows: usize,
+    nodes: &[FieldNode],
+    buffers: &[Buffer],
+    body_length: usize,
+) -> Vec<u8> {
+    let mut fbb = FlatBufferBuilder::new();
+    let nodes = fbb.create_vector(nodes);
+    let buffers = fbb.create_vector(buffers);
+    let start = fbb.start_table();
+    fbb.push_slot_always(slot(0), rows as i64);
+    fbb.push_slot_always(slot(1), nodes);
+    fbb.push_slot_always(slot(2), buffers);
+    let record_batch = fbb.end_table(start).as_union_value();
+    message(fbb, HEADER_RECORD_BATCH, record_batch, body_length)
+}
+
+fn message(
+    mut fbb: FlatBufferBuilder,
+    header_type: u8,
+    header: WIPOffset<UnionWIPOffset>,
+    body_length: usize,
+) -> Vec<u8> {
+    let start = fbb.start_table();
+    fbb.push_slot_always(slot(3), body_length as i64);
+    fbb.push_slot_always(slot(2), header);
+    fbb.push_slot_always(slot(0), METADATA_VERSION_V4);
+    fbb.push_slot_always(slot(1), header_type);
+    let message = fbb.end_table(start);
+    fbb.finish(message, None);
+    fbb.finished_data().to_vec()
+}
+
+/// The length and null count of a column.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct FieldNode {
+    length: i64,
+    null_count: i64,
+}
+
+impl Push for FieldNode {
+    type Output = FieldNode;
+
+    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
+        dst[..8].copy_from_slice(&self.length.to_le_bytes());
+        dst[8..16].copy_from_slice(&self.null_count.to_le_bytes());
+    }
+}
+
+/// Where a buffer is within the body of a message.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct Buffer {
+    offset: i64,
+    length: i64,
+}
+
+impl Push for Buffer {
+    type Output = Buffer;
+
+    fn push(&self, dst: &mut [u8], _rest: &[u8]) {
+        dst[..8].copy_from_slice(&self.offset.to_le_bytes());
+        dst[8..16].copy_from_slice(&self.length.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow::batches::BatchBuilder;
+    use data::{DataType, Datum};
+    use flatbuffers::{get_root, Table};
+    use runtime::Field;
+
+    #[test]
+    fn test_stream_writer() -> Result<(), std::io::Error> {
+        let fields = [Field {
+            qualifier: None,
+            alias: "a".to_string(),
+            data_type: DataType::Text,
+            nullable: true,
+            origin: None,
+        }];
+        let mut builder = BatchBuilder::new(&fields, 10);
+        builder.push(&[Datum::from("hello")], 1);
+        let mut writer = StreamWriter::try_new(vec![], builder.schema())?;
+        writer.write(&builder.finish())?;
+        let stream = writer.finish()?;
+
+        // Walk the messages checking their headers and that everything lines up on 8 bytes
+        let mut headers = vec![];
+        let mut rest = stream.as_slice();
+        loop {
+            assert_eq!(rest[..4], CONTINUATION.to_le_bytes());
+            let metadata_len = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+            if metadata_len == 0 {
+                assert_eq!(rest.len(), 8);
+                break;
+            }
+            assert_eq!(metadata_len % 8, 0);
+            let message = get_root::<Table>(&rest[8..8 + metadata_len]);
+            let version = message.get::<i16>(slot(0), None);
+            let header_type = message.get::<u8>(slot(1), None);
+            let body_length = message.get::<i64>(slot(3), Some(0));
+            assert_eq!(version, Some(METADATA_VERSION_V4));
+            assert_eq!(body_length.unwrap() % 8, 0);
+            headers.push(header_type.unwrap());
+            rest = &rest[8 + metadata_len + body_length.unwrap() as usize..];
+        }
+        assert_eq!(headers, vec![HEADER_SCHEMA, HEADER_RECORD_BATCH]);
+        Ok(())
+    }
+}
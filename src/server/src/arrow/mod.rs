@@ -0,0 +1,298 @@
+use crate::arrow::batches::BatchBuilder;
+use crate::arrow::ipc::StreamWriter;
+use crate::ShutdownHandle;
+use data::Datum;
+use rand::Rng;
+use runtime::connection::Connection;
+use runtime::{QueryError, Runtime};
+use std::error::Error;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{debug, error};
+
+pub mod batches;
+pub mod ipc;
+
+/// Rows per record batch sent.
+const BATCH_SIZE: usize = 8192;
+
+/// Longest string we'll accept from a client, so a bad length can't have us allocating gigs.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Each client gets a thread of its own so we cap how many we'll serve at once, on top of any
+/// max_connections.
+const MAX_CLIENTS: usize = 64;
+
+/// How often idle clients check whether we're shutting down or they've hit their wait_timeout.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const STATUS_OK: u8 = 0;
+const STATUS_ERROR: u8 = 1;
+
+/// Listens for clients wanting their results as arrow ipc streams rather than mysql rows, each
+/// client gets a thread of its own.
+/// Clients send strings as <len as u32 le><utf8>. The server starts with a status byte, for ok
+/// it's followed by a random 20 byte scramble and the client replies with their user and
+/// mysql_native_password response (as <len as u32 le><response>). After that the client sends
+/// a query at a time. The server replies to each with a status byte, 0 for ok (followed by an
+/// arrow ipc stream of the results for queries) or 1 for an error (followed by the error
+/// message).
+/// Idle clients are hung up on (after an error) once they hit their wait_timeout or when
+/// shutting down.
+pub fn serve_arrow(
+    runtime: &'static Runtime,
+    addr: &str,
+    shutdown: ShutdownHandle,
+) -> Result<JoinHandle<()>, std::io::Error> {
+    let listener = TcpListener::bind(addr)?;
+    shutdown.set_listen_addr(listener.local_addr()?);
+    let clients = Arc::new(AtomicUsize::new(0));
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            if shutdown.is_shutdown() {
+                break;
+            }
+            if let Ok(stream) = stream {
+                if clients.load(Ordering::Acquire) >= MAX_CLIENTS {
+                    reject_client(stream, &QueryError::TooManyConnections);
+                    continue;
+                }
+                let connection = match runtime.try_new_connection() {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        reject_client(stream, &err);
+                        continue;
+                    }
+                };
+                clients.fetch_add(1, Ordering::AcqRel);
+                let clients = Arc::clone(&clients);
+                let shutdown = shutdown.clone();
+                thread::spawn(move || {
+                    let peer = stream.peer_addr();
+                    if let Err(err) = serve_client(runtime, &connection, stream, &shutdown) {
+                        error!(?peer, %err, "IO error for arrow connection");
+                    }
+                    drop(connection);
+                    clients.fetch_sub(1, Ordering::AcqRel);
+                });
+            }
+        }
+    }))
+}
+
+/// Lets the client know why before hanging up on them.
+fn reject_client(stream: TcpStream, err: &QueryError) {
+    let mut writer = BufWriter::new(stream);
+    if let Err(err) = send_error(&mut writer, err) {
+        debug!(%err, "Error rejecting arrow connection");
+    }
+}
+
+fn serve_client(
+    runtime: &Runtime,
+    connection: &Connection,
+    stream: TcpStream,
+    shutdown: &ShutdownHandle,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+    let connection_id = connection.connection_id;
+
+    let mut scramble = [0_u8; 20];
+    rand::thread_rng().fill(&mut scramble);
+    writer.write_all(&[STATUS_OK])?;
+    writer.write_all(&scramble)?;
+    writer.flush()?;
+    let user = read_string(&mut reader)?;
+    let auth_response = read_bytes(&mut reader)?;
+    if !runtime.authenticate(&user, &scramble, &auth_response) {
+        let message = format!("Access denied for user '{}'", user);
+        return send_error(&mut writer, message);
+    }
+    if let Err(err) = connection.login(user) {
+        return send_error(&mut writer, &err);
+    }
+    writer.write_all(&[STATUS_OK])?;
+    writer.flush()?;
+    debug!(connection_id, "Arrow connection opened");
+
+    loop {
+        match wait_for_query(connection, &reader, shutdown)? {
+            Wait::Ready => {}
+            Wait::Shutdown => break,
+            Wait::TimedOut => {
+                send_error(
+                    &mut writer,
+                    "The client was disconnected by the server because of inactivity. See \
+                    wait_timeout for configuring this behavior.",
+                )?;
+                break;
+            }
+        }
+        let query = match read_string(&mut reader) {
+            Ok(query) => query,
+            // The client hanging up between queries is how they say goodbye
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+        debug!(connection_id, %query, "Query");
+        connection.session.statement_started(&query);
+        let sent = send_query_results(connection, &query, &mut writer);
+        connection.statement_finished();
+        sent?;
+        writer.flush()?;
+
+        // KILL CONNECTION closes the connection once the current query is done with
+        if connection.session.connection_killed.load(Ordering::Relaxed) {
+            break;
+        }
+    }
+    debug!(connection_id, "Arrow connection closed");
+    Ok(())
+}
+
+enum Wait {
+    Ready,
+    Shutdown,
+    TimedOut,
+}
+
+/// Waits for the client to send something (or hang up), checking in every POLL_INTERVAL to see
+/// if we're shutting down or the client has hit its wait_timeout.
+fn wait_for_query(
+    connection: &Connection,
+    reader: &BufReader<TcpStream>,
+    shutdown: &ShutdownHandle,
+) -> Result<Wait, std::io::Error> {
+    if !reader.buffer().is_empty() {
+        return Ok(Wait::Ready);
+    }
+    let stream = reader.get_ref();
+    let started = Instant::now();
+    stream.set_read_timeout(Some(POLL_INTERVAL))?;
+    let wait = loop {
+        match stream.peek(&mut [0_u8]) {
+            Ok(_) => break Wait::Ready,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if shutdown.is_shutdown() {
+                    break Wait::Shutdown;
+                }
+                if let Some(wait_timeout) = wait_timeout(connection) {
+                    if started.elapsed() >= wait_timeout {
+                        break Wait::TimedOut;
+                    }
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    stream.set_read_timeout(None)?;
+    Ok(wait)
+}
+
+/// How long to wait for the next query before closing the connection, from the session's
+/// wait_timeout (in seconds).
+fn wait_timeout(connection: &Connection) -> Option<Duration> {
+    match connection.session.variable("wait_timeout") {
+        Some((Datum::Integer(seconds), _)) if seconds > 0 => {
+            Some(Duration::from_secs(seconds as u64))
+        }
+        Some((Datum::BigInt(seconds), _)) if seconds > 0 => {
+            Some(Duration::from_secs(seconds as u64))
+        }
+        _ => None,
+    }
+}
+
+/// Sends the results of the query as an arrow stream. Errors hit before the first tuple can be
+/// sent as an error status, after that all we can do is hang up on the client.
+fn send_query_results<W: Write>(
+    connection: &Connection,
+    query: &str,
+    writer: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    let (fields, mut executor) = match connection.execute_statement(query) {
+        Ok(results) => results,
+        Err(err) => return send_error(writer, &err),
+    };
+    let mut builder = BatchBuilder::new(&fields, BATCH_SIZE);
+    // Pull the first tuple before replying so errors in the likes of DDL still make it back as
+    // an error status
+    let first = match executor.next() {
+        Ok(first) => first.map(|(tuple, freq)| {
+            let tuple: Vec<_> = tuple.iter().map(Datum::as_static).collect();
+            (tuple, freq)
+        }),
+        Err(err) => return send_error(writer, QueryError::from(err)),
+    };
+
+    writer.write_all(&[STATUS_OK])?;
+    let mut stream_writer = StreamWriter::try_new(writer, builder.schema())?;
+    if let Some((tuple, freq)) = first {
+        push_tuple(connection, &mut builder, &mut stream_writer, &tuple, freq)?;
+        while let Some((tuple, freq)) = executor
+            .next()
+            .map_err(|err| QueryError::from(err).to_string())?
+        {
+            push_tuple(connection, &mut builder, &mut stream_writer, tuple, freq)?;
+        }
+    }
+    if !builder.is_empty() {
+        stream_writer.write(&builder.finish())?;
+    }
+    stream_writer.finish()?;
+    Ok(())
+}
+
+/// Adds the tuple to the batch being built, sending the batch once it's full.
+fn push_tuple<W: Write>(
+    connection: &Connection,
+    builder: &mut BatchBuilder,
+    stream_writer: &mut StreamWriter<W>,
+    tuple: &[Datum],
+    freq: i64,
+) -> Result<(), std::io::Error> {
+    builder.push(tuple, freq);
+    connection
+        .session
+        .rows_sent
+        .fetch_add(freq.max(0) as u64, Ordering::Relaxed);
+    if builder.is_full() {
+        stream_writer.write(&builder.finish())?;
+    }
+    Ok(())
+}
+
+fn send_error<W: Write, E: ToString>(writer: &mut W, err: E) -> Result<(), Box<dyn Error>> {
+    let message = err.to_string();
+    writer.write_all(&[STATUS_ERROR])?;
+    writer.write_all(&(message.len() as u32).to_le_bytes())?;
+    writer.write_all(message.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_string<R: Read>(reader: &mut R) -> Result<String, std::io::Error> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|err| std::io::Error::new(ErrorKind::InvalidData, err))
+}
+
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, std::io::Error> {
+    let mut len_buffer = [0_u8; 4];
+    reader.read_exact(&mut len_buffer)?;
+    let len = u32::from_le_bytes(len_buffer) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            "Message too large",
+        ));
+    }
+    let mut buffer = vec![0; len];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
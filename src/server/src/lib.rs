@@ -1,46 +1,324 @@
-use crate::mysql::MysqlConnection;
+use crate::mysql::{reject_connection, MysqlConnection};
+use runtime::config::ServerConfig;
 use runtime::Runtime;
-use scoped_threadpool::Pool;
-use std::net::TcpListener;
-use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::sync::watch;
+use tokio::task::{spawn_local, LocalSet};
+use tracing::{debug, error};
 
 // Something to do with the infinite loop for the listen loop means that we trip up rusts deadcode
 // detection, we'll just make mysql public to get around it even though there's probably no use for
 // it outside of the server
+pub mod arrow;
 pub mod mysql;
+pub mod replication;
 
-/// Implements a tcp server that accepts mysql connections
+/// The default number of threads used to service connections.
+const DEFAULT_WORKER_THREADS: usize = 8;
+
+/// How long to wait for running statements to finish up when shutting down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Implements a tcp server that accepts mysql connections.
+/// Connections are handed out round robin to a fixed set of worker threads, each running its own
+/// single threaded tokio runtime, so idle connections don't tie up a thread each.
+/// The statements themselves are run on tokio's blocking threads (see mysql::execution) so a
+/// busy connection doesn't hold up the others on its worker.
 pub struct Server {
-    runtime: Runtime,
+    runtime: &'static Runtime,
+    worker_threads: usize,
 }
 
 impl Server {
     pub fn new(runtime: Runtime) -> Self {
-        Server { runtime }
+        // The runtime lives for as long as the server (ie the process) so rather than threading
+        // lifetimes through each spawned connection we just leak it.
+        Server {
+            runtime: Box::leak(Box::new(runtime)),
+            worker_threads: DEFAULT_WORKER_THREADS,
+        }
+    }
+
+    /// Creates a server using the worker threads from the config, the server should then be
+    /// started by listening on the config's listen_addr.
+    pub fn new_with_config(runtime: Runtime, config: &ServerConfig) -> Self {
+        Server::new(runtime).with_worker_threads(config.worker_threads)
+    }
+
+    /// Sets the number of threads used to service connections.
+    pub fn with_worker_threads(mut self, worker_threads: usize) -> Self {
+        self.worker_threads = worker_threads.max(1);
+        self
+    }
+
+    /// Starts serving query results as arrow ipc streams on addr, see arrow::serve_arrow.
+    /// Arrow clients are closed once they're idle after shutdown is called on the handle.
+    pub fn serve_arrow(
+        &self,
+        addr: &str,
+        shutdown: ShutdownHandle,
+    ) -> Result<thread::JoinHandle<()>, std::io::Error> {
+        arrow::serve_arrow(self.runtime, addr, shutdown)
     }
 
-    /// Starts listening for mysql connections. This method doesn't normally terminate.
-    pub fn listen(&mut self, addr: &str) -> Result<(), std::io::Error> {
+    /// Starts listening for mysql connections, this runs until shutdown is called on the
+    /// handle. When shutting down we stop accepting connections, close connections as they
+    /// finish up their current command, kill any running statements and flush the storage.
+    pub fn listen(&mut self, addr: &str, shutdown: ShutdownHandle) -> Result<(), std::io::Error> {
         let listener = TcpListener::bind(addr)?;
-        let mut pool = Pool::new(500);
+        shutdown.set_listen_addr(listener.local_addr()?);
+        let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+        let workers = (0..self.worker_threads)
+            .map(|_| spawn_worker(self.runtime, shutdown_receiver.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
 
-        loop {
-            if let Ok((stream, _)) = listener.accept() {
-                pool.scoped(|scope| {
-                    let connection = self.runtime.new_connection();
-                    let connection_id = connection.connection_id;
-                    scope.execute(move || {
-                        if let Err(err) = catch_unwind(AssertUnwindSafe(|| {
-                            let mut mysql_connection = MysqlConnection::new(stream, connection);
-                            if let Err(err) = mysql_connection.connect() {
-                                eprintln!("IO Error for {}\n {:?}", connection_id, err);
+        for (idx, stream) in listener.incoming().enumerate() {
+            if shutdown.is_shutdown() {
+                break;
+            }
+            if let Ok(stream) = stream {
+                if workers[idx % workers.len()].send(stream).is_err() {
+                    error!("Worker thread has gone away");
+                }
+            }
+        }
+
+        drop(workers);
+        let _ = shutdown_sender.send(true);
+        self.runtime
+            .shutdown(SHUTDOWN_TIMEOUT)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))
+    }
+}
+
+/// Used to shut down a running server from another thread, ie a signal handler.
+#[derive(Clone, Default)]
+pub struct ShutdownHandle {
+    state: Arc<ShutdownState>,
+}
+
+#[derive(Default)]
+struct ShutdownState {
+    shutdown: AtomicBool,
+    // Where the server is listening, so we can wake it up from accept
+    listen_addrs: Mutex<Vec<SocketAddr>>,
+}
+
+impl ShutdownHandle {
+    /// Asks the server to shut down, listen returns once it has.
+    pub fn shutdown(&self) {
+        self.state.shutdown.store(true, Ordering::Release);
+        // The server will be blocked in accept so we give it a poke
+        for addr in self.state.listen_addrs.lock().unwrap().iter() {
+            let _ = TcpStream::connect(addr);
+        }
+    }
+
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.state.shutdown.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_listen_addr(&self, mut addr: SocketAddr) {
+        if addr.ip().is_unspecified() {
+            if addr.is_ipv4() {
+                addr.set_ip(Ipv4Addr::LOCALHOST.into());
+            } else {
+                addr.set_ip(Ipv6Addr::LOCALHOST.into());
+            }
+        }
+        self.state.listen_addrs.lock().unwrap().push(addr);
+        // We may have been asked to shut down before we started listening.
+        if self.is_shutdown() {
+            self.shutdown();
+        }
+    }
+}
+
+/// Starts up a worker thread, returning the sender used to hand it connections.
+/// The worker thread exits once the sender is dropped and its connections have all closed.
+fn spawn_worker(
+    runtime: &'static Runtime,
+    shutdown: watch::Receiver<bool>,
+) -> Result<UnboundedSender<TcpStream>, std::io::Error> {
+    let (sender, mut receiver) = unbounded_channel::<TcpStream>();
+    let tokio_runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .enable_time()
+        .build()?;
+
+    thread::spawn(move || {
+        let local = LocalSet::new();
+        local.spawn_local(async move {
+            while let Some(stream) = receiver.recv().await {
+                let connection = match runtime.try_new_connection() {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        spawn_local(async move {
+                            if let Err(err) = reject_connection(stream, &err).await {
+                                error!(%err, "IO error rejecting connection");
                             }
-                        })) {
-                            eprintln!("Thread panic for connection {}\n {:?}", connection_id, err);
-                        }
-                    });
-                })
+                        });
+                        continue;
+                    }
+                };
+                let connection_id = connection.connection_id;
+                let shutdown = shutdown.clone();
+                debug!(connection_id, peer = ?stream.peer_addr(), "Connection opened");
+                spawn_local(async move {
+                    // Panics are caught at the task boundary so one bad connection doesn't take
+                    // out the others on this worker.
+                    let result = spawn_local(async move {
+                        stream.set_nonblocking(true)?;
+                        let stream = tokio::net::TcpStream::from_std(stream)?;
+                        let mut mysql_connection =
+                            MysqlConnection::new(stream, connection, shutdown);
+                        mysql_connection.connect().await
+                    })
+                    .await;
+                    match result {
+                        Ok(Err(err)) => error!(connection_id, %err, "IO error for connection"),
+                        Err(err) => error!(connection_id, %err, "Task panic for connection"),
+                        Ok(Ok(())) => {}
+                    }
+                    debug!(connection_id, "Connection closed");
+                });
             }
+        });
+        // Runs until all the tasks, ie the connections, have finished
+        tokio_runtime.block_on(local);
+    });
+    Ok(sender)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::time::Instant;
+
+    /// Just enough of a mysql client to log in as root and send queries.
+    struct TestClient {
+        stream: TcpStream,
+    }
+
+    impl TestClient {
+        fn connect(addr: SocketAddr) -> Self {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            let mut client = TestClient { stream };
+            client.read_packet();
+
+            // protocol_41 | secure_connection | plugin_auth, root has no password.
+            let mut response = vec![];
+            response.extend_from_slice(&0x0008_8200_u32.to_le_bytes());
+            response.extend_from_slice(&0x0100_0000_u32.to_le_bytes());
+            response.push(33);
+            response.extend_from_slice(&[0; 23]);
+            response.extend_from_slice(b"root\0");
+            response.push(0);
+            response.extend_from_slice(b"mysql_native_password\0");
+            client.write_packet(1, &response);
+            assert_eq!(client.read_packet()[0], 0x00);
+            client
+        }
+
+        /// Sends the query, reading up to the end of the column definitions if there's a
+        /// resultset. Returns whether there was.
+        fn start_query(&mut self, sql: &str) -> Result<bool, String> {
+            let mut command = vec![0x03];
+            command.extend_from_slice(sql.as_bytes());
+            self.write_packet(0, &command);
+            match self.read_packet() {
+                packet if packet[0] == 0x00 => Ok(false),
+                packet if packet[0] == 0xFF => Err(String::from_utf8_lossy(&packet[9..]).into()),
+                _ => {
+                    while !is_eof(&self.read_packet()) {}
+                    Ok(true)
+                }
+            }
+        }
+
+        /// Reads the next row, None once the rows are done with.
+        fn read_row(&mut self) -> Option<Vec<u8>> {
+            Some(self.read_packet()).filter(|packet| !is_eof(packet))
+        }
+
+        /// Runs the query returning the number of rows sent back.
+        fn query(&mut self, sql: &str) -> Result<usize, String> {
+            if !self.start_query(sql)? {
+                return Ok(0);
+            }
+            let mut rows = 0;
+            while self.read_row().is_some() {
+                rows += 1;
+            }
+            Ok(rows)
+        }
+
+        fn read_packet(&mut self) -> Vec<u8> {
+            let mut header = [0_u8; 4];
+            self.stream.read_exact(&mut header).unwrap();
+            let mut packet =
+                vec![0; u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize];
+            self.stream.read_exact(&mut packet).unwrap();
+            packet
+        }
+
+        fn write_packet(&mut self, sequence_id: u8, payload: &[u8]) {
+            let mut header = (payload.len() as u32).to_le_bytes();
+            header[3] = sequence_id;
+            self.stream.write_all(&header).unwrap();
+            self.stream.write_all(payload).unwrap();
+        }
+    }
+
+    fn is_eof(packet: &[u8]) -> bool {
+        packet[0] == 0xFE && packet.len() < 9
+    }
+
+    /// Starts up a server with a single worker thread so all the connections share it.
+    fn start_server() -> (&'static Runtime, SocketAddr) {
+        let mut server = Server::new(Runtime::new_for_test()).with_worker_threads(1);
+        let runtime = server.runtime;
+        let shutdown = ShutdownHandle::default();
+        let listening = shutdown.clone();
+        thread::spawn(move || server.listen("127.0.0.1:0", shutdown));
+        loop {
+            if let Some(addr) = listening.state.listen_addrs.lock().unwrap().first() {
+                return (runtime, *addr);
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_subscriptions_dont_block_the_worker() {
+        let (runtime, addr) = start_server();
+        let mut client = TestClient::connect(addr);
+        client.query("CREATE TABLE t (a INT)").unwrap();
+
+        let mut subscriber = TestClient::connect(addr);
+        assert_eq!(subscriber.start_query("TAIL t"), Ok(true));
+
+        // The subscription's waiting on writes, the other connection on the worker carries on
+        assert_eq!(client.query("SELECT 1"), Ok(1));
+        client.query("INSERT INTO t VALUES (1)").unwrap();
+        assert!(subscriber.read_row().is_some());
+
+        // Hanging up ends the subscription
+        drop(subscriber);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while runtime.sessions().len() > 1 {
+            assert!(Instant::now() < deadline, "Subscription still running");
+            thread::sleep(Duration::from_millis(10));
         }
+        assert_eq!(client.query("SELECT 1"), Ok(1));
     }
 }
@@ -0,0 +1,180 @@
+use crate::mysql::constants::*;
+use crate::mysql::protocol_base::{read_int_1, read_int_3, write_int_3};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Error, ErrorKind, Read, Write};
+
+/// Payloads smaller than this aren't worth compressing and are sent as is.
+const MIN_COMPRESS_LENGTH: usize = 50;
+
+pub const COMPRESSED_HEADER_LENGTH: usize = 7;
+
+/// The algorithm used for the compressed protocol.
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_basic_compression.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Compression {
+    Zlib,
+    // Along with the level the client asked for
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Picks the compression to use once the client has authenticated, zstd wins if the client
+    /// asked for both.
+    pub fn negotiate(capabilities: u32, zstd_compression_level: u8) -> Option<Self> {
+        if (capabilities & CAPABILITY_CLIENT_ZSTD_COMPRESSION_ALGORITHM) != 0 {
+            Some(Compression::Zstd(zstd_compression_level as i32))
+        } else if (capabilities & CAPABILITY_CLIENT_COMPRESS) != 0 {
+            Some(Compression::Zlib)
+        } else {
+            None
+        }
+    }
+
+    /// Wraps the payload (made up of one or more regular packets) in a compressed packet and
+    /// writes it onto the end of the buffer. Small payloads or ones that don't shrink are sent
+    /// uncompressed which is flagged with an uncompressed length of zero.
+    pub fn write_packet(
+        self,
+        sequence_id: u8,
+        payload: &[u8],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let header_start = buffer.len();
+        buffer.resize(header_start + COMPRESSED_HEADER_LENGTH, 0);
+
+        let mut uncompressed_length = 0;
+        if payload.len() >= MIN_COMPRESS_LENGTH {
+            self.compress(payload, buffer)?;
+            if buffer.len() - header_start - COMPRESSED_HEADER_LENGTH < payload.len() {
+                uncompressed_length = payload.len();
+            } else {
+                buffer.truncate(header_start + COMPRESSED_HEADER_LENGTH);
+            }
+        }
+        if uncompressed_length == 0 {
+            buffer.extend_from_slice(payload);
+        }
+
+        let mut header = Vec::with_capacity(COMPRESSED_HEADER_LENGTH);
+        write_int_3(
+            (buffer.len() - header_start - COMPRESSED_HEADER_LENGTH) as u32,
+            &mut header,
+        );
+        header.push(sequence_id);
+        write_int_3(uncompressed_length as u32, &mut header);
+        buffer[header_start..(header_start + COMPRESSED_HEADER_LENGTH)].copy_from_slice(&header);
+        Ok(())
+    }
+
+    /// Appends the uncompressed contents of a compressed packet's payload onto the end of the
+    /// buffer.
+    pub fn read_payload(
+        self,
+        payload: &[u8],
+        uncompressed_length: usize,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        if uncompressed_length == 0 {
+            buffer.extend_from_slice(payload);
+            return Ok(());
+        }
+        let start = buffer.len();
+        match self {
+            Compression::Zlib => {
+                ZlibDecoder::new(payload).read_to_end(buffer)?;
+            }
+            Compression::Zstd(_) => zstd::stream::copy_decode(payload, &mut *buffer)?,
+        }
+        if buffer.len() - start != uncompressed_length {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Compressed packet didn't match its uncompressed length",
+            ));
+        }
+        Ok(())
+    }
+
+    fn compress(self, payload: &[u8], buffer: &mut Vec<u8>) -> Result<(), Error> {
+        match self {
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(buffer, flate2::Compression::default());
+                encoder.write_all(payload)?;
+                encoder.finish()?;
+            }
+            Compression::Zstd(level) => zstd::stream::copy_encode(payload, buffer, level)?,
+        }
+        Ok(())
+    }
+}
+
+/// Reads a compressed packet's header, returning the length of the payload, the sequence id
+/// and the uncompressed length.
+pub fn read_compressed_header(header: &[u8]) -> (usize, u8, usize) {
+    let mut compressed_length = 0;
+    let mut sequence_id = 0;
+    let mut uncompressed_length = 0;
+    let header = read_int_3(&mut compressed_length, header);
+    let header = read_int_1(&mut sequence_id, header);
+    read_int_3(&mut uncompressed_length, header);
+    (
+        compressed_length as usize,
+        sequence_id,
+        uncompressed_length as usize,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(compression: Compression, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![];
+        compression.write_packet(3, payload, &mut buf)?;
+        let (compressed_length, sequence_id, uncompressed_length) = read_compressed_header(&buf);
+        assert_eq!(sequence_id, 3);
+        assert_eq!(compressed_length, buf.len() - COMPRESSED_HEADER_LENGTH);
+
+        let mut out = vec![];
+        compression.read_payload(
+            &buf[COMPRESSED_HEADER_LENGTH..],
+            uncompressed_length,
+            &mut out,
+        )?;
+        assert_eq!(out, payload);
+        Ok(buf)
+    }
+
+    #[test]
+    fn test_compressed_packets() -> Result<(), Error> {
+        let payload = "SELECT 1;".repeat(100);
+        for compression in &[Compression::Zlib, Compression::Zstd(3)] {
+            let buf = round_trip(*compression, payload.as_bytes())?;
+            assert!(buf.len() < payload.len());
+
+            // Too small to bother compressing
+            let buf = round_trip(*compression, b"\x01\x00\x00\x00\x0e")?;
+            assert_eq!(buf, b"\x05\x00\x00\x03\x00\x00\x00\x01\x00\x00\x00\x0e");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(
+            Compression::negotiate(CAPABILITY_CLIENT_PROTOCOL_41, 0),
+            None
+        );
+        assert_eq!(
+            Compression::negotiate(CAPABILITY_CLIENT_COMPRESS, 0),
+            Some(Compression::Zlib)
+        );
+        assert_eq!(
+            Compression::negotiate(
+                CAPABILITY_CLIENT_COMPRESS | CAPABILITY_CLIENT_ZSTD_COMPRESSION_ALGORITHM,
+                7
+            ),
+            Some(Compression::Zstd(7))
+        );
+    }
+}
@@ -72,9 +72,24 @@ pub const CAPABILITY_CLIENT_SESSION_TRACK: u32 = 0x00800000;
 /// Can send OK after a Text Resultset.
 pub const CAPABILITY_CLIENT_DEPRECATE_EOF: u32 = 0x01000000;
 
+/// The client can handle optional metadata information in the resultset.
+pub const CAPABILITY_CLIENT_OPTIONAL_RESULTSET_METADATA: u32 = 0x02000000;
+
+/// Compression protocol extended to support zstd compression method.
+pub const CAPABILITY_CLIENT_ZSTD_COMPRESSION_ALGORITHM: u32 = 0x04000000;
+
 pub const CHARSET_UTF8_GENERAL_CI: u8 = 33;
+pub const CHARSET_BINARY: u8 = 63;
 
 pub const STATUS_FLAG_AUTOCOMMIT: u16 = 2;
+/// Set on the final ok/eof packet of a result when another result follows it.
+pub const STATUS_FLAG_MORE_RESULTS_EXISTS: u16 = 8;
+
+pub const COLUMN_FLAG_NOT_NULL: u16 = 1;
+pub const COLUMN_FLAG_BLOB: u16 = 16;
+pub const COLUMN_FLAG_UNSIGNED: u16 = 32;
+pub const COLUMN_FLAG_BINARY: u16 = 128;
+pub const COLUMN_FLAG_NUM: u16 = 32768;
 
 // https://dev.mysql.com/doc/internals/en/com-query-response.html#packet-ProtocolText::Resultset
 pub const MYSQL_TYPE_DECIMAL: u8 = 0x00;
@@ -111,6 +126,19 @@ pub struct MyError<'a> {
 }
 
 //https://dev.mysql.com/doc/mysql-errors/8.0/en/server-error-reference.html
+pub const MYSQL_ER_CON_COUNT_ERROR: MyError<'static> = MyError {
+    code: 1040,
+    msg: "Too many connections",
+    sql_state: "08004",
+};
+
+/// The message sent to the client gets the user name etc added in.
+pub const MYSQL_ER_ACCESS_DENIED_ERROR: MyError<'static> = MyError {
+    code: 1045,
+    msg: "Access denied",
+    sql_state: "28000",
+};
+
 pub const MYSQL_ER_UNKNOWN_COM_ERROR: MyError<'static> = MyError {
     code: 1047,
     msg: "Unknown command",
@@ -141,8 +169,60 @@ pub const MYSQL_ER_NO_TABLES_USED: MyError<'static> = MyError {
     sql_state: "HY000",
 };
 
+/// The message gets replaced with the name of the missing table.
+pub const MYSQL_ER_NO_SUCH_TABLE: MyError<'static> = MyError {
+    code: 1146,
+    msg: "Table doesn't exist",
+    sql_state: "42S02",
+};
+
+/// The message sent to the client gets the user name added in.
+pub const MYSQL_ER_TOO_MANY_USER_CONNECTIONS: MyError<'static> = MyError {
+    code: 1203,
+    msg: "User already has more than 'max_user_connections' active connections",
+    sql_state: "42000",
+};
+
+pub const MYSQL_ER_WRONG_ARGUMENTS: MyError<'static> = MyError {
+    code: 1210,
+    msg: "Incorrect arguments to mysqld_stmt_execute",
+    sql_state: "HY000",
+};
+
+/// Used for write conflicts, clients know to retry on this one.
+pub const MYSQL_ER_LOCK_DEADLOCK: MyError<'static> = MyError {
+    code: 1213,
+    msg: "Deadlock found when trying to get lock; try restarting transaction",
+    sql_state: "40001",
+};
+
+pub const MYSQL_ER_UNKNOWN_STMT_HANDLER: MyError<'static> = MyError {
+    code: 1243,
+    msg: "Unknown prepared statement handler",
+    sql_state: "HY000",
+};
+
 pub const MYSQL_ER_QUERY_INTERRUPTED: MyError<'static> = MyError {
     code: 1317,
     msg: "Query execution was interrupted",
     sql_state: "70100",
 };
+
+/// The message gets replaced with the details of the value that overflowed.
+pub const MYSQL_ER_DATA_OUT_OF_RANGE: MyError<'static> = MyError {
+    code: 1690,
+    msg: "Value is out of range",
+    sql_state: "22003",
+};
+
+pub const MYSQL_ER_QUERY_TIMEOUT: MyError<'static> = MyError {
+    code: 3024,
+    msg: "Query execution was interrupted, maximum statement execution time exceeded",
+    sql_state: "HY000",
+};
+
+pub const MYSQL_ER_CLIENT_INTERACTION_TIMEOUT: MyError<'static> = MyError {
+    code: 4031,
+    msg: "The client was disconnected by the server because of inactivity. See wait_timeout and interactive_timeout for configuring this behavior.",
+    sql_state: "HY000",
+};
@@ -0,0 +1,134 @@
+use data::{DataType, Datum, Session};
+use runtime::connection::{Connection, PreparedStatement};
+use runtime::{BoxedExecutor, Field, QueryError};
+use std::iter::once;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tokio::task::spawn_blocking;
+
+/// The number of messages a running statement can get ahead of the client by before it waits.
+const CHANNEL_CAPACITY: usize = 256;
+
+type StatementResult = Result<(Vec<Field>, BoxedExecutor), QueryError>;
+
+/// The statement(s) to run for a command.
+pub enum Statements {
+    Query(String),
+    /// Several statements separated by semicolons
+    MultiQuery(String),
+    Prepared(PreparedStatement, Vec<(Datum<'static>, DataType)>),
+}
+
+/// What a running statement sends back to its connection.
+pub enum StatementMessage {
+    /// The next statement has started, its rows will follow.
+    Started(Vec<Field>),
+    Row(Vec<Datum<'static>>, i64),
+    /// The statement's rows have all been sent, more_results is set if another statement
+    /// follows.
+    Finished {
+        more_results: bool,
+    },
+    /// The statement failed, any statements after it aren't run.
+    Failed(QueryError),
+}
+
+/// Statements running on one of tokio's blocking threads, so that long running statements (or
+/// subscriptions) don't hold up the other connections sharing the worker thread.
+/// If this is dropped before the statements are finished (ie the client's gone away) they're
+/// killed.
+pub struct RunningStatements {
+    receiver: Receiver<StatementMessage>,
+    session: Arc<Session>,
+    finished: bool,
+}
+
+impl RunningStatements {
+    pub fn spawn(connection: Arc<Connection<'static>>, statements: Statements) -> Self {
+        let (sender, receiver) = channel(CHANNEL_CAPACITY);
+        let session = Arc::clone(&connection.session);
+        spawn_blocking(move || {
+            // The only way sending fails is the connection hanging up, there's no one left to
+            // tell.
+            let _ = run_statements(&connection, statements, &sender);
+        });
+        RunningStatements {
+            receiver,
+            session,
+            finished: false,
+        }
+    }
+
+    /// Waits for the next message from the statements, returns None once they're finished.
+    pub async fn next(&mut self) -> Result<Option<StatementMessage>, std::io::Error> {
+        if self.finished {
+            return Ok(None);
+        }
+        match self.receiver.recv().await {
+            Some(message) => {
+                if let StatementMessage::Finished {
+                    more_results: false,
+                }
+                | StatementMessage::Failed(_) = message
+                {
+                    self.finished = true;
+                }
+                Ok(Some(message))
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Statement thread panicked",
+            )),
+        }
+    }
+}
+
+impl Drop for RunningStatements {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.session.kill_flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn run_statements(
+    connection: &Connection,
+    statements: Statements,
+    sender: &Sender<StatementMessage>,
+) -> Result<(), SendError<StatementMessage>> {
+    let results: Box<dyn Iterator<Item = StatementResult>> = match &statements {
+        Statements::Query(query) => Box::new(once(connection.execute_statement(query))),
+        // Each statement is only executed once the rows of the one before have all been sent
+        Statements::MultiQuery(query) => Box::new(connection.execute_statements(query)),
+        Statements::Prepared(statement, parameters) => Box::new(once(
+            connection.execute_prepared_statement(statement, parameters),
+        )),
+    };
+    let mut results = results.peekable();
+    while let Some(result) = results.next() {
+        let (fields, mut executor) = match result {
+            Ok(result) => result,
+            Err(err) => return sender.blocking_send(StatementMessage::Failed(err)),
+        };
+        let send_rows = !fields.is_empty();
+        sender.blocking_send(StatementMessage::Started(fields))?;
+        loop {
+            match executor.next() {
+                Ok(Some((tuple, freq))) => {
+                    if send_rows {
+                        let tuple = tuple.iter().map(Datum::as_static).collect();
+                        sender.blocking_send(StatementMessage::Row(tuple, freq))?;
+                    }
+                }
+                Ok(None) => break,
+                Err(err) => return sender.blocking_send(StatementMessage::Failed(err.into())),
+            }
+        }
+        drop(executor);
+        let more_results = results.peek().is_some();
+        sender.blocking_send(StatementMessage::Finished { more_results })?;
+    }
+    Ok(())
+}
@@ -1,190 +1,696 @@
+use crate::mysql::compression::{read_compressed_header, Compression, COMPRESSED_HEADER_LENGTH};
 use crate::mysql::constants::*;
+use crate::mysql::execution::{RunningStatements, StatementMessage, Statements};
 use crate::mysql::packets::*;
-use crate::mysql::protocol_base::{read_int_1, read_int_3, write_int_3};
-use runtime::connection::Connection;
-use runtime::QueryError;
+use crate::mysql::protocol_base::{
+    read_int_1, read_int_3, read_int_4, write_eof_string, write_int_3,
+};
+use data::{DataType, Datum};
+use rand::Rng;
+use runtime::connection::{Connection, PreparedStatement};
+use runtime::{Field, QueryError};
 use std::cmp::min;
+use std::collections::HashMap;
 use std::fmt::Debug;
-use std::io::{Read, Write};
-use std::net::TcpStream;
 use std::num::Wrapping;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::task::yield_now;
+use tokio::time::sleep;
+use tracing::debug;
 
+mod compression;
 mod constants;
+mod execution;
 mod packets;
 mod protocol_base;
 
-pub struct MysqlConnection<'a> {
+/// The number of rows sent for a query before yielding to any other connections on the worker.
+const ROWS_PER_YIELD: usize = 256;
+
+/// With compression on, packets are buffered up to this many bytes before being compressed and
+/// sent, the same as mysql's default net_buffer_length.
+const COMPRESS_BUFFER_SIZE: usize = 16 * 1024;
+
+/// The most data a prepared statement's parameters can be sent via COM_STMT_SEND_LONG_DATA
+/// between executions, the same as mysql's default max_allowed_packet.
+const MAX_LONG_DATA_LEN: usize = 64 * 1024 * 1024;
+
+pub struct MysqlConnection {
     stream: TcpStream,
     packet_header_buf: Vec<u8>,
     packet_buf: Vec<u8>,
     capabilities: u32,
     sequence_id: Wrapping<u8>,
-    connection: Arc<Connection<'a>>,
+    // Only set once the client has authenticated, if it asked for the compressed protocol
+    compression: Option<Compression>,
+    // The compressed packets have their own sequence ids
+    compressed_sequence_id: Wrapping<u8>,
+    compressed_buf: Vec<u8>,
+    // Packets waiting to be compressed and sent
+    write_buf: Vec<u8>,
+    // The decompressed bytes received from the client, read_offset is how far into them we are
+    read_buf: Vec<u8>,
+    read_offset: usize,
+    connection: Arc<Connection<'static>>,
+    // The random challenge sent to the client for mysql_native_password auth
+    scramble: [u8; 20],
+    prepared_statements: HashMap<u32, MysqlPreparedStatement>,
+    next_statement_id: u32,
+    // Flips to true when the server is shutting down
+    shutdown: watch::Receiver<bool>,
 }
 
-impl<'a> MysqlConnection<'a> {
-    pub fn new(stream: TcpStream, connection: Arc<Connection<'a>>) -> Self {
+/// A prepared statement along with the protocol state kept between its executions.
+struct MysqlPreparedStatement {
+    statement: PreparedStatement,
+    parameter_types: Vec<u16>,
+    long_data: HashMap<u16, Vec<u8>>,
+    // Set once the long data goes over MAX_LONG_DATA_LEN, the next execute then fails
+    long_data_too_long: bool,
+}
+
+impl MysqlConnection {
+    pub fn new(
+        stream: TcpStream,
+        connection: Arc<Connection<'static>>,
+        shutdown: watch::Receiver<bool>,
+    ) -> Self {
         MysqlConnection {
             stream,
             packet_header_buf: Vec::new(),
             packet_buf: Vec::new(),
             capabilities: 0,
             sequence_id: Wrapping(0),
+            compression: None,
+            compressed_sequence_id: Wrapping(0),
+            compressed_buf: Vec::new(),
+            write_buf: Vec::new(),
+            read_buf: Vec::new(),
+            read_offset: 0,
             connection,
+            scramble: generate_scramble(),
+            prepared_statements: HashMap::new(),
+            next_statement_id: 0,
+            shutdown,
         }
     }
 
-    pub fn connect(&mut self) -> Result<(), std::io::Error> {
-        self.handshake()?;
+    pub async fn connect(&mut self) -> Result<(), std::io::Error> {
+        if !self.handshake().await? {
+            return Ok(());
+        }
         let capabilities = self.capabilities;
+        let mut shutdown = self.shutdown.clone();
 
         loop {
-            match self.receive_packet::<CommandPacket>() {
-                Ok(command) => match command {
-                    CommandPacket::ComQuit => {
-                        break;
-                    }
-                    CommandPacket::ComPing => {
-                        self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))?;
+            let wait_timeout = self.wait_timeout();
+            let command = tokio::select! {
+                command = self.receive_packet::<CommandPacket>() => Some(command?),
+                // When shutting down we close connections between commands
+                _ = shutdown.changed() => break,
+                _ = sleep(wait_timeout.unwrap_or_default()), if wait_timeout.is_some() => None,
+            };
+            let command = match command {
+                Some(command) => command,
+                None => {
+                    // Like mysql we let the client know why before hanging up on it
+                    self.sequence_id = Wrapping(0);
+                    self.compressed_sequence_id = Wrapping(0);
+                    self.send_packet(|buf| {
+                        write_err_packet_from_err(
+                            &MYSQL_ER_CLIENT_INTERACTION_TIMEOUT,
+                            capabilities,
+                            buf,
+                        )
+                    })
+                    .await?;
+                    self.flush_packets().await?;
+                    break;
+                }
+            };
+            match command {
+                CommandPacket::ComQuit => {
+                    break;
+                }
+                CommandPacket::ComPing => {
+                    self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))
+                        .await?;
+                }
+                CommandPacket::ComInitDb(com_init_db) => {
+                    if self.connection.change_database(&com_init_db.schema).is_ok() {
+                        self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))
+                            .await?;
+                    } else {
+                        self.send_packet(|buf| {
+                            write_err_packet_from_err(&MYSQL_ER_BAD_DB_ERROR, capabilities, buf)
+                        })
+                        .await?;
                     }
-                    CommandPacket::ComInitDb(com_init_db) => {
-                        if self.connection.change_database(&com_init_db.schema).is_ok() {
-                            self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))?;
+                }
+                CommandPacket::ComQuery(com_query) => {
+                    self.process_query_command(&com_query.query).await?;
+                }
+                CommandPacket::ComFieldList(com_field_list) => {
+                    self.process_field_list_command(com_field_list).await?;
+                }
+                CommandPacket::ComStatistics => {
+                    let runtime = self.connection.runtime;
+                    let statistics = format!(
+                        "Uptime: {}  Threads: {}",
+                        runtime.uptime().as_secs(),
+                        runtime.sessions().len()
+                    );
+                    self.send_packet(|buf| write_eof_string(&statistics, buf))
+                        .await?;
+                }
+                CommandPacket::ComProcessInfo => {
+                    // The deprecated way to get the processlist
+                    self.process_query_command("SHOW PROCESSLIST").await?;
+                }
+                CommandPacket::ComStmtPrepare(com_stmt_prepare) => {
+                    self.process_prepare_command(&com_stmt_prepare.query)
+                        .await?;
+                }
+                CommandPacket::ComStmtExecute(com_stmt_execute) => {
+                    self.process_execute_command(com_stmt_execute).await?;
+                }
+                CommandPacket::ComStmtSendLongData(long_data) => {
+                    // No response is sent for long data, any errors get reported on execute
+                    if let Some(prepared) =
+                        self.prepared_statements.get_mut(&long_data.statement_id)
+                    {
+                        let len: usize = prepared.long_data.values().map(Vec::len).sum();
+                        if prepared.long_data_too_long
+                            || len + long_data.data.len() > MAX_LONG_DATA_LEN
+                        {
+                            prepared.long_data.clear();
+                            prepared.long_data_too_long = true;
                         } else {
-                            self.send_packet(|buf| {
-                                write_err_packet_from_err(&MYSQL_ER_BAD_DB_ERROR, capabilities, buf)
-                            })?;
+                            prepared
+                                .long_data
+                                .entry(long_data.parameter_id)
+                                .or_default()
+                                .extend_from_slice(&long_data.data);
                         }
                     }
-                    CommandPacket::ComQuery(com_query) => {
-                        self.process_query_command(&com_query.query)?;
-                    }
-                    CommandPacket::ComUnknown => {
+                }
+                CommandPacket::ComStmtClose(statement_id) => {
+                    // No response is sent for close
+                    self.prepared_statements.remove(&statement_id);
+                }
+                CommandPacket::ComStmtReset(statement_id) => {
+                    if let Some(prepared) = self.prepared_statements.get_mut(&statement_id) {
+                        prepared.long_data.clear();
+                        prepared.long_data_too_long = false;
+                        self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))
+                            .await?;
+                    } else {
                         self.send_packet(|buf| {
                             write_err_packet_from_err(
-                                &MYSQL_ER_UNKNOWN_COM_ERROR,
+                                &MYSQL_ER_UNKNOWN_STMT_HANDLER,
                                 capabilities,
                                 buf,
                             )
-                        })?;
+                        })
+                        .await?;
                     }
-                },
-                Err(io_error) => return Err(io_error),
+                }
+                CommandPacket::ComUnknown => {
+                    self.send_packet(|buf| {
+                        write_err_packet_from_err(&MYSQL_ER_UNKNOWN_COM_ERROR, capabilities, buf)
+                    })
+                    .await?;
+                }
+            }
+            self.flush_packets().await?;
+
+            // KILL CONNECTION closes the connection once the current command is done with
+            if self
+                .connection
+                .session
+                .connection_killed
+                .load(Ordering::Relaxed)
+            {
+                break;
             }
         }
 
         Ok(())
     }
 
-    fn process_query_command(&mut self, query: &str) -> Result<(), std::io::Error> {
+    /// How long to wait for the next command before closing the connection, from the session's
+    /// wait_timeout (in seconds).
+    fn wait_timeout(&self) -> Option<Duration> {
+        match self.connection.session.variable("wait_timeout") {
+            Some((Datum::Integer(seconds), _)) if seconds > 0 => {
+                Some(Duration::from_secs(seconds as u64))
+            }
+            Some((Datum::BigInt(seconds), _)) if seconds > 0 => {
+                Some(Duration::from_secs(seconds as u64))
+            }
+            _ => None,
+        }
+    }
+
+    async fn process_query_command(&mut self, query: &str) -> Result<(), std::io::Error> {
+        debug!(connection_id = self.connection.connection_id, %query, "Query");
+        self.connection.session.statement_started(query);
+        // Each of the statements gets a result sent back, all but the last are flagged as having
+        // more results to follow
+        let statements = if (self.capabilities & CAPABILITY_CLIENT_MULTI_STATEMENTS) != 0 {
+            Statements::MultiQuery(query.to_string())
+        } else {
+            Statements::Query(query.to_string())
+        };
+        let running = RunningStatements::spawn(Arc::clone(&self.connection), statements);
+        let sent = self.send_query_results(running, false).await;
+        self.connection.statement_finished();
+        sent
+    }
+
+    /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_field_list.html
+    async fn process_field_list_command(
+        &mut self,
+        com_field_list: ComFieldListPacket,
+    ) -> Result<(), std::io::Error> {
         let capabilities = self.capabilities;
-        match self.connection.execute_statement(query) {
-            Ok((fields, mut executor)) => {
-                if !fields.is_empty() {
-                    self.send_packet(|buf| {
-                        write_resultset_packet(fields.len(), capabilities, buf)
-                    })?;
-                    for field in &fields {
-                        self.send_packet(|buf| {
-                            write_column_packet(
-                                "",
-                                &field.alias,
-                                field.data_type,
-                                capabilities,
-                                buf,
-                            )
-                        })?;
-                    }
+        let fields = match self
+            .connection
+            .table_fields(&com_field_list.table, &com_field_list.field_wildcard)
+        {
+            Ok(fields) => fields,
+            Err(err) => return self.send_query_error(&err).await,
+        };
+        for field in &fields {
+            self.send_packet(|buf| write_field_list_column_packet(field, capabilities, buf))
+                .await?;
+        }
+        if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
+            self.send_packet(|buf| write_eof_packet(capabilities, buf))
+                .await
+        } else {
+            self.send_packet(|buf| write_ok_packet(true, 0, capabilities, buf))
+                .await
+        }
+    }
+
+    /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_stmt_prepare.html
+    async fn process_prepare_command(&mut self, query: &str) -> Result<(), std::io::Error> {
+        let capabilities = self.capabilities;
+        let statement = match self.connection.prepare_statement(query) {
+            Ok(statement) => statement,
+            Err(err) => return self.send_query_error(&err).await,
+        };
+
+        self.next_statement_id += 1;
+        let statement_id = self.next_statement_id;
+        self.send_packet(|buf| {
+            write_stmt_prepare_ok_packet(
+                statement_id,
+                statement.fields.len(),
+                statement.parameter_count,
+                buf,
+            )
+        })
+        .await?;
+
+        if statement.parameter_count > 0 {
+            let parameter = Field {
+                qualifier: None,
+                alias: "?".to_string(),
+                data_type: DataType::Text,
+                nullable: true,
+                origin: None,
+            };
+            for _ in 0..statement.parameter_count {
+                self.send_packet(|buf| write_column_packet(&parameter, capabilities, buf))
+                    .await?;
+            }
+            if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
+                self.send_packet(|buf| write_eof_packet(capabilities, buf))
+                    .await?;
+            }
+        }
+
+        if !statement.fields.is_empty() {
+            for field in &statement.fields {
+                self.send_packet(|buf| write_column_packet(field, capabilities, buf))
+                    .await?;
+            }
+            if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
+                self.send_packet(|buf| write_eof_packet(capabilities, buf))
+                    .await?;
+            }
+        }
+
+        self.prepared_statements.insert(
+            statement_id,
+            MysqlPreparedStatement {
+                statement,
+                parameter_types: vec![],
+                long_data: HashMap::new(),
+                long_data_too_long: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_stmt_execute.html
+    async fn process_execute_command(
+        &mut self,
+        com_stmt_execute: ComStmtExecutePacket,
+    ) -> Result<(), std::io::Error> {
+        let capabilities = self.capabilities;
+        let prepared = match self
+            .prepared_statements
+            .get_mut(&com_stmt_execute.statement_id)
+        {
+            Some(prepared) => prepared,
+            None => {
+                return self
+                    .send_packet(|buf| {
+                        write_err_packet_from_err(&MYSQL_ER_UNKNOWN_STMT_HANDLER, capabilities, buf)
+                    })
+                    .await
+            }
+        };
 
-                    if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
-                        self.send_packet(|buf| write_eof_packet(capabilities, buf))?;
+        if prepared.long_data_too_long {
+            prepared.long_data_too_long = false;
+            let msg = format!(
+                "Parameter of prepared statement which is set through mysql_send_long_data() is \
+                 longer than {} bytes",
+                MAX_LONG_DATA_LEN
+            );
+            let my_err = MyError {
+                msg: &msg,
+                ..MYSQL_ER_WRONG_ARGUMENTS
+            };
+            return self
+                .send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                .await;
+        }
+        let parameters = read_binary_parameters(
+            &com_stmt_execute.parameters,
+            prepared.statement.parameter_count,
+            &mut prepared.parameter_types,
+            &mut prepared.long_data,
+        );
+        prepared.long_data.clear();
+        match parameters {
+            Ok(parameters) => {
+                let session = Arc::clone(&self.connection.session);
+                // Timestamp parameters are in the session's time zone
+                let parameters: Vec<_> = parameters
+                    .into_iter()
+                    .map(|(datum, datatype)| match datum.as_maybe_timestamp() {
+                        Some(timestamp) if datatype == DataType::Timestamp => {
+                            (Datum::from(session.to_utc(timestamp)), datatype)
+                        }
+                        _ => (datum, datatype),
+                    })
+                    .collect();
+                session.statement_started(&prepared.statement.sql);
+                let statements = Statements::Prepared(prepared.statement.clone(), parameters);
+                let running = RunningStatements::spawn(Arc::clone(&self.connection), statements);
+                let sent = self.send_query_results(running, true).await;
+                self.connection.statement_finished();
+                sent
+            }
+            Err(err) => {
+                let my_err = MyError {
+                    msg: &err.to_string(),
+                    ..MYSQL_ER_WRONG_ARGUMENTS
+                };
+                self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                    .await
+            }
+        }
+    }
+
+    /// Sends the results of the running statements back to the client as they come in, prepared
+    /// statements get their rows sent in the binary format. A statement failing has an error
+    /// sent back in place of its results.
+    async fn send_query_results(
+        &mut self,
+        mut running: RunningStatements,
+        binary: bool,
+    ) -> Result<(), std::io::Error> {
+        let capabilities = self.capabilities;
+        let mut fields = vec![];
+        let mut datatypes = vec![];
+        let mut time_zone = self.connection.session.time_zone();
+        let mut rows_since_yield = 0;
+        let mut watch_client = true;
+        loop {
+            let message = tokio::select! {
+                message = running.next() => message?,
+                // Subscriptions can go quiet for a long time, without keeping an eye on the client
+                // they'd keep running long after it had gone
+                hung_up = client_hung_up(&self.stream), if watch_client => {
+                    if hung_up {
+                        return Ok(());
                     }
+                    // Whatever it's sent gets read once we're done here
+                    watch_client = false;
+                    continue;
                 }
-                let datatypes: Vec<_> = fields.iter().map(|f| f.data_type).collect();
-                loop {
-                    match executor.next() {
-                        Ok(Some((tuple, freq))) => {
-                            if !fields.is_empty() {
-                                for _ in 0..freq {
-                                    self.send_packet(|buf| {
-                                        write_tuple_packet(tuple, &datatypes, buf)
-                                    })?;
-                                }
-                            }
+            };
+            let message = match message {
+                Some(message) => message,
+                None => break,
+            };
+            match message {
+                StatementMessage::Started(statement_fields) => {
+                    fields = statement_fields;
+                    datatypes = fields.iter().map(|f| f.data_type).collect();
+                    time_zone = self.connection.session.time_zone();
+                    if !fields.is_empty() {
+                        self.send_packet(|buf| {
+                            write_resultset_packet(fields.len(), capabilities, buf)
+                        })
+                        .await?;
+                        for field in &fields {
+                            self.send_packet(|buf| write_column_packet(field, capabilities, buf))
+                                .await?;
                         }
-                        Ok(None) => break,
-                        Err(err) => {
-                            let my_err = MyError {
-                                msg: &err.to_string(),
-                                sql_state: "HY000",
-                                code: 1,
-                            };
-                            self.send_packet(|buf| {
-                                write_err_packet_from_err(&my_err, capabilities, buf)
-                            })?;
-                            return Ok(());
+
+                        if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
+                            self.send_packet(|buf| write_eof_packet(capabilities, buf))
+                                .await?;
                         }
                     }
                 }
-
-                if fields.is_empty() {
-                    self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))?;
-                } else if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
-                    self.send_packet(|buf| write_eof_packet(capabilities, buf))?;
-                } else {
-                    self.send_packet(|buf| write_ok_packet(true, 0, capabilities, buf))?;
+                StatementMessage::Row(tuple, freq) => {
+                    for _ in 0..freq {
+                        self.send_packet(|buf| {
+                            if binary {
+                                write_binary_tuple_packet(&tuple, &datatypes, time_zone, buf)
+                            } else {
+                                write_tuple_packet(&tuple, &datatypes, time_zone, buf)
+                            }
+                        })
+                        .await?;
+                        rows_since_yield += 1;
+                    }
+                    self.connection
+                        .session
+                        .rows_sent
+                        .fetch_add(freq.max(0) as u64, Ordering::Relaxed);
+                    // Give the other connections on this worker a look in
+                    if rows_since_yield >= ROWS_PER_YIELD {
+                        rows_since_yield = 0;
+                        yield_now().await;
+                    }
+                }
+                StatementMessage::Finished { more_results } => {
+                    if fields.is_empty() {
+                        let session = &self.connection.session;
+                        let affected_rows = session.affected_rows.load(Ordering::Relaxed);
+                        let last_insert_id = session.last_insert_id.load(Ordering::Relaxed);
+                        let info = session.info.read().unwrap().clone();
+                        self.send_packet(|buf| {
+                            write_ok_packet_with_info(
+                                false,
+                                affected_rows,
+                                last_insert_id,
+                                &info,
+                                more_results,
+                                capabilities,
+                                buf,
+                            )
+                        })
+                        .await?;
+                    } else if (capabilities & CAPABILITY_CLIENT_DEPRECATE_EOF) == 0 {
+                        self.send_packet(|buf| {
+                            write_eof_packet_with_status(more_results, capabilities, buf)
+                        })
+                        .await?;
+                    } else {
+                        self.send_packet(|buf| {
+                            write_ok_packet_with_info(
+                                true,
+                                0,
+                                0,
+                                "",
+                                more_results,
+                                capabilities,
+                                buf,
+                            )
+                        })
+                        .await?;
+                    }
                 }
+                StatementMessage::Failed(err) => self.send_query_error(&err).await?,
             }
-            Err(QueryError::ParseError(parse_error)) => {
+        }
+        Ok(())
+    }
+
+    async fn send_query_error(&mut self, err: &QueryError) -> Result<(), std::io::Error> {
+        let capabilities = self.capabilities;
+        match err {
+            QueryError::ParseError(parse_error) => {
                 let err = MyError {
                     msg: &parse_error.to_string(),
                     ..MYSQL_ER_PARSE_ERROR
                 };
-                self.send_packet(|buf| write_err_packet_from_err(&err, capabilities, buf))?;
+                self.send_packet(|buf| write_err_packet_from_err(&err, capabilities, buf))
+                    .await
             }
-            Err(err) => {
+            err if err.is_table_not_found() => {
+                let my_err = MyError {
+                    msg: &err.to_string(),
+                    ..MYSQL_ER_NO_SUCH_TABLE
+                };
+                self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                    .await
+            }
+            err if err.is_write_conflict() => {
+                let my_err = MyError {
+                    msg: &err.to_string(),
+                    ..MYSQL_ER_LOCK_DEADLOCK
+                };
+                self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                    .await
+            }
+            err if err.is_interrupted() => {
+                self.send_packet(|buf| {
+                    write_err_packet_from_err(&MYSQL_ER_QUERY_INTERRUPTED, capabilities, buf)
+                })
+                .await
+            }
+            err if err.is_timed_out() => {
+                self.send_packet(|buf| {
+                    write_err_packet_from_err(&MYSQL_ER_QUERY_TIMEOUT, capabilities, buf)
+                })
+                .await
+            }
+            err if err.is_out_of_range() => {
+                let my_err = MyError {
+                    msg: &err.to_string(),
+                    ..MYSQL_ER_DATA_OUT_OF_RANGE
+                };
+                self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                    .await
+            }
+            err => {
                 let my_err = MyError {
                     msg: &err.to_string(),
                     sql_state: "HY000",
                     code: 1,
                 };
-                self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))?;
+                self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                    .await
             }
         }
-        Ok(())
     }
 
-    /// Set up the initial handshake with the server
-    fn handshake(&mut self) -> Result<(), std::io::Error> {
+    /// Set up the initial handshake with the server, returns false if the client failed to
+    /// authenticate, in which case the connection should be closed.
+    async fn handshake(&mut self) -> Result<bool, std::io::Error> {
         // Note that these handshake packets in the connection phase don't have the command byte.
         let connection_id = self.connection.connection_id;
+        let scramble = self.scramble;
 
-        self.send_packet(|buf| write_handshake_packet(connection_id, buf))?;
+        self.send_packet(|buf| write_handshake_packet(connection_id, &scramble, buf))
+            .await?;
 
         // Receive response
-        let handshake_response = self.receive_packet::<HandshakeResponsePacket>()?;
+        let handshake_response = self.receive_packet::<HandshakeResponsePacket>().await?;
         let capabilities = handshake_response.client_flags;
         self.capabilities = capabilities;
-        *self.connection.session.user.write().unwrap() = handshake_response.username;
+
+        // If the client tried some other auth method ask it to switch to mysql_native_password
+        let auth_response = if handshake_response.client_plugin_name == "mysql_native_password" {
+            handshake_response.auth_response
+        } else {
+            self.send_packet(|buf| write_auth_switch_request_packet(&scramble, buf))
+                .await?;
+            self.receive_packet::<AuthSwitchResponsePacket>()
+                .await?
+                .data
+        };
+
+        let user = handshake_response.username;
+        if !self
+            .connection
+            .runtime
+            .authenticate(&user, &scramble, &auth_response)
+        {
+            let message = format!(
+                "Access denied for user '{}' (using password: {})",
+                user,
+                if auth_response.is_empty() {
+                    "NO"
+                } else {
+                    "YES"
+                }
+            );
+            self.send_packet(|buf| {
+                write_err_packet(
+                    MYSQL_ER_ACCESS_DENIED_ERROR.code,
+                    &message,
+                    MYSQL_ER_ACCESS_DENIED_ERROR.sql_state,
+                    capabilities,
+                    buf,
+                )
+            })
+            .await?;
+            return Ok(false);
+        }
+
+        if let Err(err) = self.connection.login(user) {
+            let my_err = MyError {
+                msg: &err.to_string(),
+                ..MYSQL_ER_TOO_MANY_USER_CONNECTIONS
+            };
+            self.send_packet(|buf| write_err_packet_from_err(&my_err, capabilities, buf))
+                .await?;
+            return Ok(false);
+        }
         if !handshake_response.database.is_empty() {
             *self.connection.session.current_database.write().unwrap() =
                 handshake_response.database;
         }
 
-        // Ask for user's password
-        self.send_packet(write_auth_switch_request_packet)?;
-
-        // Get back the user's password
-        let _auth_response = self.receive_packet::<AuthSwitchResponsePacket>()?;
-
         // Reply with Ok.
         self.send_packet(|buf| write_ok_packet(false, 0, capabilities, buf))
+            .await?;
+        // Everything after the Ok is compressed
+        self.compression =
+            Compression::negotiate(capabilities, handshake_response.zstd_compression_level);
+        Ok(true)
     }
 
     /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_basic_packets.html#sect_protocol_basic_packets_packet
     /// Takes a closure as input, that closure must populate the bytes for the packet being sent
-    fn send_packet<F: Fn(&mut Vec<u8>)>(&mut self, f: F) -> Result<(), std::io::Error> {
+    async fn send_packet<F: Fn(&mut Vec<u8>)>(&mut self, f: F) -> Result<(), std::io::Error> {
         self.packet_buf.clear();
         f(&mut self.packet_buf);
 
@@ -197,35 +703,130 @@ impl<'a> MysqlConnection<'a> {
 
             write_int_3(packet_length, &mut self.packet_header_buf);
             self.packet_header_buf.push(self.sequence_id.0);
-            self.stream.write_all(&self.packet_header_buf)?;
-            self.stream
-                .write_all(&payload_byte[..(packet_length as usize)])?;
+            if self.compression.is_some() {
+                self.write_buf.extend_from_slice(&self.packet_header_buf);
+                self.write_buf
+                    .extend_from_slice(&payload_byte[..(packet_length as usize)]);
+            } else {
+                self.stream.write_all(&self.packet_header_buf).await?;
+                self.stream
+                    .write_all(&payload_byte[..(packet_length as usize)])
+                    .await?;
+            }
 
             payload_length -= packet_length;
             payload_byte = &payload_byte[(packet_length as usize)..];
             self.sequence_id += Wrapping(1u8);
         }
-        self.stream.flush()?;
+        if self.compression.is_none() {
+            self.stream.flush().await?;
+        } else if self.write_buf.len() >= COMPRESS_BUFFER_SIZE {
+            self.flush_packets().await?;
+        }
         Ok(())
     }
 
-    fn receive_packet<P: ClientPacket>(&mut self) -> Result<P, std::io::Error> {
-        self.stream
-            .read_exact(self.packet_header_buf.as_mut_slice())?;
-        let mut header_bytes = self.packet_header_buf.as_slice();
+    /// With compression on packets are held back so they can be compressed together, this
+    /// compresses and sends anything that's still waiting. A no-op without compression.
+    async fn flush_packets(&mut self) -> Result<(), std::io::Error> {
+        if let Some(compression) = self.compression {
+            self.compressed_buf.clear();
+            for payload in self.write_buf.chunks(0xffffff) {
+                compression.write_packet(
+                    self.compressed_sequence_id.0,
+                    payload,
+                    &mut self.compressed_buf,
+                )?;
+                self.compressed_sequence_id += Wrapping(1u8);
+            }
+            self.write_buf.clear();
+            self.stream.write_all(&self.compressed_buf).await?;
+            self.stream.flush().await?;
+        }
+        Ok(())
+    }
+
+    async fn receive_packet<P: ClientPacket>(&mut self) -> Result<P, std::io::Error> {
+        let mut header = [0_u8; 4];
+        self.read_exact(&mut header).await?;
+        let mut header_bytes = header.as_ref();
         let mut packet_length = 0_u32;
         header_bytes = read_int_3(&mut packet_length, header_bytes);
 
         read_int_1(&mut self.sequence_id.0, header_bytes);
         self.sequence_id += Wrapping(1_u8);
 
-        // A bit yuk..., sizing vec so we can just use the stream.read_exact method
-        self.packet_buf.clear();
-        self.packet_buf.resize(packet_length as usize, 0);
-        self.stream.read_exact(&mut self.packet_buf)?;
+        // A bit yuk..., sizing vec so we can just use the read_exact method
+        let mut packet_buf = std::mem::take(&mut self.packet_buf);
+        packet_buf.clear();
+        packet_buf.resize(packet_length as usize, 0);
+        let read = self.read_exact(&mut packet_buf).await;
+        self.packet_buf = packet_buf;
+        read?;
         let packet = P::read(&self.packet_buf)?;
         Ok(packet)
     }
+
+    /// Fills the buffer from the stream, or with compression on from the decompressed contents
+    /// of the compressed packets.
+    async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), std::io::Error> {
+        if let Some(compression) = self.compression {
+            while self.read_buf.len() - self.read_offset < buf.len() {
+                self.receive_compressed_packet(compression).await?;
+            }
+            let end = self.read_offset + buf.len();
+            buf.copy_from_slice(&self.read_buf[self.read_offset..end]);
+            self.read_offset = end;
+        } else {
+            self.stream.read_exact(buf).await?;
+        }
+        Ok(())
+    }
+
+    async fn receive_compressed_packet(
+        &mut self,
+        compression: Compression,
+    ) -> Result<(), std::io::Error> {
+        self.read_buf.drain(..self.read_offset);
+        self.read_offset = 0;
+
+        let mut header = [0_u8; COMPRESSED_HEADER_LENGTH];
+        self.stream.read_exact(&mut header).await?;
+        let (compressed_length, sequence_id, uncompressed_length) = read_compressed_header(&header);
+        self.compressed_sequence_id = Wrapping(sequence_id) + Wrapping(1u8);
+
+        self.compressed_buf.clear();
+        self.compressed_buf.resize(compressed_length, 0);
+        self.stream.read_exact(&mut self.compressed_buf).await?;
+        compression.read_payload(
+            &self.compressed_buf,
+            uncompressed_length,
+            &mut self.read_buf,
+        )
+    }
+}
+
+/// Turns away a client connection that the runtime refused (ie there's already max_connections
+/// connections), like mysql the error is sent in place of the handshake.
+pub async fn reject_connection(
+    stream: std::net::TcpStream,
+    err: &QueryError,
+) -> Result<(), std::io::Error> {
+    stream.set_nonblocking(true)?;
+    let mut stream = TcpStream::from_std(stream)?;
+    let my_err = MyError {
+        msg: &err.to_string(),
+        ..MYSQL_ER_CON_COUNT_ERROR
+    };
+    // We don't know the client's capabilities yet so the error goes without the sql state.
+    let mut payload = vec![];
+    write_err_packet_from_err(&my_err, 0, &mut payload);
+    let mut packet = vec![];
+    write_int_3(payload.len() as u32, &mut packet);
+    packet.push(0);
+    packet.extend_from_slice(&payload);
+    stream.write_all(&packet).await?;
+    stream.flush().await
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -234,7 +835,15 @@ enum CommandPacket {
     ComQuit,
     ComInitDb(ComInitDbPacket),
     ComQuery(ComQueryPacket),
+    ComFieldList(ComFieldListPacket),
+    ComStatistics,
+    ComProcessInfo,
     ComPing,
+    ComStmtPrepare(ComStmtPreparePacket),
+    ComStmtExecute(ComStmtExecutePacket),
+    ComStmtSendLongData(ComStmtSendLongDataPacket),
+    ComStmtClose(u32),
+    ComStmtReset(u32),
     ComUnknown,
 }
 
@@ -244,7 +853,17 @@ impl ClientPacket for CommandPacket {
             0x01 => CommandPacket::ComQuit,
             0x02 => CommandPacket::ComInitDb(ComInitDbPacket::read(&buffer[1..])?),
             0x03 => CommandPacket::ComQuery(ComQueryPacket::read(&buffer[1..])?),
+            0x04 => CommandPacket::ComFieldList(ComFieldListPacket::read(&buffer[1..])?),
+            0x09 => CommandPacket::ComStatistics,
+            0x0A => CommandPacket::ComProcessInfo,
             0x0E => CommandPacket::ComPing,
+            0x16 => CommandPacket::ComStmtPrepare(ComStmtPreparePacket::read(&buffer[1..])?),
+            0x17 => CommandPacket::ComStmtExecute(ComStmtExecutePacket::read(&buffer[1..])?),
+            0x18 => {
+                CommandPacket::ComStmtSendLongData(ComStmtSendLongDataPacket::read(&buffer[1..])?)
+            }
+            0x19 => CommandPacket::ComStmtClose(read_statement_id(&buffer[1..])),
+            0x1A => CommandPacket::ComStmtReset(read_statement_id(&buffer[1..])),
             _ => CommandPacket::ComUnknown,
         };
 
@@ -252,6 +871,29 @@ impl ClientPacket for CommandPacket {
     }
 }
 
+fn read_statement_id(buffer: &[u8]) -> u32 {
+    let mut statement_id = 0;
+    read_int_4(&mut statement_id, buffer);
+    statement_id
+}
+
+/// Waits for the client to either hang up (true) or send something (false), anything sent is
+/// left to be read later.
+async fn client_hung_up(stream: &TcpStream) -> bool {
+    !matches!(stream.peek(&mut [0_u8]).await, Ok(read) if read > 0)
+}
+
+/// Generates the random 20 byte challenge used for mysql_native_password, the bytes are kept
+/// in the printable ascii range as clients treat the scramble as a null terminated string.
+fn generate_scramble() -> [u8; 20] {
+    let mut rng = rand::thread_rng();
+    let mut scramble = [0_u8; 20];
+    for byte in scramble.iter_mut() {
+        *byte = rng.gen_range(33, 127);
+    }
+    scramble
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,6 +913,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_stmt_command_packets() -> Result<(), Box<dyn Error>> {
+        let buf = [0x16, 0x53, 0x45, 0x4c, 0x45, 0x43, 0x54, 0x20, 0x3f].as_ref();
+        assert_eq!(
+            CommandPacket::read(&buf)?,
+            CommandPacket::ComStmtPrepare(ComStmtPreparePacket {
+                query: "SELECT ?".to_string()
+            })
+        );
+
+        let buf = [0x19, 0x02, 0x00, 0x00, 0x00].as_ref();
+        assert_eq!(CommandPacket::read(&buf)?, CommandPacket::ComStmtClose(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_command_packets() -> Result<(), Box<dyn Error>> {
+        let buf = [0x04, 0x74, 0x00].as_ref();
+        assert_eq!(
+            CommandPacket::read(&buf)?,
+            CommandPacket::ComFieldList(ComFieldListPacket {
+                table: "t".to_string(),
+                field_wildcard: "".to_string()
+            })
+        );
+        assert_eq!(CommandPacket::read(&[0x09])?, CommandPacket::ComStatistics);
+        assert_eq!(CommandPacket::read(&[0x0A])?, CommandPacket::ComProcessInfo);
+        Ok(())
+    }
+
     #[test]
     fn test_connection() -> Result<(), Box<dyn Error>> {
         Ok(())
@@ -1,8 +1,14 @@
 use crate::mysql::constants::*;
 use crate::mysql::protocol_base::*;
-use data::{DataType, Datum};
+use data::chrono::{Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use data::rust_decimal::prelude::ToPrimitive;
+use data::rust_decimal::Decimal;
+use data::{DataType, Datum, DECIMAL_MAX_PRECISION, SERVER_VERSION};
+use runtime::Field;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::str::FromStr;
 
 pub trait ClientPacket
 where
@@ -16,30 +22,34 @@ const SERVER_SUPPORTED_CAPABILITIES: u32 = CAPABILITY_CLIENT_LONG_PASSWORD
     | CAPABILITY_CLIENT_LONG_FLAG
     | CAPABILITY_CLIENT_CONNECT_WITH_DB
     | CAPABILITY_CLIENT_NO_SCHEMA
+    | CAPABILITY_CLIENT_COMPRESS
     | CAPABILITY_CLIENT_PROTOCOL_41
     | CAPABILITY_CLIENT_SECURE_CONNECTION
+    | CAPABILITY_CLIENT_MULTI_STATEMENTS
+    | CAPABILITY_CLIENT_MULTI_RESULTS
     | CAPABILITY_CLIENT_CONNECT_ATTRS
     | CAPABILITY_CLIENT_PLUGIN_AUTH
     | CAPABILITY_CLIENT_PLUGIN_AUTH_LENENC_CLIENT_DATA
-    | CAPABILITY_CLIENT_DEPRECATE_EOF;
+    | CAPABILITY_CLIENT_DEPRECATE_EOF
+    | CAPABILITY_CLIENT_ZSTD_COMPRESSION_ALGORITHM;
 
 /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_connection_phase_packets_protocol_handshake_v10.html
-pub fn write_handshake_packet(connection_id: u32, buffer: &mut Vec<u8>) {
+pub fn write_handshake_packet(connection_id: u32, scramble: &[u8; 20], buffer: &mut Vec<u8>) {
     let protocol_version = 10;
-    let server_version = "8.0.0-incresql";
-    let auth_plugin_data_part_1 = [1, 2, 3, 4, 5, 6, 7, 0];
+    let server_version = SERVER_VERSION;
+    let auth_plugin_data_part_1 = &scramble[..8];
     let filler = 0;
     let character_set = CHARSET_UTF8_GENERAL_CI;
     let status_flags = 0;
-    let auth_plugin_data_len = 20;
+    let auth_plugin_data_len = 21;
     let reserved = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-    let auth_plugin_data_part_2 = [1, 1, 2, 3, 4, 5, 6, 7, 8, 9, 1, 1, 0];
+    let auth_plugin_data_part_2 = &scramble[8..];
     let auth_plugin_name = "mysql_native_password";
 
     write_int_1(protocol_version, buffer);
     write_null_string(server_version, buffer);
     write_int_4(connection_id, buffer);
-    buffer.extend_from_slice(&auth_plugin_data_part_1);
+    buffer.extend_from_slice(auth_plugin_data_part_1);
     write_int_1(filler, buffer);
     write_int_2(SERVER_SUPPORTED_CAPABILITIES as u16, buffer);
     write_int_1(character_set, buffer);
@@ -51,7 +61,7 @@ pub fn write_handshake_packet(connection_id: u32, buffer: &mut Vec<u8>) {
         write_int_1(0, buffer);
     }
     buffer.extend_from_slice(&reserved);
-    buffer.extend_from_slice(&auth_plugin_data_part_2);
+    write_null_string(auth_plugin_data_part_2, buffer);
     if (SERVER_SUPPORTED_CAPABILITIES & CAPABILITY_CLIENT_PLUGIN_AUTH) != 0 {
         write_null_string(auth_plugin_name, buffer);
     }
@@ -68,6 +78,7 @@ pub struct HandshakeResponsePacket {
     pub database: String,
     pub client_plugin_name: String,
     pub client_connection_attrs: HashMap<String, String>,
+    pub zstd_compression_level: u8,
 }
 
 impl ClientPacket for HandshakeResponsePacket {
@@ -105,14 +116,20 @@ impl ClientPacket for HandshakeResponsePacket {
                 let mut len_kvs = 0_u64;
                 buffer = read_enc_int(&mut len_kvs, buffer);
 
-                while !buffer.is_empty() {
+                let mut kvs = &buffer[..(len_kvs as usize)];
+                buffer = &buffer[(len_kvs as usize)..];
+                while !kvs.is_empty() {
                     let mut key = String::new();
                     let mut value = String::new();
-                    buffer = read_enc_string(&mut key, buffer);
-                    buffer = read_enc_string(&mut value, buffer);
+                    kvs = read_enc_string(&mut key, kvs);
+                    kvs = read_enc_string(&mut value, kvs);
                     packet.client_connection_attrs.insert(key, value);
                 }
             }
+
+            if (packet.client_flags & CAPABILITY_CLIENT_ZSTD_COMPRESSION_ALGORITHM) != 0 {
+                buffer = read_int_1(&mut packet.zstd_compression_level, buffer);
+            }
         } else {
             packet.client_flags = lower_capibilities as u32 & SERVER_SUPPORTED_CAPABILITIES;
             buffer = read_int_3(&mut packet.max_packet_size, buffer);
@@ -133,14 +150,13 @@ impl ClientPacket for HandshakeResponsePacket {
     }
 }
 
-pub fn write_auth_switch_request_packet(buffer: &mut Vec<u8>) {
+pub fn write_auth_switch_request_packet(scramble: &[u8; 20], buffer: &mut Vec<u8>) {
     let status_tag = 0xFE;
     let plugin_name = "mysql_native_password";
-    let plugin_data = [1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5];
 
     write_int_1(status_tag, buffer);
     write_null_string(plugin_name, buffer);
-    write_null_string(&plugin_data, buffer);
+    write_null_string(&scramble[..], buffer);
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -182,6 +198,290 @@ impl ClientPacket for ComQueryPacket {
     }
 }
 
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_field_list.html
+#[derive(Default, Debug, PartialEq)]
+pub struct ComFieldListPacket {
+    pub table: String,
+    pub field_wildcard: String,
+}
+
+impl ClientPacket for ComFieldListPacket {
+    fn read(buffer: &[u8]) -> Result<Self, std::io::Error> {
+        let mut packet = Self::default();
+        let buffer = read_null_string(&mut packet.table, buffer);
+        read_eof_string(&mut packet.field_wildcard, buffer);
+        Ok(packet)
+    }
+}
+
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_stmt_prepare.html
+#[derive(Default, Debug, PartialEq)]
+pub struct ComStmtPreparePacket {
+    pub query: String,
+}
+
+impl ClientPacket for ComStmtPreparePacket {
+    fn read(buffer: &[u8]) -> Result<Self, std::io::Error> {
+        let mut packet = Self::default();
+        read_eof_string(&mut packet.query, buffer);
+        Ok(packet)
+    }
+}
+
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_stmt_execute.html
+/// The parameters can't be decoded until we know how many the statement has so they're just kept
+/// as raw bytes, see read_binary_parameters.
+#[derive(Default, Debug, PartialEq)]
+pub struct ComStmtExecutePacket {
+    pub statement_id: u32,
+    pub flags: u8,
+    pub parameters: Vec<u8>,
+}
+
+impl ClientPacket for ComStmtExecutePacket {
+    fn read(mut buffer: &[u8]) -> Result<Self, std::io::Error> {
+        let mut packet = Self::default();
+        let mut iteration_count = 0_u32;
+        buffer = read_int_4(&mut packet.statement_id, buffer);
+        buffer = read_int_1(&mut packet.flags, buffer);
+        buffer = read_int_4(&mut iteration_count, buffer);
+        read_eof_bytestring(&mut packet.parameters, buffer);
+        Ok(packet)
+    }
+}
+
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_stmt_send_long_data.html
+#[derive(Default, Debug, PartialEq)]
+pub struct ComStmtSendLongDataPacket {
+    pub statement_id: u32,
+    pub parameter_id: u16,
+    pub data: Vec<u8>,
+}
+
+impl ClientPacket for ComStmtSendLongDataPacket {
+    fn read(mut buffer: &[u8]) -> Result<Self, std::io::Error> {
+        let mut packet = Self::default();
+        buffer = read_int_4(&mut packet.statement_id, buffer);
+        buffer = read_int_2(&mut packet.parameter_id, buffer);
+        read_eof_bytestring(&mut packet.data, buffer);
+        Ok(packet)
+    }
+}
+
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_stmt_prepare.html#sect_protocol_com_stmt_prepare_response_ok
+pub fn write_stmt_prepare_ok_packet(
+    statement_id: u32,
+    column_count: usize,
+    parameter_count: usize,
+    buffer: &mut Vec<u8>,
+) {
+    let status = 0;
+    let reserved = 0;
+    let warnings = 0;
+
+    write_int_1(status, buffer);
+    write_int_4(statement_id, buffer);
+    write_int_2(column_count as u16, buffer);
+    write_int_2(parameter_count as u16, buffer);
+    write_int_1(reserved, buffer);
+    write_int_2(warnings, buffer);
+}
+
+/// Decodes the parameter values from a COM_STMT_EXECUTE.
+/// The parameter types are only sent by the client when they change so they need to be kept
+/// between executions, any parameters sent via COM_STMT_SEND_LONG_DATA are taken from long_data.
+pub fn read_binary_parameters(
+    mut buffer: &[u8],
+    parameter_count: usize,
+    parameter_types: &mut Vec<u16>,
+    long_data: &mut HashMap<u16, Vec<u8>>,
+) -> Result<Vec<(Datum<'static>, DataType)>, std::io::Error> {
+    if parameter_count == 0 {
+        return Ok(vec![]);
+    }
+
+    let (null_bitmap, rest) = take_bytes(buffer, (parameter_count + 7) / 8)?;
+    let (new_params_bound, rest) = take_bytes(rest, 1)?;
+    buffer = rest;
+    if new_params_bound[0] == 1 {
+        parameter_types.clear();
+        for _ in 0..parameter_count {
+            let (parameter_type, rest) = take_bytes(buffer, 2)?;
+            parameter_types.push(u16::from_le_bytes([parameter_type[0], parameter_type[1]]));
+            buffer = rest;
+        }
+    }
+    if parameter_types.len() != parameter_count {
+        return Err(invalid_data("Parameter types were never sent"));
+    }
+
+    let mut parameters = Vec::with_capacity(parameter_count);
+    for (idx, parameter_type) in parameter_types.iter().enumerate() {
+        if (null_bitmap[idx / 8] & (1 << (idx % 8))) != 0 {
+            parameters.push((Datum::Null, DataType::Null));
+        } else if let Some(data) = long_data.remove(&(idx as u16)) {
+            parameters.push(text_parameter(data));
+        } else {
+            let (parameter, rest) = read_binary_value(*parameter_type, buffer)?;
+            parameters.push(parameter);
+            buffer = rest;
+        }
+    }
+    Ok(parameters)
+}
+
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_binary_resultset.html#sect_protocol_binary_resultset_row_value
+fn read_binary_value(
+    parameter_type: u16,
+    buffer: &[u8],
+) -> Result<((Datum<'static>, DataType), &[u8]), std::io::Error> {
+    let unsigned = (parameter_type & 0x8000) != 0;
+    match parameter_type as u8 {
+        MYSQL_TYPE_NULL => Ok(((Datum::Null, DataType::Null), buffer)),
+        MYSQL_TYPE_TINY => {
+            let (bytes, rest) = take_bytes(buffer, 1)?;
+            let i = if unsigned {
+                bytes[0] as i32
+            } else {
+                bytes[0] as i8 as i32
+            };
+            Ok(((Datum::from(i), DataType::Integer), rest))
+        }
+        MYSQL_TYPE_SHORT | MYSQL_TYPE_YEAR => {
+            let (bytes, rest) = take_bytes(buffer, 2)?;
+            let bytes = [bytes[0], bytes[1]];
+            let i = if unsigned {
+                u16::from_le_bytes(bytes) as i32
+            } else {
+                i16::from_le_bytes(bytes) as i32
+            };
+            Ok(((Datum::from(i), DataType::Integer), rest))
+        }
+        MYSQL_TYPE_LONG | MYSQL_TYPE_INT24 => {
+            let (bytes, rest) = take_bytes(buffer, 4)?;
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            if unsigned {
+                let i = u32::from_le_bytes(bytes) as i64;
+                Ok(((Datum::from(i), DataType::BigInt), rest))
+            } else {
+                let i = i32::from_le_bytes(bytes);
+                Ok(((Datum::from(i), DataType::Integer), rest))
+            }
+        }
+        MYSQL_TYPE_LONGLONG => {
+            let (bytes, rest) = take_bytes(buffer, 8)?;
+            let mut array = [0_u8; 8];
+            array.copy_from_slice(bytes);
+            let i = i64::from_le_bytes(array);
+            if unsigned && i < 0 {
+                let d = Decimal::from(u64::from_le_bytes(array));
+                Ok((decimal_parameter(d), rest))
+            } else {
+                Ok(((Datum::from(i), DataType::BigInt), rest))
+            }
+        }
+        MYSQL_TYPE_FLOAT => {
+            let (bytes, rest) = take_bytes(buffer, 4)?;
+            let f = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            // Going via the shortest string representation so that ie 0.1 doesn't come through
+            // as 0.10000000149011612
+            let d = f64::from_str(&f.to_string()).unwrap_or_else(|_| f as f64);
+            Ok(((Datum::from(d), DataType::Double), rest))
+        }
+        MYSQL_TYPE_DOUBLE => {
+            let (bytes, rest) = take_bytes(buffer, 8)?;
+            let mut array = [0_u8; 8];
+            array.copy_from_slice(bytes);
+            let d = f64::from_le_bytes(array);
+            Ok(((Datum::from(d), DataType::Double), rest))
+        }
+        MYSQL_TYPE_DATE | MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP => {
+            let (length, rest) = take_bytes(buffer, 1)?;
+            let (bytes, rest) = take_bytes(rest, length[0] as usize)?;
+            let datetime = read_binary_datetime(bytes)?;
+            if parameter_type as u8 == MYSQL_TYPE_DATE {
+                Ok(((Datum::from(datetime.date()), DataType::Date), rest))
+            } else {
+                Ok(((Datum::from(datetime), DataType::Timestamp), rest))
+            }
+        }
+        MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => {
+            let (bytes, rest) = read_binary_bytes(buffer)?;
+            let text = String::from_utf8_lossy(bytes);
+            let d = Decimal::from_str(&text).map_err(|_| invalid_data("Invalid decimal"))?;
+            Ok((decimal_parameter(d), rest))
+        }
+        // Everything else (strings, blobs, json etc) is sent as a length encoded string
+        _ => {
+            let (bytes, rest) = read_binary_bytes(buffer)?;
+            Ok((text_parameter(bytes.to_vec()), rest))
+        }
+    }
+}
+
+/// Reads a length encoded string, checking that the whole string is actually there.
+fn read_binary_bytes(buffer: &[u8]) -> Result<(&[u8], &[u8]), std::io::Error> {
+    let (first, _) = take_bytes(buffer, 1)?;
+    let int_length = match first[0] {
+        0xFC => 3,
+        0xFD => 4,
+        0xFE => 9,
+        _ => 1,
+    };
+    take_bytes(buffer, int_length)?;
+    let mut length = 0_u64;
+    let rest = read_enc_int(&mut length, buffer);
+    take_bytes(rest, length as usize)
+}
+
+/// Dates and datetimes are sent as year, month, day followed by the time parts if non zero.
+fn read_binary_datetime(bytes: &[u8]) -> Result<NaiveDateTime, std::io::Error> {
+    if bytes.len() < 4 {
+        return Err(invalid_data("Zero dates aren't supported"));
+    }
+    let year = u16::from_le_bytes([bytes[0], bytes[1]]) as i32;
+    let date = NaiveDate::from_ymd_opt(year, bytes[2] as u32, bytes[3] as u32)
+        .ok_or_else(|| invalid_data("Invalid date"))?;
+
+    let (hour, minute, second) = if bytes.len() >= 7 {
+        (bytes[4] as u32, bytes[5] as u32, bytes[6] as u32)
+    } else {
+        (0, 0, 0)
+    };
+    let micros = if bytes.len() >= 11 {
+        u32::from_le_bytes([bytes[7], bytes[8], bytes[9], bytes[10]])
+    } else {
+        0
+    };
+    date.and_hms_micro_opt(hour, minute, second, micros)
+        .ok_or_else(|| invalid_data("Invalid time"))
+}
+
+fn text_parameter(bytes: Vec<u8>) -> (Datum<'static>, DataType) {
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+    (Datum::from(text), DataType::Text)
+}
+
+fn decimal_parameter(d: Decimal) -> (Datum<'static>, DataType) {
+    let scale = d.scale() as u8;
+    (
+        Datum::from(d),
+        DataType::Decimal(DECIMAL_MAX_PRECISION, scale),
+    )
+}
+
+fn take_bytes(buffer: &[u8], length: usize) -> Result<(&[u8], &[u8]), std::io::Error> {
+    if buffer.len() < length {
+        Err(invalid_data("Truncated parameter values"))
+    } else {
+        Ok(buffer.split_at(length))
+    }
+}
+
+fn invalid_data(msg: &str) -> std::io::Error {
+    std::io::Error::new(ErrorKind::InvalidData, msg)
+}
+
 pub fn write_err_packet(
     error_code: u16,
     error_message: &str,
@@ -205,11 +505,26 @@ pub fn write_err_packet_from_err(err: &MyError, capabilities: u32, buffer: &mut
     write_err_packet(err.code, err.msg, err.sql_state, capabilities, buffer)
 }
 
-pub fn write_tuple_packet(tuple: &[Datum], types: &[DataType], buffer: &mut Vec<u8>) {
+pub fn write_tuple_packet(
+    tuple: &[Datum],
+    types: &[DataType],
+    time_zone: FixedOffset,
+    buffer: &mut Vec<u8>,
+) {
     for (idx, value) in tuple.iter().enumerate() {
         match value {
             Datum::Null => buffer.push(0xFB),
             Datum::Boolean(b) => write_enc_string(if *b { "1" } else { "0" }, buffer),
+            // Bytes are sent as is rather than as text
+            _ if types[idx] == DataType::ByteA => write_enc_string(value.as_bytea(), buffer),
+            // Timestamps are stored as utc but sent in the session's time zone
+            _ if types[idx] == DataType::Timestamp => {
+                let timestamp = Datum::from(value.as_timestamp() + time_zone);
+                write_enc_string(
+                    format!("{}", timestamp.typed_with(DataType::Timestamp)),
+                    buffer,
+                )
+            }
             // TODO We could keep a buffer and write into that, then calc the length and copy across
             // to avoid format allocating strings...
             _ => write_enc_string(format!("{}", value.typed_with(types[idx])), buffer),
@@ -217,13 +532,98 @@ pub fn write_tuple_packet(tuple: &[Datum], types: &[DataType], buffer: &mut Vec<
     }
 }
 
+/// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_binary_resultset.html#sect_protocol_binary_resultset_row
+/// The values need to be written in the binary form of the column types from write_column_packet.
+pub fn write_binary_tuple_packet(
+    tuple: &[Datum],
+    types: &[DataType],
+    time_zone: FixedOffset,
+    buffer: &mut Vec<u8>,
+) {
+    let header = 0x00;
+    write_int_1(header, buffer);
+
+    // The null bitmap for result rows is offset by 2 bits
+    let null_bitmap_start = buffer.len();
+    buffer.resize(null_bitmap_start + (tuple.len() + 7 + 2) / 8, 0);
+    for (idx, value) in tuple.iter().enumerate() {
+        if value.is_null() {
+            let bit = idx + 2;
+            buffer[null_bitmap_start + bit / 8] |= 1 << (bit % 8);
+            continue;
+        }
+        match types[idx] {
+            DataType::Boolean => write_int_1(value.as_boolean() as u8, buffer),
+            DataType::TinyInt | DataType::UnsignedTinyInt => {
+                write_int_1(value.as_integer() as u8, buffer)
+            }
+            DataType::SmallInt | DataType::UnsignedSmallInt => {
+                write_int_2(value.as_integer() as u16, buffer)
+            }
+            DataType::Integer => write_int_4(value.as_integer() as u32, buffer),
+            DataType::UnsignedInteger => write_int_4(value.as_bigint() as u32, buffer),
+            DataType::BigInt => buffer.extend_from_slice(&value.as_bigint().to_le_bytes()),
+            DataType::UnsignedBigInt => buffer.extend_from_slice(
+                &value
+                    .as_decimal()
+                    .to_u64()
+                    .unwrap_or_default()
+                    .to_le_bytes(),
+            ),
+            DataType::Double => buffer.extend_from_slice(&value.as_double().to_le_bytes()),
+            DataType::Date => write_binary_datetime(value.as_date().and_hms(0, 0, 0), buffer),
+            DataType::Timestamp => write_binary_datetime(value.as_timestamp() + time_zone, buffer),
+            DataType::ByteA => write_enc_string(value.as_bytea(), buffer),
+            _ => write_enc_string(format!("{}", value.typed_with(types[idx])), buffer),
+        }
+    }
+}
+
+/// The time parts are left off when they're zero.
+fn write_binary_datetime(datetime: NaiveDateTime, buffer: &mut Vec<u8>) {
+    let micros = datetime.nanosecond() / 1000;
+    let length = if micros != 0 {
+        11
+    } else if datetime.time() != NaiveTime::from_hms(0, 0, 0) {
+        7
+    } else {
+        4
+    };
+
+    write_int_1(length, buffer);
+    write_int_2(datetime.year() as u16, buffer);
+    write_int_1(datetime.month() as u8, buffer);
+    write_int_1(datetime.day() as u8, buffer);
+    if length >= 7 {
+        write_int_1(datetime.hour() as u8, buffer);
+        write_int_1(datetime.minute() as u8, buffer);
+        write_int_1(datetime.second() as u8, buffer);
+    }
+    if length == 11 {
+        write_int_4(micros, buffer);
+    }
+}
+
 /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_basic_ok_packet.html
 pub fn write_ok_packet(eof: bool, affected_rows: u64, capabilities: u32, buffer: &mut Vec<u8>) {
+    write_ok_packet_with_info(eof, affected_rows, 0, "", false, capabilities, buffer)
+}
+
+/// An ok packet that also reports the last insert id and info message for the statement.
+/// more_results is set when the statement was one of many sent in a single query and there are
+/// more results to come.
+pub fn write_ok_packet_with_info(
+    eof: bool,
+    affected_rows: u64,
+    last_insert_id: u64,
+    info: &str,
+    more_results: bool,
+    capabilities: u32,
+    buffer: &mut Vec<u8>,
+) {
     let header = if eof { 0xFE } else { 0 };
-    let last_insert_id = 0;
-    let status_flags = STATUS_FLAG_AUTOCOMMIT;
+    let status_flags = status_flags(more_results);
     let warnings = 0;
-    let info = "";
 
     write_int_1(header, buffer);
     write_enc_int(affected_rows, buffer);
@@ -236,16 +636,21 @@ pub fn write_ok_packet(eof: bool, affected_rows: u64, capabilities: u32, buffer:
     }
 
     if (capabilities & CAPABILITY_CLIENT_SESSION_TRACK) != 0 {
-        write_null_string(info, buffer);
+        write_enc_string(info, buffer);
     } else {
         write_eof_string(info, buffer);
     }
 }
 
 pub fn write_eof_packet(capabilities: u32, buffer: &mut Vec<u8>) {
+    write_eof_packet_with_status(false, capabilities, buffer)
+}
+
+/// An eof packet that can flag that more results follow it.
+pub fn write_eof_packet_with_status(more_results: bool, capabilities: u32, buffer: &mut Vec<u8>) {
     let header = 0xFE;
     let warnings = 0;
-    let status_flags = STATUS_FLAG_AUTOCOMMIT; // Autocommit
+    let status_flags = status_flags(more_results);
 
     write_int_1(header, buffer);
     if (capabilities & CAPABILITY_CLIENT_PROTOCOL_41) != 0 {
@@ -254,64 +659,155 @@ pub fn write_eof_packet(capabilities: u32, buffer: &mut Vec<u8>) {
     }
 }
 
+fn status_flags(more_results: bool) -> u16 {
+    if more_results {
+        STATUS_FLAG_AUTOCOMMIT | STATUS_FLAG_MORE_RESULTS_EXISTS
+    } else {
+        STATUS_FLAG_AUTOCOMMIT
+    }
+}
+
 pub fn write_resultset_packet(column_count: usize, capabilities: u32, buffer: &mut Vec<u8>) {
     let metadata_follows = 1;
-    if (capabilities & CAPABILITY_CLIENT_MULTI_RESULTS) != 0 {
+    if (capabilities & CAPABILITY_CLIENT_OPTIONAL_RESULTSET_METADATA) != 0 {
         write_int_1(metadata_follows, buffer);
     }
     write_enc_int(column_count as u64, buffer);
 }
 
 /// https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_com_query_response_text_resultset_column_definition.html
-pub fn write_column_packet(
-    table: &str,
-    name: &str,
-    data_type: DataType,
-    capabilities: u32,
-    buffer: &mut Vec<u8>,
-) {
-    // 32768 is to be set for number fields, TIMESTAMP_FLAG   1024
-    let flags = 0_u16;
-    let character_set = CHARSET_UTF8_GENERAL_CI;
+pub fn write_column_packet(field: &Field, capabilities: u32, buffer: &mut Vec<u8>) {
+    let mut flags = if field.nullable {
+        0_u16
+    } else {
+        COLUMN_FLAG_NOT_NULL
+    };
+    // Mysql sends everything other than text as binary, even numbers.
+    let mut character_set = CHARSET_BINARY;
 
     // Just used for client formatting
     // 0x00 for integers and static strings
     // 0x1f for dynamic strings, double, float
     // 0x00 to 0x51 for decimals
     let mut decimals = 0;
-    // Used for client formatting, ie varchar(1024)
-    let mut column_length = 1024;
+    // The max display width of the column in bytes, ie 11 for an int, or varchar(1024)
+    let column_length;
 
-    let column_type = match data_type {
-        DataType::Null => MYSQL_TYPE_NULL,
-        DataType::Text | DataType::Json | DataType::JsonPath => {
+    let column_type = match field.data_type {
+        DataType::Null => {
+            column_length = 0;
+            MYSQL_TYPE_NULL
+        }
+        DataType::Text | DataType::Json | DataType::JsonPath | DataType::Vector(_) => {
+            character_set = CHARSET_UTF8_GENERAL_CI;
+            column_length = 1024;
             decimals = 0x1f;
             MYSQL_TYPE_VAR_STRING
         }
-        DataType::Integer => MYSQL_TYPE_LONG,
-        DataType::Date => MYSQL_TYPE_DATE,
-        DataType::BigInt => MYSQL_TYPE_LONGLONG,
-        DataType::Boolean => MYSQL_TYPE_TINY,
+        DataType::TinyInt => {
+            column_length = 4;
+            MYSQL_TYPE_TINY
+        }
+        DataType::SmallInt => {
+            column_length = 6;
+            MYSQL_TYPE_SHORT
+        }
+        DataType::Integer => {
+            column_length = 11;
+            MYSQL_TYPE_LONG
+        }
+        DataType::BigInt => {
+            column_length = 20;
+            MYSQL_TYPE_LONGLONG
+        }
+        DataType::UnsignedTinyInt => {
+            flags |= COLUMN_FLAG_UNSIGNED;
+            column_length = 3;
+            MYSQL_TYPE_TINY
+        }
+        DataType::UnsignedSmallInt => {
+            flags |= COLUMN_FLAG_UNSIGNED;
+            column_length = 5;
+            MYSQL_TYPE_SHORT
+        }
+        DataType::UnsignedInteger => {
+            flags |= COLUMN_FLAG_UNSIGNED;
+            column_length = 10;
+            MYSQL_TYPE_LONG
+        }
+        DataType::UnsignedBigInt => {
+            flags |= COLUMN_FLAG_UNSIGNED;
+            column_length = 20;
+            MYSQL_TYPE_LONGLONG
+        }
+        DataType::Double => {
+            column_length = 22;
+            decimals = 0x1f;
+            MYSQL_TYPE_DOUBLE
+        }
+        // Same as mysql's tinyint(1)
+        DataType::Boolean => {
+            column_length = 1;
+            MYSQL_TYPE_TINY
+        }
         DataType::Decimal(precision, scale) => {
-            column_length = precision as u32;
-            // Assuming this is meant to be the scale...
+            // Room for the sign and decimal point
+            column_length = precision as u32 + if scale > 0 { 2 } else { 1 };
             decimals = scale;
             MYSQL_TYPE_NEWDECIMAL
         }
-        DataType::ByteA => MYSQL_TYPE_BLOB,
+        DataType::ByteA => {
+            flags |= COLUMN_FLAG_BLOB;
+            column_length = u32::MAX;
+            MYSQL_TYPE_BLOB
+        }
+        DataType::Date => {
+            column_length = 10;
+            MYSQL_TYPE_DATE
+        }
         // It seems mysql's timestamp type can only represent a narrow
         // band of times for years 1970-2038 while the datetime is a bigger
         // type representing times for years 1000-9999
-        DataType::Timestamp => MYSQL_TYPE_DATETIME,
+        DataType::Timestamp => {
+            // ie datetime(6)
+            column_length = 26;
+            decimals = 6;
+            MYSQL_TYPE_DATETIME
+        }
+    };
+
+    if matches!(
+        column_type,
+        MYSQL_TYPE_TINY
+            | MYSQL_TYPE_SHORT
+            | MYSQL_TYPE_LONG
+            | MYSQL_TYPE_LONGLONG
+            | MYSQL_TYPE_DOUBLE
+            | MYSQL_TYPE_NEWDECIMAL
+    ) {
+        flags |= COLUMN_FLAG_NUM;
+    }
+    if character_set == CHARSET_BINARY && field.data_type != DataType::Null {
+        flags |= COLUMN_FLAG_BINARY;
+    }
+
+    let (schema, table, org_table, org_name) = match &field.origin {
+        Some(origin) => (
+            origin.database.as_str(),
+            origin.table_alias.as_str(),
+            origin.table.as_str(),
+            origin.column.as_str(),
+        ),
+        None => ("", field.qualifier.as_deref().unwrap_or_default(), "", ""),
     };
 
     if (capabilities & CAPABILITY_CLIENT_PROTOCOL_41) != 0 {
         write_enc_string("def", buffer);
-        write_enc_string("", buffer);
+        write_enc_string(schema, buffer);
         write_enc_string(table, buffer);
-        write_enc_string("", buffer);
-        write_enc_string(name, buffer);
-        write_enc_string("", buffer);
+        write_enc_string(org_table, buffer);
+        write_enc_string(&field.alias, buffer);
+        write_enc_string(org_name, buffer);
         write_enc_int(0x0C, buffer);
         write_int_2(character_set as u16, buffer);
         write_int_4(column_length, buffer);
@@ -323,7 +819,7 @@ pub fn write_column_packet(
         write_int_2(0, buffer);
     } else {
         write_enc_string(table, buffer);
-        write_enc_string(name, buffer);
+        write_enc_string(&field.alias, buffer);
         write_int_1(1_u8, buffer);
         write_int_1(column_type, buffer);
 
@@ -340,22 +836,34 @@ pub fn write_column_packet(
     }
 }
 
+/// The column definitions sent back for COM_FIELD_LIST have the column's default value tacked
+/// onto the end, which we always send as null.
+pub fn write_field_list_column_packet(field: &Field, capabilities: u32, buffer: &mut Vec<u8>) {
+    write_column_packet(field, capabilities, buffer);
+    write_int_1(0xFB, buffer);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use runtime::FieldOrigin;
     use std::error::Error;
 
+    const TEST_SCRAMBLE: [u8; 20] = [
+        1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20,
+    ];
+
     #[test]
     fn test_handshake_packet() {
         let mut buf = vec![];
-        write_handshake_packet(1, &mut buf);
+        write_handshake_packet(1, &TEST_SCRAMBLE, &mut buf);
         assert_eq!(
             buf.as_slice(),
             &[
                 10_u8, 56, 46, 48, 46, 48, 45, 105, 110, 99, 114, 101, 115, 113, 108, 0, 1, 0, 0,
-                0, 1, 2, 3, 4, 5, 6, 7, 0, 0, 31, 130, 33, 0, 0, 56, 1, 20, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 1, 1, 2, 3, 4, 5, 6, 7, 8, 9, 1, 1, 0, 109, 121, 115, 113, 108, 95, 110, 97,
-                116, 105, 118, 101, 95, 112, 97, 115, 115, 119, 111, 114, 100, 0
+                0, 1, 2, 3, 4, 5, 6, 7, 8, 0, 63, 130, 33, 0, 0, 59, 5, 21, 0, 0, 0, 0, 0, 0, 0, 0,
+                0, 0, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 0, 109, 121, 115, 113, 108,
+                95, 110, 97, 116, 105, 118, 101, 95, 112, 97, 115, 115, 119, 111, 114, 100, 0
             ] as &[u8]
         );
     }
@@ -412,19 +920,28 @@ mod tests {
             "5.6.6-m9"
         );
         assert_eq!(packet.client_connection_attrs["_platform"], "x86_64");
+        assert_eq!(packet.zstd_compression_level, 0);
+
+        // The zstd compression level comes after the connection attributes
+        let mut zstd_packet = raw_packet.to_vec();
+        zstd_packet[3] |= (CAPABILITY_CLIENT_ZSTD_COMPRESSION_ALGORITHM >> 24) as u8;
+        zstd_packet.push(7);
+        let packet = HandshakeResponsePacket::read(zstd_packet.as_ref())?;
+        assert_eq!(packet.client_connection_attrs["_platform"], "x86_64");
+        assert_eq!(packet.zstd_compression_level, 7);
         Ok(())
     }
 
     #[test]
     fn test_auth_switch_request_packet() {
         let mut buf = vec![];
-        write_auth_switch_request_packet(&mut buf);
+        write_auth_switch_request_packet(&TEST_SCRAMBLE, &mut buf);
         assert_eq!(
             buf.as_slice(),
             &[
                 254_u8, 109, 121, 115, 113, 108, 95, 110, 97, 116, 105, 118, 101, 95, 112, 97, 115,
-                115, 119, 111, 114, 100, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5,
-                5, 5, 0
+                115, 119, 111, 114, 100, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+                17, 18, 19, 20, 0
             ] as &[u8]
         );
     }
@@ -453,6 +970,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_com_field_list_packet() -> Result<(), Box<dyn Error>> {
+        let packet = ComFieldListPacket::read(b"foo\0a%")?;
+        assert_eq!(
+            packet,
+            ComFieldListPacket {
+                table: "foo".to_string(),
+                field_wildcard: "a%".to_string()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_field_list_column_packet() {
+        let mut buf = vec![];
+        write_field_list_column_packet(
+            &field(DataType::Integer, true),
+            SERVER_SUPPORTED_CAPABILITIES,
+            &mut buf,
+        );
+        assert_eq!(
+            buf.as_slice(),
+            [
+                3, 100, 101, 102, 0, 3, 102, 111, 111, 0, 3, 98, 97, 114, 0, 12, 63, 0, 11, 0, 0,
+                0, 3, 128, 128, 0, 0, 0, 0xFB
+            ]
+            .as_ref()
+        );
+    }
+
     #[test]
     fn test_err_packet() {
         let mut buf = vec![];
@@ -504,29 +1052,141 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ok_packet_with_info() {
+        let mut buf = vec![];
+        write_ok_packet_with_info(
+            false,
+            3,
+            7,
+            "Records: 3",
+            false,
+            SERVER_SUPPORTED_CAPABILITIES,
+            &mut buf,
+        );
+        let mut expected = vec![0x00, 0x03, 0x07, 0x02, 0x00, 0x00, 0x00];
+        expected.extend_from_slice(b"Records: 3");
+        assert_eq!(buf, expected);
+
+        let mut buf = vec![];
+        write_ok_packet_with_info(
+            false,
+            1,
+            0,
+            "",
+            true,
+            SERVER_SUPPORTED_CAPABILITIES,
+            &mut buf,
+        );
+        assert_eq!(buf, vec![0x00, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00]);
+    }
+
     #[test]
     fn test_eof_packet() {
         let mut buf = vec![];
         write_eof_packet(SERVER_SUPPORTED_CAPABILITIES, &mut buf);
         // Expected response from https://dev.mysql.com/doc/dev/mysql-server/8.0.12/page_protocol_basic_eof_packet.html
         assert_eq!(buf.as_slice(), [0xfe, 0x00, 0x00, 0x02, 0x00].as_ref());
+
+        let mut buf = vec![];
+        write_eof_packet_with_status(true, SERVER_SUPPORTED_CAPABILITIES, &mut buf);
+        assert_eq!(buf.as_slice(), [0xfe, 0x00, 0x00, 0x0a, 0x00].as_ref());
+    }
+
+    fn field(data_type: DataType, nullable: bool) -> Field {
+        Field {
+            qualifier: Some("foo".to_string()),
+            alias: "bar".to_string(),
+            data_type,
+            nullable,
+            origin: None,
+        }
     }
 
     #[test]
     fn test_column_packet() {
         let mut buf = vec![];
         write_column_packet(
-            "foo",
-            "bar",
-            DataType::Integer,
+            &field(DataType::Integer, true),
             SERVER_SUPPORTED_CAPABILITIES,
             &mut buf,
         );
         assert_eq!(
             buf.as_slice(),
             [
-                3, 100, 101, 102, 0, 3, 102, 111, 111, 0, 3, 98, 97, 114, 0, 12, 33, 0, 0, 4, 0, 0,
-                3, 0, 0, 0, 0, 0
+                3, 100, 101, 102, 0, 3, 102, 111, 111, 0, 3, 98, 97, 114, 0, 12, 63, 0, 11, 0, 0,
+                0, 3, 128, 128, 0, 0, 0
+            ]
+            .as_ref()
+        );
+
+        let mut buf = vec![];
+        write_column_packet(
+            &field(DataType::Integer, false),
+            SERVER_SUPPORTED_CAPABILITIES,
+            &mut buf,
+        );
+        assert_eq!(
+            buf.as_slice(),
+            [
+                3, 100, 101, 102, 0, 3, 102, 111, 111, 0, 3, 98, 97, 114, 0, 12, 63, 0, 11, 0, 0,
+                0, 3, 129, 128, 0, 0, 0
+            ]
+            .as_ref()
+        );
+
+        let mut buf = vec![];
+        write_column_packet(
+            &field(DataType::ByteA, true),
+            SERVER_SUPPORTED_CAPABILITIES,
+            &mut buf,
+        );
+        assert_eq!(
+            buf.as_slice(),
+            [
+                3, 100, 101, 102, 0, 3, 102, 111, 111, 0, 3, 98, 97, 114, 0, 12, 63, 0, 255, 255,
+                255, 255, 252, 144, 0, 0, 0, 0
+            ]
+            .as_ref()
+        );
+
+        let mut buf = vec![];
+        write_column_packet(
+            &field(DataType::Decimal(10, 2), true),
+            SERVER_SUPPORTED_CAPABILITIES,
+            &mut buf,
+        );
+        assert_eq!(
+            buf.as_slice(),
+            [
+                3, 100, 101, 102, 0, 3, 102, 111, 111, 0, 3, 98, 97, 114, 0, 12, 63, 0, 12, 0, 0,
+                0, 246, 128, 128, 2, 0, 0
+            ]
+            .as_ref()
+        );
+    }
+
+    #[test]
+    fn test_column_packet_origin() {
+        let field = Field {
+            qualifier: Some("o".to_string()),
+            alias: "c".to_string(),
+            data_type: DataType::Text,
+            nullable: true,
+            origin: Some(FieldOrigin {
+                database: "db".to_string(),
+                table_alias: "o".to_string(),
+                table: "orders".to_string(),
+                column: "id".to_string(),
+            }),
+        };
+        let mut buf = vec![];
+        write_column_packet(&field, SERVER_SUPPORTED_CAPABILITIES, &mut buf);
+        assert_eq!(
+            buf.as_slice(),
+            [
+                3, 100, 101, 102, 2, 100, 98, 1, 111, 6, 111, 114, 100, 101, 114, 115, 1, 99, 2,
+                105, 100, 12, 33, 0, 0, 4, 0, 0, 253, 0, 0, 31, 0, 0
             ]
             .as_ref()
         );
@@ -538,4 +1198,135 @@ mod tests {
         write_resultset_packet(4, SERVER_SUPPORTED_CAPABILITIES, &mut buf);
         assert_eq!(buf.as_slice(), [4].as_ref());
     }
+
+    #[test]
+    fn test_com_stmt_execute_packet() -> Result<(), Box<dyn Error>> {
+        let raw_packet = [1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 3, 0, 2, 0, 0, 0];
+        let packet = ComStmtExecutePacket::read(raw_packet.as_ref())?;
+        assert_eq!(packet.statement_id, 1);
+        assert_eq!(packet.parameters, vec![0, 1, 3, 0, 2, 0, 0, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stmt_prepare_ok_packet() {
+        let mut buf = vec![];
+        write_stmt_prepare_ok_packet(1, 2, 3, &mut buf);
+        assert_eq!(
+            buf.as_slice(),
+            [0, 1, 0, 0, 0, 2, 0, 3, 0, 0, 0, 0].as_ref()
+        );
+    }
+
+    #[test]
+    fn test_read_binary_parameters() -> Result<(), Box<dyn Error>> {
+        let mut parameter_types = vec![];
+        let mut long_data = HashMap::new();
+        // null bitmap, new params bound, types (long, null, string, datetime, unsigned longlong)
+        let mut raw = vec![0b0000_0010, 1, 3, 0, 6, 0, 0xfd, 0, 12, 0, 8, 0x80];
+        raw.extend_from_slice(&[2, 0, 0, 0]);
+        raw.extend_from_slice(&[3, b'a', b'b', b'c']);
+        raw.extend_from_slice(&[7, 0xe4, 0x07, 1, 2, 3, 4, 5]);
+        raw.extend_from_slice(&[0xff; 8]);
+        let parameters = read_binary_parameters(&raw, 5, &mut parameter_types, &mut long_data)?;
+        assert_eq!(
+            parameters,
+            vec![
+                (Datum::from(2), DataType::Integer),
+                (Datum::Null, DataType::Null),
+                (Datum::from("abc".to_string()), DataType::Text),
+                (
+                    Datum::from(NaiveDate::from_ymd(2020, 1, 2).and_hms(3, 4, 5)),
+                    DataType::Timestamp
+                ),
+                (
+                    Datum::from(Decimal::from(std::u64::MAX)),
+                    DataType::Decimal(DECIMAL_MAX_PRECISION, 0)
+                ),
+            ]
+        );
+
+        // Types aren't resent on the next execution, and the string comes in as long data
+        long_data.insert(2, b"def".to_vec());
+        let mut raw = vec![0b0001_1010, 0];
+        raw.extend_from_slice(&[5, 0, 0, 0]);
+        let parameters = read_binary_parameters(&raw, 5, &mut parameter_types, &mut long_data)?;
+        assert_eq!(parameters[0], (Datum::from(5), DataType::Integer));
+        assert_eq!(
+            parameters[2],
+            (Datum::from("def".to_string()), DataType::Text)
+        );
+        assert!(long_data.is_empty());
+
+        assert!(
+            read_binary_parameters(&raw[..3], 5, &mut parameter_types, &mut long_data).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_tuple_packet() {
+        let mut buf = vec![];
+        write_binary_tuple_packet(
+            &[
+                Datum::from(1),
+                Datum::Null,
+                Datum::from("ab"),
+                Datum::from(NaiveDate::from_ymd(2020, 1, 2)),
+            ],
+            &[
+                DataType::Integer,
+                DataType::Text,
+                DataType::Text,
+                DataType::Date,
+            ],
+            FixedOffset::east(0),
+            &mut buf,
+        );
+        assert_eq!(
+            buf.as_slice(),
+            [
+                0,
+                0b0000_1000,
+                1,
+                0,
+                0,
+                0,
+                2,
+                b'a',
+                b'b',
+                4,
+                0xe4,
+                0x07,
+                1,
+                2
+            ]
+            .as_ref()
+        );
+    }
+
+    #[test]
+    fn test_tuple_packet_time_zone() {
+        let timestamp = Datum::from(NaiveDate::from_ymd(2020, 1, 2).and_hms(23, 0, 0));
+        let mut buf = vec![];
+        write_tuple_packet(
+            &[timestamp.ref_clone()],
+            &[DataType::Timestamp],
+            FixedOffset::east(3600),
+            &mut buf,
+        );
+        assert_eq!(buf.as_slice(), b"\x132020-01-03 00:00:00".as_ref());
+
+        let mut buf = vec![];
+        write_binary_tuple_packet(
+            &[timestamp],
+            &[DataType::Timestamp],
+            FixedOffset::west(3600),
+            &mut buf,
+        );
+        assert_eq!(
+            buf.as_slice(),
+            [0, 0, 7, 0xe4, 0x07, 1, 2, 22, 0, 0].as_ref()
+        );
+    }
 }
@@ -0,0 +1,166 @@
+use rand::Rng;
+use runtime::auth::{native_password_response, verify_native_password};
+use runtime::{ReplicatedBatch, Replication};
+use std::error::Error;
+use std::io::{BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tracing::{error, warn};
+
+/// How often an idle leader lets its followers know it's still there.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The user followers have to log in as, they're sent everything including the users table.
+const REPLICATION_USER: &str = "root";
+
+/// The most we'll read for the user name or auth response while a follower logs in.
+const MAX_LOGIN_FIELD_LEN: usize = 256;
+
+/// The largest batch a follower will accept from the leader.
+const MAX_BATCH_LEN: usize = 1024 * 1024 * 1024;
+
+/// Listens for followers, shipping each a snapshot of the storage followed by every batch
+/// committed on this (the leader) instance.
+/// Followers first have to log in as root, the leader sends a random 20 byte scramble and the
+/// follower replies with <len as u32 le><user> and <len as u32 le><mysql_native_password
+/// response>, if they don't check out the connection is dropped.
+/// Batches are then sent as <len as u32 le><batch>.
+pub fn serve_followers(
+    replication: Replication,
+    addr: &str,
+) -> Result<JoinHandle<()>, std::io::Error> {
+    replication.enable();
+    let listener = TcpListener::bind(addr)?;
+    let heartbeat = replication.clone();
+    thread::spawn(move || loop {
+        thread::sleep(HEARTBEAT_INTERVAL);
+        heartbeat.heartbeat();
+    });
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            if let Ok(stream) = stream {
+                let replication = replication.clone();
+                thread::spawn(move || {
+                    let peer = stream.peer_addr();
+                    if let Err(err) = ship_batches(&replication, stream) {
+                        error!(?peer, %err, "Replication error for follower");
+                    }
+                });
+            }
+        }
+    }))
+}
+
+fn ship_batches(replication: &Replication, mut stream: TcpStream) -> Result<(), Box<dyn Error>> {
+    let mut rng = rand::thread_rng();
+    let mut scramble = [0_u8; 20];
+    rng.fill(&mut scramble);
+    stream.write_all(&scramble)?;
+    let user = String::from_utf8(read_bytes(&mut stream, MAX_LOGIN_FIELD_LEN)?)?;
+    let auth_response = read_bytes(&mut stream, MAX_LOGIN_FIELD_LEN)?;
+    let authenticated = user == REPLICATION_USER
+        && match replication.user_authentication_string(&user)? {
+            Some(authentication_string) => {
+                verify_native_password(&authentication_string, &scramble, &auth_response)
+            }
+            None => false,
+        };
+    if !authenticated {
+        warn!(peer = ?stream.peer_addr(), %user, "Follower failed to log in");
+        return Ok(());
+    }
+
+    let mut buffer = vec![];
+    let mut send_batch = |batch: &ReplicatedBatch| -> Result<(), Box<dyn Error>> {
+        buffer.clear();
+        batch.encode(&mut buffer);
+        write_bytes(&mut stream, &buffer)?;
+        Ok(())
+    };
+    let receiver = replication.subscribe(|batch| send_batch(&batch))?;
+    for batch in receiver {
+        send_batch(&batch)?;
+    }
+    // We've been dropped for falling too far behind, the follower will reconnect.
+    Ok(())
+}
+
+/// Follows the leader at leader_addr, logging in as user and applying every batch shipped to
+/// us. If the connection to the leader is lost we'll reconnect and start again from a fresh
+/// snapshot.
+pub fn follow(
+    replication: Replication,
+    leader_addr: String,
+    user: String,
+    password: String,
+) -> JoinHandle<()> {
+    thread::spawn(move || loop {
+        if let Err(err) = apply_batches(&replication, &leader_addr, &user, &password) {
+            error!(%leader_addr, %err, "Replication error for leader");
+        }
+        thread::sleep(Duration::from_secs(1));
+    })
+}
+
+fn apply_batches(
+    replication: &Replication,
+    leader_addr: &str,
+    user: &str,
+    password: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut stream = TcpStream::connect(leader_addr)?;
+    let mut scramble = [0_u8; 20];
+    stream.read_exact(&mut scramble)?;
+    write_bytes(&mut stream, user.as_bytes())?;
+    write_bytes(&mut stream, &native_password_response(password, &scramble))?;
+
+    let mut stream = BufReader::new(stream);
+    loop {
+        let batch = ReplicatedBatch::decode(&read_bytes(&mut stream, MAX_BATCH_LEN)?)?;
+        replication.apply(&batch)?;
+    }
+}
+
+/// Reads a length prefixed message, erroring rather than allocating if the length is over
+/// max_len.
+fn read_bytes(stream: &mut impl Read, max_len: usize) -> Result<Vec<u8>, std::io::Error> {
+    let mut len_buffer = [0_u8; 4];
+    stream.read_exact(&mut len_buffer)?;
+    let len = u32::from_le_bytes(len_buffer) as usize;
+    if len > max_len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Message of {} bytes is over the limit of {}", len, max_len),
+        ));
+    }
+    let mut buffer = vec![0; len];
+    stream.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn write_bytes(stream: &mut impl Write, bytes: &[u8]) -> Result<(), std::io::Error> {
+    stream.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    stream.write_all(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_bytes_limit() {
+        let mut message = vec![];
+        write_bytes(&mut message, b"root").unwrap();
+        assert_eq!(read_bytes(&mut message.as_slice(), 4).unwrap(), b"root");
+
+        let oversized = u32::MAX.to_le_bytes();
+        assert_eq!(
+            read_bytes(&mut oversized.as_ref(), MAX_LOGIN_FIELD_LEN)
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+}
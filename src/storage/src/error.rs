@@ -5,12 +5,20 @@ use std::fmt::{Display, Formatter};
 #[derive(Debug, Eq, PartialEq)]
 pub enum StorageError {
     RocksDbError(String),
+    ReplicationError(String),
+    // The table id, a row being retracted was written to by someone else since we started
+    WriteConflict(u32),
 }
 
 impl Display for StorageError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             StorageError::RocksDbError(err) => f.write_str(err),
+            StorageError::ReplicationError(err) => f.write_str(err),
+            StorageError::WriteConflict(table_id) => f.write_fmt(format_args!(
+                "Write conflict on table {}, try restarting the statement",
+                table_id
+            )),
         }
     }
 }
@@ -1,7 +1,9 @@
 mod error;
+mod replication;
 mod storage;
 mod table;
 
-pub use crate::storage::Storage;
-pub use crate::table::Table;
+pub use crate::replication::{ReplicatedBatch, ReplicatedWrite};
+pub use crate::storage::{Storage, StorageConfig};
+pub use crate::table::{LogIter, RawWriter, Table, Transaction, Writer};
 pub use error::StorageError;
@@ -0,0 +1,234 @@
+use crate::error::StorageError;
+use data::LogicalTimestamp;
+use std::convert::TryInto;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A single key level write shipped from the leader to its followers.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ReplicatedWrite {
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+    DeleteRange(Vec<u8>, Vec<u8>),
+}
+
+/// A committed write batch, followers apply these in the order received.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct ReplicatedBatch {
+    /// The position in the leader's log once this batch has been applied.
+    pub sequence: u64,
+    /// The leader's clock (ms since 1970) as the batch was shipped, every write in the batch
+    /// was timestamped before this. Followers read as of the last one they've applied.
+    pub timestamp: u64,
+    /// The tables written to, used to bump the table versions on the follower.
+    pub tables: Vec<u32>,
+    pub writes: Vec<ReplicatedWrite>,
+}
+
+const PUT: u8 = 0;
+const DELETE: u8 = 1;
+const DELETE_RANGE: u8 = 2;
+
+impl ReplicatedBatch {
+    /// Serializes the batch to be sent over the wire, appending to buffer.
+    pub fn encode(&self, buffer: &mut Vec<u8>) {
+        buffer.extend_from_slice(&self.sequence.to_le_bytes());
+        buffer.extend_from_slice(&self.timestamp.to_le_bytes());
+        buffer.extend_from_slice(&(self.tables.len() as u32).to_le_bytes());
+        for table in &self.tables {
+            buffer.extend_from_slice(&table.to_le_bytes());
+        }
+        buffer.extend_from_slice(&(self.writes.len() as u32).to_le_bytes());
+        for write in &self.writes {
+            match write {
+                ReplicatedWrite::Put(key, value) => {
+                    buffer.push(PUT);
+                    encode_bytes(key, buffer);
+                    encode_bytes(value, buffer);
+                }
+                ReplicatedWrite::Delete(key) => {
+                    buffer.push(DELETE);
+                    encode_bytes(key, buffer);
+                }
+                ReplicatedWrite::DeleteRange(from, to) => {
+                    buffer.push(DELETE_RANGE);
+                    encode_bytes(from, buffer);
+                    encode_bytes(to, buffer);
+                }
+            }
+        }
+    }
+
+    /// Deserializes a batch written by encode.
+    pub fn decode(mut buffer: &[u8]) -> Result<Self, StorageError> {
+        let sequence = u64::from_le_bytes(take(&mut buffer, 8)?.try_into().unwrap());
+        let timestamp = u64::from_le_bytes(take(&mut buffer, 8)?.try_into().unwrap());
+        let table_count = decode_u32(&mut buffer)?;
+        let tables = (0..table_count)
+            .map(|_| decode_u32(&mut buffer))
+            .collect::<Result<_, _>>()?;
+        let write_count = decode_u32(&mut buffer)?;
+        let mut writes = Vec::with_capacity(write_count as usize);
+        for _ in 0..write_count {
+            let write = match take(&mut buffer, 1)?[0] {
+                PUT => ReplicatedWrite::Put(decode_bytes(&mut buffer)?, decode_bytes(&mut buffer)?),
+                DELETE => ReplicatedWrite::Delete(decode_bytes(&mut buffer)?),
+                DELETE_RANGE => ReplicatedWrite::DeleteRange(
+                    decode_bytes(&mut buffer)?,
+                    decode_bytes(&mut buffer)?,
+                ),
+                tag => {
+                    return Err(StorageError::ReplicationError(format!(
+                        "Unknown write type {}",
+                        tag
+                    )))
+                }
+            };
+            writes.push(write);
+        }
+        Ok(ReplicatedBatch {
+            sequence,
+            timestamp,
+            tables,
+            writes,
+        })
+    }
+}
+
+fn encode_bytes(bytes: &[u8], buffer: &mut Vec<u8>) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+fn take<'a>(buffer: &mut &'a [u8], len: usize) -> Result<&'a [u8], StorageError> {
+    if buffer.len() < len {
+        return Err(StorageError::ReplicationError(
+            "Truncated replication batch".to_string(),
+        ));
+    }
+    let (bytes, rest) = buffer.split_at(len);
+    *buffer = rest;
+    Ok(bytes)
+}
+
+fn decode_u32(buffer: &mut &[u8]) -> Result<u32, StorageError> {
+    Ok(u32::from_le_bytes(take(buffer, 4)?.try_into().unwrap()))
+}
+
+fn decode_bytes(buffer: &mut &[u8]) -> Result<Vec<u8>, StorageError> {
+    let len = decode_u32(buffer)? as usize;
+    Ok(take(buffer, len)?.to_vec())
+}
+
+/// Tracks the followers subscribed to the writes of a storage instance, and on the follower side
+/// how far through the leader's log we are.
+#[derive(Debug, Default)]
+pub(crate) struct ReplicationLog {
+    enabled: AtomicBool,
+    applied_sequence: AtomicU64,
+    applied_timestamp: AtomicU64,
+    state: Mutex<ReplicationState>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ReplicationState {
+    pub(crate) sequence: u64,
+    pub(crate) timestamp: u64,
+    pub(crate) followers: Vec<SyncSender<Arc<ReplicatedBatch>>>,
+}
+
+impl ReplicationLog {
+    pub(crate) fn enable(&self) {
+        self.enabled.store(true, Ordering::SeqCst);
+    }
+
+    /// When enabled, writers need to keep a copy of their writes to ship to followers.
+    pub(crate) fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Locks the log, commits hold this across the rocksdb write and the publish so that
+    /// followers see batches in commit order. Returns None when replication isn't enabled.
+    pub(crate) fn lock(&self) -> Option<MutexGuard<'_, ReplicationState>> {
+        if self.enabled() {
+            Some(self.state.lock().unwrap())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn applied_sequence(&self) -> u64 {
+        self.applied_sequence.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn applied_timestamp(&self) -> u64 {
+        self.applied_timestamp.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_applied(&self, sequence: u64, timestamp: u64) {
+        self.applied_sequence.store(sequence, Ordering::SeqCst);
+        self.applied_timestamp.store(timestamp, Ordering::SeqCst);
+    }
+}
+
+impl ReplicationState {
+    /// Ships a committed batch off to all the followers, followers that have gone away or have
+    /// fallen too far behind are dropped.
+    pub(crate) fn publish(&mut self, tables: Vec<u32>, writes: Vec<ReplicatedWrite>) {
+        self.sequence += 1;
+        self.send(tables, writes);
+    }
+
+    /// Ships an empty batch to the followers, so that when the leader is idle they still move
+    /// their read timestamp forward and can tell how far behind they are.
+    pub(crate) fn heartbeat(&mut self) {
+        self.send(vec![], vec![]);
+    }
+
+    /// The timestamp for the next batch, it never goes backwards even if the clock does and is
+    /// never before the writes already made.
+    pub(crate) fn next_timestamp(&mut self) -> u64 {
+        self.timestamp = self.timestamp.max(LogicalTimestamp::latest().ms);
+        self.timestamp
+    }
+
+    fn send(&mut self, tables: Vec<u32>, writes: Vec<ReplicatedWrite>) {
+        if self.followers.is_empty() {
+            return;
+        }
+        let batch = Arc::new(ReplicatedBatch {
+            sequence: self.sequence,
+            timestamp: self.next_timestamp(),
+            tables,
+            writes,
+        });
+        self.followers
+            .retain(|follower| follower.try_send(Arc::clone(&batch)).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() -> Result<(), StorageError> {
+        let batch = ReplicatedBatch {
+            sequence: 12,
+            timestamp: 1_600_000_000_000,
+            tables: vec![2, 4],
+            writes: vec![
+                ReplicatedWrite::Put(vec![0, 0, 0, 2, 1], vec![5, 6]),
+                ReplicatedWrite::Delete(vec![0, 0, 0, 4]),
+                ReplicatedWrite::DeleteRange(vec![0, 0, 0, 6], vec![0, 0, 0, 8]),
+            ],
+        };
+        let mut buffer = vec![];
+        batch.encode(&mut buffer);
+        assert_eq!(ReplicatedBatch::decode(&buffer)?, batch);
+
+        assert!(ReplicatedBatch::decode(&buffer[..buffer.len() - 1]).is_err());
+        Ok(())
+    }
+}
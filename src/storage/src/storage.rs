@@ -1,22 +1,64 @@
 use crate::error::StorageError;
-use crate::table::Table;
+use crate::replication::{ReplicatedBatch, ReplicatedWrite, ReplicationLog};
+use crate::table::{Table, TableVersions};
 use data::encoding_core::{SortableEncoding, VARINT_SIGNED_ZERO_ENC};
-use data::SortOrder;
+use data::{LogicalTimestamp, SortOrder};
 use rocksdb::compaction_filter::Decision;
 use rocksdb::{
-    BlockBasedOptions, DBCompressionType, Env, MergeOperands, Options, SliceTransform, DB,
+    BlockBasedOptions, DBCompressionType, Env, MergeOperands, Options, ReadOptions, SliceTransform,
+    WriteBatch, WriteOptions, DB,
 };
 use std::fmt::{Debug, Formatter};
+use std::sync::mpsc::{sync_channel, Receiver};
 use std::sync::Arc;
 
 /// The storage subsystem, used to manage low-level storage of tables and atomicity
 /// via rockdb's write batch operations.
 /// Adding/Removing tables etc should happen via the catalog, at this abstraction level a table has
 /// no name, its just referenced via a u32
+#[derive(Clone)]
 pub struct Storage {
     db: Arc<DB>,
+    versions: Arc<TableVersions>,
+    replication: Arc<ReplicationLog>,
 }
 
+/// The rocksdb tuning knobs, the memory used by rocksdb is mostly down to the block cache plus
+/// the write buffers (memtables).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct StorageConfig {
+    /// Bytes of uncompressed blocks cached in memory.
+    pub block_cache_size: usize,
+    /// Bytes written into a memtable before it's flushed out to disk.
+    pub write_buffer_size: usize,
+    /// Threads shared between flushes and compactions.
+    pub parallelism: i32,
+    /// The max number of flushes and compactions running at once.
+    pub max_background_jobs: i32,
+    /// The max number of files kept open, -1 for no limit.
+    pub max_open_files: i32,
+    /// Bits per key used by the bloom filters, 0 to turn them off.
+    pub bloom_filter_bits: i32,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            block_cache_size: 8 * 1024 * 1024,
+            write_buffer_size: 64 * 1024 * 1024,
+            parallelism: 4,
+            max_background_jobs: 2,
+            max_open_files: -1,
+            bloom_filter_bits: 10,
+        }
+    }
+}
+
+/// The approx number of bytes of each batch making up the initial snapshot sent to followers.
+const SNAPSHOT_BATCH_SIZE: usize = 4 * 1024 * 1024;
+/// How many committed batches a follower can fall behind by before it's dropped.
+const FOLLOWER_QUEUE_LENGTH: usize = 1024;
+
 impl Debug for Storage {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.write_str("Storage")
@@ -56,8 +98,8 @@ impl Debug for Storage {
 // <tuple-rest> in the value of the index section and the <tuple> in the log section will be written
 // in a non-sortable encoding
 //
-// We expect writes to the log section to be merges due to them being made up of deltas while the writes to
-// the log sections are likely to be puts/deletes due to them being absolute frequencies.
+// The log section is made up of deltas, a write adds to any change already logged for the same
+// tuple and timestamp, while the writes to the index section are absolute frequencies.
 //
 // Prefixes will be written as big endian, meaning that the fourth byte in the key should signal
 // if we're in the log or indexes sections.
@@ -65,33 +107,180 @@ impl Debug for Storage {
 impl Storage {
     /// Crates a new storage engine(rocks db) with data stored in the given path
     pub fn new_with_path(path: &str) -> Result<Self, StorageError> {
-        let options = Storage::options();
+        Storage::new_with_config(path, &StorageConfig::default())
+    }
+
+    /// Crates a new storage engine(rocks db) with data stored in the given path, tuned using
+    /// the given config
+    pub fn new_with_config(path: &str, config: &StorageConfig) -> Result<Self, StorageError> {
+        let options = Storage::options(config);
         let db = Arc::from(DB::open(&options, path)?);
 
-        Ok(Storage { db })
+        Ok(Storage {
+            db,
+            versions: Arc::default(),
+            replication: Arc::default(),
+        })
     }
 
     /// Creates a new in memory backed storage.
     /// to be used for testing etc
     pub fn new_in_mem() -> Result<Self, StorageError> {
-        let mut options = Storage::options();
+        let mut options = Storage::options(&StorageConfig::default());
         let env = Env::mem_env()?;
         options.set_env(&env);
         // TODO memory leak here, looking at the c api it looks like we should own the env
         // and lend it to the db for it's whole lifetime.
         std::mem::forget(env);
         let db = Arc::from(DB::open(&options, "")?);
-        Ok(Storage { db })
+        Ok(Storage {
+            db,
+            versions: Arc::default(),
+            replication: Arc::default(),
+        })
     }
 
     /// Returns the table for the given id and primary key info.
     pub fn table(&self, id: u32, length: usize, pk: Vec<SortOrder>) -> Table {
         assert_eq!(id & 1, 0, "Not a valid table id");
-        Table::new(Arc::clone(&self.db), id, length, pk)
+        Table::new(
+            Arc::clone(&self.db),
+            Arc::clone(&self.versions),
+            Arc::clone(&self.replication),
+            id,
+            length,
+            pk,
+        )
     }
 
-    /// Return the our default rocks db options
-    fn options() -> Options {
+    /// Syncs the write ahead log and flushes the memtables out to disk, used when shutting down
+    /// so the next start up doesn't need to replay the log.
+    pub fn flush(&self) -> Result<(), StorageError> {
+        let mut write_options = WriteOptions::new();
+        write_options.set_sync(true);
+        self.db.write_opt(WriteBatch::default(), &write_options)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    /// Turns on shipping of committed write batches to followers, from this point on writers
+    /// keep a copy of their writes and commits are serialized.
+    pub fn enable_replication(&self) {
+        self.replication.enable();
+    }
+
+    /// Subscribes a follower to the writes of this storage instance. The follower is first sent
+    /// a snapshot of the tables returned by table_ids (which should be all of them) via
+    /// send_snapshot, the returned receiver then yields every batch committed after the snapshot
+    /// was taken. Writes are only blocked while the (rocksdb) snapshot is taken, not while it's
+    /// sent.
+    /// A follower that falls more than FOLLOWER_QUEUE_LENGTH batches behind is dropped (its
+    /// receiver disconnects), it'll have to subscribe again and start from a fresh snapshot.
+    pub fn subscribe<F, S, E>(
+        &self,
+        table_ids: F,
+        mut send_snapshot: S,
+    ) -> Result<Receiver<Arc<ReplicatedBatch>>, E>
+    where
+        F: FnOnce() -> Result<Vec<u32>, StorageError>,
+        S: FnMut(ReplicatedBatch) -> Result<(), E>,
+        E: From<StorageError>,
+    {
+        let (snapshot, table_ids, sequence, timestamp, receiver) = {
+            let mut replication = self.replication.lock().ok_or_else(|| {
+                StorageError::ReplicationError("Replication is not enabled".to_string())
+            })?;
+            let table_ids = table_ids()?;
+            let (sender, receiver) = sync_channel(FOLLOWER_QUEUE_LENGTH);
+            replication.followers.push(sender);
+            let sequence = replication.sequence;
+            let timestamp = replication.next_timestamp();
+            (self.db.snapshot(), table_ids, sequence, timestamp, receiver)
+        };
+
+        // Start by clearing out whatever the follower had.
+        let mut batch = ReplicatedBatch {
+            sequence,
+            timestamp,
+            tables: table_ids.clone(),
+            writes: vec![ReplicatedWrite::DeleteRange(
+                0_u32.to_be_bytes().to_vec(),
+                u32::MAX.to_be_bytes().to_vec(),
+            )],
+        };
+        let mut batch_size = 0;
+        for table_id in &table_ids {
+            // Both the index and log sections of the table
+            for prefix in &[*table_id, *table_id + 1] {
+                let mut iter_options = ReadOptions::default();
+                iter_options.set_prefix_same_as_start(true);
+                iter_options.set_iterate_upper_bound((prefix + 1).to_be_bytes());
+                let mut iter = snapshot.raw_iterator_opt(iter_options);
+                iter.seek(&prefix.to_be_bytes());
+                while iter.valid() {
+                    let key = iter.key().unwrap().to_vec();
+                    let value = iter.value().unwrap().to_vec();
+                    batch_size += key.len() + value.len();
+                    batch.writes.push(ReplicatedWrite::Put(key, value));
+                    if batch_size > SNAPSHOT_BATCH_SIZE {
+                        send_snapshot(batch)?;
+                        batch = ReplicatedBatch {
+                            sequence,
+                            timestamp,
+                            tables: vec![],
+                            writes: vec![],
+                        };
+                        batch_size = 0;
+                    }
+                    iter.next();
+                }
+                iter.status().map_err(StorageError::from)?;
+            }
+        }
+        send_snapshot(batch)?;
+        Ok(receiver)
+    }
+
+    /// Applies a batch shipped from the leader, batches must be applied in the order they were
+    /// received.
+    pub fn apply_replicated(&self, batch: &ReplicatedBatch) -> Result<(), StorageError> {
+        let mut write_batch = WriteBatch::default();
+        for write in &batch.writes {
+            match write {
+                ReplicatedWrite::Put(key, value) => write_batch.put(key, value),
+                ReplicatedWrite::Delete(key) => write_batch.delete(key),
+                ReplicatedWrite::DeleteRange(from, to) => write_batch.delete_range(from, to),
+            }
+        }
+        let mut write_options = WriteOptions::new();
+        write_options.set_sync(true);
+        self.db.write_opt(write_batch, &write_options)?;
+        self.versions.bump(batch.tables.iter().copied());
+        self.replication
+            .set_applied(batch.sequence, batch.timestamp);
+        Ok(())
+    }
+
+    /// Sends an empty batch to the followers, see ReplicationState::heartbeat.
+    pub fn replication_heartbeat(&self) {
+        if let Some(mut replication) = self.replication.lock() {
+            replication.heartbeat();
+        }
+    }
+
+    /// The sequence of the last batch applied from the leader.
+    pub fn replicated_sequence(&self) -> u64 {
+        self.replication.applied_sequence()
+    }
+
+    /// The leader's timestamp for the last batch applied from it, zero if we've never applied
+    /// one.
+    pub fn replicated_timestamp(&self) -> LogicalTimestamp {
+        LogicalTimestamp::new(self.replication.applied_timestamp())
+    }
+
+    /// Return the our rocks db options
+    fn options(config: &StorageConfig) -> Options {
         let mut options = Options::default();
         let mut block_options = BlockBasedOptions::default();
         // These options are non-negotiable
@@ -103,9 +292,15 @@ impl Storage {
         options.set_compaction_filter("compaction_filter", compaction_filter);
 
         // These options are "tunable"
-        block_options.set_bloom_filter(10, false);
+        if config.bloom_filter_bits > 0 {
+            block_options.set_bloom_filter(config.bloom_filter_bits, false);
+        }
+        block_options.set_lru_cache(config.block_cache_size);
         options.set_block_based_table_factory(&block_options);
-        options.increase_parallelism(4);
+        options.set_write_buffer_size(config.write_buffer_size);
+        options.increase_parallelism(config.parallelism);
+        options.set_max_background_jobs(config.max_background_jobs);
+        options.set_max_open_files(config.max_open_files);
         options.set_compression_type(DBCompressionType::Lz4);
         options
     }
@@ -168,6 +363,7 @@ fn compaction_filter(_level: u32, key: &[u8], value: &[u8]) -> Decision {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use data::{Datum, LogicalTimestamp, TupleIter};
 
     /// Decision doesn't impl eq or debug etc, this is just to make the compactionfilter output
     /// testable
@@ -268,4 +464,77 @@ mod tests {
         assert_eq!(table.id(), 1234);
         Ok(())
     }
+
+    #[test]
+    fn test_replication() -> Result<(), StorageError> {
+        let leader = Storage::new_in_mem()?;
+        let follower = Storage::new_in_mem()?;
+        let leader_table = leader.table(1234, 1, vec![SortOrder::Asc]);
+        let follower_table = follower.table(1234, 1, vec![SortOrder::Asc]);
+
+        // Not enabled yet
+        assert!(leader
+            .subscribe(|| Ok(vec![1234]), |_batch| Ok::<_, StorageError>(()))
+            .is_err());
+        leader.enable_replication();
+
+        leader_table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(
+                &leader_table,
+                &[Datum::from(1)],
+                LogicalTimestamp::new(1),
+                1,
+            )
+        })?;
+
+        let receiver =
+            leader.subscribe(|| Ok(vec![1234]), |batch| follower.apply_replicated(&batch))?;
+        assert_eq!(follower_table.version(), 1);
+
+        leader_table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(
+                &leader_table,
+                &[Datum::from(2)],
+                LogicalTimestamp::new(2),
+                1,
+            )
+        })?;
+
+        while let Ok(batch) = receiver.try_recv() {
+            follower.apply_replicated(&batch)?;
+        }
+        assert_eq!(follower.replicated_sequence(), 2);
+        let timestamp = follower.replicated_timestamp();
+        assert!(timestamp > LogicalTimestamp::new(0));
+
+        leader.replication_heartbeat();
+        follower.apply_replicated(&receiver.try_recv().unwrap())?;
+        assert_eq!(follower.replicated_sequence(), 2);
+        assert!(follower.replicated_timestamp() >= timestamp);
+        assert_eq!(follower_table.version(), 2);
+
+        let mut iter = follower_table.full_scan(LogicalTimestamp::MAX);
+        assert_eq!(iter.next()?, Some(([Datum::from(1)].as_ref(), 1)));
+        assert_eq!(iter.next()?, Some(([Datum::from(2)].as_ref(), 1)));
+        assert_eq!(iter.next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_replication_drops_lagging_followers() -> Result<(), StorageError> {
+        let leader = Storage::new_in_mem()?;
+        leader.enable_replication();
+        let receiver = leader.subscribe(|| Ok(vec![]), |_batch| Ok::<_, StorageError>(()))?;
+
+        for _ in 0..=FOLLOWER_QUEUE_LENGTH {
+            leader.replication_heartbeat();
+        }
+
+        assert_eq!(receiver.try_iter().count(), FOLLOWER_QUEUE_LENGTH);
+        assert!(matches!(
+            receiver.try_recv(),
+            Err(std::sync::mpsc::TryRecvError::Disconnected)
+        ));
+        Ok(())
+    }
 }
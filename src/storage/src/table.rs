@@ -1,11 +1,13 @@
+use crate::replication::{ReplicatedWrite, ReplicationLog};
 use crate::StorageError;
 use data::encoding_core::SortableEncoding;
 use data::{Datum, LogicalTimestamp, SortOrder, TupleIter};
 use rocksdb::prelude::*;
 use rocksdb::{DBRawIterator, WriteBatch, WriteBatchWithIndex};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::fmt::{Debug, Formatter};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 /// A Table is at this level is a collection of rows, identified by an id.
 /// We'll expose all of these tables by id in some special schema but in general not all of these
@@ -18,6 +20,8 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct Table {
     db: Arc<DB>,
+    versions: Arc<TableVersions>,
+    replication: Arc<ReplicationLog>,
     id: u32,
     length: usize,
     pk: Vec<SortOrder>,
@@ -39,9 +43,23 @@ impl Debug for Table {
 impl Table {
     /// Creates a new table. The pk represents the number of columns in the pk and their sort
     /// orders
-    pub(crate) fn new(db: Arc<DB>, id: u32, length: usize, pk: Vec<SortOrder>) -> Self {
+    pub(crate) fn new(
+        db: Arc<DB>,
+        versions: Arc<TableVersions>,
+        replication: Arc<ReplicationLog>,
+        id: u32,
+        length: usize,
+        pk: Vec<SortOrder>,
+    ) -> Self {
         assert!(length >= pk.len());
-        Table { db, id, length, pk }
+        Table {
+            db,
+            versions,
+            replication,
+            id,
+            length,
+            pk,
+        }
     }
 
     /// Returns the id of the table.
@@ -49,6 +67,23 @@ impl Table {
         self.id
     }
 
+    /// Returns the sort orders of the primary key columns of the table.
+    pub fn pk(&self) -> &[SortOrder] {
+        &self.pk
+    }
+
+    /// Returns the number of columns in the table's tuples.
+    pub fn column_count(&self) -> usize {
+        self.length
+    }
+
+    /// Returns the write version of the table, this is a counter that's bumped every time a
+    /// write batch touching this table is committed. It's not persisted so is only meaningful for
+    /// comparing against versions seen previously by this process, ie for cache invalidation.
+    pub fn version(&self) -> u64 {
+        self.versions.get(self.id)
+    }
+
     /// Forces a rocks db compaction of the table, we'll expose this out in sql as it may be useful
     /// after bulk loads or for benchmark tests as it blocks until compaction is done
     pub fn force_rocks_compaction(&self) {
@@ -65,14 +100,52 @@ impl Table {
     where
         F: FnOnce(&mut Writer) -> Result<(), E>,
     {
-        let mut writer = Writer::new();
+        self.atomic_write_impl(true, batch)
+    }
+
+    /// Like atomic_write but without waiting for the write to be fsync'd, used to speed up bulk
+    /// loads. Call sync_wal afterwards to make sure the writes have made it to disk.
+    pub fn atomic_write_unsynced<F, E: From<StorageError>>(&self, batch: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Writer) -> Result<(), E>,
+    {
+        self.atomic_write_impl(false, batch)
+    }
+
+    fn atomic_write_impl<F, E: From<StorageError>>(&self, sync: bool, batch: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Writer) -> Result<(), E>,
+    {
+        let mut writer = Writer::new(self.replication.enabled());
         batch(&mut writer)?;
+        Ok(self.commit(writer, sync)?)
+    }
+
+    /// Writes out everything buffered in the writer, the writes (to any table) become visible
+    /// all at once.
+    fn commit(&self, writer: Writer, sync: bool) -> Result<(), StorageError> {
         let mut write_options = WriteOptions::new();
-        write_options.set_sync(true);
+        write_options.set_sync(sync);
         write_options.set_low_pri(true);
-        self.db
-            .write_opt(writer.write_batch, &write_options)
-            .map_err(StorageError::from)?;
+        let replication = self.replication.lock();
+        self.db.write_opt(writer.write_batch, &write_options)?;
+        // Only bump the versions once the data is visible
+        self.versions.bump(writer.tables_written.iter().copied());
+        if let Some(mut replication) = replication {
+            replication.publish(
+                writer.tables_written.into_iter().collect(),
+                writer.replicated_writes.unwrap_or_default(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Fsyncs the write ahead log, making any earlier unsynced writes durable.
+    pub fn sync_wal(&self) -> Result<(), StorageError> {
+        // A synced write syncs everything written to the log before it too.
+        let mut write_options = WriteOptions::new();
+        write_options.set_sync(true);
+        self.db.write_opt(WriteBatch::default(), &write_options)?;
         Ok(())
     }
 
@@ -80,16 +153,28 @@ impl Table {
     /// unsupported rocks db functionality
     pub fn atomic_write_without_index<F, E: From<StorageError>>(&self, batch: F) -> Result<(), E>
     where
-        F: FnOnce(&mut WriteBatch) -> Result<(), E>,
+        F: FnOnce(&mut RawWriter) -> Result<(), E>,
     {
-        let mut write_batch = WriteBatch::default();
-        batch(&mut write_batch)?;
+        let mut writer = RawWriter {
+            write_batch: WriteBatch::default(),
+            replicated_writes: if self.replication.enabled() {
+                Some(vec![])
+            } else {
+                None
+            },
+        };
+        batch(&mut writer)?;
         let mut write_options = WriteOptions::new();
         write_options.set_sync(true);
         write_options.set_low_pri(true);
+        let replication = self.replication.lock();
         self.db
-            .write_opt(write_batch, &write_options)
+            .write_opt(writer.write_batch, &write_options)
             .map_err(StorageError::from)?;
+        self.versions.bump(std::iter::once(self.id));
+        if let Some(mut replication) = replication {
+            replication.publish(vec![self.id], writer.replicated_writes.unwrap_or_default());
+        }
         Ok(())
     }
 
@@ -144,6 +229,19 @@ impl Table {
         from: Option<&[Datum]>,
         to: Option<&[Datum]>,
         timestamp: LogicalTimestamp,
+    ) -> impl TupleIter<E = StorageError> + '_ {
+        self.projected_range_scan(from, to, timestamp, None)
+    }
+
+    /// Range scan of the table that only decodes the columns flagged in the column mask, the
+    /// rest of the columns come back as nulls. Used when only a few columns of a wide table are
+    /// needed.
+    pub fn projected_range_scan(
+        &self,
+        from: Option<&[Datum]>,
+        to: Option<&[Datum]>,
+        timestamp: LogicalTimestamp,
+        column_mask: Option<&[bool]>,
     ) -> impl TupleIter<E = StorageError> + '_ {
         let mut iter_options = ReadOptions::default();
         iter_options.set_prefix_same_as_start(true);
@@ -167,7 +265,34 @@ impl Table {
             iter.seek(&self.id.to_be_bytes());
         }
 
-        IndexIter::new(iter, timestamp, self.length)
+        IndexIter::new(
+            iter,
+            timestamp,
+            self.length,
+            column_mask.map(<[bool]>::to_vec),
+        )
+    }
+
+    /// Scans the log section of the table, returning every change made to the table at or after
+    /// the passed in timestamp in timestamp order.
+    pub fn log_scan(&self, from: LogicalTimestamp) -> LogIter<'_> {
+        let mut iter_options = ReadOptions::default();
+        iter_options.set_prefix_same_as_start(true);
+        iter_options.set_iterate_upper_bound((self.id + 2).to_be_bytes());
+        let mut iter = self.db.raw_iterator_opt(iter_options);
+
+        let mut buf = vec![];
+        buf.extend_from_slice(&(self.id + 1).to_be_bytes());
+        buf.extend_from_slice(&from.ms.to_be_bytes());
+        iter.seek(&buf);
+
+        LogIter {
+            iter,
+            first: true,
+            tuple_buffer: right_size_new_to(self.length),
+            timestamp: LogicalTimestamp::default(),
+            freq: None,
+        }
     }
 }
 
@@ -181,10 +306,17 @@ struct IndexIter<'a> {
     first: bool,
     tuple_buffer: Vec<Datum<'static>>,
     freq: Option<i64>,
+    // The columns to decode, None for all of them
+    column_mask: Option<Vec<bool>>,
 }
 
 impl<'a> IndexIter<'a> {
-    fn new(iter: DBRawIterator<'a>, timestamp: LogicalTimestamp, column_count: usize) -> Self {
+    fn new(
+        iter: DBRawIterator<'a>,
+        timestamp: LogicalTimestamp,
+        column_count: usize,
+        column_mask: Option<Vec<bool>>,
+    ) -> Self {
         let tuple_buffer = right_size_new_to(column_count);
         IndexIter {
             iter,
@@ -192,10 +324,25 @@ impl<'a> IndexIter<'a> {
             first: true,
             tuple_buffer,
             freq: None,
+            column_mask,
         }
     }
 }
 
+/// Decodes the column into the datum unless it's been masked out, in which case it's skipped
+/// over leaving the datum as null.
+fn read_column<'a>(
+    datum: &mut Datum<'static>,
+    column_mask: &Option<Vec<bool>>,
+    idx: usize,
+    buffer: &'a [u8],
+) -> &'a [u8] {
+    match column_mask {
+        Some(mask) if !mask[idx] => Datum::skip_sortable_bytes(buffer),
+        _ => datum.from_sortable_bytes(buffer),
+    }
+}
+
 impl TupleIter for IndexIter<'_> {
     type E = StorageError;
 
@@ -221,8 +368,9 @@ impl TupleIter for IndexIter<'_> {
                 // Tuple Pk
                 let mut tuple_pk_len = 0_u64;
                 key_buf = tuple_pk_len.read_sortable_bytes(SortOrder::Asc, &key_buf);
-                for idx in 0..tuple_pk_len {
-                    key_buf = self.tuple_buffer[idx as usize].from_sortable_bytes(key_buf);
+                for idx in 0..tuple_pk_len as usize {
+                    key_buf =
+                        read_column(&mut self.tuple_buffer[idx], &self.column_mask, idx, key_buf);
                 }
 
                 // Timestamp
@@ -261,10 +409,84 @@ impl TupleIter for IndexIter<'_> {
                 // non-pk part of the tuple
                 let mut datum_count = 0_u64;
                 value_buf = datum_count.read_sortable_bytes(SortOrder::Asc, value_buf);
+                for idx in (tuple_pk_len..tuple_pk_len + datum_count).map(|idx| idx as usize) {
+                    value_buf = read_column(
+                        &mut self.tuple_buffer[idx],
+                        &self.column_mask,
+                        idx,
+                        value_buf,
+                    );
+                }
+                break;
+            } else {
+                self.freq = None;
+                self.iter.status()?;
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self) -> Option<(&[Datum<'_>], i64)> {
+        if let Some(freq) = self.freq {
+            Some((&self.tuple_buffer, freq))
+        } else {
+            None
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        self.tuple_buffer.len()
+    }
+}
+
+/// TupleIter implementation for iterating over the log section of tables, the freqs returned
+/// are the change in freq of the tuple at the timestamp.
+pub struct LogIter<'a> {
+    iter: DBRawIterator<'a>,
+    first: bool,
+    tuple_buffer: Vec<Datum<'static>>,
+    timestamp: LogicalTimestamp,
+    freq: Option<i64>,
+}
+
+impl LogIter<'_> {
+    /// The timestamp of the change at the current position of the iterator
+    pub fn timestamp(&self) -> LogicalTimestamp {
+        self.timestamp
+    }
+}
+
+impl TupleIter for LogIter<'_> {
+    type E = StorageError;
+
+    fn advance(&mut self) -> Result<(), StorageError> {
+        loop {
+            if self.first {
+                self.first = false;
+            } else {
+                self.iter.next();
+            }
+
+            if self.iter.valid() {
+                // key = <prefix+1 as u32 be>:<timestamp as u64 be>:<tuple as sorted>
+                // value = <freq delta as i64 varint>
+                let mut freq = 0_i64;
+                freq.read_sortable_bytes(SortOrder::Asc, self.iter.value().unwrap());
+                // Changes that cancelled each other out
+                if freq == 0 {
+                    continue;
+                }
+
+                let key_buf = &self.iter.key().unwrap()[4..];
+                self.timestamp.ms = u64::from_be_bytes(key_buf[..8].try_into().unwrap());
+                let mut key_buf = &key_buf[8..];
+                let mut datum_count = 0_u64;
+                key_buf = datum_count.read_sortable_bytes(SortOrder::Asc, key_buf);
                 for idx in 0..datum_count {
-                    value_buf = self.tuple_buffer[(tuple_pk_len + idx) as usize]
-                        .from_sortable_bytes(value_buf);
+                    key_buf = self.tuple_buffer[idx as usize].from_sortable_bytes(key_buf);
                 }
+                self.freq = Some(freq);
                 break;
             } else {
                 self.freq = None;
@@ -288,23 +510,60 @@ impl TupleIter for IndexIter<'_> {
     }
 }
 
+/// Per table write counters, shared between all the tables of a storage instance.
+#[derive(Debug, Default)]
+pub(crate) struct TableVersions {
+    versions: Mutex<HashMap<u32, u64>>,
+}
+
+impl TableVersions {
+    fn get(&self, id: u32) -> u64 {
+        self.versions
+            .lock()
+            .unwrap()
+            .get(&id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn bump<I: Iterator<Item = u32>>(&self, ids: I) {
+        let mut versions = self.versions.lock().unwrap();
+        for id in ids {
+            *versions.entry(id).or_default() += 1;
+        }
+    }
+}
+
 /// Abstraction through which all writes happens, allows some degree of
 /// read after write functionality which is not offered by rocksdb.
 pub struct Writer {
     write_batch: WriteBatchWithIndex,
     key_buf: Vec<u8>,
     value_buf: Vec<u8>,
+    batch_size: usize,
+    tables_written: HashSet<u32>,
+    // A copy of the writes to ship to followers, only kept when replication is enabled
+    replicated_writes: Option<Vec<ReplicatedWrite>>,
 }
 
 impl Writer {
-    fn new() -> Self {
+    fn new(replicated: bool) -> Self {
         Writer {
             write_batch: WriteBatchWithIndex::default(),
             key_buf: Vec::with_capacity(64),
             value_buf: Vec::with_capacity(64),
+            batch_size: 0,
+            tables_written: HashSet::new(),
+            replicated_writes: if replicated { Some(vec![]) } else { None },
         }
     }
 
+    /// Returns the approximate size in bytes of all the writes buffered up in this writer,
+    /// used by callers to decide when to break up large writes
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
     /// Writes the tuple into the table
     pub fn write_tuple(
         &mut self,
@@ -314,6 +573,8 @@ impl Writer {
         mut freq: i64,
     ) -> Result<(), StorageError> {
         assert_eq!(tuple.len(), table.length);
+        self.write_log(table, tuple, timestamp, freq)?;
+        self.tables_written.insert(table.id);
         // create rocksdb key
         write_index_header_key(table, tuple, &mut self.key_buf);
 
@@ -334,6 +595,12 @@ impl Writer {
 
                 self.write_batch
                     .put(&self.key_buf, &value_bytes.as_ref()[8..]);
+                self.batch_size += self.key_buf.len() + value_bytes.as_ref().len() - 8;
+                record_put(
+                    &mut self.replicated_writes,
+                    &self.key_buf,
+                    &value_bytes.as_ref()[8..],
+                );
 
                 // Restore the key
                 self.key_buf.truncate(self.key_buf.len() - 8);
@@ -343,6 +610,80 @@ impl Writer {
         write_index_header_value(table, tuple, timestamp, freq, &mut self.value_buf);
 
         self.write_batch.put(&self.key_buf, &self.value_buf);
+        self.batch_size += self.key_buf.len() + self.value_buf.len();
+        record_put(&mut self.replicated_writes, &self.key_buf, &self.value_buf);
+        Ok(())
+    }
+
+    /// Writes the tuple without reading back any existing version of it first. Much cheaper than
+    /// write_tuple but the caller must know the tuple isn't already in the table (or earlier in
+    /// this batch) as it'll be overwritten rather than have its freq added to.
+    pub fn blind_write_tuple(
+        &mut self,
+        table: &Table,
+        tuple: &[Datum],
+        timestamp: LogicalTimestamp,
+        freq: i64,
+    ) {
+        assert_eq!(tuple.len(), table.length);
+        self.write_index_header(table, tuple, timestamp, freq);
+        // Same goes for the log, there can't be an earlier change to the tuple to add to
+        write_log_key(table, tuple, timestamp, &mut self.key_buf);
+        self.put_log(freq);
+    }
+
+    /// Removes all copies of the tuple from the table, ie writes out the negation of the tuple's
+    /// current freq. Returns the number of rows removed.
+    pub fn delete_tuple(
+        &mut self,
+        table: &Table,
+        tuple: &[Datum],
+        timestamp: LogicalTimestamp,
+    ) -> Result<i64, StorageError> {
+        assert_eq!(tuple.len(), table.length);
+        write_index_header_key(table, tuple, &mut self.key_buf);
+
+        let mut freq = 0_i64;
+        if let Some(value_bytes) = self.write_batch.get(&table.db, &self.key_buf)? {
+            freq.read_sortable_bytes(SortOrder::Asc, &value_bytes.as_ref()[8..]);
+        }
+        if freq != 0 {
+            self.write_tuple(table, tuple, timestamp, -freq)?;
+        }
+        Ok(freq)
+    }
+
+    /// Returns the current freq of the tuple with the given pk, reading through any writes already
+    /// made in this batch. A freq of 0 means the tuple isn't (or is no longer) in the table.
+    pub fn freq(&mut self, table: &Table, pk: &[Datum]) -> Result<i64, StorageError> {
+        write_index_header_key(table, pk, &mut self.key_buf);
+        let mut freq = 0_i64;
+        if let Some(value_bytes) = self.write_batch.get(&table.db, &self.key_buf)? {
+            freq.read_sortable_bytes(SortOrder::Asc, &value_bytes.as_ref()[8..]);
+        }
+        Ok(freq)
+    }
+
+    /// Checks that the tuple hasn't been written to by anyone else since it was read as of
+    /// read_timestamp, ie that it wasn't written at or after read_timestamp. Used before writing
+    /// rows based on what was read, as it may be stale. own_writes are the timestamps of batches
+    /// already committed by the same statement which are ignored.
+    pub fn check_conflict(
+        &mut self,
+        table: &Table,
+        tuple: &[Datum],
+        read_timestamp: LogicalTimestamp,
+        own_writes: &[LogicalTimestamp],
+    ) -> Result<(), StorageError> {
+        write_index_header_key(table, tuple, &mut self.key_buf);
+        // Only committed writes count, anything in our own batch is fair game
+        if let Some(value_bytes) = table.db.get(&self.key_buf)? {
+            let last_timestamp =
+                LogicalTimestamp::new(u64::from_le_bytes(value_bytes[..8].try_into().unwrap()));
+            if last_timestamp >= read_timestamp && !own_writes.contains(&last_timestamp) {
+                return Err(StorageError::WriteConflict(table.id));
+            }
+        }
         Ok(())
     }
 
@@ -358,8 +699,41 @@ impl Writer {
     /// Only the pk parts of the tuple are needed but passing in more wont hurt but will delete
     /// according to that pk...
     pub fn system_delete_tuple(&mut self, table: &Table, pk: &[Datum]) {
+        self.tables_written.insert(table.id);
         write_index_header_key(table, pk, &mut self.key_buf);
         self.write_batch.delete(&self.key_buf);
+        self.batch_size += self.key_buf.len();
+        if let Some(writes) = &mut self.replicated_writes {
+            writes.push(ReplicatedWrite::Delete(self.key_buf.clone()));
+        }
+    }
+
+    /// Records the change to the tuple in the log section of the table, adding to any other
+    /// change made to the tuple at the same timestamp.
+    fn write_log(
+        &mut self,
+        table: &Table,
+        tuple: &[Datum],
+        timestamp: LogicalTimestamp,
+        mut freq: i64,
+    ) -> Result<(), StorageError> {
+        write_log_key(table, tuple, timestamp, &mut self.key_buf);
+        if let Some(value_bytes) = self.write_batch.get(&table.db, &self.key_buf)? {
+            let mut last_freq = 0_i64;
+            last_freq.read_sortable_bytes(SortOrder::Asc, value_bytes.as_ref());
+            freq += last_freq;
+        }
+        self.put_log(freq);
+        Ok(())
+    }
+
+    /// Writes the freq for the log key in key_buf
+    fn put_log(&mut self, freq: i64) {
+        self.value_buf.clear();
+        freq.write_sortable_bytes(SortOrder::Asc, &mut self.value_buf);
+        self.write_batch.put(&self.key_buf, &self.value_buf);
+        self.batch_size += self.key_buf.len() + self.value_buf.len();
+        record_put(&mut self.replicated_writes, &self.key_buf, &self.value_buf);
     }
 
     fn write_index_header(
@@ -369,10 +743,107 @@ impl Writer {
         timestamp: LogicalTimestamp,
         freq: i64,
     ) {
+        self.tables_written.insert(table.id);
         write_index_header_key(table, tuple, &mut self.key_buf);
         write_index_header_value(table, tuple, timestamp, freq, &mut self.value_buf);
 
         self.write_batch.put(&self.key_buf, &self.value_buf);
+        self.batch_size += self.key_buf.len() + self.value_buf.len();
+        record_put(&mut self.replicated_writes, &self.key_buf, &self.value_buf);
+    }
+}
+
+/// Writes buffered up across several statements, only written out (atomically) on commit.
+/// Every write is recorded in order so the transaction can be rolled back to an earlier point
+/// (ie a savepoint) by rebuilding the write batch from just the writes made before it.
+#[derive(Default)]
+pub struct Transaction {
+    writer: Option<Writer>,
+    // The table the first write went to, they all share the same db so any will do to commit
+    // through
+    table: Option<Table>,
+}
+
+impl Transaction {
+    /// Adds the writes made by batch to the transaction.
+    pub fn write<F, E: From<StorageError>>(&mut self, table: &Table, batch: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Writer) -> Result<(), E>,
+    {
+        if self.table.is_none() {
+            self.table = Some(table.clone());
+        }
+        batch(self.writer.get_or_insert_with(|| Writer::new(true)))
+    }
+
+    /// The number of writes made so far, pass it to rollback_to to undo any made after.
+    pub fn savepoint(&self) -> usize {
+        self.writer
+            .as_ref()
+            .map_or(0, |writer| writer.replicated_writes.as_ref().unwrap().len())
+    }
+
+    /// Discards the writes made since the savepoint was taken.
+    pub fn rollback_to(&mut self, savepoint: usize) {
+        if let Some(writer) = &mut self.writer {
+            let mut writes = writer.replicated_writes.take().unwrap();
+            writes.truncate(savepoint);
+            writer.write_batch = WriteBatchWithIndex::default();
+            writer.batch_size = 0;
+            for write in &writes {
+                match write {
+                    ReplicatedWrite::Put(key, value) => {
+                        writer.write_batch.put(key, value);
+                        writer.batch_size += key.len() + value.len();
+                    }
+                    ReplicatedWrite::Delete(key) => {
+                        writer.write_batch.delete(key);
+                        writer.batch_size += key.len();
+                    }
+                    // Writers don't do range deletes
+                    ReplicatedWrite::DeleteRange(..) => unreachable!(),
+                }
+            }
+            writer.replicated_writes = Some(writes);
+        }
+    }
+
+    /// Writes out all the writes made in the transaction.
+    pub fn commit(self) -> Result<(), StorageError> {
+        match (self.table, self.writer) {
+            (Some(table), Some(mut writer)) => {
+                if !table.replication.enabled() {
+                    writer.replicated_writes = None;
+                }
+                table.commit(writer, true)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn record_put(replicated_writes: &mut Option<Vec<ReplicatedWrite>>, key: &[u8], value: &[u8]) {
+    if let Some(writes) = replicated_writes {
+        writes.push(ReplicatedWrite::Put(key.to_vec(), value.to_vec()));
+    }
+}
+
+/// Lower level writer for the operations not supported by Writer, ie range deletes.
+pub struct RawWriter {
+    write_batch: WriteBatch,
+    replicated_writes: Option<Vec<ReplicatedWrite>>,
+}
+
+impl RawWriter {
+    /// Deletes all the keys from..to (exclusive)
+    pub fn delete_range<K: AsRef<[u8]>>(&mut self, from: K, to: K) {
+        if let Some(writes) = &mut self.replicated_writes {
+            writes.push(ReplicatedWrite::DeleteRange(
+                from.as_ref().to_vec(),
+                to.as_ref().to_vec(),
+            ));
+        }
+        self.write_batch.delete_range(from, to);
     }
 }
 
@@ -403,6 +874,23 @@ fn write_range_key(table: &Table, tuple: &[Datum], key_buf: &mut Vec<u8>, end: b
     }
 }
 
+fn write_log_key(
+    table: &Table,
+    tuple: &[Datum],
+    timestamp: LogicalTimestamp,
+    key_buf: &mut Vec<u8>,
+) {
+    // Log:
+    // key = <prefix+1 as u32 be>:<timestamp as u64 be>:<tuple as sorted>
+    key_buf.clear();
+    key_buf.extend_from_slice(&(table.id + 1).to_be_bytes());
+    key_buf.extend_from_slice(&timestamp.ms.to_be_bytes());
+    (tuple.len() as u64).write_sortable_bytes(SortOrder::Asc, key_buf);
+    for datum in tuple {
+        datum.as_sortable_bytes(SortOrder::Asc, key_buf);
+    }
+}
+
 fn write_index_header_value(
     table: &Table,
     tuple: &[Datum],
@@ -448,6 +936,206 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_writer_batch_size() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 2, vec![SortOrder::Asc]);
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            assert_eq!(writer.batch_size(), 0);
+            writer.write_tuple(
+                &table,
+                &[Datum::from(1), Datum::from("abc")],
+                LogicalTimestamp::new(1),
+                1,
+            )?;
+            let size = writer.batch_size();
+            assert!(size > 0);
+            writer.system_write_tuple(&table, &[Datum::from(2), Datum::from("def")], 1);
+            assert!(writer.batch_size() > size);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_writer_check_conflict() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 1, vec![SortOrder::Asc]);
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table, &[Datum::from(1)], LogicalTimestamp::new(10), 1)
+        })?;
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            assert_eq!(
+                writer.check_conflict(&table, &[Datum::from(1)], LogicalTimestamp::new(5), &[]),
+                Err(StorageError::WriteConflict(1234))
+            );
+            // Reading as of 10 doesn't see the write at 10
+            assert_eq!(
+                writer.check_conflict(&table, &[Datum::from(1)], LogicalTimestamp::new(10), &[]),
+                Err(StorageError::WriteConflict(1234))
+            );
+            writer.check_conflict(&table, &[Datum::from(1)], LogicalTimestamp::new(11), &[])?;
+            writer.check_conflict(&table, &[Datum::from(2)], LogicalTimestamp::new(5), &[])?;
+
+            // Our own writes don't count, whether from an earlier batch or this one
+            writer.check_conflict(
+                &table,
+                &[Datum::from(1)],
+                LogicalTimestamp::new(5),
+                &[LogicalTimestamp::new(10)],
+            )?;
+            writer.write_tuple(&table, &[Datum::from(2)], LogicalTimestamp::new(20), 1)?;
+            writer.check_conflict(&table, &[Datum::from(2)], LogicalTimestamp::new(5), &[])
+        })
+    }
+
+    #[test]
+    fn test_writer_freq() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 1, vec![SortOrder::Asc]);
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table, &[Datum::from(1)], LogicalTimestamp::new(10), 2)
+        })?;
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            assert_eq!(writer.freq(&table, &[Datum::from(1)])?, 2);
+            assert_eq!(writer.freq(&table, &[Datum::from(2)])?, 0);
+
+            // Reads see our own writes
+            writer.write_tuple(&table, &[Datum::from(1)], LogicalTimestamp::new(20), -2)?;
+            writer.write_tuple(&table, &[Datum::from(2)], LogicalTimestamp::new(20), 1)?;
+            assert_eq!(writer.freq(&table, &[Datum::from(1)])?, 0);
+            assert_eq!(writer.freq(&table, &[Datum::from(2)])?, 1);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_table_version() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table1 = storage.table(1234, 1, vec![SortOrder::Asc]);
+        let table2 = storage.table(1236, 1, vec![SortOrder::Asc]);
+        assert_eq!(table1.version(), 0);
+
+        table2.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table1, &[Datum::from(1)], LogicalTimestamp::new(1), 1)
+        })?;
+        assert_eq!(table1.version(), 1);
+        assert_eq!(table2.version(), 0);
+
+        // Failed batches don't count
+        let result = table1.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table1, &[Datum::from(2)], LogicalTimestamp::new(2), 1)?;
+            Err(StorageError::RocksDbError("boom".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(table1.version(), 1);
+
+        // Versions are shared between handles for the same table
+        assert_eq!(storage.table(1234, 1, vec![SortOrder::Asc]).version(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_writer_delete_tuple() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 2, vec![SortOrder::Asc, SortOrder::Asc]);
+        let tuple = vec![Datum::from(1), Datum::from("abc")];
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table, &tuple, LogicalTimestamp::new(10), 3)
+        })?;
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            assert_eq!(
+                writer.delete_tuple(&table, &tuple, LogicalTimestamp::new(20))?,
+                3
+            );
+            // Already deleted within the same batch
+            assert_eq!(
+                writer.delete_tuple(&table, &tuple, LogicalTimestamp::new(20))?,
+                0
+            );
+            Ok(())
+        })?;
+
+        let mut iter = table.full_scan(LogicalTimestamp::new(15));
+        assert_eq!(iter.next()?, Some((tuple.as_ref(), 3)));
+        let mut iter = table.full_scan(LogicalTimestamp::new(25));
+        assert_eq!(iter.next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_scan() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 2, vec![SortOrder::Asc]);
+        let tuple1 = vec![Datum::from(1), Datum::from("abc")];
+        let tuple2 = vec![Datum::from(2), Datum::from("def")];
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table, &tuple1, LogicalTimestamp::new(10), 2)?;
+            writer.blind_write_tuple(&table, &tuple2, LogicalTimestamp::new(10), 1);
+            Ok(())
+        })?;
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.delete_tuple(&table, &tuple1, LogicalTimestamp::new(20))?;
+            // Changes at the same timestamp are added together
+            writer.write_tuple(&table, &tuple2, LogicalTimestamp::new(20), 1)?;
+            writer.write_tuple(&table, &tuple2, LogicalTimestamp::new(20), 2)?;
+            Ok(())
+        })?;
+
+        let mut iter = table.log_scan(LogicalTimestamp::new(0));
+        assert_eq!(iter.next()?, Some((tuple1.as_ref(), 2)));
+        assert_eq!(iter.timestamp(), LogicalTimestamp::new(10));
+        assert_eq!(iter.next()?, Some((tuple2.as_ref(), 1)));
+        assert_eq!(iter.next()?, Some((tuple1.as_ref(), -2)));
+        assert_eq!(iter.timestamp(), LogicalTimestamp::new(20));
+        assert_eq!(iter.next()?, Some((tuple2.as_ref(), 3)));
+        assert_eq!(iter.next()?, None);
+
+        let mut iter = table.log_scan(LogicalTimestamp::new(11));
+        assert_eq!(iter.next()?, Some((tuple1.as_ref(), -2)));
+        assert_eq!(iter.next()?, Some((tuple2.as_ref(), 3)));
+        assert_eq!(iter.next()?, None);
+
+        // The log doesn't leak into scans of the table
+        let mut iter = table.full_scan(LogicalTimestamp::MAX);
+        assert_eq!(iter.next()?, Some((tuple2.as_ref(), 4)));
+        assert_eq!(iter.next()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_blind_write_tuple() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 2, vec![SortOrder::Asc]);
+        let tuple = vec![Datum::from(123), Datum::from("abc")];
+
+        table.atomic_write_unsynced::<_, StorageError>(|writer| {
+            writer.blind_write_tuple(&table, &tuple, LogicalTimestamp::new(10), 2);
+            Ok(())
+        })?;
+        table.sync_wal()?;
+
+        let mut iter = table.full_scan(LogicalTimestamp::new(20));
+        assert_eq!(iter.next()?, Some((tuple.as_ref(), 2)));
+        assert_eq!(iter.next()?, None);
+
+        // Regular writes still see the blind written tuple
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(&table, &tuple, LogicalTimestamp::new(30), 1)
+        })?;
+        let mut iter = table.full_scan(LogicalTimestamp::new(40));
+        assert_eq!(iter.next()?, Some((tuple.as_ref(), 3)));
+        assert_eq!(iter.next()?, None);
+        Ok(())
+    }
+
     #[test]
     fn test_system_write_tuple() -> Result<(), StorageError> {
         let storage = Storage::new_in_mem()?;
@@ -578,6 +1266,59 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_projected_range_scan() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 4, vec![SortOrder::Asc, SortOrder::Desc]);
+        assert_eq!(table.column_count(), 4);
+
+        table.atomic_write::<_, StorageError>(|writer| {
+            writer.write_tuple(
+                &table,
+                &[
+                    Datum::from(1),
+                    Datum::from("a"),
+                    Datum::from("some long text value"),
+                    Datum::from(10),
+                ],
+                LogicalTimestamp::new(10),
+                1,
+            )?;
+            writer.write_tuple(
+                &table,
+                &[
+                    Datum::from(2),
+                    Datum::from("b"),
+                    Datum::Null,
+                    Datum::from(20),
+                ],
+                LogicalTimestamp::new(10),
+                1,
+            )?;
+            Ok(())
+        })?;
+
+        let mask = [false, true, false, true];
+        let mut iter = table.projected_range_scan(None, None, LogicalTimestamp::MAX, Some(&mask));
+        assert_eq!(
+            iter.next()?,
+            Some((
+                [Datum::Null, Datum::from("a"), Datum::Null, Datum::from(10)].as_ref(),
+                1
+            ))
+        );
+        assert_eq!(
+            iter.next()?,
+            Some((
+                [Datum::Null, Datum::from("b"), Datum::Null, Datum::from(20)].as_ref(),
+                1
+            ))
+        );
+        assert_eq!(iter.next()?, None);
+
+        Ok(())
+    }
+
     #[test]
     fn test_range_scan_full_key_reverse() -> Result<(), StorageError> {
         let storage = Storage::new_in_mem()?;
@@ -615,4 +1356,39 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_transaction_rollback_to() -> Result<(), StorageError> {
+        let storage = Storage::new_in_mem()?;
+        let table = storage.table(1234, 1, vec![SortOrder::Asc]);
+        let write = |transaction: &mut Transaction, value: i32, freq: i64| {
+            transaction.write(&table, |writer| {
+                writer.write_tuple(
+                    &table,
+                    &[Datum::from(value)],
+                    LogicalTimestamp::new(10),
+                    freq,
+                )
+            })
+        };
+
+        let mut transaction = Transaction::default();
+        write(&mut transaction, 1, 1)?;
+        let savepoint = transaction.savepoint();
+        write(&mut transaction, 1, 1)?;
+        write(&mut transaction, 2, 1)?;
+        // Nothing's visible until commit
+        assert_eq!(table.full_scan(LogicalTimestamp::MAX).next()?, None);
+
+        transaction.rollback_to(savepoint);
+        // The write batch is rebuilt so later writes still add to the earlier ones
+        write(&mut transaction, 1, 2)?;
+        transaction.commit()?;
+
+        let mut iter = table.full_scan(LogicalTimestamp::MAX);
+        assert_eq!(iter.next()?, Some(([Datum::from(1)].as_ref(), 3)));
+        assert_eq!(iter.next()?, None);
+        assert_eq!(table.version(), 1);
+        Ok(())
+    }
 }
@@ -0,0 +1,84 @@
+use crate::runner::*;
+
+#[test]
+fn test_arithmetic() {
+    query(
+        r#"SELECT 1 + 2, 5 - 7, 3 * 4, 7 / 2, 7 / 0, 1.5 / 0, 1e0 / 0e0"#,
+        "
+        |3|-2|12|3|NULL|NULL|NULL|
+        ",
+    );
+}
+
+#[test]
+fn test_arithmetic_overflow() {
+    with_connection(|connection| {
+        let err = connection
+            .execute_statement(r#"SELECT 2147483647 + 1"#)
+            .and_then(|(_, mut executor)| Ok(executor.next()?.is_none()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "INTEGER value is out of range in '(2147483647 + 1)'"
+        );
+
+        connection.query(r#"CREATE TABLE t (a BIGINT)"#, "");
+        connection.query(r#"INSERT INTO t VALUES (9223372036854775807), (1)"#, "");
+
+        assert!(connection
+            .execute_statement(r#"SELECT a * 2 FROM t"#)
+            .and_then(|(_, mut executor)| {
+                while executor.next()?.is_some() {}
+                Ok(())
+            })
+            .is_err());
+
+        // Overflows are caught before anything gets written
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t SELECT a + 1 FROM t"#)
+            .and_then(|(_, mut executor)| Ok(executor.next()?.is_none()))
+            .is_err());
+        connection.query(r#"SELECT count(*) FROM t"#, "|2|");
+
+        // The error doesn't stick around for the next statement
+        connection.query(r#"SELECT a - 1 FROM t WHERE a = 1"#, "|0|");
+
+        let err = connection
+            .execute_statement(r#"SELECT 1e308 * 10e0"#)
+            .and_then(|(_, mut executor)| Ok(executor.next()?.is_none()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "DOUBLE value is out of range in '(1e308 * 1e1)'"
+        );
+    });
+}
+
+#[test]
+fn test_greatest_least() {
+    query(
+        r#"SELECT greatest(1, 3, 2), least(1, 3, 2), greatest(1, 2.5), least(2, 1e0), greatest('b', 'a'), greatest(1, NULL)"#,
+        "
+        |3|1|2.5|1|b|NULL|
+        ",
+    );
+
+    with_connection(|connection| {
+        connection.query_types(
+            r#"SELECT greatest(1, 2), greatest(1, cast(2 as bigint)), greatest(1, 2.5), least(1, 1e0)"#,
+            "
+            |INTEGER|BIGINT|DECIMAL(11,1)|DOUBLE|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_nullif_ifnull() {
+    query(
+        r#"SELECT nullif(1, 1), nullif(1, 2), nullif('a', NULL), ifnull(NULL, 2), ifnull(1, 2), ifnull(NULL, NULL)"#,
+        "
+        |NULL|1|a|2|1|NULL|
+        ",
+    );
+}
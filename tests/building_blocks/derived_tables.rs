@@ -0,0 +1,66 @@
+use crate::runner::{with_connection, TestQuery};
+
+#[test]
+fn test_derived_table_filters_and_aggregates() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1, "x"), (2, "x"), (3, "y")"#, "");
+
+        connection.query(
+            r#"SELECT d.b, d.total FROM (SELECT b, sum(a) AS total FROM t1 GROUP BY b) AS d
+               WHERE d.total > 2 ORDER BY b"#,
+            "
+                |x|3|
+                |y|3|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT count(*) FROM (SELECT a FROM t1 WHERE a > 1) d"#,
+            "
+                |2|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_derived_table_joins() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1, "x"), (2, "x"), (3, "y")"#, "");
+
+        connection.query_unordered(
+            r#"SELECT t1.a, d.cnt FROM t1
+               JOIN (SELECT b, count(*) AS cnt FROM t1 GROUP BY b) AS d ON t1.b = d.b"#,
+            "
+                |1|2|
+                |2|2|
+                |3|1|
+            ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT l.a, r.a FROM (SELECT a FROM t1 WHERE a < 2) l,
+               (SELECT a FROM t1 WHERE a > 2) r"#,
+            "
+                |1|3|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_nested_derived_tables() {
+    with_connection(|connection| {
+        connection.query_unordered(
+            r#"SELECT outer_t.c + 1 FROM (
+                 SELECT inner_t.c * 2 AS c FROM (SELECT 1 AS c UNION ALL SELECT 2) AS inner_t
+               ) AS outer_t"#,
+            "
+                |3|
+                |5|
+            ",
+        );
+    });
+}
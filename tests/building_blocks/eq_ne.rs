@@ -83,9 +83,9 @@ fn select_not_equal_decimals() {
 #[test]
 fn select_equal_text() {
     query(
-        r#"SELECT "abc" = "abc", "abc"="ABC""#,
+        r#"SELECT "abc" = "abc", "abc"="ABC", "abc" = "abd""#,
         "
-        |TRUE|FALSE|
+        |TRUE|TRUE|FALSE|
         ",
     );
 }
@@ -93,9 +93,29 @@ fn select_equal_text() {
 #[test]
 fn select_not_equal_text() {
     query(
-        r#"SELECT "abc" != "abc", "abc"!="ABC""#,
+        r#"SELECT "abc" != "abc", "abc"!="ABC", "abc" != "abd""#,
         "
-        |FALSE|TRUE|
+        |FALSE|FALSE|TRUE|
+        ",
+    );
+}
+
+#[test]
+fn select_null_safe_equals() {
+    query(
+        r#"SELECT NULL <=> NULL, NULL <=> 1, 1 <=> NULL, 1 <=> 1, 1 <=> 2, "1" <=> 1"#,
+        "
+        |TRUE|FALSE|FALSE|TRUE|FALSE|TRUE|
+        ",
+    );
+}
+
+#[test]
+fn select_is_distinct_from() {
+    query(
+        r#"SELECT NULL IS DISTINCT FROM NULL, NULL IS NOT DISTINCT FROM NULL, 1 IS DISTINCT FROM NULL"#,
+        "
+        |FALSE|TRUE|TRUE|
         ",
     );
 }
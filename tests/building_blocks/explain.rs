@@ -0,0 +1,81 @@
+use crate::runner::*;
+
+#[test]
+fn explain_dot() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t(i INTEGER)"#, "");
+        connection.query(
+            r#"INSERT INTO t VALUES (0), (1), (2), (3), (4), (5), (6), (7), (8), (9)"#,
+            "",
+        );
+
+        // Without stats there's no row estimates
+        connection.query(
+            r#"EXPLAIN FORMAT=DOT SELECT i FROM t WHERE i < 5"#,
+            r#"
+            |digraph plan {|
+            |  node [shape=box, fontname="monospace"];|
+            |  n0 [label="PROJECT\li: <OFFSET 0>\l"];|
+            |  n1 [label="FILTER\lpredicate: `<`(<OFFSET 0>, 5)\l"];|
+            |  n2 [label="TABLE(t)\laccess: RANGE_SCAN\lrange: to (5)\ltimestamp: LATEST\l"];|
+            |  n2 -> n1 [label="source"];|
+            |  n1 -> n0 [label="source"];|
+            |}|
+            "#,
+        );
+
+        connection.query(r#"ANALYZE TABLE t"#, "");
+        connection.query(
+            r#"EXPLAIN FORMAT = DOT SELECT i FROM t WHERE i < 5"#,
+            r#"
+            |digraph plan {|
+            |  node [shape=box, fontname="monospace"];|
+            |  n0 [label="PROJECT\li: <OFFSET 0>\lrows: ~5\l"];|
+            |  n1 [label="FILTER\lselectivity: 0.5000\lpredicate: `<`(<OFFSET 0>, 5)\lrows: ~5\l"];|
+            |  n2 [label="TABLE(t)\laccess: RANGE_SCAN\lrange: to (5)\ltimestamp: LATEST\lrows: ~10\l"];|
+            |  n2 -> n1 [label="source"];|
+            |  n1 -> n0 [label="source"];|
+            |}|
+            "#,
+        );
+    });
+}
+
+#[test]
+fn explain_range_scan() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t (a INT, b TEXT, c INT, PRIMARY KEY (a DESC, b))"#,
+            "",
+        );
+
+        // a is descending so the range runs from the upper bound down to the lower one
+        connection.query(
+            r#"EXPLAIN FORMAT=DOT SELECT b FROM t WHERE a > 1 AND a >= 5 AND a < 20 AND a <= 10"#,
+            r#"
+            |digraph plan {|
+            |  node [shape=box, fontname="monospace"];|
+            |  n0 [label="PROJECT\lb: <OFFSET 1>\l"];|
+            |  n1 [label="FILTER\lpredicate: and(and(and(`>`(<OFFSET 0>, 1), `>=`(<OFFSET 0>, 5)), `<`(<OFFSET 0>, 20)), `<=`(<OFFSET 0>, 10))\l"];|
+            |  n2 [label="TABLE(t)\laccess: RANGE_SCAN\lrange: from (10) to (5)\ldecoded_columns: a, b\ltimestamp: LATEST\l"];|
+            |  n2 -> n1 [label="source"];|
+            |  n1 -> n0 [label="source"];|
+            |}|
+            "#,
+        );
+
+        connection.query(
+            r#"EXPLAIN FORMAT=DOT SELECT c FROM t WHERE a = 3"#,
+            r#"
+            |digraph plan {|
+            |  node [shape=box, fontname="monospace"];|
+            |  n0 [label="PROJECT\lc: <OFFSET 2>\l"];|
+            |  n1 [label="FILTER\lpredicate: `=`(<OFFSET 0>, 3)\l"];|
+            |  n2 [label="TABLE(t)\laccess: RANGE_SCAN\lrange: from (3) to (3)\ldecoded_columns: a, c\ltimestamp: LATEST\l"];|
+            |  n2 -> n1 [label="source"];|
+            |  n1 -> n0 [label="source"];|
+            |}|
+            "#,
+        );
+    });
+}
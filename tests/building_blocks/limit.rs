@@ -15,13 +15,13 @@ fn select_limit_no_offset() {
     query(
         r#"EXPLAIN SELECT foo FROM (SELECT 1 as foo) LIMIT 2"#,
         "
-        |LIMIT||||
-        | |limit: 2||||
-        | |offset: 0||||
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  foo|0|INTEGER|1|
         | |source:||||
-        | |  PROJECT||||
-        | |   |output_exprs:||||
-        | |   |  foo|0|INTEGER|1|
+        | |  LIMIT||||
+        | |   |limit: 2||||
+        | |   |offset: 0||||
         | |   |source:||||
         | |   |  SINGLE||||
         ",
@@ -53,15 +53,60 @@ fn select_limit_with_offset() {
     query(
         r#"EXPLAIN SELECT foo FROM (SELECT 1 as foo) LIMIT 1, 2"#,
         "
-        |LIMIT||||
-        | |limit: 2||||
-        | |offset: 1||||
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  foo|0|INTEGER|1|
         | |source:||||
-        | |  PROJECT||||
-        | |   |output_exprs:||||
-        | |   |  foo|0|INTEGER|1|
+        | |  LIMIT||||
+        | |   |limit: 2||||
+        | |   |offset: 1||||
         | |   |source:||||
         | |   |  SINGLE||||
         ",
     );
 }
+
+#[test]
+fn select_limit_union() {
+    query(
+        r#"SELECT foo FROM (SELECT 1 as foo UNION ALL SELECT 2) LIMIT 1, 1"#,
+        "
+        |2|
+        ",
+    );
+
+    query(
+        r#"EXPLAIN SELECT foo FROM (SELECT 1 as foo UNION ALL SELECT 2) LIMIT 1, 1"#,
+        "
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  foo|0|INTEGER|<OFFSET 0>|
+        | |source:||||
+        | |  LIMIT||||
+        | |   |limit: 1||||
+        | |   |offset: 1||||
+        | |   |source:||||
+        | |   |  UNION_ALL||||
+        | |   |   |source_0:||||
+        | |   |   |  PROJECT||||
+        | |   |   |   |output_exprs:||||
+        | |   |   |   |  foo|0|INTEGER|1|
+        | |   |   |   |source:||||
+        | |   |   |   |  LIMIT||||
+        | |   |   |   |   |limit: 2||||
+        | |   |   |   |   |offset: 0||||
+        | |   |   |   |   |source:||||
+        | |   |   |   |   |  SINGLE||||
+        | |   |   |source_1:||||
+        | |   |   |  PROJECT||||
+        | |   |   |   |output_exprs:||||
+        | |   |   |   |  _col1|0|INTEGER|2|
+        | |   |   |   |source:||||
+        | |   |   |   |  LIMIT||||
+        | |   |   |   |   |limit: 2||||
+        | |   |   |   |   |offset: 0||||
+        | |   |   |   |   |source:||||
+        | |   |   |   |   |  SINGLE||||
+        ",
+    );
+}
@@ -1,11 +1,16 @@
 mod aliases;
+mod arithmetic;
 mod between;
 mod boolean_logic;
+mod derived_tables;
 mod eq_ne;
+mod explain;
 mod limit;
 mod literals;
 mod order_by;
 mod predicates;
+mod row_values;
 mod star;
 mod tables;
+mod time_travel;
 mod unions;
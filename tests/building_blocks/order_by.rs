@@ -41,3 +41,71 @@ fn select_order_by() {
         );
     });
 }
+
+#[test]
+fn select_order_by_spilled() {
+    with_connection(|connection| {
+        // A tiny sort buffer so each row gets spilled to disk as its own run
+        connection.query(r#"SET sort_buffer_size = 1"#, "");
+        connection.query(
+            r#"SELECT foo FROM (
+                    SELECT 1 as foo UNION ALL SELECT 4 UNION ALL SELECT 3 UNION ALL SELECT 2
+                    ) ORDER BY foo desc"#,
+            "
+            |4|
+            |3|
+            |2|
+            |1|
+        ",
+        );
+    });
+}
+
+#[test]
+fn select_order_by_nulls() {
+    with_connection(|connection| {
+        // By default nulls are the smallest value
+        connection.query(
+            r#"SELECT foo FROM (
+                    SELECT 1 as foo UNION ALL SELECT CAST(NULL AS INT) UNION ALL SELECT 2
+                    ) ORDER BY foo"#,
+            "
+            |NULL|
+            |1|
+            |2|
+        ",
+        );
+
+        connection.query(
+            r#"SELECT foo FROM (
+                    SELECT 1 as foo UNION ALL SELECT CAST(NULL AS INT) UNION ALL SELECT 2
+                    ) ORDER BY foo DESC"#,
+            "
+            |2|
+            |1|
+            |NULL|
+        ",
+        );
+
+        connection.query(
+            r#"SELECT foo FROM (
+                    SELECT 1 as foo UNION ALL SELECT CAST(NULL AS INT) UNION ALL SELECT 2
+                    ) ORDER BY foo NULLS LAST"#,
+            "
+            |1|
+            |2|
+            |NULL|
+        ",
+        );
+
+        connection.query(
+            r#"SELECT foo FROM (
+                    SELECT 1 as foo UNION ALL SELECT CAST(NULL AS INT) UNION ALL SELECT 2
+                    ) ORDER BY foo DESC NULLS FIRST LIMIT 2"#,
+            "
+            |NULL|
+            |2|
+        ",
+        );
+    });
+}
@@ -0,0 +1,85 @@
+use crate::runner::{with_connection, TestQuery};
+
+#[test]
+fn select_in() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT 1 IN (1, 2), 3 IN (1, 2), 3 NOT IN (1, 2), 3 IN (1, NULL)"#,
+            "
+        |TRUE|FALSE|TRUE|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"SELECT (1, "a") IN ((1, "b"), (1, "a")), (1, "a") IN ((2, "a"))"#,
+            "
+        |TRUE|FALSE|
+        ",
+        );
+    });
+}
+
+#[test]
+fn select_row_comparisons() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT (1, 2) = (1, 2), (1, 2) != (1, 2), (1, 2) < (1, 3), (1, 2) < (0, 3), (2, 1) >= (1, 9)"#,
+            "
+        |TRUE|FALSE|TRUE|FALSE|TRUE|
+        ",
+        );
+
+        assert!(connection
+            .execute_statement(r#"SELECT (1, 2) = 1"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"SELECT (1, 2) IN ((1, 2, 3))"#)
+            .is_err());
+    });
+}
+
+#[test]
+fn row_values_against_table() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t (a INT, b TEXT, c INT, PRIMARY KEY (a, b DESC))"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO t VALUES (1, "x", 1), (1, "y", 2), (2, "x", 3), (2, "y", 4), (3, "x", 5)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"SELECT c FROM t WHERE (a, b) IN ((1, "y"), (3, "x"), (1, "y"), (4, "z"))"#,
+            "
+        |2|
+        |5|
+        ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT c FROM t WHERE a IN (2, 3)"#,
+            "
+        |3|
+        |4|
+        |5|
+        ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT c FROM t WHERE (a, b) > (1, "x") AND (a, b) <= (2, "x")"#,
+            "
+        |2|
+        |3|
+        ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT c FROM t WHERE a = 2 AND b < "y""#,
+            "
+        |3|
+        ",
+        );
+    });
+}
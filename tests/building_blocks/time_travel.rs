@@ -0,0 +1,73 @@
+use crate::runner::{with_connection, TestQuery};
+use data::chrono::NaiveDateTime;
+use data::LogicalTimestamp;
+use std::thread::sleep;
+use std::time::Duration;
+
+/// The current time as a timestamp literal, sleeping either side so that writes made before and
+/// after land in different ms.
+fn checkpoint() -> String {
+    sleep(Duration::from_millis(5));
+    let ms = LogicalTimestamp::now().ms;
+    sleep(Duration::from_millis(5));
+    NaiveDateTime::from_timestamp((ms / 1000) as i64, (ms % 1000) as u32 * 1_000_000)
+        .format("%Y-%m-%d %H:%M:%S%.3f")
+        .to_string()
+}
+
+#[test]
+fn test_as_of_timestamp() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        let before_insert = checkpoint();
+        connection.query(r#"INSERT INTO t1 VALUES (1, "x"), (2, "y")"#, "");
+        let after_insert = checkpoint();
+        connection.query(r#"INSERT INTO t1 VALUES (3, "z")"#, "");
+        connection.query(r#"DELETE FROM t1 WHERE a = 1"#, "");
+
+        connection.query(
+            &format!(r#"SELECT a FROM t1 AS OF TIMESTAMP '{}'"#, before_insert),
+            "",
+        );
+        connection.query_unordered(
+            &format!(r#"SELECT a, b FROM t1 AS OF TIMESTAMP '{}'"#, after_insert),
+            "
+                |1|x|
+                |2|y|
+            ",
+        );
+        connection.query_unordered(
+            r#"SELECT a, b FROM t1"#,
+            "
+                |2|y|
+                |3|z|
+            ",
+        );
+        // Old and new versions of the same table can be compared
+        connection.query(
+            &format!(
+                r#"SELECT old.a FROM t1 AS OF TIMESTAMP '{}' old LEFT JOIN t1 ON old.a = t1.a
+                   WHERE t1.a IS NULL"#,
+                after_insert
+            ),
+            "
+                |1|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_as_of_timestamp_errors() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT)"#, "");
+        connection.query(r#"CREATE VIEW v1 AS SELECT a FROM t1"#, "");
+
+        assert!(connection
+            .execute_statement(r#"SELECT a FROM v1 AS OF TIMESTAMP '2020-01-01 00:00:00'"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"SELECT a FROM t1 AS OF TIMESTAMP 'now'"#)
+            .is_err());
+    });
+}
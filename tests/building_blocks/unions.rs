@@ -1,4 +1,4 @@
-use crate::runner::query;
+use crate::runner::{query, with_connection, TestQuery};
 
 #[test]
 fn select_union_toplevel() {
@@ -62,3 +62,61 @@ fn select_union_explain() {
         ",
     );
 }
+
+#[test]
+fn select_union_distinct() {
+    with_connection(|connection| {
+        connection.query_unordered(
+            r#"SELECT 1, "a" UNION SELECT 2, "b" UNION SELECT 1, "a""#,
+            "
+            |1|a|
+            |2|b|
+            ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT 1 UNION DISTINCT SELECT 1 UNION ALL SELECT 1"#,
+            "
+            |1|
+            |1|
+            ",
+        );
+    });
+}
+
+#[test]
+fn select_intersect_except() {
+    with_connection(|connection| {
+        connection.query_unordered(
+            r#"SELECT c1 FROM (SELECT 1 as c1 UNION ALL SELECT 2 UNION ALL SELECT 2 UNION ALL SELECT 3)
+            INTERSECT
+            SELECT c1 FROM (SELECT 2 as c1 UNION ALL SELECT 3 UNION ALL SELECT 4)"#,
+            "
+            |2|
+            |3|
+            ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT c1 FROM (SELECT 1 as c1 UNION ALL SELECT 1 UNION ALL SELECT 2 UNION ALL SELECT 3)
+            EXCEPT
+            SELECT 3"#,
+            "
+            |1|
+            |2|
+            ",
+        );
+
+        // Intersect binds tighter than union/except
+        connection.query_unordered(
+            r#"SELECT 1 EXCEPT SELECT 2 INTERSECT SELECT 2"#,
+            "
+            |1|
+            ",
+        );
+
+        assert!(connection
+            .execute_statement("SELECT 1 INTERSECT SELECT 1, 2")
+            .is_err());
+    });
+}
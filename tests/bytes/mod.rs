@@ -0,0 +1,65 @@
+use crate::runner::*;
+
+#[test]
+fn test_bytes_columns() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t (id BINARY(4), payload BLOB)"#, "");
+        connection.query(
+            r#"INSERT INTO t VALUES (x'DEADBEEF', 0x0102), (x'0A', NULL)"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO t SELECT x'00000001', CAST('abc' AS BLOB)"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT id, payload, hex(payload), length(payload) FROM t ORDER BY id"#,
+            "
+            |00000001|616263|616263|3|
+            |0a|NULL|NULL|NULL|
+            |deadbeef|0102|0102|2|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT cast(payload AS TEXT), unhex(hex(payload)) = payload FROM t WHERE id = x'00000001'"#,
+            "
+            |abc|TRUE|
+            ",
+        );
+
+        connection.query_types(
+            r#"SELECT x'01', cast('abc' AS VARBINARY(10)), hex(255), length('é')"#,
+            "
+            |BYTEA|BYTEA|TEXT|BIGINT|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_encoding_functions() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT to_base64('foobar'), to_base64(x'fbff'), from_base64('Zm9vYg=='), from_base64('not base64!')"#,
+            "
+            |Zm9vYmFy|+/8=|666f6f62|NULL|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT url_encode('a b&c=é'), url_decode('a%20b+c%3D%C3%A9'), url_decode('100%')"#,
+            "
+            |a%20b%26c%3D%C3%A9|a b c=é|NULL|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT cast(from_base64(to_base64('abc')) AS TEXT), url_decode(url_encode('x/y?z'))"#,
+            "
+            |abc|x/y?z|
+            ",
+        );
+    });
+}
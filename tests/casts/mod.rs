@@ -41,7 +41,271 @@ fn test_auto_cast() {
         | |   |  i|0|INTEGER||
         | |   |  b|1|BIGINT||
         | |   |  d|2|DECIMAL(10,2)||
+        | |   |access: FULL_SCAN||||
+        | |   |timestamp: LATEST||||
         ",
         );
     });
 }
+
+#[test]
+fn test_implicit_text_numeric_comparison() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT 5 = "5", "5.0" = 5, "10" > 9, 2 <= "1.5", "abc" = 1"#,
+            "
+        |TRUE|TRUE|TRUE|FALSE|FALSE|
+        ",
+        );
+
+        // Text that isn't a number compares as its numeric prefix, or 0, with a warning
+        connection.query(
+            r#"SELECT "abc" = 0, "5x" = 5, 1.5e0 = "1.5e0x""#,
+            "
+        |TRUE|TRUE|TRUE|
+        ",
+        );
+
+        connection.query(
+            r#"SHOW WARNINGS"#,
+            "
+        |Warning|1292|Truncated incorrect DECIMAL value: 'abc'|
+        |Warning|1292|Truncated incorrect DECIMAL value: '5x'|
+        |Warning|1292|Truncated incorrect DOUBLE value: '1.5e0x'|
+        ",
+        );
+
+        connection.query(r#"SHOW WARNINGS"#, "");
+
+        // Exponents are followed, numbers too big to represent are clamped with a warning
+        connection.query(
+            r#"SELECT "1e3" = 1000, "1e400" = 1, "1e400" > 1, "-1e400" < 1e0"#,
+            "
+        |TRUE|FALSE|TRUE|TRUE|
+        ",
+        );
+
+        connection.query(
+            r#"SHOW WARNINGS"#,
+            "
+        |Warning|1292|Truncated incorrect DECIMAL value: '1e400'|
+        |Warning|1292|Truncated incorrect DECIMAL value: '1e400'|
+        |Warning|1292|Truncated incorrect DOUBLE value: '-1e400'|
+        ",
+        );
+
+        connection.query(r#"create table t(i INTEGER, s TEXT)"#, "");
+
+        connection.query(
+            r#"EXPLAIN SELECT i = s from t"#,
+            "
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  _col1|0|BOOLEAN|`=`(to_decimal(<OFFSET 0>), to_decimal_lenient(<OFFSET 1>))|
+        | |source:||||
+        | |  TABLE(t)||||
+        | |   |columns:||||
+        | |   |  i|0|INTEGER||
+        | |   |  s|1|TEXT||
+        | |   |access: FULL_SCAN||||
+        | |   |timestamp: LATEST||||
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_doubles() {
+    with_connection(|connection| {
+        connection.query(
+            r#"select cast("1.5" as double) * 2, 1.5e3, cast(2.5e0 as decimal(10,2)), cast(1.75e0 as int), "2.5" = 25e-1"#,
+            "
+        |3|1500|2.50|1|TRUE|
+        ",
+        );
+
+        connection.query_types(
+            r#"select 1 + 1e0, cast(1 as decimal(10,2)) * 1e0, sum(1e0), avg(1e0)"#,
+            "
+        |DOUBLE|DOUBLE|DOUBLE|DOUBLE|
+        ",
+        );
+
+        connection.query(r#"create table t(d DOUBLE)"#, "");
+        connection.query(r#"insert into t values (1.5e0), (-2e0), (0e0), (1e10)"#, "");
+
+        connection.query(
+            r#"select d from t order by d"#,
+            "
+        |-2|
+        |0|
+        |1.5|
+        |10000000000|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_time_zones() {
+    with_connection(|connection| {
+        connection.query(r#"create table t(ts TIMESTAMP)"#, "");
+        connection.query(
+            r#"insert into t select cast('2020-01-01 10:00:00' as timestamp)"#,
+            "",
+        );
+        connection.query(r#"set time_zone = '+10:00'"#, "");
+        connection.query(
+            r#"insert into t select cast('2020-01-01 10:00:00' as timestamp)"#,
+            "",
+        );
+
+        // Timestamps are stored in utc
+        connection.query(
+            r#"select ts from t order by ts"#,
+            "
+        |2020-01-01 00:00:00|
+        |2020-01-01 10:00:00|
+        ",
+        );
+
+        // But rendered in the session's time zone
+        connection.query(
+            r#"select ts, cast(ts as text) from t order by ts"#,
+            "
+        |2020-01-01 00:00:00|2020-01-01 10:00:00|
+        |2020-01-01 10:00:00|2020-01-01 20:00:00|
+        ",
+        );
+
+        connection.query(
+            r#"select cast(convert_tz(cast('2004-01-01 12:00:00' as timestamp), '+00:00', '+10:00') as text), convert_tz(ts, 'GMT', 'MET') from t limit 1"#,
+            "
+        |2004-01-01 22:00:00|NULL|
+        ",
+        );
+
+        assert!(connection
+            .execute_statement(r#"set time_zone = 'Europe/London'"#)
+            .is_err());
+    });
+}
+
+#[test]
+fn test_sized_and_unsigned_ints() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t (
+                a TINYINT(4),
+                b SMALLINT UNSIGNED,
+                c INT(10) UNSIGNED NOT NULL,
+                d BIGINT UNSIGNED
+            )"#,
+            "",
+        );
+
+        connection.query(
+            r#"INSERT INTO t VALUES
+            (-128, 65535, 4294967295, 18446744073709551615),
+            (127, 0, 0, 0),
+            (1, 2, 3, 4)"#,
+            "",
+        );
+
+        // Literals have to fit in the column
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t VALUES (128, 0, 0, 0)"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t VALUES (0, -1, 0, 0)"#)
+            .is_err());
+
+        connection.query(
+            r#"SELECT a, b, c, d, a + b, c * 2, d + 1 FROM t ORDER BY a"#,
+            "
+            |-128|65535|4294967295|18446744073709551615|65407|8589934590|18446744073709551616|
+            |1|2|3|4|3|6|5|
+            |127|0|0|0|127|0|1|
+            ",
+        );
+
+        connection.query_types(
+            r#"SELECT a, b, c, d, a + b, c * 2 FROM t"#,
+            "
+            |TINYINT|SMALLINT UNSIGNED|INTEGER UNSIGNED|BIGINT UNSIGNED|INTEGER|BIGINT|
+            ",
+        );
+
+        // Out of range casts give nulls
+        connection.query(
+            r#"SELECT cast(300 AS TINYINT), cast(-1 AS UNSIGNED), cast('255' AS TINYINT UNSIGNED), cast(1.5e0 AS SMALLINT), cast(4294967296 AS INT)"#,
+            "
+            |NULL|NULL|255|1|NULL|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_try_cast() {
+    with_connection(|connection| {
+        let query_err = |query: &str| {
+            connection
+                .execute_statement(query)
+                .and_then(|(_, mut executor)| {
+                    while executor.next()?.is_some() {}
+                    Ok(())
+                })
+                .unwrap_err()
+                .to_string()
+        };
+
+        connection.query(
+            r#"SELECT TRY_CAST('abc' AS INT), TRY_CAST('12' AS INT), CAST('abc' AS INT), TRY_CAST(NULL AS DATE)"#,
+            "
+            |NULL|12|NULL|NULL|
+            ",
+        );
+
+        connection.query("SET sql_mode = 'STRICT_TRANS_TABLES'", "");
+        assert_eq!(
+            query_err(r#"SELECT CAST('abc' AS INT)"#),
+            r#"Cannot cast "abc" to INTEGER"#
+        );
+        // And again from the cached plan
+        assert_eq!(
+            query_err(r#"SELECT CAST('abc' AS INT)"#),
+            r#"Cannot cast "abc" to INTEGER"#
+        );
+        assert_eq!(
+            query_err(r#"SELECT CAST(300 AS TINYINT)"#),
+            "Cannot cast 300 to TINYINT"
+        );
+        connection.query(
+            r#"SELECT TRY_CAST('abc' AS INT), CAST('12' AS INT), CAST(NULL AS INT)"#,
+            "
+            |NULL|12|NULL|
+            ",
+        );
+
+        connection.query(r#"CREATE TABLE t (a TEXT)"#, "");
+        connection.query(r#"INSERT INTO t VALUES ('1'), ('x')"#, "");
+        query_err(r#"SELECT CAST(a AS INT) FROM t"#);
+        connection.query(
+            r#"SELECT a, TRY_CAST(a AS INT) FROM t ORDER BY a"#,
+            "
+            |1|1|
+            |x|NULL|
+            ",
+        );
+
+        connection.query("SET sql_mode = ''", "");
+        connection.query(
+            r#"SELECT a, CAST(a AS INT) FROM t ORDER BY a"#,
+            "
+            |1|1|
+            |x|NULL|
+            ",
+        );
+    });
+}
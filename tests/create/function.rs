@@ -0,0 +1,164 @@
+use crate::runner::*;
+
+// (func (export "add_one") (param i64) (result i64) local.get 0 i64.const 1 i64.add)
+const ADD_ONE: &str =
+    "0061736d0100000001060160017e017e03020100070b01076164645f6f6e6500000a09010700200042017c0b";
+// (func (export "div") (param i64 i64) (result i64) local.get 0 local.get 1 i64.div_s)
+const DIV: &str =
+    "0061736d0100000001070160027e7e017e030201000707010364697600000a09010700200020017f0b";
+
+#[test]
+fn create_functions() {
+    with_connection(|connection| {
+        connection.query(
+            &format!(
+                "CREATE FUNCTION add_one(x BIGINT) RETURNS BIGINT LANGUAGE WASM AS x'{}'",
+                ADD_ONE
+            ),
+            "",
+        );
+
+        connection.query(r#"SELECT add_one(41), add_one(NULL)"#, "|42|NULL|");
+
+        connection.query(
+            r#"SELECT name, return_type, language FROM incresql.functions"#,
+            "|add_one|BIGINT|wasm|",
+        );
+
+        assert!(connection
+            .execute_statement(&format!(
+                "CREATE FUNCTION add_one(x BIGINT) RETURNS BIGINT LANGUAGE WASM AS x'{}'",
+                ADD_ONE
+            ))
+            .is_err());
+
+        connection.query(r#"DROP FUNCTION add_one"#, "");
+
+        assert!(connection
+            .execute_statement(r#"SELECT add_one(41)"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"DROP FUNCTION add_one"#)
+            .is_err());
+    });
+}
+
+#[test]
+fn create_functions_invalid() {
+    with_connection(|connection| {
+        // Signature doesn't match the export
+        assert!(connection
+            .execute_statement(&format!(
+                "CREATE FUNCTION add_one(x INT) RETURNS BIGINT LANGUAGE WASM AS x'{}'",
+                ADD_ONE
+            ))
+            .is_err());
+        // Builtins can't be replaced
+        assert!(connection
+            .execute_statement(&format!(
+                "CREATE FUNCTION abs(x BIGINT) RETURNS BIGINT LANGUAGE WASM AS x'{}'",
+                ADD_ONE
+            ))
+            .is_err());
+        assert!(connection
+            .execute_statement(
+                r#"CREATE FUNCTION f(x TEXT) RETURNS TEXT LANGUAGE WASM AS x'0061736d'"#
+            )
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"CREATE FUNCTION f() RETURNS INT LANGUAGE WASM AS NULL"#)
+            .is_err());
+
+        connection.query(r#"SELECT name FROM incresql.functions"#, "");
+    });
+}
+
+#[test]
+fn function_traps() {
+    with_connection(|connection| {
+        connection.query(
+            &format!(
+                "CREATE FUNCTION div(x BIGINT, y BIGINT) RETURNS BIGINT LANGUAGE WASM AS x'{}'",
+                DIV
+            ),
+            "",
+        );
+
+        connection.query(r#"SELECT div(7, 2)"#, "|3|");
+
+        let err = connection
+            .execute_statement(r#"SELECT div(1, 0)"#)
+            .and_then(|(_, mut executor)| {
+                while executor.next()?.is_some() {}
+                Ok(())
+            })
+            .unwrap_err();
+        assert!(err.is_out_of_range());
+        assert!(err.to_string().starts_with("Function div failed"));
+    });
+}
+
+#[test]
+fn create_sql_functions() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE FUNCTION f(a INT) RETURNS INT AS 'a * 2 + 1'"#, "");
+        connection.query(
+            r#"CREATE FUNCTION zero_os(s TEXT) RETURNS TEXT LANGUAGE SQL AS 'regexp_replace(s, "o", "0")'"#,
+            "",
+        );
+
+        connection.query(r#"SELECT f(20), f(NULL), zero_os('foo')"#, "|41|NULL|f00|");
+        connection.query_types(r#"SELECT f(20), zero_os('foo')"#, "|INTEGER|TEXT|");
+        // Functions can call other functions
+        connection.query(
+            r#"CREATE FUNCTION g(a INT) RETURNS BIGINT AS 'f(f(a))'"#,
+            "",
+        );
+        connection.query(r#"SELECT g(1)"#, "|7|");
+        connection.query_types(r#"SELECT g(1)"#, "|BIGINT|");
+
+        connection.query(
+            r#"SELECT name, args, return_type, language, cast(body AS TEXT) FROM incresql.functions WHERE name = 'f'"#,
+            r#"|f|[["a","INTEGER"]]|INTEGER|sql|a * 2 + 1|"#,
+        );
+
+        connection.query(r#"DROP FUNCTION g"#, "");
+        assert!(connection.execute_statement(r#"SELECT g(1)"#).is_err());
+    });
+}
+
+#[test]
+fn create_sql_functions_invalid() {
+    with_connection(|connection| {
+        let create_err = |query: &str| {
+            connection
+                .execute_statement(query)
+                .err()
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            create_err(r#"CREATE FUNCTION f(a INT) RETURNS INT AS 'b + 1'"#),
+            "Invalid function body: Unknown argument b"
+        );
+        assert_eq!(
+            create_err(r#"CREATE FUNCTION f(a INT) RETURNS INT AS 'f(a)'"#),
+            "Invalid function body: Function f not found"
+        );
+        assert!(connection
+            .execute_statement(r#"CREATE FUNCTION f(a INT) RETURNS INT AS 'a +'"#)
+            .is_err());
+
+        // f can't be recreated to call g which calls f
+        connection.query(r#"CREATE FUNCTION f(a INT) RETURNS INT AS 'a + 1'"#, "");
+        connection.query(r#"CREATE FUNCTION g(a INT) RETURNS INT AS 'f(a) * 2'"#, "");
+        connection.query(r#"DROP FUNCTION f"#, "");
+        assert_eq!(
+            create_err(r#"CREATE FUNCTION f(a INT) RETURNS INT AS 'g(a)'"#),
+            "Function f can't call itself"
+        );
+
+        connection.query(r#"SELECT name FROM incresql.functions"#, "|g|");
+    });
+}
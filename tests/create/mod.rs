@@ -1,2 +1,4 @@
 mod database;
+mod function;
 mod table;
+mod user;
@@ -33,3 +33,59 @@ fn create_tables() {
         connection.query(r#"SELECT * FROM t1"#, "");
     });
 }
+
+#[test]
+fn create_table_primary_key_sort_order() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (a INT, b TEXT, PRIMARY KEY (a DESC, b ASC))"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO t1 VALUES (1, "a"), (3, "b"), (2, "c"), (3, "a")"#,
+            "",
+        );
+
+        // Rows come back in the order they're stored in
+        connection.query(
+            r#"SELECT a, b FROM t1"#,
+            "
+            |3|a|
+            |3|b|
+            |2|c|
+            |1|a|
+            ",
+        );
+
+        // The runtime filter range needs to take the sort order into account too
+        connection.query(r#"CREATE TABLE t2 (a INT)"#, "");
+        connection.query(r#"INSERT INTO t2 VALUES (2), (3)"#, "");
+        connection.query(
+            r#"SELECT t1.a, t1.b FROM t1 JOIN t2 ON t1.a = t2.a ORDER BY a, b"#,
+            "
+            |2|c|
+            |3|a|
+            |3|b|
+            ",
+        );
+    });
+}
+
+#[test]
+fn create_table_invalid_defaults() {
+    with_connection(|connection| {
+        assert!(connection
+            .execute_statement(r#"CREATE TABLE t1 (a INT DEFAULT nope(1))"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"CREATE TABLE t1 (a INT, b INT AS (c + 1))"#)
+            .is_err());
+        // Generated columns only get to see the inserted columns
+        assert!(connection
+            .execute_statement(r#"CREATE TABLE t1 (a INT, b INT AS (a + 1), c INT AS (b + 1))"#)
+            .is_err());
+
+        // Nothing should have been left behind
+        connection.query(r#"SELECT name FROM incresql.tables where name = "t1""#, "");
+    });
+}
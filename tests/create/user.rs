@@ -0,0 +1,139 @@
+use crate::runner::*;
+use runtime::auth::native_password_response;
+use runtime::Runtime;
+
+/// Logs in the way a mysql client would, answering the scramble with the password.
+fn authenticate_password(runtime: &Runtime, user: &str, password: &str) -> bool {
+    let scramble = b"abcdefghijklmnopqrst";
+    runtime.authenticate(
+        user,
+        scramble,
+        &native_password_response(password, scramble),
+    )
+}
+
+#[test]
+fn create_users() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE USER bob IDENTIFIED BY 'password'"#, "");
+
+        connection.query(
+            r#"SELECT * FROM incresql.users where name = "bob""#,
+            "
+                |bob|*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19|
+            ",
+        );
+
+        assert!(connection.execute_statement(r#"CREATE USER bob"#).is_err());
+
+        connection.query(r#"DROP USER bob"#, "");
+
+        connection.query(r#"SELECT * FROM incresql.users where name = "bob""#, "");
+
+        assert!(connection.execute_statement(r#"DROP USER bob"#).is_err());
+    });
+}
+
+#[test]
+fn authenticate_users() {
+    with_connection(|connection| {
+        let scramble = b"abcdefghijklmnopqrst";
+        assert!(connection.runtime.authenticate("root", scramble, &[]));
+        assert!(!connection.runtime.authenticate("root", scramble, &[1; 20]));
+        assert!(!connection.runtime.authenticate("bob", scramble, &[]));
+
+        connection.query(r#"CREATE USER bob IDENTIFIED BY 'password'"#, "");
+        assert!(!connection.runtime.authenticate("bob", scramble, &[]));
+        assert!(authenticate_password(connection.runtime, "bob", "password"));
+        assert!(!authenticate_password(connection.runtime, "bob", ""));
+    });
+}
+
+#[test]
+fn alter_users() {
+    with_connection(|connection| {
+        connection.query(r#"ALTER USER root IDENTIFIED BY 'secret'"#, "");
+        assert!(authenticate_password(connection.runtime, "root", "secret"));
+        assert!(!authenticate_password(connection.runtime, "root", ""));
+
+        connection.query(r#"CREATE USER bob IDENTIFIED BY 'password'"#, "");
+        let bob = connection.runtime.new_connection();
+        bob.login("bob".to_string()).unwrap();
+
+        // Bob can change their own password but nobody else's
+        bob.query(r#"SET PASSWORD = 'hunter2'"#, "");
+        assert!(authenticate_password(connection.runtime, "bob", "hunter2"));
+        assert!(bob
+            .execute_statement(r#"SET PASSWORD FOR root = ''"#)
+            .is_err());
+        assert!(authenticate_password(connection.runtime, "root", "secret"));
+    });
+}
+
+#[test]
+fn users_require_root() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE USER bob IDENTIFIED BY 'password'"#, "");
+        let bob = connection.runtime.new_connection();
+        bob.login("bob".to_string()).unwrap();
+
+        assert!(bob.execute_statement(r#"CREATE USER alice"#).is_err());
+        assert!(bob.execute_statement(r#"DROP USER root"#).is_err());
+        assert!(bob
+            .execute_statement(r#"SELECT * FROM incresql.users"#)
+            .is_err());
+        assert!(!authenticate_password(connection.runtime, "", ""));
+
+        let root = connection.runtime.new_connection();
+        root.login("root".to_string()).unwrap();
+        root.query(
+            r#"SELECT name FROM incresql.users where name = "bob""#,
+            "|bob|",
+        );
+        root.query(r#"DROP USER bob"#, "");
+    });
+}
+
+#[test]
+fn file_access_requires_root() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE USER bob IDENTIFIED BY 'password'"#, "");
+        connection.query(r#"CREATE TABLE t (a INT, b TEXT, c DECIMAL(10,1))"#, "");
+        let bob = connection.runtime.new_connection();
+        bob.login("bob".to_string()).unwrap();
+
+        for statement in &[
+            r#"SELECT * FROM directory "test_data/csv""#,
+            r#"SELECT * FROM t WHERE a IN (SELECT 1 FROM directory "test_data/csv")"#,
+            r#"LOAD DATA INFILE 'test_data/csv' INTO TABLE t"#,
+            r#"CREATE EXTERNAL TABLE e (a TEXT) LOCATION 'test_data/csv'"#,
+            r#"CREATE SINK s FROM t INTO FILE 'bobs_sink.json'"#,
+            r#"SELECT * FROM t INTO OUTFILE 'bobs_outfile.json'"#,
+        ] {
+            let err = bob.execute_statement(statement).err().unwrap();
+            assert!(err.to_string().contains("FILE privilege"), "{}", err);
+        }
+        assert!(!std::path::Path::new("bobs_sink.json").exists());
+        assert!(!std::path::Path::new("bobs_outfile.json").exists());
+    });
+}
+
+#[test]
+fn cached_plans_require_root() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE USER bob IDENTIFIED BY 'password'"#, "");
+        let root = connection.runtime.new_connection();
+        root.login("root".to_string()).unwrap();
+        root.query(
+            r#"SELECT name FROM incresql.users where name = "bob""#,
+            "|bob|",
+        );
+
+        // Root's plan is cached by now but bob mustn't get to use it
+        let bob = connection.runtime.new_connection();
+        bob.login("bob".to_string()).unwrap();
+        assert!(bob
+            .execute_statement(r#"SELECT name FROM incresql.users where name = "bob""#)
+            .is_err());
+    });
+}
@@ -43,3 +43,58 @@ fn test_delete() {
         );
     });
 }
+
+#[test]
+fn test_delete_using() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(r#"CREATE TABLE t2 (a INT)"#, "");
+
+        connection.query(
+            r#"INSERT INTO t1 VALUES (1, "abc"), (1, "abc"), (2, "def"), (3, "ghi")"#,
+            "",
+        );
+        connection.query(r#"INSERT INTO t2 VALUES (1), (1), (3)"#, "");
+
+        // Each row should only be removed once even though they match multiple rows in t2
+        connection.query(
+            r#"DELETE FROM t1 USING t2 WHERE t1.a = t2.a AND t2.a = 1"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT * FROM t1"#,
+            "
+            |2|def|
+            |3|ghi|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_multi_table_anti_join_delete() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(r#"CREATE TABLE t2 (a INT)"#, "");
+
+        connection.query(
+            r#"INSERT INTO t1 VALUES (1, "abc"), (2, "def"), (3, "ghi")"#,
+            "",
+        );
+        connection.query(r#"INSERT INTO t2 VALUES (1), (3)"#, "");
+
+        connection.query(
+            r#"DELETE x FROM t1 x LEFT JOIN t2 ON x.a = t2.a WHERE t2.a IS NULL"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT * FROM t1"#,
+            "
+            |1|abc|
+            |3|ghi|
+        ",
+        );
+    });
+}
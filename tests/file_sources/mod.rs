@@ -1,4 +1,5 @@
 use crate::runner::*;
+use std::fs;
 
 #[test]
 fn test_select_from_csv_file() {
@@ -12,3 +13,247 @@ fn test_select_from_csv_file() {
         );
     });
 }
+
+#[test]
+fn test_select_from_glob() {
+    with_connection(|connection| {
+        connection.query_unordered(
+            r#"select * from directory "test_data/partitioned/dt=2020-11-01/**/*.csv""#,
+            r#"
+        |["1","apple"]|
+        |["2","pear"]|
+        |["3","kiwi"]|
+        "#,
+        );
+
+        // Only the files directly within the directory
+        connection.query(
+            r#"select count(*) from directory "test_data" with (recursive = false)"#,
+            "
+            |0|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_select_from_compressed_files() {
+    with_connection(|connection| {
+        connection.query_unordered(
+            r#"select * from directory "test_data/compressed""#,
+            r#"
+        |["123","abc","12.1"]|
+        |["123","abc","12.1"]|
+        |["456","d,ef","13.2"]|
+        |["456","d,ef","13.2"]|
+        "#,
+        );
+    });
+}
+
+#[test]
+fn test_load_data_infile() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t (a INT, b TEXT, c DECIMAL(10,1))"#, "");
+
+        connection.query(r#"LOAD DATA INFILE 'test_data/csv' INTO TABLE t"#, "");
+
+        connection.query(
+            r#"SELECT * FROM t"#,
+            "
+            |123|abc|12.1|
+            |456|d,ef|13.2|
+            ",
+        );
+
+        // Loading again appends rather than overwriting
+        connection.query(r#"COPY t FROM 'test_data/csv' WITHOUT SYNC"#, "");
+
+        connection.query(
+            r#"SELECT count(*) FROM t"#,
+            "
+            |4|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_load_data_columns() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t (b TEXT, a INT DEFAULT 1)"#, "");
+
+        connection.query(r#"LOAD DATA INFILE 'test_data/csv' INTO TABLE t (b)"#, "");
+
+        connection.query(
+            r#"SELECT * FROM t"#,
+            "
+            |123|1|
+            |456|1|
+            ",
+        );
+
+        assert!(connection
+            .execute_statement(r#"LOAD DATA INFILE 'test_data/csv' INTO TABLE missing"#)
+            .is_err());
+    });
+}
+
+#[test]
+fn test_external_table() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE EXTERNAL TABLE t (a INT, b TEXT, c DECIMAL(10,1)) LOCATION 'test_data/csv' FORMAT CSV"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT * FROM t"#,
+            "
+            |123|abc|12.1|
+            |456|d,ef|13.2|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT b, a + 1 FROM t WHERE c > 13"#,
+            "
+            |d,ef|457|
+            ",
+        );
+
+        // The header option skips the first line of each file
+        connection.query(
+            r#"CREATE EXTERNAL TABLE h (a INT, b TEXT) LOCATION 'test_data/csv' (HEADER)"#,
+            "",
+        );
+        connection.query(
+            r#"SELECT * FROM h"#,
+            "
+            |456|d,ef|
+            ",
+        );
+
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t VALUES (1, 'a', 1.0)"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"SELECT * FROM t AS OF TIMESTAMP '2020-01-01'"#)
+            .is_err());
+
+        connection.query(r#"DROP TABLE t"#, "");
+        assert!(connection.execute_statement(r#"SELECT * FROM t"#).is_err());
+    });
+}
+
+#[test]
+fn test_partitioned_external_table() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE EXTERNAL TABLE sales (id INT, item TEXT) PARTITIONED BY (dt DATE, region TEXT)
+               LOCATION 'test_data/partitioned'"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"SELECT * FROM sales"#,
+            "
+            |1|apple|2020-11-01|eu|
+            |2|pear|2020-11-01|eu|
+            |3|kiwi|2020-11-01|us|
+            |4|plum|2020-11-02|us|
+            |5|fig|2020-11-02|us|
+            ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT id, item FROM sales WHERE region = 'us' AND dt = cast('2020-11-01' as date)"#,
+            "
+            |3|kiwi|
+            ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT region, count(*) FROM sales WHERE id > 1 GROUP BY region"#,
+            "
+            |eu|1|
+            |us|3|
+            ",
+        );
+
+        // Nothing left to scan
+        connection.query(r#"SELECT * FROM sales WHERE region = 'asia'"#, "");
+    });
+}
+
+#[test]
+fn test_select_into_outfile() {
+    with_connection(|connection| {
+        let dir = std::env::temp_dir();
+        let path = |name: &str| {
+            dir.join(format!("incresql_outfile_{}.{}", std::process::id(), name))
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let (json_path, csv_path, gz_path) = (path("json"), path("csv"), path("csv.gz"));
+        for path in &[&json_path, &csv_path, &gz_path] {
+            let _ = fs::remove_file(path);
+        }
+
+        connection.query(r#"CREATE TABLE t (a INT, b TEXT)"#, "");
+        connection.query(r#"INSERT INTO t VALUES (1, "a|b"), (2, NULL)"#, "");
+
+        connection.query(
+            &format!("SELECT * FROM t ORDER BY a INTO OUTFILE '{}'", json_path),
+            "",
+        );
+        assert_eq!(
+            fs::read_to_string(&json_path).unwrap(),
+            "{\"a\":1,\"b\":\"a|b\"}\n{\"a\":2,\"b\":null}\n"
+        );
+        // Existing files are never overwritten
+        assert!(connection
+            .execute_statement(&format!("SELECT 1 INTO OUTFILE '{}'", json_path))
+            .is_err());
+        assert_eq!(fs::read_to_string(&json_path).unwrap().lines().count(), 2);
+
+        connection.query(
+            &format!(
+                "SELECT * FROM t ORDER BY a INTO OUTFILE '{}' FORMAT CSV FIELDS TERMINATED BY '|'",
+                csv_path
+            ),
+            "",
+        );
+        assert_eq!(fs::read_to_string(&csv_path).unwrap(), "1|\"a|b\"\n2|\n");
+
+        // The csv can be loaded straight back in
+        connection.query(r#"CREATE TABLE t2 (a INT, b TEXT)"#, "");
+        connection.query(
+            &format!(
+                "LOAD DATA INFILE '{}' INTO TABLE t2 FIELDS TERMINATED BY '|'",
+                csv_path
+            ),
+            "",
+        );
+        connection.query(
+            r#"SELECT a, b FROM t2 WHERE a = 1"#,
+            "
+            |1|a|b|
+            ",
+        );
+
+        connection.query(
+            &format!(
+                "SELECT * FROM t INTO OUTFILE '{}' FORMAT CSV COMPRESSION GZIP",
+                gz_path
+            ),
+            "",
+        );
+        assert_eq!(&fs::read(&gz_path).unwrap()[..2], &[0x1f, 0x8b]);
+
+        for path in &[json_path, csv_path, gz_path] {
+            let _ = fs::remove_file(path);
+        }
+    });
+}
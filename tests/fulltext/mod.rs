@@ -0,0 +1,110 @@
+use crate::runner::*;
+
+#[test]
+fn test_match_against() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE docs (id INT, body TEXT, FULLTEXT INDEX body_idx (body))"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO docs VALUES
+            (1, "The quick brown fox"),
+            (2, "A lazy dog"),
+            (3, "Quick, the dog is coming!"),
+            (4, NULL)"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT id FROM docs WHERE MATCH (body) AGAINST ('QUICK') ORDER BY id"#,
+            "
+            |1|
+            |3|
+            ",
+        );
+
+        // Rows matching many of the terms are only returned once
+        connection.query(
+            r#"SELECT id FROM docs WHERE MATCH (body) AGAINST ('fox dog cat' IN NATURAL LANGUAGE MODE) ORDER BY id"#,
+            "
+            |1|
+            |2|
+            |3|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT id FROM docs d WHERE MATCH (d.body) AGAINST ('dog') AND id > 2"#,
+            "
+            |3|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT id, MATCH (body) AGAINST ('lazy') FROM docs ORDER BY id"#,
+            "
+            |1|FALSE|
+            |2|TRUE|
+            |3|FALSE|
+            |4|NULL|
+            ",
+        );
+
+        // The index is kept up to date by deletes
+        connection.query(r#"DELETE FROM docs WHERE id = 3"#, "");
+        connection.query(
+            r#"SELECT id FROM docs WHERE MATCH (body) AGAINST ('dog')"#,
+            "
+            |2|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_create_fulltext_index() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE docs (id INT, body TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO docs VALUES (1, "hello world"), (2, "goodbye")"#,
+            "",
+        );
+
+        // Existing rows get indexed
+        connection.query(r#"CREATE FULLTEXT INDEX body_idx ON docs (body)"#, "");
+        connection.query(
+            r#"SELECT id FROM docs WHERE MATCH (body) AGAINST ('world')"#,
+            "
+            |1|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT index_name, column_name, non_unique FROM information_schema.statistics
+            WHERE table_name = "docs" AND index_name = "body_idx""#,
+            "
+            |body_idx|body|TRUE|
+            ",
+        );
+
+        assert!(connection
+            .execute_statement("CREATE FULLTEXT INDEX body_idx ON docs (body)")
+            .is_err());
+        assert!(connection
+            .execute_statement("CREATE FULLTEXT INDEX id_idx ON docs (id)")
+            .is_err());
+        assert!(connection
+            .execute_statement("CREATE TABLE bad (id INT, FULLTEXT (id))")
+            .is_err());
+        assert!(connection
+            .execute_statement("CREATE TABLE bad (id INT, body TEXT, FULLTEXT (body)) PARTITION BY HASH (id) PARTITIONS 2")
+            .is_err());
+
+        connection.query(r#"DROP TABLE docs"#, "");
+        connection.query(
+            r#"SELECT index_name FROM information_schema.statistics WHERE table_name = "docs""#,
+            "",
+        );
+    });
+}
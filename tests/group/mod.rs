@@ -13,6 +13,9 @@ fn test_auto_convert_project() {
         | |  TABLE(databases)||||
         | |   |columns:||||
         | |   |  name|0|TEXT||
+        | |   |access: FULL_SCAN||||
+        | |   |decoded_columns: NONE||||
+        | |   |timestamp: LATEST||||
         ",
         );
 
@@ -31,13 +34,364 @@ fn test_auto_convert_project() {
             "",
         );
 
+        connection.query_unordered(
+            r#"select c1, count(*), count(c2), sum(c2) from test group by c1"#,
+            "
+            |a|2|2|3|
+            |b|2|1|3|
+            |c|1|0|NULL|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_distinct_on() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (id INT, ts INT, v TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        (1, 1, "a"), (1, 3, "b"), (1, 2, "c"), (2, 5, "d"), (2, 4, "e"), (3, 1, NULL)"#,
+            "",
+        );
+
+        // Latest row per id
+        connection.query(
+            r#"select distinct on (id) id, ts, v from test order by id, ts desc"#,
+            "
+            |1|3|b|
+            |2|5|d|
+            |3|1|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select distinct on (id) * from test order by id desc, ts limit 2"#,
+            "
+            |3|1|NULL|
+            |2|4|e|
+        ",
+        );
+
+        // Keys don't have to be in the select list
+        connection.query(
+            r#"select distinct on (id) v from test order by v"#,
+            "
+            |NULL|
+            |a|
+            |d|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_distinct() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (id INT, ts INT, v TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        (1, 1, "a"), (1, 3, "a"), (1, 2, "c"), (2, 5, "d"), (2, 4, "d"), (3, 1, NULL)"#,
+            "",
+        );
+
+        connection.query(
+            r#"select distinct id, v from test order by id, v"#,
+            "
+            |1|a|
+            |1|c|
+            |2|d|
+            |3|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select distinct v from test order by v desc limit 2"#,
+            "
+            |d|
+            |c|
+        ",
+        );
+
+        connection.query(r#"select distinct count(*) from test"#, "|6|");
+
+        // The group by gives one row per v but the counts can still repeat
+        connection.query(
+            r#"select distinct count(*) as c from test group by v order by c"#,
+            "
+            |1|
+            |2|
+        ",
+        );
+        connection.query(
+            r#"select count(*) from (select distinct v from test) sub"#,
+            "|4|",
+        );
+    });
+}
+
+#[test]
+fn test_group_over_union() {
+    // Group bys over unions get split into partial and final aggregations
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t(k TEXT, v INTEGER) PARTITION BY HASH (v) PARTITIONS 4"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO t VALUES
+            ("a", 1), ("a", 2), ("b", 3), ("a", 3), ("b", NULL), (NULL, 5), (NULL, 6)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"SELECT k, count(*), sum(v), avg(v), one
+            FROM (SELECT k, v, 1 as one FROM t) sub
+            GROUP BY k"#,
+            "
+            |NULL|2|11|5.50000000000000|1|
+            |a|3|6|2.00000000000000|1|
+            |b|2|3|3.00000000000000|1|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT count(*), sum(v) + 1 FROM t"#,
+            "
+            |7|21|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT count(*), sum(v) FROM t WHERE v = 1"#,
+            "
+            |1|1|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_distinct_aggregates() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (c1 TEXT, c2 INT)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        ("a", 1), ("a", 1), ("a", 2), ("b", 3), ("b", 3), ("b", NULL), ("c", NULL)"#,
+            "",
+        );
+
+        connection.query(
+            r#"select count(distinct c2), sum(DISTINCT c2) from test"#,
+            "
+            |3|6|
+        ",
+        );
+
+        connection.query_unordered(
+            r#"select c1, count(distinct c2), avg(distinct c2) from test group by c1"#,
+            "
+            |a|2|1.50000000000000|
+            |b|1|3.00000000000000|
+            |c|0|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select count(distinct c2) from test where false"#,
+            "
+            |0|
+        ",
+        );
+
+        // Only a single set of distinct arguments is supported
+        assert!(connection
+            .execute_statement("select count(distinct c1), count(distinct c2) from test")
+            .is_err());
+        assert!(connection
+            .execute_statement("select count(*), count(distinct c2) from test")
+            .is_err());
+        assert!(connection
+            .execute_statement("select to_bigint(distinct c2) from test")
+            .is_err());
+    });
+}
+
+#[test]
+fn test_window_groups() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE events (ts TIMESTAMP, v INT)"#, "");
         connection.query(
-            r#"select c1, count(*), count(c2), sum(c2) from test group by c1 order by c1"#,
+            r#"INSERT INTO events
+            SELECT CAST("2020-11-01 12:01:00" AS TIMESTAMP), 1
+            UNION ALL SELECT CAST("2020-11-01 12:04:00" AS TIMESTAMP), 2
+            UNION ALL SELECT CAST("2020-11-01 12:06:00" AS TIMESTAMP), 4"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"SELECT tumble(ts, 300), sum(v) FROM events GROUP BY tumble(ts, 300)"#,
+            "
+            |2020-11-01 12:00:00|3|
+            |2020-11-01 12:05:00|4|
+        ",
+        );
+
+        connection.query_unordered(
+            r#"SELECT hop(ts, 300, 600) AS w, count(*), sum(v) FROM events GROUP BY hop(ts, 300, 600)"#,
+            "
+            |2020-11-01 11:55:00|2|3|
+            |2020-11-01 12:00:00|3|7|
+            |2020-11-01 12:05:00|1|4|
+        ",
+        );
+
+        assert!(connection
+            .execute_statement("SELECT count(*) FROM events GROUP BY hop(ts, 300, 500)")
+            .is_err());
+    });
+}
+
+#[test]
+fn test_parallel_group() {
+    with_connection(|connection| {
+        connection.query(r#"SET parallel_workers = 4"#, "");
+        connection.query(r#"Create table test (c1 TEXT, c2 INT)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        ("a", 1), ("a", 2), ("b", 3), ("b", NULL), ("c", NULL)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"select c1, count(*), count(c2), sum(c2) from test where c1 != "d" group by c1"#,
             "
             |a|2|2|3|
             |b|2|1|3|
             |c|1|0|NULL|
         ",
         );
+
+        connection.query(
+            r#"select count(*), sum(c2) from test where c2 > 1"#,
+            "
+            |2|5|
+        ",
+        );
+
+        connection.query(
+            r#"select count(*), sum(c2) from test where c2 > 10"#,
+            "
+            |0|NULL|
+        ",
+        );
+
+        connection.query_unordered(
+            r#"select c2 + 1 from test where c2 is not null"#,
+            "
+            |2|
+            |3|
+            |4|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_approx_aggregates() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (c1 TEXT, c2 INT)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        ("a", 1), ("a", 2), ("a", 3), ("b", 3), ("b", 5), ("b", NULL), ("c", NULL)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"select c1, approx_count_distinct(c2), approx_percentile(c2, 0.5) from test group by c1"#,
+            "
+            |a|3|2|
+            |b|2|4|
+            |c|0|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select approx_count_distinct(c1), approx_percentile(c2, 1) from test"#,
+            "
+            |3|5|
+        ",
+        );
+
+        connection.query(
+            r#"select approx_count_distinct(c2) from test where false"#,
+            "
+            |0|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_exact_percentiles() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (c1 TEXT, c2 INT, c3 DOUBLE)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        ("a", 1, 1e0), ("a", 2, 2e0), ("a", 4, 4e0), ("a", 10, 10e0),
+        ("b", 3, 3e0), ("b", NULL, NULL), ("c", NULL, NULL)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"select c1, median(c2), median(c3), percentile_cont(c2, 0.25), percentile_disc(c2, 0.25) from test group by c1"#,
+            "
+            |a|3.00000000000000|3|1.75000000000000|1|
+            |b|3.00000000000000|3|3.00000000000000|3|
+            |c|NULL|NULL|NULL|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select percentile_disc(c1, 0.5), percentile_cont(c3, 1), percentile_cont(c2, 2) from test"#,
+            "
+            |a|10|NULL|
+        ",
+        );
+
+        connection.query_types(
+            r#"select median(c2), median(c3), percentile_disc(c1, 0.5) from test"#,
+            "
+        |DECIMAL(28,14)|DOUBLE|TEXT|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_bool_and_bit_aggregates() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (c1 TEXT, c2 INT, c3 BOOLEAN)"#, "");
+        connection.query(
+            r#"INSERT INTO test VALUES
+        ("a", 6, true), ("a", 3, false), ("b", 5, true), ("b", NULL, NULL), ("c", NULL, NULL)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"select c1, bool_and(c3), bool_or(c3), bit_and(c2), bit_or(c2), bit_xor(c2), any_value(c1) from test group by c1"#,
+            "
+            |a|FALSE|TRUE|2|7|5|a|
+            |b|TRUE|TRUE|5|5|5|b|
+            |c|NULL|NULL|18446744073709551615|0|0|c|
+        ",
+        );
+
+        connection.query_types(
+            r#"select bool_and(c3), bit_xor(c2), any_value(c1) from test"#,
+            "
+        |BOOLEAN|BIGINT UNSIGNED|TEXT|
+        ",
+        );
     });
 }
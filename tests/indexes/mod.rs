@@ -0,0 +1,56 @@
+use crate::runner::*;
+
+#[test]
+fn test_secondary_index_scans() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE people (id INT, name TEXT, age INT)"#, "");
+        connection.query(
+            r#"INSERT INTO people VALUES (1, "alice", 30), (2, "bob", 25), (3, "carol", 35)"#,
+            "",
+        );
+
+        // Existing rows get indexed, new rows are indexed as they're inserted
+        connection.query(r#"CREATE INDEX age_idx ON people (age, name)"#, "");
+        connection.query(r#"INSERT INTO people VALUES (4, "dave", 25)"#, "");
+
+        connection.query(
+            r#"SELECT id, name FROM people WHERE age = 25 ORDER BY id"#,
+            "
+            |2|bob|
+            |4|dave|
+            ",
+        );
+        connection.query(
+            r#"SELECT name FROM people WHERE age = 25 AND name > "c""#,
+            "|dave|",
+        );
+        connection.query(
+            r#"SELECT id FROM people WHERE age >= 30 ORDER BY id"#,
+            "
+            |1|
+            |3|
+            ",
+        );
+
+        connection.query(r#"DELETE FROM people WHERE age = 25 AND id = 2"#, "");
+        connection.query(r#"SELECT id FROM people WHERE age = 25"#, "|4|");
+    });
+}
+
+#[test]
+fn test_secondary_index_statistics() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t (a INT, b TEXT)"#, "");
+        connection.query(r#"CREATE INDEX b_idx ON t (b)"#, "");
+
+        connection.query(
+            r#"SELECT index_name, seq_in_index, column_name, non_unique
+            FROM information_schema.statistics WHERE table_name = "t""#,
+            "
+            |PRIMARY|1|a|FALSE|
+            |PRIMARY|2|b|FALSE|
+            |b_idx|1|b|TRUE|
+            ",
+        );
+    });
+}
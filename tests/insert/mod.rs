@@ -47,3 +47,182 @@ fn test_insert_from_values() {
         );
     });
 }
+
+#[test]
+fn test_insert_column_list_and_defaults() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (a INT, b TEXT DEFAULT "none", c INT, ts TIMESTAMP DEFAULT CURRENT_TIMESTAMP)"#,
+            "",
+        );
+
+        connection.query(r#"INSERT INTO t1 (c, a) VALUES (3, 1)"#, "");
+        connection.query(r#"INSERT INTO t1 (a, b) SELECT 2, "abc""#, "");
+
+        connection.query(
+            r#"SELECT a, b, c, ts IS NOT NULL FROM t1"#,
+            "
+                |1|none|3|TRUE|
+                |2|abc|NULL|TRUE|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_insert_not_null() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (a INT NOT NULL, b INT NOT NULL DEFAULT 0, c INT NULL)"#,
+            "",
+        );
+
+        connection.query(r#"INSERT INTO t1 (a) VALUES (1)"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (2, 3, NULL)"#, "");
+
+        // Nulls as literals are caught when planning, otherwise when writing the rows
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t1 VALUES (NULL, 1, 1)"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t1 (b) VALUES (1)"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t1 SELECT c, b, c FROM t1"#)
+            .and_then(|(_, mut executor)| Ok(executor.next()?.is_none()))
+            .is_err());
+
+        connection.query(
+            r#"SELECT * FROM t1"#,
+            "
+                |1|0|NULL|
+                |2|3|NULL|
+            ",
+        );
+
+        // Not null columns are known to never be null
+        connection.query(
+            r#"SELECT a IS NULL, c IS NULL FROM t1 WHERE a = 1"#,
+            "|FALSE|TRUE|",
+        );
+    });
+}
+
+#[test]
+fn test_insert_generated_columns() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b INT AS (a + 1))"#, "");
+
+        connection.query(r#"INSERT INTO t1 (a) VALUES (1), (2)"#, "");
+        // Without a column list the values line up with the columns that aren't generated
+        connection.query(r#"INSERT INTO t1 VALUES (3)"#, "");
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t1 VALUES (4, 100)"#)
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t1 (a, b) VALUES (4, 100)"#)
+            .is_err());
+
+        connection.query(
+            r#"SELECT * FROM t1"#,
+            "
+                |1|2|
+                |2|3|
+                |3|4|
+            ",
+        );
+
+        connection.query(r#"DELETE FROM t1 WHERE b = 3"#, "");
+
+        connection.query(
+            r#"SELECT * FROM t1"#,
+            "
+                |1|2|
+                |3|4|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_insert_affected_rows() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+
+        connection.execute_affected(r#"INSERT INTO t1 VALUES (1, "abc"), (2, "def")"#, 2);
+        connection.execute_affected(r#"INSERT INTO t1 SELECT a + 2, b FROM t1"#, 2);
+        connection.execute_affected(r#"DELETE FROM t1 WHERE a > 1"#, 3);
+        connection.execute_affected(r#"DELETE FROM t1 WHERE a > 1"#, 0);
+
+        connection.query_types(r#"SELECT * FROM t1"#, "|INTEGER|TEXT|");
+        connection.query_unordered(
+            r#"SELECT * FROM t1"#,
+            "
+                |1|abc|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_replace() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (id INT, v TEXT, n INT, PRIMARY KEY (id))"#,
+            "",
+        );
+
+        connection.execute_affected(r#"REPLACE INTO t1 VALUES (1, "a", 1), (2, "b", 2)"#, 2);
+        connection.execute_affected(r#"REPLACE INTO t1 VALUES (1, "c", 3)"#, 2);
+        // Later rows in the statement replace earlier ones
+        connection.query(r#"REPLACE t1 VALUES (3, "d", 4), (3, "e", 5)"#, "");
+
+        connection.query_unordered(
+            r#"SELECT * FROM t1"#,
+            "
+                |1|c|3|
+                |2|b|2|
+                |3|e|5|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_insert_on_duplicate_key_update() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (id INT, v TEXT, n INT, PRIMARY KEY (id))"#,
+            "",
+        );
+
+        connection.query(r#"INSERT INTO t1 VALUES (1, "a", 1), (2, "b", 2)"#, "");
+        connection.execute_affected(
+            r#"INSERT INTO t1 VALUES (1, "c", 10) ON DUPLICATE KEY UPDATE n = n + VALUES(n)"#,
+            2,
+        );
+        connection.query(
+            r#"INSERT INTO t1 VALUES (3, "d", 1), (3, "e", 1), (2, "f", 5)
+               ON DUPLICATE KEY UPDATE n = n + VALUES(n), v = VALUES(v)"#,
+            "",
+        );
+
+        connection.query_unordered(
+            r#"SELECT * FROM t1"#,
+            "
+                |1|a|11|
+                |2|f|7|
+                |3|e|2|
+            ",
+        );
+
+        assert!(connection
+            .execute_statement(
+                r#"INSERT INTO t1 VALUES (1, "a", 1) ON DUPLICATE KEY UPDATE n = "abc""#
+            )
+            .is_err());
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t1 VALUES (1, "a", 1) ON DUPLICATE KEY UPDATE x = 1"#)
+            .is_err());
+    });
+}
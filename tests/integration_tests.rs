@@ -1,13 +1,22 @@
 mod building_blocks;
+mod bytes;
 mod casts;
 mod create;
 mod delete;
 mod file_sources;
+mod fulltext;
 mod group;
+mod indexes;
 mod insert;
 mod join;
 mod json;
+mod materialized_views;
 mod optimize;
+mod partitions;
+mod query_cache;
 mod runner;
 mod show;
+mod subscriptions;
+mod text;
+mod vector;
 mod views;
@@ -96,6 +96,8 @@ fn test_inner_joins() {
         | |   |   |   |   |columns:||||
         | |   |   |   |   |  l_id|0|INTEGER||
         | |   |   |   |   |  l_text|1|TEXT||
+        | |   |   |   |   |access: FULL_SCAN||||
+        | |   |   |   |   |timestamp: LATEST||||
         | |   |   |right:||||
         | |   |   |  PROJECT||||
         | |   |   |   |output_exprs:||||
@@ -107,6 +109,8 @@ fn test_inner_joins() {
         | |   |   |   |   |columns:||||
         | |   |   |   |   |  r_id|0|INTEGER||
         | |   |   |   |   |  r_text|1|TEXT||
+        | |   |   |   |   |access: FULL_SCAN||||
+        | |   |   |   |   |timestamp: LATEST||||
         "#,
         );
 
@@ -264,3 +268,186 @@ fn test_left_joins() {
         );
     });
 }
+
+#[test]
+fn test_join_runtime_filters() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE fact (f_id INT, f_dim TEXT, f_value INT)"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO fact VALUES
+        (1, "a", 10), (2, "b", 20), (3, "c", 30), (4, "a", 40),
+        (5, "d", 50), (6, "b", 60), (null, "a", 70), (8, null, 80)
+        "#,
+            "",
+        );
+
+        connection.query(
+            r#"CREATE TABLE dim (d_id INT, d_name TEXT, d_region TEXT)"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO dim VALUES
+        (2, "a", "north"), (4, "b", "south"), (6, "c", "north"), (100, "e", "east")
+        "#,
+            "",
+        );
+
+        // Filter derived from the first(pk) column of the probe side.
+        connection.query(
+            r#"SELECT f_id, f_value, d_name FROM fact join dim
+        on f_id = d_id
+        WHERE d_region = "north"
+        ORDER BY f_id
+        "#,
+            r#"
+        |2|20|a|
+        |6|60|c|
+        "#,
+        );
+
+        // Filter on a non leading column.
+        connection.query(
+            r#"SELECT f_id, f_dim, d_region FROM fact join dim
+        on f_dim = d_name
+        ORDER BY f_id
+        "#,
+            r#"
+        |NULL|a|north|
+        |1|a|north|
+        |2|b|south|
+        |3|c|north|
+        |4|a|north|
+        |6|b|south|
+        "#,
+        );
+
+        // Empty build side
+        connection.query(
+            r#"SELECT f_id FROM fact join dim
+        on f_id = d_id
+        WHERE d_region = "west"
+        "#,
+            "",
+        );
+
+        // Left joins must keep all the rows
+        connection.query(
+            r#"SELECT f_id, d_name FROM fact left join dim
+        on f_id = d_id AND d_region = "north"
+        ORDER BY f_id
+        "#,
+            r#"
+        |NULL|NULL|
+        |1|NULL|
+        |2|a|
+        |3|NULL|
+        |4|NULL|
+        |5|NULL|
+        |6|c|
+        |8|NULL|
+        "#,
+        );
+    });
+}
+
+#[test]
+fn test_null_safe_joins() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t_left (l_id INT, l_text TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO t_left VALUES (1, "la"), (2, "lb"), (null, "lc")"#,
+            "",
+        );
+
+        connection.query(r#"CREATE TABLE t_right (r_id INT, r_text TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO t_right VALUES (1, "ra"), (3, "rb"), (null, "rc")"#,
+            "",
+        );
+
+        // Unlike =, nulls match each other
+        connection.query(
+            r#"SELECT l_text, r_text FROM t_left JOIN t_right ON l_id <=> r_id
+        ORDER BY l_text
+        "#,
+            r#"
+        |la|ra|
+        |lc|rc|
+        "#,
+        );
+    });
+}
+
+#[test]
+fn test_subqueries() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t_left (l_id INT, l_text TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO t_left VALUES (1, "la"), (2, "lc"), (3, "le")"#,
+            "",
+        );
+
+        connection.query(r#"CREATE TABLE t_right (r_id INT, r_text TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO t_right VALUES (1, "ra"), (1, "rb"), (3, "x"), (4, "re")"#,
+            "",
+        );
+
+        // Each left row should only come out once no matter how many matches
+        connection.query(
+            r#"SELECT * FROM t_left WHERE l_id IN (SELECT r_id FROM t_right) ORDER BY l_id"#,
+            r#"
+        |1|la|
+        |3|le|
+        "#,
+        );
+
+        connection.query(
+            r#"SELECT * FROM t_left WHERE l_id NOT IN (SELECT r_id FROM t_right)"#,
+            r#"
+        |2|lc|
+        "#,
+        );
+
+        connection.query(
+            r#"SELECT l_text FROM t_left
+            WHERE EXISTS (SELECT 1 FROM t_right WHERE r_id = l_id AND r_text = "ra")"#,
+            r#"
+        |la|
+        "#,
+        );
+
+        connection.query(
+            r#"SELECT l_text FROM t_left
+            WHERE NOT EXISTS (SELECT * FROM t_right WHERE t_right.r_id = t_left.l_id)"#,
+            r#"
+        |lc|
+        "#,
+        );
+
+        connection.query(
+            r#"SELECT l_text FROM t_left
+            WHERE EXISTS (SELECT * FROM t_right WHERE r_id = 4) AND l_id > 1
+            ORDER BY l_text"#,
+            r#"
+        |lc|
+        |le|
+        "#,
+        );
+
+        connection.query(
+            r#"DELETE FROM t_left WHERE l_id IN (SELECT r_id FROM t_right WHERE r_text = "x")"#,
+            "",
+        );
+        connection.query(
+            r#"SELECT * FROM t_left ORDER BY l_id"#,
+            r#"
+        |1|la|
+        |2|lc|
+        "#,
+        );
+    });
+}
@@ -19,6 +19,25 @@ fn test_json_extract() {
     });
 }
 
+#[test]
+fn test_json_extract_path_features() {
+    with_connection(|connection| {
+        connection.query(
+            r#"select cast("{\"a\": 1, \"b\": {\"a\": 2}}" as json)->"$..a", cast("[0,1,2,3,4]" as json)->"$[1:3]", cast("[0,1,2,3,4]" as json)->"$[0, -1:]""#,
+            "
+        |[1,2]|[1,2]|[0,4]|
+        ",
+        );
+
+        connection.query(
+            r#"select cast("[{\"n\": \"a\", \"p\": 5}, {\"n\": \"b\", \"p\": 15}]" as json)->"$[?(@.p > 10 && @.n != 'a')].n""#,
+            r#"
+        |["b"]|
+        "#,
+        );
+    });
+}
+
 #[test]
 fn test_json_unquote() {
     with_connection(|connection| {
@@ -59,3 +78,153 @@ fn test_json_unquote() {
         );
     });
 }
+
+#[test]
+fn test_json_aggregates() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t (id INT, k TEXT, v INT, PRIMARY KEY (id))"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO t VALUES (1, "a", 1), (2, "b", NULL), (3, "c", 3), (4, "a", 4)"#,
+            "",
+        );
+
+        connection.query(
+            r#"select json_arrayagg(v), array_agg(k), json_objectagg(k, v), type_of(array_agg(id)) from t"#,
+            r#"
+        |[1,null,3,4]|["a","b","c","a"]|{"a":4,"b":null,"c":3}|JSON|
+        "#,
+        );
+
+        connection.query_unordered(
+            r#"select k, json_arrayagg(id), json_objectagg(k, cast(v as json)) from t group by k"#,
+            r#"
+        |a|[1,4]|{"a":4}|
+        |b|[2]|{"b":null}|
+        |c|[3]|{"c":3}|
+        "#,
+        );
+
+        connection.query(
+            r#"select json_arrayagg(v) from t where false"#,
+            "
+        |NULL|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_json_modify() {
+    with_connection(|connection| {
+        connection.query(
+            r#"select json_set(cast("{\"a\": 1, \"b\": [1, 2]}" as json), "$.a", 10, "$.c", "foo")"#,
+            r#"
+        |{"a":10,"b":[1,2],"c":"foo"}|
+        "#,
+        );
+
+        connection.query(
+            r#"select json_insert(cast("{\"a\": 1, \"b\": [1, 2]}" as json), "$.a", 10, "$.b[5]", true)"#,
+            r#"
+        |{"a":1,"b":[1,2,true]}|
+        "#,
+        );
+
+        connection.query(
+            r#"select json_replace(cast("{\"a\": 1, \"b\": [1, 2]}" as json), "$.a", cast("[3]" as json), "$.c", 1)"#,
+            r#"
+        |{"a":[3],"b":[1,2]}|
+        "#,
+        );
+
+        connection.query(
+            r#"select json_remove(cast("{\"a\": 1, \"b\": [1, 2]}" as json), "$.b[0]", "$.a")"#,
+            r#"
+        |{"b":[2]}|
+        "#,
+        );
+
+        // Wildcards and removing the whole document aren't allowed
+        connection.query(
+            r#"select json_set(cast("[1, 2]" as json), "$[*]", 1), json_remove(cast("[1, 2]" as json), "$")"#,
+            "
+        |NULL|NULL|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_json_inspection() {
+    with_connection(|connection| {
+        connection.query(
+            r#"select json_length(cast("[1, 2, 3]" as json)), json_length(cast("{\"a\": [1, 2]}" as json), "$.a"), json_length(cast("1" as json))"#,
+            "
+        |3|2|1|
+        ",
+        );
+
+        connection.query(
+            r#"select json_keys(cast("{\"a\": 1, \"b\": {\"c\": 2}}" as json)), json_keys(cast("{\"a\": 1, \"b\": {\"c\": 2}}" as json), "$.b"), json_keys(cast("[1]" as json))"#,
+            r#"
+        |["a","b"]|["c"]|NULL|
+        "#,
+        );
+
+        connection.query(
+            r#"select json_type(cast("{}" as json)), json_type(cast("[]" as json)), json_type(cast("1" as json)), json_type(cast("1.5" as json)), json_type(cast("\"a\"" as json)), json_type(cast("null" as json))"#,
+            "
+        |OBJECT|ARRAY|INTEGER|DECIMAL|STRING|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select json_contains(cast("{\"a\": 1, \"b\": [1, 2]}" as json), cast("{\"b\": [2]}" as json)), json_contains(cast("{\"a\": 1, \"b\": [1, 2]}" as json), cast("3" as json), "$.b"), json_contains(cast("{\"a\": 1}" as json), cast("1" as json), "$.a")"#,
+            "
+        |TRUE|FALSE|TRUE|
+        ",
+        );
+
+        connection.query(
+            r#"select json_search(cast("[\"abc\", {\"x\": \"abc\"}]" as json), "one", "abc"), json_search(cast("[\"abc\", {\"x\": \"abc\"}]" as json), "all", "a%"), json_search(cast("[\"abc\", {\"x\": \"abc\"}]" as json), "all", "abc", "\\", "$[1]")"#,
+            r#"
+        |"$[0]"|["$[0]","$[1].x"]|"$[1].x"|
+        "#,
+        );
+    });
+}
+
+#[test]
+fn test_json_table() {
+    with_connection(|connection| {
+        connection.query(r#"create table orders(id INTEGER, doc JSON)"#, "");
+        connection.query(
+            r#"insert into orders
+                select 1, cast("{\"items\": [{\"name\": \"apple\", \"qty\": 2}, {\"name\": \"pear\"}]}" as json)
+                union all select 2, cast("{\"items\": []}" as json)
+                union all select 3, cast("{\"items\": [{\"name\": \"plum\", \"qty\": \"x\"}]}" as json)"#,
+            "",
+        );
+
+        connection.query(
+            r#"select o.id, jt.idx, jt.name, jt.qty
+               from orders o, json_table(o.doc, "$.items[*]" columns (idx for ordinality, name text path "$.name", qty int path "$.qty")) as jt
+               order by id, idx"#,
+            "
+        |1|1|apple|2|
+        |1|2|pear|NULL|
+        |3|1|plum|NULL|
+        ",
+        );
+
+        connection.query(
+            r#"select name, item from orders join json_table(doc, "$.items[*]" columns (name text path "$.name", item json path "$")) items on name = "pear""#,
+            r#"
+        |pear|{"name":"pear"}|
+        "#,
+        );
+    });
+}
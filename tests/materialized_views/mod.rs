@@ -0,0 +1,231 @@
+use crate::runner::*;
+
+#[test]
+fn test_materialized_view_inserts_and_deletes() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1, "abc"), (2, "def")"#, "");
+
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv AS SELECT a * 10 AS a10, b FROM t1 WHERE a < 5"#,
+            "",
+        );
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |10|abc|
+            |20|def|
+        ",
+        );
+
+        connection.query(
+            r#"INSERT INTO t1 VALUES (3, "ghi"), (3, "ghi"), (7, "jkl")"#,
+            "",
+        );
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |10|abc|
+            |20|def|
+            |30|ghi|
+            |30|ghi|
+        ",
+        );
+
+        connection.query(r#"DELETE FROM t1 WHERE a = 3 LIMIT 1"#, "");
+        connection.query(r#"DELETE FROM t1 WHERE a = 1"#, "");
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |20|def|
+            |30|ghi|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_materialized_view_aggregates() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b INT)"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1, 1), (1, 2), (2, 5)"#, "");
+
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv AS SELECT a, sum(b) AS total FROM t1 GROUP BY a"#,
+            "",
+        );
+        connection.query(r#"INSERT INTO t1 VALUES (1, 3), (3, 4)"#, "");
+        connection.query(r#"DELETE FROM t1 WHERE a = 2"#, "");
+
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |1|6|
+            |3|4|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_materialized_view_min_max() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b INT)"#, "");
+        connection.query(
+            r#"INSERT INTO t1 VALUES (1, 1), (1, 2), (1, 3), (2, 5)"#,
+            "",
+        );
+
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv AS
+            SELECT a, min(b) AS lowest, max(b) AS highest, count(*) AS c FROM t1 GROUP BY a"#,
+            "",
+        );
+        connection.query(r#"DELETE FROM t1 WHERE b = 1 OR b = 3"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (2, 4), (3, 7)"#, "");
+        connection.query(r#"DELETE FROM t1 WHERE b = 7"#, "");
+
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |1|2|2|1|
+            |2|4|5|2|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_materialized_view_percentiles() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b INT)"#, "");
+        connection.query(
+            r#"INSERT INTO t1 VALUES (1, 1), (1, 2), (1, 3), (2, 5)"#,
+            "",
+        );
+
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv AS
+            SELECT a, median(b) AS m, percentile_disc(b, 0.5) AS p FROM t1 GROUP BY a"#,
+            "",
+        );
+        connection.query(r#"DELETE FROM t1 WHERE b = 1"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (2, 4), (2, 9)"#, "");
+
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |1|2.50000000000000|2|
+            |2|5.00000000000000|5|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_materialized_view_bool_aggregates() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b INT, c BOOLEAN)"#, "");
+        connection.query(
+            r#"INSERT INTO t1 VALUES (1, 1, true), (1, 2, false), (2, 3, true)"#,
+            "",
+        );
+
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv AS
+            SELECT a, bool_and(c) AS all_c, bool_or(c) AS any_c, bit_xor(b) AS x, any_value(b) AS v
+            FROM t1 GROUP BY a"#,
+            "",
+        );
+        connection.query(r#"DELETE FROM t1 WHERE b = 1 OR b = 3"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (2, 4, false)"#, "");
+
+        connection.query_unordered(
+            r#"SELECT * FROM mv"#,
+            "
+            |1|FALSE|FALSE|2|2|
+            |2|FALSE|FALSE|4|4|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_materialized_view_joins_and_chains() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE orders (customer_id INT, amount INT)"#, "");
+        connection.query(r#"CREATE TABLE customers (id INT, name TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO customers VALUES (1, "alice"), (2, "bob")"#,
+            "",
+        );
+
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW named_orders AS
+            SELECT name, amount FROM orders JOIN customers ON customer_id = id"#,
+            "",
+        );
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW big_orders AS
+            SELECT name FROM named_orders WHERE amount > 100"#,
+            "",
+        );
+
+        connection.query(r#"INSERT INTO orders VALUES (1, 50), (2, 500)"#, "");
+        connection.query(r#"INSERT INTO customers VALUES (3, "carol")"#, "");
+        connection.query(r#"INSERT INTO orders VALUES (3, 200)"#, "");
+
+        connection.query_unordered(
+            r#"SELECT * FROM named_orders"#,
+            "
+            |alice|50|
+            |bob|500|
+            |carol|200|
+        ",
+        );
+        connection.query_unordered(
+            r#"SELECT * FROM big_orders"#,
+            "
+            |bob|
+            |carol|
+        ",
+        );
+    });
+}
+
+#[test]
+fn test_materialized_view_not_writable() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT)"#, "");
+        connection.query(r#"CREATE MATERIALIZED VIEW mv AS SELECT a FROM t1"#, "");
+
+        assert!(connection
+            .execute_statement(r#"INSERT INTO mv VALUES (1)"#)
+            .is_err());
+
+        connection.query(r#"DROP TABLE mv"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1)"#, "");
+        connection.query(r#"SELECT * FROM t1"#, "|1|");
+    });
+}
+
+#[test]
+fn test_materialized_view_transaction() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT)"#, "");
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv AS SELECT count(*) AS c FROM t1"#,
+            "",
+        );
+
+        connection.query(r#"BEGIN"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1), (2)"#, "");
+        connection.query(r#"SAVEPOINT s1"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (3)"#, "");
+        connection.query(r#"ROLLBACK TO SAVEPOINT s1"#, "");
+        connection.query(r#"SELECT * FROM mv"#, "|0|");
+        connection.query(r#"COMMIT"#, "");
+
+        connection.query(r#"SELECT * FROM mv"#, "|2|");
+    });
+}
@@ -1 +1,2 @@
 mod constant_folding;
+mod statistics;
@@ -0,0 +1,117 @@
+use crate::runner::*;
+
+#[test]
+fn test_analyze_table() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t(i INTEGER, s TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO t VALUES
+            (0, "a"), (1, "a"), (2, "b"), (3, "b"), (4, NULL),
+            (5, "c"), (6, "c"), (7, "d"), (8, NULL), (9, NULL)"#,
+            "",
+        );
+
+        // No selectivity estimate until the table has been analyzed
+        connection.query(
+            r#"EXPLAIN SELECT i FROM t WHERE i < 5"#,
+            "
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  i|0|INTEGER|<OFFSET 0>|
+        | |source:||||
+        | |  FILTER||||
+        | |   |predicate:||||
+        | |   |||BOOLEAN|`<`(<OFFSET 0>, 5)|
+        | |   |source:||||
+        | |   |  TABLE(t)||||
+        | |   |   |columns:||||
+        | |   |   |  i|0|INTEGER||
+        | |   |   |  s|1|TEXT||
+        | |   |   |access: RANGE_SCAN||||
+        | |   |   |range: to (5)||||
+        | |   |   |decoded_columns: i||||
+        | |   |   |timestamp: LATEST||||
+        ",
+        );
+
+        connection.query(r#"ANALYZE TABLE t"#, "");
+
+        connection.query(
+            r#"SELECT s.column_name, s.row_count, s.null_count, s.ndv
+            FROM incresql.column_statistics s
+            JOIN incresql.tables t ON s.table_id = t.table_id
+            WHERE t.name = "t"
+            ORDER BY column_name"#,
+            "
+        |i|10|0|10|
+        |s|10|3|4|
+        ",
+        );
+
+        connection.query(
+            r#"SELECT column_name, row_count, null_count, ndv, min_value, max_value
+            FROM information_schema.column_statistics
+            WHERE schema_name = "default" AND table_name = "t"
+            ORDER BY column_name"#,
+            "
+        |i|10|0|10|0|9|
+        |s|10|3|4|a|d|
+        ",
+        );
+
+        connection.query(
+            r#"EXPLAIN SELECT i FROM t WHERE i < 5"#,
+            "
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  i|0|INTEGER|<OFFSET 0>|
+        | |source:||||
+        | |  FILTER||||
+        | |   |selectivity: 0.5000||||
+        | |   |predicate:||||
+        | |   |||BOOLEAN|`<`(<OFFSET 0>, 5)|
+        | |   |source:||||
+        | |   |  TABLE(t)||||
+        | |   |   |columns:||||
+        | |   |   |  i|0|INTEGER||
+        | |   |   |  s|1|TEXT||
+        | |   |   |access: RANGE_SCAN||||
+        | |   |   |range: to (5)||||
+        | |   |   |decoded_columns: i||||
+        | |   |   |timestamp: LATEST||||
+        ",
+        );
+
+        // Analyzing again replaces the stats
+        connection.query(r#"INSERT INTO t VALUES (10, "e")"#, "");
+        connection.query(r#"ANALYZE TABLE t"#, "");
+        connection.query(
+            r#"SELECT s.column_name, s.row_count, s.null_count
+            FROM incresql.column_statistics s
+            JOIN incresql.tables t ON s.table_id = t.table_id
+            WHERE t.name = "t"
+            ORDER BY column_name"#,
+            "
+        |i|11|0|
+        |s|11|3|
+        ",
+        );
+        connection.query(
+            r#"SELECT column_name, max_value FROM information_schema.column_statistics
+            WHERE table_name = "t"
+            ORDER BY column_name"#,
+            "
+        |i|10|
+        |s|e|
+        ",
+        );
+
+        // The stats go along with the table
+        connection.query(r#"DROP TABLE t"#, "");
+        connection.query(
+            r#"SELECT column_name FROM information_schema.column_statistics
+            WHERE table_name = "t""#,
+            "",
+        );
+    });
+}
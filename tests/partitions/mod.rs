@@ -0,0 +1,113 @@
+use crate::runner::*;
+
+#[test]
+fn range_partitions() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t(i INTEGER, s TEXT) PARTITION BY RANGE (i) (
+              PARTITION p0 VALUES LESS THAN (10),
+              PARTITION p1 VALUES LESS THAN (20)
+            )"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO t VALUES (1, "a"), (12, "b"), (15, "c"), (NULL, "d")"#,
+            "",
+        );
+
+        // Values past the last bound have nowhere to go
+        assert!(connection
+            .execute_statement(r#"INSERT INTO t VALUES (20, "e")"#)
+            .and_then(|(_, mut executor)| Ok(executor.next()?.is_none()))
+            .is_err());
+        connection.query(
+            r#"ALTER TABLE t ADD PARTITION (PARTITION p2 VALUES LESS THAN MAXVALUE)"#,
+            "",
+        );
+        connection.query(r#"INSERT INTO t VALUES (20, "e")"#, "");
+
+        connection.query_unordered(
+            r#"SELECT i, s FROM t"#,
+            "
+            |1|a|
+            |12|b|
+            |15|c|
+            |NULL|d|
+            |20|e|
+            ",
+        );
+
+        connection.query(
+            r#"EXPLAIN SELECT s FROM t WHERE i >= 10 AND i < 20"#,
+            "
+        |PROJECT||||
+        | |output_exprs:||||
+        | |  s|0|TEXT|<OFFSET 1>|
+        | |source:||||
+        | |  FILTER||||
+        | |   |predicate:||||
+        | |   |||BOOLEAN|and(`>=`(<OFFSET 0>, 10), `<`(<OFFSET 0>, 20))|
+        | |   |source:||||
+        | |   |  TABLE(t)||||
+        | |   |   |columns:||||
+        | |   |   |  i|0|INTEGER||
+        | |   |   |  s|1|TEXT||
+        | |   |   |access: RANGE_SCAN||||
+        | |   |   |range: from (10) to (20)||||
+        | |   |   |timestamp: LATEST||||
+        | |   |   |partitions: p1||||
+        ",
+        );
+        connection.query_unordered(
+            r#"SELECT s FROM t WHERE i >= 10 AND i < 20"#,
+            "
+            |b|
+            |c|
+            ",
+        );
+        // Every partition gets pruned
+        connection.query(r#"SELECT s FROM t WHERE i < 0 AND i >= 20"#, "");
+
+        connection.query(r#"ALTER TABLE t DROP PARTITION p1"#, "");
+        connection.query_unordered(
+            r#"SELECT i, s FROM t"#,
+            "
+            |1|a|
+            |NULL|d|
+            |20|e|
+            ",
+        );
+
+        connection.query(r#"COMPACT TABLE t PARTITION p0"#, "");
+        connection.query(r#"COMPACT TABLE t"#, "");
+        connection.query(r#"DROP TABLE t"#, "");
+    });
+}
+
+#[test]
+fn hash_partitions() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t(i INTEGER) PARTITION BY HASH (i) PARTITIONS 4"#,
+            "",
+        );
+        connection.query(r#"INSERT INTO t VALUES (1), (2), (3), (4), (5)"#, "");
+
+        connection.query_unordered(
+            r#"SELECT i FROM t"#,
+            "
+            |1|
+            |2|
+            |3|
+            |4|
+            |5|
+            ",
+        );
+        connection.query(r#"SELECT i FROM t WHERE i = 3"#, "|3|");
+
+        // Only range partitions can be dropped
+        assert!(connection
+            .execute_statement(r#"ALTER TABLE t DROP PARTITION p0"#)
+            .is_err());
+    });
+}
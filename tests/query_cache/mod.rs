@@ -0,0 +1,48 @@
+use crate::runner::*;
+
+#[test]
+fn test_query_cache_invalidation() {
+    with_connection(|connection| {
+        connection.query(r#"SET query_cache_type = ON"#, "");
+        connection.query(r#"Create table test (c1 INT)"#, "");
+        connection.query(r#"Create table other (c1 INT)"#, "");
+        connection.query(r#"INSERT INTO test VALUES (1), (2)"#, "");
+
+        let sql = r#"select sum(c1), count(*) from test"#;
+        connection.query(sql, "|3|2|");
+        connection.query(sql, "|3|2|");
+
+        // Writes to other tables don't matter
+        connection.query(r#"INSERT INTO other VALUES (10)"#, "");
+        connection.query(sql, "|3|2|");
+
+        connection.query(r#"INSERT INTO test VALUES (3)"#, "");
+        connection.query(sql, "|6|3|");
+
+        connection.query(r#"DELETE FROM test WHERE c1 = 1"#, "");
+        connection.query(sql, "|5|2|");
+
+        // Session dependant functions are folded into the plan so still get re-evaluated
+        connection.query(r#"select database()"#, "|default|");
+        connection.query(r#"use incresql"#, "");
+        connection.query(r#"select database()"#, "|incresql|");
+    });
+}
+
+#[test]
+fn test_plan_cache_invalidation() {
+    with_connection(|connection| {
+        connection.query(r#"Create table test (c1 INT, c2 INT)"#, "");
+        connection.query(r#"INSERT INTO test VALUES (1, 10)"#, "");
+
+        let sql = r#"select * from test"#;
+        connection.query(sql, "|1|10|");
+        connection.query(sql, "|1|10|");
+
+        // Recreating the table means the cached plan is no longer valid
+        connection.query(r#"DROP TABLE test"#, "");
+        connection.query(r#"Create table test (c1 INT)"#, "");
+        connection.query(r#"INSERT INTO test VALUES (2)"#, "");
+        connection.query(sql, "|2|");
+    });
+}
@@ -1,5 +1,6 @@
 use runtime::connection::Connection;
 use runtime::Runtime;
+use std::sync::atomic::Ordering;
 
 /// Creates a new connection and passes it to the closure
 pub fn with_connection<F: FnOnce(&Connection)>(f: F) {
@@ -14,67 +15,115 @@ pub fn query(query: &str, expected: &str) {
 }
 
 pub trait TestQuery {
+    /// Executes the query and compares the rows returned, in order, against expected.
     fn query(&self, query: &str, expected: &str);
+    /// Like query but sorts both the actual and expected rows before comparing, for queries
+    /// that don't have a defined order.
+    fn query_unordered(&self, query: &str, expected: &str);
+    /// Asserts the datatypes of the columns returned by the query, ie "|INTEGER|TEXT|".
+    fn query_types(&self, query: &str, expected: &str);
+    /// Executes a dml statement and asserts the number of rows it affected.
+    fn execute_affected(&self, statement: &str, expected: u64);
 }
 
 impl TestQuery for Connection<'_> {
     fn query(&self, query: &str, expected: &str) {
-        let (fields, mut executor) = self.execute_statement(query).unwrap();
-        let types: Vec<_> = fields.iter().map(|f| f.data_type).collect();
-        let mut rows: Vec<String> = vec![];
-        while let Some((tuple, freq)) = executor.next().unwrap() {
-            for _ in 0..freq {
-                let row = tuple
-                    .iter()
-                    .enumerate()
-                    .map(|(idx, value)| value.typed_with(types[idx]).to_string())
-                    .collect::<Vec<_>>()
-                    .join("|");
+        compare_rows(&query_rows(self, query), &expected_rows(expected));
+    }
 
-                rows.push(format!("|{}|", row));
-            }
-        }
+    fn query_unordered(&self, query: &str, expected: &str) {
+        let mut rows = query_rows(self, query);
+        let mut expected_rows = expected_rows(expected);
+        rows.sort();
+        expected_rows.sort();
+        compare_rows(&rows, &expected_rows);
+    }
 
-        let expected_rows: Vec<_> = expected
-            .split("\n")
-            .filter_map(|row| {
-                let trimmed = row.trim();
-                if trimmed.is_empty() {
-                    None
-                } else {
-                    Some(trimmed)
-                }
-            })
-            .collect();
+    fn query_types(&self, query: &str, expected: &str) {
+        let (fields, _executor) = self.execute_statement(query).unwrap();
+        let types = fields
+            .iter()
+            .map(|f| f.data_type.to_string())
+            .collect::<Vec<_>>()
+            .join("|");
+        assert_eq!(format!("|{}|", types), expected.trim());
+    }
 
-        for ((idx, actual), expected) in rows.iter().enumerate().zip(expected_rows.iter()) {
-            if actual != expected {
-                panic!(
-                    "actual != expected @ line {}\n  actual={}\nexpected={}\n\nactual_rows:\n{}\n\nexpected_rows:\n{}\n",
-                    idx + 1,
-                    actual,
-                    expected,
-                    rows.join("\n"),
-                    expected_rows.join("\n")
-                );
-            }
-        }
-        if rows.len() > expected_rows.len() {
+    fn execute_affected(&self, statement: &str, expected: u64) {
+        let (_fields, mut executor) = self.execute_statement(statement).unwrap();
+        while executor.next().unwrap().is_some() {}
+        let affected_rows = self.session.affected_rows.load(Ordering::Relaxed);
+        if affected_rows != expected {
             panic!(
-                "actual has {} more rows than expected\n\nactual_rows:\n{}\n\nexpected_rows:\n{}\n",
-                rows.len() - expected_rows.len(),
-                rows.join("\n"),
-                expected_rows.join("\n")
+                "expected {} affected rows but got {}\n\nstatement:\n{}\n",
+                expected, affected_rows, statement
             );
         }
+    }
+}
+
+/// Runs the query returning the rows formatted as |col1|col2|, rows with a freq > 1 are repeated.
+fn query_rows(connection: &Connection, query: &str) -> Vec<String> {
+    let (fields, mut executor) = connection.execute_statement(query).unwrap();
+    let types: Vec<_> = fields.iter().map(|f| f.data_type).collect();
+    let mut rows: Vec<String> = vec![];
+    while let Some((tuple, freq)) = executor.next().unwrap() {
+        for _ in 0..freq {
+            let row = tuple
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| value.typed_with(types[idx]).to_string())
+                .collect::<Vec<_>>()
+                .join("|");
+
+            rows.push(format!("|{}|", row));
+        }
+    }
+    rows
+}
 
-        if rows.len() < expected_rows.len() {
+fn expected_rows(expected: &str) -> Vec<&str> {
+    expected
+        .split("\n")
+        .filter_map(|row| {
+            let trimmed = row.trim();
+            if trimmed.is_empty() {
+                None
+            } else {
+                Some(trimmed)
+            }
+        })
+        .collect()
+}
+
+fn compare_rows(rows: &[String], expected_rows: &[&str]) {
+    for ((idx, actual), expected) in rows.iter().enumerate().zip(expected_rows.iter()) {
+        if actual != expected {
             panic!(
-                "actual has {} less rows than expected\n\nactual_rows:\n{}\n\nexpected_rows:\n{}\n",
-                expected_rows.len() - rows.len(),
+                "actual != expected @ line {}\n  actual={}\nexpected={}\n\nactual_rows:\n{}\n\nexpected_rows:\n{}\n",
+                idx + 1,
+                actual,
+                expected,
                 rows.join("\n"),
                 expected_rows.join("\n")
             );
         }
     }
+    if rows.len() > expected_rows.len() {
+        panic!(
+            "actual has {} more rows than expected\n\nactual_rows:\n{}\n\nexpected_rows:\n{}\n",
+            rows.len() - expected_rows.len(),
+            rows.join("\n"),
+            expected_rows.join("\n")
+        );
+    }
+
+    if rows.len() < expected_rows.len() {
+        panic!(
+            "actual has {} less rows than expected\n\nactual_rows:\n{}\n\nexpected_rows:\n{}\n",
+            expected_rows.len() - rows.len(),
+            rows.join("\n"),
+            expected_rows.join("\n")
+        );
+    }
 }
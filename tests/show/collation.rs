@@ -0,0 +1,25 @@
+use crate::runner::query;
+
+#[test]
+fn show_collation() {
+    query(
+        r#"SHOW COLLATION LIKE 'utf8%'"#,
+        "
+        |utf8mb4_general_ci|utf8mb4|45|Yes|Yes|1|
+        |utf8mb4_bin|utf8mb4|46||Yes|1|
+        |utf8_general_ci|utf8|33|Yes|Yes|1|
+        ",
+    );
+}
+
+#[test]
+fn show_character_set() {
+    query(
+        r#"SHOW CHARACTER SET"#,
+        "
+        |utf8mb4|UTF-8 Unicode|utf8mb4_general_ci|4|
+        |utf8|UTF-8 Unicode|utf8_general_ci|3|
+        |binary|Binary pseudo charset|binary|1|
+        ",
+    );
+}
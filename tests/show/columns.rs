@@ -0,0 +1,65 @@
+use crate::runner::*;
+
+#[test]
+fn show_columns() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (a INT NOT NULL, b TEXT DEFAULT "x", c INT AS (a + 1), PRIMARY KEY (a))"#,
+            "",
+        );
+
+        connection.query(
+            r#"SHOW COLUMNS FROM t1"#,
+            "
+            |a|INTEGER|NO|PRI|NULL||
+            |b|TEXT|YES||\"x\"||
+            |c|INTEGER|YES||NULL|STORED GENERATED|
+            ",
+        );
+
+        connection.query(
+            r#"DESCRIBE default.t1"#,
+            "
+            |a|INTEGER|NO|PRI|NULL||
+            |b|TEXT|YES||\"x\"||
+            |c|INTEGER|YES||NULL|STORED GENERATED|
+            ",
+        );
+
+        connection.query(
+            r#"SHOW FIELDS FROM databases FROM incresql"#,
+            "
+            |name|TEXT|YES|PRI|NULL||
+            ",
+        );
+
+        assert!(connection
+            .execute_statement("SHOW COLUMNS FROM missing")
+            .is_err());
+    })
+}
+
+#[test]
+fn information_schema_columns() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT NOT NULL)"#, "");
+        connection.query(r#"CREATE VIEW v1 AS SELECT b FROM t1"#, "");
+
+        connection.query(
+            r#"SELECT table_name, column_name, ordinal_position, data_type, is_nullable, column_key
+            FROM information_schema.columns WHERE table_schema = "default""#,
+            "
+            |t1|a|1|INTEGER|YES|PRI|
+            |t1|b|2|TEXT|NO|PRI|
+            |v1|b|1|TEXT|YES||
+            ",
+        );
+
+        connection.query(r#"DROP TABLE t1"#, "");
+
+        connection.query(
+            r#"SELECT column_name FROM information_schema.columns WHERE table_name = "t1""#,
+            "",
+        );
+    })
+}
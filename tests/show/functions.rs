@@ -14,35 +14,104 @@ fn show_functions() {
         |/|
         |<|
         |<=|
+        |<=>|
         |=|
         |>|
         |>=|
         |and|
+        |any_value|
+        |approx_count_distinct|
+        |approx_percentile|
+        |array_agg|
         |avg|
         |between|
+        |bit_and|
+        |bit_or|
+        |bit_xor|
+        |bool_and|
+        |bool_or|
         |coalesce|
+        |collation_key|
+        |connection_id|
+        |convert_tz|
+        |cosine_distance|
         |count|
+        |current_timestamp|
+        |current_user|
         |database|
         |date_sub|
+        |euclidean_distance|
+        |first|
+        |from_base64|
+        |greatest|
+        |hex|
+        |hop|
         |if|
+        |ifnull|
+        |inner_product|
         |isfalse|
         |isnull|
         |istrue|
+        |json_arrayagg|
+        |json_contains|
         |json_extract|
+        |json_insert|
+        |json_keys|
+        |json_length|
+        |json_objectagg|
+        |json_remove|
+        |json_replace|
+        |json_search|
+        |json_set|
+        |json_type|
         |json_unquote|
+        |least|
+        |length|
+        |match|
+        |max|
+        |median|
+        |min|
         |not|
+        |now|
+        |nullif|
         |or|
+        |percentile_cont|
+        |percentile_disc|
+        |regexp_extract|
+        |regexp_replace|
+        |replication_lag|
+        |schema|
+        |split|
         |sum|
+        |to_base64|
         |to_bigint|
         |to_bool|
+        |to_bytes|
         |to_date|
         |to_decimal|
+        |to_decimal_lenient|
+        |to_double|
+        |to_double_lenient|
         |to_int|
         |to_json|
         |to_jsonpath|
+        |to_smallint|
         |to_text|
         |to_timestamp|
+        |to_tinyint|
+        |to_ubigint|
+        |to_uint|
+        |to_usmallint|
+        |to_utinyint|
+        |to_vector|
+        |tumble|
         |type_of|
+        |unhex|
+        |url_decode|
+        |url_encode|
+        |user|
+        |vector_dims|
+        |version|
         ",
     );
 }
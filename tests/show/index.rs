@@ -0,0 +1,61 @@
+use crate::runner::*;
+
+#[test]
+fn show_index() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SHOW INDEX FROM incresql.databases"#,
+            "
+            |databases|FALSE|PRIMARY|1|name|A|2|
+            ",
+        );
+
+        connection.query(
+            r#"SHOW KEYS FROM tables IN incresql"#,
+            "
+            |tables|FALSE|PRIMARY|1|database_name|A|4|
+            |tables|FALSE|PRIMARY|2|name|A|4|
+            ",
+        );
+
+        assert!(connection
+            .execute_statement("SHOW INDEX FROM missing")
+            .is_err());
+    })
+}
+
+#[test]
+fn information_schema_statistics() {
+    with_connection(|connection| {
+        connection.query(
+            r#"CREATE TABLE t1 (a INT, b TEXT, c BOOLEAN, PRIMARY KEY (a, b DESC))"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT table_schema, table_name, non_unique, index_name, seq_in_index, column_name, collation
+            FROM information_schema.statistics WHERE table_name = "t1""#,
+            "
+            |default|t1|FALSE|PRIMARY|1|a|A|
+            |default|t1|FALSE|PRIMARY|2|b|D|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT s.index_table_id = t.table_id
+            FROM information_schema.statistics s
+            JOIN incresql.tables t ON s.table_schema = t.database_name AND s.table_name = t.name
+            WHERE s.table_name = "t1" AND s.seq_in_index = 1"#,
+            "
+            |TRUE|
+            ",
+        );
+
+        connection.query(r#"DROP TABLE t1"#, "");
+
+        connection.query(
+            r#"SELECT column_name FROM information_schema.statistics WHERE table_name = "t1""#,
+            "",
+        );
+    })
+}
@@ -1,3 +1,8 @@
+mod collation;
+mod columns;
 mod databases;
 mod functions;
+mod index;
+mod processlist;
 mod tables;
+mod variables;
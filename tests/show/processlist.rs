@@ -0,0 +1,45 @@
+use crate::runner::{with_connection, TestQuery};
+use std::sync::atomic::Ordering;
+
+#[test]
+fn show_processlist() {
+    with_connection(|connection| {
+        *connection.session.user.write().unwrap() = "root".to_string();
+        let other = connection.runtime.new_connection();
+        *other.session.user.write().unwrap() = "bob".to_string();
+        other.session.statement_started("SELECT 1");
+
+        connection.query(
+            r#"SHOW PROCESSLIST"#,
+            "
+            |1|root|default|Sleep|0|NULL|
+            |2|bob|default|Query|0|SELECT 1|
+            ",
+        );
+
+        drop(other);
+        connection.query(
+            r#"SHOW FULL PROCESSLIST"#,
+            "
+            |1|root|default|Sleep|0|NULL|
+            ",
+        );
+    });
+}
+
+#[test]
+fn kill() {
+    with_connection(|connection| {
+        let other = connection.runtime.new_connection();
+        let other_id = other.connection_id;
+
+        connection.query(&format!("KILL QUERY {}", other_id), "");
+        assert!(other.session.kill_flag.load(Ordering::Relaxed));
+        assert!(!other.session.connection_killed.load(Ordering::Relaxed));
+
+        connection.query(&format!("KILL {}", other_id), "");
+        assert!(other.session.connection_killed.load(Ordering::Relaxed));
+
+        assert!(connection.execute_statement("KILL 12345").is_err());
+    });
+}
@@ -10,9 +10,33 @@ fn show_tables() {
         connection.query(
             r#"SHOW TABLES"#,
             "
+            |column_bounds|
+            |column_statistics|
+            |columns|
             |databases|
+            |functions|
+            |indexes|
+            |partitions|
             |prefix_tables|
+            |sequences|
+            |sinks|
+            |slow_log|
             |tables|
+            |users|
+       ",
+        );
+    })
+}
+
+#[test]
+fn show_tables_from() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SHOW TABLES FROM information_schema"#,
+            "
+            |column_statistics|
+            |columns|
+            |statistics|
        ",
         );
     })
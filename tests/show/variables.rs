@@ -0,0 +1,64 @@
+use crate::runner::{query, with_connection, TestQuery};
+
+#[test]
+fn show_variables() {
+    query(
+        r#"SHOW VARIABLES LIKE 'character_set_c%'"#,
+        "
+        |character_set_client|utf8mb4|
+        |character_set_connection|utf8mb4|
+        ",
+    );
+}
+
+#[test]
+fn select_system_variables() {
+    query(
+        r#"SELECT @@version_comment, @@session.transaction_isolation, @@max_allowed_packet + 1"#,
+        "
+        |incresql|REPEATABLE-READ|16777216|
+        ",
+    );
+}
+
+#[test]
+fn set_variables() {
+    with_connection(|connection| {
+        connection.query(r#"SET NAMES latin1"#, "");
+        connection.query(r#"SET @@session.sql_mode = "ANSI", autocommit = 0"#, "");
+
+        connection.query(
+            r#"SELECT @@character_set_results, @@sql_mode, @@autocommit"#,
+            "
+            |latin1|ANSI|0|
+            ",
+        );
+    });
+}
+
+#[test]
+fn user_variables() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SET @x = 2, @name = "abc", @d = CAST("2020-01-02" AS DATE)"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT @x + 1, @name, @d, @unset"#,
+            "
+            |3|abc|2020-01-02|NULL|
+            ",
+        );
+        connection.query(
+            r#"SELECT a FROM (SELECT 1 AS a UNION ALL SELECT 2 AS a) AS t WHERE a = @x"#,
+            "
+            |2|
+            ",
+        );
+
+        connection.query(r#"SET @x = @x * 10"#, "");
+        connection.query(r#"SELECT @x"#, "|20|");
+        connection.query(r#"SHOW VARIABLES LIKE '%x'"#, "");
+    });
+}
@@ -0,0 +1,161 @@
+use crate::runner::*;
+use data::Datum;
+use runtime::BoxedExecutor;
+use std::fs;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// Reads the next n changes from a subscription, dropping the timestamps.
+fn changes(executor: &mut BoxedExecutor, n: usize) -> Vec<Vec<Datum<'static>>> {
+    (0..n)
+        .map(|_| {
+            let (tuple, _freq) = executor.next().unwrap().unwrap();
+            tuple[..tuple.len() - 1]
+                .iter()
+                .map(|datum| datum.as_static())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_tail() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(r#"INSERT INTO t1 VALUES (1, "x")"#, "");
+        // Only changes made after the tail starts are sent
+        sleep(Duration::from_millis(2));
+
+        let (fields, mut executor) = connection.execute_statement("TAIL t1").unwrap();
+        let aliases: Vec<_> = fields.iter().map(|field| field.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["a", "b", "freq", "timestamp"]);
+
+        let other = connection.runtime.new_connection();
+        other.query(r#"INSERT INTO t1 VALUES (2, "y"), (2, "y")"#, "");
+        sleep(Duration::from_millis(2));
+        other.query(r#"DELETE FROM t1 WHERE a = 1"#, "");
+
+        assert_eq!(
+            changes(&mut executor, 2),
+            vec![
+                vec![Datum::from(2), Datum::from("y"), Datum::from(2_i64)],
+                vec![Datum::from(1), Datum::from("x"), Datum::from(-1_i64)],
+            ]
+        );
+
+        other.query(&format!("KILL QUERY {}", connection.connection_id), "");
+        assert!(executor.next().unwrap().is_none());
+    });
+}
+
+#[test]
+fn test_subscribe() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(
+            r#"CREATE VIEW v1 AS SELECT b, count(*) AS c FROM t1 GROUP BY b"#,
+            "",
+        );
+        connection.query(r#"INSERT INTO t1 VALUES (1, "x")"#, "");
+
+        let (_, mut subscription) = connection
+            .execute_statement("SUBSCRIBE TO SELECT b, count(*) FROM t1 GROUP BY b")
+            .unwrap();
+        // Tailing a view reruns it
+        let (_, mut tail) = connection.execute_statement("TAIL v1").unwrap();
+
+        let other = connection.runtime.new_connection();
+        other.query(r#"INSERT INTO t1 VALUES (2, "x")"#, "");
+
+        let expected = vec![
+            vec![Datum::from("x"), Datum::from(1_i64), Datum::from(-1_i64)],
+            vec![Datum::from("x"), Datum::from(2_i64), Datum::from(1_i64)],
+        ];
+        for executor in vec![&mut subscription, &mut tail] {
+            let mut changes = changes(executor, 2);
+            changes.sort();
+            assert_eq!(changes, expected);
+        }
+
+        assert!(connection
+            .execute_statement(r#"SUBSCRIBE TO SELECT * FROM directory "test_data/csv""#)
+            .is_err());
+    });
+}
+
+/// Waits for the sink to write n records, dropping the timestamps off the end.
+fn sink_records(path: &str, n: usize) -> Vec<String> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        let records: Vec<_> = contents
+            .lines()
+            .map(|line| line[..line.rfind(',').unwrap()].to_string())
+            .collect();
+        if records.len() >= n || Instant::now() > deadline {
+            return records;
+        }
+        sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn test_sink() {
+    with_connection(|connection| {
+        let dir = std::env::temp_dir();
+        let json_path = dir.join(format!("incresql_sink_{}.json", std::process::id()));
+        let csv_path = dir.join(format!("incresql_sink_{}.csv", std::process::id()));
+        let json_path = json_path.to_str().unwrap();
+        let csv_path = csv_path.to_str().unwrap();
+        let _ = fs::remove_file(json_path);
+        let _ = fs::remove_file(csv_path);
+
+        connection.query(r#"CREATE TABLE t1 (a INT, b TEXT)"#, "");
+        connection.query(
+            r#"CREATE MATERIALIZED VIEW mv1 AS SELECT a, b FROM t1 WHERE a > 1"#,
+            "",
+        );
+        connection.query(
+            &format!("CREATE SINK json_sink FROM t1 INTO FILE '{}'", json_path),
+            "",
+        );
+        connection.query(
+            &format!(
+                "CREATE SINK csv_sink FROM mv1 INTO FILE '{}' FORMAT CSV",
+                csv_path
+            ),
+            "",
+        );
+        assert!(connection
+            .execute_statement(&format!(
+                "CREATE SINK json_sink FROM t1 INTO FILE '{}'",
+                json_path
+            ))
+            .is_err());
+
+        connection.query(r#"INSERT INTO t1 VALUES (1, "x"), (2, "y, z")"#, "");
+        sleep(Duration::from_millis(2));
+        connection.query(r#"DELETE FROM t1 WHERE a = 2"#, "");
+
+        let mut records = sink_records(json_path, 3);
+        records[..2].sort();
+        assert_eq!(
+            records,
+            vec![
+                r#"{"a":1,"b":"x","freq":1"#,
+                r#"{"a":2,"b":"y, z","freq":1"#,
+                r#"{"a":2,"b":"y, z","freq":-1"#,
+            ]
+        );
+        assert_eq!(
+            sink_records(csv_path, 2),
+            vec![r#"2,"y, z",1"#, r#"2,"y, z",-1"#]
+        );
+
+        connection.query(r#"DROP SINK json_sink"#, "");
+        connection.query(r#"DROP SINK csv_sink"#, "");
+        assert!(connection.execute_statement("DROP SINK csv_sink").is_err());
+        let _ = fs::remove_file(json_path);
+        let _ = fs::remove_file(csv_path);
+    });
+}
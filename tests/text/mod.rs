@@ -0,0 +1,115 @@
+use crate::runner::*;
+
+#[test]
+fn test_regexp_functions() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT regexp_extract('order-123-456', '\\d+'), regexp_extract('key=value', '(\\w+)=(\\w+)', 2), regexp_extract('abc', '\\d+')"#,
+            "
+            |123|value|NULL|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT regexp_replace('2020-01-31', '(\\d+)-(\\d+)-(\\d+)', '$3/$2/$1')"#,
+            "
+            |31/01/2020|
+            ",
+        );
+
+        let err = connection
+            .execute_statement(r#"SELECT regexp_replace('a  b', '(', '')"#)
+            .and_then(|(_, mut executor)| Ok(executor.next()?.is_none()))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Illegal argument to a regular expression"));
+
+        connection.query(r#"CREATE TABLE t (s TEXT)"#, "");
+        connection.query(r#"INSERT INTO t VALUES ('a1'), ('b22'), ('c')"#, "");
+        connection.query(
+            r#"SELECT s, regexp_extract(s, '[0-9]+') FROM t ORDER BY s"#,
+            "
+            |a1|1|
+            |b22|22|
+            |c|NULL|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_split() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT split('a,b,,c', ','), split('abc', ''), split(NULL, ',')"#,
+            r#"
+            |["a","b","","c"]|["a","b","c"]|NULL|
+            "#,
+        );
+
+        connection.query(
+            r#"SELECT split('a,b', ',')->'$[1]'"#,
+            r#"
+            |"b"|
+            "#,
+        );
+    });
+}
+
+#[test]
+fn test_collation() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE t (id INT, s TEXT)"#, "");
+        connection.query(
+            r#"INSERT INTO t VALUES (1, 'apple'), (2, 'Apple'), (3, 'banana'), (4, '_x')"#,
+            "",
+        );
+
+        // utf8mb4_general_ci by default so comparisons ignore case
+        connection.query(
+            r#"SELECT id FROM t WHERE s = 'APPLE' ORDER BY id"#,
+            "
+            |1|
+            |2|
+            ",
+        );
+        connection.query(
+            r#"SELECT 'a' < 'B', 'abc' BETWEEN 'ABA' AND 'ABD'"#,
+            "
+            |TRUE|TRUE|
+            ",
+        );
+        connection.query(
+            r#"SELECT s, id FROM t ORDER BY s, id"#,
+            "
+            |apple|1|
+            |Apple|2|
+            |banana|3|
+            |_x|4|
+            ",
+        );
+        connection.query_unordered(
+            r#"SELECT count(*) FROM t GROUP BY s"#,
+            "
+            |2|
+            |1|
+            |1|
+            ",
+        );
+        connection.query(r#"SELECT count(DISTINCT s) FROM t"#, "|3|");
+
+        connection.query(r#"SET NAMES utf8mb4 COLLATE utf8mb4_bin"#, "");
+        connection.query(r#"SELECT id FROM t WHERE s = 'APPLE'"#, "");
+        connection.query(
+            r#"SELECT s FROM t ORDER BY s"#,
+            "
+            |Apple|
+            |_x|
+            |apple|
+            |banana|
+            ",
+        );
+        connection.query(r#"SELECT count(DISTINCT s) FROM t"#, "|4|");
+    });
+}
@@ -0,0 +1,90 @@
+use crate::runner::*;
+
+#[test]
+fn test_vector_columns() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE items (id INT, embedding VECTOR(3))"#, "");
+        connection.query(
+            r#"INSERT INTO items VALUES (1, [1, 2, 3]), (2, [4, 6, 3])"#,
+            "",
+        );
+        connection.query(
+            r#"INSERT INTO items SELECT 3, CAST('[0, 0, 1]' AS VECTOR(3))"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT id, embedding, vector_dims(embedding) FROM items ORDER BY id"#,
+            "
+            |1|[1,2,3]|3|
+            |2|[4,6,3]|3|
+            |3|[0,0,1]|3|
+            ",
+        );
+
+        // Vectors of the wrong size can't be inserted
+        assert!(connection
+            .execute_statement(r#"INSERT INTO items VALUES (4, [1, 2])"#)
+            .is_err());
+    });
+}
+
+#[test]
+fn test_vector_distances() {
+    with_connection(|connection| {
+        connection.query(
+            r#"SELECT euclidean_distance([1, 2, 3], [4, 6, 3]), cosine_distance([1, 0], [0, 1]), inner_product([1, 2], [3, 4])"#,
+            "
+            |5.000000|1.000000|11.000000|
+            ",
+        );
+
+        // Mismatched dimensions give null
+        connection.query(
+            r#"SELECT euclidean_distance([1, 2, 3], [1, 2])"#,
+            "
+            |NULL|
+            ",
+        );
+    });
+}
+
+#[test]
+fn test_vector_nearest_neighbours() {
+    with_connection(|connection| {
+        connection.query(r#"CREATE TABLE items (id INT, embedding VECTOR(2))"#, "");
+        connection.query(
+            r#"INSERT INTO items VALUES
+            (1, [0, 0]),
+            (2, [5, 0]),
+            (3, [1, 0]),
+            (4, [10, 0]),
+            (5, [3, 0])"#,
+            "",
+        );
+
+        connection.query(
+            r#"SELECT id, euclidean_distance(embedding, [1, 0]) AS dist FROM items ORDER BY dist LIMIT 2"#,
+            "
+            |3|0.000000|
+            |1|1.000000|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT id, euclidean_distance(embedding, [1, 0]) AS dist FROM items ORDER BY dist LIMIT 2 OFFSET 2"#,
+            "
+            |5|2.000000|
+            |2|4.000000|
+            ",
+        );
+
+        connection.query(
+            r#"SELECT id, euclidean_distance(embedding, [1, 0]) AS dist FROM items ORDER BY dist LIMIT 9223372036854775807 OFFSET 3"#,
+            "
+            |2|4.000000|
+            |4|9.000000|
+            ",
+        );
+    });
+}